@@ -1,7 +1,32 @@
 /// Code generation module for creating FFI bindings from DWARF type information
 pub mod backend;
+pub mod csharp;
+pub mod deno;
+mod doc;
+pub mod dot;
+pub mod ffi_spec;
+mod graph;
+pub mod header;
+pub mod java;
 pub mod js;
-mod koffi;
+pub mod json;
+pub mod koffi;
+mod layout;
+pub mod nim;
+pub mod rust;
+pub mod wasm;
 
-pub use backend::FfiBackend;
+pub use backend::{
+    FfiBackend, JsOptions, ModuleFormat, Rename, TypeMappingOverrides, is_js_reserved,
+};
+pub use csharp::CsharpCodegen;
+pub use deno::DenoCodegen;
+pub use dot::DotCodegen;
+pub use ffi_spec::FfiSpecCodegen;
+pub use header::HeaderCodegen;
+pub use java::JavaCodegen;
 pub use js::JsCodegen;
+pub use json::JsonCodegen;
+pub use nim::NimCodegen;
+pub use rust::RustCodegen;
+pub use wasm::WasmCodegen;
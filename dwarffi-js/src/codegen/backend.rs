@@ -1,3 +1,8 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
 /// FFI backend for JavaScript code generation
 /// Currently only Koffi is supported, but this abstraction allows for future backends
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -6,3 +11,248 @@ pub enum FfiBackend {
     #[default]
     Koffi,
 }
+
+impl FfiBackend {
+    pub const ALL: &[FfiBackend] = &[FfiBackend::Koffi];
+
+    /// the name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            FfiBackend::Koffi => "koffi",
+        }
+    }
+}
+
+impl fmt::Display for FfiBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for FfiBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|backend| backend.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let available = Self::ALL.iter().map(|b| b.name()).collect::<Vec<_>>();
+                format!(
+                    "unknown FFI backend '{}' (available: {})",
+                    s,
+                    available.join(", ")
+                )
+            })
+    }
+}
+
+/// how the generated JavaScript module imports koffi and exports its bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ModuleFormat {
+    /// ES module: `import` and `export`
+    Esm,
+    /// CommonJS: `require` and `module.exports`
+    #[default]
+    Cjs,
+}
+
+/// the name a C function is bound to in generated code. the library is
+/// still asked for the C symbol; only the binding is renamed.
+#[derive(Clone, Default)]
+pub struct Rename(Option<Arc<RenameFn>>);
+
+type RenameFn = dyn Fn(&str) -> String + Send + Sync;
+
+impl Rename {
+    /// bind each function under whatever `rename` returns for its C name
+    pub fn new(rename: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        Rename(Some(Arc::new(rename)))
+    }
+
+    /// drop `prefix` from the names that start with it, e.g. `mylib_init`
+    /// becomes `init`. a name that would be left empty, start with a digit,
+    /// or be a JavaScript reserved word (`mylib_delete`) is kept whole.
+    pub fn strip_prefix(prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        Rename::new(move |name| match name.strip_prefix(prefix.as_str()) {
+            Some(rest)
+                if rest.starts_with(|c: char| !c.is_ascii_digit()) && !is_js_reserved(rest) =>
+            {
+                rest.to_string()
+            }
+            _ => name.to_string(),
+        })
+    }
+
+    /// the binding name of the C function `name`
+    pub fn apply(&self, name: &str) -> String {
+        match &self.0 {
+            Some(rename) => rename(name),
+            None => name.to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for Rename {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(_) => f.write_str("Rename(..)"),
+            None => f.write_str("Rename(identity)"),
+        }
+    }
+}
+
+/// whether `name` is reserved in JavaScript, strict mode and modules
+/// included, and so can't name a binding
+pub fn is_js_reserved(name: &str) -> bool {
+    const RESERVED: &[&str] = &[
+        "arguments",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "debugger",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "enum",
+        "eval",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "function",
+        "if",
+        "implements",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "with",
+        "yield",
+    ];
+    RESERVED.contains(&name)
+}
+
+/// out-param heuristic: C has no out parameters, so rely on the common
+/// `out`/`out_*` naming convention. only a pointer can be written through,
+/// but depth alone says nothing: `allocate_matrix(int** matrix)` fills in
+/// the rows of an array rather than returning one pointer.
+pub(super) fn is_out_param(name: &str, pointer_depth: usize) -> bool {
+    pointer_depth > 0 && (name == "out" || name.starts_with("out_"))
+}
+
+/// target type names to use for C primitives instead of a backend's
+/// defaults, keyed by the primitive's name as DWARF spells it (`long double`,
+/// `long unsigned int`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeMappingOverrides(HashMap<String, String>);
+
+impl TypeMappingOverrides {
+    /// map the primitive `c_name` to `target`, replacing any earlier mapping
+    pub fn insert(&mut self, c_name: impl Into<String>, target: impl Into<String>) {
+        self.0.insert(c_name.into(), target.into());
+    }
+
+    /// the target type `c_name` is overridden to, if any
+    pub fn get(&self, c_name: &str) -> Option<&str> {
+        self.0.get(c_name).map(String::as_str)
+    }
+
+    /// read overrides from a JSON object of C name to target type, e.g.
+    /// `{"long double": "longdouble"}`
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json)
+            .map(TypeMappingOverrides)
+            .map_err(|e| format!("invalid type map: {}", e))
+    }
+}
+
+/// backend, module format, function names and primitive type mappings of
+/// generated JavaScript bindings
+#[derive(Debug, Clone, Default)]
+pub struct JsOptions {
+    pub backend: FfiBackend,
+    pub module_format: ModuleFormat,
+    pub rename: Rename,
+    pub type_map: TypeMappingOverrides,
+    /// export everything as one object under this name, an identifier,
+    /// instead of binding by binding
+    pub namespace: Option<String>,
+    /// load the library when the exported `init(libraryPath)` is called,
+    /// which returns the function bindings, rather than on import
+    pub runtime_load: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_name_round_trips() {
+        for backend in FfiBackend::ALL {
+            assert_eq!(backend.to_string().parse::<FfiBackend>(), Ok(*backend));
+        }
+        assert_eq!("Koffi".parse::<FfiBackend>(), Ok(FfiBackend::Koffi));
+        assert_eq!(
+            "ref-napi".parse::<FfiBackend>(),
+            Err("unknown FFI backend 'ref-napi' (available: koffi)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_strip_prefix_keeps_names_it_would_break() {
+        let rename = Rename::strip_prefix("mylib_");
+        assert_eq!(rename.apply("mylib_init"), "init");
+        assert_eq!(rename.apply("other_init"), "other_init");
+        assert_eq!(rename.apply("mylib_"), "mylib_");
+        assert_eq!(rename.apply("mylib_2d_draw"), "mylib_2d_draw");
+        assert_eq!(rename.apply("mylib_delete"), "mylib_delete");
+        assert_eq!(rename.apply("mylib_deleted"), "deleted");
+        assert_eq!(Rename::default().apply("mylib_init"), "mylib_init");
+    }
+
+    #[test]
+    fn test_out_param_needs_a_pointer_and_an_out_name() {
+        assert!(is_out_param("out", 1));
+        assert!(is_out_param("out_count", 1));
+        assert!(is_out_param("out_matrix", 2));
+        assert!(!is_out_param("out", 0));
+        assert!(!is_out_param("matrix", 2));
+        assert!(!is_out_param("outline", 1));
+    }
+
+    #[test]
+    fn test_type_map_from_json() {
+        let type_map = TypeMappingOverrides::from_json(r#"{"long double": "longdouble"}"#).unwrap();
+        assert_eq!(type_map.get("long double"), Some("longdouble"));
+        assert_eq!(type_map.get("double"), None);
+        assert!(TypeMappingOverrides::from_json(r#"{"long double": 16}"#).is_err());
+    }
+}
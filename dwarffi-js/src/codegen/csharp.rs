@@ -0,0 +1,1006 @@
+/// C# code generation using P/Invoke (`DllImport`)
+use anyhow::{Result, anyhow};
+use dwarffi::{
+    BaseTypeKind, EnumVariant, FunctionSignature, Parameter, ResolvedPrimitive, StructField, Type,
+    TypeId, TypeRegistry, UnionField,
+};
+use std::collections::HashSet;
+
+use super::backend::is_out_param;
+use super::doc;
+use super::graph::{collect_required_types, topological_sort, unwrap_type};
+use super::layout::{self, Member};
+
+pub struct CsharpCodegen;
+
+impl CsharpCodegen {
+    pub fn generate_module(
+        type_registry: &TypeRegistry,
+        functions: &[FunctionSignature],
+        generate_functions: bool,
+        library_path: &str,
+    ) -> Result<String> {
+        let mut output = String::new();
+
+        output.push_str(&generate_header());
+
+        let required_types = collect_required_types(type_registry, functions);
+        let sorted_types = topological_sort(type_registry, required_types)?;
+
+        let mut generated_names = HashSet::new();
+        for type_id in sorted_types {
+            if let Some(type_) = type_registry.get_type(type_id) {
+                output.push_str(&generate_type_definition(
+                    type_registry,
+                    type_,
+                    &mut generated_names,
+                )?);
+            }
+        }
+
+        if generate_functions {
+            output.push_str(&generate_native_methods(
+                type_registry,
+                functions,
+                library_path,
+            )?);
+        }
+
+        Ok(output)
+    }
+}
+
+fn generate_header() -> String {
+    "// Auto-generated by dwarffi-js\n\
+     // Do not edit manually!\n\
+     // Backend: C# P/Invoke\n\
+     //\n\
+     // unions containing arrays use fixed buffers, which require\n\
+     // <AllowUnsafeBlocks>true</AllowUnsafeBlocks> in your project\n\n\
+     using System;\n\
+     using System.Runtime.InteropServices;\n\n"
+        .to_string()
+}
+
+/// C# name for a struct/union/enum, falling back to the typedef name for
+/// anonymous aggregates (`typedef struct { ... } Point`), then to their
+/// synthetic name if demunged
+fn aggregate_name(
+    type_registry: &TypeRegistry,
+    aggregate: &Type,
+    name: &str,
+    typedef_name: Option<&str>,
+) -> Result<String> {
+    if !name.starts_with('<') {
+        return Ok(name.to_string());
+    }
+    typedef_name
+        .map(str::to_string)
+        .or_else(|| type_registry.demunged_name(aggregate.id))
+        .ok_or_else(|| anyhow!("anonymous aggregate without a typedef name"))
+}
+
+/// map a resolved C primitive to a C# type by size and signedness, so that
+/// e.g. `long` becomes `long` on LP64 targets without hardcoding names
+fn primitive_to_csharp(primitive: &ResolvedPrimitive) -> Result<&'static str> {
+    if primitive.name == "_Bool" {
+        return Ok("bool");
+    }
+
+    let cs_type = match (primitive.is_float, primitive.size, primitive.is_signed) {
+        (true, 4, _) => "float",
+        (true, 8, _) => "double",
+        (false, 1, true) => "sbyte",
+        (false, 1, false) => "byte",
+        (false, 2, true) => "short",
+        (false, 2, false) => "ushort",
+        (false, 4, true) => "int",
+        (false, 4, false) => "uint",
+        (false, 8, true) => "long",
+        (false, 8, false) => "ulong",
+        // C# has no extended precision type for `long double`
+        _ => {
+            return Err(anyhow!(
+                "unsupported primitive type for C#: {} ({} bytes)",
+                primitive.name,
+                primitive.size
+            ));
+        }
+    };
+
+    Ok(cs_type)
+}
+
+/// C# type for a value in a field, return or callback parameter position.
+/// pointers become IntPtr, except callback typedefs which use their delegate.
+fn type_to_csharp(type_registry: &TypeRegistry, type_id: TypeId) -> Result<String> {
+    let unwrapped = unwrap_type(type_registry, type_id)?;
+
+    if unwrapped.pointer_depth > 0 {
+        if unwrapped.pointer_depth == 1
+            && let BaseTypeKind::Function { .. } = &unwrapped.base.kind
+            && let Some(name) = unwrapped.typedef_name
+        {
+            return Ok(name.to_string());
+        }
+        return Ok("IntPtr".to_string());
+    }
+
+    let cs_type = match &unwrapped.base.kind {
+        BaseTypeKind::Primitive { name, .. } if name == "void" => "void".to_string(),
+        BaseTypeKind::Primitive { .. } => {
+            let primitive = type_registry
+                .resolve_primitive(unwrapped.base.id)
+                .ok_or_else(|| anyhow!("Primitive type not resolvable: {:?}", type_id))?;
+            primitive_to_csharp(&primitive)?.to_string()
+        }
+        BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. }
+            if type_registry.is_opaque(unwrapped.base.id) =>
+        {
+            "IntPtr".to_string()
+        }
+        BaseTypeKind::Struct { name, .. }
+        | BaseTypeKind::Union { name, .. }
+        | BaseTypeKind::Enum { name, .. } => {
+            aggregate_name(type_registry, unwrapped.base, name, unwrapped.typedef_name)?
+        }
+        // arrays decay to pointers outside of struct fields
+        BaseTypeKind::Array { .. } | BaseTypeKind::Function { .. } => "IntPtr".to_string(),
+        BaseTypeKind::Typedef { .. } => unreachable!("typedefs are unwrapped"),
+    };
+
+    Ok(cs_type)
+}
+
+/// flatten a (possibly multi-dimensional) array to its innermost element type
+/// and total element count
+fn flatten_array(type_registry: &TypeRegistry, type_id: TypeId) -> Result<(TypeId, usize)> {
+    let unwrapped = unwrap_type(type_registry, type_id)?;
+
+    match &unwrapped.base.kind {
+        BaseTypeKind::Array {
+            element_type_id,
+            count,
+            ..
+        } if unwrapped.pointer_depth == 0 => {
+            let (element, inner_count) = flatten_array(type_registry, *element_type_id)?;
+            Ok((element, count * inner_count))
+        }
+        _ => Ok((type_id, 1)),
+    }
+}
+
+fn is_char(type_registry: &TypeRegistry, type_id: TypeId) -> bool {
+    type_registry
+        .resolve_primitive(type_id)
+        .map(|p| p.name == "char")
+        .unwrap_or(false)
+}
+
+fn is_array(type_registry: &TypeRegistry, type_id: TypeId) -> Result<bool> {
+    let unwrapped = unwrap_type(type_registry, type_id)?;
+    Ok(unwrapped.pointer_depth == 0 && matches!(unwrapped.base.kind, BaseTypeKind::Array { .. }))
+}
+
+/// C# reserves these; C identifiers that collide need an `@` prefix
+fn escape_identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "abstract",
+        "as",
+        "base",
+        "bool",
+        "break",
+        "byte",
+        "case",
+        "catch",
+        "char",
+        "checked",
+        "class",
+        "const",
+        "continue",
+        "decimal",
+        "default",
+        "delegate",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "event",
+        "explicit",
+        "extern",
+        "false",
+        "finally",
+        "fixed",
+        "float",
+        "for",
+        "foreach",
+        "goto",
+        "if",
+        "implicit",
+        "in",
+        "int",
+        "interface",
+        "internal",
+        "is",
+        "lock",
+        "long",
+        "namespace",
+        "new",
+        "null",
+        "object",
+        "operator",
+        "out",
+        "override",
+        "params",
+        "private",
+        "protected",
+        "public",
+        "readonly",
+        "ref",
+        "return",
+        "sbyte",
+        "sealed",
+        "short",
+        "sizeof",
+        "stackalloc",
+        "static",
+        "string",
+        "struct",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typeof",
+        "uint",
+        "ulong",
+        "unchecked",
+        "unsafe",
+        "ushort",
+        "using",
+        "virtual",
+        "void",
+        "volatile",
+        "while",
+    ];
+
+    if KEYWORDS.contains(&name) {
+        format!("@{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// generate a single type definition
+fn generate_type_definition(
+    type_registry: &TypeRegistry,
+    type_: &Type,
+    generated_names: &mut HashSet<String>,
+) -> Result<String> {
+    // (definition name, kind to generate) -- typedefs name anonymous aggregates.
+    // pointer depth is ignored: `Person*` may be the only use of `Person`
+    let demunged;
+    let (name, kind) = match &type_.kind {
+        BaseTypeKind::Struct { name, .. }
+        | BaseTypeKind::Union { name, .. }
+        | BaseTypeKind::Enum { name, .. } => {
+            // anonymous aggregates are generated through their typedef, or
+            // under their synthetic name if demunged
+            if name.starts_with('<') {
+                match type_registry.demunged_name(type_.id) {
+                    Some(name) => demunged = name,
+                    None => return Ok(String::new()),
+                }
+                (demunged.as_str(), &type_.kind)
+            } else {
+                (name.as_str(), &type_.kind)
+            }
+        }
+        BaseTypeKind::Typedef {
+            name,
+            aliased_type_id,
+        } => {
+            let aliased = type_registry
+                .get_type(*aliased_type_id)
+                .ok_or_else(|| anyhow!("Aliased type not found"))?;
+
+            match &aliased.kind {
+                BaseTypeKind::Function { .. } if aliased.pointer_depth == 1 => {
+                    (name.as_str(), &aliased.kind)
+                }
+                BaseTypeKind::Struct {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Union {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Enum {
+                    name: aliased_name, ..
+                } if aliased.pointer_depth == 0 && aliased_name.starts_with('<') => {
+                    (name.as_str(), &aliased.kind)
+                }
+                // C# has no typedefs; uses resolve through to the aliased type
+                _ => return Ok(String::new()),
+            }
+        }
+        _ => return Ok(String::new()),
+    };
+
+    if generated_names.contains(name) {
+        return Ok(String::new());
+    }
+
+    let output = match kind {
+        BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. }
+            if type_registry.is_opaque(type_.id) =>
+        {
+            generate_struct(type_registry, name, &[], true)
+        }
+        BaseTypeKind::Struct { fields, .. } => generate_struct(type_registry, name, fields, false),
+        BaseTypeKind::Union { variants, .. } => generate_union(type_registry, name, variants),
+        BaseTypeKind::Enum {
+            backing_id,
+            variants,
+            ..
+        } => generate_enum(type_registry, name, *backing_id, variants),
+        BaseTypeKind::Function {
+            return_type_id,
+            parameter_type_ids,
+            ..
+        } => generate_delegate(type_registry, name, *return_type_id, parameter_type_ids),
+        _ => return Ok(String::new()),
+    };
+
+    generated_names.insert(name.to_string());
+    match (output, doc::type_description(type_registry, type_)) {
+        (Ok(definition), Some(description)) => Ok(summary(description) + &definition),
+        (Ok(definition), None) => Ok(definition),
+        // e.g. a `long double` field, which C# has no type for
+        (Err(e), _) => Ok(format!("// {}: {}\n\n", name, e)),
+    }
+}
+
+/// `/// <summary>` XML documentation for a description
+fn summary(description: &str) -> String {
+    let escaped = description
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    doc::line_comment(&format!("<summary>\n{}\n</summary>", escaped), "", "/// ")
+}
+
+fn generate_struct(
+    type_registry: &TypeRegistry,
+    name: &str,
+    fields: &[StructField],
+    is_opaque: bool,
+) -> Result<String> {
+    let mut output = String::new();
+
+    if is_opaque {
+        output.push_str(&format!(
+            "// {} (opaque - no definition available, use IntPtr)\n\n",
+            name
+        ));
+        return Ok(output);
+    }
+
+    output.push_str("[StructLayout(LayoutKind.Sequential)]\n");
+    output.push_str(&format!("public struct {}\n{{\n", name));
+
+    for member in layout::struct_members(name, fields, "C#")? {
+        match member {
+            Member::Field(field) => output.push_str(&format!(
+                "    {}\n",
+                field_declaration(type_registry, &field.name, field.type_id)?
+            )),
+            Member::Unit(unit) => output.push_str(&format!(
+                "    private {} _bitfield{};\n",
+                unsigned_of_size(unit.size)?,
+                unit.index
+            )),
+            Member::Bitfield {
+                field,
+                unit,
+                shift,
+                width,
+            } => output.push_str(&bitfield_property(
+                type_registry,
+                field,
+                &format!("_bitfield{}", unit.index),
+                unit.size,
+                shift,
+                width,
+            )?),
+        }
+    }
+
+    output.push_str("}\n\n");
+
+    Ok(output)
+}
+
+fn unsigned_of_size(size: usize) -> Result<&'static str> {
+    match size {
+        1 => Ok("byte"),
+        2 => Ok("ushort"),
+        4 => Ok("uint"),
+        8 => Ok("ulong"),
+        _ => Err(anyhow!("no {}-byte integer to hold bitfields", size)),
+    }
+}
+
+/// a property reading and writing `width` bits at `shift` in `backing`.
+/// signed members are sign-extended by shifting through the signed type of
+/// the unit's size.
+fn bitfield_property(
+    type_registry: &TypeRegistry,
+    field: &StructField,
+    backing: &str,
+    unit: usize,
+    shift: u32,
+    width: u32,
+) -> Result<String> {
+    let name = escape_identifier(&field.name);
+    let field_type = type_to_csharp(type_registry, field.type_id)?;
+    let unsigned = unsigned_of_size(unit)?;
+    let unit_bits = unit as u32 * 8;
+    let mask = layout::low_bits(width) << shift;
+    let suffix = match unit {
+        4 => "u",
+        8 => "ul",
+        _ => "",
+    };
+
+    let bits = if field_type == "bool" {
+        format!("(({} & 0x{:X}{}) != 0)", backing, mask, suffix)
+    } else if type_registry
+        .resolve_primitive(field.type_id)
+        .is_some_and(|primitive| primitive.is_signed)
+    {
+        let signed = match unit {
+            1 => "sbyte",
+            2 => "short",
+            4 => "int",
+            _ => "long",
+        };
+        format!(
+            "({})(({})({} << {}) >> {})",
+            field_type,
+            signed,
+            backing,
+            unit_bits - shift - width,
+            unit_bits - width
+        )
+    } else {
+        format!(
+            "({})(({} & 0x{:X}{}) >> {})",
+            field_type, backing, mask, suffix, shift
+        )
+    };
+    let value = if field_type == "bool" {
+        format!("(value ? 1{} : 0{})", suffix, suffix)
+    } else {
+        format!("({})value", unsigned)
+    };
+
+    Ok(format!(
+        "    public {} {}\n    {{\n        get => {};\n        set => {} = ({})(({} & ~0x{:X}{}) | (({} << {}) & 0x{:X}{}));\n    }}\n",
+        field_type,
+        name,
+        bits,
+        backing,
+        unsigned,
+        backing,
+        mask,
+        suffix,
+        value,
+        shift,
+        mask,
+        suffix
+    ))
+}
+
+/// sequential struct field; arrays are marshaled inline by value
+fn field_declaration(type_registry: &TypeRegistry, name: &str, type_id: TypeId) -> Result<String> {
+    let name = escape_identifier(name);
+
+    if is_array(type_registry, type_id)? {
+        let (element_id, count) = flatten_array(type_registry, type_id)?;
+        let element_type = type_to_csharp(type_registry, element_id)?;
+
+        // char buffers are most useful as strings
+        if is_char(type_registry, element_id) {
+            return Ok(format!(
+                "[MarshalAs(UnmanagedType.ByValTStr, SizeConst = {})] public string {};",
+                count, name
+            ));
+        }
+
+        return Ok(format!(
+            "[MarshalAs(UnmanagedType.ByValArray, SizeConst = {})] public {}[] {};",
+            count, element_type, name
+        ));
+    }
+
+    let field_type = type_to_csharp(type_registry, type_id)?;
+    if field_type == "bool" {
+        return Ok(format!(
+            "[MarshalAs(UnmanagedType.I1)] public bool {};",
+            name
+        ));
+    }
+
+    Ok(format!("public {} {};", field_type, name))
+}
+
+/// unions use an explicit layout with every variant at offset 0. managed
+/// arrays may not overlap other fields, so array variants become fixed buffers
+/// (which makes the struct unsafe).
+fn generate_union(
+    type_registry: &TypeRegistry,
+    name: &str,
+    variants: &[UnionField],
+) -> Result<String> {
+    let mut body = String::new();
+    let mut is_unsafe = false;
+
+    for variant in variants {
+        let variant_name = escape_identifier(&variant.name);
+
+        if is_array(type_registry, variant.type_id)? {
+            let (element_id, count) = flatten_array(type_registry, variant.type_id)?;
+            let element_type = type_to_csharp(type_registry, element_id)?;
+
+            // fixed buffers only allow primitive element types
+            if type_registry.resolve_primitive(element_id).is_none() {
+                body.push_str(&format!(
+                    "    // {}: array of {} cannot overlap in an explicit layout\n",
+                    variant_name, element_type
+                ));
+                continue;
+            }
+
+            is_unsafe = true;
+            body.push_str(&format!(
+                "    [FieldOffset(0)] public fixed {} {}[{}];\n",
+                element_type, variant_name, count
+            ));
+            continue;
+        }
+
+        let variant_type = type_to_csharp(type_registry, variant.type_id)?;
+        if variant_type == "bool" {
+            body.push_str(&format!(
+                "    [FieldOffset(0)] [MarshalAs(UnmanagedType.I1)] public bool {};\n",
+                variant_name
+            ));
+        } else {
+            body.push_str(&format!(
+                "    [FieldOffset(0)] public {} {};\n",
+                variant_type, variant_name
+            ));
+        }
+    }
+
+    let mut output = String::new();
+    output.push_str("[StructLayout(LayoutKind.Explicit)]\n");
+    output.push_str(&format!(
+        "public {}struct {}\n{{\n",
+        if is_unsafe { "unsafe " } else { "" },
+        name
+    ));
+    output.push_str(&body);
+    output.push_str("}\n\n");
+
+    Ok(output)
+}
+
+fn generate_enum(
+    type_registry: &TypeRegistry,
+    name: &str,
+    backing_id: TypeId,
+    variants: &[EnumVariant],
+) -> Result<String> {
+    let backing = type_registry
+        .resolve_primitive(backing_id)
+        .ok_or_else(|| anyhow!("Enum backing type not found"))?;
+    let underlying = primitive_to_csharp(&backing)?;
+
+    let mut output = String::new();
+
+    output.push_str(&format!("public enum {} : {}\n{{\n", name, underlying));
+
+    for variant in variants {
+        output.push_str(&format!(
+            "    {} = {},\n",
+            escape_identifier(&variant.name),
+            variant.value_string(!backing.is_signed)
+        ));
+    }
+
+    output.push_str("}\n\n");
+
+    Ok(output)
+}
+
+fn generate_delegate(
+    type_registry: &TypeRegistry,
+    name: &str,
+    return_type_id: Option<TypeId>,
+    parameter_type_ids: &[TypeId],
+) -> Result<String> {
+    let return_type = match return_type_id {
+        Some(id) => type_to_csharp(type_registry, id)?,
+        None => "void".to_string(),
+    };
+
+    let params = parameter_type_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| Ok(format!("{} arg{}", type_to_csharp(type_registry, *id)?, i)))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(format!(
+        "[UnmanagedFunctionPointer(CallingConvention.Cdecl)]\n\
+         public delegate {} {}({});\n\n",
+        return_type,
+        name,
+        params.join(", ")
+    ))
+}
+
+/// declare a P/Invoke parameter. pointer parameters get friendlier
+/// marshaling where the intent is clear:
+/// - `const char*` -> `string`
+/// - `T* out_x` (out-param naming) -> `out T`
+/// - `Struct*` -> `ref Struct`
+///
+/// everything else that is a pointer stays an `IntPtr`.
+fn parameter_declaration(
+    type_registry: &TypeRegistry,
+    param: &Parameter,
+    index: usize,
+) -> Result<String> {
+    let name = if param.name.is_empty() {
+        format!("arg{}", index)
+    } else {
+        escape_identifier(&param.name)
+    };
+
+    let unwrapped = unwrap_type(type_registry, param.type_id)?;
+
+    if unwrapped.pointer_depth == 1 {
+        let pointee = &unwrapped.base;
+
+        match &pointee.kind {
+            BaseTypeKind::Primitive { name: prim, .. } if prim == "char" && unwrapped.is_const => {
+                return Ok(format!("[MarshalAs(UnmanagedType.LPStr)] string {}", name));
+            }
+            BaseTypeKind::Primitive { name: prim, .. } if prim == "void" || prim == "char" => {}
+            BaseTypeKind::Function { .. } => {}
+            BaseTypeKind::Struct {
+                is_opaque: true, ..
+            } => {}
+            BaseTypeKind::Primitive { .. }
+            | BaseTypeKind::Struct { .. }
+            | BaseTypeKind::Union { .. }
+            | BaseTypeKind::Enum { .. }
+                if !unwrapped.is_const =>
+            {
+                // pointee type, as if the parameter were passed by value
+                let pointee_type = match &pointee.kind {
+                    BaseTypeKind::Struct { name: agg, .. }
+                    | BaseTypeKind::Union { name: agg, .. }
+                    | BaseTypeKind::Enum { name: agg, .. } => {
+                        aggregate_name(type_registry, pointee, agg, unwrapped.typedef_name)?
+                    }
+                    _ => type_to_csharp(type_registry, pointee.id)?,
+                };
+
+                if is_out_param(&param.name, unwrapped.pointer_depth) {
+                    return Ok(format!("out {} {}", pointee_type, name));
+                }
+                if matches!(
+                    pointee.kind,
+                    BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. }
+                ) {
+                    return Ok(format!("ref {} {}", pointee_type, name));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let param_type = type_to_csharp(type_registry, param.type_id)?;
+    if param_type == "bool" {
+        return Ok(format!("[MarshalAs(UnmanagedType.I1)] bool {}", name));
+    }
+
+    Ok(format!("{} {}", param_type, name))
+}
+
+/// generate the static class of DllImport declarations
+fn generate_native_methods(
+    type_registry: &TypeRegistry,
+    functions: &[FunctionSignature],
+    library_path: &str,
+) -> Result<String> {
+    let mut output = String::new();
+
+    output.push_str("public static class NativeMethods\n{\n");
+    output.push_str("    // Library path - UPDATE THIS to match your deployment\n");
+    output.push_str(&format!(
+        "    private const string LibraryPath = \"{}\";\n\n",
+        library_path
+    ));
+
+    for func in functions {
+        if let Some(description) = &func.description {
+            output.push_str(&doc::line_comment(&summary(description), "    ", ""));
+        }
+        for line in native_method(type_registry, func)?.lines() {
+            output.push_str(&format!("    {}\n", line));
+        }
+        output.push('\n');
+    }
+
+    // drop the blank line after the last member
+    if output.ends_with("\n\n") {
+        output.pop();
+    }
+    output.push_str("}\n");
+
+    Ok(output)
+}
+
+/// the `DllImport` declaration of one function, a member of the module's
+/// `NativeMethods` class, with its attributes on the lines before it. a
+/// function P/Invoke can't call is rendered as a comment saying why.
+///
+/// ```
+/// # use dwarffi::{FunctionSignature, Parameter, Type, TypeRegistry};
+/// use dwarffi_js::codegen::csharp;
+/// # let mut registry = TypeRegistry::new();
+/// # let int = registry.register_type(Type::primitive("int", 4));
+/// # let param = |name: &str| Parameter::new(name, int);
+/// # let add = FunctionSignature::new("add", int, vec![param("a"), param("b")]);
+///
+/// // `int add(int a, int b)`
+/// assert_eq!(
+///     csharp::render_function(&add, &registry),
+///     "[DllImport(LibraryPath, CallingConvention = CallingConvention.Cdecl)]\n\
+///      public static extern int add(int a, int b);"
+/// );
+/// ```
+pub fn render_function(func: &FunctionSignature, type_registry: &TypeRegistry) -> String {
+    native_method(type_registry, func).unwrap_or_else(|e| format!("// {}: {}", func.name, e))
+}
+
+fn native_method(type_registry: &TypeRegistry, func: &FunctionSignature) -> Result<String> {
+    if func.is_variadic {
+        return Ok(format!(
+            "// {}: variadic function not supported by P/Invoke",
+            func.name
+        ));
+    }
+
+    let return_unwrapped = unwrap_type(type_registry, func.return_type_id)?;
+    let return_type = type_to_csharp(type_registry, func.return_type_id)?;
+
+    let params = func
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(i, param)| parameter_declaration(type_registry, param, i))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut output = String::new();
+    output.push_str("[DllImport(LibraryPath, CallingConvention = CallingConvention.Cdecl)]\n");
+
+    // returned strings are owned by the library; marshaling them to
+    // `string` would make the runtime free them
+    if return_unwrapped.pointer_depth == 1
+        && matches!(&return_unwrapped.base.kind, BaseTypeKind::Primitive { name, .. } if name == "char")
+    {
+        output.push_str("// returns char*: read with Marshal.PtrToStringAnsi\n");
+    }
+
+    if return_type == "bool" {
+        output.push_str("[return: MarshalAs(UnmanagedType.I1)]\n");
+    }
+
+    output.push_str(&format!(
+        "public static extern {} {}({});",
+        return_type,
+        escape_identifier(&func.name),
+        params.join(", ")
+    ));
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolved(name: &str, size: usize, is_signed: bool, is_float: bool) -> ResolvedPrimitive {
+        ResolvedPrimitive {
+            name: name.to_string(),
+            size,
+            is_signed,
+            is_float,
+        }
+    }
+
+    #[test]
+    fn test_primitive_to_csharp_integers() {
+        let cases = [
+            ("signed char", 1, true, "sbyte"),
+            ("unsigned char", 1, false, "byte"),
+            ("short int", 2, true, "short"),
+            ("short unsigned int", 2, false, "ushort"),
+            ("int", 4, true, "int"),
+            ("unsigned int", 4, false, "uint"),
+            ("long int", 8, true, "long"),
+            ("long unsigned int", 8, false, "ulong"),
+        ];
+
+        for (name, size, is_signed, expected) in cases {
+            assert_eq!(
+                primitive_to_csharp(&resolved(name, size, is_signed, false)).unwrap(),
+                expected,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_primitive_to_csharp_float_and_bool() {
+        assert_eq!(
+            primitive_to_csharp(&resolved("float", 4, true, true)).unwrap(),
+            "float"
+        );
+        assert_eq!(
+            primitive_to_csharp(&resolved("double", 8, true, true)).unwrap(),
+            "double"
+        );
+        assert!(primitive_to_csharp(&resolved("long double", 16, true, true)).is_err());
+        assert_eq!(
+            primitive_to_csharp(&resolved("_Bool", 1, false, false)).unwrap(),
+            "bool"
+        );
+    }
+
+    #[test]
+    fn test_struct_with_a_long_double_is_skipped() {
+        let mut registry = TypeRegistry::new();
+        let long_double_id = registry.register_type(Type::primitive("long double", 16));
+        let sample = registry.register_type(Type::new(BaseTypeKind::Struct {
+            name: "Sample".to_string(),
+            fields: vec![StructField::new("value", long_double_id, 0, 16)],
+            size: 16,
+            alignment: 16,
+            is_opaque: false,
+        }));
+
+        let definition = generate_type_definition(
+            &registry,
+            registry.get_type(sample).unwrap(),
+            &mut HashSet::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            definition,
+            "// Sample: unsupported primitive type for C#: long double (16 bytes)\n\n"
+        );
+    }
+
+    #[test]
+    fn test_primitive_to_csharp_rejects_unknown_width() {
+        assert!(primitive_to_csharp(&resolved("__int128", 16, true, false)).is_err());
+    }
+
+    #[test]
+    fn test_escape_identifier() {
+        assert_eq!(escape_identifier("string"), "@string");
+        assert_eq!(escape_identifier("out"), "@out");
+        assert_eq!(escape_identifier("count"), "count");
+    }
+
+    #[test]
+    fn test_union_array_variant_uses_fixed_buffer() {
+        let mut registry = TypeRegistry::new();
+        let int_id = registry.register_type(Type::primitive("int", 4));
+        let char_id = registry.register_type(Type::primitive("char", 1));
+        let bytes_id = registry.register_type(Type::new(BaseTypeKind::Array {
+            element_type_id: char_id,
+            count: 4,
+            size: 4,
+            stride: 1,
+            is_dynamic: false,
+        }));
+
+        let union_def = generate_union(
+            &registry,
+            "DataUnion",
+            &[
+                UnionField {
+                    name: "as_int".to_string(),
+                    type_id: int_id,
+                    discriminant_value: None,
+                },
+                UnionField {
+                    name: "as_bytes".to_string(),
+                    type_id: bytes_id,
+                    discriminant_value: None,
+                },
+            ],
+        )
+        .unwrap();
+
+        assert!(union_def.contains("[StructLayout(LayoutKind.Explicit)]"));
+        assert!(union_def.contains("public unsafe struct DataUnion"));
+        assert!(union_def.contains("[FieldOffset(0)] public int as_int;"));
+        assert!(union_def.contains("[FieldOffset(0)] public fixed sbyte as_bytes[4];"));
+    }
+
+    #[test]
+    fn test_bitfields_share_a_backing_field() {
+        let mut registry = TypeRegistry::new();
+        let uint_id = registry.register_type(Type::primitive("unsigned int", 4));
+        let int_id = registry.register_type(Type::primitive("int", 4));
+        let bitfield = |name: &str, type_id, bit_offset: u32, bit_size| StructField {
+            bit_size: Some(bit_size),
+            bit_offset: Some(bit_offset),
+            ..StructField::new(name, type_id, bit_offset as usize / 8, 4)
+        };
+
+        // `unsigned ready : 1; int level : 3; int count; unsigned error : 1;`
+        let struct_def = generate_struct(
+            &registry,
+            "StatusFlags",
+            &[
+                bitfield("ready", uint_id, 0, 1),
+                bitfield("level", int_id, 1, 3),
+                StructField::new("count", int_id, 4, 4),
+                bitfield("error", uint_id, 64, 1),
+            ],
+            false,
+        )
+        .unwrap();
+
+        assert!(struct_def.contains("    private uint _bitfield0;\n    public uint ready\n"));
+        assert!(struct_def.contains("get => (uint)((_bitfield0 & 0x1u) >> 0);"));
+        // sign-extended from the top of the unit
+        assert!(struct_def.contains("get => (int)((int)(_bitfield0 << 28) >> 29);"));
+        assert!(struct_def.contains(
+            "set => _bitfield0 = (uint)((_bitfield0 & ~0xEu) | (((uint)value << 1) & 0xEu));"
+        ));
+        assert!(struct_def.contains("    public int count;\n    private uint _bitfield1;\n"));
+        assert_eq!(struct_def.matches("private uint").count(), 2);
+    }
+
+    #[test]
+    fn test_bitfields_of_mixed_widths_in_one_unit_are_refused() {
+        let mut registry = TypeRegistry::new();
+        let uchar_id = registry.register_type(Type::primitive("unsigned char", 1));
+        let uint_id = registry.register_type(Type::primitive("unsigned int", 4));
+
+        // `unsigned char a : 3; unsigned int b : 5;` share the first byte
+        let fields = [
+            StructField {
+                bit_size: Some(3),
+                bit_offset: Some(0),
+                ..StructField::new("a", uchar_id, 0, 1)
+            },
+            StructField {
+                bit_size: Some(5),
+                bit_offset: Some(3),
+                ..StructField::new("b", uint_id, 0, 4)
+            },
+        ];
+        assert!(generate_struct(&registry, "Mixed", &fields, false).is_err());
+    }
+}
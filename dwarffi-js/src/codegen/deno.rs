@@ -0,0 +1,511 @@
+/// Deno code generation: a TypeScript module that opens the library with
+/// `Deno.dlopen`, with a `{ struct: [...] }` descriptor per struct and an
+/// `UnsafeCallback` definition per function-pointer typedef
+use anyhow::{Result, anyhow};
+use dwarffi::{
+    BaseTypeKind, EnumVariant, FunctionSignature, StructField, Type, TypeId, TypeRegistry,
+};
+use std::collections::HashSet;
+
+use super::doc;
+use super::graph::{collect_required_types, topological_sort, unwrap_type};
+
+pub struct DenoCodegen;
+
+impl DenoCodegen {
+    /// `pointer_width` is the target's, used to check struct descriptors
+    /// against the C layout
+    pub fn generate_module(
+        type_registry: &TypeRegistry,
+        functions: &[FunctionSignature],
+        generate_functions: bool,
+        library_path: &str,
+        pointer_width: usize,
+    ) -> Result<String> {
+        let mut output = String::new();
+
+        output.push_str(&generate_header());
+
+        let required_types = collect_required_types(type_registry, functions);
+        let sorted_types = topological_sort(type_registry, required_types)?;
+
+        let mut generated_names = HashSet::new();
+        for type_id in sorted_types {
+            if let Some(type_) = type_registry.get_type(type_id) {
+                output.push_str(&generate_type_definition(
+                    type_registry,
+                    type_,
+                    pointer_width,
+                    &mut generated_names,
+                ));
+            }
+        }
+
+        if generate_functions {
+            output.push_str(&generate_symbols(type_registry, functions, pointer_width));
+            output.push_str("// Library path - UPDATE THIS to match your deployment\n");
+            output.push_str(&format!(
+                "const LIBRARY_PATH = \"{}\";\n\n",
+                escape_string(library_path)
+            ));
+            output.push_str(
+                "/** the opened library; call its functions through `lib.symbols` */\n\
+                 export const lib = Deno.dlopen(LIBRARY_PATH, symbols);\n",
+            );
+        }
+
+        Ok(output)
+    }
+}
+
+fn generate_header() -> String {
+    "// Auto-generated by dwarffi-js\n\
+     // Do not edit manually!\n\
+     // Backend: Deno FFI (Deno.dlopen)\n\
+     //\n\
+     // structs passed by value are Uint8Arrays of the struct's size. pass a\n\
+     // function pointer as `new Deno.UnsafeCallback(Definition, fn).pointer`\n\n"
+        .to_string()
+}
+
+/// escape a string literal's contents
+fn escape_string(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn is_anonymous(name: &str) -> bool {
+    name.starts_with('<')
+}
+
+/// where a value appears: arguments of the library's functions can be
+/// passed as typed arrays and callbacks, everything else is a plain pointer
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Position {
+    Argument,
+    Value,
+}
+
+fn is_void(type_registry: &TypeRegistry, type_id: TypeId) -> Result<bool> {
+    let unwrapped = unwrap_type(type_registry, type_id)?;
+    Ok(unwrapped.pointer_depth == 0
+        && matches!(&unwrapped.base.kind, BaseTypeKind::Primitive { name, .. } if name == "void"))
+}
+
+/// the native type string or struct descriptor of a value. arrays decay to
+/// pointers; a pointer argument to a scalar is a `buffer`, so a typed array
+/// can be passed for it, and a function pointer argument is a `function`.
+fn native_type(
+    type_registry: &TypeRegistry,
+    type_id: TypeId,
+    position: Position,
+    pointer_width: usize,
+) -> Result<String> {
+    let unwrapped = unwrap_type(type_registry, type_id)?;
+    let base = unwrapped.base;
+
+    if unwrapped.pointer_depth > 0 {
+        let native = match &base.kind {
+            _ if position == Position::Value || unwrapped.pointer_depth > 1 => "pointer",
+            BaseTypeKind::Function { .. } => "function",
+            BaseTypeKind::Primitive { name, .. } if name != "void" => "buffer",
+            _ => "pointer",
+        };
+        return Ok(format!("\"{}\"", native));
+    }
+
+    match &base.kind {
+        BaseTypeKind::Primitive { .. } => {
+            let primitive = type_registry
+                .resolve_primitive(base.id)
+                .ok_or_else(|| anyhow!("Primitive type not resolvable: {:?}", type_id))?;
+            let native = match (primitive.is_float, primitive.size) {
+                _ if primitive.name == "_Bool" => "bool".to_string(),
+                (true, 4) => "f32".to_string(),
+                (true, 8) => "f64".to_string(),
+                (false, 1 | 2 | 4 | 8) => {
+                    let sign = if primitive.is_signed { 'i' } else { 'u' };
+                    format!("{}{}", sign, primitive.size * 8)
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "unsupported primitive type for Deno: {} ({} bytes)",
+                        primitive.name,
+                        primitive.size
+                    ));
+                }
+            };
+            Ok(format!("\"{}\"", native))
+        }
+        BaseTypeKind::Enum { backing_id, .. } => {
+            native_type(type_registry, *backing_id, position, pointer_width)
+        }
+        BaseTypeKind::Struct { name, .. } | BaseTypeKind::Union { name, .. }
+            if type_registry.is_opaque(base.id) =>
+        {
+            Err(anyhow!("opaque type {} passed by value", name))
+        }
+        BaseTypeKind::Struct { fields, size, .. } => {
+            // the descriptor is checked here too, since a struct whose
+            // layout Deno can't reproduce is emitted as a comment
+            let descriptor = struct_descriptor(type_registry, base, fields, *size, pointer_width)?;
+            Ok(type_registry
+                .display_name(base.id)
+                .filter(|name| !is_anonymous(name))
+                .unwrap_or(descriptor))
+        }
+        BaseTypeKind::Union { .. } => Err(anyhow!(
+            "{} is a union, which Deno cannot describe",
+            type_registry
+                .display_name(base.id)
+                .unwrap_or_else(|| base.get_name())
+        )),
+        BaseTypeKind::Array { .. } if position == Position::Argument => {
+            Ok("\"pointer\"".to_string())
+        }
+        BaseTypeKind::Array { .. } => Err(anyhow!("array passed by value")),
+        BaseTypeKind::Function { .. } => Err(anyhow!("function type passed by value")),
+        BaseTypeKind::Typedef { .. } => unreachable!("typedefs are unwrapped"),
+    }
+}
+
+/// push the descriptor of a struct member, repeating array elements since
+/// Deno has no array descriptors
+fn push_member(
+    type_registry: &TypeRegistry,
+    type_id: TypeId,
+    pointer_width: usize,
+    members: &mut Vec<String>,
+) -> Result<()> {
+    let unwrapped = unwrap_type(type_registry, type_id)?;
+    match &unwrapped.base.kind {
+        BaseTypeKind::Array {
+            element_type_id,
+            count,
+            ..
+        } if unwrapped.pointer_depth == 0 => {
+            for _ in 0..*count {
+                push_member(type_registry, *element_type_id, pointer_width, members)?;
+            }
+            Ok(())
+        }
+        _ => {
+            members.push(native_type(
+                type_registry,
+                type_id,
+                Position::Value,
+                pointer_width,
+            )?);
+            Ok(())
+        }
+    }
+}
+
+/// `{ struct: [...] }` with one entry per member in offset order. Deno lays
+/// members out at their natural alignment, so packed structs and bitfields
+/// are rejected rather than described with the wrong offsets.
+fn struct_descriptor(
+    type_registry: &TypeRegistry,
+    struct_type: &Type,
+    fields: &[StructField],
+    size: usize,
+    pointer_width: usize,
+) -> Result<String> {
+    let name = type_registry
+        .display_name(struct_type.id)
+        .unwrap_or_else(|| struct_type.get_name());
+    let mut members = Vec::new();
+    let mut end = 0usize;
+    let mut max_alignment = 1;
+    for field in fields {
+        let field_size = type_registry
+            .size_of(field.type_id, pointer_width)
+            .ok_or_else(|| anyhow!("{} has a member of unknown size", name))?;
+        let alignment = type_registry
+            .align_of(field.type_id, pointer_width)
+            .unwrap_or(1)
+            .max(1);
+        if field.offset != end.next_multiple_of(alignment) || field.size != field_size {
+            return Err(anyhow!(
+                "{} is packed or has bitfields, which Deno cannot describe",
+                name
+            ));
+        }
+        push_member(type_registry, field.type_id, pointer_width, &mut members)?;
+        end = field.offset + field_size;
+        max_alignment = max_alignment.max(alignment);
+    }
+    if end.next_multiple_of(max_alignment) != size {
+        return Err(anyhow!(
+            "{} is packed or over-aligned, which Deno cannot describe",
+            name
+        ));
+    }
+
+    Ok(format!("{{ struct: [{}] }}", members.join(", ")))
+}
+
+/// a struct descriptor, enum object or callback definition for one type, or
+/// nothing if it's structural or was already emitted under the same name. a
+/// type Deno can't describe is rendered as a comment saying why.
+fn generate_type_definition(
+    type_registry: &TypeRegistry,
+    type_: &Type,
+    pointer_width: usize,
+    generated_names: &mut HashSet<String>,
+) -> String {
+    // anonymous aggregates are emitted under the typedef that names them
+    let name = type_registry
+        .display_name(type_.id)
+        .unwrap_or_else(|| type_.get_name());
+    if is_anonymous(&name) || generated_names.contains(&name) {
+        return String::new();
+    }
+
+    let output = match &type_.kind {
+        BaseTypeKind::Struct { .. } if type_registry.is_opaque(type_.id) => {
+            Ok(format!("// {} (opaque - use a pointer)\n\n", name))
+        }
+        BaseTypeKind::Struct { fields, size, .. } => {
+            struct_descriptor(type_registry, type_, fields, *size, pointer_width)
+                .map(|descriptor| format!("export const {} = {} as const;\n\n", name, descriptor))
+        }
+        BaseTypeKind::Enum {
+            backing_id,
+            variants,
+            ..
+        } => Ok(generate_enum(
+            &name,
+            variants,
+            type_registry.is_unsigned_enum(*backing_id),
+        )),
+        BaseTypeKind::Typedef {
+            name,
+            aliased_type_id,
+        } => match type_registry.get_type(*aliased_type_id) {
+            Some(Type {
+                kind:
+                    BaseTypeKind::Function {
+                        return_type_id,
+                        parameter_type_ids,
+                        ..
+                    },
+                pointer_depth: 1,
+                ..
+            }) => callback_definition(
+                type_registry,
+                *return_type_id,
+                parameter_type_ids,
+                pointer_width,
+            )
+            .map(|definition| {
+                format!(
+                    "/** for `new Deno.UnsafeCallback({}, fn)` */\n\
+                     export const {} = {} as const;\n\n",
+                    name, name, definition
+                )
+            }),
+            _ => return String::new(),
+        },
+        _ => return String::new(),
+    };
+
+    generated_names.insert(name.clone());
+    match (output, doc::type_description(type_registry, type_)) {
+        (Ok(definition), Some(description)) => doc::block_comment(description, "") + &definition,
+        (Ok(definition), None) => definition,
+        (Err(e), _) => format!("// {}: {}\n\n", name, e),
+    }
+}
+
+fn generate_enum(name: &str, variants: &[EnumVariant], is_unsigned: bool) -> String {
+    let mut output = format!("export const {} = Object.freeze({{\n", name);
+    for variant in variants {
+        output.push_str(&format!(
+            "  {}: {},\n",
+            variant.name,
+            variant.value_string(is_unsigned)
+        ));
+    }
+    output.push_str("});\n\n");
+    output
+}
+
+/// `{ parameters: [...], result: ... }` for a function the library calls
+/// back into, so every pointer is a plain `pointer`
+fn callback_definition(
+    type_registry: &TypeRegistry,
+    return_type_id: Option<TypeId>,
+    parameter_type_ids: &[TypeId],
+    pointer_width: usize,
+) -> Result<String> {
+    let parameters = parameter_type_ids
+        .iter()
+        .map(|id| native_type(type_registry, *id, Position::Value, pointer_width))
+        .collect::<Result<Vec<_>>>()?;
+    let result = result_type(type_registry, return_type_id, pointer_width)?;
+    Ok(format!(
+        "{{ parameters: [{}], result: {} }}",
+        parameters.join(", "),
+        result
+    ))
+}
+
+fn result_type(
+    type_registry: &TypeRegistry,
+    return_type_id: Option<TypeId>,
+    pointer_width: usize,
+) -> Result<String> {
+    match return_type_id {
+        Some(id) if !is_void(type_registry, id)? => {
+            native_type(type_registry, id, Position::Value, pointer_width)
+        }
+        _ => Ok("\"void\"".to_string()),
+    }
+}
+
+fn symbol_definition(
+    type_registry: &TypeRegistry,
+    func: &FunctionSignature,
+    pointer_width: usize,
+) -> Result<String> {
+    if func.is_variadic {
+        return Err(anyhow!(
+            "variadic functions are not supported by Deno.dlopen"
+        ));
+    }
+
+    let parameters = func
+        .parameters
+        .iter()
+        .map(|param| {
+            native_type(
+                type_registry,
+                param.type_id,
+                Position::Argument,
+                pointer_width,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let result = result_type(type_registry, Some(func.return_type_id), pointer_width)?;
+    Ok(format!(
+        "{{ parameters: [{}], result: {} }}",
+        parameters.join(", "),
+        result
+    ))
+}
+
+/// the `symbols` object passed to `Deno.dlopen`, one entry per function. a
+/// function Deno can't call is rendered as a comment saying why.
+fn generate_symbols(
+    type_registry: &TypeRegistry,
+    functions: &[FunctionSignature],
+    pointer_width: usize,
+) -> String {
+    let mut output = String::from("export const symbols = {\n");
+    for func in functions {
+        match symbol_definition(type_registry, func, pointer_width) {
+            Ok(definition) => {
+                let signature = func.to_string(type_registry);
+                let comment = match &func.description {
+                    Some(description) => format!("{}\n\n{}", description, signature),
+                    None => signature,
+                };
+                output.push_str(&doc::block_comment(&comment, "  "));
+                output.push_str(&format!("  {}: {},\n", func.name, definition));
+            }
+            Err(e) => output.push_str(&format!("  // {}: {}\n", func.name, e)),
+        }
+    }
+    output.push_str("} as const;\n\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make(kind: BaseTypeKind, pointer_depth: usize) -> Type {
+        Type {
+            pointer_depth,
+            pointer_qualifiers: vec![Default::default(); pointer_depth],
+            ..Type::new(kind)
+        }
+    }
+
+    fn primitive(name: &str, size: usize, pointer_depth: usize) -> Type {
+        make(
+            BaseTypeKind::Primitive {
+                name: name.to_string(),
+                size,
+                alignment: size,
+                encoding: None,
+            },
+            pointer_depth,
+        )
+    }
+
+    #[test]
+    fn test_native_types() {
+        let mut registry = TypeRegistry::new();
+        let int_id = registry.register_type(primitive("int", 4, 0));
+        let ulong_id = registry.register_type(primitive("long unsigned int", 8, 0));
+        let float_id = registry.register_type(primitive("float", 4, 0));
+        let char_ptr_id = registry.register_type(primitive("char", 1, 1));
+        let void_ptr_id = registry.register_type(primitive("void", 1, 1));
+
+        let native = |id, position| native_type(&registry, id, position, 8).unwrap();
+        assert_eq!(native(int_id, Position::Argument), "\"i32\"");
+        assert_eq!(native(ulong_id, Position::Argument), "\"u64\"");
+        assert_eq!(native(float_id, Position::Value), "\"f32\"");
+        // a typed array can be passed for a scalar pointer argument only
+        assert_eq!(native(char_ptr_id, Position::Argument), "\"buffer\"");
+        assert_eq!(native(char_ptr_id, Position::Value), "\"pointer\"");
+        assert_eq!(native(void_ptr_id, Position::Argument), "\"pointer\"");
+    }
+
+    #[test]
+    fn test_struct_descriptors_need_natural_layout() {
+        let mut registry = TypeRegistry::new();
+        let char_id = registry.register_type(primitive("char", 1, 0));
+        let int_id = registry.register_type(primitive("int", 4, 0));
+        let struct_type = |fields, size| {
+            make(
+                BaseTypeKind::Struct {
+                    name: "Flags".to_string(),
+                    fields,
+                    size,
+                    alignment: 4,
+                    is_opaque: false,
+                },
+                0,
+            )
+        };
+
+        let natural = struct_type(
+            vec![
+                StructField::new("flags", char_id, 0, 1),
+                StructField::new("count", int_id, 4, 4),
+            ],
+            8,
+        );
+        let BaseTypeKind::Struct { fields, size, .. } = &natural.kind else {
+            unreachable!()
+        };
+        assert_eq!(
+            struct_descriptor(&registry, &natural, fields, *size, 8).unwrap(),
+            "{ struct: [\"i8\", \"i32\"] }"
+        );
+
+        let packed = struct_type(
+            vec![
+                StructField::new("flags", char_id, 0, 1),
+                StructField::new("count", int_id, 1, 4),
+            ],
+            5,
+        );
+        let BaseTypeKind::Struct { fields, size, .. } = &packed.kind else {
+            unreachable!()
+        };
+        assert!(struct_descriptor(&registry, &packed, fields, *size, 8).is_err());
+    }
+}
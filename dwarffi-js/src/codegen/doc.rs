@@ -0,0 +1,70 @@
+/// doc comments for generated bindings, from `DW_AT_description`
+use dwarffi::{BaseTypeKind, Type, TypeRegistry};
+
+/// the description of a type definition. a typedef of an anonymous
+/// aggregate is emitted as the aggregate, so either may carry it.
+pub(super) fn type_description<'a>(
+    type_registry: &'a TypeRegistry,
+    type_: &Type,
+) -> Option<&'a str> {
+    type_registry
+        .description(type_.id)
+        .or_else(|| match &type_.kind {
+            BaseTypeKind::Typedef {
+                aliased_type_id, ..
+            } => type_registry.description(*aliased_type_id),
+            _ => None,
+        })
+}
+
+/// `text` as line comments starting with `prefix`, e.g. `/// ` for Rust,
+/// each line indented by `indent`
+pub(super) fn line_comment(text: &str, indent: &str, prefix: &str) -> String {
+    text.lines()
+        .map(|line| {
+            // no trailing space on an empty line
+            let line = format!("{}{}", prefix, line).trim_end().to_string();
+            format!("{}{}\n", indent, line)
+        })
+        .collect()
+}
+
+/// `text` as a `/** ... */` block comment (JSDoc, Javadoc, Doxygen), each
+/// line indented by `indent`
+pub(super) fn block_comment(text: &str, indent: &str) -> String {
+    // `*/` would end the comment early
+    let text = text.replace("*/", "*\\/");
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    if let [line] = lines.as_slice() {
+        return format!("{}/** {} */\n", indent, line);
+    }
+
+    let mut output = format!("{}/**\n", indent);
+    for line in lines {
+        if line.is_empty() {
+            output.push_str(&format!("{} *\n", indent));
+        } else {
+            output.push_str(&format!("{} * {}\n", indent, line));
+        }
+    }
+    output.push_str(&format!("{} */\n", indent));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments_keep_lines_and_close_safely() {
+        assert_eq!(block_comment("add two ints", ""), "/** add two ints */\n");
+        assert_eq!(
+            block_comment("first\n\nsecond */", "  "),
+            "  /**\n   * first\n   *\n   * second *\\/\n   */\n"
+        );
+        assert_eq!(
+            line_comment("first\n\nsecond", "    ", "/// "),
+            "    /// first\n    ///\n    /// second\n"
+        );
+    }
+}
@@ -0,0 +1,333 @@
+/// Graphviz DOT graph of how named types reference each other
+use dwarffi::{BaseTypeKind, Type, TypeId, TypeRegistry};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+pub struct DotCodegen;
+
+impl DotCodegen {
+    /// nodes are structs, unions, enums and typedefs. edges embedding a value
+    /// are solid; edges through a pointer are dashed.
+    pub fn generate(type_registry: &TypeRegistry) -> String {
+        let graph = TypeGraph::new(type_registry);
+
+        // key nodes by unqualified id so `Point`, `const Point` and `Point*`
+        // all land on the same node
+        let mut nodes: HashMap<TypeId, &Type> = HashMap::new();
+        for type_ in type_registry.all_types() {
+            if graph.is_node(type_) {
+                let node_id = type_.unqualified_id();
+                // prefer the unqualified type itself when it is registered
+                if type_.id == node_id || !nodes.contains_key(&node_id) {
+                    nodes.insert(node_id, type_);
+                }
+            }
+        }
+
+        let mut edges = BTreeSet::new();
+        for (&node_id, type_) in &nodes {
+            graph.collect_edges(node_id, type_, &mut edges);
+        }
+
+        let mut sorted_nodes: Vec<(TypeId, &Type)> =
+            nodes.iter().map(|(id, type_)| (*id, *type_)).collect();
+        sorted_nodes.sort_by(|a, b| node_label(a.1).cmp(&node_label(b.1)).then(a.0.cmp(&b.0)));
+
+        let mut output = String::from("digraph types {\n");
+        output.push_str("    rankdir=LR;\n");
+        output.push_str("    node [fontname=\"monospace\"];\n\n");
+
+        for (id, type_) in &sorted_nodes {
+            output.push_str(&format!(
+                "    {} [label=\"{}\", shape={}];\n",
+                node_name(*id),
+                escape(&node_label(type_)),
+                graph.node_shape(type_)
+            ));
+        }
+
+        if !edges.is_empty() {
+            output.push('\n');
+        }
+
+        for edge in &edges {
+            output.push_str(&format!(
+                "    {} -> {} [label=\"{}\"{}];\n",
+                node_name(edge.from),
+                node_name(edge.to),
+                escape(&edge.label),
+                if edge.via_pointer {
+                    ", style=dashed"
+                } else {
+                    ""
+                }
+            ));
+        }
+
+        output.push_str("}\n");
+        output
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Edge {
+    from: TypeId,
+    to: TypeId,
+    label: String,
+    via_pointer: bool,
+}
+
+struct TypeGraph<'a> {
+    type_registry: &'a TypeRegistry,
+    /// ids aliased directly by some typedef
+    typedef_targets: HashSet<TypeId>,
+}
+
+impl<'a> TypeGraph<'a> {
+    fn new(type_registry: &'a TypeRegistry) -> Self {
+        let typedef_targets = type_registry
+            .all_types()
+            .filter_map(|t| match &t.kind {
+                BaseTypeKind::Typedef {
+                    aliased_type_id, ..
+                } => Some(*aliased_type_id),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            type_registry,
+            typedef_targets,
+        }
+    }
+
+    /// structs, unions, enums and typedefs get nodes. typedefs of primitives
+    /// (e.g. `uint8_t`) are left out, and anonymous aggregates behind a
+    /// typedef are drawn as the typedef.
+    fn is_node(&self, type_: &Type) -> bool {
+        match &type_.kind {
+            BaseTypeKind::Struct { .. }
+            | BaseTypeKind::Union { .. }
+            | BaseTypeKind::Enum { .. } => {
+                !is_anonymous(type_) || !self.typedef_targets.contains(&type_.unqualified_id())
+            }
+            BaseTypeKind::Typedef { .. } => !matches!(
+                self.type_registry
+                    .resolve_typedef(type_.id)
+                    .map(|t| &t.kind),
+                Some(BaseTypeKind::Primitive { .. })
+            ),
+            _ => false,
+        }
+    }
+
+    /// the anonymous aggregate a typedef names, if any
+    fn typedef_target(&self, type_: &Type) -> Option<&'a Type> {
+        let BaseTypeKind::Typedef {
+            aliased_type_id, ..
+        } = &type_.kind
+        else {
+            return None;
+        };
+        let aliased = self.type_registry.get_type(*aliased_type_id)?;
+        (aliased.pointer_depth == 0 && is_anonymous(aliased)).then_some(aliased)
+    }
+
+    fn node_shape(&self, type_: &Type) -> &'static str {
+        let type_ = self.typedef_target(type_).unwrap_or(type_);
+        match &type_.kind {
+            BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. } => "box",
+            BaseTypeKind::Enum { .. } => "octagon",
+            _ => "ellipse",
+        }
+    }
+
+    /// add edges from a node to the named types its members refer to
+    fn collect_edges(&self, node_id: TypeId, type_: &Type, edges: &mut BTreeSet<Edge>) {
+        // a typedef of an anonymous aggregate stands in for it
+        let type_ = self.typedef_target(type_).unwrap_or(type_);
+
+        match &type_.kind {
+            BaseTypeKind::Struct { fields, .. } => {
+                for field in fields {
+                    self.add_reference(node_id, field.type_id, "field", edges);
+                }
+            }
+            BaseTypeKind::Union { variants, .. } => {
+                for variant in variants {
+                    self.add_reference(node_id, variant.type_id, "field", edges);
+                }
+            }
+            BaseTypeKind::Typedef {
+                aliased_type_id, ..
+            } => {
+                self.add_reference(node_id, *aliased_type_id, "typedef", edges);
+            }
+            _ => {}
+        }
+    }
+
+    /// follow a reference through pointers, arrays and function signatures
+    /// until it reaches a node
+    fn add_reference(&self, from: TypeId, type_id: TypeId, how: &str, edges: &mut BTreeSet<Edge>) {
+        let mut via_pointer = false;
+        let mut via_array = false;
+        let mut current = type_id;
+
+        // bounded in case of malformed cycles
+        for _ in 0..=self.type_registry.len() {
+            let Some(type_) = self.type_registry.get_type(current) else {
+                return;
+            };
+            via_pointer |= type_.pointer_depth > 0;
+
+            match &type_.kind {
+                BaseTypeKind::Array {
+                    element_type_id, ..
+                } => {
+                    via_array = true;
+                    current = *element_type_id;
+                }
+                BaseTypeKind::Function {
+                    return_type_id,
+                    parameter_type_ids,
+                    ..
+                } => {
+                    // function pointer: its signature is referenced indirectly
+                    for id in return_type_id.iter().chain(parameter_type_ids) {
+                        self.add_pointer_reference(from, *id, edges);
+                    }
+                    return;
+                }
+                BaseTypeKind::Primitive { .. } => return,
+                // anonymous aggregates are drawn as their typedef, and
+                // primitive aliases have no node
+                BaseTypeKind::Typedef {
+                    aliased_type_id, ..
+                } if !self.is_node(type_) => current = *aliased_type_id,
+                _ if !self.is_node(type_) => return,
+                _ => {
+                    // typedefs keep their label; otherwise say how it is reached
+                    let label = if how == "typedef" {
+                        how
+                    } else if via_pointer {
+                        "pointer"
+                    } else if via_array {
+                        "array"
+                    } else {
+                        how
+                    };
+
+                    edges.insert(Edge {
+                        from,
+                        to: type_.unqualified_id(),
+                        label: label.to_string(),
+                        via_pointer,
+                    });
+                    return;
+                }
+            }
+        }
+    }
+
+    fn add_pointer_reference(&self, from: TypeId, type_id: TypeId, edges: &mut BTreeSet<Edge>) {
+        let mut pointer_edges = BTreeSet::new();
+        self.add_reference(from, type_id, "pointer", &mut pointer_edges);
+        edges.extend(pointer_edges.into_iter().map(|edge| Edge {
+            via_pointer: true,
+            label: "pointer".to_string(),
+            ..edge
+        }));
+    }
+}
+
+fn is_anonymous(type_: &Type) -> bool {
+    type_.get_name().starts_with('<')
+}
+
+fn node_name(id: TypeId) -> String {
+    format!("t{:016x}", id.0)
+}
+
+fn node_label(type_: &Type) -> String {
+    match &type_.kind {
+        BaseTypeKind::Struct { name, .. } => format!("struct {}", name),
+        BaseTypeKind::Union { name, .. } => format!("union {}", name),
+        BaseTypeKind::Enum { name, .. } => format!("enum {}", name),
+        _ => type_.get_name(),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dwarffi::StructField;
+
+    fn register(registry: &mut TypeRegistry, kind: BaseTypeKind, pointer_depth: usize) -> TypeId {
+        registry.register_type(Type {
+            pointer_depth,
+            ..Type::new(kind)
+        })
+    }
+
+    fn field(name: &str, type_id: TypeId, offset: usize) -> StructField {
+        StructField {
+            declaration_index: offset / 8,
+            ..StructField::new(name, type_id, offset, 8)
+        }
+    }
+
+    #[test]
+    fn test_value_edges_solid_pointer_edges_dashed() {
+        let mut registry = TypeRegistry::new();
+        let int_id = register(
+            &mut registry,
+            BaseTypeKind::Primitive {
+                name: "int".to_string(),
+                size: 4,
+                alignment: 4,
+                encoding: None,
+            },
+            0,
+        );
+        let point_kind = BaseTypeKind::Struct {
+            name: "Point".to_string(),
+            fields: vec![field("x", int_id, 0), field("y", int_id, 4)],
+            size: 8,
+            alignment: 4,
+            is_opaque: false,
+        };
+        let point_id = register(&mut registry, point_kind.clone(), 0);
+        let point_ptr_id = register(&mut registry, point_kind, 1);
+        let line_id = register(
+            &mut registry,
+            BaseTypeKind::Struct {
+                name: "Line".to_string(),
+                fields: vec![field("start", point_id, 0), field("next", point_ptr_id, 8)],
+                size: 16,
+                alignment: 8,
+                is_opaque: false,
+            },
+            0,
+        );
+
+        let dot = DotCodegen::generate(&registry);
+
+        let from = node_name(line_id);
+        let to = node_name(point_id);
+        assert!(dot.starts_with("digraph types {"));
+        assert!(dot.contains(&format!("{} [label=\"struct Point\", shape=box];", to)));
+        assert!(dot.contains(&format!("{} -> {} [label=\"field\"];", from, to)));
+        assert!(dot.contains(&format!(
+            "{} -> {} [label=\"pointer\", style=dashed];",
+            from, to
+        )));
+
+        // primitives are not nodes, and the pointer type shares Point's node
+        assert!(!dot.contains("label=\"int\""));
+        assert!(!dot.contains(&node_name(point_ptr_id)));
+    }
+}
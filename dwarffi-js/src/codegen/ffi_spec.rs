@@ -0,0 +1,602 @@
+/// language-agnostic "FFI spec": every function with how each value should be
+/// marshaled, and every type flattened to primitive widths, so generators for
+/// other languages can be written against it instead of the type registry.
+/// the format is described by `schema/ffi-spec.schema.json`.
+use super::backend::is_out_param;
+use anyhow::Result;
+use dwarffi::{
+    BaseTypeKind, FunctionSignature, ResolvedPrimitive, TargetInfo, Type, TypeId, TypeRegistry,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// typedef chains and nested arrays are never this deep in real code
+const MAX_DEPTH: usize = 64;
+
+pub struct FfiSpecCodegen;
+
+impl FfiSpecCodegen {
+    /// bumped on any change a consumer could trip over: renamed or removed
+    /// fields, new `kind`s or `passing` values
+    pub const VERSION: u32 = 1;
+
+    pub fn generate(
+        type_registry: &TypeRegistry,
+        functions: &[FunctionSignature],
+        target: &TargetInfo,
+    ) -> Result<String> {
+        let mut builder = SpecBuilder {
+            type_registry,
+            pending: Vec::new(),
+        };
+
+        let functions = functions
+            .iter()
+            .map(|func| builder.function(func))
+            .collect();
+
+        // define every aggregate the functions reach, including through
+        // fields of other aggregates
+        let mut types = BTreeMap::new();
+        let mut seen = HashSet::new();
+        while let Some(id) = builder.pending.pop() {
+            let Some(type_) = type_registry.get_type(id) else {
+                continue;
+            };
+            let name = builder.name(type_);
+            if seen.insert(name.clone()) {
+                let definition = builder.definition(type_);
+                types.insert(name, definition);
+            }
+        }
+
+        let spec = Spec {
+            format: "dwarffi-ffi-spec",
+            version: Self::VERSION,
+            target: SpecTarget {
+                architecture: target.architecture.clone(),
+                pointer_width: target.pointer_width,
+                endianness: target.endianness.to_string(),
+            },
+            functions,
+            types,
+        };
+
+        Ok(serde_json::to_string_pretty(&spec)?)
+    }
+}
+
+#[derive(Serialize)]
+struct Spec {
+    format: &'static str,
+    version: u32,
+    target: SpecTarget,
+    functions: Vec<SpecFunction>,
+    /// structs, unions and enums by name
+    types: BTreeMap<String, SpecDefinition>,
+}
+
+#[derive(Serialize)]
+struct SpecTarget {
+    architecture: String,
+    pointer_width: usize,
+    endianness: String,
+}
+
+#[derive(Serialize)]
+struct SpecFunction {
+    name: String,
+    returns: SpecValue,
+    parameters: Vec<SpecParameter>,
+    is_variadic: bool,
+    is_noreturn: bool,
+}
+
+#[derive(Serialize)]
+struct SpecParameter {
+    name: String,
+    #[serde(flatten)]
+    value: SpecValue,
+}
+
+/// a value crossing the boundary, with a hint for how to pass it
+#[derive(Serialize)]
+struct SpecValue {
+    #[serde(rename = "type")]
+    type_: SpecType,
+    passing: Passing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Passing {
+    /// a scalar copied by value
+    Value,
+    /// a NUL-terminated `char*`
+    String,
+    /// a non-const pointer the function writes its result through, by the
+    /// `out`/`out_` naming convention
+    Out,
+    /// a struct or union copied by value
+    ByValueStruct,
+    /// a function pointer the library calls back
+    Callback,
+    /// any other pointer, passed through as an address
+    Pointer,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum SpecType {
+    Void,
+    Int {
+        size: usize,
+        signed: bool,
+        /// enums are their backing integer, named here
+        #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+        enum_: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        typedef: Option<String>,
+    },
+    Float {
+        size: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        typedef: Option<String>,
+    },
+    Bool {
+        size: usize,
+    },
+    Pointer {
+        pointee: Box<SpecType>,
+        pointee_is_const: bool,
+    },
+    Array {
+        count: usize,
+        element: Box<SpecType>,
+    },
+    Struct {
+        name: String,
+    },
+    Union {
+        name: String,
+    },
+    Callback {
+        /// the function-pointer typedef, if it has one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        returns: Box<SpecType>,
+        parameters: Vec<SpecType>,
+        is_variadic: bool,
+    },
+    /// something dwarffi couldn't model, e.g. a C++ pointer-to-member
+    Unknown {
+        c_type: String,
+    },
+}
+
+impl SpecType {
+    /// levels of pointers before the first non-pointer
+    fn pointer_depth(&self) -> usize {
+        match self {
+            SpecType::Pointer { pointee, .. } => 1 + pointee.pointer_depth(),
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum SpecDefinition {
+    Struct {
+        size: usize,
+        alignment: usize,
+        fields: Vec<SpecField>,
+        /// all `float`/`double` members in at most 16 bytes, which the
+        /// System V AMD64 ABI passes by value in SSE registers
+        is_small_float_aggregate: bool,
+    },
+    Union {
+        size: usize,
+        alignment: usize,
+        fields: Vec<SpecUnionField>,
+    },
+    /// declared but never defined; only usable behind a pointer
+    Opaque,
+    Enum {
+        size: usize,
+        signed: bool,
+        values: Vec<SpecEnumValue>,
+    },
+}
+
+#[derive(Serialize)]
+struct SpecField {
+    name: String,
+    offset: usize,
+    #[serde(rename = "type")]
+    type_: SpecType,
+}
+
+#[derive(Serialize)]
+struct SpecUnionField {
+    name: String,
+    #[serde(rename = "type")]
+    type_: SpecType,
+}
+
+#[derive(Serialize)]
+struct SpecEnumValue {
+    name: String,
+    value: i64,
+}
+
+struct SpecBuilder<'a> {
+    type_registry: &'a TypeRegistry,
+    /// aggregates referenced so far, to be defined under `types`
+    pending: Vec<TypeId>,
+}
+
+impl SpecBuilder<'_> {
+    fn function(&mut self, func: &FunctionSignature) -> SpecFunction {
+        let parameters = func
+            .parameters
+            .iter()
+            .map(|param| SpecParameter {
+                name: param.name.clone(),
+                value: self.value(param.type_id, Some(&param.name)),
+            })
+            .collect();
+
+        SpecFunction {
+            name: func.name.clone(),
+            returns: self.value(func.return_type_id, None),
+            parameters,
+            is_variadic: func.is_variadic,
+            is_noreturn: func.is_noreturn,
+        }
+    }
+
+    /// a parameter (with its name) or return value (without)
+    fn value(&mut self, id: TypeId, parameter: Option<&str>) -> SpecValue {
+        let type_ = self.type_of(id, 0);
+        let is_string = self
+            .type_registry
+            .get_type(id)
+            .is_some_and(|t| t.is_c_string_pointer(self.type_registry));
+
+        let passing = match &type_ {
+            _ if is_string => Passing::String,
+            SpecType::Struct { .. } | SpecType::Union { .. } => Passing::ByValueStruct,
+            SpecType::Callback { .. } => Passing::Callback,
+            SpecType::Pointer {
+                pointee,
+                pointee_is_const: false,
+            } if !matches!(**pointee, SpecType::Void | SpecType::Callback { .. })
+                && parameter.is_some_and(|name| is_out_param(name, type_.pointer_depth())) =>
+            {
+                Passing::Out
+            }
+            SpecType::Pointer { .. } => Passing::Pointer,
+            _ => Passing::Value,
+        };
+
+        SpecValue { type_, passing }
+    }
+
+    fn type_of(&mut self, id: TypeId, depth: usize) -> SpecType {
+        match self.type_registry.get_type(id) {
+            Some(type_) if depth < MAX_DEPTH => self.flatten(type_, depth),
+            Some(type_) => SpecType::Unknown {
+                c_type: type_.to_c_string(self.type_registry),
+            },
+            None => SpecType::Unknown {
+                c_type: format!("<missing:{:016x}>", id.0),
+            },
+        }
+    }
+
+    fn flatten(&mut self, type_: &Type, depth: usize) -> SpecType {
+        // a function pointer's first level is the function itself
+        let mut pointer_depth = type_.pointer_depth;
+        let mut base = match &type_.kind {
+            BaseTypeKind::Function {
+                return_type_id,
+                parameter_type_ids,
+                is_variadic,
+            } if pointer_depth > 0 => {
+                pointer_depth -= 1;
+                SpecType::Callback {
+                    name: None,
+                    returns: Box::new(match return_type_id {
+                        Some(id) => self.type_of(*id, depth + 1),
+                        None => SpecType::Void,
+                    }),
+                    parameters: parameter_type_ids
+                        .iter()
+                        .map(|id| self.type_of(*id, depth + 1))
+                        .collect(),
+                    is_variadic: *is_variadic,
+                }
+            }
+            BaseTypeKind::Function { .. } => SpecType::Unknown {
+                c_type: type_.to_c_string(self.type_registry),
+            },
+            _ if type_.is_placeholder() => SpecType::Unknown {
+                c_type: type_.get_name(),
+            },
+            BaseTypeKind::Primitive { name, .. } if name == "void" => SpecType::Void,
+            BaseTypeKind::Primitive {
+                name,
+                size,
+                encoding,
+                ..
+            } => primitive(&ResolvedPrimitive::from_base(name, *size, *encoding)),
+            BaseTypeKind::Typedef {
+                name,
+                aliased_type_id,
+            } => match self.type_of(*aliased_type_id, depth + 1) {
+                SpecType::Int {
+                    size,
+                    signed,
+                    enum_,
+                    ..
+                } => SpecType::Int {
+                    size,
+                    signed,
+                    enum_,
+                    typedef: Some(name.clone()),
+                },
+                SpecType::Float { size, .. } => SpecType::Float {
+                    size,
+                    typedef: Some(name.clone()),
+                },
+                SpecType::Callback {
+                    name: None,
+                    returns,
+                    parameters,
+                    is_variadic,
+                } => SpecType::Callback {
+                    name: Some(name.clone()),
+                    returns,
+                    parameters,
+                    is_variadic,
+                },
+                aliased => aliased,
+            },
+            BaseTypeKind::Struct { .. } => {
+                self.pending.push(type_.id);
+                SpecType::Struct {
+                    name: self.name(type_),
+                }
+            }
+            BaseTypeKind::Union { .. } => {
+                self.pending.push(type_.id);
+                SpecType::Union {
+                    name: self.name(type_),
+                }
+            }
+            BaseTypeKind::Enum {
+                backing_id,
+                variants,
+                size,
+                ..
+            } => {
+                self.pending.push(type_.id);
+                SpecType::Int {
+                    size: *size,
+                    signed: self.enum_is_signed(*backing_id, variants.iter().map(|v| v.value)),
+                    enum_: Some(self.name(type_)),
+                    typedef: None,
+                }
+            }
+            BaseTypeKind::Array {
+                element_type_id,
+                count,
+                ..
+            } => SpecType::Array {
+                count: *count,
+                element: Box::new(self.type_of(*element_type_id, depth + 1)),
+            },
+        };
+
+        // innermost pointer first, as in the C declaration
+        for level in (0..pointer_depth).rev() {
+            let pointee_is_const = if level + 1 == pointer_depth {
+                type_.is_const
+            } else {
+                type_.pointer_qualifiers(level + 1).is_const
+            };
+            base = SpecType::Pointer {
+                pointee: Box::new(base),
+                pointee_is_const,
+            };
+        }
+        base
+    }
+
+    fn definition(&mut self, type_: &Type) -> SpecDefinition {
+        match &type_.kind {
+            BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. }
+                if self.type_registry.is_opaque(type_.id) =>
+            {
+                SpecDefinition::Opaque
+            }
+            BaseTypeKind::Struct {
+                fields,
+                size,
+                alignment,
+                ..
+            } => SpecDefinition::Struct {
+                size: *size,
+                alignment: *alignment,
+                fields: fields
+                    .iter()
+                    .map(|field| SpecField {
+                        name: field.name.clone(),
+                        offset: field.offset,
+                        type_: self.type_of(field.type_id, 0),
+                    })
+                    .collect(),
+                is_small_float_aggregate: self.type_registry.is_small_float_aggregate(type_.id),
+            },
+            BaseTypeKind::Union {
+                variants,
+                size,
+                alignment,
+                ..
+            } => SpecDefinition::Union {
+                size: *size,
+                alignment: *alignment,
+                fields: variants
+                    .iter()
+                    .map(|variant| SpecUnionField {
+                        name: variant.name.clone(),
+                        type_: self.type_of(variant.type_id, 0),
+                    })
+                    .collect(),
+            },
+            BaseTypeKind::Enum {
+                backing_id,
+                variants,
+                size,
+                ..
+            } => SpecDefinition::Enum {
+                size: *size,
+                signed: self.enum_is_signed(*backing_id, variants.iter().map(|v| v.value)),
+                values: variants
+                    .iter()
+                    .map(|variant| SpecEnumValue {
+                        name: variant.name.clone(),
+                        value: variant.value,
+                    })
+                    .collect(),
+            },
+            // only aggregates are queued
+            _ => SpecDefinition::Opaque,
+        }
+    }
+
+    /// anonymous aggregates go by their typedef, as they do in C
+    fn name(&self, type_: &Type) -> String {
+        self.type_registry
+            .display_name(type_.id)
+            .unwrap_or_else(|| type_.get_name())
+    }
+
+    /// from the backing type when known, otherwise from the values
+    fn enum_is_signed(&self, backing_id: TypeId, mut values: impl Iterator<Item = i64>) -> bool {
+        match self.type_registry.resolve_primitive(backing_id) {
+            Some(backing) => backing.is_signed,
+            None => values.any(|value| value < 0),
+        }
+    }
+}
+
+fn primitive(primitive: &ResolvedPrimitive) -> SpecType {
+    if primitive.is_float {
+        SpecType::Float {
+            size: primitive.size,
+            typedef: None,
+        }
+    } else if primitive.name == "_Bool" || primitive.name == "bool" {
+        SpecType::Bool {
+            size: primitive.size,
+        }
+    } else {
+        SpecType::Int {
+            size: primitive.size,
+            signed: primitive.is_signed,
+            enum_: None,
+            typedef: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dwarffi::{Endianness, Parameter};
+
+    fn make(kind: BaseTypeKind, pointer_depth: usize) -> Type {
+        Type {
+            pointer_depth,
+            pointer_qualifiers: vec![Default::default(); pointer_depth],
+            ..Type::new(kind)
+        }
+    }
+
+    fn primitive(name: &str, size: usize) -> BaseTypeKind {
+        BaseTypeKind::Primitive {
+            name: name.to_string(),
+            size,
+            alignment: size,
+            encoding: None,
+        }
+    }
+
+    #[test]
+    fn test_passing_hints_and_flattened_types() {
+        let mut registry = TypeRegistry::new();
+        let void_id = registry.register_type(make(primitive("void", 0), 0));
+        let int_kind = primitive("int", 4);
+        let int_id = registry.register_type(make(int_kind.clone(), 0));
+        let int_ptr_id = registry.register_type(make(int_kind, 1));
+        let ulong_id = registry.register_type(make(primitive("long unsigned int", 8), 0));
+        let size_t_id = registry.register_type(make(
+            BaseTypeKind::Typedef {
+                name: "size_t".to_string(),
+                aliased_type_id: ulong_id,
+            },
+            0,
+        ));
+        let callback_id = registry.register_type(make(
+            BaseTypeKind::Function {
+                return_type_id: None,
+                parameter_type_ids: vec![int_id],
+                is_variadic: false,
+            },
+            1,
+        ));
+        let handler_id = registry.register_type(make(
+            BaseTypeKind::Typedef {
+                name: "Handler".to_string(),
+                aliased_type_id: callback_id,
+            },
+            0,
+        ));
+
+        let functions = vec![FunctionSignature::new(
+            "read_values",
+            void_id,
+            vec![
+                Parameter::new("len", size_t_id),
+                Parameter::new("values", int_ptr_id),
+                Parameter::new("out_count", int_ptr_id),
+                Parameter::new("on_done", handler_id),
+            ],
+        )];
+        let target = TargetInfo {
+            architecture: "x86_64".to_string(),
+            pointer_width: 8,
+            endianness: Endianness::Little,
+        };
+
+        let spec = FfiSpecCodegen::generate(&registry, &functions, &target).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&spec).unwrap();
+        assert_eq!(value["version"], FfiSpecCodegen::VERSION);
+
+        let function = &value["functions"][0];
+        assert_eq!(function["returns"]["type"]["kind"], "void");
+        let params = &function["parameters"];
+        assert_eq!(
+            params[0]["type"],
+            serde_json::json!({"kind": "int", "size": 8, "signed": false, "typedef": "size_t"})
+        );
+        assert_eq!(params[0]["passing"], "value");
+        assert_eq!(params[1]["passing"], "pointer");
+        assert_eq!(params[2]["passing"], "out");
+        assert_eq!(params[3]["passing"], "callback");
+        assert_eq!(params[3]["type"]["name"], "Handler");
+        assert_eq!(params[3]["type"]["parameters"][0]["kind"], "int");
+    }
+}
@@ -0,0 +1,194 @@
+/// type dependency graph helpers shared by the code generation backends
+use anyhow::{Result, anyhow};
+use dwarffi::{BaseTypeKind, FunctionSignature, Type, TypeId, TypeRegistry};
+use std::collections::HashSet;
+
+/// collect all types referenced by function signatures. primitives are
+/// left out since they don't need definitions.
+pub(super) fn collect_required_types(
+    type_registry: &TypeRegistry,
+    functions: &[FunctionSignature],
+) -> HashSet<TypeId> {
+    let mut required = HashSet::new();
+
+    for func in functions {
+        required.extend(func.reachable_types(type_registry));
+    }
+
+    required.retain(|id| {
+        type_registry
+            .get_type(*id)
+            .is_some_and(|t| !matches!(t.kind, BaseTypeKind::Primitive { .. }))
+    });
+
+    required
+}
+
+/// sort types by dependencies
+pub(super) fn topological_sort(
+    type_registry: &TypeRegistry,
+    types: HashSet<TypeId>,
+) -> Result<Vec<TypeId>> {
+    let mut sorted = Vec::new();
+    let mut visited = HashSet::new();
+    let mut visiting = HashSet::new();
+
+    // type ids are content hashes, so starting from them in id order gives
+    // the same output on every run, unlike HashSet iteration
+    let mut roots: Vec<TypeId> = types.iter().copied().collect();
+    roots.sort();
+
+    for type_id in roots {
+        visit_type(
+            type_registry,
+            type_id,
+            &types,
+            &mut visited,
+            &mut visiting,
+            &mut sorted,
+        )?;
+    }
+
+    Ok(sorted)
+}
+
+fn visit_type(
+    type_registry: &TypeRegistry,
+    type_id: TypeId,
+    all_types: &HashSet<TypeId>,
+    visited: &mut HashSet<TypeId>,
+    visiting: &mut HashSet<TypeId>,
+    sorted: &mut Vec<TypeId>,
+) -> Result<()> {
+    if visited.contains(&type_id) {
+        return Ok(());
+    }
+
+    if visiting.contains(&type_id) {
+        // circular dependency - this is OK for pointers
+        return Ok(());
+    }
+
+    let type_ = type_registry
+        .get_type(type_id)
+        .ok_or_else(|| anyhow!("Type not found: {:?}", type_id))?;
+
+    visiting.insert(type_id);
+
+    // visit dependencies first
+    match &type_.kind {
+        BaseTypeKind::Struct { fields, .. } => {
+            for field in fields {
+                // only visit if it's in our required set and not a pointer
+                let field_type = type_registry.get_type(field.type_id);
+                if all_types.contains(&field.type_id)
+                    && field_type.map(|t| t.pointer_depth == 0).unwrap_or(false)
+                {
+                    visit_type(
+                        type_registry,
+                        field.type_id,
+                        all_types,
+                        visited,
+                        visiting,
+                        sorted,
+                    )?;
+                }
+            }
+        }
+        BaseTypeKind::Union { variants, .. } => {
+            for variant in variants {
+                let variant_type = type_registry.get_type(variant.type_id);
+                if all_types.contains(&variant.type_id)
+                    && variant_type.map(|t| t.pointer_depth == 0).unwrap_or(false)
+                {
+                    visit_type(
+                        type_registry,
+                        variant.type_id,
+                        all_types,
+                        visited,
+                        visiting,
+                        sorted,
+                    )?;
+                }
+            }
+        }
+        BaseTypeKind::Array {
+            element_type_id, ..
+        } if all_types.contains(element_type_id) => {
+            visit_type(
+                type_registry,
+                *element_type_id,
+                all_types,
+                visited,
+                visiting,
+                sorted,
+            )?;
+        }
+        BaseTypeKind::Typedef {
+            aliased_type_id, ..
+        } if all_types.contains(aliased_type_id) => {
+            visit_type(
+                type_registry,
+                *aliased_type_id,
+                all_types,
+                visited,
+                visiting,
+                sorted,
+            )?;
+        }
+        _ => {}
+    }
+
+    visiting.remove(&type_id);
+    visited.insert(type_id);
+    sorted.push(type_id);
+
+    Ok(())
+}
+
+/// a type with its typedef layers peeled off
+pub(super) struct Unwrapped<'a> {
+    /// pointer depth accumulated across the typedef chain
+    pub(super) pointer_depth: usize,
+    /// const at any layer of the chain
+    pub(super) is_const: bool,
+    pub(super) base: &'a Type,
+    /// innermost typedef name, used to name anonymous aggregates and callbacks
+    pub(super) typedef_name: Option<&'a str>,
+}
+
+/// follow `type_id` through its typedefs down to the type they name
+pub(super) fn unwrap_type(type_registry: &TypeRegistry, type_id: TypeId) -> Result<Unwrapped<'_>> {
+    let mut current = type_registry
+        .get_type(type_id)
+        .ok_or_else(|| anyhow!("Type not found: {:?}", type_id))?;
+    let mut pointer_depth = 0;
+    let mut is_const = false;
+    let mut typedef_name = None;
+
+    // bounded so a malformed typedef cycle cannot hang codegen
+    for _ in 0..=type_registry.len() {
+        pointer_depth += current.pointer_depth;
+        is_const |= current.is_const;
+
+        let BaseTypeKind::Typedef {
+            name,
+            aliased_type_id,
+        } = &current.kind
+        else {
+            return Ok(Unwrapped {
+                pointer_depth,
+                is_const,
+                base: current,
+                typedef_name,
+            });
+        };
+
+        typedef_name = Some(name.as_str());
+        current = type_registry
+            .get_type(*aliased_type_id)
+            .ok_or_else(|| anyhow!("Aliased type not found"))?;
+    }
+
+    Err(anyhow!("typedef cycle at {:?}", type_id))
+}
@@ -0,0 +1,282 @@
+/// C header generation: re-declares the library's types and functions from
+/// DWARF, for libraries that ship without a usable header
+use anyhow::{Result, anyhow};
+use dwarffi::{
+    BaseTypeKind, EnumVariant, FunctionSignature, StructField, Type, TypeId, TypeRegistry,
+};
+use std::collections::HashSet;
+
+use super::doc;
+use super::graph::{collect_required_types, topological_sort};
+
+pub struct HeaderCodegen;
+
+impl HeaderCodegen {
+    pub fn generate(
+        type_registry: &TypeRegistry,
+        functions: &[FunctionSignature],
+    ) -> Result<String> {
+        let mut output = String::new();
+
+        output.push_str(&generate_preamble());
+
+        let required_types = collect_required_types(type_registry, functions);
+        let sorted_types = topological_sort(type_registry, required_types)?;
+
+        let mut generated_names = HashSet::new();
+        for type_id in sorted_types {
+            if let Some(type_) = type_registry.get_type(type_id) {
+                output.push_str(&generate_type_definition(
+                    type_registry,
+                    type_,
+                    &mut generated_names,
+                )?);
+            }
+        }
+
+        output.push('\n');
+        for func in functions {
+            if let Some(description) = &func.description {
+                output.push_str(&doc::block_comment(description, ""));
+            }
+            output.push_str(&render_function(func, type_registry));
+            output.push('\n');
+        }
+
+        Ok(output)
+    }
+}
+
+fn generate_preamble() -> String {
+    "// Auto-generated by dwarffi-js\n\
+     // Do not edit manually!\n\
+     // Backend: C header\n\n\
+     #pragma once\n\n\
+     #include <stdbool.h>\n\
+     #include <stddef.h>\n\
+     #include <stdint.h>\n\
+     #include <sys/types.h>\n\n"
+        .to_string()
+}
+
+/// typedefs that come from the standard headers included above
+fn is_standard_typedef(name: &str) -> bool {
+    matches!(
+        name,
+        "int8_t"
+            | "uint8_t"
+            | "int16_t"
+            | "uint16_t"
+            | "int32_t"
+            | "uint32_t"
+            | "int64_t"
+            | "uint64_t"
+            | "intptr_t"
+            | "uintptr_t"
+            | "size_t"
+            | "ssize_t"
+            | "ptrdiff_t"
+            | "bool"
+    ) || name.starts_with("__")
+}
+
+fn is_anonymous(name: &str) -> bool {
+    name.starts_with('<')
+}
+
+/// definition of one type, or nothing if it's structural (pointers, arrays,
+/// primitives) or was already emitted under the same name
+fn generate_type_definition(
+    type_registry: &TypeRegistry,
+    type_: &Type,
+    generated_names: &mut HashSet<String>,
+) -> Result<String> {
+    // pointer and qualified variants share the definition of their base.
+    // tags and typedef names live in separate namespaces, so
+    // `typedef struct Foo Foo` needs both.
+    let name = type_.get_name();
+    let namespace = match &type_.kind {
+        BaseTypeKind::Struct { .. } => "struct",
+        BaseTypeKind::Union { .. } => "union",
+        BaseTypeKind::Enum { .. } => "enum",
+        _ => "typedef",
+    };
+    if is_anonymous(&name) || !generated_names.insert(format!("{} {}", namespace, name)) {
+        return Ok(String::new());
+    }
+
+    let definition = match &type_.kind {
+        // a forward declaration for types only used through pointers
+        BaseTypeKind::Struct { .. } if type_registry.is_opaque(type_.id) => {
+            format!("struct {};\n\n", name)
+        }
+        BaseTypeKind::Union { .. } if type_registry.is_opaque(type_.id) => {
+            format!("union {};\n\n", name)
+        }
+        BaseTypeKind::Struct { fields, .. } => {
+            format!(
+                "struct {} {};\n\n",
+                name,
+                struct_body(type_registry, type_, fields)
+            )
+        }
+        BaseTypeKind::Union { .. } => {
+            format!("union {} {};\n\n", name, union_body(type_registry, type_))
+        }
+        BaseTypeKind::Enum {
+            backing_id,
+            variants,
+            ..
+        } => {
+            let body = enum_body(variants, type_registry.is_unsigned_enum(*backing_id));
+            format!("enum {} {};\n\n", name, body)
+        }
+        BaseTypeKind::Typedef {
+            aliased_type_id, ..
+        } => {
+            if is_standard_typedef(&name) {
+                return Ok(String::new());
+            }
+
+            let aliased = type_registry
+                .get_type(*aliased_type_id)
+                .ok_or_else(|| anyhow!("Aliased type not found"))?;
+            generate_typedef(type_registry, &name, aliased)
+        }
+        BaseTypeKind::Primitive { .. }
+        | BaseTypeKind::Array { .. }
+        | BaseTypeKind::Function { .. } => String::new(),
+    };
+
+    match doc::type_description(type_registry, type_) {
+        Some(description) if !definition.is_empty() => {
+            Ok(doc::block_comment(description, "") + &definition)
+        }
+        _ => Ok(definition),
+    }
+}
+
+/// `typedef struct { ... } Point;` for anonymous aggregates, otherwise the
+/// aliased type declared with the typedef name as declarator
+fn generate_typedef(type_registry: &TypeRegistry, name: &str, aliased: &Type) -> String {
+    let prefix = if aliased.is_const { "const " } else { "" };
+    let star = "*".repeat(aliased.pointer_depth);
+    let declarator = format!("{}{}", star, name);
+
+    let body = match &aliased.kind {
+        BaseTypeKind::Struct {
+            name: tag,
+            fields,
+            is_opaque: false,
+            ..
+        } if is_anonymous(tag) => Some(format!(
+            "struct {}",
+            struct_body(type_registry, aliased, fields)
+        )),
+        BaseTypeKind::Union { name: tag, .. } if is_anonymous(tag) => {
+            Some(format!("union {}", union_body(type_registry, aliased)))
+        }
+        BaseTypeKind::Enum {
+            name: tag,
+            backing_id,
+            variants,
+            ..
+        } if is_anonymous(tag) => Some(format!(
+            "enum {}",
+            enum_body(variants, type_registry.is_unsigned_enum(*backing_id))
+        )),
+        _ => None,
+    };
+
+    match body {
+        Some(body) => format!("typedef {}{} {};\n\n", prefix, body, declarator),
+        None => format!(
+            "typedef {};\n\n",
+            aliased.c_declaration(type_registry, name)
+        ),
+    }
+}
+
+fn struct_body(type_registry: &TypeRegistry, type_: &Type, fields: &[StructField]) -> String {
+    let mut body = String::from("{\n");
+    for field in type_.fields_in_declaration_order() {
+        let declaration = member_declaration(type_registry, field.type_id, &field.name);
+        match field.bit_size {
+            Some(bits) => body.push_str(&format!("    {} : {};\n", declaration, bits)),
+            None => body.push_str(&format!("    {};\n", declaration)),
+        }
+    }
+    if fields.is_empty() {
+        body.push_str("    char _empty;\n");
+    }
+    body.push('}');
+    body
+}
+
+fn union_body(type_registry: &TypeRegistry, type_: &Type) -> String {
+    let mut body = String::from("{\n");
+    if let BaseTypeKind::Union { variants, .. } = &type_.kind {
+        for variant in variants {
+            body.push_str(&format!(
+                "    {};\n",
+                member_declaration(type_registry, variant.type_id, &variant.name)
+            ));
+        }
+    }
+    body.push('}');
+    body
+}
+
+fn enum_body(variants: &[EnumVariant], is_unsigned: bool) -> String {
+    let mut body = String::from("{\n");
+    for variant in variants {
+        body.push_str(&format!(
+            "    {} = {},\n",
+            variant.name,
+            variant.value_string(is_unsigned)
+        ));
+    }
+    body.push('}');
+    body
+}
+
+fn member_declaration(type_registry: &TypeRegistry, type_id: TypeId, name: &str) -> String {
+    type_registry
+        .get_type(type_id)
+        .map(|t| t.c_declaration(type_registry, name))
+        .unwrap_or_else(|| format!("void *{}", name))
+}
+
+/// the C prototype of one function, as [`HeaderCodegen::generate`] declares
+/// it. types are named as in the library, so the header's type definitions
+/// are needed to compile it.
+///
+/// ```
+/// # use dwarffi::{FunctionSignature, Parameter, Type, TypeRegistry};
+/// use dwarffi_js::codegen::header;
+/// # let mut registry = TypeRegistry::new();
+/// # let int = registry.register_type(Type::primitive("int", 4));
+/// # let param = |name: &str| Parameter::new(name, int);
+/// # let add = FunctionSignature::new("add", int, vec![param("a"), param("b")]);
+///
+/// // `int add(int a, int b)`
+/// assert_eq!(header::render_function(&add, &registry), "int add(int a, int b);");
+/// ```
+pub fn render_function(func: &FunctionSignature, type_registry: &TypeRegistry) -> String {
+    let mut params: Vec<String> = func
+        .parameters
+        .iter()
+        .map(|p| member_declaration(type_registry, p.type_id, &p.name))
+        .collect();
+    if func.is_variadic {
+        params.push("...".to_string());
+    } else if params.is_empty() && func.is_prototyped {
+        params.push("void".to_string());
+    }
+
+    let declarator = format!("{}({})", func.name, params.join(", "));
+    format!(
+        "{};",
+        member_declaration(type_registry, func.return_type_id, &declarator)
+    )
+}
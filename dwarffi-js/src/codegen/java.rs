@@ -0,0 +1,845 @@
+/// Java code generation using the Foreign Function & Memory API
+/// (`java.lang.foreign`, Java 22+)
+use anyhow::{Result, anyhow};
+use dwarffi::{
+    BaseTypeKind, EnumVariant, FunctionSignature, ResolvedPrimitive, StructField, Type, TypeId,
+    TypeRegistry, UnionField,
+};
+use std::collections::HashSet;
+
+use super::doc;
+use super::graph::{collect_required_types, topological_sort, unwrap_type};
+
+/// the generated class; Java wants it saved as `NativeLibrary.java`
+const CLASS_NAME: &str = "NativeLibrary";
+
+pub struct JavaCodegen;
+
+impl JavaCodegen {
+    /// `pointer_width` is the target's, used to size pointer members when
+    /// padding struct and union layouts
+    pub fn generate_module(
+        type_registry: &TypeRegistry,
+        functions: &[FunctionSignature],
+        generate_functions: bool,
+        library_path: &str,
+        pointer_width: usize,
+    ) -> Result<String> {
+        let mut output = String::new();
+
+        output.push_str(&generate_header());
+        output.push_str(&format!(
+            "public final class {0} {{\n    private {0}() {{}}\n\n",
+            CLASS_NAME
+        ));
+
+        if generate_functions {
+            output.push_str("    // Library path - UPDATE THIS to match your deployment\n");
+            output.push_str(&format!(
+                "    private static final String LIBRARY_PATH = \"{}\";\n\n",
+                escape_string(library_path)
+            ));
+            output.push_str(
+                "    private static final Linker LINKER = Linker.nativeLinker();\n\
+                 \x20   private static final SymbolLookup LIBRARY =\n\
+                 \x20       SymbolLookup.libraryLookup(LIBRARY_PATH, Arena.global());\n\n",
+            );
+        }
+
+        let required_types = collect_required_types(type_registry, functions);
+        let sorted_types = topological_sort(type_registry, required_types)?;
+
+        let mut generated_names = HashSet::new();
+        for type_id in sorted_types {
+            if let Some(type_) = type_registry.get_type(type_id) {
+                let definition = generate_type_definition(
+                    type_registry,
+                    type_,
+                    pointer_width,
+                    &mut generated_names,
+                );
+                output.push_str(&indent(&definition));
+            }
+        }
+
+        if generate_functions {
+            for func in functions {
+                if let Some(description) = &func.description {
+                    output.push_str(&indent(&doc::block_comment(description, "")));
+                }
+                output.push_str(&indent(&render_function(func, type_registry)));
+                output.push('\n');
+            }
+        }
+
+        // drop the blank line after the last member
+        if output.ends_with("\n\n") {
+            output.pop();
+        }
+        output.push_str("}\n");
+
+        Ok(output)
+    }
+}
+
+fn generate_header() -> String {
+    format!(
+        "// Auto-generated by dwarffi-js\n\
+         // Do not edit manually!\n\
+         // Backend: Java Foreign Function & Memory API (Java 22+)\n\
+         //\n\
+         // save as {}.java. each struct and union has a LAYOUT whose fields are\n\
+         // reached with e.g. LAYOUT.varHandle(MemoryLayout.PathElement.groupElement(\"x\"))\n\n\
+         import java.lang.foreign.*;\n\
+         import java.lang.invoke.MethodHandle;\n\n\
+         import static java.lang.foreign.ValueLayout.*;\n\n",
+        CLASS_NAME
+    )
+}
+
+/// indent every non-empty line by one level, for members of the class
+fn indent(text: &str) -> String {
+    let mut output = String::new();
+    for line in text.lines() {
+        if !line.is_empty() {
+            output.push_str("    ");
+            output.push_str(line);
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// escape a Java string literal's contents
+fn escape_string(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// class name for a struct/union/enum, falling back to the typedef name for
+/// anonymous aggregates, then to their synthetic name if demunged
+fn aggregate_name(
+    type_registry: &TypeRegistry,
+    aggregate: &Type,
+    name: &str,
+    typedef_name: Option<&str>,
+) -> Option<String> {
+    if !name.starts_with('<') {
+        return Some(escape_identifier(name));
+    }
+    typedef_name
+        .map(escape_identifier)
+        .or_else(|| type_registry.demunged_name(aggregate.id))
+}
+
+/// map a resolved C primitive to a `ValueLayout` constant by size. Java
+/// integers are signed, so unsigned types share the signed layouts.
+fn primitive_layout(primitive: &ResolvedPrimitive) -> Result<&'static str> {
+    if primitive.name == "_Bool" {
+        return Ok("JAVA_BOOLEAN");
+    }
+
+    let layout = match (primitive.is_float, primitive.size) {
+        (true, 4) => "JAVA_FLOAT",
+        (true, 8) => "JAVA_DOUBLE",
+        (false, 1) => "JAVA_BYTE",
+        (false, 2) => "JAVA_SHORT",
+        (false, 4) => "JAVA_INT",
+        (false, 8) => "JAVA_LONG",
+        _ => {
+            return Err(anyhow!(
+                "unsupported primitive type for Java: {} ({} bytes)",
+                primitive.name,
+                primitive.size
+            ));
+        }
+    };
+
+    Ok(layout)
+}
+
+fn is_void(type_registry: &TypeRegistry, type_id: TypeId) -> Result<bool> {
+    let unwrapped = unwrap_type(type_registry, type_id)?;
+    Ok(unwrapped.pointer_depth == 0
+        && matches!(&unwrapped.base.kind, BaseTypeKind::Primitive { name, .. } if name == "void"))
+}
+
+/// layout of a value passed to or returned from a function. arrays decay to
+/// pointers, and aggregates passed by value need a named layout.
+fn value_layout(type_registry: &TypeRegistry, type_id: TypeId) -> Result<String> {
+    let unwrapped = unwrap_type(type_registry, type_id)?;
+
+    if unwrapped.pointer_depth > 0 {
+        return Ok("ADDRESS".to_string());
+    }
+
+    match &unwrapped.base.kind {
+        BaseTypeKind::Primitive { .. } => {
+            let primitive = type_registry
+                .resolve_primitive(unwrapped.base.id)
+                .ok_or_else(|| anyhow!("Primitive type not resolvable: {:?}", type_id))?;
+            Ok(primitive_layout(&primitive)?.to_string())
+        }
+        BaseTypeKind::Enum { backing_id, .. } => value_layout(type_registry, *backing_id),
+        BaseTypeKind::Struct { name, .. } | BaseTypeKind::Union { name, .. }
+            if type_registry.is_opaque(unwrapped.base.id) =>
+        {
+            Err(anyhow!("opaque type {} passed by value", name))
+        }
+        BaseTypeKind::Struct { name, .. } | BaseTypeKind::Union { name, .. } => {
+            aggregate_name(type_registry, unwrapped.base, name, unwrapped.typedef_name)
+                .map(|name| format!("{}.LAYOUT", name))
+                .ok_or_else(|| anyhow!("anonymous aggregate passed by value"))
+        }
+        BaseTypeKind::Array { .. } => Ok("ADDRESS".to_string()),
+        BaseTypeKind::Function { .. } => Err(anyhow!("function type passed by value")),
+        BaseTypeKind::Typedef { .. } => unreachable!("typedefs are unwrapped"),
+    }
+}
+
+/// the largest alignment a member at `offset` can have
+fn offset_alignment(offset: usize) -> usize {
+    if offset == 0 {
+        usize::MAX
+    } else {
+        1 << offset.trailing_zeros()
+    }
+}
+
+/// lower a layout's alignment to `alignment` where C aligns it less than
+/// Java would, e.g. in packed structs or `double` on 32-bit x86
+fn with_alignment(layout: String, natural: usize, alignment: usize) -> String {
+    if alignment < natural {
+        format!("{}.withByteAlignment({})", layout, alignment)
+    } else {
+        layout
+    }
+}
+
+/// layout of a struct member or union variant. `alignment` is the most its
+/// offset allows; anonymous aggregates are laid out inline.
+fn member_layout(
+    type_registry: &TypeRegistry,
+    type_id: TypeId,
+    alignment: usize,
+    pointer_width: usize,
+) -> Result<String> {
+    let unwrapped = unwrap_type(type_registry, type_id)?;
+
+    if unwrapped.pointer_depth > 0 {
+        return Ok(with_alignment(
+            "ADDRESS".to_string(),
+            pointer_width,
+            alignment,
+        ));
+    }
+
+    match &unwrapped.base.kind {
+        BaseTypeKind::Primitive {
+            alignment: c_alignment,
+            ..
+        } => {
+            let primitive = type_registry
+                .resolve_primitive(unwrapped.base.id)
+                .ok_or_else(|| anyhow!("Primitive type not resolvable: {:?}", type_id))?;
+            Ok(with_alignment(
+                primitive_layout(&primitive)?.to_string(),
+                primitive.size,
+                alignment.min((*c_alignment).max(1)),
+            ))
+        }
+        BaseTypeKind::Enum { backing_id, .. } => {
+            member_layout(type_registry, *backing_id, alignment, pointer_width)
+        }
+        BaseTypeKind::Array {
+            element_type_id,
+            count,
+            stride,
+            ..
+        } => {
+            // every element must be aligned, not just the first
+            let element_alignment = alignment.min(offset_alignment(*stride));
+            Ok(format!(
+                "MemoryLayout.sequenceLayout({}, {})",
+                count,
+                member_layout(
+                    type_registry,
+                    *element_type_id,
+                    element_alignment,
+                    pointer_width
+                )?
+            ))
+        }
+        BaseTypeKind::Struct { name, .. } | BaseTypeKind::Union { name, .. }
+            if type_registry.is_opaque(unwrapped.base.id) =>
+        {
+            Err(anyhow!("opaque type {} has no layout", name))
+        }
+        BaseTypeKind::Struct { name, .. } | BaseTypeKind::Union { name, .. }
+            if let Some(name) =
+                aggregate_name(type_registry, unwrapped.base, name, unwrapped.typedef_name) =>
+        {
+            Ok(format!("{}.LAYOUT", name))
+        }
+        // anonymous aggregates without a class of their own
+        BaseTypeKind::Struct { fields, size, .. } => {
+            let members = struct_members(type_registry, fields, *size, pointer_width)?;
+            Ok(format!("MemoryLayout.structLayout({})", members.join(", ")))
+        }
+        BaseTypeKind::Union { variants, size, .. } => {
+            let members = union_members(type_registry, variants, *size, pointer_width)?;
+            Ok(format!("MemoryLayout.unionLayout({})", members.join(", ")))
+        }
+        BaseTypeKind::Function { .. } => Err(anyhow!("function type as a struct member")),
+        BaseTypeKind::Typedef { .. } => unreachable!("typedefs are unwrapped"),
+    }
+}
+
+fn named(layout: String, name: &str) -> String {
+    if name.is_empty() {
+        layout
+    } else {
+        format!("{}.withName(\"{}\")", layout, name)
+    }
+}
+
+fn padding(size: usize) -> String {
+    format!("MemoryLayout.paddingLayout({})", size)
+}
+
+/// a struct's members with explicit padding, so every offset is C's
+/// whatever Java would otherwise choose. fields are in memory order.
+fn struct_members(
+    type_registry: &TypeRegistry,
+    fields: &[StructField],
+    size: usize,
+    pointer_width: usize,
+) -> Result<Vec<String>> {
+    let mut members = Vec::new();
+    let mut end = 0;
+
+    for field in fields {
+        // bitfields share a storage unit: the first member in it stands in
+        // for the unit and the rest fall inside it. a unit that overlaps
+        // what's laid out already or runs past the end (packed) is left to
+        // padding.
+        let offset = match field.bit_offset {
+            Some(bit_offset) if field.is_bitfield() => {
+                let unit = field.size.max(1);
+                bit_offset as usize / (unit * 8) * unit
+            }
+            _ => field.offset,
+        };
+        if offset < end || (field.is_bitfield() && offset + field.size > size) {
+            continue;
+        }
+
+        if offset > end {
+            members.push(padding(offset - end));
+        }
+        let layout = member_layout(
+            type_registry,
+            field.type_id,
+            offset_alignment(offset),
+            pointer_width,
+        )?;
+        members.push(named(layout, &field.name));
+        end = offset + field.size;
+    }
+
+    if size > end {
+        members.push(padding(size - end));
+    }
+
+    Ok(members)
+}
+
+/// a union's variants, padded to its size when C's is larger than its
+/// largest variant
+fn union_members(
+    type_registry: &TypeRegistry,
+    variants: &[UnionField],
+    size: usize,
+    pointer_width: usize,
+) -> Result<Vec<String>> {
+    let mut members = Vec::new();
+    let mut largest = 0;
+
+    for variant in variants {
+        let layout = member_layout(type_registry, variant.type_id, usize::MAX, pointer_width)?;
+        members.push(named(layout, &variant.name));
+        largest = largest.max(
+            type_registry
+                .size_of(variant.type_id, pointer_width)
+                .unwrap_or(0),
+        );
+    }
+
+    if size > largest {
+        members.push(padding(size));
+    }
+
+    Ok(members)
+}
+
+/// Java reserves these; C identifiers that collide get a `_` suffix
+fn escape_identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "abstract",
+        "assert",
+        "boolean",
+        "break",
+        "byte",
+        "case",
+        "catch",
+        "char",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "extends",
+        "false",
+        "final",
+        "finally",
+        "float",
+        "for",
+        "goto",
+        "if",
+        "implements",
+        "import",
+        "instanceof",
+        "int",
+        "interface",
+        "long",
+        "native",
+        "new",
+        "null",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "short",
+        "static",
+        "strictfp",
+        "super",
+        "switch",
+        "synchronized",
+        "this",
+        "throw",
+        "throws",
+        "transient",
+        "true",
+        "try",
+        "void",
+        "volatile",
+        "while",
+        "_",
+    ];
+
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// generate a single type definition: a nested class named after the type.
+/// a type Java can't lay out is rendered as a comment saying why.
+fn generate_type_definition(
+    type_registry: &TypeRegistry,
+    type_: &Type,
+    pointer_width: usize,
+    generated_names: &mut HashSet<String>,
+) -> String {
+    // (class name, kind to generate) -- typedefs name anonymous aggregates.
+    // pointer depth is ignored: `Person*` may be the only use of `Person`
+    let (name, kind) = match &type_.kind {
+        BaseTypeKind::Struct { name, .. }
+        | BaseTypeKind::Union { name, .. }
+        | BaseTypeKind::Enum { name, .. } => {
+            // anonymous aggregates are generated through their typedef, or
+            // under their synthetic name if demunged
+            match aggregate_name(type_registry, type_, name, None) {
+                Some(name) => (name, &type_.kind),
+                None => return String::new(),
+            }
+        }
+        BaseTypeKind::Typedef {
+            name,
+            aliased_type_id,
+        } => {
+            let Some(aliased) = type_registry.get_type(*aliased_type_id) else {
+                return String::new();
+            };
+
+            match &aliased.kind {
+                BaseTypeKind::Function { .. } if aliased.pointer_depth == 1 => {
+                    (escape_identifier(name), &aliased.kind)
+                }
+                BaseTypeKind::Struct {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Union {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Enum {
+                    name: aliased_name, ..
+                } if aliased.pointer_depth == 0 && aliased_name.starts_with('<') => {
+                    (escape_identifier(name), &aliased.kind)
+                }
+                // Java has no typedefs; uses resolve through to the aliased type
+                _ => return String::new(),
+            }
+        }
+        _ => return String::new(),
+    };
+
+    if generated_names.contains(&name) {
+        return String::new();
+    }
+
+    let output = match kind {
+        BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. }
+            if type_registry.is_opaque(type_.id) =>
+        {
+            Ok(format!(
+                "// {} (opaque - no definition available, use a MemorySegment)\n\n",
+                name
+            ))
+        }
+        BaseTypeKind::Struct { fields, size, .. } => {
+            struct_members(type_registry, fields, *size, pointer_width)
+                .map(|members| generate_layout_class(&name, "struct", &members))
+        }
+        BaseTypeKind::Union { variants, size, .. } => {
+            union_members(type_registry, variants, *size, pointer_width)
+                .map(|members| generate_layout_class(&name, "union", &members))
+        }
+        BaseTypeKind::Enum {
+            backing_id,
+            variants,
+            ..
+        } => generate_enum(type_registry, &name, *backing_id, variants),
+        BaseTypeKind::Function {
+            return_type_id,
+            parameter_type_ids,
+            ..
+        } => function_descriptor(type_registry, *return_type_id, parameter_type_ids)
+            .map(|descriptor| generate_callback(&name, &descriptor)),
+        _ => return String::new(),
+    };
+
+    generated_names.insert(name.clone());
+    match (output, doc::type_description(type_registry, type_)) {
+        (Ok(definition), Some(description)) => doc::block_comment(description, "") + &definition,
+        (Ok(definition), None) => definition,
+        (Err(e), _) => format!("// {}: {}\n\n", name, e),
+    }
+}
+
+/// `kind` is `struct` or `union`, naming both the `MemoryLayout` factory
+/// and the layout class
+fn generate_layout_class(name: &str, kind: &str, members: &[String]) -> String {
+    let class = if kind == "struct" {
+        "StructLayout"
+    } else {
+        "UnionLayout"
+    };
+
+    let mut output = String::new();
+    output.push_str(&format!(
+        "public static final class {0} {{\n    private {0}() {{}}\n\n",
+        name
+    ));
+    output.push_str(&format!(
+        "    public static final {} LAYOUT = MemoryLayout.{}Layout(\n",
+        class, kind
+    ));
+    let members = members
+        .iter()
+        .map(|member| format!("        {}", member))
+        .collect::<Vec<_>>();
+    if !members.is_empty() {
+        output.push_str(&members.join(",\n"));
+        output.push('\n');
+    }
+    output.push_str(&format!("    ).withName(\"{}\");\n}}\n\n", name));
+
+    output
+}
+
+/// enums become a class of constants typed like their backing integer
+fn generate_enum(
+    type_registry: &TypeRegistry,
+    name: &str,
+    backing_id: TypeId,
+    variants: &[EnumVariant],
+) -> Result<String> {
+    let backing = type_registry
+        .resolve_primitive(backing_id)
+        .ok_or_else(|| anyhow!("Enum backing type not found"))?;
+    let is_long = backing.size == 8;
+
+    let mut output = String::new();
+    output.push_str(&format!(
+        "public static final class {0} {{\n    private {0}() {{}}\n\n",
+        name
+    ));
+
+    for variant in variants {
+        // values of unsigned enums can overflow a Java int
+        let value = if is_long {
+            format!("{}L", variant.value)
+        } else if i32::try_from(variant.value).is_ok() {
+            variant.value.to_string()
+        } else {
+            format!("(int) {}L", variant.value)
+        };
+        output.push_str(&format!(
+            "    public static final {} {} = {};\n",
+            if is_long { "long" } else { "int" },
+            escape_identifier(&variant.name),
+            value
+        ));
+    }
+
+    output.push_str("}\n\n");
+
+    Ok(output)
+}
+
+/// function-pointer typedefs get a descriptor for `Linker.upcallStub`
+fn generate_callback(name: &str, descriptor: &str) -> String {
+    format!(
+        "public static final class {0} {{\n    private {0}() {{}}\n\n    \
+         public static final FunctionDescriptor DESCRIPTOR =\n        {1};\n}}\n\n",
+        name, descriptor
+    )
+}
+
+fn function_descriptor(
+    type_registry: &TypeRegistry,
+    return_type_id: Option<TypeId>,
+    parameter_type_ids: &[TypeId],
+) -> Result<String> {
+    let mut layouts = parameter_type_ids
+        .iter()
+        .map(|id| value_layout(type_registry, *id))
+        .collect::<Result<Vec<_>>>()?;
+
+    match return_type_id {
+        Some(id) if !is_void(type_registry, id)? => {
+            layouts.insert(0, value_layout(type_registry, id)?);
+            Ok(format!("FunctionDescriptor.of({})", layouts.join(", ")))
+        }
+        _ => Ok(format!("FunctionDescriptor.ofVoid({})", layouts.join(", "))),
+    }
+}
+
+/// the `MethodHandle` of one function, a member of the module's
+/// `NativeLibrary` class. a function the linker can't call is rendered as a
+/// comment saying why.
+///
+/// ```
+/// # use dwarffi::{FunctionSignature, Parameter, Type, TypeRegistry};
+/// use dwarffi_js::codegen::java;
+/// # let mut registry = TypeRegistry::new();
+/// # let int = registry.register_type(Type::primitive("int", 4));
+/// # let param = |name: &str| Parameter::new(name, int);
+/// # let add = FunctionSignature::new("add", int, vec![param("a"), param("b")]);
+///
+/// // `int add(int a, int b)`
+/// assert_eq!(
+///     java::render_function(&add, &registry),
+///     "public static final MethodHandle add = LINKER.downcallHandle(\n    \
+///      LIBRARY.find(\"add\").orElseThrow(),\n    \
+///      FunctionDescriptor.of(JAVA_INT, JAVA_INT, JAVA_INT));\n"
+/// );
+/// ```
+pub fn render_function(func: &FunctionSignature, type_registry: &TypeRegistry) -> String {
+    downcall_handle(type_registry, func).unwrap_or_else(|e| format!("// {}: {}\n", func.name, e))
+}
+
+fn downcall_handle(type_registry: &TypeRegistry, func: &FunctionSignature) -> Result<String> {
+    if func.is_variadic {
+        return Ok(format!(
+            "// {}: variadic function not supported (see Linker.Option.firstVariadicArg)\n",
+            func.name
+        ));
+    }
+
+    let parameter_type_ids = func
+        .parameters
+        .iter()
+        .map(|param| param.type_id)
+        .collect::<Vec<_>>();
+    let descriptor = function_descriptor(
+        type_registry,
+        Some(func.return_type_id),
+        &parameter_type_ids,
+    )?;
+
+    Ok(format!(
+        "public static final MethodHandle {} = LINKER.downcallHandle(\n    \
+         LIBRARY.find(\"{}\").orElseThrow(),\n    {});\n",
+        escape_identifier(&func.name),
+        func.name,
+        descriptor
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, type_id: TypeId, offset: usize, size: usize) -> StructField {
+        StructField {
+            declaration_index: offset,
+            ..StructField::new(name, type_id, offset, size)
+        }
+    }
+
+    #[test]
+    fn test_primitive_layouts() {
+        let resolved = |name: &str, size, is_float| ResolvedPrimitive {
+            name: name.to_string(),
+            size,
+            is_signed: true,
+            is_float,
+        };
+
+        assert_eq!(
+            primitive_layout(&resolved("char", 1, false)).unwrap(),
+            "JAVA_BYTE"
+        );
+        assert_eq!(
+            primitive_layout(&resolved("long unsigned int", 8, false)).unwrap(),
+            "JAVA_LONG"
+        );
+        assert_eq!(
+            primitive_layout(&resolved("float", 4, true)).unwrap(),
+            "JAVA_FLOAT"
+        );
+        assert_eq!(
+            primitive_layout(&resolved("_Bool", 1, false)).unwrap(),
+            "JAVA_BOOLEAN"
+        );
+        assert!(primitive_layout(&resolved("long double", 16, true)).is_err());
+    }
+
+    #[test]
+    fn test_struct_layout_pads_to_c_offsets() {
+        let mut registry = TypeRegistry::new();
+        let char_ = registry.register_type(Type::primitive("char", 1));
+        let int = registry.register_type(Type::primitive("int", 4));
+        let double = registry.register_type(Type::primitive("double", 8));
+
+        // struct { char tag; int value; double weight; char flag; }
+        let members = struct_members(
+            &registry,
+            &[
+                field("tag", char_, 0, 1),
+                field("value", int, 4, 4),
+                field("weight", double, 8, 8),
+                field("flag", char_, 16, 1),
+            ],
+            24,
+            8,
+        )
+        .unwrap();
+        assert_eq!(
+            members,
+            [
+                "JAVA_BYTE.withName(\"tag\")",
+                "MemoryLayout.paddingLayout(3)",
+                "JAVA_INT.withName(\"value\")",
+                "JAVA_DOUBLE.withName(\"weight\")",
+                "JAVA_BYTE.withName(\"flag\")",
+                "MemoryLayout.paddingLayout(7)",
+            ]
+        );
+
+        // packed: struct __attribute__((packed)) { char tag; double weight; },
+        // and `unsigned a : 12, b : 12` sharing one unit
+        let members = struct_members(
+            &registry,
+            &[field("tag", char_, 0, 1), field("weight", double, 1, 8)],
+            9,
+            8,
+        )
+        .unwrap();
+        assert_eq!(
+            members[1],
+            "JAVA_DOUBLE.withByteAlignment(1).withName(\"weight\")"
+        );
+        let bitfield = |name, bit_offset: u32, bits| StructField {
+            bit_size: Some(bits),
+            bit_offset: Some(bit_offset),
+            ..field(name, int, bit_offset as usize / 8, 4)
+        };
+        let members = struct_members(
+            &registry,
+            &[bitfield("a", 0, 12), bitfield("b", 12, 12)],
+            4,
+            8,
+        )
+        .unwrap();
+        assert_eq!(members, ["JAVA_INT.withName(\"a\")"]);
+
+        // `unsigned ready : 1, mode : 3; int count; unsigned error : 1;`
+        let members = struct_members(
+            &registry,
+            &[
+                bitfield("ready", 0, 1),
+                bitfield("mode", 1, 3),
+                field("count", int, 4, 4),
+                bitfield("error", 64, 1),
+            ],
+            12,
+            8,
+        )
+        .unwrap();
+        assert_eq!(
+            members,
+            [
+                "JAVA_INT.withName(\"ready\")",
+                "JAVA_INT.withName(\"count\")",
+                "JAVA_INT.withName(\"error\")",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_union_pads_to_its_size() {
+        let mut registry = TypeRegistry::new();
+        let int = registry.register_type(Type::primitive("int", 4));
+        let members = union_members(
+            &registry,
+            &[UnionField {
+                name: "as_int".to_string(),
+                type_id: int,
+                discriminant_value: None,
+            }],
+            8,
+            8,
+        )
+        .unwrap();
+        assert_eq!(
+            members,
+            [
+                "JAVA_INT.withName(\"as_int\")",
+                "MemoryLayout.paddingLayout(8)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_escape_identifier() {
+        assert_eq!(escape_identifier("native"), "native_");
+        assert_eq!(escape_identifier("count"), "count");
+    }
+}
@@ -1,8 +1,8 @@
 /// JavaScript code generation dispatch
 use anyhow::Result;
-use dwarffi::{FunctionSignature, TypeRegistry};
+use dwarffi::{FunctionSignature, MacroConstant, TypeRegistry};
 
-use super::backend::FfiBackend;
+use super::backend::JsOptions;
 use super::koffi;
 
 pub struct JsCodegen;
@@ -11,18 +11,21 @@ impl JsCodegen {
     pub fn generate_module(
         type_registry: &TypeRegistry,
         functions: &[FunctionSignature],
+        macros: &[MacroConstant],
         generate_types: bool,
         generate_functions: bool,
         library_path: &str,
-        _backend: FfiBackend,
+        options: JsOptions,
     ) -> Result<String> {
         // Currently only Koffi is supported
         koffi::generate(
             type_registry,
             functions,
+            macros,
             generate_types,
             generate_functions,
             library_path,
+            &options,
         )
     }
 }
@@ -0,0 +1,406 @@
+/// JSON representation of extracted functions and types, for consumers that
+/// want the analysis without going through a language backend
+use anyhow::{Result, anyhow};
+use dwarffi::{
+    BaseTypeKind, FunctionSignature, InlineKind, Qualifiers, ResolvedPrimitive, TargetInfo, Type,
+    TypeId, TypeRegistry,
+};
+use serde::Serialize;
+
+pub struct JsonCodegen;
+
+impl JsonCodegen {
+    /// JSON Schema (draft 2020-12) for the output of `generate`
+    pub const SCHEMA: &'static str = include_str!("../../schema/analysis.schema.json");
+
+    pub fn generate(
+        type_registry: &TypeRegistry,
+        functions: &[FunctionSignature],
+        target: &TargetInfo,
+    ) -> Result<String> {
+        let functions = functions
+            .iter()
+            .map(|func| json_function(type_registry, func))
+            .collect::<Result<Vec<_>>>()?;
+
+        // sort by name, then id, so output is stable across runs
+        let mut types: Vec<JsonType> = type_registry
+            .all_types()
+            .map(|t| json_type(type_registry, t))
+            .collect();
+        types.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+
+        let output = JsonOutput {
+            target: JsonTarget {
+                architecture: target.architecture.clone(),
+                pointer_width: target.pointer_width,
+                endianness: target.endianness.to_string(),
+            },
+            functions,
+            types,
+        };
+
+        Ok(serde_json::to_string_pretty(&output)?)
+    }
+}
+
+#[derive(Serialize)]
+struct JsonOutput {
+    target: JsonTarget,
+    functions: Vec<JsonFunction>,
+    types: Vec<JsonType>,
+}
+
+#[derive(Serialize)]
+struct JsonTarget {
+    architecture: String,
+    pointer_width: usize,
+    endianness: String,
+}
+
+#[derive(Serialize)]
+struct JsonFunction {
+    name: String,
+    return_type: JsonTypeRef,
+    parameters: Vec<JsonParameter>,
+    is_variadic: bool,
+    is_exported: bool,
+    is_external: bool,
+    is_noreturn: bool,
+    inline_kind: InlineKind,
+    is_virtual: bool,
+    /// hex for the same reason as type ids
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonParameter {
+    name: String,
+    #[serde(rename = "type")]
+    type_: JsonTypeRef,
+    is_artificial: bool,
+}
+
+/// a use of a type in a signature. typedefs carry their name and, when they
+/// bottom out in a primitive, the concrete primitive they resolve to.
+#[derive(Serialize)]
+struct JsonTypeRef {
+    id: String,
+    c_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    typedef: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolved: Option<ResolvedPrimitive>,
+}
+
+#[derive(Serialize)]
+struct JsonType {
+    id: String,
+    name: String,
+    pointer_depth: usize,
+    is_const: bool,
+    is_volatile: bool,
+    /// qualifiers of each pointer level, outermost first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pointer_qualifiers: Vec<Qualifiers>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(flatten)]
+    kind: JsonTypeKind,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum JsonTypeKind {
+    Primitive {
+        size: usize,
+    },
+    Struct {
+        size: usize,
+        is_opaque: bool,
+        fields: Vec<JsonField>,
+    },
+    Union {
+        size: usize,
+        variants: Vec<JsonVariant>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        discriminant: Option<JsonDiscriminant>,
+    },
+    Enum {
+        size: usize,
+        backing_id: String,
+        variants: Vec<JsonEnumVariant>,
+    },
+    Array {
+        size: usize,
+        element_type_id: String,
+        count: usize,
+        stride: usize,
+        is_dynamic: bool,
+    },
+    Typedef {
+        aliased_type_id: String,
+    },
+    Function {
+        return_type_id: Option<String>,
+        parameter_type_ids: Vec<String>,
+        is_variadic: bool,
+    },
+}
+
+#[derive(Serialize)]
+struct JsonField {
+    name: String,
+    type_id: String,
+    offset: usize,
+    size: usize,
+    declaration_index: usize,
+    /// only for members stored in a byte order other than the target's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    endianness: Option<String>,
+    /// only for bitfields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bit_size: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bit_offset: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct JsonVariant {
+    name: String,
+    type_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    discriminant_value: Option<i64>,
+}
+
+/// the tag member of a discriminated union (a Rust enum)
+#[derive(Serialize)]
+struct JsonDiscriminant {
+    name: String,
+    type_id: String,
+    offset: usize,
+}
+
+#[derive(Serialize)]
+struct JsonEnumVariant {
+    name: String,
+    /// unsigned when the backing type is, for values above `i64::MAX`
+    value: serde_json::Number,
+}
+
+/// type ids are 64-bit hashes, which javascript numbers cannot represent
+/// exactly, so they are emitted as hex strings
+fn id_string(id: TypeId) -> String {
+    format!("{:016x}", id.0)
+}
+
+fn json_function(type_registry: &TypeRegistry, func: &FunctionSignature) -> Result<JsonFunction> {
+    let parameters = func
+        .parameters
+        .iter()
+        .map(|param| {
+            Ok(JsonParameter {
+                name: param.name.clone(),
+                type_: json_type_ref(type_registry, param.type_id)?,
+                is_artificial: param.is_artificial,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(JsonFunction {
+        name: func.name.clone(),
+        return_type: json_type_ref(type_registry, func.return_type_id)?,
+        parameters,
+        is_variadic: func.is_variadic,
+        is_exported: func.is_exported,
+        is_external: func.is_external,
+        is_noreturn: func.is_noreturn,
+        inline_kind: func.inline_kind,
+        is_virtual: func.is_virtual,
+        address: func.address.map(|address| format!("{:#x}", address)),
+        description: func.description.clone(),
+    })
+}
+
+fn json_type_ref(type_registry: &TypeRegistry, type_id: TypeId) -> Result<JsonTypeRef> {
+    let type_ = type_registry
+        .get_type(type_id)
+        .ok_or_else(|| anyhow!("Type not found: {:?}", type_id))?;
+
+    let typedef = match &type_.kind {
+        BaseTypeKind::Typedef { name, .. } => Some(name.clone()),
+        _ => None,
+    };
+
+    // only typedefs need resolving; a bare primitive already names itself
+    let resolved = typedef
+        .as_ref()
+        .and_then(|_| type_registry.resolve_primitive(type_id));
+
+    Ok(JsonTypeRef {
+        id: id_string(type_id),
+        c_type: type_.to_c_string(type_registry),
+        typedef,
+        resolved,
+    })
+}
+
+fn json_type(type_registry: &TypeRegistry, type_: &Type) -> JsonType {
+    let kind = match &type_.kind {
+        BaseTypeKind::Primitive { size, .. } => JsonTypeKind::Primitive { size: *size },
+        BaseTypeKind::Struct {
+            fields,
+            size,
+            is_opaque,
+            ..
+        } => JsonTypeKind::Struct {
+            size: *size,
+            is_opaque: *is_opaque,
+            fields: fields
+                .iter()
+                .map(|f| JsonField {
+                    name: f.name.clone(),
+                    type_id: id_string(f.type_id),
+                    offset: f.offset,
+                    size: f.size,
+                    declaration_index: f.declaration_index,
+                    endianness: f.endianness.map(|endianness| endianness.to_string()),
+                    bit_size: f.bit_size,
+                    bit_offset: f.bit_offset,
+                })
+                .collect(),
+        },
+        BaseTypeKind::Union {
+            variants,
+            size,
+            discriminant,
+            ..
+        } => JsonTypeKind::Union {
+            size: *size,
+            variants: variants
+                .iter()
+                .map(|v| JsonVariant {
+                    name: v.name.clone(),
+                    type_id: id_string(v.type_id),
+                    discriminant_value: v.discriminant_value,
+                })
+                .collect(),
+            discriminant: discriminant.as_ref().map(|d| JsonDiscriminant {
+                name: d.name.clone(),
+                type_id: id_string(d.type_id),
+                offset: d.offset,
+            }),
+        },
+        BaseTypeKind::Enum {
+            backing_id,
+            variants,
+            size,
+            ..
+        } => JsonTypeKind::Enum {
+            size: *size,
+            backing_id: id_string(*backing_id),
+            variants: variants
+                .iter()
+                .map(|v| JsonEnumVariant {
+                    name: v.name.clone(),
+                    value: if type_registry.is_unsigned_enum(*backing_id) {
+                        (v.value as u64).into()
+                    } else {
+                        v.value.into()
+                    },
+                })
+                .collect(),
+        },
+        BaseTypeKind::Array {
+            element_type_id,
+            count,
+            size,
+            stride,
+            is_dynamic,
+        } => JsonTypeKind::Array {
+            size: *size,
+            element_type_id: id_string(*element_type_id),
+            count: *count,
+            stride: *stride,
+            is_dynamic: *is_dynamic,
+        },
+        BaseTypeKind::Typedef {
+            aliased_type_id, ..
+        } => JsonTypeKind::Typedef {
+            aliased_type_id: id_string(*aliased_type_id),
+        },
+        BaseTypeKind::Function {
+            return_type_id,
+            parameter_type_ids,
+            is_variadic,
+        } => JsonTypeKind::Function {
+            return_type_id: return_type_id.map(id_string),
+            parameter_type_ids: parameter_type_ids.iter().map(|id| id_string(*id)).collect(),
+            is_variadic: *is_variadic,
+        },
+    };
+
+    JsonType {
+        id: id_string(type_.id),
+        // anonymous aggregates go by their typedef, as they do in C
+        name: type_registry
+            .display_name(type_.id)
+            .unwrap_or_else(|| type_.get_name()),
+        pointer_depth: type_.pointer_depth,
+        is_const: type_.is_const,
+        is_volatile: type_.is_volatile,
+        pointer_qualifiers: type_.pointer_qualifiers.clone(),
+        // on the type itself, not repeated for each pointer to it
+        description: (type_.id == type_.unqualified_id())
+            .then(|| type_registry.description(type_.id))
+            .flatten()
+            .map(str::to_string),
+        kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dwarffi::{Endianness, Parameter};
+
+    #[test]
+    fn test_typedef_parameter_includes_resolved_primitive() {
+        let mut registry = TypeRegistry::new();
+        let void_id = registry.register_type(Type::primitive("void", 0));
+        let ulong_id = registry.register_type(Type::primitive("long unsigned int", 8));
+        let size_t_id = registry.register_type(Type::new(BaseTypeKind::Typedef {
+            name: "size_t".to_string(),
+            aliased_type_id: ulong_id,
+        }));
+
+        let functions = vec![FunctionSignature::new(
+            "set_len",
+            void_id,
+            vec![Parameter::new("len", size_t_id)],
+        )];
+        let target = TargetInfo {
+            architecture: "x86_64".to_string(),
+            pointer_width: 8,
+            endianness: Endianness::Little,
+        };
+
+        let json = JsonCodegen::generate(&registry, &functions, &target).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        let param_type = &value["functions"][0]["parameters"][0]["type"];
+        assert_eq!(param_type["c_type"], "size_t");
+        assert_eq!(param_type["typedef"], "size_t");
+        assert_eq!(param_type["resolved"]["name"], "long unsigned int");
+        assert_eq!(param_type["resolved"]["size"], 8);
+        assert_eq!(param_type["resolved"]["is_signed"], false);
+
+        // plain primitives are not annotated
+        let return_type = &value["functions"][0]["return_type"];
+        assert!(return_type.get("typedef").is_none());
+        assert!(return_type.get("resolved").is_none());
+    }
+}
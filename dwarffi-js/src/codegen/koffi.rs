@@ -1,29 +1,53 @@
 /// javascript code generation using koffi
 /// (https://koffi.dev)
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
 use dwarffi::{
-    BaseTypeKind, EnumVariant, FunctionSignature, StructField, Type, TypeId, TypeRegistry,
-    UnionField,
+    BaseTypeKind, EnumVariant, FunctionPointer, FunctionSignature, MacroConstant, MacroValue,
+    StructField, Type, TypeId, TypeRegistry, UnionField,
 };
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
+
+use super::backend::{JsOptions, ModuleFormat, TypeMappingOverrides};
+use super::doc;
+use super::graph::{collect_required_types, topological_sort};
 
 pub fn generate(
     type_registry: &TypeRegistry,
     functions: &[FunctionSignature],
+    macros: &[MacroConstant],
     generate_types: bool,
     generate_functions: bool,
     library_path: &str,
+    options: &JsOptions,
 ) -> Result<String> {
     let mut output = String::new();
 
     output.push_str(&generate_header());
 
-    output.push_str(&generate_imports());
+    output.push_str(&generate_imports(options.module_format));
+
+    // what each function is bound to in JS; koffi still looks up the C name
+    let bindings: Vec<String> = functions
+        .iter()
+        .map(|f| options.rename.apply(&f.name))
+        .collect();
+    for (i, binding) in bindings.iter().enumerate() {
+        if let Some(j) = bindings[..i].iter().position(|b| b == binding) {
+            bail!(
+                "{} and {} would both be bound as {}",
+                functions[j].name,
+                functions[i].name,
+                binding
+            );
+        }
+    }
 
     // need types that are referenced in functions
-    let required_types = collect_required_types(type_registry, functions)?;
+    let required_types = collect_required_types(type_registry, functions);
 
-    let mut generated_names = HashSet::new();
+    // sorted, since the exports are listed from it
+    let mut generated_names = BTreeSet::new();
+    let mut callback_names = Vec::new();
 
     if generate_types {
         let sorted_types = topological_sort(type_registry, required_types)?;
@@ -31,39 +55,144 @@ pub fn generate(
         // dependency order
         for type_id in sorted_types {
             if let Some(type_) = type_registry.get_type(type_id) {
-                let type_def =
-                    generate_type_definition(type_registry, type_, &mut generated_names)?;
+                let type_def = generate_type_definition(
+                    type_registry,
+                    type_,
+                    &options.type_map,
+                    &mut generated_names,
+                )?;
                 output.push_str(&type_def);
             }
         }
     }
 
+    // a macro can't share a name with a type or function it would shadow,
+    // nor redeclare one of the module's own identifiers like `lib`; either
+    // would be a syntax error, so the macro is left out
+    let identifiers = module_identifiers(options);
+    let constants: Vec<&MacroConstant> = macros
+        .iter()
+        .filter(|m| {
+            !generated_names.contains(&m.name)
+                && !bindings.contains(&m.name)
+                && !identifiers.contains(&m.name.as_str())
+        })
+        .collect();
+    if !constants.is_empty() {
+        output.push_str(&generate_constants(&constants)?);
+    }
+
+    // members of anonymous enums have no type to live under, so they are
+    // constants like macros, unless a macro of the same name came first
+    let enum_constants: Vec<(String, i64)> = type_registry
+        .anonymous_enum_constants()
+        .into_iter()
+        .filter(|(name, _)| {
+            !generated_names.contains(name)
+                && !bindings.contains(name)
+                && !identifiers.contains(&name.as_str())
+                && !constants.iter().any(|m| m.name == *name)
+        })
+        .collect();
+    if !enum_constants.is_empty() {
+        output.push_str(&generate_enum_constants(&enum_constants));
+    }
+    let constant_names: Vec<String> = constants
+        .iter()
+        .map(|c| c.name.clone())
+        .chain(enum_constants.into_iter().map(|(name, _)| name))
+        .collect();
+
     if generate_functions {
         let callback_types = collect_callback_types(type_registry, functions)?;
 
         // in koffi, callbacks need to be created with .proto() before library
         // is loaded, so do that first.
         if !callback_types.is_empty() {
-            output.push_str(&generate_callback_protos(type_registry, &callback_types)?);
+            output.push_str(&generate_callback_protos(
+                type_registry,
+                &options.type_map,
+                &callback_types,
+            )?);
+            callback_names = callback_types.into_iter().map(|(name, _)| name).collect();
+        }
+
+        // nor can a binding take the name of a type, callback or one of the
+        // module's own identifiers
+        for (func, binding) in functions.iter().zip(&bindings) {
+            let taken = if generated_names.contains(binding) {
+                format!("the type {}", binding)
+            } else if callback_names.contains(binding) {
+                format!("the callback {}", binding)
+            } else if identifiers.contains(&binding.as_str()) {
+                format!("the module's own {}", binding)
+            } else {
+                continue;
+            };
+            if !func.is_variadic {
+                bail!(
+                    "{} and {} would both be bound as {}",
+                    func.name,
+                    taken,
+                    binding
+                );
+            }
         }
 
         output.push_str(&generate_function_bindings(
             type_registry,
             functions,
+            &bindings,
             library_path,
+            options,
         )?);
     }
 
+    // with a runtime load, `init` returns the functions instead
+    let function_names = if options.runtime_load {
+        vec!["init"]
+    } else {
+        exported_functions(functions, &bindings)
+    };
     output.push_str(&generate_exports(
         generate_types,
         generate_functions,
         &generated_names,
-        functions,
+        &callback_names,
+        &constant_names,
+        &function_names,
+        options,
     )?);
 
     Ok(output)
 }
 
+/// names the generated module declares besides the library's types,
+/// functions and constants: koffi and the loaded library, the export
+/// namespaces, and in CommonJS the module wrapper's parameters
+fn module_identifiers(options: &JsOptions) -> Vec<&str> {
+    let mut identifiers = vec![
+        "koffi",
+        "lib",
+        "registerCallback",
+        "types",
+        "callbacks",
+        "constants",
+    ];
+    identifiers.push(if options.runtime_load {
+        "init"
+    } else {
+        "LIBRARY_PATH"
+    });
+    if let Some(namespace) = &options.namespace {
+        identifiers.push(namespace);
+    }
+    if options.module_format == ModuleFormat::Cjs {
+        identifiers.extend(["module", "exports", "require", "__filename", "__dirname"]);
+    }
+    identifiers
+}
+
 fn generate_header() -> String {
     "// Auto-generated by dwarffi-js\n\
          // Do not edit manually!\n\
@@ -73,28 +202,11 @@ fn generate_header() -> String {
         .to_string()
 }
 
-fn generate_imports() -> String {
-    "const koffi = require('koffi')\n\n".to_string()
-}
-
-/// collect all types referenced by function signatures
-fn collect_required_types(
-    type_registry: &TypeRegistry,
-    functions: &[FunctionSignature],
-) -> Result<HashSet<TypeId>> {
-    let mut required = HashSet::new();
-
-    for func in functions {
-        // add return type
-        add_type_transitive(type_registry, func.return_type_id, &mut required)?;
-
-        // add parameter types
-        for param in &func.parameters {
-            add_type_transitive(type_registry, param.type_id, &mut required)?;
-        }
+fn generate_imports(module_format: ModuleFormat) -> String {
+    match module_format {
+        ModuleFormat::Esm => "import koffi from 'koffi'\n\n".to_string(),
+        ModuleFormat::Cjs => "const koffi = require('koffi')\n\n".to_string(),
     }
-
-    Ok(required)
 }
 
 /// collect all function pointer types (callbacks) used in function signatures
@@ -150,23 +262,13 @@ fn collect_callbacks_from_type(
     }
 
     // check if this is a typedef to a function pointer
-    if let BaseTypeKind::Typedef {
-        name,
-        aliased_type_id,
-    } = &type_.kind
+    // (e.g., "typedef void (*Callback)(...)")
+    if let BaseTypeKind::Typedef { name, .. } = &type_.kind
+        && type_.pointer_depth == 0
+        && type_.is_function_pointer(type_registry)
     {
-        let aliased = type_registry
-            .get_type(*aliased_type_id)
-            .ok_or_else(|| anyhow!("Aliased type not found"))?;
-
-        // check if typedef points to a function pointer
-        if aliased.pointer_depth > 0
-            && let BaseTypeKind::Function { .. } = &aliased.kind
-        {
-            // This is a typedef to a function pointer (e.g., "typedef void (*Callback)(...)")
-            callbacks.push((name.clone(), *aliased_type_id));
-            return Ok(());
-        }
+        callbacks.push((name.clone(), type_id));
+        return Ok(());
     }
 
     // recursively check composite types
@@ -187,193 +289,12 @@ fn collect_callbacks_from_type(
     Ok(())
 }
 
-/// recursively add a type and all types it references
-fn add_type_transitive(
-    type_registry: &TypeRegistry,
-    type_id: TypeId,
-    collected: &mut HashSet<TypeId>,
-) -> Result<()> {
-    if collected.contains(&type_id) {
-        return Ok(());
-    }
-
-    let type_ = type_registry
-        .get_type(type_id)
-        .ok_or_else(|| anyhow!("Type not found: {:?}", type_id))?;
-
-    // skip primitives - they don't need definitions
-    if matches!(&type_.kind, BaseTypeKind::Primitive { .. }) {
-        return Ok(());
-    }
-
-    collected.insert(type_id);
-
-    // recursively collect referenced types
-    match &type_.kind {
-        BaseTypeKind::Struct { fields, .. } => {
-            for field in fields {
-                add_type_transitive(type_registry, field.type_id, collected)?;
-            }
-        }
-        BaseTypeKind::Union { variants, .. } => {
-            for variant in variants {
-                add_type_transitive(type_registry, variant.type_id, collected)?;
-            }
-        }
-        BaseTypeKind::Enum { backing_id, .. } => {
-            add_type_transitive(type_registry, *backing_id, collected)?;
-        }
-        BaseTypeKind::Array {
-            element_type_id, ..
-        } => {
-            add_type_transitive(type_registry, *element_type_id, collected)?;
-        }
-        BaseTypeKind::Typedef {
-            aliased_type_id, ..
-        } => {
-            add_type_transitive(type_registry, *aliased_type_id, collected)?;
-        }
-        BaseTypeKind::Function {
-            return_type_id,
-            parameter_type_ids,
-            ..
-        } => {
-            if let Some(ret_id) = return_type_id {
-                add_type_transitive(type_registry, *ret_id, collected)?;
-            }
-            for param_id in parameter_type_ids {
-                add_type_transitive(type_registry, *param_id, collected)?;
-            }
-        }
-        BaseTypeKind::Primitive { .. } => {}
-    }
-
-    Ok(())
-}
-
-/// sort types by dependencies
-fn topological_sort(type_registry: &TypeRegistry, types: HashSet<TypeId>) -> Result<Vec<TypeId>> {
-    let mut sorted = Vec::new();
-    let mut visited = HashSet::new();
-    let mut visiting = HashSet::new();
-
-    for &type_id in &types {
-        visit_type(
-            type_registry,
-            type_id,
-            &types,
-            &mut visited,
-            &mut visiting,
-            &mut sorted,
-        )?;
-    }
-
-    Ok(sorted)
-}
-
-fn visit_type(
-    type_registry: &TypeRegistry,
-    type_id: TypeId,
-    all_types: &HashSet<TypeId>,
-    visited: &mut HashSet<TypeId>,
-    visiting: &mut HashSet<TypeId>,
-    sorted: &mut Vec<TypeId>,
-) -> Result<()> {
-    if visited.contains(&type_id) {
-        return Ok(());
-    }
-
-    if visiting.contains(&type_id) {
-        // circular dependency - this is OK for pointers
-        return Ok(());
-    }
-
-    let type_ = type_registry
-        .get_type(type_id)
-        .ok_or_else(|| anyhow!("Type not found: {:?}", type_id))?;
-
-    visiting.insert(type_id);
-
-    // visit dependencies first
-    match &type_.kind {
-        BaseTypeKind::Struct { fields, .. } => {
-            for field in fields {
-                // only visit if it's in our required set and not a pointer
-                let field_type = type_registry.get_type(field.type_id);
-                if all_types.contains(&field.type_id)
-                    && field_type.map(|t| t.pointer_depth == 0).unwrap_or(false)
-                {
-                    visit_type(
-                        type_registry,
-                        field.type_id,
-                        all_types,
-                        visited,
-                        visiting,
-                        sorted,
-                    )?;
-                }
-            }
-        }
-        BaseTypeKind::Union { variants, .. } => {
-            for variant in variants {
-                let variant_type = type_registry.get_type(variant.type_id);
-                if all_types.contains(&variant.type_id)
-                    && variant_type.map(|t| t.pointer_depth == 0).unwrap_or(false)
-                {
-                    visit_type(
-                        type_registry,
-                        variant.type_id,
-                        all_types,
-                        visited,
-                        visiting,
-                        sorted,
-                    )?;
-                }
-            }
-        }
-        BaseTypeKind::Array {
-            element_type_id, ..
-        } => {
-            if all_types.contains(element_type_id) {
-                visit_type(
-                    type_registry,
-                    *element_type_id,
-                    all_types,
-                    visited,
-                    visiting,
-                    sorted,
-                )?;
-            }
-        }
-        BaseTypeKind::Typedef {
-            aliased_type_id, ..
-        } => {
-            if all_types.contains(aliased_type_id) {
-                visit_type(
-                    type_registry,
-                    *aliased_type_id,
-                    all_types,
-                    visited,
-                    visiting,
-                    sorted,
-                )?;
-            }
-        }
-        _ => {}
-    }
-
-    visiting.remove(&type_id);
-    visited.insert(type_id);
-    sorted.push(type_id);
-
-    Ok(())
-}
-
 /// generate a single type definition
 fn generate_type_definition(
     type_registry: &TypeRegistry,
     type_: &Type,
-    generated_names: &mut HashSet<String>,
+    type_map: &TypeMappingOverrides,
+    generated_names: &mut BTreeSet<String>,
 ) -> Result<String> {
     // get the type name for deduplication
     let type_name = match &type_.kind {
@@ -396,31 +317,36 @@ fn generate_type_definition(
             // primitives don't need definitions in Koffi
             Ok(String::new())
         }
-        BaseTypeKind::Struct {
-            name,
-            fields,
-            is_opaque,
-            ..
-        } => {
+        BaseTypeKind::Struct { name, fields, .. } => {
             // skip anonymous structs - they'll be referenced by their typedef
             if name.starts_with("<") {
                 return Ok(String::new());
             }
-            generate_struct(type_registry, name, fields, *is_opaque)
+            let is_opaque = type_registry.is_opaque(type_.id);
+            generate_struct(type_registry, type_map, name, fields, is_opaque)
         }
         BaseTypeKind::Union { name, variants, .. } => {
             // skip anonymous unions
             if name.starts_with("<") {
                 return Ok(String::new());
             }
-            generate_union(type_registry, name, variants)
+            if type_registry.is_opaque(type_.id) {
+                // only used through pointers, like an opaque struct
+                return generate_struct(type_registry, type_map, name, &[], true);
+            }
+            generate_union(type_registry, type_map, name, variants)
         }
-        BaseTypeKind::Enum { name, variants, .. } => {
+        BaseTypeKind::Enum {
+            name,
+            backing_id,
+            variants,
+            ..
+        } => {
             // skip anonymous enums
             if name.starts_with("<") {
                 return Ok(String::new());
             }
-            generate_enum(name, variants)
+            generate_enum(name, variants, type_registry.is_unsigned_enum(*backing_id))
         }
         BaseTypeKind::Array { .. } => {
             // arrays are generated inline in struct fields
@@ -429,7 +355,13 @@ fn generate_type_definition(
         BaseTypeKind::Typedef {
             name,
             aliased_type_id,
-        } => generate_typedef(type_registry, name, *aliased_type_id, generated_names),
+        } => generate_typedef(
+            type_registry,
+            type_map,
+            name,
+            *aliased_type_id,
+            generated_names,
+        ),
         BaseTypeKind::Function { .. } => {
             // function pointers are generated inline
             Ok(String::new())
@@ -444,11 +376,17 @@ fn generate_type_definition(
         generated_names.insert(name);
     }
 
-    result
+    match (result, doc::type_description(type_registry, type_)) {
+        (Ok(definition), Some(description)) if !definition.is_empty() => {
+            Ok(doc::block_comment(description, "") + &definition)
+        }
+        (result, _) => result,
+    }
 }
 
 fn generate_struct(
     type_registry: &TypeRegistry,
+    type_map: &TypeMappingOverrides,
     name: &str,
     fields: &[StructField],
     is_opaque: bool,
@@ -457,14 +395,15 @@ fn generate_struct(
 
     if is_opaque {
         output.push_str(&format!("// {} (opaque - no definition available)\n", name));
-        output.push_str(&format!("const {} = koffi.opaque()\n\n", name));
+        // named, so signatures can refer to `Name*`
+        output.push_str(&format!("const {} = koffi.opaque('{}')\n\n", name, name));
         return Ok(output);
     }
 
     output.push_str(&format!("const {} = koffi.struct('{}', {{\n", name, name));
 
     for field in fields {
-        let field_type = type_to_koffi_string(type_registry, field.type_id)?;
+        let field_type = type_to_koffi_string(type_registry, type_map, field.type_id)?;
 
         // add comment if field is an enum (to help developers)
         let field_type_info = type_registry.get_type(field.type_id);
@@ -490,6 +429,7 @@ fn generate_struct(
 
 fn generate_union(
     type_registry: &TypeRegistry,
+    type_map: &TypeMappingOverrides,
     name: &str,
     variants: &[UnionField],
 ) -> Result<String> {
@@ -498,7 +438,7 @@ fn generate_union(
     output.push_str(&format!("const {} = koffi.union('{}', {{\n", name, name));
 
     for variant in variants {
-        let variant_type = type_to_koffi_string(type_registry, variant.type_id)?;
+        let variant_type = type_to_koffi_string(type_registry, type_map, variant.type_id)?;
         output.push_str(&format!("  {}: {},\n", variant.name, variant_type));
     }
 
@@ -507,14 +447,18 @@ fn generate_union(
     Ok(output)
 }
 
-fn generate_enum(name: &str, variants: &[EnumVariant]) -> Result<String> {
+fn generate_enum(name: &str, variants: &[EnumVariant], is_unsigned: bool) -> Result<String> {
     let mut output = String::new();
 
     output.push_str(&format!("// Enum: {}\n", name));
     output.push_str(&format!("const {} = {{\n", name));
 
     for variant in variants {
-        output.push_str(&format!("  {}: {},\n", variant.name, variant.value));
+        output.push_str(&format!(
+            "  {}: {},\n",
+            variant.name,
+            variant.value_string(is_unsigned)
+        ));
     }
 
     output.push_str("}\n\n");
@@ -524,9 +468,10 @@ fn generate_enum(name: &str, variants: &[EnumVariant]) -> Result<String> {
 
 fn generate_typedef(
     type_registry: &TypeRegistry,
+    type_map: &TypeMappingOverrides,
     name: &str,
     aliased_type_id: TypeId,
-    generated_names: &mut HashSet<String>,
+    generated_names: &mut BTreeSet<String>,
 ) -> Result<String> {
     let aliased_type = type_registry
         .get_type(aliased_type_id)
@@ -542,21 +487,24 @@ fn generate_typedef(
         BaseTypeKind::Struct {
             name: struct_name,
             fields,
-            is_opaque,
             ..
         } if struct_name.starts_with("<") => {
-            generate_struct(type_registry, name, fields, *is_opaque)
+            let is_opaque = type_registry.is_opaque(aliased_type_id);
+            generate_struct(type_registry, type_map, name, fields, is_opaque)
         }
         BaseTypeKind::Union {
             name: union_name,
             variants,
             ..
-        } if union_name.starts_with("<") => generate_union(type_registry, name, variants),
+        } if union_name.starts_with("<") => generate_union(type_registry, type_map, name, variants),
         BaseTypeKind::Enum {
             name: enum_name,
+            backing_id,
             variants,
             ..
-        } if enum_name.starts_with("<") => generate_enum(name, variants),
+        } if enum_name.starts_with("<") => {
+            generate_enum(name, variants, type_registry.is_unsigned_enum(*backing_id))
+        }
         _ => {
             // check if the aliased type has a named definition
             let aliased_name = match &aliased_type.kind {
@@ -581,14 +529,18 @@ fn generate_typedef(
 }
 
 /// convert a TypeId to a Koffi type string (e.g., "'int'", "'Point *'", "koffi.array('char', 64)")
-fn type_to_koffi_string(type_registry: &TypeRegistry, type_id: TypeId) -> Result<String> {
+fn type_to_koffi_string(
+    type_registry: &TypeRegistry,
+    type_map: &TypeMappingOverrides,
+    type_id: TypeId,
+) -> Result<String> {
     let type_ = type_registry
         .get_type(type_id)
         .ok_or_else(|| anyhow!("Type not found: {:?}", type_id))?;
 
     // build the base type string
     let mut type_str = match &type_.kind {
-        BaseTypeKind::Primitive { name, .. } => primitive_to_koffi(name)?,
+        BaseTypeKind::Primitive { name, .. } => primitive_koffi_string(type_map, name)?,
         BaseTypeKind::Struct { name, .. } => format!("'{}'", name),
         BaseTypeKind::Union { name, .. } => format!("'{}'", name),
         BaseTypeKind::Enum { backing_id, .. } => {
@@ -599,7 +551,7 @@ fn type_to_koffi_string(type_registry: &TypeRegistry, type_id: TypeId) -> Result
                 .ok_or_else(|| anyhow!("Enum backing type not found"))?;
 
             match &backing_type.kind {
-                BaseTypeKind::Primitive { name, .. } => primitive_to_koffi(name)?,
+                BaseTypeKind::Primitive { name, .. } => primitive_koffi_string(type_map, name)?,
                 _ => "'int'".to_string(), // default fallback
             }
         }
@@ -608,7 +560,7 @@ fn type_to_koffi_string(type_registry: &TypeRegistry, type_id: TypeId) -> Result
             count,
             ..
         } => {
-            let elem_type = type_to_koffi_string(type_registry, *element_type_id)?;
+            let elem_type = type_to_koffi_string(type_registry, type_map, *element_type_id)?;
             return Ok(format!("koffi.array({}, {})", elem_type, count));
         }
         BaseTypeKind::Typedef {
@@ -621,20 +573,19 @@ fn type_to_koffi_string(type_registry: &TypeRegistry, type_id: TypeId) -> Result
                 .ok_or_else(|| anyhow!("Aliased type not found"))?;
 
             // if typedef points to function pointer, keep the typedef name
-            if aliased.pointer_depth > 0 {
-                if let BaseTypeKind::Function { .. } = &aliased.kind {
-                    // function pointer typedef - use typedef name
-                    format!("'{}'", name)
-                } else {
-                    // pointer to something else - recursively resolve
-                    return type_to_koffi_string(type_registry, *aliased_type_id);
-                }
+            if aliased.is_function_pointer(type_registry) {
+                format!("'{}'", name)
+            } else if aliased.pointer_depth > 0 {
+                // pointer to something else - recursively resolve
+                return type_to_koffi_string(type_registry, type_map, *aliased_type_id);
+            } else if let Some(exact) = exact_width_koffi(name) {
+                format!("'{}'", exact)
             } else {
                 // not a pointer - check if it's an enum or other type
                 match &aliased.kind {
                     BaseTypeKind::Enum { .. } => {
                         // Typedef to enum - resolve to underlying int type
-                        return type_to_koffi_string(type_registry, *aliased_type_id);
+                        return type_to_koffi_string(type_registry, type_map, *aliased_type_id);
                     }
                     BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. } => {
                         // typedef to struct/union - use typedef name for convenience
@@ -642,7 +593,7 @@ fn type_to_koffi_string(type_registry: &TypeRegistry, type_id: TypeId) -> Result
                     }
                     _ => {
                         // other typedefs - recursively resolve
-                        return type_to_koffi_string(type_registry, *aliased_type_id);
+                        return type_to_koffi_string(type_registry, type_map, *aliased_type_id);
                     }
                 }
             }
@@ -679,6 +630,14 @@ fn type_to_koffi_string(type_registry: &TypeRegistry, type_id: TypeId) -> Result
     Ok(type_str)
 }
 
+/// the Koffi type string of a C primitive, from `type_map` if it has one
+fn primitive_koffi_string(type_map: &TypeMappingOverrides, c_name: &str) -> Result<String> {
+    match type_map.get(c_name) {
+        Some(target) => Ok(format!("'{}'", target)),
+        None => primitive_to_koffi(c_name),
+    }
+}
+
 /// map C primitive type names to Koffi type strings. Note DWARF normalizes
 /// type names so these are a subset of what's possible in C code.
 fn primitive_to_koffi(c_name: &str) -> Result<String> {
@@ -700,57 +659,120 @@ fn primitive_to_koffi(c_name: &str) -> Result<String> {
         "double" => "'double'",
         "long double" => "'double'",
 
-        // fixed-width integer types (from <stdint.h>)
-        "int8_t" => "'int8_t'",
-        "uint8_t" => "'uint8_t'",
-        "int16_t" => "'int16_t'",
-        "uint16_t" => "'uint16_t'",
-        "int32_t" => "'int32_t'",
-        "uint32_t" => "'uint32_t'",
-        "int64_t" => "'int64_t'",
-        "uint64_t" => "'uint64_t'",
-
         // standard library types (from <stddef.h>)
-        "size_t" => "'size_t'",
         "ssize_t" => "'int64_t'",
         "ptrdiff_t" => "'int64_t'",
         "intptr_t" => "'int64_t'",
         "uintptr_t" => "'uint64_t'",
 
-        _ => {
-            return Err(anyhow!("unknown primitive type for Koffi: {}", c_name));
-        }
+        _ => match exact_width_koffi(c_name) {
+            Some(exact) => return Ok(format!("'{}'", exact)),
+            None => return Err(anyhow!("unknown primitive type for Koffi: {}", c_name)),
+        },
     };
 
     Ok(koffi_type.to_string())
 }
 
+/// koffi's width-exact name for a <stdint.h> typedef, or `size_t`, which
+/// koffi sizes for the platform. these are used as-is rather than resolved,
+/// so the width doesn't depend on how the platform spells the underlying
+/// `int`/`long`. pointer-sized typedefs like `intptr_t` vary with the target
+/// and are resolved to their primitive instead.
+fn exact_width_koffi(typedef_name: &str) -> Option<&'static str> {
+    let koffi_type = match typedef_name {
+        // fixed-width integer types (from <stdint.h>)
+        "int8_t" => "int8_t",
+        "uint8_t" => "uint8_t",
+        "int16_t" => "int16_t",
+        "uint16_t" => "uint16_t",
+        "int32_t" => "int32_t",
+        "uint32_t" => "uint32_t",
+        "int64_t" => "int64_t",
+        "uint64_t" => "uint64_t",
+
+        // koffi's own, the width of a pointer
+        "size_t" => "size_t",
+
+        _ => return None,
+    };
+
+    Some(koffi_type)
+}
+
+/// `const NAME = value` for each `#define` constant
+fn generate_constants(constants: &[&MacroConstant]) -> Result<String> {
+    let mut output = String::from("// Constants (#define)\n");
+
+    for constant in constants {
+        let value = match &constant.value {
+            // past 2^53 a JS number loses precision
+            MacroValue::Integer(value) => js_integer(*value),
+            MacroValue::Unsigned(value) => format!("{}n", value),
+            MacroValue::String(value) => serde_json::to_string(value)?,
+        };
+        output.push_str(&format!("const {} = {}\n", constant.name, value));
+    }
+
+    output.push('\n');
+
+    Ok(output)
+}
+
+fn generate_enum_constants(constants: &[(String, i64)]) -> String {
+    let mut output = String::from("// Constants (anonymous enums)\n");
+
+    for (name, value) in constants {
+        output.push_str(&format!("const {} = {}\n", name, js_integer(*value)));
+    }
+
+    output.push('\n');
+
+    output
+}
+
+/// an integer literal; past 2^53 a JS number loses precision, so those
+/// become BigInts
+fn js_integer(value: i64) -> String {
+    if value.unsigned_abs() > (1 << 53) - 1 {
+        format!("{}n", value)
+    } else {
+        value.to_string()
+    }
+}
+
 /// generate koffi.proto() definitions for callback types
 fn generate_callback_protos(
     type_registry: &TypeRegistry,
+    type_map: &TypeMappingOverrides,
     callbacks: &[(String, TypeId)],
 ) -> Result<String> {
     let mut output = String::new();
 
-    output.push_str("// Callback function pointer types\n");
+    output.push_str(
+        "// Callback function pointer types, exported as `callbacks.<typedef name>`.\n\
+         // A JS function can be passed directly where the library only calls it\n\
+         // during the call; otherwise keep it alive with registerCallback() and\n\
+         // release it with koffi.unregister() once the library is done with it.\n",
+    );
 
-    for (typedef_name, func_ptr_type_id) in callbacks {
-        let func_ptr_type = type_registry
-            .get_type(*func_ptr_type_id)
+    for (typedef_name, typedef_id) in callbacks {
+        let typedef = type_registry
+            .get_type(*typedef_id)
             .ok_or_else(|| anyhow!("Function pointer type not found"))?;
 
-        if let BaseTypeKind::Function {
+        if let Some(FunctionPointer {
             return_type_id,
             parameter_type_ids,
             ..
-        } = &func_ptr_type.kind
+        }) = typedef.as_function_pointer(type_registry)
         {
             // build the callback signature
             let mut sig = String::new();
 
             // return type
             if let Some(ret_id) = return_type_id {
-                let ret_str = type_to_koffi_c_string(type_registry, *ret_id)?;
+                let ret_str = type_to_koffi_c_string(type_registry, type_map, ret_id)?;
                 sig.push_str(&ret_str);
             } else {
                 sig.push_str("void");
@@ -769,7 +791,7 @@ fn generate_callback_protos(
                     if i > 0 {
                         sig.push_str(", ");
                     }
-                    let param_str = type_to_koffi_c_string(type_registry, *param_id)?;
+                    let param_str = type_to_koffi_c_string(type_registry, type_map, *param_id)?;
                     sig.push_str(&param_str);
                 }
             }
@@ -783,45 +805,72 @@ fn generate_callback_protos(
         }
     }
 
-    output.push('\n');
+    output.push_str(
+        "\nfunction registerCallback(proto, fn) {\n\
+         \x20 return koffi.register(fn, koffi.pointer(proto))\n\
+         }\n\n",
+    );
 
     Ok(output)
 }
 
-/// generate function bindings using lib.func() with C signatures
+/// the bindings of the functions that have one; variadic functions don't
+fn exported_functions<'a>(functions: &[FunctionSignature], bindings: &'a [String]) -> Vec<&'a str> {
+    functions
+        .iter()
+        .zip(bindings)
+        .filter(|(f, _)| !f.is_variadic)
+        .map(|(_, binding)| binding.as_str())
+        .collect()
+}
+
+/// generate function bindings using lib.func() with C signatures, at the top
+/// level or, for a runtime load, in an `init(libraryPath)` that returns them
 fn generate_function_bindings(
     type_registry: &TypeRegistry,
     functions: &[FunctionSignature],
+    bindings: &[String],
     library_path: &str,
+    options: &JsOptions,
 ) -> Result<String> {
     let mut output = String::new();
 
-    output.push_str("// Library path - UPDATE THIS to match your deployment\n");
-    output.push_str(&format!("const LIBRARY_PATH = '{}'\n\n", library_path));
+    let indent = if options.runtime_load {
+        output.push_str("// Load the library and bind its functions\n");
+        output.push_str("function init(libraryPath) {\n");
+        output.push_str("  const lib = koffi.load(libraryPath)\n\n");
+        "  "
+    } else {
+        output.push_str("// Library path - UPDATE THIS to match your deployment\n");
+        output.push_str(&format!("const LIBRARY_PATH = '{}'\n\n", library_path));
 
-    output.push_str("// Load library\n");
-    output.push_str("const lib = koffi.load(LIBRARY_PATH)\n\n");
+        output.push_str("// Load library\n");
+        output.push_str("const lib = koffi.load(LIBRARY_PATH)\n\n");
 
-    output.push_str("// Function bindings\n");
+        output.push_str("// Function bindings\n");
+        ""
+    };
 
-    for func in functions {
-        if func.is_variadic {
-            // Variadic functions not supported
-            output.push_str(&format!(
-                "// {}: variadic function not supported by Koffi\n",
-                func.name
-            ));
-            continue;
+    for (func, binding) in functions.iter().zip(bindings) {
+        if let Some(description) = &func.description {
+            output.push_str(&doc::block_comment(description, indent));
         }
+        output.push_str(indent);
+        output.push_str(&function_binding(
+            type_registry,
+            &options.type_map,
+            func,
+            binding,
+        )?);
+        output.push('\n');
+    }
 
-        // generate Koffi-compatible C signature
-        // (cannot use DWARF signature directly - enums/callbacks need special handling)
-        let koffi_signature = func_to_koffi_signature(type_registry, func)?;
-
-        output.push_str(&format!(
-            "const {} = lib.func('{}')\n",
-            func.name, koffi_signature
-        ));
+    if options.runtime_load {
+        output.push_str("\n  return {\n");
+        for binding in exported_functions(functions, bindings) {
+            output.push_str(&format!("    {},\n", binding));
+        }
+        output.push_str("  }\n}\n");
     }
 
     output.push('\n');
@@ -829,18 +878,74 @@ fn generate_function_bindings(
     Ok(output)
 }
 
+/// the koffi binding of one function, as [`generate`] writes it:
+/// `const name = lib.func('...')`, loaded from a `lib` handle, with any
+/// callback typedef referring to its `koffi.proto`. a function koffi can't
+/// call is rendered as a comment saying why.
+///
+/// ```
+/// # use dwarffi::{FunctionSignature, Parameter, Type, TypeRegistry};
+/// use dwarffi_js::codegen::koffi;
+/// # let mut registry = TypeRegistry::new();
+/// # let int = registry.register_type(Type::primitive("int", 4));
+/// # let param = |name: &str| Parameter::new(name, int);
+/// # let add = FunctionSignature::new("add", int, vec![param("a"), param("b")]);
+///
+/// // `int add(int a, int b)`
+/// assert_eq!(
+///     koffi::render_function(&add, &registry),
+///     "const add = lib.func('int add(int a, int b)')"
+/// );
+/// ```
+pub fn render_function(func: &FunctionSignature, type_registry: &TypeRegistry) -> String {
+    function_binding(
+        type_registry,
+        &TypeMappingOverrides::default(),
+        func,
+        &func.name,
+    )
+    .unwrap_or_else(|e| format!("// {}: {}", func.name, e))
+}
+
+/// `const binding = lib.func(...)`, where the signature keeps the C name koffi
+/// looks the function up by
+fn function_binding(
+    type_registry: &TypeRegistry,
+    type_map: &TypeMappingOverrides,
+    func: &FunctionSignature,
+    binding: &str,
+) -> Result<String> {
+    if func.is_variadic {
+        // Variadic functions not supported
+        return Ok(format!(
+            "// {}: variadic function not supported by Koffi",
+            func.name
+        ));
+    }
+
+    // generate Koffi-compatible C signature
+    // (cannot use DWARF signature directly - enums/callbacks need special handling)
+    let koffi_signature = func_to_koffi_signature(type_registry, type_map, func)?;
+
+    Ok(format!(
+        "const {} = lib.func('{}')",
+        binding, koffi_signature
+    ))
+}
+
 /// convert a function signature to Koffi-compatible C signature string
 /// differences from DWARF signature:
 /// - Enum types replaced with underlying integer types
 /// - Function pointer parameters get * suffix (e.g., Callback*)
 fn func_to_koffi_signature(
     type_registry: &TypeRegistry,
+    type_map: &TypeMappingOverrides,
     func: &FunctionSignature,
 ) -> Result<String> {
     let mut sig = String::new();
 
     // return type
-    let return_type_str = type_to_koffi_c_string(type_registry, func.return_type_id)?;
+    let return_type_str = type_to_koffi_c_string(type_registry, type_map, func.return_type_id)?;
     sig.push_str(&return_type_str);
     sig.push(' ');
 
@@ -856,7 +961,7 @@ fn func_to_koffi_signature(
             if i > 0 {
                 sig.push_str(", ");
             }
-            let param_type_str = type_to_koffi_c_string(type_registry, param.type_id)?;
+            let param_type_str = type_to_koffi_c_string(type_registry, type_map, param.type_id)?;
             sig.push_str(&param_type_str);
             if !param.name.is_empty() {
                 sig.push(' ');
@@ -872,42 +977,48 @@ fn func_to_koffi_signature(
 
 /// convert a type to Koffi-compatible C type string for function signatures
 /// this is different from type_to_koffi_string() which is for struct fields
-fn type_to_koffi_c_string(type_registry: &TypeRegistry, type_id: TypeId) -> Result<String> {
+fn type_to_koffi_c_string(
+    type_registry: &TypeRegistry,
+    type_map: &TypeMappingOverrides,
+    type_id: TypeId,
+) -> Result<String> {
     let type_ = type_registry
         .get_type(type_id)
         .ok_or_else(|| anyhow!("Type not found: {:?}", type_id))?;
 
-    // special case: typedef to function pointer
+    // special case: typedef to function pointer (e.g., Callback, Comparator)
     // Koffi needs "TypedefName*" for callback parameters (with * suffix)
-    if let BaseTypeKind::Typedef {
-        name,
-        aliased_type_id,
-    } = &type_.kind
+    if let BaseTypeKind::Typedef { name, .. } = &type_.kind
+        && type_.is_function_pointer(type_registry)
     {
-        let aliased = type_registry.get_type(*aliased_type_id);
-        if let Some(aliased_type) = aliased {
-            // check if this typedef points to a function pointer
-            if aliased_type.pointer_depth > 0
-                && let BaseTypeKind::Function { .. } = &aliased_type.kind
-            {
-                // this is a callback typedef (e.g., Callback, Comparator)
-                // Koffi requires "Callback*" syntax when used as parameter
-                return Ok(format!("{}*", name));
-            }
-        }
+        return Ok(format!("{}*", name));
+    }
+
+    // koffi marshals `char*` to and from JS strings
+    if type_.is_c_string_pointer(type_registry) {
+        let is_const = type_.is_const
+            || type_registry
+                .resolve_typedef(type_id)
+                .is_some_and(|resolved| resolved.is_const);
+        return Ok(if is_const { "const char*" } else { "char*" }.to_string());
     }
 
     let mut type_str = match &type_.kind {
-        BaseTypeKind::Primitive { name, .. } => {
+        BaseTypeKind::Primitive { name, .. } => match type_map.get(name) {
+            Some(target) => target.to_string(),
             // map DWARF type names to Koffi-compatible C type names
             // Koffi has specific expectations for type names in function signatures
-            match name.as_str() {
+            None => match name.as_str() {
                 "_Bool" => "bool".to_string(),
+                // signed/unsigned char pointers are byte buffers; spell them
+                // so koffi does not treat them as strings
+                "signed char" if type_.pointer_depth > 0 => "int8_t".to_string(),
+                "unsigned char" if type_.pointer_depth > 0 => "uint8_t".to_string(),
                 "signed char" => "char".to_string(),
                 "long double" => "double".to_string(),
                 _ => name.clone(),
-            }
-        }
+            },
+        },
         BaseTypeKind::Struct { name, .. } => name.clone(),
         BaseTypeKind::Union { name, .. } => name.clone(),
         BaseTypeKind::Enum { backing_id, .. } => {
@@ -917,7 +1028,9 @@ fn type_to_koffi_c_string(type_registry: &TypeRegistry, type_id: TypeId) -> Resu
                 .ok_or_else(|| anyhow!("Enum backing type not found"))?;
 
             match &backing_type.kind {
-                BaseTypeKind::Primitive { name, .. } => name.clone(),
+                BaseTypeKind::Primitive { name, .. } => {
+                    type_map.get(name).unwrap_or(name).to_string()
+                }
                 _ => "int".to_string(),
             }
         }
@@ -927,7 +1040,7 @@ fn type_to_koffi_c_string(type_registry: &TypeRegistry, type_id: TypeId) -> Resu
             // arrays in function parameters decay to pointers
             // Koffi doesn't accept array syntax like "int[5]*"
             // use the element type - pointer will be added via pointer_depth
-            return type_to_koffi_c_string(type_registry, *element_type_id);
+            return type_to_koffi_c_string(type_registry, type_map, *element_type_id);
         }
         BaseTypeKind::Typedef {
             name,
@@ -942,7 +1055,8 @@ fn type_to_koffi_c_string(type_registry: &TypeRegistry, type_id: TypeId) -> Resu
             // BUT: if the current type has pointers, we need to add them after recursion
             if let BaseTypeKind::Enum { .. } = &aliased.kind {
                 // recursively resolve to get the underlying int type
-                let mut base_str = type_to_koffi_c_string(type_registry, *aliased_type_id)?;
+                let mut base_str =
+                    type_to_koffi_c_string(type_registry, type_map, *aliased_type_id)?;
 
                 // add any pointer stars from the typedef itself
                 for _ in 0..type_.pointer_depth {
@@ -957,6 +1071,20 @@ fn type_to_koffi_c_string(type_registry: &TypeRegistry, type_id: TypeId) -> Resu
                 return Ok(base_str);
             }
 
+            // stdint/stddef typedefs have width-exact koffi names
+            if aliased.pointer_depth == 0
+                && let Some(exact) = exact_width_koffi(name)
+            {
+                let mut base_str = exact.to_string();
+                for _ in 0..type_.pointer_depth {
+                    base_str.push('*');
+                }
+                if type_.is_const {
+                    base_str = format!("const {}", base_str);
+                }
+                return Ok(base_str);
+            }
+
             // for typedef to struct/union, use the typedef name
             // (don't recurse, or we'll get anonymous struct names like "<anonymous>")
             match &aliased.kind {
@@ -966,7 +1094,8 @@ fn type_to_koffi_c_string(type_registry: &TypeRegistry, type_id: TypeId) -> Resu
                 }
                 _ => {
                     // for other types (primitives, etc), recurse
-                    let mut base_str = type_to_koffi_c_string(type_registry, *aliased_type_id)?;
+                    let mut base_str =
+                        type_to_koffi_c_string(type_registry, type_map, *aliased_type_id)?;
 
                     // add any pointer stars from the typedef itself
                     for _ in 0..type_.pointer_depth {
@@ -1005,47 +1134,199 @@ fn type_to_koffi_c_string(type_registry: &TypeRegistry, type_id: TypeId) -> Resu
 fn generate_exports(
     generate_types: bool,
     generate_functions: bool,
-    generated_names: &HashSet<String>,
-    functions: &[FunctionSignature],
+    generated_names: &BTreeSet<String>,
+    callback_names: &[String],
+    constant_names: &[String],
+    function_names: &[&str],
+    options: &JsOptions,
 ) -> Result<String> {
-    let mut output = String::new();
-
-    output.push_str("// Exports\n");
-    output.push_str("module.exports = {\n");
+    // (key, members): a namespace object when members is Some, otherwise a
+    // top-level binding exported under its own name
+    let mut entries: Vec<(String, Option<Vec<String>>)> = Vec::new();
 
     if generate_functions {
         // export types under 'types' namespace and individual functions
         if generate_types {
-            output.push_str("  types: {\n");
+            entries.push((
+                "types".to_string(),
+                Some(generated_names.iter().cloned().collect()),
+            ));
+        }
 
-            for name in generated_names {
-                output.push_str(&format!("    {},\n", name));
-            }
+        // callback protos, and the helper to register JS functions as them
+        if !callback_names.is_empty() {
+            entries.push(("callbacks".to_string(), Some(callback_names.to_vec())));
+            entries.push(("registerCallback".to_string(), None));
+        }
 
-            output.push_str("  },\n");
+        if !constant_names.is_empty() {
+            entries.push(("constants".to_string(), Some(constant_names.to_vec())));
         }
 
         // export individual functions
-        for func in functions {
-            if !func.is_variadic {
-                output.push_str(&format!("  {},\n", func.name));
-            }
+        for name in function_names {
+            entries.push((name.to_string(), None));
         }
     } else if generate_types {
-        // export types directly
-        for name in generated_names {
-            output.push_str(&format!("  {},\n", name));
+        // export types and constants directly
+        for name in generated_names.iter().chain(constant_names).cloned() {
+            entries.push((name, None));
         }
     }
 
-    output.push_str("}\n");
+    let mut output = String::new();
+    output.push_str("// Exports\n");
+
+    match (options.module_format, &options.namespace) {
+        // everything under one key, so bindings of several libraries can be
+        // combined without their names clashing
+        (ModuleFormat::Cjs, Some(namespace)) => {
+            output.push_str("module.exports = {\n");
+            output.push_str(&format!("  {}: {{\n", namespace));
+            output.push_str(&object_members(&entries, "    "));
+            output.push_str("  },\n}\n");
+        }
+        (ModuleFormat::Esm, Some(namespace)) => {
+            output.push_str(&format!("export const {} = {{\n", namespace));
+            output.push_str(&object_members(&entries, "  "));
+            output.push_str(&format!("}}\nexport default {}\n", namespace));
+        }
+        (ModuleFormat::Cjs, None) => {
+            output.push_str("module.exports = {\n");
+            output.push_str(&object_members(&entries, "  "));
+            output.push_str("}\n");
+        }
+        (ModuleFormat::Esm, None) => {
+            // namespaces become exported objects, the rest are exported by
+            // name, and everything together is the default export
+            let mut bindings = Vec::new();
+            for (key, members) in &entries {
+                match members {
+                    Some(members) => {
+                        output.push_str(&format!("export const {} = {{\n", key));
+                        for member in members {
+                            output.push_str(&format!("  {},\n", member));
+                        }
+                        output.push_str("}\n");
+                    }
+                    None => bindings.push(key.as_str()),
+                }
+            }
+            if !bindings.is_empty() {
+                output.push_str("export {\n");
+                for binding in &bindings {
+                    output.push_str(&format!("  {},\n", binding));
+                }
+                output.push_str("}\n");
+            }
+            output.push_str("export default {\n");
+            for (key, _) in &entries {
+                output.push_str(&format!("  {},\n", key));
+            }
+            output.push_str("}\n");
+        }
+    }
 
     Ok(output)
 }
 
+/// the export entries as the members of an object literal, one per line
+fn object_members(entries: &[(String, Option<Vec<String>>)], indent: &str) -> String {
+    let mut output = String::new();
+    for (key, members) in entries {
+        match members {
+            Some(members) => {
+                output.push_str(&format!("{}{}: {{\n", indent, key));
+                for member in members {
+                    output.push_str(&format!("{}  {},\n", indent, member));
+                }
+                output.push_str(&format!("{}}},\n", indent));
+            }
+            None => output.push_str(&format!("{}{},\n", indent, key)),
+        }
+    }
+    output
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codegen::backend::Rename;
+
+    #[test]
+    fn test_macros_named_like_module_identifiers_are_left_out() {
+        let macro_constant = |name: &str| MacroConstant {
+            name: name.to_string(),
+            value: MacroValue::Integer(1),
+            file: "lib.h".to_string(),
+            line: 1,
+        };
+        let macros = [
+            macro_constant("lib"),
+            macro_constant("LIBRARY_PATH"),
+            macro_constant("require"),
+            macro_constant("LIB_VERSION"),
+        ];
+
+        let output = generate(
+            &TypeRegistry::new(),
+            &[],
+            &macros,
+            true,
+            true,
+            "./libfoo.so",
+            &JsOptions::default(),
+        )
+        .unwrap();
+        assert!(output.contains("// Constants (#define)\nconst LIB_VERSION = 1\n\n"));
+        assert_eq!(output.matches("const lib = ").count(), 1);
+        assert!(!output.contains("const LIBRARY_PATH = 1"));
+        assert!(!output.contains("const require"));
+    }
+
+    #[test]
+    fn test_bindings_named_like_types_or_module_identifiers_are_refused() {
+        let mut registry = TypeRegistry::new();
+        let int_id = registry.register_type(Type::primitive("int", 4));
+        let point_id = registry.register_type(Type::new(BaseTypeKind::Struct {
+            name: "Point".to_string(),
+            fields: vec![StructField::new("x", int_id, 0, 4)],
+            size: 4,
+            alignment: 4,
+            is_opaque: false,
+        }));
+        let bind = |names: &[&str], rename: Rename| {
+            let functions: Vec<_> = names
+                .iter()
+                .map(|name| FunctionSignature::new(name, point_id, vec![]))
+                .collect();
+            let options = JsOptions {
+                rename,
+                ..JsOptions::default()
+            };
+            generate(
+                &registry,
+                &functions,
+                &[],
+                true,
+                true,
+                "./libfoo.so",
+                &options,
+            )
+        };
+
+        assert!(bind(&["point_make"], Rename::default()).is_ok());
+        let error = bind(&["Point"], Rename::default()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Point and the type Point would both be bound as Point"
+        );
+        let error = bind(&["foo_lib"], Rename::strip_prefix("foo_")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "foo_lib and the module's own lib would both be bound as lib"
+        );
+    }
 
     #[test]
     fn test_primitive_to_koffi_void() {
@@ -1150,6 +1431,56 @@ mod tests {
         assert!(primitive_to_koffi("bool").is_err()); // should be _Bool
     }
 
+    #[test]
+    fn test_char_pointers_map_to_strings_and_byte_buffers() {
+        let mut registry = TypeRegistry::new();
+        let mut char_pointer = |name: &str, is_const: bool| {
+            registry.register_type(Type {
+                is_const,
+                ..Type::pointer_to(Type::primitive(name, 1))
+            })
+        };
+        let string = char_pointer("char", false);
+        let const_string = char_pointer("char", true);
+        let bytes = char_pointer("unsigned char", false);
+        let signed_bytes = char_pointer("signed char", true);
+
+        let koffi =
+            |id| type_to_koffi_c_string(&registry, &TypeMappingOverrides::default(), id).unwrap();
+        assert_eq!(koffi(string), "char*");
+        assert_eq!(koffi(const_string), "const char*");
+        assert_eq!(koffi(bytes), "uint8_t*");
+        assert_eq!(koffi(signed_bytes), "const int8_t*");
+    }
+
+    #[test]
+    fn test_pointer_sized_typedefs_resolve_to_their_primitive() {
+        let mut registry = TypeRegistry::new();
+        let mut typedef = |name: &str, aliased: Type| {
+            let aliased_type_id = registry.register_type(aliased);
+            registry.register_type(Type::new(BaseTypeKind::Typedef {
+                name: name.to_string(),
+                aliased_type_id,
+            }))
+        };
+        // as on a 32-bit target
+        let ssize = typedef("ssize_t", Type::primitive("int", 4));
+        let intptr = typedef("intptr_t", Type::primitive("int", 4));
+        let uintptr = typedef("uintptr_t", Type::primitive("unsigned int", 4));
+        let int64 = typedef("int64_t", Type::primitive("long long", 8));
+
+        let type_map = TypeMappingOverrides::default();
+        let koffi = |id| type_to_koffi_c_string(&registry, &type_map, id).unwrap();
+        assert_eq!(koffi(ssize), "int");
+        assert_eq!(koffi(intptr), "int");
+        assert_eq!(koffi(uintptr), "unsigned int");
+        assert_eq!(koffi(int64), "int64_t");
+        assert_eq!(
+            type_to_koffi_string(&registry, &type_map, uintptr).unwrap(),
+            "'uint'"
+        );
+    }
+
     /// Test that covers all types that DWARF actually produces.
     /// This list is based on analysis of GCC/Clang DWARF output.
     #[test]
@@ -0,0 +1,152 @@
+/// struct layouts for backends whose structs have no bitfields: bitfields
+/// are read and written through the integer storage unit they share
+use anyhow::{Result, anyhow};
+use dwarffi::StructField;
+
+/// an integer holding one or more bitfields, the `index`th of its struct
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct BitfieldUnit {
+    pub index: usize,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// a struct member in memory order
+#[derive(Debug)]
+pub(super) enum Member<'a> {
+    /// an ordinary field
+    Field(&'a StructField),
+    /// a storage unit starts here; the bitfields in it follow
+    Unit(BitfieldUnit),
+    /// `width` bits at `shift` within `unit`
+    Bitfield {
+        field: &'a StructField,
+        unit: BitfieldUnit,
+        shift: u32,
+        width: u32,
+    },
+}
+
+/// the members of a struct, with bitfields grouped into storage units of
+/// their declared type's size by `bit_offset`. a bitfield straddling its
+/// unit, or units of different sizes overlapping, can't be described by a
+/// field per unit, so those are errors naming `language`.
+pub(super) fn struct_members<'a>(
+    struct_name: &str,
+    fields: &'a [StructField],
+    language: &str,
+) -> Result<Vec<Member<'a>>> {
+    let mut members = Vec::new();
+    let mut end = 0;
+    let mut current: Option<BitfieldUnit> = None;
+
+    for field in fields {
+        let (Some(width), Some(bit_offset)) = (field.bit_size, field.bit_offset) else {
+            members.push(Member::Field(field));
+            end = field.offset + field.size;
+            continue;
+        };
+
+        let size = field.size.max(1);
+        let offset = bit_offset as usize / (size * 8) * size;
+        let shift = bit_offset - offset as u32 * 8;
+        if shift + width > size as u32 * 8 {
+            return Err(anyhow!(
+                "{}.{} straddles its storage unit, which {} cannot describe",
+                struct_name,
+                field.name,
+                language
+            ));
+        }
+
+        let unit = match current {
+            Some(unit) if unit.offset == offset && unit.size == size => unit,
+            _ if offset < end => {
+                return Err(anyhow!(
+                    "{} packs bitfields of different widths together, which {} cannot describe",
+                    struct_name,
+                    language
+                ));
+            }
+            _ => {
+                let unit = BitfieldUnit {
+                    index: current.map_or(0, |unit| unit.index + 1),
+                    offset,
+                    size,
+                };
+                members.push(Member::Unit(unit));
+                end = offset + size;
+                unit
+            }
+        };
+        current = Some(unit);
+        members.push(Member::Bitfield {
+            field,
+            unit,
+            shift,
+            width,
+        });
+    }
+
+    Ok(members)
+}
+
+/// the mask of `width` low bits
+pub(super) fn low_bits(width: u32) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dwarffi::TypeId;
+
+    fn bitfield(name: &str, bit_offset: u32, bit_size: u32, size: usize) -> StructField {
+        StructField {
+            bit_size: Some(bit_size),
+            bit_offset: Some(bit_offset),
+            ..StructField::new(name, TypeId(0), bit_offset as usize / 8, size)
+        }
+    }
+
+    #[test]
+    fn test_bitfields_crossing_a_byte_share_their_unit() {
+        // `unsigned a : 12; unsigned b : 12; int c;` - b starts at byte 1
+        let fields = [
+            bitfield("a", 0, 12, 4),
+            bitfield("b", 12, 12, 4),
+            StructField::new("c", TypeId(0), 4, 4),
+        ];
+        let members = struct_members("S", &fields, "Rust").unwrap();
+
+        let unit = BitfieldUnit {
+            index: 0,
+            offset: 0,
+            size: 4,
+        };
+        assert!(matches!(members[0], Member::Unit(u) if u == unit));
+        assert!(matches!(
+            members[2],
+            Member::Bitfield {
+                unit: u,
+                shift: 12,
+                width: 12,
+                ..
+            } if u == unit
+        ));
+        assert!(matches!(members[3], Member::Field(field) if field.name == "c"));
+        assert_eq!(members.len(), 4);
+    }
+
+    #[test]
+    fn test_mixed_widths_in_one_unit_are_refused() {
+        // `unsigned char a : 3; unsigned int b : 5;` share the first byte
+        let fields = [bitfield("a", 0, 3, 1), bitfield("b", 3, 5, 4)];
+        let error = struct_members("Mixed", &fields, "Nim").unwrap_err();
+        assert!(error.to_string().contains("which Nim cannot describe"));
+    }
+}
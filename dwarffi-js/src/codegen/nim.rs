@@ -0,0 +1,809 @@
+/// Nim FFI code generation: a `type` section of `{.bycopy.}` objects and
+/// enums, and `proc` declarations loaded with `{.importc, dynlib.}`
+use anyhow::{Result, anyhow};
+use dwarffi::{
+    BaseTypeKind, EnumVariant, FunctionSignature, ResolvedPrimitive, StructField, Type, TypeId,
+    TypeRegistry, UnionField,
+};
+use std::collections::HashSet;
+
+use super::doc;
+use super::graph::{collect_required_types, topological_sort};
+
+pub struct NimCodegen;
+
+impl NimCodegen {
+    pub fn generate_module(
+        type_registry: &TypeRegistry,
+        functions: &[FunctionSignature],
+        generate_functions: bool,
+        library_path: &str,
+    ) -> Result<String> {
+        let mut output = String::new();
+
+        output.push_str(&generate_header());
+        output.push_str(&format!("const libraryPath = \"{}\"\n\n", library_path));
+
+        let required_types = collect_required_types(type_registry, functions);
+        let sorted_types = topological_sort(type_registry, required_types)?;
+
+        // one section, so objects may refer to types declared after them
+        let mut definitions = String::new();
+        let mut constants = String::new();
+        let mut generated_names = HashSet::new();
+        for type_id in sorted_types {
+            if let Some(type_) = type_registry.get_type(type_id) {
+                generate_type_definition(
+                    type_registry,
+                    type_,
+                    &mut generated_names,
+                    &mut definitions,
+                    &mut constants,
+                )?;
+            }
+        }
+        if !definitions.is_empty() {
+            output.push_str("type\n");
+            output.push_str(&definitions);
+        }
+        if !constants.is_empty() {
+            output.push_str("const\n");
+            output.push_str(&constants);
+            output.push('\n');
+        }
+
+        if generate_functions {
+            output.push_str(&generate_procs(type_registry, functions)?);
+        }
+
+        Ok(output)
+    }
+}
+
+fn generate_header() -> String {
+    "# Auto-generated by dwarffi-js\n\
+     # Do not edit manually!\n\
+     # Backend: Nim FFI\n\n"
+        .to_string()
+}
+
+/// Nim spelling of the standard typedefs, which are not re-declared
+fn standard_typedef_to_nim(name: &str) -> Option<&'static str> {
+    let nim_type = match name {
+        "int8_t" => "int8",
+        "uint8_t" => "uint8",
+        "int16_t" => "int16",
+        "uint16_t" => "uint16",
+        "int32_t" => "int32",
+        "uint32_t" => "uint32",
+        "int64_t" => "int64",
+        "uint64_t" => "uint64",
+        "size_t" => "csize_t",
+        "uintptr_t" => "uint",
+        "ssize_t" | "ptrdiff_t" | "intptr_t" => "int",
+        _ => return None,
+    };
+    Some(nim_type)
+}
+
+/// the primitive behind a libc-internal typedef like `__uint32_t`. these are
+/// spelled out instead of re-declared, like the standard typedefs.
+fn libc_internal_primitive(
+    type_registry: &TypeRegistry,
+    name: &str,
+    aliased_type_id: TypeId,
+) -> Option<ResolvedPrimitive> {
+    if !name.starts_with("__") {
+        return None;
+    }
+    type_registry.resolve_primitive(aliased_type_id)
+}
+
+/// map a C primitive to Nim. the C type names keep their `c` aliases
+/// (`cint`, `culong`, ...) so the platform's sizes are used; anything else
+/// is mapped by size and signedness.
+fn primitive_to_nim(primitive: &ResolvedPrimitive) -> Result<&'static str> {
+    let nim_type = match primitive.name.as_str() {
+        "_Bool" | "bool" => "bool",
+        "char" => "cchar",
+        "signed char" => "cschar",
+        "unsigned char" => "cuchar",
+        "short int" | "short" => "cshort",
+        "short unsigned int" | "unsigned short" => "cushort",
+        "int" => "cint",
+        "unsigned int" => "cuint",
+        "long int" | "long" => "clong",
+        "long unsigned int" | "unsigned long" => "culong",
+        "long long int" | "long long" => "clonglong",
+        "long long unsigned int" | "unsigned long long" => "culonglong",
+        "float" => "cfloat",
+        "double" => "cdouble",
+        "long double" => "clongdouble",
+        _ => match (primitive.is_float, primitive.size, primitive.is_signed) {
+            (true, 4, _) => "float32",
+            (true, 8, _) => "float64",
+            (false, 1, true) => "int8",
+            (false, 1, false) => "uint8",
+            (false, 2, true) => "int16",
+            (false, 2, false) => "uint16",
+            (false, 4, true) => "int32",
+            (false, 4, false) => "uint32",
+            (false, 8, true) => "int64",
+            (false, 8, false) => "uint64",
+            _ => {
+                return Err(anyhow!(
+                    "unsupported primitive type for Nim: {} ({} bytes)",
+                    primitive.name,
+                    primitive.size
+                ));
+            }
+        },
+    };
+    Ok(nim_type)
+}
+
+/// Nim type for a field, parameter or return value. typedefs keep their
+/// name, since they are declared in the type section.
+fn type_to_nim(type_registry: &TypeRegistry, type_id: TypeId) -> Result<String> {
+    let type_ = type_registry
+        .get_type(type_id)
+        .ok_or_else(|| anyhow!("Type not found: {:?}", type_id))?;
+
+    // a function pointer's first level is the proc itself, and `void*` and
+    // `char*` have their own names
+    let mut pointer_depth = type_.pointer_depth;
+    let mut nim_type = match &type_.kind {
+        BaseTypeKind::Function {
+            return_type_id,
+            parameter_type_ids,
+            is_variadic,
+        } => {
+            if pointer_depth == 0 {
+                return Err(anyhow!(
+                    "bare function type {:?} has no Nim equivalent",
+                    type_id
+                ));
+            }
+            pointer_depth -= 1;
+            proc_type(
+                type_registry,
+                *return_type_id,
+                parameter_type_ids,
+                *is_variadic,
+            )?
+        }
+        BaseTypeKind::Primitive { name, .. } if name == "void" => {
+            if pointer_depth == 0 {
+                return Err(anyhow!("void has no Nim type outside a pointer"));
+            }
+            pointer_depth -= 1;
+            "pointer".to_string()
+        }
+        BaseTypeKind::Primitive { name, .. } if name == "char" && pointer_depth > 0 => {
+            pointer_depth -= 1;
+            "cstring".to_string()
+        }
+        BaseTypeKind::Primitive {
+            name,
+            size,
+            encoding,
+            ..
+        } => primitive_to_nim(&ResolvedPrimitive::from_base(name, *size, *encoding))?.to_string(),
+        BaseTypeKind::Typedef {
+            name,
+            aliased_type_id,
+        } => {
+            if let Some(nim_type) = standard_typedef_to_nim(name) {
+                nim_type.to_string()
+            } else if let Some(primitive) =
+                libc_internal_primitive(type_registry, name, *aliased_type_id)
+            {
+                primitive_to_nim(&primitive)?.to_string()
+            } else {
+                type_name(name)
+            }
+        }
+        BaseTypeKind::Struct { name, .. }
+        | BaseTypeKind::Union { name, .. }
+        | BaseTypeKind::Enum { name, .. } => {
+            if name.starts_with('<') {
+                let name = type_registry
+                    .demunged_name(type_.id)
+                    .ok_or_else(|| anyhow!("anonymous aggregate without a typedef name"))?;
+                type_name(&name)
+            } else {
+                type_name(name)
+            }
+        }
+        BaseTypeKind::Array {
+            element_type_id,
+            count,
+            ..
+        } => {
+            let element = type_to_nim(type_registry, *element_type_id)?;
+            if *count == 0 {
+                format!("UncheckedArray[{}]", element)
+            } else {
+                format!("array[{}, {}]", count, element)
+            }
+        }
+    };
+
+    for _ in 0..pointer_depth {
+        nim_type = format!("ptr {}", nim_type);
+    }
+
+    Ok(nim_type)
+}
+
+/// `proc (a: cint): cint {.cdecl.}`
+fn proc_type(
+    type_registry: &TypeRegistry,
+    return_type_id: Option<TypeId>,
+    parameter_type_ids: &[TypeId],
+    is_variadic: bool,
+) -> Result<String> {
+    let params = parameter_type_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| Ok(format!("arg{}: {}", i, type_to_nim(type_registry, *id)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let pragmas = if is_variadic {
+        "cdecl, varargs"
+    } else {
+        "cdecl"
+    };
+
+    Ok(format!(
+        "proc ({}){} {{.{}.}}",
+        params.join(", "),
+        return_suffix(type_registry, return_type_id)?,
+        pragmas
+    ))
+}
+
+/// `: T`, or nothing for void
+fn return_suffix(type_registry: &TypeRegistry, return_type_id: Option<TypeId>) -> Result<String> {
+    let Some(id) = return_type_id else {
+        return Ok(String::new());
+    };
+    if type_registry
+        .get_type(id)
+        .is_some_and(|t| t.pointer_depth == 0 && t.get_name() == "void")
+    {
+        return Ok(String::new());
+    }
+    Ok(format!(": {}", type_to_nim(type_registry, id)?))
+}
+
+/// Nim identifiers can't start or end with `_` or have two in a row, and
+/// ignore case and underscores after the first letter. C names are
+/// collapsed to fit; a leading underscore becomes a `c_` prefix so that
+/// `struct _Foo` and `typedef ... Foo` stay distinct.
+fn sanitize(name: &str) -> String {
+    let parts = name
+        .split('_')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    let mut ident = parts.join("_");
+    if ident.is_empty() {
+        ident = "x".to_string();
+    }
+    if name.starts_with('_') || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident = format!("c_{}", ident);
+    }
+    ident
+}
+
+/// a value identifier: sanitized, and quoted with backticks if it is a
+/// keyword
+fn escape_identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "addr",
+        "and",
+        "as",
+        "asm",
+        "bind",
+        "block",
+        "break",
+        "case",
+        "cast",
+        "concept",
+        "const",
+        "continue",
+        "converter",
+        "defer",
+        "discard",
+        "distinct",
+        "div",
+        "do",
+        "elif",
+        "else",
+        "end",
+        "enum",
+        "except",
+        "export",
+        "finally",
+        "for",
+        "from",
+        "func",
+        "if",
+        "import",
+        "in",
+        "include",
+        "interface",
+        "is",
+        "isnot",
+        "iterator",
+        "let",
+        "macro",
+        "method",
+        "mixin",
+        "mod",
+        "nil",
+        "not",
+        "notin",
+        "object",
+        "of",
+        "or",
+        "out",
+        "proc",
+        "ptr",
+        "raise",
+        "ref",
+        "return",
+        "shl",
+        "shr",
+        "static",
+        "template",
+        "try",
+        "tuple",
+        "type",
+        "using",
+        "var",
+        "when",
+        "while",
+        "xor",
+        "yield",
+    ];
+
+    let ident = sanitize(name);
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("`{}`", ident)
+    } else {
+        ident
+    }
+}
+
+/// type names are exported from the module
+fn type_name(name: &str) -> String {
+    sanitize(name)
+}
+
+/// append one type's declaration to the type section, and for enums whose
+/// values can't be a Nim enum, its constants to `constants`
+fn generate_type_definition(
+    type_registry: &TypeRegistry,
+    type_: &Type,
+    generated_names: &mut HashSet<String>,
+    definitions: &mut String,
+    constants: &mut String,
+) -> Result<()> {
+    // (definition name, kind to generate). anonymous aggregates are named by
+    // their typedef, or by their synthetic name if demunged; other typedefs
+    // become aliases. pointer depth is ignored: `Person*` may be the only use
+    // of `Person`.
+    let demunged;
+    let (name, kind) = match &type_.kind {
+        BaseTypeKind::Struct { name, .. }
+        | BaseTypeKind::Union { name, .. }
+        | BaseTypeKind::Enum { name, .. } => {
+            if name.starts_with('<') {
+                match type_registry.demunged_name(type_.id) {
+                    Some(name) => demunged = name,
+                    None => return Ok(()),
+                }
+                (demunged.as_str(), &type_.kind)
+            } else {
+                (name.as_str(), &type_.kind)
+            }
+        }
+        BaseTypeKind::Typedef {
+            name,
+            aliased_type_id,
+        } => {
+            if standard_typedef_to_nim(name).is_some()
+                || libc_internal_primitive(type_registry, name, *aliased_type_id).is_some()
+            {
+                return Ok(());
+            }
+
+            let aliased = type_registry
+                .get_type(*aliased_type_id)
+                .ok_or_else(|| anyhow!("Aliased type not found"))?;
+
+            match &aliased.kind {
+                BaseTypeKind::Struct {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Union {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Enum {
+                    name: aliased_name, ..
+                } if aliased.pointer_depth == 0 && aliased_name.starts_with('<') => {
+                    (name.as_str(), &aliased.kind)
+                }
+                // `typedef struct Person Person` names the struct itself
+                BaseTypeKind::Struct {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Union {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Enum {
+                    name: aliased_name, ..
+                } if aliased.pointer_depth == 0 && aliased_name == name => {
+                    return Ok(());
+                }
+                _ => (name.as_str(), &type_.kind),
+            }
+        }
+        _ => return Ok(()),
+    };
+
+    let name = type_name(name);
+    if !generated_names.insert(name.clone()) {
+        return Ok(());
+    }
+
+    if let Some(description) = doc::type_description(type_registry, type_) {
+        definitions.push_str(&doc::line_comment(description, "  ", "## "));
+    }
+    match kind {
+        BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. }
+            if type_registry.is_opaque(type_.id) =>
+        {
+            definitions.push_str(&generate_object(type_registry, &name, &[], 0, true)?)
+        }
+        BaseTypeKind::Struct { fields, size, .. } => definitions.push_str(&generate_object(
+            type_registry,
+            &name,
+            fields,
+            *size,
+            false,
+        )?),
+        BaseTypeKind::Union { variants, .. } => {
+            definitions.push_str(&generate_union(type_registry, &name, variants)?)
+        }
+        BaseTypeKind::Enum {
+            backing_id,
+            variants,
+            ..
+        } => generate_enum(
+            type_registry,
+            &name,
+            *backing_id,
+            variants,
+            definitions,
+            constants,
+        )?,
+        BaseTypeKind::Typedef {
+            aliased_type_id, ..
+        } => definitions.push_str(&format!(
+            "  {}* = {}\n\n",
+            name,
+            type_to_nim(type_registry, *aliased_type_id)?
+        )),
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// structs are passed by value in C, so the object is `{.bycopy.}` rather
+/// than Nim's choice of by-reference for large objects
+fn generate_object(
+    type_registry: &TypeRegistry,
+    name: &str,
+    fields: &[StructField],
+    size: usize,
+    is_opaque: bool,
+) -> Result<String> {
+    if is_opaque {
+        return Ok(format!(
+            "  ## opaque - no definition available, use through pointers\n  {}* = object\n\n",
+            name
+        ));
+    }
+
+    let mut output = format!("  {}* {{.bycopy.}} = object\n", name);
+
+    for field in fields {
+        // anonymous struct/union members have no name
+        let field_name = if field.name.is_empty() {
+            format!("unnamed{}", field.declaration_index)
+        } else {
+            escape_identifier(&field.name)
+        };
+        // Nim emits `{.bitsize.}` fields as C bitfields of the same type, so
+        // the C compiler packs them into storage units as the library did
+        let pragma = match field.bit_size {
+            Some(width) => format!(" {{.bitsize: {}.}}", width),
+            None => String::new(),
+        };
+        output.push_str(&format!(
+            "    {}*{}: {}\n",
+            field_name,
+            pragma,
+            type_to_nim(type_registry, field.type_id)?
+        ));
+    }
+    if fields.is_empty() && size > 0 {
+        output.push_str(&format!("    data: array[{}, uint8]\n", size));
+    }
+    output.push('\n');
+
+    Ok(output)
+}
+
+/// `{.union.}` objects overlay their fields like a C union
+fn generate_union(
+    type_registry: &TypeRegistry,
+    name: &str,
+    variants: &[UnionField],
+) -> Result<String> {
+    let mut output = format!("  {}* {{.bycopy, union.}} = object\n", name);
+    for variant in variants {
+        output.push_str(&format!(
+            "    {}*: {}\n",
+            escape_identifier(&variant.name),
+            type_to_nim(type_registry, variant.type_id)?
+        ));
+    }
+    output.push('\n');
+    Ok(output)
+}
+
+/// a Nim enum of the backing type's size. Nim enum values must be distinct
+/// and ascending, so enums with aliased values become an integer alias with
+/// constants instead.
+fn generate_enum(
+    type_registry: &TypeRegistry,
+    name: &str,
+    backing_id: TypeId,
+    variants: &[EnumVariant],
+    definitions: &mut String,
+    constants: &mut String,
+) -> Result<()> {
+    let backing = type_registry
+        .resolve_primitive(backing_id)
+        .ok_or_else(|| anyhow!("Enum backing type not found"))?;
+
+    let mut sorted = variants.iter().collect::<Vec<_>>();
+    if backing.is_signed {
+        sorted.sort_by_key(|v| v.value);
+    } else {
+        sorted.sort_by_key(|v| v.value as u64);
+    }
+    let distinct = sorted.windows(2).all(|pair| pair[0].value != pair[1].value);
+
+    if distinct && !sorted.is_empty() {
+        definitions.push_str(&format!(
+            "  {}* {{.size: {}.}} = enum\n",
+            name, backing.size
+        ));
+        for variant in sorted {
+            definitions.push_str(&format!(
+                "    {} = {}\n",
+                escape_identifier(&variant.name),
+                variant.value_string(!backing.is_signed)
+            ));
+        }
+        definitions.push('\n');
+        return Ok(());
+    }
+
+    definitions.push_str(&format!(
+        "  {}* = {}\n\n",
+        name,
+        primitive_to_nim(&backing)?
+    ));
+    for variant in variants {
+        constants.push_str(&format!(
+            "  {}*: {} = {}\n",
+            escape_identifier(&variant.name),
+            name,
+            variant.value_string(!backing.is_signed)
+        ));
+    }
+    Ok(())
+}
+
+fn generate_procs(type_registry: &TypeRegistry, functions: &[FunctionSignature]) -> Result<String> {
+    let mut output = String::new();
+
+    for func in functions {
+        output.push_str(&render_function(func, type_registry));
+        output.push('\n');
+        // a proc's doc comment follows its declaration
+        if let Some(description) = &func.description {
+            output.push_str(&doc::line_comment(description, "  ", "## "));
+        }
+    }
+
+    Ok(output)
+}
+
+/// the `importc` proc of one function, loaded from the module's
+/// `libraryPath`. a function Nim can't declare is rendered as a comment
+/// saying why.
+///
+/// ```
+/// # use dwarffi::{FunctionSignature, Parameter, Type, TypeRegistry};
+/// use dwarffi_js::codegen::nim;
+/// # let mut registry = TypeRegistry::new();
+/// # let int = registry.register_type(Type::primitive("int", 4));
+/// # let param = |name: &str| Parameter::new(name, int);
+/// # let add = FunctionSignature::new("add", int, vec![param("a"), param("b")]);
+///
+/// // `int add(int a, int b)`
+/// assert_eq!(
+///     nim::render_function(&add, &registry),
+///     "proc add*(a: cint, b: cint): cint {.importc: \"add\", cdecl, dynlib: libraryPath.}"
+/// );
+/// ```
+pub fn render_function(func: &FunctionSignature, type_registry: &TypeRegistry) -> String {
+    match proc_declaration(type_registry, func) {
+        Ok(declaration) => declaration,
+        Err(e) => {
+            log::warn!("skip function {}: {}", func.name, e);
+            format!("# {}: {}", func.name, e)
+        }
+    }
+}
+
+fn proc_declaration(type_registry: &TypeRegistry, func: &FunctionSignature) -> Result<String> {
+    let params = func
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            let name = if param.name.is_empty() {
+                format!("arg{}", i)
+            } else {
+                escape_identifier(&param.name)
+            };
+            Ok(format!(
+                "{}: {}",
+                name,
+                type_to_nim(type_registry, param.type_id)?
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut pragmas = vec![
+        format!("importc: \"{}\"", func.name),
+        "cdecl".to_string(),
+        "dynlib: libraryPath".to_string(),
+    ];
+    if func.is_variadic {
+        pragmas.push("varargs".to_string());
+    }
+    if func.is_noreturn {
+        pragmas.push("noreturn".to_string());
+    }
+
+    Ok(format!(
+        "proc {}*({}){} {{.{}.}}",
+        escape_identifier(&func.name),
+        params.join(", "),
+        return_suffix(type_registry, Some(func.return_type_id))?,
+        pragmas.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pointers_and_strings() {
+        let mut registry = TypeRegistry::new();
+        let const_char_ptr =
+            registry.register_type(Type::pointer_to(Type::primitive("char", 1).with_const()));
+        let int_ptr_ptr = registry.register_type(Type::pointer_to(Type::pointer_to(
+            Type::primitive("int", 4),
+        )));
+        let void_ptr = registry.register_type(Type::pointer_to(Type::primitive("void", 0)));
+        let ulong = registry.register_type(Type::primitive("long unsigned int", 8));
+
+        assert_eq!(type_to_nim(&registry, const_char_ptr).unwrap(), "cstring");
+        assert_eq!(type_to_nim(&registry, int_ptr_ptr).unwrap(), "ptr ptr cint");
+        assert_eq!(type_to_nim(&registry, void_ptr).unwrap(), "pointer");
+        assert_eq!(type_to_nim(&registry, ulong).unwrap(), "culong");
+    }
+
+    #[test]
+    fn test_enum_with_aliased_values_uses_constants() {
+        let mut registry = TypeRegistry::new();
+        let int_id = registry.register_type(Type::primitive("int", 4));
+        let variant = |name: &str, value| EnumVariant {
+            name: name.to_string(),
+            value,
+        };
+
+        let mut definitions = String::new();
+        let mut constants = String::new();
+        generate_enum(
+            &registry,
+            "Mode",
+            int_id,
+            &[variant("MODE_B", 1), variant("MODE_A", 0)],
+            &mut definitions,
+            &mut constants,
+        )
+        .unwrap();
+        assert_eq!(
+            definitions,
+            "  Mode* {.size: 4.} = enum\n    MODE_A = 0\n    MODE_B = 1\n\n"
+        );
+        assert!(constants.is_empty());
+
+        definitions.clear();
+        generate_enum(
+            &registry,
+            "Level",
+            int_id,
+            &[variant("LEVEL_LOW", 0), variant("LEVEL_DEFAULT", 0)],
+            &mut definitions,
+            &mut constants,
+        )
+        .unwrap();
+        assert_eq!(definitions, "  Level* = cint\n\n");
+        assert_eq!(
+            constants,
+            "  LEVEL_LOW*: Level = 0\n  LEVEL_DEFAULT*: Level = 0\n"
+        );
+    }
+
+    #[test]
+    fn test_bitfields_keep_their_widths() {
+        let mut registry = TypeRegistry::new();
+        let uint_id = registry.register_type(Type::primitive("unsigned int", 4));
+        let int_id = registry.register_type(Type::primitive("int", 4));
+        let bitfield = |name: &str, type_id, bit_offset: u32, bit_size| StructField {
+            bit_size: Some(bit_size),
+            bit_offset: Some(bit_offset),
+            ..StructField::new(name, type_id, bit_offset as usize / 8, 4)
+        };
+
+        // `unsigned a : 12; int b : 12; int c;` - b starts at byte 1
+        let object = generate_object(
+            &registry,
+            "Packed",
+            &[
+                bitfield("a", uint_id, 0, 12),
+                bitfield("b", int_id, 12, 12),
+                StructField::new("c", int_id, 4, 4),
+            ],
+            8,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            object,
+            "  Packed* {.bycopy.} = object\n    a* {.bitsize: 12.}: cuint\n    \
+             b* {.bitsize: 12.}: cint\n    c*: cint\n\n"
+        );
+    }
+
+    #[test]
+    fn test_identifiers() {
+        assert_eq!(escape_identifier("type"), "`type`");
+        assert_eq!(escape_identifier("count"), "count");
+        assert_eq!(escape_identifier("__reserved__"), "c_reserved");
+        assert_eq!(escape_identifier("a__b"), "a_b");
+        assert_eq!(type_name("_Node"), "c_Node");
+    }
+}
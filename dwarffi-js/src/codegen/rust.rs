@@ -0,0 +1,831 @@
+/// Rust FFI code generation: `#[repr(C)]` types and an `extern "C"` block
+use anyhow::{Result, anyhow};
+use dwarffi::{
+    BaseTypeKind, EnumVariant, FunctionSignature, ResolvedPrimitive, StructField, Type, TypeId,
+    TypeRegistry, UnionField, rust_fixed_width, rust_primitive,
+};
+use std::collections::HashSet;
+
+use super::doc;
+use super::graph::{collect_required_types, topological_sort};
+use super::layout::{self, BitfieldUnit, Member};
+
+pub struct RustCodegen;
+
+impl RustCodegen {
+    /// the bindings, inside `pub mod namespace { ... }` when a namespace is
+    /// given
+    pub fn generate_module(
+        type_registry: &TypeRegistry,
+        functions: &[FunctionSignature],
+        generate_functions: bool,
+        library_path: &str,
+        namespace: Option<&str>,
+    ) -> Result<String> {
+        let mut output = String::new();
+
+        let required_types = collect_required_types(type_registry, functions);
+        let sorted_types = topological_sort(type_registry, required_types)?;
+
+        let mut generated_names = HashSet::new();
+        for type_id in sorted_types {
+            if let Some(type_) = type_registry.get_type(type_id) {
+                output.push_str(&generate_type_definition(
+                    type_registry,
+                    type_,
+                    &mut generated_names,
+                )?);
+            }
+        }
+
+        if generate_functions {
+            output.push_str(&generate_extern_block(
+                type_registry,
+                functions,
+                library_path,
+            )?);
+        }
+
+        Ok(match namespace {
+            Some(namespace) => format!(
+                "{}pub mod {} {{\n{}}}\n",
+                generate_header(),
+                escape_identifier(namespace),
+                indent(&(ALLOW_LINTS.to_string() + &output))
+            ),
+            None => generate_header() + ALLOW_LINTS + &output,
+        })
+    }
+}
+
+/// C names that aren't Rust style
+const ALLOW_LINTS: &str =
+    "#![allow(non_camel_case_types, non_snake_case, non_upper_case_globals, dead_code)]\n\n";
+
+fn generate_header() -> String {
+    "// Auto-generated by dwarffi-js\n\
+     // Do not edit manually!\n\
+     // Backend: Rust FFI\n\n"
+        .to_string()
+}
+
+/// `code` indented one level, for the body of a module
+fn indent(code: &str) -> String {
+    code.lines()
+        .map(|line| {
+            if line.is_empty() {
+                "\n".to_string()
+            } else {
+                format!("    {}\n", line)
+            }
+        })
+        .collect()
+}
+
+/// the primitive behind a libc-internal typedef like `__uint32_t`. these are
+/// spelled out instead of re-declared, like the standard typedefs.
+fn libc_internal_primitive(
+    type_registry: &TypeRegistry,
+    name: &str,
+    aliased_type_id: TypeId,
+) -> Option<ResolvedPrimitive> {
+    if !name.starts_with("__") {
+        return None;
+    }
+    type_registry.resolve_primitive(aliased_type_id)
+}
+
+/// the `core::ffi` type of a C primitive, as `Type::to_rust_type` spells
+/// it. one Rust has no equivalent for, like `long double`, is an error
+/// rather than an opaque byte array.
+fn primitive_to_rust(primitive: &ResolvedPrimitive) -> Result<&'static str> {
+    rust_primitive(&primitive.name).ok_or_else(|| {
+        anyhow!(
+            "unsupported primitive type for Rust: {} ({} bytes)",
+            primitive.name,
+            primitive.size
+        )
+    })
+}
+
+/// Rust type for a field, parameter or return value. typedefs keep their
+/// name, since they are emitted as `pub type` aliases.
+fn type_to_rust(type_registry: &TypeRegistry, type_id: TypeId) -> Result<String> {
+    let type_ = type_registry
+        .get_type(type_id)
+        .ok_or_else(|| anyhow!("Type not found: {:?}", type_id))?;
+
+    // a function pointer's first level is the `fn` itself
+    let mut pointer_depth = type_.pointer_depth;
+    let mut rust_type = match &type_.kind {
+        BaseTypeKind::Function {
+            return_type_id,
+            parameter_type_ids,
+            is_variadic,
+        } => {
+            if pointer_depth == 0 {
+                return Err(anyhow!(
+                    "bare function type {:?} has no Rust equivalent",
+                    type_id
+                ));
+            }
+            pointer_depth -= 1;
+            format!(
+                "Option<{}>",
+                fn_pointer(
+                    type_registry,
+                    *return_type_id,
+                    parameter_type_ids,
+                    *is_variadic
+                )?
+            )
+        }
+        BaseTypeKind::Primitive { name, .. } if name == "void" => {
+            if pointer_depth == 0 {
+                "()".to_string()
+            } else {
+                "core::ffi::c_void".to_string()
+            }
+        }
+        BaseTypeKind::Primitive {
+            name,
+            size,
+            encoding,
+            ..
+        } => primitive_to_rust(&ResolvedPrimitive::from_base(name, *size, *encoding))?.to_string(),
+        BaseTypeKind::Typedef {
+            name,
+            aliased_type_id,
+        } => {
+            if let Some(rust_type) = rust_fixed_width(name) {
+                rust_type.to_string()
+            } else if let Some(primitive) =
+                libc_internal_primitive(type_registry, name, *aliased_type_id)
+            {
+                primitive_to_rust(&primitive)?.to_string()
+            } else {
+                name.clone()
+            }
+        }
+        BaseTypeKind::Struct { name, .. }
+        | BaseTypeKind::Union { name, .. }
+        | BaseTypeKind::Enum { name, .. } => {
+            if name.starts_with('<') {
+                type_registry
+                    .demunged_name(type_.id)
+                    .ok_or_else(|| anyhow!("anonymous aggregate without a typedef name"))?
+            } else {
+                name.clone()
+            }
+        }
+        BaseTypeKind::Array {
+            element_type_id,
+            count,
+            ..
+        } => format!(
+            "[{}; {}]",
+            type_to_rust(type_registry, *element_type_id)?,
+            count
+        ),
+    };
+
+    // innermost pointer first; each one is `*const` if what it points to is
+    for level in (0..pointer_depth).rev() {
+        let pointee_is_const = if level + 1 == pointer_depth {
+            type_.is_const
+        } else {
+            type_.pointer_qualifiers(level + 1).is_const
+        };
+        let pointer = if pointee_is_const { "*const" } else { "*mut" };
+        rust_type = format!("{} {}", pointer, rust_type);
+    }
+
+    Ok(rust_type)
+}
+
+fn fn_pointer(
+    type_registry: &TypeRegistry,
+    return_type_id: Option<TypeId>,
+    parameter_type_ids: &[TypeId],
+    is_variadic: bool,
+) -> Result<String> {
+    let mut params = parameter_type_ids
+        .iter()
+        .map(|id| type_to_rust(type_registry, *id))
+        .collect::<Result<Vec<_>>>()?;
+    if is_variadic {
+        params.push("...".to_string());
+    }
+
+    Ok(format!(
+        "unsafe extern \"C\" fn({}){}",
+        params.join(", "),
+        return_suffix(type_registry, return_type_id)?
+    ))
+}
+
+/// ` -> T`, or nothing for void
+fn return_suffix(type_registry: &TypeRegistry, return_type_id: Option<TypeId>) -> Result<String> {
+    let Some(id) = return_type_id else {
+        return Ok(String::new());
+    };
+    let return_type = type_to_rust(type_registry, id)?;
+    if return_type == "()" {
+        return Ok(String::new());
+    }
+    Ok(format!(" -> {}", return_type))
+}
+
+/// Rust reserves these; C identifiers that collide become raw identifiers
+fn escape_identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do",
+        "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in",
+        "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+        "return", "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe",
+        "unsized", "use", "virtual", "where", "while", "yield",
+    ];
+
+    match name {
+        // these can't be raw identifiers
+        "self" | "Self" | "super" | "crate" | "_" => format!("{}_", name),
+        _ if KEYWORDS.contains(&name) => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
+
+/// generate a single type definition
+fn generate_type_definition(
+    type_registry: &TypeRegistry,
+    type_: &Type,
+    generated_names: &mut HashSet<String>,
+) -> Result<String> {
+    // (definition name, kind to generate). anonymous aggregates are named by
+    // their typedef, or by their synthetic name if demunged; other typedefs
+    // become aliases. pointer depth is ignored: `Person*` may be the only use
+    // of `Person`.
+    let demunged;
+    let (name, kind) = match &type_.kind {
+        BaseTypeKind::Struct { name, .. }
+        | BaseTypeKind::Union { name, .. }
+        | BaseTypeKind::Enum { name, .. } => {
+            if name.starts_with('<') {
+                match type_registry.demunged_name(type_.id) {
+                    Some(name) => demunged = name,
+                    None => return Ok(String::new()),
+                }
+                (demunged.as_str(), &type_.kind)
+            } else {
+                (name.as_str(), &type_.kind)
+            }
+        }
+        BaseTypeKind::Typedef {
+            name,
+            aliased_type_id,
+        } => {
+            if rust_fixed_width(name).is_some()
+                || libc_internal_primitive(type_registry, name, *aliased_type_id).is_some()
+            {
+                return Ok(String::new());
+            }
+
+            let aliased = type_registry
+                .get_type(*aliased_type_id)
+                .ok_or_else(|| anyhow!("Aliased type not found"))?;
+
+            match &aliased.kind {
+                BaseTypeKind::Struct {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Union {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Enum {
+                    name: aliased_name, ..
+                } if aliased.pointer_depth == 0 && aliased_name.starts_with('<') => {
+                    (name.as_str(), &aliased.kind)
+                }
+                // `typedef struct Person Person` names the struct itself
+                BaseTypeKind::Struct {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Union {
+                    name: aliased_name, ..
+                }
+                | BaseTypeKind::Enum {
+                    name: aliased_name, ..
+                } if aliased.pointer_depth == 0 && aliased_name == name => {
+                    return Ok(String::new());
+                }
+                _ => (name.as_str(), &type_.kind),
+            }
+        }
+        _ => return Ok(String::new()),
+    };
+
+    if !generated_names.insert(name.to_string()) {
+        return Ok(String::new());
+    }
+
+    let doc = doc::type_description(type_registry, type_)
+        .map(|text| doc::line_comment(text, "", "/// "))
+        .unwrap_or_default();
+    let definition = match kind {
+        BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. }
+            if type_registry.is_opaque(type_.id) =>
+        {
+            generate_struct(type_registry, name, &[], 0, true)
+        }
+        BaseTypeKind::Struct { fields, size, .. } => {
+            generate_struct(type_registry, name, fields, *size, false)
+        }
+        BaseTypeKind::Union {
+            variants,
+            size,
+            alignment,
+            ..
+        } => generate_union(type_registry, name, variants, *size, *alignment),
+        BaseTypeKind::Enum {
+            backing_id,
+            variants,
+            ..
+        } => generate_enum(type_registry, name, *backing_id, variants),
+        BaseTypeKind::Typedef {
+            aliased_type_id, ..
+        } => Ok(format!(
+            "pub type {} = {};\n\n",
+            name,
+            type_to_rust(type_registry, *aliased_type_id)?
+        )),
+        _ => Ok(String::new()),
+    }?;
+    Ok(doc + &definition)
+}
+
+fn generate_struct(
+    type_registry: &TypeRegistry,
+    name: &str,
+    fields: &[StructField],
+    size: usize,
+    is_opaque: bool,
+) -> Result<String> {
+    let mut output = String::new();
+
+    if is_opaque {
+        output.push_str(&format!(
+            "/// opaque - no definition available, use through pointers\n\
+             #[repr(C)]\n\
+             pub struct {} {{\n    _private: [u8; 0],\n}}\n\n",
+            name
+        ));
+        return Ok(output);
+    }
+
+    output.push_str("#[repr(C)]\n#[derive(Debug, Clone, Copy)]\n");
+    output.push_str(&format!("pub struct {} {{\n", name));
+
+    let mut accessors = String::new();
+    for member in layout::struct_members(name, fields, "Rust")? {
+        match member {
+            Member::Field(field) => {
+                // anonymous struct/union members have no name
+                let field_name = if field.name.is_empty() {
+                    format!("_unnamed{}", field.declaration_index)
+                } else {
+                    escape_identifier(&field.name)
+                };
+                output.push_str(&format!(
+                    "    pub {}: {},\n",
+                    field_name,
+                    type_to_rust(type_registry, field.type_id)?
+                ));
+            }
+            Member::Unit(unit) => output.push_str(&format!(
+                "    _bitfield{}: u{},\n",
+                unit.index,
+                unit.size * 8
+            )),
+            Member::Bitfield {
+                field,
+                unit,
+                shift,
+                width,
+            } => {
+                if !field.name.is_empty() {
+                    if !accessors.is_empty() {
+                        accessors.push('\n');
+                    }
+                    accessors.push_str(&bitfield_accessors(
+                        type_registry,
+                        field,
+                        unit,
+                        shift,
+                        width,
+                    )?);
+                }
+            }
+        }
+    }
+    if fields.is_empty() && size > 0 {
+        output.push_str(&format!("    _data: [u8; {}],\n", size));
+    }
+
+    output.push_str("}\n\n");
+    if !accessors.is_empty() {
+        output.push_str(&format!("impl {} {{\n{}}}\n\n", name, accessors));
+    }
+    output.push_str(&size_assertion(name, size));
+
+    Ok(output)
+}
+
+/// a getter and setter for `width` bits at `shift` in a storage unit.
+/// signed members are sign-extended by shifting through the signed integer
+/// of the unit's size.
+fn bitfield_accessors(
+    type_registry: &TypeRegistry,
+    field: &StructField,
+    unit: BitfieldUnit,
+    shift: u32,
+    width: u32,
+) -> Result<String> {
+    let field_type = type_to_rust(type_registry, field.type_id)?;
+    let primitive = type_registry.resolve_primitive(field.type_id);
+    let unit_bits = unit.size as u32 * 8;
+    let storage = format!("self._bitfield{}", unit.index);
+    let mask = layout::low_bits(width);
+
+    let bits = if primitive
+        .as_ref()
+        .is_some_and(|p| rust_primitive(&p.name) == Some("bool"))
+    {
+        format!("({} >> {}) & 1 != 0", storage, shift)
+    } else if primitive.as_ref().is_some_and(|p| p.is_signed) {
+        format!(
+            "(({} << {}) as i{} >> {}) as {}",
+            storage,
+            unit_bits - shift - width,
+            unit_bits,
+            unit_bits - width,
+            field_type
+        )
+    } else {
+        format!(
+            "(({} >> {}) & {:#x}) as {}",
+            storage, shift, mask, field_type
+        )
+    };
+
+    Ok(format!(
+        "    pub fn {}(&self) -> {} {{\n        {}\n    }}\n\n\
+         \x20   pub fn set_{}(&mut self, value: {}) {{\n        \
+         {} = ({} & !({:#x} << {})) | ((value as u{} & {:#x}) << {});\n    }}\n",
+        escape_identifier(&field.name),
+        field_type,
+        bits,
+        field.name,
+        field_type,
+        storage,
+        storage,
+        mask,
+        shift,
+        unit_bits,
+        mask,
+        shift
+    ))
+}
+
+/// a compile-time check that the Rust layout has the C size
+fn size_assertion(name: &str, size: usize) -> String {
+    format!(
+        "const _: () = assert!(core::mem::size_of::<{}>() == {});\n\n",
+        name, size
+    )
+}
+
+/// unions are a byte buffer with the union's size and alignment, plus an
+/// unsafe getter and a safe setter per variant. note that a byte buffer is
+/// passed in integer registers, so a union made only of floating point
+/// members does not match C when passed by value on x86-64.
+fn generate_union(
+    type_registry: &TypeRegistry,
+    name: &str,
+    variants: &[UnionField],
+    size: usize,
+    alignment: usize,
+) -> Result<String> {
+    let all_float = !variants.is_empty()
+        && variants.iter().all(|v| {
+            type_registry
+                .resolve_primitive(v.type_id)
+                .is_some_and(|p| p.is_float)
+        });
+    if all_float {
+        log::warn!(
+            "union {} has only floating point members; passing it by value from Rust may not match C",
+            name
+        );
+    }
+
+    let mut output = String::new();
+    output.push_str(&format!(
+        "#[repr(C, align({}))]\n#[derive(Debug, Clone, Copy)]\n",
+        alignment.max(1)
+    ));
+    output.push_str(&format!(
+        "pub struct {} {{\n    bytes: [u8; {}],\n}}\n\n",
+        name, size
+    ));
+
+    output.push_str(&format!(
+        "impl Default for {} {{\n    fn default() -> Self {{\n        Self {{ bytes: [0; {}] }}\n    }}\n}}\n\n",
+        name, size
+    ));
+
+    output.push_str(&format!("impl {} {{\n", name));
+    for (i, variant) in variants.iter().enumerate() {
+        let variant_type = type_to_rust(type_registry, variant.type_id)?;
+        let getter = escape_identifier(&variant.name);
+        let setter = format!("set_{}", variant.name);
+
+        if i > 0 {
+            output.push('\n');
+        }
+        output.push_str(&format!(
+            "    /// read the bytes as `{}`. safety: it must be the variant last written\n\
+             \x20   pub unsafe fn {}(&self) -> {} {{\n\
+             \x20       unsafe {{ core::ptr::read(self as *const Self as *const {}) }}\n\
+             \x20   }}\n\n",
+            variant.name, getter, variant_type, variant_type
+        ));
+        output.push_str(&format!(
+            "    pub fn {}(&mut self, value: {}) {{\n\
+             \x20       unsafe {{ core::ptr::write(self as *mut Self as *mut {}, value) }}\n\
+             \x20   }}\n",
+            setter, variant_type, variant_type
+        ));
+    }
+    output.push_str("}\n\n");
+
+    output.push_str(&size_assertion(name, size));
+
+    Ok(output)
+}
+
+/// C enums can hold values outside their variants, so they become an
+/// integer alias with constants rather than a Rust enum
+fn generate_enum(
+    type_registry: &TypeRegistry,
+    name: &str,
+    backing_id: TypeId,
+    variants: &[EnumVariant],
+) -> Result<String> {
+    let backing = type_registry
+        .resolve_primitive(backing_id)
+        .ok_or_else(|| anyhow!("Enum backing type not found"))?;
+
+    let mut output = format!("pub type {} = {};\n", name, primitive_to_rust(&backing)?);
+    for variant in variants {
+        output.push_str(&format!(
+            "pub const {}: {} = {};\n",
+            escape_identifier(&variant.name),
+            name,
+            variant.value_string(!backing.is_signed)
+        ));
+    }
+    output.push('\n');
+
+    Ok(output)
+}
+
+/// `libfoo.so` -> `foo`, for `#[link(name = ...)]`
+fn link_name(library_path: &str) -> &str {
+    let file_name = library_path.rsplit('/').next().unwrap_or(library_path);
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    stem.strip_prefix("lib").unwrap_or(stem)
+}
+
+fn generate_extern_block(
+    type_registry: &TypeRegistry,
+    functions: &[FunctionSignature],
+    library_path: &str,
+) -> Result<String> {
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "#[link(name = \"{}\")]\nunsafe extern \"C\" {{\n",
+        link_name(library_path)
+    ));
+
+    for func in functions {
+        if let Some(description) = &func.description {
+            output.push_str(&doc::line_comment(description, "    ", "/// "));
+        }
+        output.push_str(&format!("    {}\n", render_function(func, type_registry)));
+    }
+
+    output.push_str("}\n");
+
+    Ok(output)
+}
+
+/// the declaration of one function in the module's `extern "C"` block. a
+/// function Rust can't declare is rendered as a comment saying why.
+///
+/// ```
+/// # use dwarffi::{FunctionSignature, Parameter, Type, TypeRegistry};
+/// use dwarffi_js::codegen::rust;
+/// # let mut registry = TypeRegistry::new();
+/// # let int = registry.register_type(Type::primitive("int", 4));
+/// # let param = |name: &str| Parameter::new(name, int);
+/// # let add = FunctionSignature::new("add", int, vec![param("a"), param("b")]);
+///
+/// // `int add(int a, int b)`
+/// assert_eq!(
+///     rust::render_function(&add, &registry),
+///     "pub fn add(a: core::ffi::c_int, b: core::ffi::c_int) -> core::ffi::c_int;"
+/// );
+/// ```
+pub fn render_function(func: &FunctionSignature, type_registry: &TypeRegistry) -> String {
+    match function_declaration(type_registry, func) {
+        Ok(declaration) => declaration,
+        // e.g. `long double`, which Rust has no type for
+        Err(e) => {
+            log::warn!("skip function {}: {}", func.name, e);
+            format!("// {}: {}", func.name, e)
+        }
+    }
+}
+
+fn function_declaration(type_registry: &TypeRegistry, func: &FunctionSignature) -> Result<String> {
+    let mut params = func
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            let name = if param.name.is_empty() {
+                format!("arg{}", i)
+            } else {
+                escape_identifier(&param.name)
+            };
+            Ok(format!(
+                "{}: {}",
+                name,
+                type_to_rust(type_registry, param.type_id)?
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if func.is_variadic {
+        params.push("...".to_string());
+    }
+
+    // a noreturn function can only return void
+    let return_suffix = if func.is_noreturn {
+        " -> !".to_string()
+    } else {
+        return_suffix(type_registry, Some(func.return_type_id))?
+    };
+
+    Ok(format!(
+        "pub fn {}({}){};",
+        escape_identifier(&func.name),
+        params.join(", "),
+        return_suffix
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_has_variant_accessors() {
+        let mut registry = TypeRegistry::new();
+        let int_id = registry.register_type(Type::primitive("int", 4));
+        let float_id = registry.register_type(Type::primitive("float", 4));
+
+        let union_def = generate_union(
+            &registry,
+            "DataUnion",
+            &[
+                UnionField {
+                    name: "as_int".to_string(),
+                    type_id: int_id,
+                    discriminant_value: None,
+                },
+                UnionField {
+                    name: "as_float".to_string(),
+                    type_id: float_id,
+                    discriminant_value: None,
+                },
+            ],
+            4,
+            4,
+        )
+        .unwrap();
+
+        assert!(union_def.contains("#[repr(C, align(4))]"));
+        assert!(union_def.contains("pub struct DataUnion {\n    bytes: [u8; 4],\n}"));
+        assert!(union_def.contains("pub unsafe fn as_int(&self) -> core::ffi::c_int {"));
+        assert!(union_def.contains("pub unsafe fn as_float(&self) -> core::ffi::c_float {"));
+        assert!(union_def.contains("pub fn set_as_float(&mut self, value: core::ffi::c_float) {"));
+    }
+
+    #[test]
+    fn test_bitfields_are_read_through_their_storage_unit() {
+        let mut registry = TypeRegistry::new();
+        let uint_id = registry.register_type(Type::primitive("unsigned int", 4));
+        let int_id = registry.register_type(Type::primitive("int", 4));
+        let bitfield = |name: &str, type_id, bit_offset: u32, bit_size| StructField {
+            bit_size: Some(bit_size),
+            bit_offset: Some(bit_offset),
+            ..StructField::new(name, type_id, bit_offset as usize / 8, 4)
+        };
+
+        // `unsigned a : 12; int b : 12; int c;` - b starts at byte 1, so
+        // one member per offset would have given it a field of its own
+        let struct_def = generate_struct(
+            &registry,
+            "Packed",
+            &[
+                bitfield("a", uint_id, 0, 12),
+                bitfield("b", int_id, 12, 12),
+                StructField::new("c", int_id, 4, 4),
+            ],
+            8,
+            false,
+        )
+        .unwrap();
+
+        assert!(struct_def.contains(
+            "pub struct Packed {\n    _bitfield0: u32,\n    pub c: core::ffi::c_int,\n}"
+        ));
+        assert!(struct_def.contains("((self._bitfield0 >> 0) & 0xfff) as core::ffi::c_uint"));
+        // sign-extended from the top of the unit
+        assert!(struct_def.contains("((self._bitfield0 << 8) as i32 >> 20) as core::ffi::c_int"));
+        assert!(struct_def.contains(
+            "self._bitfield0 = (self._bitfield0 & !(0xfff << 12)) | ((value as u32 & 0xfff) << 12);"
+        ));
+        assert!(
+            struct_def.ends_with("const _: () = assert!(core::mem::size_of::<Packed>() == 8);\n\n")
+        );
+    }
+
+    #[test]
+    fn test_pointer_mutability_follows_pointee_const() {
+        let mut registry = TypeRegistry::new();
+        let const_char_ptr =
+            registry.register_type(Type::pointer_to(Type::primitive("char", 1).with_const()));
+        let int_ptr_ptr = registry.register_type(Type::pointer_to(Type::pointer_to(
+            Type::primitive("int", 4),
+        )));
+        let void_ptr = registry.register_type(Type::pointer_to(Type::primitive("void", 0)));
+
+        assert_eq!(
+            type_to_rust(&registry, const_char_ptr).unwrap(),
+            "*const core::ffi::c_char"
+        );
+        assert_eq!(
+            type_to_rust(&registry, int_ptr_ptr).unwrap(),
+            "*mut *mut core::ffi::c_int"
+        );
+        assert_eq!(
+            type_to_rust(&registry, void_ptr).unwrap(),
+            "*mut core::ffi::c_void"
+        );
+    }
+
+    #[test]
+    fn test_primitives_without_a_rust_type_are_errors() {
+        let mut registry = TypeRegistry::new();
+        let long_double = registry.register_type(Type::primitive("long double", 16));
+        let long = registry.register_type(Type::primitive("long int", 8));
+        let int64 = registry.register_type(Type::new(BaseTypeKind::Typedef {
+            name: "int64_t".to_string(),
+            aliased_type_id: long,
+        }));
+
+        assert!(type_to_rust(&registry, long_double).is_err());
+        assert_eq!(type_to_rust(&registry, int64).unwrap(), "i64");
+    }
+
+    #[test]
+    fn test_escape_identifier_and_link_name() {
+        assert_eq!(escape_identifier("type"), "r#type");
+        assert_eq!(escape_identifier("self"), "self_");
+        assert_eq!(escape_identifier("count"), "count");
+        assert_eq!(link_name("./libtestlib.so"), "testlib");
+        assert_eq!(link_name("libfoo.1.dylib"), "foo");
+    }
+
+    #[test]
+    fn test_namespace_wraps_bindings_in_a_module() {
+        let output =
+            RustCodegen::generate_module(&TypeRegistry::new(), &[], false, "", Some("type"))
+                .unwrap();
+        assert!(output.ends_with(
+            "pub mod r#type {\n    #![allow(non_camel_case_types, non_snake_case, \
+             non_upper_case_globals, dead_code)]\n\n}\n"
+        ));
+    }
+}
@@ -0,0 +1,396 @@
+/// WebAssembly code generation: an ES module with the memory layout of each
+/// type and a `bind(instance)` helper over the module's exports, for C
+/// compiled to wasm (emscripten, wasi-sdk) and loaded with
+/// `WebAssembly.instantiate`
+use anyhow::Result;
+use dwarffi::{BaseTypeKind, EnumVariant, FunctionSignature, Type, TypeId, TypeRegistry};
+use std::collections::HashSet;
+
+use super::doc;
+use super::graph::{collect_required_types, topological_sort};
+use super::layout::{self, Member};
+
+pub struct WasmCodegen;
+
+impl WasmCodegen {
+    /// `pointer_width` is the target's, 4 for wasm32 and 8 for wasm64
+    pub fn generate_module(
+        type_registry: &TypeRegistry,
+        functions: &[FunctionSignature],
+        pointer_width: usize,
+        generate_functions: bool,
+    ) -> Result<String> {
+        let mut output = String::new();
+
+        output.push_str(&generate_header());
+
+        let required_types = collect_required_types(type_registry, functions);
+        let sorted_types = topological_sort(type_registry, required_types)?;
+
+        let mut generated_names = HashSet::new();
+        for type_id in sorted_types {
+            if let Some(type_) = type_registry.get_type(type_id) {
+                output.push_str(&generate_type_definition(
+                    type_registry,
+                    type_,
+                    pointer_width,
+                    &mut generated_names,
+                )?);
+            }
+        }
+
+        if generate_functions {
+            output.push_str(&generate_bind(type_registry, functions));
+        }
+
+        Ok(output)
+    }
+}
+
+fn generate_header() -> String {
+    "// Auto-generated by dwarffi-js\n\
+     // Do not edit manually!\n\
+     // Backend: WebAssembly\n\n\
+     // DataView accessors for scalar field types. wasm memory is little endian.\n\
+     const ACCESSORS = {\n  \
+       i8: ['getInt8', 'setInt8'],\n  \
+       u8: ['getUint8', 'setUint8'],\n  \
+       i16: ['getInt16', 'setInt16'],\n  \
+       u16: ['getUint16', 'setUint16'],\n  \
+       i32: ['getInt32', 'setInt32'],\n  \
+       u32: ['getUint32', 'setUint32'],\n  \
+       i64: ['getBigInt64', 'setBigInt64'],\n  \
+       u64: ['getBigUint64', 'setBigUint64'],\n  \
+       f32: ['getFloat32', 'setFloat32'],\n  \
+       f64: ['getFloat64', 'setFloat64'],\n\
+     };\n\n\
+     function scalarField(layout, field) {\n  \
+       const { offset, type, bitOffset, bits } = layout.fields[field];\n  \
+       if (!(type in ACCESSORS)) {\n    \
+         throw new TypeError(`${field} is not a scalar field`);\n  \
+       }\n  \
+       return { offset, type, accessor: ACCESSORS[type], bitOffset, bits };\n\
+     }\n\n\
+     // a bitfield is `bits` bits at `bitOffset` in the storage unit at\n\
+     // `offset`, whose `type` says whether it is sign-extended\n\
+     function extractBits(unit, type, bitOffset, bits) {\n  \
+       const width = Number(type.slice(1));\n  \
+       const raw = BigInt.asUintN(width, BigInt(unit));\n  \
+       let value = (raw >> BigInt(bitOffset)) & ((1n << BigInt(bits)) - 1n);\n  \
+       if (type[0] === 'i') {\n    \
+         value = BigInt.asIntN(bits, value);\n  \
+       }\n  \
+       return width === 64 ? value : Number(value);\n\
+     }\n\n\
+     function insertBits(unit, type, bitOffset, bits, value) {\n  \
+       const width = Number(type.slice(1));\n  \
+       const mask = ((1n << BigInt(bits)) - 1n) << BigInt(bitOffset);\n  \
+       const raw = BigInt.asUintN(width, BigInt(unit));\n  \
+       const shifted = BigInt.asUintN(width, BigInt(value)) << BigInt(bitOffset);\n  \
+       let result = (raw & ~mask) | (shifted & mask);\n  \
+       if (type[0] === 'i') {\n    \
+         result = BigInt.asIntN(width, result);\n  \
+       }\n  \
+       return width === 64 ? result : Number(result);\n\
+     }\n\n\
+     /** read a scalar field of the struct at `ptr` in `memory` */\n\
+     export function readField(memory, ptr, layout, field) {\n  \
+       const { offset, type, accessor, bitOffset, bits } = scalarField(layout, field);\n  \
+       const unit = new DataView(memory.buffer)[accessor[0]](Number(ptr) + offset, true);\n  \
+       return bits === undefined ? unit : extractBits(unit, type, bitOffset, bits);\n\
+     }\n\n\
+     /** write a scalar field of the struct at `ptr` in `memory` */\n\
+     export function writeField(memory, ptr, layout, field, value) {\n  \
+       const { offset, type, accessor, bitOffset, bits } = scalarField(layout, field);\n  \
+       const view = new DataView(memory.buffer);\n  \
+       const address = Number(ptr) + offset;\n  \
+       if (bits !== undefined) {\n    \
+         value = insertBits(view[accessor[0]](address, true), type, bitOffset, bits, value);\n  \
+       }\n  \
+       view[accessor[1]](address, value, true);\n\
+     }\n\n"
+        .to_string()
+}
+
+fn is_anonymous(name: &str) -> bool {
+    name.starts_with('<')
+}
+
+/// layout or enum object for one type, or nothing if it's structural or
+/// was already emitted under the same name
+fn generate_type_definition(
+    type_registry: &TypeRegistry,
+    type_: &Type,
+    pointer_width: usize,
+    generated_names: &mut HashSet<String>,
+) -> Result<String> {
+    // anonymous aggregates are emitted under the typedef that names them
+    let name = type_registry
+        .display_name(type_.id)
+        .unwrap_or_else(|| type_.get_name());
+    if is_anonymous(&name) || generated_names.contains(&name) {
+        return Ok(String::new());
+    }
+
+    let output = match &type_.kind {
+        BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. }
+            if type_registry.is_opaque(type_.id) =>
+        {
+            return Ok(String::new());
+        }
+        BaseTypeKind::Struct {
+            fields,
+            size,
+            alignment,
+            ..
+        } => {
+            let fields = layout::struct_members(&name, fields, "a wasm layout")?
+                .into_iter()
+                .filter_map(|member| match member {
+                    Member::Field(f) => Some(LayoutField {
+                        name: &f.name,
+                        offset: f.offset,
+                        size: f.size,
+                        field_type: field_type(type_registry, f.type_id, pointer_width),
+                        bits: None,
+                    }),
+                    Member::Unit(_) => None,
+                    Member::Bitfield {
+                        field,
+                        unit,
+                        shift,
+                        width,
+                    } => Some(LayoutField {
+                        name: &field.name,
+                        offset: unit.offset,
+                        size: unit.size,
+                        field_type: field_type(type_registry, field.type_id, pointer_width),
+                        bits: Some((shift, width)),
+                    }),
+                })
+                .collect::<Vec<_>>();
+            generate_layout(&name, *size, *alignment, &fields)
+        }
+        BaseTypeKind::Union {
+            variants,
+            size,
+            alignment,
+            ..
+        } => {
+            let fields = variants
+                .iter()
+                .map(|v| LayoutField {
+                    name: &v.name,
+                    offset: 0,
+                    size: type_registry.size_of(v.type_id, pointer_width).unwrap_or(0),
+                    field_type: field_type(type_registry, v.type_id, pointer_width),
+                    bits: None,
+                })
+                .collect::<Vec<_>>();
+            generate_layout(&name, *size, *alignment, &fields)
+        }
+        BaseTypeKind::Enum {
+            backing_id,
+            variants,
+            ..
+        } => generate_enum(&name, variants, type_registry.is_unsigned_enum(*backing_id)),
+        _ => return Ok(String::new()),
+    };
+
+    generated_names.insert(name);
+    Ok(match type_registry.description(type_.id) {
+        Some(description) => doc::block_comment(description, "") + &output,
+        None => output,
+    })
+}
+
+/// one entry of a layout's `fields`. a bitfield is the storage unit it is
+/// read from, plus its `(bitOffset, bits)` within the unit.
+struct LayoutField<'a> {
+    name: &'a str,
+    offset: usize,
+    size: usize,
+    field_type: Option<String>,
+    bits: Option<(u32, u32)>,
+}
+
+/// `export const Name = { size, align, fields: { ... } }`. a field's `type`
+/// is a scalar accessor key, the layout name of a nested aggregate, or null.
+fn generate_layout(name: &str, size: usize, alignment: usize, fields: &[LayoutField]) -> String {
+    let mut output = format!(
+        "export const {} = {{\n  size: {},\n  align: {},\n  fields: {{\n",
+        name, size, alignment
+    );
+    for field in fields {
+        let field_type = match &field.field_type {
+            Some(field_type) => format!("'{}'", field_type),
+            None => "null".to_string(),
+        };
+        let bits = match field.bits {
+            Some((bit_offset, bits)) => format!(", bitOffset: {}, bits: {}", bit_offset, bits),
+            None => String::new(),
+        };
+        output.push_str(&format!(
+            "    {}: {{ offset: {}, size: {}, type: {}{} }},\n",
+            field.name, field.offset, field.size, field_type, bits
+        ));
+    }
+    output.push_str("  },\n};\n\n");
+    output
+}
+
+fn generate_enum(name: &str, variants: &[EnumVariant], is_unsigned: bool) -> String {
+    let mut output = format!("export const {} = Object.freeze({{\n", name);
+    for variant in variants {
+        output.push_str(&format!(
+            "  {}: {},\n",
+            variant.name,
+            variant.value_string(is_unsigned)
+        ));
+    }
+    output.push_str("});\n\n");
+    output
+}
+
+/// how a field is read from memory: pointers are addresses of the target's
+/// width, enums are read as their backing integer
+fn field_type(
+    type_registry: &TypeRegistry,
+    type_id: TypeId,
+    pointer_width: usize,
+) -> Option<String> {
+    let type_ = type_registry.get_type(type_id)?;
+    if type_.pointer_depth > 0 {
+        return Some(integer_accessor(pointer_width, false));
+    }
+
+    if let Some(primitive) = type_registry.resolve_primitive(type_id) {
+        return Some(match (primitive.is_float, primitive.size) {
+            (true, 4) => "f32".to_string(),
+            (true, 8) => "f64".to_string(),
+            (true, _) => return None,
+            (false, size) => integer_accessor(size, primitive.is_signed),
+        });
+    }
+
+    let resolved = type_registry.resolve_typedef(type_id)?;
+    if resolved.pointer_depth > 0 {
+        return Some(integer_accessor(pointer_width, false));
+    }
+    match &resolved.kind {
+        BaseTypeKind::Enum { backing_id, .. } => {
+            field_type(type_registry, *backing_id, pointer_width)
+        }
+        BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. } => type_registry
+            .display_name(resolved.id)
+            .filter(|name| !is_anonymous(name)),
+        _ => None,
+    }
+}
+
+fn integer_accessor(size: usize, is_signed: bool) -> String {
+    let sign = if is_signed { 'i' } else { 'u' };
+    format!("{}{}", sign, size * 8)
+}
+
+/// `bind(instance)`: checks the instance exports every function and returns
+/// them together with its memory
+fn generate_bind(type_registry: &TypeRegistry, functions: &[FunctionSignature]) -> String {
+    let mut output = String::from("const FUNCTIONS = [\n");
+    for func in functions {
+        output.push_str(&format!("  '{}',\n", func.name));
+    }
+    output.push_str("];\n\n");
+
+    output.push_str(
+        "/** the library's functions from an instantiated module's exports */\n\
+         export function bind(instance) {\n  \
+           const exports = instance.exports;\n  \
+           const missing = FUNCTIONS.filter((name) => typeof exports[name] !== 'function');\n  \
+           if (missing.length > 0) {\n    \
+             throw new Error(`module does not export: ${missing.join(', ')}`);\n  \
+           }\n  \
+           return {\n    \
+             memory: exports.memory,\n",
+    );
+    for func in functions {
+        let signature = func.to_string(type_registry);
+        let comment = match &func.description {
+            Some(description) => format!("{}\n\n{}", description, signature),
+            None => signature,
+        };
+        output.push_str(&doc::block_comment(&comment, "    "));
+        output.push_str(&format!("    {}: exports.{},\n", func.name, func.name));
+    }
+    output.push_str("  };\n}\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dwarffi::StructField;
+
+    fn primitive(name: &str, size: usize, pointer_depth: usize) -> Type {
+        Type {
+            pointer_depth,
+            pointer_qualifiers: vec![Default::default(); pointer_depth],
+            ..Type::primitive(name, size)
+        }
+    }
+
+    #[test]
+    fn test_field_types_use_target_pointer_width() {
+        let mut registry = TypeRegistry::new();
+        let int_id = registry.register_type(primitive("int", 4, 0));
+        let uchar_id = registry.register_type(primitive("unsigned char", 1, 0));
+        let double_id = registry.register_type(primitive("double", 8, 0));
+        let char_ptr_id = registry.register_type(primitive("char", 1, 1));
+
+        assert_eq!(field_type(&registry, int_id, 4).as_deref(), Some("i32"));
+        assert_eq!(field_type(&registry, uchar_id, 4).as_deref(), Some("u8"));
+        assert_eq!(field_type(&registry, double_id, 4).as_deref(), Some("f64"));
+        assert_eq!(
+            field_type(&registry, char_ptr_id, 4).as_deref(),
+            Some("u32")
+        );
+        assert_eq!(
+            field_type(&registry, char_ptr_id, 8).as_deref(),
+            Some("u64")
+        );
+    }
+
+    #[test]
+    fn test_bitfields_are_described_by_their_storage_unit() {
+        let mut registry = TypeRegistry::new();
+        let uint_id = registry.register_type(primitive("unsigned int", 4, 0));
+        let int_id = registry.register_type(primitive("int", 4, 0));
+        let bitfield = |name: &str, type_id, bit_offset: u32, bit_size| StructField {
+            bit_size: Some(bit_size),
+            bit_offset: Some(bit_offset),
+            ..StructField::new(name, type_id, bit_offset as usize / 8, 4)
+        };
+        let packed = Type::new(BaseTypeKind::Struct {
+            name: "Packed".to_string(),
+            fields: vec![
+                bitfield("a", uint_id, 0, 12),
+                bitfield("b", int_id, 12, 12),
+                StructField::new("c", int_id, 4, 4),
+            ],
+            size: 8,
+            alignment: 4,
+            is_opaque: false,
+        });
+
+        let layout = generate_type_definition(&registry, &packed, 4, &mut HashSet::new()).unwrap();
+        assert!(
+            layout
+                .contains("    a: { offset: 0, size: 4, type: 'u32', bitOffset: 0, bits: 12 },\n")
+        );
+        assert!(
+            layout
+                .contains("    b: { offset: 0, size: 4, type: 'i32', bitOffset: 12, bits: 12 },\n")
+        );
+        assert!(layout.contains("    c: { offset: 4, size: 4, type: 'i32' },\n"));
+    }
+}
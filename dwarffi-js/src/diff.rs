@@ -0,0 +1,186 @@
+/// ABI comparison between two builds of a library
+use dwarffi::{AnalysisResult, FunctionSignature, TypeId};
+use std::collections::BTreeMap;
+
+/// one difference between the old and new function sets, by function name
+#[derive(Debug, PartialEq)]
+pub enum AbiChange {
+    Added(String),
+    Removed(String),
+    /// same name, different signature. `layout_only` is set when the
+    /// signatures spell the same but a type they reference changed shape,
+    /// e.g. a struct passed by value gained a field or a bitfield in it
+    /// changed width.
+    Changed {
+        old: String,
+        new: String,
+        layout_only: bool,
+    },
+}
+
+/// functions added, removed or changed between `old` and `new`, sorted by name
+pub fn diff(old: &AnalysisResult, new: &AnalysisResult) -> Vec<AbiChange> {
+    let by_name = |result: &AnalysisResult| -> BTreeMap<String, FunctionSignature> {
+        result
+            .signatures
+            .iter()
+            .map(|sig| (sig.name.clone(), sig.clone()))
+            .collect()
+    };
+    let old_sigs = by_name(old);
+    let new_sigs = by_name(new);
+
+    let mut names: Vec<&String> = old_sigs.keys().chain(new_sigs.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut changes = Vec::new();
+    for name in names {
+        match (old_sigs.get(name), new_sigs.get(name)) {
+            (Some(before), None) => {
+                changes.push(AbiChange::Removed(before.to_string(&old.type_registry)));
+            }
+            (None, Some(after)) => {
+                changes.push(AbiChange::Added(after.to_string(&new.type_registry)));
+            }
+            (Some(before), Some(after)) => {
+                let same_spelling =
+                    before.abi_key(&old.type_registry) == after.abi_key(&new.type_registry);
+                // type ids are content hashes, so they also catch layout changes
+                if same_spelling && type_ids(before) == type_ids(after) {
+                    continue;
+                }
+
+                changes.push(AbiChange::Changed {
+                    old: before.to_string(&old.type_registry),
+                    new: after.to_string(&new.type_registry),
+                    layout_only: same_spelling,
+                });
+            }
+            (None, None) => unreachable!("name comes from one of the maps"),
+        }
+    }
+
+    changes
+}
+
+fn type_ids(sig: &FunctionSignature) -> Vec<TypeId> {
+    std::iter::once(sig.return_type_id)
+        .chain(sig.parameters.iter().map(|p| p.type_id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dwarffi::{
+        BaseTypeKind, Endianness, Parameter, StructField, TargetInfo, Type, TypeRegistry,
+    };
+
+    /// `int name(<param_type> value)` for each (name, param_type)
+    fn analysis(functions: &[(&str, &str, usize)]) -> AnalysisResult {
+        let mut type_registry = TypeRegistry::new();
+        let int_id = type_registry.register_type(Type::primitive("int", 4));
+
+        let signatures = functions
+            .iter()
+            .map(|(name, param_type, size)| {
+                FunctionSignature::new(
+                    name,
+                    int_id,
+                    vec![Parameter::new(
+                        "value",
+                        type_registry.register_type(Type::primitive(param_type, *size)),
+                    )],
+                )
+            })
+            .collect();
+
+        result(signatures, type_registry)
+    }
+
+    fn result(signatures: Vec<FunctionSignature>, type_registry: TypeRegistry) -> AnalysisResult {
+        let target = TargetInfo {
+            architecture: "x86_64".to_string(),
+            pointer_width: 8,
+            endianness: Endianness::Little,
+        };
+        AnalysisResult::new(signatures, type_registry, target)
+    }
+
+    #[test]
+    fn test_diff_reports_added_removed_and_changed() {
+        let old = analysis(&[
+            ("kept", "int", 4),
+            ("gone", "int", 4),
+            ("widened", "int", 4),
+        ]);
+        let new = analysis(&[
+            ("kept", "int", 4),
+            ("fresh", "int", 4),
+            ("widened", "long", 8),
+        ]);
+
+        assert_eq!(
+            diff(&old, &new),
+            [
+                AbiChange::Added("int fresh(int value)".to_string()),
+                AbiChange::Removed("int gone(int value)".to_string()),
+                AbiChange::Changed {
+                    old: "int widened(int value)".to_string(),
+                    new: "int widened(long value)".to_string(),
+                    layout_only: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_detects_layout_change_with_same_spelling() {
+        let old = analysis(&[("f", "word", 4)]);
+        let new = analysis(&[("f", "word", 8)]);
+
+        assert_eq!(
+            diff(&old, &new),
+            [AbiChange::Changed {
+                old: "int f(word value)".to_string(),
+                new: "int f(word value)".to_string(),
+                layout_only: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_detects_bitfield_width_change() {
+        // `int f(Flags flags)` with `struct Flags { unsigned mode : <width>; }`
+        let analysis = |width| {
+            let mut type_registry = TypeRegistry::new();
+            let int_id = type_registry.register_type(Type::primitive("int", 4));
+            let uint_id = type_registry.register_type(Type::primitive("unsigned int", 4));
+            let flags_id = type_registry.register_type(Type::new(BaseTypeKind::Struct {
+                name: "Flags".to_string(),
+                fields: vec![StructField {
+                    bit_size: Some(width),
+                    bit_offset: Some(0),
+                    ..StructField::new("mode", uint_id, 0, 4)
+                }],
+                size: 4,
+                alignment: 4,
+                is_opaque: false,
+            }));
+            let signature =
+                FunctionSignature::new("f", int_id, vec![Parameter::new("flags", flags_id)]);
+            result(vec![signature], type_registry)
+        };
+
+        assert_eq!(diff(&analysis(3), &analysis(3)), []);
+        assert_eq!(
+            diff(&analysis(3), &analysis(4)),
+            [AbiChange::Changed {
+                old: "int f(struct Flags flags)".to_string(),
+                new: "int f(struct Flags flags)".to_string(),
+                layout_only: true,
+            }]
+        );
+    }
+}
@@ -0,0 +1,4 @@
+//! dwarffi-js - generate FFI bindings from the function signatures and types
+//! [`dwarffi`] extracts. the `dwarffi-js` binary is a CLI over [`codegen`].
+
+pub mod codegen;
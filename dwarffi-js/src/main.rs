@@ -1,10 +1,19 @@
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result, anyhow};
+use clap::{Args, CommandFactory, Parser, Subcommand};
 use log::{debug, info, warn};
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 
-mod codegen;
-use codegen::{FfiBackend, JsCodegen};
+mod diff;
+mod summary;
+use diff::AbiChange;
+use dwarffi::Visibility;
+use dwarffi_js::codegen::{
+    CsharpCodegen, DenoCodegen, DotCodegen, FfiBackend, FfiSpecCodegen, HeaderCodegen, JavaCodegen,
+    JsCodegen, JsOptions, JsonCodegen, ModuleFormat, NimCodegen, Rename, RustCodegen,
+    TypeMappingOverrides, WasmCodegen, is_js_reserved,
+};
+use summary::Summary;
 
 /// dwarffi-js - extract C FFI signatures and generate JavaScript bindings
 #[derive(Parser)]
@@ -12,25 +21,182 @@ use codegen::{FfiBackend, JsCodegen};
 #[command(version)]
 #[command(about = "extract function signatures from C libraries using DWARF debug info", long_about = None)]
 struct Cli {
-    /// path to the library file (.dylib, .so, .o, or dSYM)
-    library: PathBuf,
-
-    /// show all functions (including internal/hidden ones)
-    #[arg(long)]
-    all: bool,
+    #[command(subcommand)]
+    command: Command,
 
     /// suppress informational messages (only show signatures)
-    #[arg(short = 'q', long)]
+    #[arg(short = 'q', long, global = true)]
     quiet: bool,
 
     /// verbose logging to console (-v for info, -vv for debug, -vvv for trace)
-    #[arg(short, long, action = clap::ArgAction::Count)]
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     verbose: u8,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// print C signatures or generate bindings (the default)
+    Extract(ExtractArgs),
+
+    /// compare the functions of two builds of a library
+    Diff(DiffArgs),
+
+    /// list the named types (structs, unions, enums, typedefs)
+    Types(LibraryArgs),
+
+    /// emit a C header declaring the types and functions
+    Header(LibraryArgs),
+
+    /// output JSON representation of types and functions
+    Json(JsonArgs),
+}
+
+/// which functions to read from a library
+#[derive(Args)]
+struct FilterArgs {
+    /// architecture slice to read from a universal binary (e.g. x86_64, arm64);
+    /// defaults to the host architecture
+    #[arg(long, value_name = "ARCH")]
+    arch: Option<String>,
+
+    /// show all functions (including internal/hidden ones); same as
+    /// `--visibility all`
+    #[arg(long, conflicts_with = "visibility")]
+    all: bool,
+
+    /// which functions to show: `exported` from the symbol table, `external`
+    /// (global, e.g. the public API of an object file) or `all`
+    #[arg(long, value_name = "LEVEL", default_value_t = Visibility::Exported)]
+    visibility: Visibility,
+
+    /// skip functions whose names match this glob (e.g. '*_impl'); repeatable
+    #[arg(long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+
+    /// skip C++ functions (mangled names, and namespace or class members
+    /// that aren't `extern "C"`)
+    #[arg(long)]
+    extern_c_only: bool,
+
+    /// skip C++ virtual methods, which are called through a vtable rather
+    /// than by their symbol
+    #[arg(long)]
+    no_virtual: bool,
+
+    /// spell integer types the same whichever compiler built the library
+    /// (`unsigned long` for gcc's `long unsigned int`), e.g. to diff builds
+    /// from different toolchains
+    #[arg(long)]
+    normalize_int_names: bool,
+
+    /// keep only the types the listed functions use, dropping those only
+    /// excluded or hidden functions need (and anonymous enums of constants)
+    #[arg(long)]
+    prune_types: bool,
+
+    /// exit with an error, listing them, if any compilation unit or function
+    /// was skipped because its DWARF couldn't be read. without it they are
+    /// only logged as warnings.
+    #[arg(long)]
+    fail_on_skipped: bool,
+}
+
+#[derive(Args)]
+struct LibraryArgs {
+    /// path to the library file (.dylib, .so, .o, or dSYM)
+    library: PathBuf,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+#[derive(Args)]
+struct JsonArgs {
+    /// path to the library file (.dylib, .so, .o, or dSYM)
+    #[arg(required_unless_present = "print_schema")]
+    library: Option<PathBuf>,
+
+    /// print the JSON Schema describing the output, then exit
+    #[arg(long)]
+    print_schema: bool,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+#[derive(Args)]
+struct ExtractArgs {
+    /// path to the library file (.dylib, .so, .o, or dSYM)
+    library: PathBuf,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+
+    /// list functions without parameters as `f()` rather than `f(void)`.
+    /// functions declared without a prototype are listed as `f()` anyway
+    #[arg(long)]
+    no_default_void_param: bool,
 
     /// output JavaScript bindings using Koffi FFI
     #[arg(long)]
     js: bool,
 
+    /// module format of the JavaScript bindings
+    #[arg(long, value_enum, default_value_t = ModuleFormat::Cjs, requires = "js")]
+    module_format: ModuleFormat,
+
+    /// FFI library the JavaScript bindings use
+    #[arg(long, default_value_t = FfiBackend::Koffi, requires = "js")]
+    backend: FfiBackend,
+
+    /// bind functions without this prefix in the JavaScript bindings (e.g.
+    /// `mylib_` binds `mylib_init` as `init`); the C symbol is unchanged
+    #[arg(long, value_name = "PREFIX", requires = "js")]
+    strip_prefix: Option<String>,
+
+    /// JSON file mapping C primitive names to koffi types, used instead of
+    /// the defaults (e.g. `{"long double": "longdouble"}`)
+    #[arg(long, value_name = "FILE", requires = "js")]
+    type_map: Option<PathBuf>,
+
+    /// export an `init(libraryPath)` that loads the library and returns the
+    /// function bindings, instead of loading --library-path on import
+    #[arg(long, requires = "js", conflicts_with = "library_path")]
+    runtime_load: bool,
+
+    /// wrap the JavaScript or Rust bindings in one exported object or
+    /// `pub mod` named NAME, so bindings of several libraries don't clash
+    #[arg(long, value_name = "NAME", value_parser = parse_namespace)]
+    namespace: Option<String>,
+
+    /// output C# bindings using P/Invoke (DllImport)
+    #[arg(long)]
+    csharp: bool,
+
+    /// output Rust bindings (`#[repr(C)]` types and an `extern "C"` block)
+    #[arg(long)]
+    rust: bool,
+
+    /// output Nim bindings (`{.bycopy.}` objects and `{.importc, dynlib.}`
+    /// procs)
+    #[arg(long)]
+    nim: bool,
+
+    /// output Java 22+ bindings using the Foreign Function & Memory API
+    /// (`MemoryLayout`s and downcall `MethodHandle`s)
+    #[arg(long)]
+    java: bool,
+
+    /// output an ES module for a library compiled to WebAssembly (struct
+    /// layouts and a `bind(instance)` helper for `WebAssembly.instantiate`)
+    #[arg(long)]
+    wasm: bool,
+
+    /// output a TypeScript module for Deno (`Deno.dlopen` symbols, struct
+    /// descriptors and `UnsafeCallback` definitions)
+    #[arg(long)]
+    deno: bool,
+
     /// generate type definitions (structs, unions, enums)
     #[arg(long)]
     types: bool,
@@ -43,82 +209,494 @@ struct Cli {
     #[arg(long)]
     library_path: Option<String>,
 
+    /// name anonymous structs, unions and enums that no typedef names after
+    /// where they're declared (e.g. `Person_anon_union_0`), so the Rust, C#
+    /// and Nim bindings can declare them
+    #[arg(long)]
+    demunge_anonymous: bool,
+
     /// output JSON representation of types and functions
     #[arg(short = 'j', long)]
     json: bool,
+
+    /// output the FFI spec: each function with marshaling hints and each
+    /// type flattened to primitive widths, for generators in other languages
+    #[arg(long)]
+    ffi_spec: bool,
+
+    /// output a Graphviz DOT graph of type dependencies
+    #[arg(long)]
+    emit_dot: bool,
+
+    /// print the target architecture, pointer width, byte order and the
+    /// compilers that produced the library, then exit
+    #[arg(long)]
+    target_info: bool,
+
+    /// print counts of functions and types instead of the listing. internal
+    /// functions are always counted.
+    #[arg(long)]
+    summary: bool,
+
+    /// with --summary, exit with an error if any types are unresolved
+    #[arg(long, requires = "summary")]
+    strict: bool,
+
+    /// check the extracted types for dangling references, typedef cycles,
+    /// sizes that disagree with their members and overlapping fields, print
+    /// what is found and exit with an error if anything is
+    #[arg(long)]
+    validate: bool,
+
+    /// exit with an error, listing what is missing, if any type an emitted
+    /// function uses could not be resolved: an unknown DWARF construct, a
+    /// dangling reference, or a struct used by value but never defined
+    #[arg(long, conflicts_with = "summary")]
+    fail_on_unresolved: bool,
+}
+
+#[derive(Args)]
+struct DiffArgs {
+    /// the library as previously built
+    old: PathBuf,
+
+    /// the library to compare against it
+    new: PathBuf,
+
+    #[command(flatten)]
+    filter: FilterArgs,
 }
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let cli = Cli::parse_from(with_default_subcommand(std::env::args_os()));
 
     init_logger(cli.verbose, cli.quiet);
 
-    let exported_only = !cli.all;
+    match &cli.command {
+        Command::Extract(args) => run_extract(args),
+        Command::Diff(args) => run_diff(args),
+        Command::Types(args) => run_types(args),
+        Command::Header(args) => run_header(args),
+        Command::Json(args) => run_json(args),
+    }
+}
+
+/// `dwarffi-js <library> [options]` predates the subcommands, so a command
+/// line whose first positional isn't a subcommand is treated as `extract`
+fn with_default_subcommand(args: impl Iterator<Item = OsString>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = args.collect();
+    let first_positional = args
+        .iter()
+        .skip(1)
+        .find(|arg| !arg.to_string_lossy().starts_with('-'));
+
+    let is_subcommand = |arg: &OsString| {
+        let arg = arg.to_string_lossy();
+        arg == "help" || Cli::command().find_subcommand(arg.as_ref()).is_some()
+    };
+    if first_positional.is_some_and(|arg| !is_subcommand(arg)) {
+        args.insert(1, OsString::from("extract"));
+    }
+    args
+}
 
-    info!("library: {}", cli.library.display());
-    info!(
-        "mode: {}",
-        if exported_only {
-            "exported only"
-        } else {
-            "all functions"
+fn run_extract(args: &ExtractArgs) -> Result<()> {
+    let analyzer = load_analyzer(&args.library, &args.filter)?;
+
+    if args.target_info {
+        let target = analyzer.target_info()?;
+        println!("architecture: {}", target.architecture);
+        println!("pointer width: {} bytes", target.pointer_width);
+        println!("endianness: {}", target.endianness);
+        for producer in analyzer.producers()? {
+            println!("producer: {}", producer);
         }
-    );
+        return Ok(());
+    }
 
-    // load the library
-    debug!("load library file: {}", cli.library.display());
-    let analyzer = dwarffi::DwarfAnalyzer::from_file(&cli.library)?;
+    if args.summary {
+        let result = analyze_functions(&analyzer, &args.filter, Visibility::All)?;
+        let summary = Summary::new(&result);
+        println!("{}", summary);
+        if args.strict && summary.unresolved_types > 0 {
+            anyhow::bail!("{} unresolved types", summary.unresolved_types);
+        }
+        return Ok(());
+    }
 
-    let result = analyzer.extract_analysis(exported_only)?;
+    if args.namespace.is_some() && !(args.js || args.rust) {
+        anyhow::bail!("--namespace applies to --js and --rust bindings");
+    }
 
+    let mut result = analyze(&analyzer, &args.filter)?;
+    if args.validate {
+        let issues = result.type_registry.validate();
+        for issue in &issues {
+            println!("{}", issue);
+        }
+        if !issues.is_empty() {
+            anyhow::bail!("{} type integrity issues", issues.len());
+        }
+        return Ok(());
+    }
+    if args.fail_on_unresolved {
+        check_unresolved(&result)?;
+    }
     if result.signatures.is_empty() {
-        warn!(
-            "no functions found in the library. maybe you compiled without debug info, or stripped the binary?"
-        );
         return Ok(());
     }
+    result
+        .type_registry
+        .set_demunge_anonymous(args.demunge_anonymous);
 
-    // sort signatures by name for consistent output
-    let mut sorted_sigs = result.signatures;
-    sorted_sigs.sort_by(|a, b| a.name.cmp(&b.name));
+    if args.json {
+        print_json(&result)?;
+    } else if args.ffi_spec {
+        println!(
+            "{}",
+            FfiSpecCodegen::generate(&result.type_registry, &result.signatures, &result.target)?
+        );
+    } else if args.emit_dot {
+        print!("{}", DotCodegen::generate(&result.type_registry));
+    } else if args.js {
+        // bindings are loaded on the host, so the library must match it
+        if !result.target.matches_host() {
+            warn!(
+                "target {} differs from host {} ({} endian); generated bindings may not load",
+                result.target,
+                std::env::consts::ARCH,
+                dwarffi::Endianness::host()
+            );
+        }
 
-    if cli.json {
-        unimplemented!("JSON output not yet implemented");
-    } else if cli.js {
         // determine what to generate
         let generate_types = true; // types always needed
-        let generate_functions = cli.functions;
-
-        // library path for function bindings
-        let library_path = cli.library_path.unwrap_or_else(|| {
-            // default: use the input library filename
-            cli.library
-                .file_name()
-                .and_then(|n| n.to_str())
-                .map(|s| format!("./{}", s))
-                .unwrap_or_else(|| "./library.dylib".to_string())
-        });
+        let generate_functions = args.functions;
+
+        let library_path = binding_library_path(args);
 
         // generate JavaScript bindings using Koffi
         let js_code = JsCodegen::generate_module(
             &result.type_registry,
-            &sorted_sigs,
+            &result.signatures,
+            &result.macros,
             generate_types,
             generate_functions,
             &library_path,
-            FfiBackend::default(), // Always use Koffi
+            JsOptions {
+                backend: args.backend,
+                module_format: args.module_format,
+                rename: args
+                    .strip_prefix
+                    .as_deref()
+                    .map(Rename::strip_prefix)
+                    .unwrap_or_default(),
+                type_map: load_type_map(args.type_map.as_deref())?,
+                namespace: args.namespace.clone(),
+                runtime_load: args.runtime_load,
+            },
         )?;
         println!("{}", js_code);
+    } else if args.csharp {
+        let library_path = binding_library_path(args);
+
+        let cs_code = CsharpCodegen::generate_module(
+            &result.type_registry,
+            &result.signatures,
+            args.functions,
+            &library_path,
+        )?;
+        println!("{}", cs_code);
+    } else if args.rust {
+        let library_path = binding_library_path(args);
+
+        let rs_code = RustCodegen::generate_module(
+            &result.type_registry,
+            &result.signatures,
+            args.functions,
+            &library_path,
+            args.namespace.as_deref(),
+        )?;
+        println!("{}", rs_code);
+    } else if args.nim {
+        let library_path = binding_library_path(args);
+
+        let nim_code = NimCodegen::generate_module(
+            &result.type_registry,
+            &result.signatures,
+            args.functions,
+            &library_path,
+        )?;
+        println!("{}", nim_code);
+    } else if args.java {
+        let library_path = binding_library_path(args);
+
+        let java_code = JavaCodegen::generate_module(
+            &result.type_registry,
+            &result.signatures,
+            args.functions,
+            &library_path,
+            result.target.pointer_width,
+        )?;
+        println!("{}", java_code);
+    } else if args.wasm {
+        let wasm_code = WasmCodegen::generate_module(
+            &result.type_registry,
+            &result.signatures,
+            result.target.pointer_width,
+            args.functions,
+        )?;
+        println!("{}", wasm_code);
+    } else if args.deno {
+        let library_path = binding_library_path(args);
+
+        let deno_code = DenoCodegen::generate_module(
+            &result.type_registry,
+            &result.signatures,
+            args.functions,
+            &library_path,
+            result.target.pointer_width,
+        )?;
+        println!("{}", deno_code);
     } else {
         // standard C signature output
-        for sig in &sorted_sigs {
-            println!("{};", sig.to_string(&result.type_registry));
+        for sig in &result.signatures {
+            println!(
+                "{};",
+                sig.to_string_with_void_param(&result.type_registry, !args.no_default_void_param)
+            );
         }
     }
 
     Ok(())
 }
 
+fn run_diff(args: &DiffArgs) -> Result<()> {
+    let old = analyze(&load_analyzer(&args.old, &args.filter)?, &args.filter)?;
+    let new = analyze(&load_analyzer(&args.new, &args.filter)?, &args.filter)?;
+
+    if old.target != new.target {
+        warn!("comparing {} against {}", old.target, new.target);
+    }
+
+    for change in diff::diff(&old, &new) {
+        match change {
+            AbiChange::Added(sig) => println!("+ {};", sig),
+            AbiChange::Removed(sig) => println!("- {};", sig),
+            AbiChange::Changed {
+                old,
+                new,
+                layout_only,
+            } => {
+                println!("~ {};", new);
+                if layout_only {
+                    println!("    a type it uses changed layout");
+                } else {
+                    println!("    was: {};", old);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_types(args: &LibraryArgs) -> Result<()> {
+    let result = analyze(&load_analyzer(&args.library, &args.filter)?, &args.filter)?;
+    let pointer_width = result.target.pointer_width;
+
+    // a struct only ever used through a pointer has no entry of its own, so
+    // every variant is listed by the base type it points to or qualifies
+    let mut types: Vec<(String, Option<usize>)> = result
+        .type_registry
+        .all_types()
+        .filter_map(|t| {
+            let (kind, size) = match &t.kind {
+                dwarffi::BaseTypeKind::Struct {
+                    size, is_opaque, ..
+                } => ("struct", (!is_opaque).then_some(*size)),
+                dwarffi::BaseTypeKind::Union { size, .. } => ("union", Some(*size)),
+                dwarffi::BaseTypeKind::Enum { size, .. } => ("enum", Some(*size)),
+                dwarffi::BaseTypeKind::Typedef {
+                    aliased_type_id, ..
+                } => (
+                    "typedef",
+                    result
+                        .type_registry
+                        .size_of(*aliased_type_id, pointer_width),
+                ),
+                _ => return None,
+            };
+            let name = t.get_name();
+            (!name.starts_with('<')).then(|| (format!("{} {}", kind, name), size))
+        })
+        .collect();
+    types.sort();
+    types.dedup();
+
+    for (name, size) in types {
+        match size {
+            Some(1) => println!("{} (1 byte)", name),
+            Some(size) => println!("{} ({} bytes)", name, size),
+            None => println!("{}", name),
+        }
+    }
+
+    Ok(())
+}
+
+fn run_header(args: &LibraryArgs) -> Result<()> {
+    let result = analyze(&load_analyzer(&args.library, &args.filter)?, &args.filter)?;
+    print!(
+        "{}",
+        HeaderCodegen::generate(&result.type_registry, &result.signatures)?
+    );
+    Ok(())
+}
+
+fn run_json(args: &JsonArgs) -> Result<()> {
+    let library = match &args.library {
+        Some(library) if !args.print_schema => library,
+        _ => {
+            print!("{}", JsonCodegen::SCHEMA);
+            return Ok(());
+        }
+    };
+
+    let result = analyze(&load_analyzer(library, &args.filter)?, &args.filter)?;
+    print_json(&result)
+}
+
+fn print_json(result: &dwarffi::AnalysisResult) -> Result<()> {
+    let json = JsonCodegen::generate(&result.type_registry, &result.signatures, &result.target)?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn load_analyzer(library: &Path, filter: &FilterArgs) -> Result<dwarffi::DwarfAnalyzer> {
+    info!("library: {}", library.display());
+
+    // load the library
+    debug!("load library file: {}", library.display());
+    let analyzer = match &filter.arch {
+        Some(arch) => dwarffi::DwarfAnalyzer::from_file_with_arch(library, arch)?,
+        None => dwarffi::DwarfAnalyzer::from_file(library)?,
+    };
+    Ok(analyzer)
+}
+
+/// fail, listing each function's unresolved types on stderr, if any function
+/// in `result` uses one
+fn check_unresolved(result: &dwarffi::AnalysisResult) -> Result<()> {
+    let mut functions = 0;
+    for sig in &result.signatures {
+        let unresolved = sig.unresolved_types(&result.type_registry);
+        if unresolved.is_empty() {
+            continue;
+        }
+        functions += 1;
+        for unresolved in unresolved {
+            eprintln!("{}: {}", sig.name, unresolved);
+        }
+    }
+    if functions > 0 {
+        anyhow::bail!("{} functions use unresolved types", functions);
+    }
+    Ok(())
+}
+
+/// extract functions and types, with signatures sorted by name
+fn analyze(
+    analyzer: &dwarffi::DwarfAnalyzer,
+    filter: &FilterArgs,
+) -> Result<dwarffi::AnalysisResult> {
+    let visibility = if filter.all {
+        Visibility::All
+    } else {
+        filter.visibility
+    };
+    analyze_functions(analyzer, filter, visibility)
+}
+
+/// `analyze`, choosing the visibility independently of `--visibility`
+fn analyze_functions(
+    analyzer: &dwarffi::DwarfAnalyzer,
+    filter: &FilterArgs,
+    visibility: Visibility,
+) -> Result<dwarffi::AnalysisResult> {
+    info!("mode: {} functions", visibility);
+
+    let options = dwarffi::ExtractOptions::new()
+        .visibility(visibility)
+        .exclude_all(&filter.exclude)
+        .extern_c_only(filter.extern_c_only)
+        .skip_virtual(filter.no_virtual)
+        .normalize_primitive_names(filter.normalize_int_names)
+        .continue_on_error(filter.fail_on_skipped);
+    let mut result = analyzer.extract_analysis_with(&options)?;
+    if !result.errors.is_empty() {
+        for error in &result.errors {
+            eprintln!("skipped {}", error);
+        }
+        anyhow::bail!(
+            "{} units or functions could not be read",
+            result.errors.len()
+        );
+    }
+
+    if result.signatures.is_empty() {
+        warn!(
+            "no functions found in the library. maybe you compiled without debug info, or stripped the binary?"
+        );
+    }
+
+    // sort signatures by name for consistent output
+    result.signatures.sort_by(|a, b| a.name.cmp(&b.name));
+    result.index_functions();
+    if filter.prune_types {
+        result.prune_types();
+    }
+    Ok(result)
+}
+
+/// `--namespace` must be an identifier in every language it applies to
+fn parse_namespace(name: &str) -> Result<String, String> {
+    let mut chars = name.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_identifier {
+        Err(format!("'{}' is not an identifier", name))
+    } else if is_js_reserved(name) {
+        Err(format!("'{}' is a reserved word in JavaScript", name))
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+/// primitive type overrides from `--type-map`, if given
+fn load_type_map(path: Option<&Path>) -> Result<TypeMappingOverrides> {
+    let Some(path) = path else {
+        return Ok(TypeMappingOverrides::default());
+    };
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    TypeMappingOverrides::from_json(&json).map_err(|e| anyhow!("{}: {}", path.display(), e))
+}
+
+/// library path for function bindings
+fn binding_library_path(args: &ExtractArgs) -> String {
+    args.library_path.clone().unwrap_or_else(|| {
+        // default: use the input library filename
+        args.library
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|s| format!("./{}", s))
+            .unwrap_or_else(|| "./library.dylib".to_string())
+    })
+}
+
 fn init_logger(verbose: u8, quiet: bool) {
     // If quiet mode is enabled, only show warnings and errors
     let log_level = if quiet {
@@ -0,0 +1,149 @@
+/// counts of what was extracted from a library, for CI checks that don't
+/// need the full listing
+use dwarffi::{AnalysisResult, BaseTypeKind};
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub exported_functions: usize,
+    pub internal_functions: usize,
+    /// registered types by kind; pointer variants of any type count as
+    /// "pointer"
+    pub types_by_kind: BTreeMap<&'static str, usize>,
+    /// placeholder types plus references to types that were never registered
+    pub unresolved_types: usize,
+}
+
+impl Summary {
+    pub fn new(result: &AnalysisResult) -> Self {
+        let exported_functions = result.signatures.iter().filter(|s| s.is_exported).count();
+
+        let mut types_by_kind = BTreeMap::new();
+        for type_ in result.type_registry.all_types() {
+            let kind = match &type_.kind {
+                _ if type_.pointer_depth > 0 => "pointer",
+                BaseTypeKind::Primitive { .. } => "primitive",
+                BaseTypeKind::Struct { .. } => "struct",
+                BaseTypeKind::Union { .. } => "union",
+                BaseTypeKind::Enum { .. } => "enum",
+                BaseTypeKind::Array { .. } => "array",
+                BaseTypeKind::Typedef { .. } => "typedef",
+                BaseTypeKind::Function { .. } => "function",
+            };
+            *types_by_kind.entry(kind).or_insert(0) += 1;
+        }
+
+        let placeholders = result
+            .type_registry
+            .all_types()
+            .filter(|t| t.is_placeholder())
+            .count();
+
+        Self {
+            exported_functions,
+            internal_functions: result.signatures.len() - exported_functions,
+            types_by_kind,
+            unresolved_types: placeholders + result.type_registry.missing_type_ids().len(),
+        }
+    }
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "functions: {} ({} exported, {} internal)",
+            self.exported_functions + self.internal_functions,
+            self.exported_functions,
+            self.internal_functions
+        )?;
+        writeln!(f, "types: {}", self.types_by_kind.values().sum::<usize>())?;
+        for (kind, count) in &self.types_by_kind {
+            writeln!(f, "  {}: {}", kind, count)?;
+        }
+        write!(f, "unresolved types: {}", self.unresolved_types)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dwarffi::{
+        Endianness, FunctionSignature, POINTER_TO_MEMBER, StructField, TargetInfo, Type, TypeId,
+        TypeRegistry,
+    };
+
+    fn make(kind: BaseTypeKind, pointer_depth: usize) -> Type {
+        Type {
+            pointer_depth,
+            pointer_qualifiers: vec![Default::default(); pointer_depth],
+            ..Type::new(kind)
+        }
+    }
+
+    fn primitive(name: &str) -> BaseTypeKind {
+        BaseTypeKind::Primitive {
+            name: name.to_string(),
+            size: 4,
+            alignment: 4,
+            encoding: None,
+        }
+    }
+
+    fn function(name: &str, return_type_id: TypeId, is_exported: bool) -> FunctionSignature {
+        FunctionSignature {
+            is_exported,
+            ..FunctionSignature::new(name, return_type_id, Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_summary_counts() {
+        let mut type_registry = TypeRegistry::new();
+        let int_id = type_registry.register_type(make(primitive("int"), 0));
+        type_registry.register_type(make(primitive("int"), 1));
+        type_registry.register_type(make(primitive(POINTER_TO_MEMBER), 0));
+        // a field whose type is not in the registry
+        type_registry.register_type(make(
+            BaseTypeKind::Struct {
+                name: "Holder".to_string(),
+                fields: vec![StructField::new("value", TypeId(42), 0, 4)],
+                size: 4,
+                alignment: 4,
+                is_opaque: false,
+            },
+            0,
+        ));
+
+        let signatures = vec![
+            function("api_call", int_id, true),
+            function("helper", int_id, false),
+            function("other_call", int_id, true),
+        ];
+        let target = TargetInfo {
+            architecture: "x86_64".to_string(),
+            pointer_width: 8,
+            endianness: Endianness::Little,
+        };
+        let result = AnalysisResult::new(signatures, type_registry, target);
+
+        let summary = Summary::new(&result);
+        assert_eq!(summary.exported_functions, 2);
+        assert_eq!(summary.internal_functions, 1);
+        assert_eq!(
+            summary.types_by_kind,
+            BTreeMap::from([("pointer", 1), ("primitive", 2), ("struct", 1)])
+        );
+        assert_eq!(summary.unresolved_types, 2);
+
+        assert_eq!(
+            summary.to_string(),
+            "functions: 3 (2 exported, 1 internal)\n\
+             types: 4\n  \
+               pointer: 1\n  \
+               primitive: 2\n  \
+               struct: 1\n\
+             unresolved types: 2"
+        );
+    }
+}
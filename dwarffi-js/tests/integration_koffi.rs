@@ -51,9 +51,6 @@ fn test_koffi_bindings_end_to_end() {
     fs::write(&bindings_path, &bindings_code).expect("Failed to write bindings.js");
     debug!("Wrote bindings to: {:?}", bindings_path);
 
-    // update LIBRARY_PATH in bindings
-    update_library_path(&bindings_path, &workspace_root);
-
     // copy the test runner script
     let test_script_source = workspace_root
         .join("dwarffi-js")
@@ -67,10 +64,17 @@ fn test_koffi_bindings_end_to_end() {
     info!("Installing koffi dependency");
     install_koffi(&temp_path);
 
+    // the bindings load the library from the path init() is given
+    let lib_path = common::get_test_dylib_path()
+        .canonicalize()
+        .expect("Failed to get absolute path for library");
+    debug!("Loading library from: {:?}", lib_path);
+
     // run the Node.js tests with TAP output
     info!("Running Node.js test suite");
     let output = Command::new("node")
         .args(["--test", "--test-reporter=tap", "test.mjs"])
+        .env("TESTLIB_PATH", &lib_path)
         .current_dir(&temp_path)
         .output()
         .expect("Failed to execute Node.js tests");
@@ -266,8 +270,7 @@ fn generate_bindings(workspace_root: &Path) -> String {
             testlib_path.to_str().unwrap(),
             "--js",
             "--functions",
-            "--library-path",
-            "./libtestlib.dylib", // TODO FIXME: make platform-specific?
+            "--runtime-load",
         ])
         .current_dir(workspace_root)
         .output()
@@ -307,24 +310,3 @@ fn install_koffi(dir: &Path) {
 
     debug!("Koffi installed successfully");
 }
-
-/// update the LIBRARY_PATH constant in the generated bindings to use absolute path
-#[cfg(target_os = "macos")]
-fn update_library_path(bindings_path: &Path, _workspace_root: &Path) {
-    let content = fs::read_to_string(bindings_path).expect("Failed to read bindings.js");
-
-    // platform-specific dylib path
-    let lib_path = common::get_test_dylib_path()
-        .canonicalize()
-        .expect("Failed to get absolute path for library");
-
-    debug!("Setting library path to: {:?}", lib_path);
-
-    // replace the LIBRARY_PATH line
-    let updated = content.replace(
-        "const LIBRARY_PATH = './libtestlib.dylib'",
-        &format!("const LIBRARY_PATH = '{}'", lib_path.display()),
-    );
-
-    fs::write(bindings_path, updated).expect("Failed to update bindings.js");
-}
@@ -8,7 +8,11 @@ import assert from 'node:assert';
 import { createRequire } from 'node:module';
 
 const require = createRequire(import.meta.url);
-const bindings = require('./bindings.js');
+const koffi = require('koffi');
+// the bindings are generated with --runtime-load, so the library is loaded
+// from wherever the test harness says it is
+const { init, ...module } = require('./bindings.js');
+const bindings = { ...module, ...init(process.env.TESTLIB_PATH) };
 
 // ============================================================================
 // Basic Primitives
@@ -52,6 +56,18 @@ describe('Basic Primitives', () => {
   });
 });
 
+// ============================================================================
+// Constants
+// ============================================================================
+
+describe('Constants', () => {
+  test('#define constants are exported', () => {
+    assert.strictEqual(bindings.constants.TESTLIB_MAX_POINTS, 1024);
+    assert.strictEqual(bindings.constants.TESTLIB_INVALID, -1);
+    assert.strictEqual(bindings.constants.TESTLIB_VERSION, '1.2.0');
+  });
+});
+
 // ============================================================================
 // Structs by Value
 // ============================================================================
@@ -217,3 +233,43 @@ describe('Struct Pointers', () => {
     // just verify no crash
   });
 });
+
+// ============================================================================
+// Callbacks
+// ============================================================================
+
+describe('Callbacks', () => {
+  test('sort_array calls a JS comparator', () => {
+    // typed arrays share memory with C, so the sort is visible here
+    const arr = new Int32Array([5, 2, 8, 1, 9, 3, 7]);
+    const ascending = (a, b) => koffi.decode(a, 'int') - koffi.decode(b, 'int');
+
+    bindings.sort_array(arr, arr.length, ascending);
+    assert.deepStrictEqual(Array.from(arr), [1, 2, 3, 5, 7, 8, 9]);
+  });
+
+  test('registered comparator can be reused until unregistered', () => {
+    const descending = bindings.registerCallback(
+      bindings.callbacks.Comparator,
+      (a, b) => koffi.decode(b, 'int') - koffi.decode(a, 'int')
+    );
+
+    try {
+      const arr = new Int32Array([1, 3, 2]);
+      bindings.sort_array(arr, arr.length, descending);
+      assert.deepStrictEqual(Array.from(arr), [3, 2, 1]);
+    } finally {
+      koffi.unregister(descending);
+    }
+  });
+
+  test('register_callback passes userdata back', () => {
+    let calls = 0;
+    bindings.register_callback((value, userdata) => {
+      calls += 1;
+      assert.strictEqual(value, 0);
+      assert.strictEqual(userdata, null);
+    }, null);
+    assert.strictEqual(calls, 1);
+  });
+});
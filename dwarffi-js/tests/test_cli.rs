@@ -0,0 +1,587 @@
+/// subcommand behaviour of the dwarffi-js binary against the test_c library
+mod common;
+
+use std::process::Command;
+
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_dwarffi-js"))
+        .args(args)
+        .output()
+        .expect("Failed to run dwarffi-js");
+
+    assert!(
+        output.status.success(),
+        "dwarffi-js {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("output is not utf-8")
+}
+
+#[test]
+fn test_no_subcommand_defaults_to_extract() {
+    let lib = common::get_test_lib_path();
+    let lib = lib.to_str().unwrap();
+
+    let default = run(&[lib, "-q"]);
+    assert_eq!(default, run(&["extract", lib, "-q"]));
+    assert!(default.contains("int add_operation(int a, int b);"));
+}
+
+#[test]
+fn test_no_default_void_param_lists_empty_parameters_bare() {
+    let lib = common::get_test_lib_path();
+    let lib = lib.to_str().unwrap();
+
+    assert!(run(&[lib, "-q"]).contains("int return_int(void);\n"));
+    let listing = run(&[lib, "-q", "--no-default-void-param"]);
+    assert!(listing.contains("int return_int();\n"), "{}", listing);
+    assert!(!listing.contains("(void)"), "{}", listing);
+}
+
+#[test]
+fn test_diff_of_identical_libraries_is_empty() {
+    let lib = common::get_test_lib_path();
+    let lib = lib.to_str().unwrap();
+
+    assert_eq!(run(&["diff", lib, lib, "-q"]), "");
+}
+
+#[test]
+fn test_types_lists_named_types() {
+    let types = run(&["types", common::get_test_lib_path().to_str().unwrap(), "-q"]);
+
+    assert!(types.contains("typedef Point (8 bytes)"), "{}", types);
+    // only ever used through pointers
+    assert!(types.contains("typedef Person (104 bytes)"), "{}", types);
+    assert!(types.contains("typedef ByteBuffer (24 bytes)"), "{}", types);
+    assert!(!types.contains("1 bytes"), "{}", types);
+}
+
+#[test]
+fn test_header_compiles() {
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    if Command::new(&cc).arg("--version").output().is_err() {
+        eprintln!("skipping: {} not found", cc);
+        return;
+    }
+
+    let header = run(&[
+        "header",
+        common::get_test_lib_path().to_str().unwrap(),
+        "-q",
+    ]);
+    assert!(header.contains("Point add_points(Point p1, Point p2);"));
+    // defined in testlib.c, so only declared for `InternalState*`
+    assert!(header.contains("struct InternalState;\n"), "{}", header);
+    assert!(!header.contains("struct InternalState {"));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("testlib.h");
+    std::fs::write(&path, &header).unwrap();
+
+    let output = Command::new(&cc)
+        .args(["-fsyntax-only", "-x", "c"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "generated header does not compile: {}\n{}",
+        String::from_utf8_lossy(&output.stderr),
+        header
+    );
+}
+
+#[test]
+fn test_header_keeps_bitfield_widths() {
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    if Command::new(&cc).arg("--version").output().is_err() {
+        eprintln!("skipping: {} not found", cc);
+        return;
+    }
+
+    let header = run(&[
+        "header",
+        common::get_test_lib_path().to_str().unwrap(),
+        "-q",
+    ]);
+    assert!(header.contains("unsigned int mode : 3;"), "{}", header);
+
+    // laid out as in testlib.h only if the widths survive
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("testlib.h"), &header).unwrap();
+    let check = dir.path().join("check.c");
+    std::fs::write(
+        &check,
+        "#include \"testlib.h\"\n\
+         _Static_assert(sizeof(StatusFlags) == 12, \"StatusFlags layout\");\n",
+    )
+    .unwrap();
+
+    let output = Command::new(&cc)
+        .arg("-fsyntax-only")
+        .arg(&check)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_json_output_matches_schema() {
+    let schema: serde_json::Value =
+        serde_json::from_str(&run(&["json", "--print-schema"])).expect("schema is not json");
+    let validator = jsonschema::validator_for(&schema).expect("invalid schema");
+
+    let lib = common::get_test_lib_path();
+    let output: serde_json::Value =
+        serde_json::from_str(&run(&["json", lib.to_str().unwrap(), "--all", "-q"]))
+            .expect("output is not json");
+
+    let errors: Vec<String> = validator
+        .iter_errors(&output)
+        .map(|e| format!("{} at {}", e, e.instance_path))
+        .collect();
+    assert!(
+        errors.is_empty(),
+        "schema violations:\n{}",
+        errors.join("\n")
+    );
+}
+
+#[test]
+fn test_ffi_spec_matches_schema() {
+    let schema: serde_json::Value =
+        serde_json::from_str(include_str!("../schema/ffi-spec.schema.json"))
+            .expect("schema is not json");
+    let validator = jsonschema::validator_for(&schema).expect("invalid schema");
+
+    let lib = common::get_test_lib_path();
+    let output: serde_json::Value =
+        serde_json::from_str(&run(&[lib.to_str().unwrap(), "--ffi-spec", "--all", "-q"]))
+            .expect("output is not json");
+
+    let errors: Vec<String> = validator
+        .iter_errors(&output)
+        .map(|e| format!("{} at {}", e, e.instance_path))
+        .collect();
+    assert!(
+        errors.is_empty(),
+        "schema violations:\n{}",
+        errors.join("\n")
+    );
+
+    let function = |name: &str| {
+        output["functions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|f| f["name"] == name)
+            .unwrap_or_else(|| panic!("{} not found", name))
+            .clone()
+    };
+    let create_person = function("create_person");
+    assert_eq!(create_person["parameters"][0]["passing"], "string");
+    assert_eq!(create_person["returns"]["passing"], "pointer");
+    let create_point = function("create_point");
+    assert_eq!(create_point["returns"]["passing"], "by_value_struct");
+    assert_eq!(output["types"]["Point"]["kind"], "struct");
+    assert_eq!(output["types"]["Point"]["is_small_float_aggregate"], false);
+    assert_eq!(
+        output["types"]["Rectangle"]["is_small_float_aggregate"],
+        true
+    );
+    let apply_operation = function("apply_operation");
+    assert_eq!(apply_operation["parameters"][2]["passing"], "callback");
+    let allocate_matrix = function("allocate_matrix");
+    assert_eq!(allocate_matrix["parameters"][0]["passing"], "pointer");
+}
+
+#[test]
+fn test_rust_bindings_compile() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    if Command::new(&rustc).arg("--version").output().is_err() {
+        eprintln!("skipping: {} not found", rustc);
+        return;
+    }
+
+    let bindings = run(&[
+        common::get_test_lib_path().to_str().unwrap(),
+        "--rust",
+        "--functions",
+        "-q",
+    ]);
+    assert!(bindings.contains("pub unsafe fn as_int(&self) -> core::ffi::c_int"));
+    assert!(bindings.contains("pub unsafe fn as_float(&self) -> core::ffi::c_float"));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("testlib.rs");
+    std::fs::write(&path, &bindings).unwrap();
+
+    let output = Command::new(&rustc)
+        .args(["--edition=2021", "--crate-type=lib", "--emit=metadata"])
+        .arg("--out-dir")
+        .arg(dir.path())
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "generated bindings do not compile: {}\n{}",
+        String::from_utf8_lossy(&output.stderr),
+        bindings
+    );
+}
+
+#[test]
+fn test_nim_bindings_check() {
+    let bindings = run(&[
+        common::get_test_lib_path().to_str().unwrap(),
+        "--nim",
+        "--functions",
+        "-q",
+    ]);
+    assert!(bindings.contains("  Point* {.bycopy.} = object\n    x*: cint\n    y*: cint\n"));
+    assert!(bindings.contains("  DataUnion* {.bycopy, union.} = object\n"));
+    assert!(bindings.contains(
+        "proc add_points*(p1: Point, p2: Point): Point {.importc: \"add_points\", cdecl, dynlib: libraryPath.}"
+    ));
+
+    let nim = std::env::var("NIM").unwrap_or_else(|_| "nim".to_string());
+    if Command::new(&nim).arg("--version").output().is_err() {
+        eprintln!("skipping nim check: {} not found", nim);
+        return;
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("testlib.nim");
+    std::fs::write(&path, &bindings).unwrap();
+
+    let output = Command::new(&nim)
+        .args(["check", "--hints:off"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "generated bindings do not check: {}\n{}",
+        String::from_utf8_lossy(&output.stderr),
+        bindings
+    );
+}
+
+#[test]
+fn test_java_bindings_compile() {
+    let bindings = run(&[
+        common::get_test_lib_path().to_str().unwrap(),
+        "--java",
+        "--functions",
+        "-q",
+    ]);
+    assert!(bindings.contains(
+        "            JAVA_BYTE.withName(\"flags\"),\n            MemoryLayout.paddingLayout(3),\n"
+    ));
+    assert!(bindings.contains(
+        "    public static final MethodHandle add_points = LINKER.downcallHandle(\n        \
+         LIBRARY.find(\"add_points\").orElseThrow(),\n        \
+         FunctionDescriptor.of(Point.LAYOUT, Point.LAYOUT, Point.LAYOUT));\n"
+    ));
+
+    // java.lang.foreign is final from Java 22
+    let javac = std::env::var("JAVAC").unwrap_or_else(|_| "javac".to_string());
+    let supported = Command::new(&javac)
+        .args(["--release", "22", "-version"])
+        .output()
+        .is_ok_and(|output| output.status.success());
+    if !supported {
+        eprintln!("skipping java check: {} does not support Java 22", javac);
+        return;
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("NativeLibrary.java");
+    std::fs::write(&path, &bindings).unwrap();
+
+    let output = Command::new(&javac)
+        .args(["--release", "22", "-d"])
+        .arg(dir.path())
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "generated bindings do not compile: {}\n{}",
+        String::from_utf8_lossy(&output.stderr),
+        bindings
+    );
+}
+
+#[test]
+fn test_wasm_bindings_parse() {
+    let bindings = run(&[
+        common::get_test_lib_path().to_str().unwrap(),
+        "--wasm",
+        "--functions",
+        "-q",
+    ]);
+    assert!(bindings.contains("export function bind(instance)"));
+    assert!(bindings.contains("width: { offset: 0, size: 4, type: 'f32' }"));
+
+    if Command::new("node").arg("--version").output().is_err() {
+        eprintln!("skipping syntax check: node not found");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("testlib.mjs");
+    std::fs::write(&path, &bindings).unwrap();
+
+    let output = Command::new("node")
+        .arg("--check")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "generated bindings do not parse: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_deno_bindings_check() {
+    let bindings = run(&[
+        common::get_test_lib_path().to_str().unwrap(),
+        "--deno",
+        "--functions",
+        "-q",
+    ]);
+    assert!(bindings.contains("export const Point = { struct: [\"i32\", \"i32\"] } as const;\n"));
+    assert!(bindings.contains("  add_points: { parameters: [Point, Point], result: Point },\n"));
+    assert!(bindings.contains(
+        "  apply_operation: { parameters: [\"i32\", \"i32\", \"function\"], result: \"i32\" },\n"
+    ));
+    assert!(bindings.contains("  // sum_varargs: variadic functions are not supported"));
+    assert!(bindings.contains("export const lib = Deno.dlopen(LIBRARY_PATH, symbols);\n"));
+
+    if Command::new("deno").arg("--version").output().is_err() {
+        eprintln!("skipping type check: deno not found");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("testlib.ts");
+    std::fs::write(&path, &bindings).unwrap();
+
+    let output = Command::new("deno")
+        .arg("check")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "generated bindings do not type check: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_summary_counts_internal_functions() {
+    let lib = common::get_test_lib_path();
+    let summary = run(&[lib.to_str().unwrap(), "--summary", "--strict", "-q"]);
+    let functions = summary.lines().next().unwrap();
+    assert!(
+        functions.starts_with("functions: ") && !functions.contains(" 0 internal"),
+        "{}",
+        summary
+    );
+    assert!(summary.contains("\n  struct: "));
+    assert!(summary.ends_with("unresolved types: 0\n"));
+}
+
+#[test]
+fn test_validate_accepts_test_library() {
+    let lib = common::get_test_lib_path();
+    let output = run(&[lib.to_str().unwrap(), "--summary", "--validate", "-q"]);
+    assert!(output.starts_with("functions: "), "{}", output);
+}
+
+#[test]
+fn test_fail_on_skipped_lists_unreadable_units() {
+    let dir = tempfile::tempdir().unwrap();
+    let debug_info = dir.path().join("debug_info");
+    let library = dir.path().join("libbroken.so");
+    let status = Command::new("objcopy")
+        .arg("--dump-section")
+        .arg(format!(".debug_info={}", debug_info.display()))
+        .arg(common::get_test_lib_path())
+        .arg(&library)
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("skipping: objcopy unavailable or failed");
+        return;
+    }
+
+    // the 2-byte version follows the 4-byte unit length
+    let mut section = std::fs::read(&debug_info).unwrap();
+    section[4..6].copy_from_slice(&99u16.to_le_bytes());
+    std::fs::write(&debug_info, &section).unwrap();
+    let status = Command::new("objcopy")
+        .arg("--update-section")
+        .arg(format!(".debug_info={}", debug_info.display()))
+        .arg(&library)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // skipped with a warning by default
+    let library = library.to_str().unwrap();
+    run(&[library, "-q"]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dwarffi-js"))
+        .args([library, "-q", "--fail-on-skipped"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("skipped unit header: "), "{}", stderr);
+}
+
+#[test]
+fn test_fail_on_unresolved_lists_functions_with_unresolved_types() {
+    let lib = common::get_test_lib_path();
+    let lib = lib.to_str().unwrap();
+    assert_eq!(run(&[lib, "-q", "--fail-on-unresolved"]), run(&[lib, "-q"]));
+
+    // dwarffi has no model for C++ pointers to members
+    let cxx = std::env::var("CXX").unwrap_or_else(|_| "c++".to_string());
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("member.cpp");
+    std::fs::write(
+        &source,
+        "struct S { int a; };\n\
+         extern \"C\" int member_value(S* s, int S::* m) { return s->*m; }\n\
+         extern \"C\" int plain_value(S* s) { return s->a; }\n",
+    )
+    .unwrap();
+    let member_lib = dir.path().join("libmember.so");
+    let status = Command::new(&cxx)
+        .args(["-g", "-O0", "-shared", "-fPIC", "-o"])
+        .arg(&member_lib)
+        .arg(&source)
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("skipping: {} unavailable or failed", cxx);
+        return;
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dwarffi-js"))
+        .arg(&member_lib)
+        .args(["-q", "--js", "--functions", "--fail-on-unresolved"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty(), "bindings were still printed");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("member_value: <pointer-to-member> could not be resolved"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("plain_value"), "{}", stderr);
+    assert!(
+        stderr.contains("1 functions use unresolved types"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_unknown_backend_is_a_usage_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dwarffi-js"))
+        .args([
+            common::get_test_lib_path().to_str().unwrap(),
+            "--js",
+            "--backend",
+            "ref-napi",
+        ])
+        .output()
+        .expect("Failed to run dwarffi-js");
+
+    // clap exits with 2 for usage errors; a panic would be 101
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unknown FFI backend 'ref-napi' (available: koffi)"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_reserved_namespace_is_a_usage_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dwarffi-js"))
+        .args([
+            common::get_test_lib_path().to_str().unwrap(),
+            "--js",
+            "--namespace",
+            "class",
+        ])
+        .output()
+        .expect("Failed to run dwarffi-js");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("'class' is a reserved word in JavaScript"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_demunge_anonymous_names_nested_aggregates() {
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("person.c");
+    std::fs::write(
+        &source,
+        "struct Person { int kind; union { int id; float score; } value; };\n\
+         int person_kind(struct Person p) { return p.kind; }\n",
+    )
+    .unwrap();
+    let lib = dir.path().join("libperson.so");
+    let status = Command::new(&cc)
+        .args(["-g", "-O0", "-shared", "-fPIC", "-o"])
+        .arg(&lib)
+        .arg(&source)
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("skipping: {} unavailable or failed", cc);
+        return;
+    }
+    let lib = lib.to_str().unwrap();
+
+    // without a name the union can't be declared
+    let output = Command::new(env!("CARGO_BIN_EXE_dwarffi-js"))
+        .args([lib, "--rust", "--functions", "-q"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let args = [lib, "--rust", "--functions", "--demunge-anonymous", "-q"];
+    let bindings = run(&args);
+    assert!(
+        bindings.contains("pub struct Person_anon_union_0 {"),
+        "{}",
+        bindings
+    );
+    assert!(
+        bindings.contains("pub value: Person_anon_union_0,"),
+        "{}",
+        bindings
+    );
+    assert_eq!(bindings, run(&args));
+}
@@ -0,0 +1,223 @@
+/// checks on the generated Koffi bindings for the test_c library that don't
+/// need node to run them
+mod common;
+
+use std::process::Command;
+
+fn generate_js_bindings() -> String {
+    generate_js_bindings_with(&[])
+}
+
+fn generate_js_bindings_with(extra_args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_dwarffi-js"))
+        .arg(common::get_test_lib_path())
+        .args(["--js", "--functions", "-q"])
+        .args(extra_args)
+        .output()
+        .expect("Failed to run dwarffi-js");
+
+    assert!(
+        output.status.success(),
+        "dwarffi-js failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("bindings are not utf-8")
+}
+
+#[test]
+fn test_fixed_width_typedefs_use_exact_koffi_types() {
+    let bindings = generate_js_bindings();
+
+    assert!(
+        bindings.contains("lib.func('int64_t process_long(int64_t value)')"),
+        "process_long should keep int64_t:\n{}",
+        bindings
+    );
+    assert!(bindings.contains("lib.func('uint8_t process_byte(uint8_t value)')"));
+    assert!(bindings.contains("size_t length)"));
+}
+
+#[test]
+fn test_callback_typedefs_become_exported_protos() {
+    let bindings = generate_js_bindings();
+
+    assert!(
+        bindings
+            .contains("const Comparator = koffi.proto('int Comparator(const void*, const void*)')")
+    );
+    assert!(bindings.contains("const Callback = koffi.proto('void Callback(int, void*)')"));
+    assert!(
+        bindings.contains("lib.func('void sort_array(int* arr, size_t count, Comparator* cmp)')")
+    );
+    assert!(bindings.contains("  callbacks: {\n    Callback,\n    Comparator,\n  },\n"));
+    assert!(bindings.contains("  registerCallback,\n"));
+}
+
+#[test]
+fn test_define_constants_are_emitted() {
+    let bindings = generate_js_bindings();
+
+    assert!(bindings.contains("const TESTLIB_MAX_POINTS = 1024\n"));
+    assert!(bindings.contains("const TESTLIB_INVALID = -1\n"));
+    assert!(bindings.contains("const TESTLIB_VERSION = \"1.2.0\"\n"));
+    assert!(!bindings.contains("TESTLIB_SQUARE"));
+    assert!(bindings.contains("  constants: {\n    TESTLIB_MAX_POINTS,\n"));
+}
+
+#[test]
+fn test_anonymous_enum_members_are_constants() {
+    let bindings = generate_js_bindings();
+
+    assert!(bindings.contains(
+        "// Constants (anonymous enums)\n\
+         const TESTLIB_STATE_INITIAL_COUNT = 0\n\
+         const TESTLIB_STATE_BUFFER_SIZE = 256\n"
+    ));
+    assert!(bindings.contains("    TESTLIB_STATE_BUFFER_SIZE,\n  },\n"));
+}
+
+#[test]
+fn test_bindings_are_identical_across_runs() {
+    // hash iteration order differs per process, so a few runs catch it
+    let first = generate_js_bindings();
+    for _ in 0..3 {
+        assert_eq!(generate_js_bindings(), first);
+    }
+}
+
+#[test]
+fn test_module_formats_import_and_export() {
+    let cjs = generate_js_bindings_with(&["--module-format", "cjs"]);
+    assert_eq!(cjs, generate_js_bindings(), "cjs is the default");
+    assert!(cjs.contains("const koffi = require('koffi')\n"));
+    assert!(cjs.contains("module.exports = {\n  types: {\n"));
+    assert!(!cjs.contains("export "));
+
+    let esm = generate_js_bindings_with(&["--module-format", "esm"]);
+    assert!(esm.contains("import koffi from 'koffi'\n"));
+    assert!(esm.contains("export const types = {\n"));
+    assert!(esm.contains("export const callbacks = {\n"));
+    assert!(esm.contains("export {\n  registerCallback,\n"));
+    assert!(esm.contains("export default {\n  types,\n"));
+    assert!(!esm.contains("require("));
+    assert!(!esm.contains("module.exports"));
+
+    if Command::new("node").arg("--version").output().is_err() {
+        eprintln!("skipping syntax check: node not found");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    for (file, bindings) in [("bindings.cjs", &cjs), ("bindings.mjs", &esm)] {
+        let path = dir.path().join(file);
+        std::fs::write(&path, bindings).unwrap();
+        let output = Command::new("node")
+            .arg("--check")
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{} does not parse: {}",
+            file,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+#[test]
+fn test_strip_prefix_renames_bindings_only() {
+    let bindings = generate_js_bindings_with(&["--strip-prefix", "process_"]);
+
+    assert!(bindings.contains("const int32 = lib.func('int32_t process_int32(int32_t value)')\n"));
+    assert!(bindings.contains("  int32,\n"));
+    assert!(!bindings.contains("  process_int32,\n"));
+    // `2d_array` isn't an identifier
+    assert!(bindings.contains("const process_2d_array = lib.func("));
+    assert!(bindings.contains("const add_two_ints = lib.func("));
+}
+
+#[test]
+fn test_type_map_overrides_primitive_mappings() {
+    let dir = tempfile::tempdir().unwrap();
+    let type_map = dir.path().join("types.json");
+    std::fs::write(&type_map, r#"{"long double": "longdouble_t"}"#).unwrap();
+
+    let bindings = generate_js_bindings_with(&["--type-map", type_map.to_str().unwrap()]);
+
+    assert!(
+        bindings.contains("lib.func('longdouble_t get_long_double(void)')"),
+        "long double should use the override:\n{}",
+        bindings
+    );
+    // primitives without an override keep the defaults
+    assert!(bindings.contains("lib.func('int64_t process_long(int64_t value)')"));
+}
+
+#[test]
+fn test_opaque_handles_are_named_koffi_opaques() {
+    let bindings = generate_js_bindings();
+
+    assert!(
+        bindings.contains("const InternalState = koffi.opaque('InternalState')\n"),
+        "InternalState is only used through pointers:\n{}",
+        bindings
+    );
+    assert!(bindings.contains("lib.func('InternalState* init_state(void)')"));
+}
+
+#[test]
+fn test_namespace_wraps_all_exports() {
+    let cjs = generate_js_bindings_with(&["--namespace", "TestLib"]);
+    assert!(cjs.contains("module.exports = {\n  TestLib: {\n    types: {\n"));
+    assert!(cjs.contains("    add_two_ints,\n"));
+
+    let esm = generate_js_bindings_with(&["--namespace", "TestLib", "--module-format", "esm"]);
+    assert!(esm.contains("export const TestLib = {\n  types: {\n"));
+    assert!(esm.trim_end().ends_with("}\nexport default TestLib"));
+    // nothing else is exported
+    assert_eq!(esm.matches("export ").count(), 2, "{}", esm);
+}
+
+#[test]
+fn test_unsigned_enum_values_are_not_negative() {
+    let bindings = generate_js_bindings();
+
+    assert!(
+        bindings.contains("  PERMISSION_ALL: 4294967295,\n"),
+        "{}",
+        bindings
+    );
+    assert!(!bindings.contains("PERMISSION_ALL: -1"));
+}
+
+#[test]
+fn test_runtime_load_binds_functions_in_init() {
+    let bindings = generate_js_bindings_with(&["--runtime-load"]);
+
+    assert!(!bindings.contains("LIBRARY_PATH"));
+    assert!(
+        bindings.contains("function init(libraryPath) {\n  const lib = koffi.load(libraryPath)\n")
+    );
+    assert!(bindings.contains("  const add_two_ints = lib.func("));
+    assert!(bindings.contains("  return {\n    add_operation,\n"));
+    // functions are only exported through init
+    assert!(bindings.trim_end().ends_with("  init,\n}"), "{}", bindings);
+
+    if Command::new("node").arg("--version").output().is_err() {
+        eprintln!("skipping syntax check: node not found");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bindings.cjs");
+    std::fs::write(&path, &bindings).unwrap();
+    let output = Command::new("node")
+        .arg("--check")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "bindings do not parse: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,192 @@
+//! resolving the parameter types of a large synthetic unit, the way
+//! `DwarfAnalyzer` does for each function.
+//!
+//! `cargo bench -p dwarffi --bench type_resolution`
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use dwarffi::TypeResolver;
+use gimli::write::{AttributeValue, DwarfUnit, EndianVec, Sections, UnitEntryId};
+use gimli::{Dwarf, EndianSlice, LittleEndian};
+
+/// a unit of `count` structs, each holding the one before it by value, by
+/// pointer and by pointer to const, and a function per struct taking it
+/// through several pointer chains of its own, as headers tend to
+fn synthetic_sections(count: usize) -> Sections<EndianVec<LittleEndian>> {
+    let mut dwarf = DwarfUnit::new(gimli::Encoding {
+        format: gimli::Format::Dwarf32,
+        version: 4,
+        address_size: 8,
+    });
+    let root = dwarf.unit.root();
+
+    let int = add_named(&mut dwarf, root, gimli::DW_TAG_base_type, "int");
+    dwarf
+        .unit
+        .get_mut(int)
+        .set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+    let char_ = add_named(&mut dwarf, root, gimli::DW_TAG_base_type, "char");
+    dwarf
+        .unit
+        .get_mut(char_)
+        .set(gimli::DW_AT_byte_size, AttributeValue::Udata(1));
+
+    let name_array = dwarf.unit.add(root, gimli::DW_TAG_array_type);
+    dwarf
+        .unit
+        .get_mut(name_array)
+        .set(gimli::DW_AT_type, AttributeValue::UnitRef(char_));
+    let bound = dwarf.unit.add(name_array, gimli::DW_TAG_subrange_type);
+    dwarf
+        .unit
+        .get_mut(bound)
+        .set(gimli::DW_AT_count, AttributeValue::Udata(16));
+
+    let mut previous: Option<(UnitEntryId, u64)> = None;
+    for i in 0..count {
+        let record = add_named(
+            &mut dwarf,
+            root,
+            gimli::DW_TAG_structure_type,
+            &format!("Record{}", i),
+        );
+        let mut members = vec![("id", int, 4), ("name", name_array, 16)];
+        if let Some((previous, previous_size)) = previous {
+            let pointer = pointer_to(&mut dwarf, root, previous);
+            let const_previous = dwarf.unit.add(root, gimli::DW_TAG_const_type);
+            dwarf
+                .unit
+                .get_mut(const_previous)
+                .set(gimli::DW_AT_type, AttributeValue::UnitRef(previous));
+            let const_pointer = pointer_to(&mut dwarf, root, const_previous);
+            members.push(("parent", previous, previous_size));
+            members.push(("prev", pointer, 8));
+            members.push(("first", const_pointer, 8));
+        }
+
+        let mut size = 0;
+        for (name, type_, member_size) in members {
+            let member = add_named(&mut dwarf, record, gimli::DW_TAG_member, name);
+            let entry = dwarf.unit.get_mut(member);
+            entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(type_));
+            entry.set(
+                gimli::DW_AT_data_member_location,
+                AttributeValue::Udata(size),
+            );
+            size += member_size;
+        }
+        dwarf
+            .unit
+            .get_mut(record)
+            .set(gimli::DW_AT_byte_size, AttributeValue::Udata(size));
+
+        let typedef = add_named(
+            &mut dwarf,
+            root,
+            gimli::DW_TAG_typedef,
+            &format!("record{}_t", i),
+        );
+        dwarf
+            .unit
+            .get_mut(typedef)
+            .set(gimli::DW_AT_type, AttributeValue::UnitRef(record));
+
+        // `void record_i(record_t*, const record_t*, struct Record**, ...)`
+        let const_record = dwarf.unit.add(root, gimli::DW_TAG_const_type);
+        dwarf
+            .unit
+            .get_mut(const_record)
+            .set(gimli::DW_AT_type, AttributeValue::UnitRef(record));
+        let volatile_record = dwarf.unit.add(root, gimli::DW_TAG_volatile_type);
+        dwarf
+            .unit
+            .get_mut(volatile_record)
+            .set(gimli::DW_AT_type, AttributeValue::UnitRef(record));
+        let pointer = pointer_to(&mut dwarf, root, record);
+        let parameters = [
+            pointer_to(&mut dwarf, root, typedef),
+            pointer_to(&mut dwarf, root, const_record),
+            pointer_to(&mut dwarf, root, volatile_record),
+            pointer_to(&mut dwarf, root, pointer),
+            pointer,
+        ];
+        let function = add_named(
+            &mut dwarf,
+            root,
+            gimli::DW_TAG_subprogram,
+            &format!("record{}", i),
+        );
+        for parameter in parameters {
+            let entry = dwarf.unit.add(function, gimli::DW_TAG_formal_parameter);
+            dwarf
+                .unit
+                .get_mut(entry)
+                .set(gimli::DW_AT_type, AttributeValue::UnitRef(parameter));
+        }
+
+        previous = Some((record, size));
+    }
+
+    let mut sections = Sections::new(EndianVec::new(LittleEndian));
+    dwarf.write(&mut sections).unwrap();
+    sections
+}
+
+fn add_named(
+    dwarf: &mut DwarfUnit,
+    parent: UnitEntryId,
+    tag: gimli::DwTag,
+    name: &str,
+) -> UnitEntryId {
+    let id = dwarf.unit.add(parent, tag);
+    dwarf.unit.get_mut(id).set(
+        gimli::DW_AT_name,
+        AttributeValue::String(name.as_bytes().to_vec()),
+    );
+    id
+}
+
+fn pointer_to(dwarf: &mut DwarfUnit, parent: UnitEntryId, pointee: UnitEntryId) -> UnitEntryId {
+    let pointer = dwarf.unit.add(parent, gimli::DW_TAG_pointer_type);
+    let entry = dwarf.unit.get_mut(pointer);
+    entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(pointee));
+    entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(8));
+    pointer
+}
+
+/// resolve every parameter type in the unit with a fresh resolver,
+/// returning how many types were registered
+fn resolve_parameters(sections: &Sections<EndianVec<LittleEndian>>) -> usize {
+    let dwarf = Dwarf::load(|id| -> gimli::Result<_> {
+        let data = sections.get(id).map_or(&[][..], |section| section.slice());
+        Ok(EndianSlice::new(data, LittleEndian))
+    })
+    .unwrap();
+    let header = dwarf.units().next().unwrap().unwrap();
+    let unit = dwarf.unit(header).unwrap();
+
+    let mut resolver = TypeResolver::new(&dwarf, &unit);
+    let mut entries = unit.entries();
+    while let Some((_, entry)) = entries.next_dfs().unwrap() {
+        if entry.tag() == gimli::DW_TAG_formal_parameter
+            && let Some(value) = entry.attr_value(gimli::DW_AT_type).unwrap()
+        {
+            resolver.build_type_registry_entry_for(value).unwrap();
+        }
+    }
+    resolver.into_registry().len()
+}
+
+fn bench_type_resolution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resolve_parameters");
+    for count in [100, 1000] {
+        let sections = synthetic_sections(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &sections, |b, s| {
+            b.iter(|| resolve_parameters(s))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_type_resolution);
+criterion_main!(benches);
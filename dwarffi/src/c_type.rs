@@ -0,0 +1,312 @@
+//! a parser for simple C type spellings (`const char*`, `char const *`,
+//! `unsigned long`, `struct Point* const*`), to compare against extracted
+//! types without depending on how `to_c_string` spaces and orders them
+
+use crate::prelude::*;
+use crate::type_registry::Qualifiers;
+
+/// words that make up a builtin type's specifier, in any order
+const BUILTIN_WORDS: &[&str] = &[
+    "void", "char", "short", "int", "long", "float", "double", "signed", "unsigned", "_Bool",
+    "bool", "_Complex",
+];
+
+/// a parsed C type: its specifier, the qualifiers of the base type, and
+/// those of each pointer level, outermost first
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct CType {
+    pub specifier: Specifier,
+    pub qualifiers: Qualifiers,
+    pub pointers: Vec<Qualifiers>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Specifier {
+    /// a builtin type, spelled canonically by [`builtin_name`]
+    Builtin(String),
+    /// `struct`, `union` or `enum` with its tag
+    Tagged(&'static str, String),
+    /// a typedef name (or an enum, which `to_c_string` spells bare)
+    Name(String),
+}
+
+/// parse a type spelled without a declarator name: a specifier with
+/// qualifiers in any order, then `*`s each followed by its own qualifiers.
+/// None for anything else, including arrays and function types.
+pub(crate) fn parse(spelling: &str) -> Option<CType> {
+    let mut tokens = Vec::new();
+    for word in spelling.split_whitespace() {
+        for (i, part) in word.split('*').enumerate() {
+            if i > 0 {
+                tokens.push("*");
+            }
+            if !part.is_empty() {
+                tokens.push(part);
+            }
+        }
+    }
+
+    let mut qualifiers = Qualifiers::default();
+    let mut words = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(&token) = tokens.peek() {
+        if token == "*" {
+            break;
+        }
+        tokens.next();
+        if !qualify(&mut qualifiers, token) {
+            words.push(token);
+        }
+    }
+
+    // innermost first, as written
+    let mut pointers = Vec::new();
+    for token in tokens {
+        if token == "*" {
+            pointers.push(Qualifiers::default());
+        } else if !qualify(pointers.last_mut()?, token) {
+            return None;
+        }
+    }
+    pointers.reverse();
+
+    let specifier = match words.as_slice() {
+        [keyword, tag] if is_tag_keyword(keyword) && is_identifier(tag) => {
+            let keyword = match *keyword {
+                "struct" => "struct",
+                "union" => "union",
+                _ => "enum",
+            };
+            Specifier::Tagged(keyword, tag.to_string())
+        }
+        [name] if !BUILTIN_WORDS.contains(name) && !is_tag_keyword(name) => {
+            is_identifier(name).then(|| Specifier::Name(name.to_string()))?
+        }
+        words => Specifier::Builtin(builtin_name(words)?),
+    };
+
+    Some(CType {
+        specifier,
+        qualifiers,
+        pointers,
+    })
+}
+
+/// apply `token` to `qualifiers` if it is one. `restrict` is accepted and
+/// dropped, since extracted types don't keep it.
+fn qualify(qualifiers: &mut Qualifiers, token: &str) -> bool {
+    match token {
+        "const" => qualifiers.is_const = true,
+        "volatile" => qualifiers.is_volatile = true,
+        "restrict" | "__restrict" => {}
+        _ => return false,
+    }
+    true
+}
+
+fn is_tag_keyword(word: &str) -> bool {
+    matches!(word, "struct" | "union" | "enum")
+}
+
+fn is_identifier(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// one spelling for each builtin type, however its words are ordered or
+/// abbreviated: `unsigned long`, `long unsigned int` and `unsigned long int`
+/// are all `int long unsigned`. None if `words` aren't all builtin words.
+pub(crate) fn builtin_name(words: &[&str]) -> Option<String> {
+    if words.is_empty() || !words.iter().all(|word| BUILTIN_WORDS.contains(word)) {
+        return None;
+    }
+
+    let mut words: Vec<&str> = words
+        .iter()
+        .map(|&word| if word == "bool" { "_Bool" } else { word })
+        .collect();
+    let has = |words: &[&str], word| words.contains(&word);
+
+    // `signed` only changes plain `char`
+    if !has(&words, "char") {
+        words.retain(|&word| word != "signed");
+    }
+    // `short`, `long` and `unsigned` alone imply `int`
+    let implies_int = ["short", "long", "unsigned"]
+        .iter()
+        .any(|word| has(&words, word));
+    let has_other = ["int", "char", "double"]
+        .iter()
+        .any(|word| has(&words, word));
+    if implies_int && !has_other {
+        words.push("int");
+    }
+    if words.is_empty() {
+        // a lone `signed`
+        words.push("int");
+    }
+
+    words.sort_unstable();
+    Some(words.join(" "))
+}
+
+/// the usual C spelling of an integer type however the compiler spelled it:
+/// gcc's `long unsigned int` and clang's `unsigned long` are both `unsigned
+/// long`. `is_unsigned`, from the base type's `DW_AT_encoding`, decides the
+/// sign over the words, except for plain `char` which is its own type. None
+/// for anything that isn't a builtin integer type.
+pub(crate) fn canonical_integer_name(name: &str, is_unsigned: Option<bool>) -> Option<String> {
+    let words: Vec<&str> = name.split_whitespace().collect();
+    let count = |word| words.iter().filter(|&&w| w == word).count();
+    let is_integer_word = |word: &&str| {
+        matches!(
+            *word,
+            "signed" | "unsigned" | "char" | "short" | "int" | "long" | "__int128"
+        )
+    };
+    if words.is_empty() || !words.iter().all(is_integer_word) {
+        return None;
+    }
+    if count("signed") + count("unsigned") > 1 || count("int") > 1 {
+        return None;
+    }
+
+    let rank = match (
+        count("char"),
+        count("short"),
+        count("long"),
+        count("__int128"),
+    ) {
+        (1, 0, 0, 0) if count("int") == 0 => "char",
+        (0, 1, 0, 0) => "short",
+        (0, 0, 0, 0) => "int",
+        (0, 0, 1, 0) => "long",
+        (0, 0, 2, 0) => "long long",
+        (0, 0, 0, 1) if count("int") == 0 => "__int128",
+        _ => return None,
+    };
+    if rank == "char" {
+        return Some(match (count("signed"), count("unsigned")) {
+            (1, _) => "signed char".to_string(),
+            (_, 1) => "unsigned char".to_string(),
+            _ => "char".to_string(),
+        });
+    }
+
+    let is_unsigned = is_unsigned.unwrap_or(count("unsigned") == 1);
+    Some(if is_unsigned {
+        format!("unsigned {}", rank)
+    } else {
+        rank.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONST: Qualifiers = Qualifiers {
+        is_const: true,
+        is_volatile: false,
+    };
+
+    #[test]
+    fn test_qualifier_order_and_spacing() {
+        let expected = parse("const char*").unwrap();
+        assert_eq!(expected.specifier, Specifier::Builtin("char".to_string()));
+        assert_eq!(expected.qualifiers, CONST);
+        assert_eq!(expected.pointers, [Qualifiers::default()]);
+        for spelling in ["char const *", "const char *", "  const   char*"] {
+            assert_eq!(parse(spelling).as_ref(), Some(&expected), "{}", spelling);
+        }
+    }
+
+    #[test]
+    fn test_pointer_qualifiers_outermost_first() {
+        // a const pointer to a pointer to volatile struct Point
+        let parsed = parse("volatile struct Point** const").unwrap();
+        assert_eq!(
+            parsed.specifier,
+            Specifier::Tagged("struct", "Point".to_string())
+        );
+        assert!(parsed.qualifiers.is_volatile);
+        assert_eq!(parsed.pointers, [CONST, Qualifiers::default()]);
+    }
+
+    #[test]
+    fn test_builtin_spellings() {
+        assert_eq!(
+            builtin_name(&["unsigned", "long"]),
+            builtin_name(&["long", "unsigned", "int"])
+        );
+        assert_eq!(builtin_name(&["signed", "int"]), builtin_name(&["int"]));
+        assert_ne!(builtin_name(&["signed", "char"]), builtin_name(&["char"]));
+        assert_eq!(builtin_name(&["bool"]), builtin_name(&["_Bool"]));
+        assert_eq!(builtin_name(&["Point"]), None);
+    }
+
+    #[test]
+    fn test_canonical_integer_names() {
+        // gcc spelling, clang spelling, canonical
+        for (gcc, clang, canonical) in [
+            ("long unsigned int", "unsigned long", "unsigned long"),
+            ("long long int", "long long", "long long"),
+            (
+                "long long unsigned int",
+                "unsigned long long",
+                "unsigned long long",
+            ),
+            ("short unsigned int", "unsigned short", "unsigned short"),
+            ("long int", "long", "long"),
+            (
+                "__int128 unsigned",
+                "unsigned __int128",
+                "unsigned __int128",
+            ),
+        ] {
+            assert_eq!(
+                canonical_integer_name(gcc, None).as_deref(),
+                Some(canonical)
+            );
+            assert_eq!(
+                canonical_integer_name(clang, None).as_deref(),
+                Some(canonical)
+            );
+        }
+
+        // the encoding wins, but plain char keeps its own name
+        assert_eq!(
+            canonical_integer_name("int", Some(true)).as_deref(),
+            Some("unsigned int")
+        );
+        assert_eq!(
+            canonical_integer_name("char", Some(true)).as_deref(),
+            Some("char")
+        );
+        assert_eq!(
+            canonical_integer_name("signed char", Some(false)).as_deref(),
+            Some("signed char")
+        );
+
+        for name in ["_Bool", "double", "long double", "wchar_t", "char int", ""] {
+            assert_eq!(canonical_integer_name(name, None), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_rejects_what_it_cannot_parse() {
+        for spelling in [
+            "",
+            "int[4]",
+            "void (*)(int)",
+            "const* int",
+            "struct",
+            "Point Point",
+        ] {
+            assert_eq!(parse(spelling), None, "{}", spelling);
+        }
+    }
+}
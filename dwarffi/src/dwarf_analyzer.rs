@@ -1,79 +1,690 @@
+use crate::collections::{HashMap, HashSet};
+use crate::error::{DwarffiError, Result};
+use crate::lines::{self, LineRow};
+use crate::macros::{self, MacroConstant, MacroSections};
+use crate::pattern::glob_match;
+use crate::prelude::*;
+#[cfg(feature = "profiling")]
+use crate::profiling::Timings;
 use crate::reader;
-use crate::symbol_reader::SymbolReader;
-use crate::type_registry::TypeRegistry;
-use crate::type_resolver::TypeResolver;
-use crate::types::{FunctionSignature, Parameter};
-use anyhow::Result;
+use crate::symbol_reader::{SymbolInfo, SymbolReader};
+use crate::target::{Endianness, TargetInfo, architecture_name};
+use crate::type_registry::{TypeId, TypeRegistry, UnitId};
+use crate::type_resolver::{self, TypeResolver};
+use crate::types::{FunctionSignature, InlineKind, Parameter};
 use gimli::{AttributeValue, Dwarf, Reader};
-use std::collections::HashSet;
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+#[cfg(feature = "profiling")]
+use std::time::Instant;
 
 pub struct DwarfAnalyzer {
-    data: Vec<u8>,
+    input: Input,
+}
+
+/// what an analyzer reads DWARF from
+enum Input {
+    /// an object file, wasm module or static archive
+    Object(Vec<u8>),
+    /// sections handed over without a container, see
+    /// [`DwarfAnalyzer::from_sections`]
+    Sections {
+        sections: HashMap<gimli::SectionId, Vec<u8>>,
+        endianness: Endianness,
+        pointer_width: usize,
+    },
+}
+
+/// reported after each compilation unit is processed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitProgress {
+    /// zero-based index of the unit just processed
+    pub unit_index: usize,
+    pub total_units: usize,
+    pub functions_so_far: usize,
+    pub types_so_far: usize,
+}
+
+/// a compilation unit, or a function in one, that was skipped because its
+/// DWARF couldn't be read. collected with `ExtractOptions::continue_on_error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitError {
+    /// the unit, or None if its header couldn't be read
+    pub unit: Option<UnitId>,
+    /// the function that was skipped, or None if the whole unit (or the rest
+    /// of it) was
+    pub function: Option<String>,
+    pub message: String,
+}
+
+impl UnitError {
+    fn new(
+        unit: Option<gimli::UnitSectionOffset>,
+        function: Option<&str>,
+        message: impl core::fmt::Display,
+    ) -> Self {
+        Self {
+            unit: unit.map(type_resolver::unit_id),
+            function: function.map(str::to_string),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl core::fmt::Display for UnitError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.unit {
+            Some(unit) => write!(f, "unit at {:#x}", unit.offset)?,
+            None => write!(f, "unit header")?,
+        }
+        if let Some(function) = &self.function {
+            write!(f, ", function {}", function)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// which functions to extract, by how widely they are visible
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Visibility {
+    /// in the binary's dynamic symbol table
+    #[default]
+    Exported,
+    /// global linkage (`DW_AT_external`), whether or not a symbol exports
+    /// it, e.g. every non-`static` function of an object file
+    External,
+    /// everything, `static` functions included
+    All,
+}
+
+impl Visibility {
+    pub const ALL: &[Visibility] = &[Visibility::Exported, Visibility::External, Visibility::All];
+
+    /// the name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Visibility::Exported => "exported",
+            Visibility::External => "external",
+            Visibility::All => "all",
+        }
+    }
+}
+
+impl core::fmt::Display for Visibility {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl core::str::FromStr for Visibility {
+    type Err = String;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|visibility| visibility.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let available = Self::ALL.iter().map(|v| v.name()).collect::<Vec<_>>();
+                format!(
+                    "unknown visibility '{}' (available: {})",
+                    s,
+                    available.join(", ")
+                )
+            })
+    }
+}
+
+/// what `DwarfAnalyzer::extract_analysis_with` extracts. defaults to exported
+/// functions only, with nothing excluded.
+///
+/// ```
+/// let options = dwarffi::ExtractOptions::new()
+///     .exported_only(false)
+///     .exclude("internal_*");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    visibility: Visibility,
+    exclude: Vec<String>,
+    continue_on_error: bool,
+    extern_c_only: bool,
+    skip_virtual: bool,
+    normalize_primitive_names: bool,
+}
+
+impl ExtractOptions {
+    pub fn new() -> Self {
+        Self {
+            visibility: Visibility::Exported,
+            exclude: Vec::new(),
+            continue_on_error: false,
+            extern_c_only: false,
+            skip_virtual: false,
+            normalize_primitive_names: false,
+        }
+    }
+
+    /// only keep functions exported from the binary's symbol table, or keep
+    /// everything: `visibility` with `Exported` or `All`
+    pub fn exported_only(self, exported_only: bool) -> Self {
+        self.visibility(if exported_only {
+            Visibility::Exported
+        } else {
+            Visibility::All
+        })
+    }
+
+    /// keep the functions at least this visible
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// drop functions whose names match this glob (e.g. `*_impl`, `__*`)
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// `exclude` for each pattern
+    pub fn exclude_all<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exclude.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// report units and functions that fail to parse in
+    /// `AnalysisResult::errors` instead of only logging them, so callers get
+    /// the partial result along with what was left out
+    pub fn continue_on_error(mut self, continue_on_error: bool) -> Self {
+        self.continue_on_error = continue_on_error;
+        self
+    }
+
+    /// skip functions with C++ linkage: those with mangled names, and
+    /// namespace or class members that aren't `extern "C"`
+    pub fn extern_c_only(mut self, extern_c_only: bool) -> Self {
+        self.extern_c_only = extern_c_only;
+        self
+    }
+
+    /// skip C++ virtual methods, which are called through an object's vtable
+    /// rather than by their symbol
+    pub fn skip_virtual(mut self, skip_virtual: bool) -> Self {
+        self.skip_virtual = skip_virtual;
+        self
+    }
+
+    /// spell integer types the same whichever compiler built the binary
+    /// (`unsigned long` for gcc's `long unsigned int`), so signatures and
+    /// type ids compare equal across toolchains
+    pub fn normalize_primitive_names(mut self, normalize: bool) -> Self {
+        self.normalize_primitive_names = normalize;
+        self
+    }
+
+    fn is_excluded(&self, name: &str) -> bool {
+        self.exclude.iter().any(|pattern| glob_match(pattern, name))
+    }
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub struct AnalysisResult {
     pub signatures: Vec<FunctionSignature>,
     pub type_registry: TypeRegistry,
+    pub target: TargetInfo,
+    /// `#define` constants, when the library was built with `-g3`
+    pub macros: Vec<MacroConstant>,
+    /// distinct `DW_AT_producer` strings of the compilation units, e.g.
+    /// "GNU C17 11.2.0 -g", in the order they were first seen
+    pub producers: Vec<String>,
+    /// what was skipped, with `ExtractOptions::continue_on_error`; empty
+    /// otherwise
+    pub errors: Vec<UnitError>,
+    /// position in `signatures` of the first function of each name, for
+    /// `function_by_name`. call `index_functions` after changing
+    /// `signatures`; a stale or empty index only makes lookups slower.
+    function_index: HashMap<String, usize>,
+    /// where the extraction spent its time
+    #[cfg(feature = "profiling")]
+    pub timings: Timings,
+}
+
+impl AnalysisResult {
+    /// the result of analyzing `signatures`, with no macros, producers or
+    /// errors
+    pub fn new(
+        signatures: Vec<FunctionSignature>,
+        type_registry: TypeRegistry,
+        target: TargetInfo,
+    ) -> Self {
+        let mut result = Self {
+            signatures,
+            type_registry,
+            target,
+            macros: Vec::new(),
+            producers: Vec::new(),
+            errors: Vec::new(),
+            function_index: HashMap::new(),
+            #[cfg(feature = "profiling")]
+            timings: Timings::default(),
+        };
+        result.index_functions();
+        result
+    }
+
+    /// the function named `name`, or the first one if several are (e.g.
+    /// `static` functions of different files)
+    pub fn function_by_name(&self, name: &str) -> Option<&FunctionSignature> {
+        match self
+            .function_index
+            .get(name)
+            .and_then(|&i| self.signatures.get(i))
+        {
+            Some(sig) if sig.name == name => Some(sig),
+            _ => self.signatures.iter().find(|sig| sig.name == name),
+        }
+    }
+
+    /// drop the types no function in `signatures` uses, e.g. after removing
+    /// some functions, so only what the rest need is emitted
+    pub fn prune_types(&mut self) {
+        let roots: Vec<TypeId> = self
+            .signatures
+            .iter()
+            .flat_map(|sig| {
+                core::iter::once(sig.return_type_id).chain(sig.parameters.iter().map(|p| p.type_id))
+            })
+            .collect();
+        self.type_registry.retain_reachable(&roots);
+    }
+
+    /// rebuild `function_index` from `signatures`
+    pub fn index_functions(&mut self) {
+        self.function_index.clear();
+        for (i, sig) in self.signatures.iter().enumerate() {
+            self.function_index.entry(sig.name.clone()).or_insert(i);
+        }
+    }
+
+    /// members of anonymous enums no typedef names (`enum { A = 1 };`):
+    /// constants with no usable type, much like `#define`s
+    pub fn anonymous_constants(&self) -> Vec<(String, i64)> {
+        self.type_registry.anonymous_enum_constants()
+    }
+
+    /// structs and unions used only through pointers, like handle types
+    /// (see [`TypeRegistry::is_opaque`]), which bindings should expose as
+    /// opaque pointers rather than structs
+    pub fn opaque_types(&self) -> Vec<TypeId> {
+        self.type_registry.opaque_types()
+    }
 }
 
 impl DwarfAnalyzer {
     pub fn new(data: Vec<u8>) -> Self {
-        Self { data }
+        Self {
+            input: Input::Object(data),
+        }
     }
 
-    /// load the dynamic library from file path
+    /// analyze DWARF sections already extracted from their container (e.g.
+    /// `.debug_info`, `.debug_abbrev`, `.debug_str`), bypassing object file
+    /// parsing. missing sections read as empty. with no symbol table, the
+    /// functions with global linkage count as exported, as for a dSYM, and
+    /// none has an address to cross-check.
+    pub fn from_sections(
+        sections: HashMap<gimli::SectionId, Vec<u8>>,
+        endianness: Endianness,
+        pointer_width: usize,
+    ) -> Self {
+        Self {
+            input: Input::Sections {
+                sections,
+                endianness,
+                pointer_width,
+            },
+        }
+    }
+
+    /// the object file's bytes, or None for sections given without one
+    fn object_data(&self) -> Option<&[u8]> {
+        match &self.input {
+            Input::Object(data) => Some(data),
+            Input::Sections { .. } => None,
+        }
+    }
+
+    /// true if there is a non-empty .debug_info section
+    fn has_debug_info(&self) -> Result<bool> {
+        match &self.input {
+            Input::Object(data) => reader::has_debug_info(data),
+            Input::Sections { sections, .. } => Ok(sections
+                .get(&gimli::SectionId::DebugInfo)
+                .is_some_and(|section| !section.is_empty())),
+        }
+    }
+
+    /// analyze a library already in memory. for a universal (fat) Mach-O
+    /// binary, the `arch` slice is used, or the host architecture's if None.
+    /// this is the way in without the `fs` feature, and without std, where
+    /// the host architecture is unknown and `arch` has to be given.
+    pub fn from_bytes(data: Vec<u8>, arch: Option<&str>) -> Result<Self> {
+        Ok(Self::new(reader::select_slice(data, arch)?))
+    }
+
+    /// load the dynamic library from file path. for a universal (fat) Mach-O
+    /// binary, the host architecture's slice is used.
+    #[cfg(feature = "fs")]
     pub fn from_file(path: &std::path::Path) -> Result<Self> {
-        let data = reader::load_file(path)?;
-        Ok(Self::new(data))
+        Self::from_bytes(reader::load_file(path)?, None)
     }
 
-    /// get all exported function symbols (STT_FUNC)
-    pub fn get_exported_symbols(&self) -> Result<HashSet<String>> {
+    /// load the dynamic library from file path, using the `arch` slice
+    /// (e.g. "x86_64", "arm64") of a universal (fat) Mach-O binary
+    #[cfg(feature = "fs")]
+    pub fn from_file_with_arch(path: &std::path::Path, arch: &str) -> Result<Self> {
+        Self::from_bytes(reader::load_file(path)?, Some(arch))
+    }
+
+    /// exported function symbols (STT_FUNC), one per name, with GNU version
+    /// suffixes split off
+    pub fn get_exported_symbols(&self) -> Result<Vec<SymbolInfo>> {
+        if let Some(members) = self.archive_members()? {
+            let mut symbols: Vec<SymbolInfo> = Vec::new();
+            for (_, member_symbols) in Self::each_member(&members, Self::get_exported_symbols)? {
+                for symbol in member_symbols {
+                    if !symbols.iter().any(|s| s.name == symbol.name) {
+                        symbols.push(symbol);
+                    }
+                }
+            }
+            return Ok(symbols);
+        }
+
+        let Some(data) = self.object_data() else {
+            return Ok(Vec::new());
+        };
         log::debug!("read exported symbols from binary");
-        let symbol_reader = SymbolReader::new(&self.data)?;
+        let symbol_reader = SymbolReader::new(data)?;
         let symbols = symbol_reader.get_exported_symbols()?;
         Ok(symbols)
     }
 
+    /// addresses of every function symbol, by symbol name; several when
+    /// `static` functions of different files share a name
+    pub fn get_function_addresses(&self) -> Result<HashMap<String, Vec<u64>>> {
+        match self.object_data() {
+            Some(data) => Ok(SymbolReader::new(data)?.get_function_addresses()),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    /// object-like `#define` constants with literal values, from the
+    /// library's own sources. empty unless it was compiled with `-g3`.
+    pub fn extract_macros(&self) -> Result<Vec<MacroConstant>> {
+        if let Some(members) = self.archive_members()? {
+            let mut all_macros = Vec::new();
+            for (_, member_macros) in Self::each_member(&members, Self::extract_macros)? {
+                merge_macros(&mut all_macros, member_macros);
+            }
+            return Ok(all_macros);
+        }
+        if !self.has_debug_info()? {
+            return Err(DwarffiError::NoDebugInfo);
+        }
+        let (dwarf, macro_sections) = self.load_dwarf()?;
+
+        let mut all_macros = Vec::new();
+        for header in Self::unit_headers(&dwarf, &mut Vec::new()) {
+            if let Some(unit) = Self::parse_unit(&dwarf, header, &mut Vec::new()) {
+                Self::collect_unit_macros(&dwarf, &unit, &macro_sections, &mut all_macros);
+            }
+        }
+        Ok(all_macros)
+    }
+
+    /// the line table of every compilation unit: the source file, line and
+    /// column of each range of machine code, for mapping addresses back to
+    /// source as `addr2line` does. rows are in line-program order, unit by
+    /// unit; sort them by address to look addresses up.
+    pub fn line_mappings(&self) -> Result<Vec<LineRow>> {
+        if let Some(members) = self.archive_members()? {
+            let member_rows = Self::each_member(&members, Self::line_mappings)?;
+            return Ok(member_rows.into_iter().flat_map(|(_, rows)| rows).collect());
+        }
+        if !self.has_debug_info()? {
+            return Err(DwarffiError::NoDebugInfo);
+        }
+        let (dwarf, _) = self.load_dwarf()?;
+
+        let mut rows = Vec::new();
+        for header in Self::unit_headers(&dwarf, &mut Vec::new()) {
+            if let Some(unit) = Self::parse_unit(&dwarf, header, &mut Vec::new()) {
+                rows.extend(lines::unit_line_rows(&dwarf, &unit)?);
+            }
+        }
+        Ok(rows)
+    }
+
+    /// the compilers that produced the library's compilation units, from
+    /// `DW_AT_producer`, without duplicates
+    pub fn producers(&self) -> Result<Vec<String>> {
+        if let Some(members) = self.archive_members()? {
+            let mut producers = Vec::new();
+            for (_, member_producers) in Self::each_member(&members, Self::producers)? {
+                for producer in member_producers {
+                    if !producers.contains(&producer) {
+                        producers.push(producer);
+                    }
+                }
+            }
+            return Ok(producers);
+        }
+        if !self.has_debug_info()? {
+            return Err(DwarffiError::NoDebugInfo);
+        }
+        let (dwarf, _) = self.load_dwarf()?;
+
+        let mut producers = Vec::new();
+        for header in Self::unit_headers(&dwarf, &mut Vec::new()) {
+            if let Some(unit) = Self::parse_unit(&dwarf, header, &mut Vec::new()) {
+                Self::collect_unit_producer(&dwarf, &unit, &mut producers);
+            }
+        }
+        Ok(producers)
+    }
+
+    /// architecture, pointer width and byte order of the binary. for a
+    /// static archive, those of its first object.
+    pub fn target_info(&self) -> Result<TargetInfo> {
+        if let Some(members) = self.archive_members()? {
+            return members
+                .iter()
+                .find_map(|(_, member)| member.target_info().ok())
+                .ok_or_else(|| {
+                    DwarffiError::UnsupportedFormat("archive with no object files".to_string())
+                });
+        }
+        match &self.input {
+            Input::Object(data) => TargetInfo::from_data(data),
+            Input::Sections {
+                endianness,
+                pointer_width,
+                ..
+            } => Ok(TargetInfo {
+                architecture: architecture_name(object::Architecture::Unknown),
+                pointer_width: *pointer_width,
+                endianness: *endianness,
+            }),
+        }
+    }
+
     /// extract function signatures and type registry from DWARF debug info
     pub fn extract_analysis(&self, exported_only: bool) -> Result<AnalysisResult> {
-        let section_loader = reader::object_section_loader(&self.data)?;
-        let dwarf = Dwarf::load(section_loader)?;
-        log::debug!("DWARF data load success");
+        self.extract_analysis_with(&ExtractOptions::new().exported_only(exported_only))
+    }
 
-        // export only?
-        let exported_symbols = if exported_only {
-            Some(self.get_exported_symbols()?)
-        } else {
-            None
+    /// like `extract_analysis`, but drops functions whose names match any of
+    /// the `exclude` globs (e.g. `*_impl`, `__*`)
+    pub fn extract_analysis_excluding(
+        &self,
+        exported_only: bool,
+        exclude: &[String],
+    ) -> Result<AnalysisResult> {
+        let options = ExtractOptions::new()
+            .exported_only(exported_only)
+            .exclude_all(exclude.iter().cloned());
+        self.extract_analysis_with(&options)
+    }
+
+    /// extract function signatures and type registry as configured by `options`
+    pub fn extract_analysis_with(&self, options: &ExtractOptions) -> Result<AnalysisResult> {
+        self.extract_analysis_with_progress(options, &|_| {})
+    }
+
+    /// `extract_analysis_with`, calling `progress` after each compilation unit
+    pub fn extract_analysis_with_progress(
+        &self,
+        options: &ExtractOptions,
+        progress: &dyn Fn(UnitProgress),
+    ) -> Result<AnalysisResult> {
+        if let Some(members) = self.archive_members()? {
+            return Self::extract_archive_analysis(&members, options, progress);
+        }
+
+        let target = self.target_info()?;
+        if !self.has_debug_info()? {
+            return Err(DwarffiError::NoDebugInfo);
+        }
+
+        #[cfg(feature = "profiling")]
+        let mut timings = Timings::default();
+        #[cfg(feature = "profiling")]
+        let start = Instant::now();
+        let (dwarf, macro_sections) = self.load_dwarf()?;
+        #[cfg(feature = "profiling")]
+        {
+            timings.section_load = start.elapsed();
+            log::info!("loaded DWARF sections in {:?}", timings.section_load);
+        }
+
+        // read even when not filtering, so `is_exported` is accurate
+        let exported_symbols: HashSet<String> = self
+            .get_exported_symbols()?
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect();
+
+        let (symbol_addresses, is_relocatable) = match self.object_data() {
+            Some(data) => {
+                let symbol_reader = SymbolReader::new(data)?;
+                (
+                    symbol_reader.get_function_addresses(),
+                    symbol_reader.is_relocatable(),
+                )
+            }
+            None => (HashMap::new(), false),
         };
 
+        // headers are cheap to read, so collect them up front for progress
+        let mut errors = Vec::new();
+        let headers = Self::unit_headers(&dwarf, &mut errors);
+        let total_units = headers.len();
+
         let mut all_signatures = Vec::new();
+        let mut all_macros = Vec::new();
+        let mut producers = Vec::new();
         let mut combined_registry = TypeRegistry::new();
-        let mut unit_iter = dwarf.units();
         let mut unit_count = 0;
 
-        while let Some(header) = unit_iter.next()? {
+        for header in headers {
             unit_count += 1;
             log::debug!("processing compilation unit {}", unit_count);
 
-            let unit = dwarf.unit(header)?;
-            let mut type_resolver = TypeResolver::new(&dwarf, &unit);
+            let Some(unit) = Self::parse_unit(&dwarf, header, &mut errors) else {
+                continue;
+            };
+            let mut type_resolver = TypeResolver::new(&dwarf, &unit)
+                .normalize_primitive_names(options.normalize_primitive_names);
 
             // Extract function signatures with TypeId-based parameters
-            let unit_sigs = self.extract_functions_from_unit(
+            #[cfg(feature = "profiling")]
+            let start = Instant::now();
+            let mut unit_sigs = self.extract_functions_from_unit(
                 &dwarf,
                 &unit,
                 &exported_symbols,
+                options,
                 &mut type_resolver,
-            )?;
+                &mut errors,
+            );
+            #[cfg(feature = "profiling")]
+            {
+                let elapsed = start.elapsed();
+                log::info!(
+                    "extracted {} functions of unit {} in {:?}",
+                    unit_sigs.len(),
+                    unit_count,
+                    elapsed
+                );
+                timings.units.push(elapsed);
+            }
+
+            unit_sigs.retain(|sig| {
+                let excluded = options.is_excluded(&sig.name);
+                if excluded {
+                    log::trace!("exclude function: {}", sig.name);
+                }
+                !excluded
+            });
+            for sig in &mut unit_sigs {
+                Self::cross_check_address(sig, &symbol_addresses, is_relocatable);
+
+                // every call to it was inlined and no copy was kept
+                if sig.inline_kind != InlineKind::NotInlined && sig.address.is_none() {
+                    log::warn!(
+                        "{} is inline-only; it has no definition in the library to bind to",
+                        sig.name
+                    );
+                }
+            }
 
             log::debug!("found {} functions in unit {}", unit_sigs.len(), unit_count);
             all_signatures.extend(unit_sigs);
 
+            Self::collect_anonymous_enums(&unit, &mut type_resolver);
+            Self::collect_unit_macros(&dwarf, &unit, &macro_sections, &mut all_macros);
+            Self::collect_unit_producer(&dwarf, &unit, &mut producers);
+
+            #[cfg(feature = "profiling")]
+            timings.add(type_resolver.timings());
+
             // Merge type registry from this unit
             let unit_registry = type_resolver.into_registry();
+            #[cfg(feature = "profiling")]
+            let start = Instant::now();
             combined_registry.merge(unit_registry);
+            #[cfg(feature = "profiling")]
+            {
+                timings.merge += start.elapsed();
+            }
+
+            progress(UnitProgress {
+                unit_index: unit_count - 1,
+                total_units,
+                functions_so_far: all_signatures.len(),
+                types_so_far: combined_registry.len(),
+            });
         }
 
         log::info!(
@@ -82,33 +693,471 @@ impl DwarfAnalyzer {
             all_signatures.len(),
             combined_registry.len()
         );
+        #[cfg(feature = "profiling")]
+        log::info!("{}", timings);
 
-        Ok(AnalysisResult {
-            signatures: all_signatures,
-            type_registry: combined_registry,
-        })
+        let mut result = AnalysisResult::new(all_signatures, combined_registry, target);
+        result.macros = all_macros;
+        result.producers = producers;
+        if options.continue_on_error {
+            result.errors = errors;
+        }
+        #[cfg(feature = "profiling")]
+        {
+            result.timings = timings;
+        }
+        Ok(result)
+    }
+
+    /// headers of every compilation unit. a malformed header ends the list,
+    /// since the units after it can't be located.
+    fn unit_headers(
+        dwarf: &Dwarf<reader::DwarfReader>,
+        errors: &mut Vec<UnitError>,
+    ) -> Vec<gimli::UnitHeader<reader::DwarfReader>> {
+        let mut headers = Vec::new();
+        let mut header_iter = dwarf.units();
+        loop {
+            match header_iter.next() {
+                Ok(Some(header)) => headers.push(header),
+                Ok(None) => break,
+                Err(e) => {
+                    log::warn!(
+                        "malformed compilation unit header after {} units, skipping the rest: {}",
+                        headers.len(),
+                        e
+                    );
+                    errors.push(UnitError::new(None, None, e));
+                    break;
+                }
+            }
+        }
+        headers
     }
 
+    /// parse a unit's abbreviations and attributes, or None with a warning
+    /// if they are malformed
+    fn parse_unit(
+        dwarf: &Dwarf<reader::DwarfReader>,
+        header: gimli::UnitHeader<reader::DwarfReader>,
+        errors: &mut Vec<UnitError>,
+    ) -> Option<gimli::Unit<reader::DwarfReader>> {
+        let offset = header.offset();
+        match dwarf.unit(header) {
+            Ok(unit) => Some(unit),
+            Err(e) => {
+                log::warn!("skip malformed compilation unit at {:?}: {}", offset, e);
+                errors.push(UnitError::new(Some(offset), None, e));
+                None
+            }
+        }
+    }
+
+    /// add the unit's `DW_AT_producer` to `producers` if it's new
+    fn collect_unit_producer(
+        dwarf: &Dwarf<reader::DwarfReader>,
+        unit: &gimli::Unit<reader::DwarfReader>,
+        producers: &mut Vec<String>,
+    ) {
+        let mut entries = unit.entries();
+        let Ok(Some((_, root))) = entries.next_dfs() else {
+            return;
+        };
+        if let Ok(Some(attr)) = root.attr(gimli::DW_AT_producer)
+            && let Some(producer) = Self::read_attr_string(dwarf, unit, &attr)
+            && !producers.contains(&producer)
+        {
+            log::debug!("producer: {}", producer);
+            producers.push(producer);
+        }
+    }
+
+    /// register the unit's file-scope anonymous enums (`enum { A = 1 };`).
+    /// nothing refers to them by type, so resolving functions never reaches
+    /// them, but their members are constants of the library's interface.
+    /// enums a typedef names are left to be registered through their uses.
+    fn collect_anonymous_enums(
+        unit: &gimli::Unit<reader::DwarfReader>,
+        type_resolver: &mut TypeResolver<reader::DwarfReader>,
+    ) {
+        let mut enums = Vec::new();
+        let mut typedef_targets = HashSet::new();
+
+        let Ok(mut tree) = unit.entries_tree(None) else {
+            return;
+        };
+        let Ok(root) = tree.root() else {
+            return;
+        };
+        let mut children = root.children();
+        while let Ok(Some(child)) = children.next() {
+            let entry = child.entry();
+            match entry.tag() {
+                gimli::DW_TAG_typedef => {
+                    if let Ok(Some(AttributeValue::UnitRef(target))) =
+                        entry.attr_value(gimli::DW_AT_type)
+                    {
+                        typedef_targets.insert(target);
+                    }
+                }
+                gimli::DW_TAG_enumeration_type
+                    if !matches!(entry.attr(gimli::DW_AT_name), Ok(Some(_)))
+                        && !Self::attr_flag_is_true(
+                            entry.attr(gimli::DW_AT_declaration).ok().flatten(),
+                        ) =>
+                {
+                    enums.push(entry.offset());
+                }
+                _ => {}
+            }
+        }
+
+        for offset in enums {
+            if typedef_targets.contains(&offset) {
+                continue;
+            }
+            if let Err(e) = type_resolver.build_type_registry_entry(offset) {
+                log::warn!("skip anonymous enum at {:#010x}: {}", offset.0, e);
+            }
+        }
+    }
+
+    /// DWARF sections, plus the macro sections gimli doesn't load itself
+    fn load_dwarf(
+        &self,
+    ) -> Result<(
+        Dwarf<reader::DwarfReader>,
+        MacroSections<reader::DwarfReader>,
+    )> {
+        match &self.input {
+            Input::Object(data) => Self::load_sections(reader::object_section_loader(data)?),
+            Input::Sections {
+                sections,
+                endianness,
+                ..
+            } => {
+                let endianness = match endianness {
+                    Endianness::Little => gimli::RunTimeEndian::Little,
+                    Endianness::Big => gimli::RunTimeEndian::Big,
+                };
+                Self::load_sections(reader::provided_section_loader(sections, endianness))
+            }
+        }
+    }
+
+    fn load_sections(
+        section_loader: impl Fn(gimli::SectionId) -> Result<reader::DwarfReader>,
+    ) -> Result<(
+        Dwarf<reader::DwarfReader>,
+        MacroSections<reader::DwarfReader>,
+    )> {
+        let dwarf = Dwarf::load(&section_loader)?;
+        let macro_sections = MacroSections {
+            debug_macro: section_loader(gimli::SectionId::DebugMacro)?,
+            debug_macinfo: section_loader(gimli::SectionId::DebugMacinfo)?,
+        };
+        log::debug!("DWARF data load success");
+        Ok((dwarf, macro_sections))
+    }
+
+    /// add a unit's macros to `all_macros`. headers are seen by many units,
+    /// so the first definition of a name is kept.
+    fn collect_unit_macros(
+        dwarf: &Dwarf<reader::DwarfReader>,
+        unit: &gimli::Unit<reader::DwarfReader>,
+        macro_sections: &MacroSections<reader::DwarfReader>,
+        all_macros: &mut Vec<MacroConstant>,
+    ) {
+        // macros are an extra; a malformed table shouldn't fail the analysis
+        let unit_macros = match macros::unit_macros(dwarf, unit, macro_sections) {
+            Ok(unit_macros) => unit_macros,
+            Err(e) => {
+                log::warn!("skip macros of unit at {:?}: {}", unit.header.offset(), e);
+                return;
+            }
+        };
+        merge_macros(all_macros, unit_macros);
+    }
+
+    /// the objects of a static archive, each analyzed on its own, or None if
+    /// this isn't an archive
+    fn archive_members(&self) -> Result<Option<Vec<(String, DwarfAnalyzer)>>> {
+        let Some(data) = self.object_data() else {
+            return Ok(None);
+        };
+        let Some(members) = reader::archive_members(data)? else {
+            return Ok(None);
+        };
+        Ok(Some(
+            members
+                .into_iter()
+                .map(|(name, data)| (name, Self::new(data.to_vec())))
+                .collect(),
+        ))
+    }
+
+    /// `f` for each archive member it succeeds on. members that aren't
+    /// objects or have no debug info are skipped (an archive may mix in
+    /// objects built without `-g`), but if none are left there is no debug
+    /// info to read.
+    fn each_member<T>(
+        members: &[(String, DwarfAnalyzer)],
+        f: impl Fn(&DwarfAnalyzer) -> Result<T>,
+    ) -> Result<Vec<(&str, T)>> {
+        let mut results = Vec::new();
+        for (name, member) in members {
+            match f(member) {
+                Ok(result) => results.push((name.as_str(), result)),
+                Err(e @ (DwarffiError::NoDebugInfo | DwarffiError::ObjectParse(_))) => {
+                    log::debug!("skip archive member {}: {}", name, e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if results.is_empty() {
+            return Err(DwarffiError::NoDebugInfo);
+        }
+        Ok(results)
+    }
+
+    /// analyze each object of a static archive and merge the results, as
+    /// `extract_analysis_multi` does for separate files. `progress` is
+    /// reported per member, each counting its own units.
+    fn extract_archive_analysis(
+        members: &[(String, DwarfAnalyzer)],
+        options: &ExtractOptions,
+        progress: &dyn Fn(UnitProgress),
+    ) -> Result<AnalysisResult> {
+        let results = Self::each_member(members, |member| {
+            member.extract_analysis_with_progress(options, progress)
+        })?;
+
+        let mut results = results.into_iter();
+        let (_, mut combined) = results.next().ok_or(DwarffiError::NoDebugInfo)?;
+        let mut seen = combined
+            .signatures
+            .iter()
+            .map(|sig| sig.name.clone())
+            .collect();
+        for (name, result) in results {
+            Self::merge_analysis(&mut combined, result, name, &mut seen);
+        }
+        combined.index_functions();
+        Ok(combined)
+    }
+
+    /// add `result`, read from `source`, to `combined`. a function already in
+    /// `seen` is kept from the first result it appeared in.
+    fn merge_analysis(
+        combined: &mut AnalysisResult,
+        result: AnalysisResult,
+        source: &str,
+        seen: &mut HashSet<String>,
+    ) {
+        if result.target != combined.target {
+            log::warn!(
+                "{} targets {}, expected {}",
+                source,
+                result.target,
+                combined.target
+            );
+        }
+
+        for sig in result.signatures {
+            if seen.insert(sig.name.clone()) {
+                combined.signatures.push(sig);
+            } else {
+                log::warn!(
+                    "function {} also defined in {}, keeping the first definition",
+                    sig.name,
+                    source
+                );
+            }
+        }
+
+        #[cfg(feature = "profiling")]
+        let start = Instant::now();
+        let shift = combined.type_registry.merge_source(result.type_registry);
+        #[cfg(feature = "profiling")]
+        {
+            combined.timings.add(&result.timings);
+            combined.timings.merge += start.elapsed();
+        }
+        merge_macros(&mut combined.macros, result.macros);
+        for mut error in result.errors {
+            if let Some(unit) = &mut error.unit {
+                unit.source += shift;
+            }
+            combined.errors.push(error);
+        }
+        for producer in result.producers {
+            if !combined.producers.contains(&producer) {
+                combined.producers.push(producer);
+            }
+        }
+    }
+
+    /// analyze several files (e.g. a library split across multiple shared
+    /// objects) into one result. type registries are merged; a function
+    /// defined in more than one file is kept from the first file only. the
+    /// target is taken from the first file.
+    #[cfg(feature = "fs")]
+    pub fn extract_analysis_multi(
+        paths: &[PathBuf],
+        exported_only: bool,
+    ) -> Result<AnalysisResult> {
+        Self::extract_analysis_multi_with(
+            paths,
+            &ExtractOptions::new().exported_only(exported_only),
+        )
+    }
+
+    /// `extract_analysis_multi` as configured by `options`
+    #[cfg(feature = "fs")]
+    pub fn extract_analysis_multi_with(
+        paths: &[PathBuf],
+        options: &ExtractOptions,
+    ) -> Result<AnalysisResult> {
+        let Some((first, rest)) = paths.split_first() else {
+            return Err(DwarffiError::NoInput);
+        };
+
+        let mut combined = Self::from_file(first)?.extract_analysis_with(options)?;
+        let mut seen: HashSet<String> = combined
+            .signatures
+            .iter()
+            .map(|sig| sig.name.clone())
+            .collect();
+
+        for path in rest {
+            let result = Self::from_file(path)?.extract_analysis_with(options)?;
+            Self::merge_analysis(
+                &mut combined,
+                result,
+                &path.display().to_string(),
+                &mut seen,
+            );
+        }
+
+        for (name, count) in combined.type_registry.name_collisions() {
+            log::warn!(
+                "{} distinct types named {} across the inputs; lookups by name are ambiguous",
+                count,
+                name
+            );
+        }
+
+        combined.index_functions();
+        Ok(combined)
+    }
+
+    /// analyze the executable and libraries a running process has mapped,
+    /// read from `/proc/PID/maps`, into one result as
+    /// `extract_analysis_multi` does. only files mapped executable are read,
+    /// and those that aren't objects or have no debug info (often the system
+    /// libraries) are skipped; if none are left there is no debug info to
+    /// read.
+    #[cfg(all(feature = "fs", target_os = "linux"))]
+    pub fn extract_analysis_pid(pid: u32, options: &ExtractOptions) -> Result<AnalysisResult> {
+        let mut results = Vec::new();
+        for path in crate::proc_maps::mapped_files(pid)? {
+            let result = Self::from_file(&path).and_then(|a| a.extract_analysis_with(options));
+            match result {
+                Ok(result) => results.push((path.display().to_string(), result)),
+                Err(e @ (DwarffiError::NoDebugInfo | DwarffiError::ObjectParse(_))) => {
+                    log::debug!("skip mapping {}: {}", path.display(), e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut results = results.into_iter();
+        let (_, mut combined) = results.next().ok_or(DwarffiError::NoDebugInfo)?;
+        let mut seen = combined
+            .signatures
+            .iter()
+            .map(|sig| sig.name.clone())
+            .collect();
+        for (path, result) in results {
+            Self::merge_analysis(&mut combined, result, &path, &mut seen);
+        }
+        combined.index_functions();
+        Ok(combined)
+    }
+
+    /// every function defined in the unit. a function whose DIEs fail to
+    /// parse is skipped with a warning, and a broken DIE tree ends the unit
+    /// early, keeping what was read before it.
     fn extract_functions_from_unit(
         &self,
         dwarf: &Dwarf<reader::DwarfReader>,
         unit: &gimli::Unit<reader::DwarfReader>,
-        exported_symbols: &Option<HashSet<String>>,
+        exported_symbols: &HashSet<String>,
+        options: &ExtractOptions,
         type_resolver: &mut TypeResolver<reader::DwarfReader>,
-    ) -> Result<Vec<FunctionSignature>> {
+        errors: &mut Vec<UnitError>,
+    ) -> Vec<FunctionSignature> {
         let mut signatures = Vec::new();
         let mut function_count = 0;
         let mut entries = unit.entries();
 
+        // the namespace or class each ancestor of the current entry opens,
+        // and the enclosing scope of each member subprogram seen so far, for
+        // definitions that refer back to their declaration
+        let mut scopes: Vec<Option<String>> = Vec::new();
+        let mut depth: isize = 0;
+        let mut member_scopes: HashMap<gimli::UnitOffset, String> = HashMap::new();
+
         // DWARF entries are tree-like. functions are grouped with their return
         // types, parameters, etc. dfs will pull out children i.e. parameters,
         // return types together.
-        while let Some((_, entry)) = entries.next_dfs()? {
+        loop {
+            let entry = match entries.next_dfs() {
+                Ok(Some((delta, entry))) => {
+                    depth += delta;
+                    entry
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    log::warn!(
+                        "malformed DIE tree in unit at {:?}, skipping the rest of it: {}",
+                        unit.header.offset(),
+                        e
+                    );
+                    errors.push(UnitError::new(Some(unit.header.offset()), None, e));
+                    break;
+                }
+            };
+
+            scopes.truncate(depth.max(0) as usize);
+            scopes.push(Self::scope_name(dwarf, unit, entry));
+
             // function definitions marked with DW_TAG_subprogram
             if entry.tag() != gimli::DW_TAG_subprogram {
                 continue;
             }
 
+            let enclosing = &scopes[..scopes.len() - 1];
+            let mut scope = enclosing
+                .iter()
+                .flatten()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join("::");
+            if scope.is_empty() {
+                for reference in [gimli::DW_AT_specification, gimli::DW_AT_abstract_origin] {
+                    if let Ok(Some(AttributeValue::UnitRef(offset))) = entry.attr_value(reference)
+                        && let Some(referenced) = member_scopes.get(&offset)
+                    {
+                        scope = referenced.clone();
+                        break;
+                    }
+                }
+            }
+            if !scope.is_empty() {
+                member_scopes.insert(entry.offset(), scope.clone());
+            }
+
             // skip function declarations (keep only definitions)
             if Self::attr_flag_is_true(entry.attr(gimli::DW_AT_declaration).ok().flatten()) {
                 log::trace!("skip function declaration at {:#010x}", entry.offset().0);
@@ -118,7 +1167,7 @@ impl DwarfAnalyzer {
             function_count += 1;
 
             // skip no-name functions
-            let name = match self.get_function_name(dwarf, unit, entry) {
+            let mut name = match self.get_function_name(dwarf, unit, entry) {
                 Some(n) => {
                     log::trace!("found function: {}", n);
                     n
@@ -129,45 +1178,57 @@ impl DwarfAnalyzer {
                 }
             };
 
-            // check against exported symbols
-            let is_exported = exported_symbols
-                .as_ref()
-                .map(|symbols| {
-                    // macOS prepends an underscore to symbol name
-                    symbols.contains(&name) || symbols.contains(&format!("_{}", name))
-                })
-                .unwrap_or(true);
-
-            // skip if not exported
-            if exported_symbols.is_some() && !is_exported {
-                log::trace!("skip non-exported function: {}", name);
-                continue;
-            }
-
-            // extract the return type TypeId
-            let return_type_id = if let Some(type_attr) = entry.attr(gimli::DW_AT_type)? {
-                if let AttributeValue::UnitRef(offset) = type_attr.value() {
-                    type_resolver.build_type_registry_entry(offset)?
-                } else {
-                    type_resolver.get_void_type_id()?
-                }
+            // check against exported symbols. macOS prepends an underscore
+            // to symbol names. without a symbol table (a dSYM, or a file of
+            // only the `.debug*` sections) there is nothing to check
+            // against, so global linkage (`DW_AT_external`) stands in for it.
+            let is_symbol = exported_symbols.contains(&name)
+                || exported_symbols.contains(&format!("_{}", name));
+            let is_exported = if exported_symbols.is_empty() {
+                Self::is_external(unit, entry)
             } else {
-                type_resolver.get_void_type_id()?
+                is_symbol
             };
 
-            log::debug!("{:>12} {:#010x}: {}()", "function", entry.offset().0, name);
+            // skip if not visible enough
+            match options.visibility {
+                Visibility::Exported if !is_exported => {
+                    log::trace!("skip non-exported function: {}", name);
+                    continue;
+                }
+                Visibility::External if !is_symbol && !Self::is_external(unit, entry) => {
+                    log::trace!("skip static function: {}", name);
+                    continue;
+                }
+                _ => {}
+            }
 
-            // extract the parameters
-            let (parameters, is_variadic) =
-                self.extract_parameters(dwarf, unit, entry, type_resolver)?;
+            // a C++ member with no linkage name only has its own name, which
+            // other classes and namespaces can share, so qualify it. one
+            // whose bare name is a symbol is `extern "C"` and keeps it, and
+            // an Objective-C method's selector name already names its class.
+            let is_mangled = name.starts_with("_Z");
+            let is_qualified =
+                !scope.is_empty() && !is_mangled && !is_symbol && !Self::is_objc_method(&name);
+            if is_qualified {
+                name = format!("{}::{}", scope, name);
+            }
+            if options.extern_c_only && (is_mangled || is_qualified) {
+                log::trace!("skip C++ function: {}", name);
+                continue;
+            }
+            if options.skip_virtual && Self::is_virtual(unit, entry) {
+                log::trace!("skip virtual method: {}", name);
+                continue;
+            }
 
-            signatures.push(FunctionSignature {
-                name: name.clone(),
-                return_type_id,
-                parameters,
-                is_variadic,
-                is_exported,
-            });
+            match self.extract_function(dwarf, unit, entry, &name, is_exported, type_resolver) {
+                Ok(sig) => signatures.push(sig),
+                Err(e) => {
+                    log::warn!("skip function {}: {}", name, e);
+                    errors.push(UnitError::new(Some(unit.header.offset()), Some(&name), e));
+                }
+            }
         }
 
         log::debug!(
@@ -176,7 +1237,190 @@ impl DwarfAnalyzer {
             function_count,
             signatures.len()
         );
-        Ok(signatures)
+        signatures
+    }
+
+    /// the name a namespace or class contributes to the qualified names of
+    /// its members, if the entry is one
+    fn scope_name(
+        dwarf: &Dwarf<reader::DwarfReader>,
+        unit: &gimli::Unit<reader::DwarfReader>,
+        entry: &gimli::DebuggingInformationEntry<reader::DwarfReader>,
+    ) -> Option<String> {
+        let is_namespace = match entry.tag() {
+            gimli::DW_TAG_namespace => true,
+            gimli::DW_TAG_class_type | gimli::DW_TAG_structure_type | gimli::DW_TAG_union_type => {
+                false
+            }
+            _ => return None,
+        };
+
+        let name = entry
+            .attr(gimli::DW_AT_name)
+            .ok()
+            .flatten()
+            .and_then(|attr| Self::read_attr_string(dwarf, unit, &attr));
+        match name {
+            None if is_namespace => Some("(anonymous namespace)".to_string()),
+            name => name,
+        }
+    }
+
+    /// an Objective-C method, named by its selector: `-[NSString length]`
+    /// or `+[NSString stringWithUTF8String:]`
+    fn is_objc_method(name: &str) -> bool {
+        (name.starts_with("-[") || name.starts_with("+[")) && name.ends_with(']')
+    }
+
+    /// `DW_AT_external`, which a definition completing a declaration may
+    /// leave to the declaration
+    fn is_external(
+        unit: &gimli::Unit<reader::DwarfReader>,
+        entry: &gimli::DebuggingInformationEntry<reader::DwarfReader>,
+    ) -> bool {
+        Self::attr_flag_is_true(
+            Self::subprogram_attr(unit, entry, gimli::DW_AT_external)
+                .ok()
+                .flatten(),
+        )
+    }
+
+    /// `DW_AT_virtuality` other than none. an out-of-class definition of a
+    /// method leaves it to its declaration in the class, and a destructor's
+    /// concrete copies reach that declaration through their abstract
+    /// instance, so both references are followed.
+    fn is_virtual(
+        unit: &gimli::Unit<reader::DwarfReader>,
+        entry: &gimli::DebuggingInformationEntry<reader::DwarfReader>,
+    ) -> bool {
+        let mut offset = entry.offset();
+        // origin, then the declaration it specifies
+        for _ in 0..3 {
+            let Ok(entry) = unit.entry(offset) else {
+                return false;
+            };
+            if let Ok(Some(value)) = entry.attr_value(gimli::DW_AT_virtuality) {
+                return value != AttributeValue::Virtuality(gimli::DW_VIRTUALITY_none);
+            }
+            let reference = [gimli::DW_AT_abstract_origin, gimli::DW_AT_specification]
+                .into_iter()
+                .find_map(|name| match entry.attr_value(name) {
+                    Ok(Some(AttributeValue::UnitRef(next))) => Some(next),
+                    _ => None,
+                });
+            match reference {
+                Some(next) => offset = next,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// signature of one `DW_TAG_subprogram` definition
+    fn extract_function(
+        &self,
+        dwarf: &Dwarf<reader::DwarfReader>,
+        unit: &gimli::Unit<reader::DwarfReader>,
+        entry: &gimli::DebuggingInformationEntry<reader::DwarfReader>,
+        name: &str,
+        is_exported: bool,
+        type_resolver: &mut TypeResolver<reader::DwarfReader>,
+    ) -> Result<FunctionSignature> {
+        // extract the return type TypeId. a definition that refers to its
+        // declaration (e.g. a C++ member) leaves the return type there
+        let return_type_id = match Self::subprogram_attr(unit, entry, gimli::DW_AT_type)? {
+            Some(attr) => type_resolver.build_type_registry_entry_for(attr.value())?,
+            None => None,
+        };
+        let return_type_id = match return_type_id {
+            Some(id) => id,
+            None => type_resolver.get_void_type_id()?,
+        };
+
+        log::debug!("{:>12} {:#010x}: {}()", "function", entry.offset().0, name);
+
+        // extract the parameters
+        let (parameters, is_variadic) =
+            self.extract_parameters(dwarf, unit, entry, type_resolver)?;
+
+        // an address, or with DWARF 5 an index into .debug_addr
+        let address = match entry.attr_value(gimli::DW_AT_low_pc)? {
+            Some(value) => dwarf.attr_address(unit, value)?,
+            None => None,
+        };
+
+        let is_noreturn =
+            Self::attr_flag_is_true(Self::subprogram_attr(unit, entry, gimli::DW_AT_noreturn)?);
+        // only C marks prototypes; other languages have nothing else
+        let is_prototyped = !Self::is_c_unit(unit)?
+            || Self::attr_flag_is_true(Self::subprogram_attr(
+                unit,
+                entry,
+                gimli::DW_AT_prototyped,
+            )?);
+        let inline_kind = match Self::subprogram_attr(unit, entry, gimli::DW_AT_inline)?
+            .map(|attr| attr.value())
+        {
+            Some(AttributeValue::Inline(gimli::DW_INL_inlined)) => InlineKind::Inlined,
+            Some(AttributeValue::Inline(gimli::DW_INL_declared_not_inlined)) => {
+                InlineKind::DeclaredNotInlined
+            }
+            Some(AttributeValue::Inline(gimli::DW_INL_declared_inlined)) => {
+                InlineKind::DeclaredInlined
+            }
+            _ => InlineKind::NotInlined,
+        };
+        let description = Self::subprogram_attr(unit, entry, gimli::DW_AT_description)?
+            .and_then(|attr| Self::read_attr_string(dwarf, unit, &attr))
+            .map(|description| description.trim().to_string())
+            .filter(|description| !description.is_empty());
+
+        Ok(FunctionSignature {
+            name: name.to_string(),
+            return_type_id,
+            parameters,
+            is_variadic,
+            is_prototyped,
+            is_exported,
+            is_external: Self::is_external(unit, entry),
+            description,
+            address,
+            is_noreturn,
+            inline_kind,
+            is_virtual: Self::is_virtual(unit, entry),
+        })
+    }
+
+    /// fill in or check a function's DWARF address against its symbols. a
+    /// name with several symbols only fills in an address it can match.
+    fn cross_check_address(
+        sig: &mut FunctionSignature,
+        symbol_addresses: &HashMap<String, Vec<u64>>,
+        is_relocatable: bool,
+    ) {
+        // macOS prepends an underscore to symbol name
+        let symbols = symbol_addresses
+            .get(&sig.name)
+            .or_else(|| symbol_addresses.get(&format!("_{}", sig.name)))
+            .map_or(&[][..], Vec::as_slice);
+
+        match (sig.address, symbols) {
+            // in an object file every section is at address 0, so low_pc is
+            // only an offset into whichever section holds the function; take
+            // the symbol's, if there is only one to choose
+            (_, [symbol]) if is_relocatable => sig.address = Some(*symbol),
+            (_, [_, _, ..]) if is_relocatable => sig.address = None,
+            (None, [symbol]) => sig.address = Some(*symbol),
+            (Some(address), [_, ..]) if !symbols.contains(&address) => {
+                log::warn!(
+                    "{}: DWARF address {:#x} matches none of its symbols {:#x?}",
+                    sig.name,
+                    address,
+                    symbols
+                );
+            }
+            _ => {}
+        }
     }
 
     // attempt to extract the function name from the unit. returns None if no
@@ -281,6 +1525,52 @@ impl DwarfAnalyzer {
         Self::read_entry_name(dwarf, unit, referenced)
     }
 
+    /// an attribute of a subprogram or parameter, or of the declaration or
+    /// abstract inline instance it refers to, which is where e.g. `DW_AT_inline` lives for
+    /// an out-of-line copy of an inline function
+    fn subprogram_attr(
+        unit: &gimli::Unit<reader::DwarfReader>,
+        entry: &gimli::DebuggingInformationEntry<reader::DwarfReader>,
+        name: gimli::DwAt,
+    ) -> Result<Option<gimli::Attribute<reader::DwarfReader>>> {
+        if let Some(attr) = entry.attr(name)? {
+            return Ok(Some(attr));
+        }
+
+        for reference in [gimli::DW_AT_abstract_origin, gimli::DW_AT_specification] {
+            if let Some(AttributeValue::UnitRef(offset)) = entry.attr_value(reference)? {
+                let mut entries = unit.entries_at_offset(offset)?;
+                if let Some((_, referenced)) = entries.next_dfs()?
+                    && let Some(attr) = referenced.attr(name)?
+                {
+                    return Ok(Some(attr));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// whether the unit's `DW_AT_language` is C or Objective-C, where a
+    /// function may be declared without a prototype
+    fn is_c_unit(unit: &gimli::Unit<reader::DwarfReader>) -> Result<bool> {
+        let mut entries = unit.entries();
+        let Some((_, root)) = entries.next_dfs()? else {
+            return Ok(false);
+        };
+        Ok(matches!(
+            root.attr_value(gimli::DW_AT_language)?,
+            Some(AttributeValue::Language(
+                gimli::DW_LANG_C89
+                    | gimli::DW_LANG_C
+                    | gimli::DW_LANG_C99
+                    | gimli::DW_LANG_C11
+                    | gimli::DW_LANG_C17
+                    | gimli::DW_LANG_ObjC
+            ))
+        ))
+    }
+
     /// check if an attribute is a flag and is true
     fn attr_flag_is_true(attr: Option<gimli::Attribute<reader::DwarfReader>>) -> bool {
         let Some(attr) = attr else {
@@ -336,6 +1626,11 @@ impl DwarfAnalyzer {
     /// therefore the returned tuple contains the list of parameters and whether
     /// the function from whom the parameters are extracted is variadic.
     ///
+    /// formal parameters appear in source order. an out-of-line copy of an
+    /// inline function is the exception: its parameters point at the abstract
+    /// instance's, may be listed in any order, and may be missing altogether
+    /// when optimized out, so the abstract instance decides the order there.
+    ///
     /// We also carry the stateful type resolver with us and update it, since we
     /// may encounter types that are not yet analyzed in the parameters.
     fn extract_parameters(
@@ -345,10 +1640,83 @@ impl DwarfAnalyzer {
         func_entry: &gimli::DebuggingInformationEntry<reader::DwarfReader>,
         type_resolver: &mut TypeResolver<reader::DwarfReader>,
     ) -> Result<(Vec<Parameter>, bool)> {
-        let mut parameters = Vec::new();
+        let (offsets, mut is_variadic) = Self::formal_parameters(unit, func_entry.offset())?;
+        let mut parameters = Vec::with_capacity(offsets.len());
+        for offset in offsets {
+            parameters.push(self.read_parameter(dwarf, unit, offset, type_resolver)?);
+        }
+
+        // a C++ method's implicit `this`, which compilers mark artificial
+        // too, though not always
+        if let Some(AttributeValue::UnitRef(object_pointer)) =
+            func_entry.attr_value(gimli::DW_AT_object_pointer)?
+        {
+            for (parameter, _) in &mut parameters {
+                if parameter.dwarf_offset == Some(object_pointer.0 as u64) {
+                    parameter.is_artificial = true;
+                }
+            }
+        }
+
+        if let Some(AttributeValue::UnitRef(origin)) =
+            func_entry.attr_value(gimli::DW_AT_abstract_origin)?
+        {
+            let (origin_offsets, origin_variadic) = Self::formal_parameters(unit, origin)?;
+            is_variadic |= origin_variadic;
+
+            let mut concrete = parameters;
+            parameters = Vec::with_capacity(origin_offsets.len());
+            for origin_offset in origin_offsets {
+                match concrete.iter().position(|(_, o)| *o == Some(origin_offset)) {
+                    Some(index) => parameters.push(concrete.remove(index)),
+                    None => {
+                        log::debug!(
+                            "{:>12} {:#010x}: optimized out of {:#010x}",
+                            "parameter",
+                            origin_offset.0,
+                            func_entry.offset().0,
+                        );
+                        parameters.push(self.read_parameter(
+                            dwarf,
+                            unit,
+                            origin_offset,
+                            type_resolver,
+                        )?);
+                    }
+                }
+            }
+            // anything the abstract instance does not know about goes last
+            parameters.extend(concrete);
+        } else if let Some(AttributeValue::UnitRef(declaration)) =
+            func_entry.attr_value(gimli::DW_AT_specification)?
+        {
+            let (declared, _) = Self::formal_parameters(unit, declaration)?;
+            if !declared.is_empty() && declared.len() != parameters.len() {
+                log::warn!(
+                    "function at {:#010x} has {} parameters but its declaration has {}",
+                    func_entry.offset().0,
+                    parameters.len(),
+                    declared.len(),
+                );
+            }
+        }
+
+        let parameters = parameters
+            .into_iter()
+            .map(|(parameter, _)| parameter)
+            .collect();
+        Ok((parameters, is_variadic))
+    }
+
+    /// the formal parameter children of a subprogram, in order, and whether it
+    /// also has unspecified (variadic) parameters
+    fn formal_parameters(
+        unit: &gimli::Unit<reader::DwarfReader>,
+        offset: gimli::UnitOffset,
+    ) -> Result<(Vec<gimli::UnitOffset>, bool)> {
+        let mut offsets = Vec::new();
         let mut is_variadic = false;
 
-        let offset = func_entry.offset();
         let mut tree = unit.entries_tree(Some(offset))?;
         let func_node = tree.root()?;
 
@@ -359,38 +1727,7 @@ impl DwarfAnalyzer {
 
             match child_entry.tag() {
                 // formal are named params with types
-                gimli::DW_TAG_formal_parameter => {
-                    let param_name = child_entry
-                        .attr(gimli::DW_AT_name)
-                        .ok()
-                        .flatten()
-                        .and_then(|attr| Self::read_attr_string(dwarf, unit, &attr))
-                        .unwrap_or_default();
-
-                    // Get parameter type TypeId
-                    let param_type_id =
-                        if let Ok(Some(type_attr)) = child_entry.attr(gimli::DW_AT_type) {
-                            if let AttributeValue::UnitRef(offset) = type_attr.value() {
-                                type_resolver.build_type_registry_entry(offset)?
-                            } else {
-                                type_resolver.get_void_type_id()?
-                            }
-                        } else {
-                            type_resolver.get_void_type_id()?
-                        };
-
-                    log::debug!(
-                        "{:>12} {:#010x}: {}",
-                        "parameter",
-                        child_entry.offset().0,
-                        param_name,
-                    );
-
-                    parameters.push(Parameter {
-                        name: param_name,
-                        type_id: param_type_id,
-                    });
-                }
+                gimli::DW_TAG_formal_parameter => offsets.push(child_entry.offset()),
 
                 // unspecified -> variadic
                 gimli::DW_TAG_unspecified_parameters => {
@@ -411,6 +1748,72 @@ impl DwarfAnalyzer {
             }
         }
 
-        Ok((parameters, is_variadic))
+        Ok((offsets, is_variadic))
+    }
+
+    /// read one formal parameter, along with the abstract instance parameter
+    /// it points at if any. name and type come from there when the entry
+    /// itself only carries a location.
+    fn read_parameter(
+        &self,
+        dwarf: &Dwarf<reader::DwarfReader>,
+        unit: &gimli::Unit<reader::DwarfReader>,
+        offset: gimli::UnitOffset,
+        type_resolver: &mut TypeResolver<reader::DwarfReader>,
+    ) -> Result<(Parameter, Option<gimli::UnitOffset>)> {
+        let entry = unit.entry(offset)?;
+
+        let param_name = Self::subprogram_attr(unit, &entry, gimli::DW_AT_name)?
+            .and_then(|attr| Self::read_attr_string(dwarf, unit, &attr))
+            .unwrap_or_default();
+        let is_artificial = Self::attr_flag_is_true(Self::subprogram_attr(
+            unit,
+            &entry,
+            gimli::DW_AT_artificial,
+        )?);
+
+        // Get parameter type TypeId
+        let param_type_id = match Self::subprogram_attr(unit, &entry, gimli::DW_AT_type)? {
+            Some(attr) => type_resolver.build_type_registry_entry_for(attr.value())?,
+            None => None,
+        };
+        let param_type_id = match param_type_id {
+            Some(id) => id,
+            None => type_resolver.get_void_type_id()?,
+        };
+
+        log::debug!("{:>12} {:#010x}: {}", "parameter", offset.0, param_name);
+
+        let origin = match entry.attr_value(gimli::DW_AT_abstract_origin)? {
+            Some(AttributeValue::UnitRef(origin)) => Some(origin),
+            _ => None,
+        };
+
+        let parameter = Parameter {
+            name: param_name,
+            type_id: param_type_id,
+            dwarf_offset: Some(offset.0 as u64),
+            is_artificial,
+        };
+        Ok((parameter, origin))
+    }
+}
+
+/// append macros whose names aren't defined yet, warning when a name is
+/// defined differently
+fn merge_macros(all_macros: &mut Vec<MacroConstant>, new_macros: Vec<MacroConstant>) {
+    for constant in new_macros {
+        match all_macros.iter().find(|m| m.name == constant.name) {
+            Some(existing) if existing.value != constant.value => log::warn!(
+                "macro {} is {:?} in {} but {:?} in {}, keeping the first",
+                constant.name,
+                existing.value,
+                existing.file,
+                constant.value,
+                constant.file
+            ),
+            Some(_) => {}
+            None => all_macros.push(constant),
+        }
     }
 }
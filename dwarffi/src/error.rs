@@ -0,0 +1,119 @@
+//! errors returned from the public API
+use crate::prelude::*;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+pub type Result<T> = core::result::Result<T, DwarffiError>;
+
+/// why analysis of a binary failed. callers can match on this to tell e.g. a
+/// missing `-g` apart from a file that isn't a binary at all.
+#[derive(Debug, thiserror::Error)]
+pub enum DwarffiError {
+    /// the file could not be opened or read
+    #[cfg(feature = "std")]
+    #[error("failed to read file: {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// the data is not a valid object file
+    #[error("failed to parse object file")]
+    ObjectParse(#[from] object::read::Error),
+
+    /// the object file has no DWARF sections; rebuild with debug info (-g)
+    #[error("no DWARF debug info found (was the library compiled with -g?)")]
+    NoDebugInfo,
+
+    /// a debug section is present but compressed in a way that could not be
+    /// undone, e.g. a truncated zlib stream
+    #[error("failed to decompress section {section}")]
+    SectionDecompress {
+        section: String,
+        #[source]
+        source: object::read::Error,
+    },
+
+    /// the DWARF data is present but could not be read
+    #[error("failed to parse DWARF debug info")]
+    DwarfParse(#[source] Box<dyn core::error::Error + Send + Sync + 'static>),
+
+    /// an analysis of several files was given none
+    #[error("no input files")]
+    NoInput,
+
+    /// a valid container that can't be analyzed directly, e.g. a dyld shared
+    /// cache or a universal (fat) Mach-O binary whose slice wasn't selected
+    #[error("unsupported file format: {0}")]
+    UnsupportedFormat(String),
+
+    /// a universal (fat) Mach-O binary has no slice for the architecture
+    #[error(
+        "no {requested} slice in universal binary (available: {})",
+        available.join(", ")
+    )]
+    ArchitectureNotFound {
+        requested: String,
+        available: Vec<String>,
+    },
+}
+
+impl From<gimli::Error> for DwarffiError {
+    #[cfg(feature = "std")]
+    fn from(e: gimli::Error) -> Self {
+        DwarffiError::DwarfParse(Box::new(e))
+    }
+
+    // gimli's errors only implement `Error` with std
+    #[cfg(not(feature = "std"))]
+    fn from(e: gimli::Error) -> Self {
+        DwarffiError::DwarfParse(e.to_string().into())
+    }
+}
+
+impl From<anyhow::Error> for DwarffiError {
+    fn from(e: anyhow::Error) -> Self {
+        DwarffiError::DwarfParse(e.into())
+    }
+}
+
+/// what reading DWARF inside the crate returns
+pub(crate) type ReadResult<T> = core::result::Result<T, ReadError>;
+
+/// an error reading DWARF, to show in a [`UnitError`](crate::UnitError) or
+/// wrap in [`DwarffiError::DwarfParse`]. anyhow's `?` only takes errors
+/// implementing `Error`, which gimli's don't without std, so they come
+/// through here.
+#[derive(Debug)]
+pub(crate) struct ReadError(anyhow::Error);
+
+impl core::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<gimli::Error> for ReadError {
+    #[cfg(feature = "std")]
+    fn from(e: gimli::Error) -> Self {
+        ReadError(e.into())
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn from(e: gimli::Error) -> Self {
+        ReadError(anyhow::Error::msg(e))
+    }
+}
+
+impl From<anyhow::Error> for ReadError {
+    fn from(e: anyhow::Error) -> Self {
+        ReadError(e)
+    }
+}
+
+impl From<ReadError> for DwarffiError {
+    fn from(e: ReadError) -> Self {
+        e.0.into()
+    }
+}
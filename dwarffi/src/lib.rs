@@ -5,16 +5,72 @@
 //! - only works on macOS and Linux
 //! - some limitations around arrays and nested types
 //! - use at your own risk!
+//!
+//! without the `std` feature (on by default through `fs`) the crate is
+//! `no_std` and needs only `alloc`: analysis works on bytes already in
+//! memory (`from_bytes`), and whatever touches files, processes or clocks is
+//! left out.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+mod c_type;
 mod dwarf_analyzer;
+mod error;
+mod lines;
+mod macros;
+mod pattern;
+#[cfg(all(feature = "fs", target_os = "linux"))]
+mod proc_maps;
+#[cfg(feature = "profiling")]
+mod profiling;
 mod reader;
+mod sip;
 mod symbol_reader;
+mod target;
 pub mod type_registry;
 mod type_resolver;
 pub mod types;
+mod validation;
+mod wasm;
 
-pub use dwarf_analyzer::{AnalysisResult, DwarfAnalyzer};
+/// what std's prelude brings in, so modules read the same without it
+mod prelude {
+    pub(crate) use alloc::boxed::Box;
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec::Vec;
+    pub(crate) use alloc::{format, vec};
+}
+
+/// std's hash maps, or hashbrown's where there is no std
+mod collections {
+    pub(crate) use alloc::collections::{BTreeMap, VecDeque};
+    #[cfg(not(feature = "std"))]
+    pub(crate) use hashbrown::{HashMap, HashSet};
+    #[cfg(feature = "std")]
+    pub(crate) use std::collections::{HashMap, HashSet};
+}
+
+pub use dwarf_analyzer::{
+    AnalysisResult, DwarfAnalyzer, ExtractOptions, UnitError, UnitProgress, Visibility,
+};
+pub use error::{DwarffiError, Result};
+pub use lines::LineRow;
+pub use macros::{MacroConstant, MacroValue};
+pub use pattern::glob_match;
+#[cfg(feature = "profiling")]
+pub use profiling::Timings;
+pub use reader::DwarfReader;
+pub use symbol_reader::SymbolInfo;
+pub use target::{Endianness, TargetInfo};
 pub use type_registry::{
-    BaseTypeKind, EnumVariant, StructField, Type, TypeId, TypeRegistry, UnionField,
+    BaseTypeKind, Discriminant, EnumVariant, FunctionPointer, MAX_DECLARATION_DEPTH,
+    POINTER_TO_MEMBER, PrimitiveEncoding, Qualifiers, ResolvedPrimitive, StructField, Type, TypeId,
+    TypeRegistry, UnionField, UnitId,
 };
-pub use types::{FunctionSignature, Parameter};
+#[cfg(feature = "rust-types")]
+pub use type_registry::{rust_fixed_width, rust_primitive};
+pub use type_resolver::TypeResolver;
+pub use types::{FunctionSignature, InlineKind, Parameter};
+pub use validation::{UnresolvedType, ValidationIssue};
@@ -0,0 +1,89 @@
+//! the line-number program (`.debug_line`): which source line each machine
+//! instruction address came from
+use crate::error::ReadResult as Result;
+use crate::prelude::*;
+use gimli::{Dwarf, Reader, Unit};
+use serde::Serialize;
+
+/// one row of a unit's line table: the code starting at `address` comes
+/// from `file`:`line`:`column`, until the next row's address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LineRow {
+    pub address: u64,
+    /// full path of the source file, if the file table names one
+    pub file: Option<String>,
+    /// 1-based, or 0 for code with no source line (compiler-generated)
+    pub line: u64,
+    /// 1-based, or 0 for the whole line
+    pub column: u64,
+    /// a recommended breakpoint location: the start of a statement
+    pub is_stmt: bool,
+}
+
+/// the rows of the unit's line program, in program order. the row that
+/// ends each sequence only marks where the last one stops, so it is left
+/// out.
+pub(crate) fn unit_line_rows<R: Reader>(dwarf: &Dwarf<R>, unit: &Unit<R>) -> Result<Vec<LineRow>> {
+    let Some(program) = unit.line_program.clone() else {
+        return Ok(Vec::new());
+    };
+
+    let mut rows = Vec::new();
+    let mut state = program.rows();
+    while let Some((header, row)) = state.next_row()? {
+        if row.end_sequence() {
+            continue;
+        }
+        let column = match row.column() {
+            gimli::ColumnType::LeftEdge => 0,
+            gimli::ColumnType::Column(column) => column.get(),
+        };
+        rows.push(LineRow {
+            address: row.address(),
+            file: file_path(dwarf, unit, header, row.file_index()),
+            line: row.line().map_or(0, |line| line.get()),
+            column,
+            is_stmt: row.is_stmt(),
+        });
+    }
+    Ok(rows)
+}
+
+/// full path of an entry in a line program's file table, completed with
+/// its include directory and the unit's compilation directory
+pub(crate) fn file_path<R: Reader>(
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
+    header: &gimli::LineProgramHeader<R>,
+    index: u64,
+) -> Option<String> {
+    let file = header.file(index)?;
+    let string = |value| {
+        dwarf
+            .attr_string(unit, value)
+            .ok()
+            .and_then(|s| s.to_string_lossy().ok().map(|s| s.into_owned()))
+    };
+
+    let name = string(file.path_name())?;
+    if name.starts_with('/') {
+        return Some(name);
+    }
+    let mut path = file.directory(header).and_then(string).unwrap_or_default();
+    if !path.starts_with('/')
+        && let Some(comp_dir) = &unit.comp_dir
+    {
+        let comp_dir = comp_dir.to_string_lossy().ok()?;
+        path = if path.is_empty() {
+            comp_dir.into_owned()
+        } else {
+            format!("{}/{}", comp_dir, path)
+        };
+    }
+
+    Some(if path.is_empty() {
+        name
+    } else {
+        format!("{}/{}", path.trim_end_matches('/'), name)
+    })
+}
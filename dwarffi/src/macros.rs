@@ -0,0 +1,494 @@
+//! object-like `#define` constants recorded in `.debug_macro` (DWARF 5 and
+//! the GNU extension) or `.debug_macinfo` (DWARF 2-4). compilers only emit
+//! these with `-g3`.
+use crate::error::ReadResult as Result;
+use crate::lines;
+use crate::prelude::*;
+use anyhow::anyhow;
+use gimli::{AttributeValue, Dwarf, Format, Reader, ReaderOffset, Unit};
+use serde::Serialize;
+
+/// a `#define NAME value` whose value is a literal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MacroConstant {
+    pub name: String,
+    pub value: MacroValue,
+    /// path of the file that defines it
+    pub file: String,
+    pub line: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum MacroValue {
+    Integer(i64),
+    /// an integer above `i64::MAX`, e.g. `0xFFFFFFFFFFFFFFFFull`
+    Unsigned(u64),
+    String(String),
+}
+
+/// the raw macro sections, loaded alongside `Dwarf`
+pub(crate) struct MacroSections<R> {
+    pub debug_macro: R,
+    pub debug_macinfo: R,
+}
+
+/// imports nest, but a cycle would be a malformed section
+const MAX_IMPORT_DEPTH: usize = 32;
+
+const DW_MACINFO_DEFINE: u8 = 0x01;
+const DW_MACINFO_UNDEF: u8 = 0x02;
+const DW_MACINFO_START_FILE: u8 = 0x03;
+const DW_MACINFO_END_FILE: u8 = 0x04;
+const DW_MACINFO_VENDOR_EXT: u8 = 0xff;
+
+/// constants defined by the unit's own sources, in definition order.
+/// built-ins, command-line definitions, and system headers are left out, as
+/// are macros `#undef`ed again before the end of the unit.
+pub(crate) fn unit_macros<R: Reader>(
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
+    sections: &MacroSections<R>,
+) -> Result<Vec<MacroConstant>> {
+    let mut entries = unit.entries();
+    let Some((_, root)) = entries.next_dfs()? else {
+        return Ok(Vec::new());
+    };
+
+    let mut parser = MacroParser {
+        dwarf,
+        unit,
+        files: Vec::new(),
+        defined: Vec::new(),
+    };
+
+    // gcc spells the DWARF 5 attribute DW_AT_GNU_macros before version 5
+    let macros = root
+        .attr_value(gimli::DW_AT_macros)?
+        .or(root.attr_value(gimli::DW_AT_GNU_macros)?);
+    match macros {
+        Some(AttributeValue::DebugMacroRef(offset)) => {
+            parser.parse_macro_unit(&sections.debug_macro, offset.0.into_u64(), 0)?
+        }
+        Some(AttributeValue::SecOffset(offset)) => {
+            parser.parse_macro_unit(&sections.debug_macro, offset.into_u64(), 0)?
+        }
+        _ => {
+            if let Some(AttributeValue::DebugMacinfoRef(offset)) =
+                root.attr_value(gimli::DW_AT_macro_info)?
+            {
+                parser.parse_macinfo(&sections.debug_macinfo, offset.0.into_u64())?;
+            }
+        }
+    }
+
+    Ok(parser.defined)
+}
+
+struct MacroParser<'a, R: Reader> {
+    dwarf: &'a Dwarf<R>,
+    unit: &'a Unit<R>,
+    /// the `start_file` stack; empty for built-in and command-line macros
+    files: Vec<Option<String>>,
+    defined: Vec<MacroConstant>,
+}
+
+impl<R: Reader> MacroParser<'_, R> {
+    /// one `.debug_macro` unit: a header, then opcodes up to a zero byte
+    fn parse_macro_unit(&mut self, section: &R, offset: u64, depth: usize) -> Result<()> {
+        if depth > MAX_IMPORT_DEPTH {
+            return Err(anyhow!("macro imports nested deeper than {}", MAX_IMPORT_DEPTH).into());
+        }
+
+        let mut input = section.clone();
+        input.skip(R::Offset::from_u64(offset)?)?;
+
+        let version = input.read_u16()?;
+        if !(4..=5).contains(&version) {
+            return Err(anyhow!("unsupported .debug_macro version {}", version).into());
+        }
+        let flags = input.read_u8()?;
+        let format = if flags & 0x1 != 0 {
+            Format::Dwarf64
+        } else {
+            Format::Dwarf32
+        };
+        if flags & 0x2 != 0 {
+            input.read_offset(format)?; // debug_line_offset
+        }
+
+        // forms of each vendor opcode's operands, so unknown ones can be skipped
+        let mut operand_forms: Vec<(u8, Vec<gimli::DwForm>)> = Vec::new();
+        if flags & 0x4 != 0 {
+            for _ in 0..input.read_u8()? {
+                let opcode = input.read_u8()?;
+                let count = input.read_uleb128()?;
+                let forms = (0..count)
+                    .map(|_| Ok(gimli::DwForm(input.read_u8()?.into())))
+                    .collect::<Result<Vec<_>>>()?;
+                operand_forms.push((opcode, forms));
+            }
+        }
+
+        loop {
+            let opcode = gimli::DwMacro(input.read_u8()?);
+            match opcode {
+                gimli::DwMacro(0) => return Ok(()),
+                gimli::DW_MACRO_define | gimli::DW_MACRO_undef => {
+                    let line = input.read_uleb128()?;
+                    let text = input.read_null_terminated_slice()?;
+                    self.apply(opcode == gimli::DW_MACRO_define, line, text)?;
+                }
+                gimli::DW_MACRO_define_strp | gimli::DW_MACRO_undef_strp => {
+                    let line = input.read_uleb128()?;
+                    let offset = input.read_offset(format)?;
+                    let text = self.dwarf.attr_string(
+                        self.unit,
+                        AttributeValue::DebugStrRef(gimli::DebugStrOffset(offset)),
+                    )?;
+                    self.apply(opcode == gimli::DW_MACRO_define_strp, line, text)?;
+                }
+                gimli::DW_MACRO_define_strx | gimli::DW_MACRO_undef_strx => {
+                    let line = input.read_uleb128()?;
+                    let index = input.read_uleb128()?;
+                    let text = self.dwarf.attr_string(
+                        self.unit,
+                        AttributeValue::DebugStrOffsetsIndex(gimli::DebugStrOffsetsIndex(
+                            R::Offset::from_u64(index)?,
+                        )),
+                    )?;
+                    self.apply(opcode == gimli::DW_MACRO_define_strx, line, text)?;
+                }
+                // strings in a supplementary object file we don't have
+                gimli::DW_MACRO_define_sup | gimli::DW_MACRO_undef_sup => {
+                    input.read_uleb128()?;
+                    input.read_offset(format)?;
+                }
+                gimli::DW_MACRO_start_file => {
+                    input.read_uleb128()?; // line
+                    let file = input.read_uleb128()?;
+                    self.files.push(self.file_name(file));
+                }
+                gimli::DW_MACRO_end_file => {
+                    self.files.pop();
+                }
+                gimli::DW_MACRO_import => {
+                    let offset = input.read_offset(format)?.into_u64();
+                    self.parse_macro_unit(section, offset, depth + 1)?;
+                }
+                gimli::DW_MACRO_import_sup => {
+                    input.read_offset(format)?;
+                }
+                _ => {
+                    let Some((_, forms)) = operand_forms.iter().find(|(op, _)| *op == opcode.0)
+                    else {
+                        return Err(anyhow!("unknown macro opcode {:#x}", opcode.0).into());
+                    };
+                    for form in forms {
+                        skip_form(&mut input, *form, format)?;
+                    }
+                }
+            }
+        }
+    }
+
+    /// the pre-DWARF 5 format: one flat list per unit, with no imports
+    fn parse_macinfo(&mut self, section: &R, offset: u64) -> Result<()> {
+        let mut input = section.clone();
+        input.skip(R::Offset::from_u64(offset)?)?;
+
+        loop {
+            match input.read_u8()? {
+                0 => return Ok(()),
+                kind @ (DW_MACINFO_DEFINE | DW_MACINFO_UNDEF) => {
+                    let line = input.read_uleb128()?;
+                    let text = input.read_null_terminated_slice()?;
+                    self.apply(kind == DW_MACINFO_DEFINE, line, text)?;
+                }
+                DW_MACINFO_START_FILE => {
+                    input.read_uleb128()?; // line
+                    let file = input.read_uleb128()?;
+                    self.files.push(self.file_name(file));
+                }
+                DW_MACINFO_END_FILE => {
+                    self.files.pop();
+                }
+                DW_MACINFO_VENDOR_EXT => {
+                    input.read_uleb128()?;
+                    input.read_null_terminated_slice()?;
+                }
+                kind => return Err(anyhow!("unknown macinfo type {:#x}", kind).into()),
+            }
+        }
+    }
+
+    /// record a `#define` or drop the macro on `#undef`
+    fn apply(&mut self, define: bool, line: u64, text: R) -> Result<()> {
+        let text = text.to_string_lossy()?;
+        let name = text
+            .split(|c: char| c == '(' || c.is_whitespace())
+            .next()
+            .unwrap_or_default();
+        self.defined.retain(|m| m.name != name);
+        if !define {
+            return Ok(());
+        }
+
+        // built-in and command-line macros come before the first start_file
+        let Some(Some(file)) = self.files.last() else {
+            return Ok(());
+        };
+        if name.starts_with('_') || is_system_header(file) {
+            return Ok(());
+        }
+
+        if let Some((name, value)) = parse_definition(&text) {
+            self.defined.push(MacroConstant {
+                name,
+                value,
+                file: file.clone(),
+                line,
+            });
+        }
+        Ok(())
+    }
+
+    /// full path of an entry in the unit's line program file table
+    fn file_name(&self, index: u64) -> Option<String> {
+        let header = self.unit.line_program.as_ref()?.header();
+        lines::file_path(self.dwarf, self.unit, header, index)
+    }
+}
+
+/// the include directories gcc and clang search for the C library and their
+/// own headers. `/usr/local/include` is searched too, but holds third-party
+/// libraries, which may well be the one being analyzed.
+const SYSTEM_INCLUDE_DIRS: &[&str] = &[
+    "/usr/include/",
+    "/usr/lib/gcc/",
+    "/usr/lib/gcc-cross/",
+    "/usr/lib64/gcc/",
+    "/usr/libexec/gcc/",
+    "/usr/lib/clang/",
+    "/usr/lib/llvm",
+    "/Library/Developer/",
+    "/Applications/Xcode",
+];
+
+/// headers from the C library and toolchain, whose constants (`INT_MAX`,
+/// `EXIT_SUCCESS`, ...) aren't the library's own
+fn is_system_header(path: &str) -> bool {
+    SYSTEM_INCLUDE_DIRS
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+}
+
+/// operands of a vendor opcode, described by the unit's operand table
+fn skip_form<R: Reader>(input: &mut R, form: gimli::DwForm, format: Format) -> Result<()> {
+    match form {
+        gimli::DW_FORM_flag | gimli::DW_FORM_data1 | gimli::DW_FORM_strx1 => {
+            input.read_u8()?;
+        }
+        gimli::DW_FORM_data2 | gimli::DW_FORM_strx2 => {
+            input.read_u16()?;
+        }
+        gimli::DW_FORM_strx3 => {
+            input.skip(R::Offset::from_u8(3))?;
+        }
+        gimli::DW_FORM_data4 | gimli::DW_FORM_strx4 => {
+            input.read_u32()?;
+        }
+        gimli::DW_FORM_data8 => {
+            input.read_u64()?;
+        }
+        gimli::DW_FORM_data16 => {
+            input.skip(R::Offset::from_u8(16))?;
+        }
+        gimli::DW_FORM_udata | gimli::DW_FORM_strx => {
+            input.read_uleb128()?;
+        }
+        gimli::DW_FORM_sdata => {
+            input.read_sleb128()?;
+        }
+        gimli::DW_FORM_sec_offset | gimli::DW_FORM_strp | gimli::DW_FORM_line_strp => {
+            input.read_offset(format)?;
+        }
+        gimli::DW_FORM_string => {
+            input.read_null_terminated_slice()?;
+        }
+        gimli::DW_FORM_block => {
+            let len = input.read_uleb128()?;
+            input.skip(R::Offset::from_u64(len)?)?;
+        }
+        gimli::DW_FORM_block1 => {
+            let len = input.read_u8()?;
+            input.skip(R::Offset::from_u8(len))?;
+        }
+        _ => return Err(anyhow!("unsupported macro operand form {}", form).into()),
+    }
+    Ok(())
+}
+
+/// name and value of an object-like `NAME value` definition with a literal
+/// value. function-like macros and other expressions give None.
+pub(crate) fn parse_definition(text: &str) -> Option<(String, MacroValue)> {
+    let name_len = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    let (name, body) = text.split_at(name_len);
+    if name.is_empty() || body.starts_with('(') {
+        return None;
+    }
+
+    let value = parse_value(body.trim())?;
+    Some((name.to_string(), value))
+}
+
+fn parse_value(body: &str) -> Option<MacroValue> {
+    let mut body = body;
+    while let Some(inner) = body.strip_prefix('(').and_then(|b| b.strip_suffix(')')) {
+        body = inner.trim();
+    }
+
+    if let Some(literal) = body.strip_prefix('"').and_then(|b| b.strip_suffix('"')) {
+        return unescape(literal).map(MacroValue::String);
+    }
+
+    let (negative, digits) = match body.strip_prefix('-') {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, body),
+    };
+    let magnitude = parse_integer(digits)?;
+    if negative {
+        return 0i64
+            .checked_sub_unsigned(magnitude)
+            .map(MacroValue::Integer);
+    }
+    Some(match i64::try_from(magnitude) {
+        Ok(value) => MacroValue::Integer(value),
+        Err(_) => MacroValue::Unsigned(magnitude),
+    })
+}
+
+/// a C integer literal, with any `u`/`l` suffix
+fn parse_integer(literal: &str) -> Option<u64> {
+    let literal = literal.trim_end_matches(['u', 'U', 'l', 'L']);
+    let (digits, radix) = if let Some(hex) = literal
+        .strip_prefix("0x")
+        .or_else(|| literal.strip_prefix("0X"))
+    {
+        (hex, 16)
+    } else if let Some(binary) = literal
+        .strip_prefix("0b")
+        .or_else(|| literal.strip_prefix("0B"))
+    {
+        (binary, 2)
+    } else if literal.len() > 1 && literal.starts_with('0') {
+        (&literal[1..], 8)
+    } else {
+        (literal, 10)
+    };
+
+    // from_str_radix accepts a leading '+', C doesn't
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    u64::from_str_radix(digits, radix).ok()
+}
+
+/// the simple escapes of a C string literal. a literal with any other escape,
+/// or that is really several concatenated literals, gives None.
+fn unescape(literal: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return None,
+            '\\' => value.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                '0' => '\0',
+                c @ ('\\' | '"' | '\'') => c,
+                _ => return None,
+            }),
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(text: &str) -> Option<i64> {
+        match parse_definition(text)? {
+            (_, MacroValue::Integer(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_integer_definitions() {
+        assert_eq!(
+            parse_definition("MAX_SIZE 1024"),
+            Some(("MAX_SIZE".to_string(), MacroValue::Integer(1024)))
+        );
+        assert_eq!(int("MASK 0x0Fu"), Some(15));
+        assert_eq!(int("MODE 0755"), Some(0o755));
+        assert_eq!(int("BITS 0b101"), Some(5));
+        assert_eq!(int("ZERO 0"), Some(0));
+        assert_eq!(int("INVALID (-1)"), Some(-1));
+        assert_eq!(int("BIG 9223372036854775807LL"), Some(i64::MAX));
+        assert_eq!(int("MIN (-9223372036854775808)"), Some(i64::MIN));
+        assert_eq!(
+            parse_definition("ALL_ONES 0xFFFFFFFFFFFFFFFFull"),
+            Some(("ALL_ONES".to_string(), MacroValue::Unsigned(u64::MAX)))
+        );
+        assert_eq!(int("TOO_SMALL (-9223372036854775809)"), None);
+    }
+
+    #[test]
+    fn test_parse_string_definitions() {
+        assert_eq!(
+            parse_definition("VERSION \"1.2.0\""),
+            Some((
+                "VERSION".to_string(),
+                MacroValue::String("1.2.0".to_string())
+            ))
+        );
+        assert_eq!(
+            parse_definition(r#"QUOTED "say \"hi\"\n""#),
+            Some((
+                "QUOTED".to_string(),
+                MacroValue::String("say \"hi\"\n".to_string())
+            ))
+        );
+        assert_eq!(parse_definition(r#"JOINED "a" "b""#), None);
+    }
+
+    #[test]
+    fn test_skip_non_literal_definitions() {
+        // function-like, empty, expressions, floats, other macros
+        assert_eq!(parse_definition("SQUARE(x) ((x) * (x))"), None);
+        assert_eq!(parse_definition("HEADER_H"), None);
+        assert_eq!(parse_definition("SHIFTED (1 << 4)"), None);
+        assert_eq!(parse_definition("PI 3.14159"), None);
+        assert_eq!(parse_definition("ALIAS MAX_SIZE"), None);
+        assert_eq!(parse_definition("PLUS +1"), None);
+    }
+
+    #[test]
+    fn test_system_headers() {
+        assert!(is_system_header("/usr/include/stdint.h"));
+        assert!(is_system_header(
+            "/Library/Developer/CommandLineTools/SDKs/MacOSX.sdk/usr/include/stdio.h"
+        ));
+        assert!(is_system_header(
+            "/usr/lib/gcc/x86_64-linux-gnu/12/include/stddef.h"
+        ));
+        assert!(!is_system_header("/home/me/project/include/lib.h"));
+        assert!(!is_system_header("/usr/local/include/mylib.h"));
+    }
+}
@@ -0,0 +1,58 @@
+//! shell-style glob matching for function names
+use crate::prelude::*;
+
+/// match `name` against a glob where `*` matches any run of characters
+/// (including none) and `?` matches exactly one character
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` seen and the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // let the last `*` swallow one more character
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    // trailing stars match the empty remainder
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*_impl", "parse_impl"));
+        assert!(glob_match("__*", "__internal"));
+        assert!(glob_match("get_?", "get_x"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(glob_match("exact", "exact"));
+
+        assert!(!glob_match("*_impl", "impl_parse"));
+        assert!(!glob_match("__*", "_single"));
+        assert!(!glob_match("get_?", "get_xy"));
+        assert!(!glob_match("exact", "exactly"));
+    }
+}
@@ -0,0 +1,69 @@
+//! the files a running process has mapped, from `/proc/PID/maps` (Linux)
+use crate::error::{DwarffiError, Result};
+use std::path::PathBuf;
+
+/// the files `pid` has mapped executable, as [`executable_mappings`] lists
+/// them
+pub(crate) fn mapped_files(pid: u32) -> Result<Vec<PathBuf>> {
+    let path = PathBuf::from(format!("/proc/{}/maps", pid));
+    let maps =
+        std::fs::read_to_string(&path).map_err(|source| DwarffiError::Io { path, source })?;
+    Ok(executable_mappings(&maps))
+}
+
+/// paths of the files mapped executable in a `/proc/PID/maps` listing, in
+/// the order they are first mapped: the executable, then its libraries.
+/// anonymous and JIT regions, pseudo-files like `[vdso]` and files deleted
+/// since they were mapped are left out.
+pub(crate) fn executable_mappings(maps: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for line in maps.lines() {
+        // address perms offset dev inode, then the path padded with spaces
+        let fields: Vec<&str> = line.splitn(6, ' ').collect();
+        let [_, perms, _, _, inode, path] = fields.as_slice() else {
+            continue;
+        };
+        let path = path.trim_start();
+        if !perms.contains('x')
+            || *inode == "0"
+            || !path.starts_with('/')
+            || path.ends_with(" (deleted)")
+        {
+            continue;
+        }
+
+        let path = PathBuf::from(path);
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_file_backed_executable_mappings() {
+        let maps = "\
+55d0c8a00000-55d0c8a01000 r--p 00000000 08:01 1311 /usr/bin/app
+55d0c8a01000-55d0c8a02000 r-xp 00001000 08:01 1311 /usr/bin/app
+55d0c9b00000-55d0c9b21000 rw-p 00000000 00:00 0                          [heap]
+7f1a2c000000-7f1a2c021000 rwxp 00000000 00:00 0 
+7f1a2d028000-7f1a2d1bd000 r-xp 00028000 08:01 2049 /usr/lib/libc.so.6
+7f1a2d200000-7f1a2d201000 r-xp 00001000 08:01 4097 /opt/my lib/libfoo.so
+7f1a2d300000-7f1a2d301000 r-xp 00001000 08:01 4098 /tmp/libold.so (deleted)
+7f1a2d400000-7f1a2d401000 r-xp 00000000 08:01 2049 /usr/lib/libc.so.6
+7ffd4b7f0000-7ffd4b7f2000 r-xp 00000000 00:00 0                          [vdso]
+";
+        assert_eq!(
+            executable_mappings(maps),
+            [
+                PathBuf::from("/usr/bin/app"),
+                PathBuf::from("/usr/lib/libc.so.6"),
+                PathBuf::from("/opt/my lib/libfoo.so"),
+            ]
+        );
+    }
+}
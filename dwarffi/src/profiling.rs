@@ -0,0 +1,89 @@
+//! wall-clock time spent in each phase of an extraction, recorded with the
+//! `profiling` feature to see where a slow binary spends it
+use core::fmt;
+use std::time::Duration;
+
+/// where an extraction spent its time. results merged from several files
+/// (or archive members) add their timings together.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// loading the DWARF sections from the object file
+    pub section_load: Duration,
+    /// extracting each compilation unit's functions, in unit order. this
+    /// includes resolving the types they use.
+    pub units: Vec<Duration>,
+    /// resolving types through `TypeResolver::build_type_registry_entry`,
+    /// summed over every unit
+    pub type_resolution: Duration,
+    /// merging unit registries, and results of several files, into one
+    pub merge: Duration,
+    /// `build_type_registry_entry` calls that registered no new type, the
+    /// type having been resolved before
+    pub type_cache_hits: usize,
+    /// `build_type_registry_entry` calls that extracted at least one type
+    pub type_cache_misses: usize,
+}
+
+impl Timings {
+    /// add the timings of another extraction, e.g. of a merged file
+    pub fn add(&mut self, other: &Timings) {
+        self.section_load += other.section_load;
+        self.units.extend_from_slice(&other.units);
+        self.type_resolution += other.type_resolution;
+        self.merge += other.merge;
+        self.type_cache_hits += other.type_cache_hits;
+        self.type_cache_misses += other.type_cache_misses;
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let slowest = self.units.iter().max().copied().unwrap_or_default();
+        write!(
+            f,
+            "section load {:?}, {} units in {:?} (slowest {:?}), type resolution {:?} \
+             ({} cache hits, {} misses), merge {:?}",
+            self.section_load,
+            self.units.len(),
+            self.units.iter().sum::<Duration>(),
+            slowest,
+            self.type_resolution,
+            self.type_cache_hits,
+            self.type_cache_misses,
+            self.merge
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_merges_units_in_order() {
+        let ms = Duration::from_millis;
+        let mut timings = Timings {
+            section_load: ms(1),
+            units: vec![ms(2)],
+            type_cache_hits: 3,
+            ..Default::default()
+        };
+        timings.add(&Timings {
+            section_load: ms(4),
+            units: vec![ms(5), ms(6)],
+            merge: ms(7),
+            type_cache_misses: 1,
+            ..Default::default()
+        });
+
+        assert_eq!(timings.section_load, ms(5));
+        assert_eq!(timings.units, [ms(2), ms(5), ms(6)]);
+        assert_eq!(timings.merge, ms(7));
+        assert_eq!((timings.type_cache_hits, timings.type_cache_misses), (3, 1));
+        assert!(
+            timings
+                .to_string()
+                .contains("3 units in 13ms (slowest 6ms)")
+        );
+    }
+}
@@ -1,61 +1,299 @@
 //! Load files and read them with DWARF
-use anyhow::{Context, Result};
+use crate::collections::HashMap;
+use crate::error::{DwarffiError, Result};
+use crate::prelude::*;
+use crate::target::{HOST_ARCHITECTURE, architecture_name, normalize_architecture_name};
+use crate::wasm::{self, WasmModule};
+use alloc::borrow::Cow;
 use gimli::{EndianRcSlice, RunTimeEndian};
-use object::{Object, ObjectSection};
+use object::read::archive::ArchiveFile;
+use object::read::macho::{FatArch, MachOFatFile32, MachOFatFile64};
+use object::{
+    FileKind, Object, ObjectKind, ObjectSection, ObjectSymbol, RelocationKind, RelocationTarget,
+};
+/// the gimli reader [`DwarfAnalyzer`](crate::DwarfAnalyzer) reads sections
+/// with: shared section data in the file's byte order
 pub type DwarfReader = EndianRcSlice<RunTimeEndian>;
 
+#[cfg(feature = "fs")]
 pub fn load_file(path: &std::path::Path) -> Result<Vec<u8>> {
     log::debug!("load file: {}", path.display());
 
-    let file = std::fs::File::open(path)
-        .with_context(|| format!("failed to open file: {}", path.display()))?;
+    let io_error = |source| DwarffiError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let file = std::fs::File::open(path).map_err(io_error)?;
 
-    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    let mmap = unsafe { memmap2::Mmap::map(&file).map_err(io_error)? };
     let data = mmap.to_vec();
 
     log::debug!("file load success, size: {} bytes", data.len());
     Ok(data)
 }
 
-pub fn object_section_loader(
-    data: &[u8],
-) -> Result<impl Fn(gimli::SectionId) -> Result<DwarfReader>> {
-    let object_file = object::File::parse(data)?;
-    log::debug!("parse object file success");
-    let endianness = if object_file.is_little_endian() {
-        RunTimeEndian::Little
-    } else {
-        RunTimeEndian::Big
+/// pick one architecture's slice out of a universal (fat) Mach-O binary,
+/// defaulting to the host architecture. other files are returned unchanged.
+pub fn select_slice(data: Vec<u8>, arch: Option<&str>) -> Result<Vec<u8>> {
+    // anything unparseable is left for `parse_object` to report
+    let slices = match FileKind::parse(&data[..]) {
+        Ok(FileKind::MachOFat32) => fat_slices(MachOFatFile32::parse(&data[..])?.arches(), &data)?,
+        Ok(FileKind::MachOFat64) => fat_slices(MachOFatFile64::parse(&data[..])?.arches(), &data)?,
+        _ => return Ok(data),
     };
 
-    let load_section = move |id: gimli::SectionId| -> Result<DwarfReader> {
-        let section_name = id.name();
-        let section_data = match object_file.section_by_name(section_name) {
+    let requested = normalize_architecture_name(arch.unwrap_or(HOST_ARCHITECTURE));
+    match slices.iter().find(|(name, _)| *name == requested) {
+        Some((_, slice)) => {
+            log::debug!("selected {} slice ({} bytes)", requested, slice.len());
+            Ok(slice.to_vec())
+        }
+        None => Err(DwarffiError::ArchitectureNotFound {
+            requested,
+            available: slices.into_iter().map(|(name, _)| name).collect(),
+        }),
+    }
+}
+
+fn fat_slices<'data, A: FatArch>(
+    arches: &[A],
+    data: &'data [u8],
+) -> Result<Vec<(String, &'data [u8])>> {
+    arches
+        .iter()
+        .map(|arch| Ok((architecture_name(arch.architecture()), arch.data(data)?)))
+        .collect()
+}
+
+/// a static archive member's name and contents
+pub type ArchiveMember<'data> = (String, &'data [u8]);
+
+/// the members of an `ar` static archive, or None if `data` isn't one. the
+/// archive's symbol table is not a member.
+pub fn archive_members(data: &[u8]) -> Result<Option<Vec<ArchiveMember<'_>>>> {
+    if !matches!(FileKind::parse(data), Ok(FileKind::Archive)) {
+        return Ok(None);
+    }
+
+    let archive = ArchiveFile::parse(data)?;
+    let mut members = Vec::new();
+    for member in archive.members() {
+        let member = member?;
+        let name = String::from_utf8_lossy(member.name()).into_owned();
+        members.push((name, member.data(data)?));
+    }
+    log::debug!("archive with {} members", members.len());
+    Ok(Some(members))
+}
+
+/// parse the object file, rejecting containers that hold more than one object
+pub fn parse_object(data: &[u8]) -> Result<object::File<'_>> {
+    match FileKind::parse(data)? {
+        kind @ (FileKind::Archive
+        | FileKind::MachOFat32
+        | FileKind::MachOFat64
+        | FileKind::DyldCache) => Err(DwarffiError::UnsupportedFormat(format!("{:?}", kind))),
+        _ => Ok(object::File::parse(data)?),
+    }
+}
+
+/// true if the object file carries a non-empty .debug_info section
+pub fn has_debug_info(data: &[u8]) -> Result<bool> {
+    let section_name = gimli::SectionId::DebugInfo.name();
+    if wasm::is_wasm(data) {
+        let module = WasmModule::parse(data)?;
+        return Ok(module
+            .section(section_name)
+            .is_some_and(|section| !section.is_empty()));
+    }
+
+    let object_file = parse_object(data)?;
+    Ok(find_section(&object_file, section_name).is_some_and(|section| section.size() > 0))
+}
+
+/// the section gimli calls `section_name` (`.debug_info`, ...). on Mach-O
+/// it is `__debug_info` in the `__DWARF` segment, cut to 16 bytes;
+/// `section_by_name` knows that, and GNU `.zdebug_*` names, and any other
+/// spelling is matched by its normalized name.
+fn find_section<'data, 'file>(
+    object_file: &'file object::File<'data>,
+    section_name: &str,
+) -> Option<object::Section<'data, 'file>> {
+    object_file.section_by_name(section_name).or_else(|| {
+        object_file.sections().find(|section| {
+            section
+                .name()
+                .is_ok_and(|name| section_name_matches(name, section_name))
+        })
+    })
+}
+
+/// Mach-O section names hold at most 16 bytes
+const MACHO_SECTION_NAME_LEN: usize = 16;
+
+/// whether `name` is the gimli section `section_name`, ignoring a
+/// `segment,` qualifier and the `.` or `__` prefix, and allowing for a
+/// Mach-O name cut to 16 bytes
+fn section_name_matches(name: &str, section_name: &str) -> bool {
+    fn normalize(name: &str) -> &str {
+        let name = name.rsplit(',').next().unwrap_or(name);
+        name.strip_prefix("__")
+            .or_else(|| name.strip_prefix('.'))
+            .unwrap_or(name)
+    }
+
+    let (name, wanted) = (normalize(name), normalize(section_name));
+    if name == wanted {
+        return true;
+    }
+    let truncated = MACHO_SECTION_NAME_LEN - "__".len();
+    name.len() == truncated && wanted.starts_with(name)
+}
+
+/// where DWARF sections are read from: an object file's sections, or the
+/// custom sections of a wasm module
+enum SectionSource<'data> {
+    Object(Box<object::File<'data>>),
+    Wasm(WasmModule<'data>),
+}
+
+impl<'data> SectionSource<'data> {
+    /// contents of the named section, empty if it does not exist
+    fn section_data(&self, section_name: &str) -> Result<Cow<'data, [u8]>> {
+        let object_file = match self {
+            SectionSource::Object(object_file) => object_file,
+            SectionSource::Wasm(module) => {
+                let section_data = module.section(section_name).unwrap_or_else(|| {
+                    log::debug!("section not found: {}", section_name);
+                    &[]
+                });
+                return Ok(Cow::Borrowed(section_data));
+            }
+        };
+
+        // `section_by_name` also finds GNU-style `.zdebug_*` sections, and
+        // `uncompressed_data` inflates those as well as `SHF_COMPRESSED` ones
+        match find_section(object_file, section_name) {
             Some(section) => {
                 log::debug!(
-                    "load section: {} (size: {} bytes)",
+                    "load section: {} (size: {} bytes, compression: {:?})",
                     section_name,
-                    section.size()
+                    section.size(),
+                    section.compressed_file_range().map(|r| r.format)
                 );
-                match section.uncompressed_data() {
-                    Ok(data) => data,
-                    // could not decompress
-                    Err(_) => {
-                        log::warn!("decompress section fail, section: {}", section_name);
-                        std::borrow::Cow::Borrowed(&[][..])
+                // a section that is present but unreadable would otherwise
+                // look like a library with no types
+                let section_data = section.uncompressed_data().map_err(|source| {
+                    DwarffiError::SectionDecompress {
+                        section: section_name.to_string(),
+                        source,
                     }
+                })?;
+                if object_file.kind() != ObjectKind::Relocatable {
+                    return Ok(section_data);
                 }
+                // in a `.o` the offsets into `.debug_str` and friends are
+                // only filled in by the linker
+                let mut section_data = section_data.into_owned();
+                relocate(object_file, &section, &mut section_data);
+                Ok(Cow::Owned(section_data))
             }
             // name does not exist
             None => {
                 log::debug!("section not found: {}", section_name);
-                std::borrow::Cow::Borrowed(&[][..])
+                Ok(Cow::Borrowed(&[][..]))
             }
+        }
+    }
+}
+
+/// apply the absolute relocations of a relocatable object's section, the
+/// only kind DWARF sections carry
+fn relocate(object_file: &object::File<'_>, section: &object::Section<'_, '_>, data: &mut [u8]) {
+    for (offset, relocation) in section.relocations() {
+        if relocation.kind() != RelocationKind::Absolute {
+            log::debug!("skip {:?} relocation at {:#x}", relocation.kind(), offset);
+            continue;
+        }
+        let base = match relocation.target() {
+            RelocationTarget::Symbol(index) => match object_file.symbol_by_index(index) {
+                Ok(symbol) => symbol.address(),
+                Err(_) => continue,
+            },
+            RelocationTarget::Section(index) => match object_file.section_by_index(index) {
+                Ok(section) => section.address(),
+                Err(_) => continue,
+            },
+            _ => continue,
         };
 
+        let size = usize::from(relocation.size() / 8);
+        let Some(field) = usize::try_from(offset)
+            .ok()
+            .filter(|_| (1..=8).contains(&size))
+            .and_then(|start| data.get_mut(start..start.checked_add(size)?))
+        else {
+            log::debug!("unsupported relocation at {:#x}", offset);
+            continue;
+        };
+        let little = object_file.is_little_endian();
+        let mut addend = relocation.addend();
+        if relocation.has_implicit_addend() {
+            addend = addend.wrapping_add(read_uint(field, little) as i64);
+        }
+        write_uint(field, base.wrapping_add(addend as u64), little);
+    }
+}
+
+/// an unsigned field of up to 8 bytes
+fn read_uint(field: &[u8], little: bool) -> u64 {
+    let mut bytes = [0; 8];
+    if little {
+        bytes[..field.len()].copy_from_slice(field);
+        u64::from_le_bytes(bytes)
+    } else {
+        bytes[8 - field.len()..].copy_from_slice(field);
+        u64::from_be_bytes(bytes)
+    }
+}
+
+fn write_uint(field: &mut [u8], value: u64, little: bool) {
+    let len = field.len();
+    if little {
+        field.copy_from_slice(&value.to_le_bytes()[..len]);
+    } else {
+        field.copy_from_slice(&value.to_be_bytes()[8 - len..]);
+    }
+}
+
+pub fn object_section_loader(
+    data: &[u8],
+) -> Result<impl Fn(gimli::SectionId) -> Result<DwarfReader>> {
+    let (source, endianness) = if wasm::is_wasm(data) {
+        log::debug!("parse wasm module");
+        // wasm is always little endian
+        (
+            SectionSource::Wasm(WasmModule::parse(data)?),
+            RunTimeEndian::Little,
+        )
+    } else {
+        let object_file = parse_object(data)?;
+        log::debug!("parse object file success");
+        let endianness = if object_file.is_little_endian() {
+            RunTimeEndian::Little
+        } else {
+            RunTimeEndian::Big
+        };
+        (SectionSource::Object(Box::new(object_file)), endianness)
+    };
+
+    let load_section = move |id: gimli::SectionId| -> Result<DwarfReader> {
+        let section_data = source.section_data(id.name())?;
+
         // copies out of section data
         let owned_data = section_data.into_owned();
-        let rc_data = std::rc::Rc::from(owned_data);
+        let rc_data = alloc::rc::Rc::from(owned_data);
         let reader = EndianRcSlice::new(rc_data, endianness);
 
         Ok(reader)
@@ -63,3 +301,94 @@ pub fn object_section_loader(
 
     Ok(load_section)
 }
+
+/// a loader over DWARF sections handed over as bytes, with no object file
+/// around them. a section not in `sections` reads as empty.
+pub fn provided_section_loader(
+    sections: &HashMap<gimli::SectionId, Vec<u8>>,
+    endianness: RunTimeEndian,
+) -> impl Fn(gimli::SectionId) -> Result<DwarfReader> + '_ {
+    move |id| {
+        let section_data = sections.get(&id).map_or(&[][..], Vec::as_slice);
+        Ok(EndianRcSlice::new(
+            alloc::rc::Rc::from(section_data),
+            endianness,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CPU_TYPE_X86_64: u32 = 0x0100_0007;
+    const CPU_TYPE_ARM64: u32 = 0x0100_000c;
+
+    /// a 32-bit fat header with one slice per (cputype, payload)
+    fn fat_binary(slices: &[(u32, &[u8])]) -> Vec<u8> {
+        const ALIGN: u32 = 4; // 2^4
+        let mut data = Vec::new();
+        data.extend_from_slice(&0xcafe_babe_u32.to_be_bytes());
+        data.extend_from_slice(&(slices.len() as u32).to_be_bytes());
+
+        let mut offset = (8 + 20 * slices.len()).next_multiple_of(1 << ALIGN);
+        let mut payloads = Vec::new();
+        for (cputype, payload) in slices {
+            for field in [*cputype, 0, offset as u32, payload.len() as u32, ALIGN] {
+                data.extend_from_slice(&field.to_be_bytes());
+            }
+            payloads.push((offset, *payload));
+            offset = (offset + payload.len()).next_multiple_of(1 << ALIGN);
+        }
+        for (offset, payload) in payloads {
+            data.resize(offset, 0);
+            data.extend_from_slice(payload);
+        }
+        data
+    }
+
+    #[test]
+    fn test_select_slice_from_fat_binary() {
+        let data = fat_binary(&[(CPU_TYPE_X86_64, b"intel"), (CPU_TYPE_ARM64, b"apple")]);
+
+        let x86 = select_slice(data.clone(), Some("x86_64")).unwrap();
+        assert_eq!(x86, b"intel");
+        let arm = select_slice(data.clone(), Some("arm64")).unwrap();
+        assert_eq!(arm, b"apple");
+
+        match select_slice(data, Some("riscv64")) {
+            Err(DwarffiError::ArchitectureNotFound {
+                requested,
+                available,
+            }) => {
+                assert_eq!(requested, "riscv64");
+                assert_eq!(available, ["x86_64", "aarch64"]);
+            }
+            other => panic!("expected ArchitectureNotFound, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_section_name_matches() {
+        assert!(section_name_matches("__debug_info", ".debug_info"));
+        assert!(section_name_matches("__DWARF,__debug_line", ".debug_line"));
+        assert!(section_name_matches(
+            "__debug_str_offs",
+            ".debug_str_offsets"
+        ));
+        assert!(section_name_matches(".debug_abbrev", ".debug_abbrev"));
+
+        assert!(!section_name_matches("__debug_str", ".debug_str_offsets"));
+        assert!(!section_name_matches("__debug_info", ".debug_line"));
+        assert!(!section_name_matches("__text", ".debug_info"));
+    }
+
+    #[test]
+    fn test_fat_binary_needs_slice_selection() {
+        let data = fat_binary(&[(CPU_TYPE_X86_64, b"intel")]);
+        assert!(matches!(
+            parse_object(&data),
+            Err(DwarffiError::UnsupportedFormat(_))
+        ));
+    }
+}
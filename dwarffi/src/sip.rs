@@ -0,0 +1,121 @@
+//! SipHash-1-3 with zero keys, the hash behind std's `DefaultHasher`. type
+//! ids are computed with it so they stay the same with or without std, and
+//! don't change if std ever picks another algorithm.
+use core::hash::Hasher;
+
+#[derive(Debug, Clone)]
+pub(crate) struct SipHasher13 {
+    v0: u64,
+    v1: u64,
+    v2: u64,
+    v3: u64,
+    /// bytes written that don't yet fill a word, little endian
+    tail: u64,
+    /// how many of them there are
+    tail_len: usize,
+    length: usize,
+}
+
+impl SipHasher13 {
+    pub(crate) fn new() -> Self {
+        Self {
+            v0: 0x736f_6d65_7073_6575,
+            v1: 0x646f_7261_6e64_6f6d,
+            v2: 0x6c79_6765_6e65_7261,
+            v3: 0x7465_6462_7974_6573,
+            tail: 0,
+            tail_len: 0,
+            length: 0,
+        }
+    }
+
+    fn round(&mut self) {
+        self.v0 = self.v0.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(13) ^ self.v0;
+        self.v0 = self.v0.rotate_left(32);
+        self.v2 = self.v2.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(16) ^ self.v2;
+        self.v0 = self.v0.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(21) ^ self.v0;
+        self.v2 = self.v2.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(17) ^ self.v2;
+        self.v2 = self.v2.rotate_left(32);
+    }
+
+    fn compress(&mut self, word: u64) {
+        self.v3 ^= word;
+        self.round();
+        self.v0 ^= word;
+    }
+}
+
+impl Hasher for SipHasher13 {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.length += bytes.len();
+
+        // top up the bytes left over from the last write to a word first
+        if self.tail_len > 0 {
+            let fill = bytes.len().min(8 - self.tail_len);
+            self.tail |= load_le(&bytes[..fill]) << (8 * self.tail_len);
+            self.tail_len += fill;
+            bytes = &bytes[fill..];
+            if self.tail_len < 8 {
+                return;
+            }
+            self.compress(self.tail);
+        }
+
+        let mut words = bytes.chunks_exact(8);
+        for word in &mut words {
+            self.compress(u64::from_le_bytes(word.try_into().unwrap()));
+        }
+        self.tail = load_le(words.remainder());
+        self.tail_len = words.remainder().len();
+    }
+
+    fn finish(&self) -> u64 {
+        let mut state = self.clone();
+        state.compress(((self.length as u64 & 0xff) << 56) | self.tail);
+        state.v2 ^= 0xff;
+        for _ in 0..3 {
+            state.round();
+        }
+        state.v0 ^ state.v1 ^ state.v2 ^ state.v3
+    }
+}
+
+/// fewer than 8 bytes as a little-endian word
+fn load_le(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |word, &byte| word << 8 | u64::from(byte))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use core::hash::Hash;
+    use std::collections::hash_map::DefaultHasher;
+
+    #[test]
+    fn test_matches_the_default_hasher() {
+        for len in [0, 1, 7, 8, 9, 16, 63, 200] {
+            let bytes: Vec<u8> = (0..len).map(|i| (i * 37 + 11) as u8).collect();
+            let mut sip = SipHasher13::new();
+            let mut default = DefaultHasher::new();
+            bytes.hash(&mut sip);
+            bytes.hash(&mut default);
+            assert_eq!(sip.finish(), default.finish(), "{} bytes", len);
+
+            // the same bytes in uneven writes
+            let mut sip = SipHasher13::new();
+            let mut default = DefaultHasher::new();
+            for piece in bytes.chunks(3) {
+                sip.write(piece);
+                default.write(piece);
+            }
+            assert_eq!(sip.finish(), default.finish(), "{} bytes in threes", len);
+        }
+    }
+}
@@ -1,36 +1,84 @@
-use anyhow::{Context, Result};
+use crate::collections::HashMap;
+use crate::error::Result;
+use crate::prelude::*;
+use crate::reader;
+use crate::wasm::{self, WasmModule};
+use object::read::elf::{ElfFile, FileHeader};
 use object::{Object, ObjectSymbol};
-use std::collections::HashSet;
+
+/// a function symbol defined by the library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolInfo {
+    /// the name without its GNU version suffix, as DWARF has it
+    pub name: String,
+    pub is_weak: bool,
+    pub is_global: bool,
+    /// GNU symbol version, e.g. `GLIBC_2.17` for `memcpy@@GLIBC_2.17`
+    pub version: Option<String>,
+}
 
 /// Extracts exported function symbols from a dynamic library
-pub struct SymbolReader<'data> {
-    object_file: object::File<'data>,
+pub enum SymbolReader<'data> {
+    Object(Box<object::File<'data>>),
+    /// wasm modules have no symbol table; their exports are the public API
+    Wasm(WasmModule<'data>),
 }
 
 impl<'data> SymbolReader<'data> {
     pub fn new(data: &'data [u8]) -> Result<Self> {
         log::debug!("create symbol reader for {} bytes", data.len());
-        let object_file = object::File::parse(data).context("failed to parse object file")?;
+        if wasm::is_wasm(data) {
+            return Ok(Self::Wasm(WasmModule::parse(data)?));
+        }
+        let object_file = reader::parse_object(data)?;
 
         log::debug!("object file format: {:?}", object_file.format());
-        Ok(Self { object_file })
+        Ok(Self::Object(Box::new(object_file)))
     }
 
-    /// get unique symbol names
-    pub fn get_exported_symbols(&self) -> Result<HashSet<String>> {
-        let mut symbols = HashSet::new();
+    /// defined function symbols the library exports, one per name. names
+    /// have GNU version suffixes (`func@@VERS`) split off into `version`,
+    /// so they match DWARF names. where a name is defined more than once,
+    /// e.g. at several versions, the default version or a strong symbol wins.
+    pub fn get_exported_symbols(&self) -> Result<Vec<SymbolInfo>> {
+        let object_file = match self {
+            Self::Object(object_file) => object_file,
+            Self::Wasm(module) => {
+                let symbols: Vec<SymbolInfo> = module
+                    .function_exports()
+                    .map(|name| SymbolInfo {
+                        name: name.to_string(),
+                        is_weak: false,
+                        is_global: true,
+                        version: None,
+                    })
+                    .collect();
+                log::info!("total exported wasm functions found: {}", symbols.len());
+                return Ok(symbols);
+            }
+        };
+        let mut symbols = ExportedSymbols::default();
 
         log::debug!("check dynamic symbols");
         let mut dynamic_count = 0;
 
+        // `.dynsym` names don't carry versions; `.gnu.version` has them
+        let versions = dynamic_symbol_versions(object_file);
+
         // try dynamic symbols first
-        for symbol in self.object_file.dynamic_symbols() {
+        for symbol in object_file.dynamic_symbols() {
             dynamic_count += 1;
-            if symbol.is_definition() && symbol.kind() == object::SymbolKind::Text
+            if symbol.is_definition()
+                && symbol.kind() == object::SymbolKind::Text
                 && let Ok(name) = symbol.name()
             {
                 log::trace!("symbol: {}", name);
-                symbols.insert(name.to_string());
+                let (name, suffix) = split_version(name);
+                let (version, is_default) = match versions.get(&symbol.index().0) {
+                    Some((version, is_hidden)) => (Some(version.as_str()), Some(!is_hidden)),
+                    None => suffix.unzip(),
+                };
+                symbols.insert(&symbol, name, version, is_default.unwrap_or(true));
             }
         }
 
@@ -45,7 +93,7 @@ impl<'data> SymbolReader<'data> {
             log::debug!("no dynamic symbols found, check regular symbol table");
             let mut regular_count = 0;
 
-            for symbol in self.object_file.symbols() {
+            for symbol in object_file.symbols() {
                 regular_count += 1;
                 if symbol.is_definition() && symbol.kind() == object::SymbolKind::Text {
                     // if global, then its exported.
@@ -53,7 +101,9 @@ impl<'data> SymbolReader<'data> {
                         && let Ok(name) = symbol.name()
                     {
                         log::trace!("regular symbol: {}", name);
-                        symbols.insert(name.to_string());
+                        let (name, suffix) = split_version(name);
+                        let (version, is_default) = suffix.unzip();
+                        symbols.insert(&symbol, name, version, is_default.unwrap_or(true));
                     }
                 }
             }
@@ -66,6 +116,156 @@ impl<'data> SymbolReader<'data> {
         }
 
         log::info!("total exported function symbols found: {}", symbols.len());
-        Ok(symbols)
+        Ok(symbols.into_vec())
+    }
+
+    /// addresses of every defined function symbol, from both symbol tables,
+    /// by name. a name has several when `static` functions of different
+    /// files share it, or in an object file, where each is an offset into
+    /// its own section.
+    pub fn get_function_addresses(&self) -> HashMap<String, Vec<u64>> {
+        let mut addresses: HashMap<String, Vec<u64>> = HashMap::new();
+        let Self::Object(object_file) = self else {
+            return addresses;
+        };
+        for symbol in object_file.symbols().chain(object_file.dynamic_symbols()) {
+            if symbol.is_definition()
+                && symbol.kind() == object::SymbolKind::Text
+                && let Ok(name) = symbol.name()
+            {
+                let known = addresses
+                    .entry(split_version(name).0.to_string())
+                    .or_default();
+                if !known.contains(&symbol.address()) {
+                    known.push(symbol.address());
+                }
+            }
+        }
+
+        log::debug!("found addresses for {} function symbols", addresses.len());
+        addresses
+    }
+
+    /// true for object files (`.o`), whose DWARF addresses are unrelocated
+    pub fn is_relocatable(&self) -> bool {
+        match self {
+            Self::Object(object_file) => object_file.kind() == object::ObjectKind::Relocatable,
+            Self::Wasm(_) => false,
+        }
+    }
+}
+
+/// exported symbols by name, keeping the best definition of each
+#[derive(Default)]
+struct ExportedSymbols {
+    /// (symbol, is the default version), in the order first seen
+    symbols: Vec<(SymbolInfo, bool)>,
+    index: HashMap<String, usize>,
+}
+
+impl ExportedSymbols {
+    fn insert<'a>(
+        &mut self,
+        symbol: &impl ObjectSymbol<'a>,
+        name: &str,
+        version: Option<&str>,
+        is_default: bool,
+    ) {
+        let info = SymbolInfo {
+            name: name.to_string(),
+            is_weak: symbol.is_weak(),
+            is_global: symbol.is_global(),
+            version: version.map(str::to_string),
+        };
+
+        match self.index.get(name) {
+            // `func@VERS` is an older version kept for compatibility, and a
+            // weak definition is one a strong one overrides
+            Some(&i) => {
+                let (existing, existing_default) = &self.symbols[i];
+                if (is_default, !info.is_weak) > (*existing_default, !existing.is_weak) {
+                    self.symbols[i] = (info, is_default);
+                }
+            }
+            None => {
+                self.index.insert(name.to_string(), self.symbols.len());
+                self.symbols.push((info, is_default));
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    fn into_vec(self) -> Vec<SymbolInfo> {
+        self.symbols.into_iter().map(|(symbol, _)| symbol).collect()
+    }
+}
+
+/// split a GNU version suffix off a symbol name: `func@@VERS` is the
+/// default version of `func` and `func@VERS` a hidden, older one. returns
+/// the name and, if versioned, (version, is default).
+fn split_version(name: &str) -> (&str, Option<(&str, bool)>) {
+    match name.split_once('@') {
+        Some((base, version)) => match version.strip_prefix('@') {
+            Some(version) => (base, Some((version, true))),
+            None => (base, Some((version, false))),
+        },
+        None => (name, None),
+    }
+}
+
+/// version and whether it is hidden (`@` rather than `@@`) of each ELF
+/// dynamic symbol that has one, by symbol index
+fn dynamic_symbol_versions(object_file: &object::File) -> HashMap<usize, (String, bool)> {
+    match object_file {
+        object::File::Elf32(elf) => elf_symbol_versions(elf),
+        object::File::Elf64(elf) => elf_symbol_versions(elf),
+        _ => HashMap::new(),
+    }
+}
+
+fn elf_symbol_versions<Elf: FileHeader>(elf: &ElfFile<'_, Elf>) -> HashMap<usize, (String, bool)> {
+    let mut versions = HashMap::new();
+    let endian = elf.endian();
+    let table = match elf.elf_section_table().versions(endian, elf.data()) {
+        Ok(Some(table)) => table,
+        Ok(None) => return versions,
+        Err(e) => {
+            log::warn!("unreadable symbol version table: {}", e);
+            return versions;
+        }
+    };
+
+    for index in 0..elf.elf_dynamic_symbol_table().len() {
+        let version_index = table.version_index(endian, object::SymbolIndex(index));
+        if let Ok(Some(version)) = table.version(version_index) {
+            let name = String::from_utf8_lossy(version.name()).into_owned();
+            versions.insert(index, (name, version_index.is_hidden()));
+        }
+    }
+    versions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_version() {
+        assert_eq!(split_version("add"), ("add", None));
+        assert_eq!(
+            split_version("memcpy@@GLIBC_2.14"),
+            ("memcpy", Some(("GLIBC_2.14", true)))
+        );
+        assert_eq!(
+            split_version("memcpy@GLIBC_2.2.5"),
+            ("memcpy", Some(("GLIBC_2.2.5", false)))
+        );
     }
 }
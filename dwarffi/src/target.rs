@@ -0,0 +1,165 @@
+//! target architecture metadata for the analyzed binary
+use crate::error::Result;
+use crate::prelude::*;
+use crate::reader;
+use crate::wasm::{self, WasmModule};
+use object::{Architecture, Object};
+use serde::Serialize;
+
+/// byte order of the analyzed binary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    /// byte order of the machine running this code
+    pub fn host() -> Self {
+        if cfg!(target_endian = "little") {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        }
+    }
+}
+
+impl core::fmt::Display for Endianness {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Endianness::Little => write!(f, "little"),
+            Endianness::Big => write!(f, "big"),
+        }
+    }
+}
+
+/// the architecture this code runs on
+#[cfg(feature = "std")]
+pub(crate) const HOST_ARCHITECTURE: &str = std::env::consts::ARCH;
+/// without std the host isn't known, so no architecture matches it
+#[cfg(not(feature = "std"))]
+pub(crate) const HOST_ARCHITECTURE: &str = "";
+
+/// architecture, pointer width and byte order of the analyzed binary.
+/// architecture names follow rust's `std::env::consts::ARCH` where possible
+/// (e.g. "x86_64", "aarch64") so they can be compared against the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetInfo {
+    pub architecture: String,
+    pub pointer_width: usize, // in bytes
+    pub endianness: Endianness,
+}
+
+impl TargetInfo {
+    /// read target metadata from the object file headers
+    pub fn from_data(data: &[u8]) -> Result<Self> {
+        if wasm::is_wasm(data) {
+            return Ok(Self::from_wasm(&WasmModule::parse(data)?));
+        }
+        let object_file = reader::parse_object(data)?;
+        Ok(Self::from_object(&object_file))
+    }
+
+    pub(crate) fn from_object(object_file: &object::File) -> Self {
+        let arch = object_file.architecture();
+
+        let pointer_width = arch
+            .address_size()
+            .map(|size| size.bytes() as usize)
+            .unwrap_or(if object_file.is_64() { 8 } else { 4 });
+
+        let endianness = if object_file.is_little_endian() {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        };
+
+        log::debug!(
+            "target: {:?}, {} byte pointers, {} endian",
+            arch,
+            pointer_width,
+            endianness
+        );
+
+        Self {
+            architecture: architecture_name(arch),
+            pointer_width,
+            endianness,
+        }
+    }
+
+    /// wasm has no header naming the memory model, so the pointer width is
+    /// taken from the DWARF address size
+    fn from_wasm(module: &WasmModule) -> Self {
+        let pointer_width = module.pointer_width();
+        let architecture = if pointer_width == 8 {
+            Architecture::Wasm64
+        } else {
+            Architecture::Wasm32
+        };
+        log::debug!(
+            "target: {:?}, {} byte pointers",
+            architecture,
+            pointer_width
+        );
+
+        Self {
+            architecture: architecture_name(architecture),
+            pointer_width,
+            endianness: Endianness::Little,
+        }
+    }
+
+    /// true if the host running this code has the same architecture and byte
+    /// order as the target
+    pub fn matches_host(&self) -> bool {
+        self.architecture == HOST_ARCHITECTURE && self.endianness == Endianness::host()
+    }
+}
+
+impl core::fmt::Display for TargetInfo {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} ({}-bit, {} endian)",
+            self.architecture,
+            self.pointer_width * 8,
+            self.endianness
+        )
+    }
+}
+
+/// canonical name for a user-supplied architecture, accepting apple and
+/// toolchain spellings such as "arm64" and "amd64"
+pub(crate) fn normalize_architecture_name(name: &str) -> String {
+    match name.to_ascii_lowercase().as_str() {
+        "arm64" | "aarch64" => "aarch64".to_string(),
+        "amd64" | "x86-64" | "x86_64" => "x86_64".to_string(),
+        "i386" | "i686" | "x86" => "x86".to_string(),
+        other => other.to_string(),
+    }
+}
+
+pub(crate) fn architecture_name(arch: Architecture) -> String {
+    let name = match arch {
+        Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => "aarch64",
+        Architecture::Arm => "arm",
+        Architecture::I386 => "x86",
+        Architecture::X86_64 | Architecture::X86_64_X32 => "x86_64",
+        Architecture::LoongArch64 => "loongarch64",
+        Architecture::Mips => "mips",
+        Architecture::Mips64 | Architecture::Mips64_N32 => "mips64",
+        Architecture::PowerPc => "powerpc",
+        Architecture::PowerPc64 => "powerpc64",
+        Architecture::Riscv32 => "riscv32",
+        Architecture::Riscv64 => "riscv64",
+        Architecture::S390x => "s390x",
+        Architecture::Sparc | Architecture::Sparc32Plus => "sparc",
+        Architecture::Sparc64 => "sparc64",
+        Architecture::Wasm32 => "wasm32",
+        Architecture::Wasm64 => "wasm64",
+        Architecture::Unknown => "unknown",
+        other => return format!("{:?}", other).to_lowercase(),
+    };
+    name.to_string()
+}
@@ -1,39 +1,97 @@
+use crate::c_type::{self, Specifier};
+use crate::prelude::*;
+use crate::sip::SipHasher13;
+use crate::target::Endianness;
 use serde::Serialize;
 /// type registry for storing and managing C type information extracted from DWARF
-use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
+use crate::collections::{HashMap, HashSet, VecDeque};
+use core::hash::{Hash, Hasher};
+#[cfg(not(feature = "std"))]
+use core::cell::OnceCell as OnceLock;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
 use log;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub struct TypeId(pub u64);
 
+/// primitive name given to C++ pointer-to-member types, which are not
+/// modeled. consumers generating bindings should skip these.
+pub const POINTER_TO_MEMBER: &str = "<pointer-to-member>";
+
+/// a compilation unit, by the input it was read from and the offset of its
+/// header in `.debug_info`. DIE offsets are relative to their unit, so they
+/// need this to be unambiguous.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct UnitId {
+    /// which file, archive member or process mapping the unit was read
+    /// from, numbered in the order they were merged. 0 for a single file.
+    pub source: u32,
+    /// offset of the unit's header in `.debug_info`, or in `.debug_types`
+    /// for DWARF 4 type units
+    pub offset: u64,
+}
+
 impl Hash for TypeId {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.0.hash(state);
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// equality and hashing are structural: `dwarf_offset` only records where a
+/// type was first seen, so the same type read from two offsets is `==`
+#[derive(Debug, Clone)]
 pub struct Type {
     pub id: TypeId,
     pub kind: BaseTypeKind,
     pub pointer_depth: usize,
+    /// qualifiers of the base type: the `const` in `const char*`
     pub is_const: bool,
     pub is_volatile: bool,
+    /// qualifiers of each pointer level, outermost first: `[const]` for
+    /// `char* const`. empty when no pointer level is qualified.
+    pub pointer_qualifiers: Vec<Qualifiers>,
+    /// offset of the DIE within its compilation unit. only unique within
+    /// that unit; see [`TypeRegistry::get_by_unit_offset`].
     pub dwarf_offset: Option<u64>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl PartialEq for Type {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.kind == other.kind
+            && self.pointer_depth == other.pointer_depth
+            && self.is_const == other.is_const
+            && self.is_volatile == other.is_volatile
+            && self.pointer_qualifiers == other.pointer_qualifiers
+    }
+}
+
+impl Eq for Type {}
+
+impl Hash for Type {
+    // equal types have equal content-addressed ids, so the id is enough
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BaseTypeKind {
     /// int, float, uint8_t, size_t, etc.
     Primitive {
         name: String,
         size: usize,
         alignment: usize,
+        /// from `DW_AT_encoding`; None when the base type didn't say or
+        /// the type wasn't read from DWARF
+        encoding: Option<PrimitiveEncoding>,
     },
 
     Struct {
         name: String,
+        /// sorted by offset (memory order); see `StructField::declaration_index`
+        /// for source order
         fields: Vec<StructField>,
         size: usize,
         alignment: usize,
@@ -45,6 +103,9 @@ pub enum BaseTypeKind {
         variants: Vec<UnionField>,
         size: usize,
         alignment: usize,
+        /// the member selecting the active variant when this is a
+        /// discriminated union (`DW_TAG_variant_part`, e.g. a Rust enum)
+        discriminant: Option<Discriminant>,
     },
 
     Enum {
@@ -59,6 +120,12 @@ pub enum BaseTypeKind {
         element_type_id: TypeId,
         count: usize,
         size: usize,
+        /// bytes between consecutive elements; the element size unless the
+        /// compiler says otherwise
+        stride: usize,
+        /// bound is a runtime value (e.g. a VLA), not a constant. count and
+        /// size are 0 and say nothing about the real length.
+        is_dynamic: bool,
     },
 
     Typedef {
@@ -74,123 +141,319 @@ pub enum BaseTypeKind {
     },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// `const`/`volatile` applied to one pointer level
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct Qualifiers {
+    pub is_const: bool,
+    pub is_volatile: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StructField {
     pub name: String,
     pub type_id: TypeId,
     pub offset: usize, // offset in bytes from struct start
     pub size: usize,   // size in bytes
+    /// position among the struct's members as declared in source. bitfields
+    /// can make this differ from offset order.
+    pub declaration_index: usize,
+    /// byte order of the member when `DW_AT_endianity` sets one (e.g. gcc's
+    /// `scalar_storage_order`); None for the target's default
+    pub endianness: Option<Endianness>,
+    /// width in bits of a bitfield member, from `DW_AT_bit_size`; None for
+    /// an ordinary member
+    pub bit_size: Option<u32>,
+    /// where a bitfield starts, in bits from the start of the struct; its
+    /// `offset` is the byte this falls in
+    pub bit_offset: Option<u32>,
+}
+
+impl StructField {
+    /// a member at byte `offset`, in the target's byte order, declared
+    /// first. set `declaration_index` where declaration order matters.
+    pub fn new(name: &str, type_id: TypeId, offset: usize, size: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            type_id,
+            offset,
+            size,
+            declaration_index: 0,
+            endianness: None,
+            bit_size: None,
+            bit_offset: None,
+        }
+    }
+
+    /// whether this member is a bitfield
+    pub fn is_bitfield(&self) -> bool {
+        self.bit_size.is_some()
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UnionField {
     pub name: String,
     pub type_id: TypeId,
+    /// the discriminant value selecting this variant (`DW_AT_discr_value`);
+    /// None in plain unions and for a discriminated union's default variant
+    pub discriminant_value: Option<i64>,
+}
+
+/// the tag member of a discriminated union, which variants don't overlay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discriminant {
+    pub name: String,
+    pub type_id: TypeId,
+    pub offset: usize,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EnumVariant {
     pub name: String,
+    /// an enumerator of an unsigned enum above `i64::MAX` is stored as the
+    /// `i64` with the same bits
     pub value: i64,
 }
 
-#[derive(Serialize)]
-enum CanonicalTypeKind {
-    Primitive(CanonicalPrimitive),
-    Struct(CanonicalStruct),
-    Union(CanonicalUnion),
-    Enum(CanonicalEnum),
-    Array(CanonicalArray),
-    Typedef(CanonicalTypedef),
-    Function(CanonicalFunction),
+impl EnumVariant {
+    /// the value as a decimal literal, read as unsigned when the enum's
+    /// backing type is (see `TypeRegistry::is_unsigned_enum`)
+    pub fn value_string(&self, is_unsigned: bool) -> String {
+        if is_unsigned {
+            (self.value as u64).to_string()
+        } else {
+            self.value.to_string()
+        }
+    }
 }
 
-#[derive(Serialize)]
-struct CanonicalPrimitive {
-    name: String,
-    size: usize,
-    alignment: usize,
+/// the signature of a function pointer, borrowed from its
+/// `BaseTypeKind::Function`. see `Type::as_function_pointer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionPointer<'a> {
+    /// None for void
+    pub return_type_id: Option<TypeId>,
+    pub parameter_type_ids: &'a [TypeId],
+    pub is_variadic: bool,
 }
 
-#[derive(Serialize)]
-struct CanonicalStruct {
-    name: String,
-    fields: Vec<CanonicalField>,
-    size: usize,
-    alignment: usize,
-    is_opaque: bool,
+/// how a base type's bits are read, from its `DW_AT_encoding`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PrimitiveEncoding {
+    Signed,
+    Unsigned,
+    Boolean,
+    Float,
 }
 
-#[derive(Serialize)]
-struct CanonicalField {
-    name: String,
-    type_id: TypeId,
-    offset: usize,
-    size: usize,
+impl PrimitiveEncoding {
+    /// None for encodings a C binding has no use for, e.g. `DW_ATE_address`
+    pub fn from_dwarf(encoding: gimli::DwAte) -> Option<Self> {
+        match encoding {
+            gimli::DW_ATE_signed | gimli::DW_ATE_signed_char | gimli::DW_ATE_signed_fixed => {
+                Some(PrimitiveEncoding::Signed)
+            }
+            gimli::DW_ATE_unsigned
+            | gimli::DW_ATE_unsigned_char
+            | gimli::DW_ATE_unsigned_fixed
+            | gimli::DW_ATE_UTF => Some(PrimitiveEncoding::Unsigned),
+            gimli::DW_ATE_boolean => Some(PrimitiveEncoding::Boolean),
+            gimli::DW_ATE_float
+            | gimli::DW_ATE_complex_float
+            | gimli::DW_ATE_imaginary_float
+            | gimli::DW_ATE_decimal_float => Some(PrimitiveEncoding::Float),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Serialize)]
-struct CanonicalUnion {
-    name: String,
-    variants: Vec<CanonicalUnionVariant>,
-    size: usize,
-    alignment: usize,
+/// concrete primitive a type resolves to after following typedefs, e.g.
+/// `size_t` -> `long unsigned int` (8 bytes, unsigned)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolvedPrimitive {
+    pub name: String,
+    pub size: usize,
+    pub is_signed: bool,
+    pub is_float: bool,
 }
 
-#[derive(Serialize, Ord, PartialOrd, Eq, PartialEq)]
-struct CanonicalUnionVariant {
-    name: String,
-    type_id: TypeId,
-}
+impl ResolvedPrimitive {
+    /// classify a DWARF base type by its name, e.g. `long unsigned int`
+    pub fn from_name(name: &str, size: usize) -> Self {
+        Self::from_base(name, size, None)
+    }
 
-#[derive(Serialize)]
-struct CanonicalEnum {
-    name: String,
-    backing_id: TypeId,
-    variants: Vec<CanonicalEnumVariant>,
-    size: usize,
+    /// classify a DWARF base type by its encoding, falling back to its
+    /// name when there is none
+    pub fn from_base(name: &str, size: usize, encoding: Option<PrimitiveEncoding>) -> Self {
+        let (is_signed, is_float) = match encoding {
+            Some(PrimitiveEncoding::Signed) => (true, false),
+            Some(PrimitiveEncoding::Unsigned | PrimitiveEncoding::Boolean) => (false, false),
+            Some(PrimitiveEncoding::Float) => (true, true),
+            None => {
+                let is_float = name.contains("float") || name.contains("double");
+                (is_float || primitive_is_signed(name), is_float)
+            }
+        };
+        Self {
+            name: name.to_string(),
+            size,
+            is_signed,
+            is_float,
+        }
+    }
 }
 
-#[derive(Serialize, Ord, PartialOrd, Eq, PartialEq)]
-struct CanonicalEnumVariant {
-    name: String,
-    value: i64,
+/// signedness of a C base type from its DWARF name, for base types without
+/// a `DW_AT_encoding`. plain `char` is treated as signed, which holds for
+/// x86, x86_64 and Apple arm64.
+fn primitive_is_signed(name: &str) -> bool {
+    if name.contains("unsigned") || name == "_Bool" || name == "bool" {
+        return false;
+    }
+    // fixed-width spellings (uint8_t, uintptr_t, size_t)
+    !(name.starts_with("uint") || name == "size_t")
 }
 
-#[derive(Serialize)]
-struct CanonicalArray {
-    element_type_id: TypeId,
-    count: usize,
-    size: usize,
-}
+/// where the canonical bytes of a type go. they're laid out the way the
+/// legacy bincode encoding of a `(kind, pointer_depth, is_const, is_volatile,
+/// pointer_qualifiers)` tuple would lay them out, which is how ids were first
+/// computed: fixed-width little-endian integers, `u64` lengths, `u32` enum
+/// variants and a byte in front of each `Some`.
+trait CanonicalSink {
+    fn bytes(&mut self, bytes: &[u8]);
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.bytes(&[value as u8]);
+    }
+
+    fn str(&mut self, value: &str) {
+        self.usize(value.len());
+        self.bytes(value.as_bytes());
+    }
+
+    fn id(&mut self, id: TypeId) {
+        self.u64(id.0);
+    }
 
-#[derive(Serialize)]
-struct CanonicalTypedef {
-    name: String,
-    aliased_type_id: TypeId,
+    /// the tag of an `Option`; optional fields added after ids were first
+    /// computed are left out entirely when absent, so those ids stay put
+    fn some(&mut self) {
+        self.bool(true);
+    }
 }
 
-#[derive(Serialize)]
-struct CanonicalFunction {
-    return_type_id: Option<TypeId>,
-    parameter_type_ids: Vec<TypeId>, // order matters (calling convention)
-    is_variadic: bool,
+impl CanonicalSink for Vec<u8> {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
 }
 
 impl BaseTypeKind {
-    /// convert to canonical form for hashing
-    /// sorts enum/union variants by name
-    fn to_canonical(&self) -> CanonicalTypeKind {
+    /// ids of the types this kind refers to directly
+    pub(crate) fn referenced_type_ids(&self) -> Vec<TypeId> {
+        match self {
+            BaseTypeKind::Primitive { .. } => Vec::new(),
+            BaseTypeKind::Struct { fields, .. } => fields.iter().map(|f| f.type_id).collect(),
+            BaseTypeKind::Union {
+                variants,
+                discriminant,
+                ..
+            } => variants
+                .iter()
+                .map(|v| v.type_id)
+                .chain(discriminant.iter().map(|d| d.type_id))
+                .collect(),
+            BaseTypeKind::Enum { backing_id, .. } => vec![*backing_id],
+            BaseTypeKind::Array {
+                element_type_id, ..
+            } => vec![*element_type_id],
+            BaseTypeKind::Typedef {
+                aliased_type_id, ..
+            } => vec![*aliased_type_id],
+            BaseTypeKind::Function {
+                return_type_id,
+                parameter_type_ids,
+                ..
+            } => return_type_id
+                .iter()
+                .chain(parameter_type_ids)
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// rewrite the ids this kind refers to directly
+    pub(crate) fn map_type_ids(&mut self, mut map: impl FnMut(TypeId) -> TypeId) {
+        match self {
+            BaseTypeKind::Primitive { .. } => {}
+            BaseTypeKind::Struct { fields, .. } => {
+                for field in fields {
+                    field.type_id = map(field.type_id);
+                }
+            }
+            BaseTypeKind::Union {
+                variants,
+                discriminant,
+                ..
+            } => {
+                for variant in variants {
+                    variant.type_id = map(variant.type_id);
+                }
+                if let Some(discriminant) = discriminant {
+                    discriminant.type_id = map(discriminant.type_id);
+                }
+            }
+            BaseTypeKind::Enum { backing_id, .. } => *backing_id = map(*backing_id),
+            BaseTypeKind::Array {
+                element_type_id, ..
+            } => *element_type_id = map(*element_type_id),
+            BaseTypeKind::Typedef {
+                aliased_type_id, ..
+            } => *aliased_type_id = map(*aliased_type_id),
+            BaseTypeKind::Function {
+                return_type_id,
+                parameter_type_ids,
+                ..
+            } => {
+                for id in return_type_id.iter_mut().chain(parameter_type_ids) {
+                    *id = map(*id);
+                }
+            }
+        }
+    }
+
+    /// write the canonical form hashed into ids: enum and union variants
+    /// sorted by name, everything else in declaration (or memory) order
+    fn write_canonical(&self, out: &mut impl CanonicalSink) {
         match self {
             BaseTypeKind::Primitive {
                 name,
                 size,
                 alignment,
-            } => CanonicalTypeKind::Primitive(CanonicalPrimitive {
-                name: name.clone(),
-                size: *size,
-                alignment: *alignment,
-            }),
+                encoding,
+            } => {
+                out.u32(0);
+                out.str(name);
+                out.usize(*size);
+                out.usize(*alignment);
+                if let Some(encoding) = encoding {
+                    out.some();
+                    out.u32(*encoding as u32);
+                }
+            }
 
             BaseTypeKind::Struct {
                 name,
@@ -199,24 +462,32 @@ impl BaseTypeKind {
                 alignment,
                 is_opaque,
             } => {
+                out.u32(1);
+                out.str(name);
                 // keep field order (memory layout is order-dependent)
-                let canonical_fields = fields
-                    .iter()
-                    .map(|f| CanonicalField {
-                        name: f.name.clone(),
-                        type_id: f.type_id,
-                        offset: f.offset,
-                        size: f.size,
-                    })
-                    .collect();
-
-                CanonicalTypeKind::Struct(CanonicalStruct {
-                    name: name.clone(),
-                    fields: canonical_fields,
-                    size: *size,
-                    alignment: *alignment,
-                    is_opaque: *is_opaque,
-                })
+                out.usize(fields.len());
+                for field in fields {
+                    out.str(&field.name);
+                    out.id(field.type_id);
+                    out.usize(field.offset);
+                    out.usize(field.size);
+                    out.usize(field.declaration_index);
+                    if let Some(endianness) = field.endianness {
+                        out.some();
+                        out.u32(endianness as u32);
+                    }
+                    if let Some(bit_size) = field.bit_size {
+                        out.some();
+                        out.u32(bit_size);
+                    }
+                    if let Some(bit_offset) = field.bit_offset {
+                        out.some();
+                        out.u32(bit_offset);
+                    }
+                }
+                out.usize(*size);
+                out.usize(*alignment);
+                out.bool(*is_opaque);
             }
 
             BaseTypeKind::Union {
@@ -224,23 +495,29 @@ impl BaseTypeKind {
                 variants,
                 size,
                 alignment,
+                discriminant,
             } => {
-                // sort variants by name for canonical ordering
-                let mut sorted_variants: Vec<_> = variants
-                    .iter()
-                    .map(|v| CanonicalUnionVariant {
-                        name: v.name.clone(),
-                        type_id: v.type_id,
-                    })
-                    .collect();
-                sorted_variants.sort_by(|a, b| a.name.cmp(&b.name));
-
-                CanonicalTypeKind::Union(CanonicalUnion {
-                    name: name.clone(),
-                    variants: sorted_variants,
-                    size: *size,
-                    alignment: *alignment,
-                })
+                out.u32(2);
+                out.str(name);
+                let mut sorted: Vec<&UnionField> = variants.iter().collect();
+                sorted.sort_by(|a, b| a.name.cmp(&b.name));
+                out.usize(sorted.len());
+                for variant in sorted {
+                    out.str(&variant.name);
+                    out.id(variant.type_id);
+                    if let Some(value) = variant.discriminant_value {
+                        out.some();
+                        out.u64(value as u64);
+                    }
+                }
+                out.usize(*size);
+                out.usize(*alignment);
+                if let Some(discriminant) = discriminant {
+                    out.some();
+                    out.str(&discriminant.name);
+                    out.id(discriminant.type_id);
+                    out.usize(discriminant.offset);
+                }
             }
 
             BaseTypeKind::Enum {
@@ -249,53 +526,62 @@ impl BaseTypeKind {
                 variants,
                 size,
             } => {
-                // sort variants by name for canonical ordering
-                let mut sorted_variants: Vec<_> = variants
-                    .iter()
-                    .map(|v| CanonicalEnumVariant {
-                        name: v.name.clone(),
-                        value: v.value,
-                    })
-                    .collect();
-                sorted_variants.sort_by(|a, b| a.name.cmp(&b.name));
-
-                CanonicalTypeKind::Enum(CanonicalEnum {
-                    name: name.clone(),
-                    backing_id: *backing_id,
-                    variants: sorted_variants,
-                    size: *size,
-                })
+                out.u32(3);
+                out.str(name);
+                out.id(*backing_id);
+                let mut sorted: Vec<&EnumVariant> = variants.iter().collect();
+                sorted.sort_by(|a, b| a.name.cmp(&b.name));
+                out.usize(sorted.len());
+                for variant in sorted {
+                    out.str(&variant.name);
+                    out.u64(variant.value as u64);
+                }
+                out.usize(*size);
             }
 
             BaseTypeKind::Array {
                 element_type_id,
                 count,
                 size,
-            } => CanonicalTypeKind::Array(CanonicalArray {
-                element_type_id: *element_type_id,
-                count: *count,
-                size: *size,
-            }),
+                stride,
+                is_dynamic,
+            } => {
+                out.u32(4);
+                out.id(*element_type_id);
+                out.usize(*count);
+                out.usize(*size);
+                out.usize(*stride);
+                out.bool(*is_dynamic);
+            }
 
             BaseTypeKind::Typedef {
                 name,
                 aliased_type_id,
-            } => CanonicalTypeKind::Typedef(CanonicalTypedef {
-                name: name.clone(),
-                aliased_type_id: *aliased_type_id,
-            }),
+            } => {
+                out.u32(5);
+                out.str(name);
+                out.id(*aliased_type_id);
+            }
 
             BaseTypeKind::Function {
                 return_type_id,
                 parameter_type_ids,
                 is_variadic,
             } => {
+                out.u32(6);
+                match return_type_id {
+                    Some(id) => {
+                        out.some();
+                        out.id(*id);
+                    }
+                    None => out.bool(false),
+                }
                 // keep parameter order (calling convention is order-dependent)
-                CanonicalTypeKind::Function(CanonicalFunction {
-                    return_type_id: *return_type_id,
-                    parameter_type_ids: parameter_type_ids.clone(),
-                    is_variadic: *is_variadic,
-                })
+                out.usize(parameter_type_ids.len());
+                for id in parameter_type_ids {
+                    out.id(*id);
+                }
+                out.bool(*is_variadic);
             }
         }
     }
@@ -306,49 +592,282 @@ fn compute_type_id(
     pointer_depth: usize,
     is_const: bool,
     is_volatile: bool,
+    pointer_qualifiers: &[Qualifiers],
 ) -> TypeId {
-    use bincode::Options;
-    use std::collections::hash_map::DefaultHasher;
-
-    let canonical = kind.to_canonical();
-
-    let bytes = bincode::DefaultOptions::new()
-        .with_fixint_encoding() // Ensure consistent integer encoding
-        .serialize(&(canonical, pointer_depth, is_const, is_volatile))
-        .expect("serialization cannot fail");
+    // unqualified levels past the last qualified one are the same as none
+    let qualified = pointer_qualifiers
+        .iter()
+        .take(pointer_depth)
+        .rposition(|qualifiers| *qualifiers != Qualifiers::default())
+        .map_or(0, |last| last + 1);
+    let pointer_qualifiers = &pointer_qualifiers[..qualified];
+
+    let mut bytes = Vec::with_capacity(256);
+    write_canonical_type(
+        &mut bytes,
+        kind,
+        pointer_depth,
+        is_const,
+        is_volatile,
+        pointer_qualifiers,
+    );
+
+    hash_canonical(&bytes)
+}
 
-    let mut hasher = DefaultHasher::new();
+fn hash_canonical(bytes: &[u8]) -> TypeId {
+    let mut hasher = SipHasher13::new();
     bytes.hash(&mut hasher);
     TypeId(hasher.finish())
 }
 
+/// `kind` referring to the final ids of the types `renames` gives them
+fn renamed_kind(kind: &BaseTypeKind, renames: &HashMap<TypeId, TypeId>) -> BaseTypeKind {
+    let mut kind = kind.clone();
+    kind.map_type_ids(|id| renames.get(&id).copied().unwrap_or(id));
+    kind
+}
+
+/// the content-addressed id of `type_` once the types it refers to have
+/// the ids `renames` gives them
+fn renamed_type_id(type_: &Type, renames: &HashMap<TypeId, TypeId>) -> TypeId {
+    compute_type_id(
+        &renamed_kind(&type_.kind, renames),
+        type_.pointer_depth,
+        type_.is_const,
+        type_.is_volatile,
+        &type_.pointer_qualifiers,
+    )
+}
+
+/// canonical bytes whose ids are written by `write_id`, for the kinds of a
+/// cycle
+struct CycleSink<'a, F> {
+    bytes: &'a mut Vec<u8>,
+    write_id: F,
+}
+
+impl<F: FnMut(&mut Vec<u8>, TypeId)> CanonicalSink for CycleSink<'_, F> {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn id(&mut self, id: TypeId) {
+        (self.write_id)(self.bytes, id);
+    }
+}
+
+/// strongly connected components of a graph given as adjacency lists, each
+/// one after the components it has edges into (Tarjan's algorithm, without
+/// recursion)
+fn strongly_connected(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+    let mut index = vec![UNVISITED; edges.len()];
+    let mut low = vec![0; edges.len()];
+    let mut on_stack = vec![false; edges.len()];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0;
+
+    for root in 0..edges.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        // each node being visited, with the next of its edges to follow
+        let mut walk = vec![(root, 0)];
+        index[root] = next_index;
+        low[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&(node, edge)) = walk.last() {
+            if let Some(&target) = edges[node].get(edge) {
+                let top = walk.len() - 1;
+                walk[top].1 += 1;
+                if index[target] == UNVISITED {
+                    index[target] = next_index;
+                    low[target] = next_index;
+                    next_index += 1;
+                    stack.push(target);
+                    on_stack[target] = true;
+                    walk.push((target, 0));
+                } else if on_stack[target] {
+                    low[node] = low[node].min(index[target]);
+                }
+                continue;
+            }
+
+            walk.pop();
+            if let Some(&(parent, _)) = walk.last() {
+                low[parent] = low[parent].min(low[node]);
+            }
+            if low[node] == index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+/// id of a pointer to a type on a cycle, like `struct Node*` inside `struct
+/// Node`, whose kind can't be hashed with the ids it refers to. `cycle` is
+/// the hash of the kind's `cycle_encoding`, and the levels are hashed as
+/// for any other type.
+fn cyclic_type_id(
+    cycle: u64,
+    pointer_depth: usize,
+    is_const: bool,
+    is_volatile: bool,
+    pointer_qualifiers: &[Qualifiers],
+) -> TypeId {
+    let mut bytes = Vec::with_capacity(32);
+    // past the kind variants, so no other type hashes the same bytes
+    bytes.u32(7);
+    bytes.u64(cycle);
+    write_canonical_levels(
+        &mut bytes,
+        pointer_depth,
+        is_const,
+        is_volatile,
+        pointer_qualifiers,
+    );
+    hash_canonical(&bytes)
+}
+
+/// the canonical bytes of a type: its kind, then how it is pointed to and
+/// qualified
+fn write_canonical_type(
+    out: &mut impl CanonicalSink,
+    kind: &BaseTypeKind,
+    pointer_depth: usize,
+    is_const: bool,
+    is_volatile: bool,
+    pointer_qualifiers: &[Qualifiers],
+) {
+    kind.write_canonical(out);
+    write_canonical_levels(
+        out,
+        pointer_depth,
+        is_const,
+        is_volatile,
+        pointer_qualifiers,
+    );
+}
+
+/// the canonical bytes of how a type is pointed to and qualified
+fn write_canonical_levels(
+    out: &mut impl CanonicalSink,
+    pointer_depth: usize,
+    is_const: bool,
+    is_volatile: bool,
+    pointer_qualifiers: &[Qualifiers],
+) {
+    out.usize(pointer_depth);
+    out.bool(is_const);
+    out.bool(is_volatile);
+    out.usize(pointer_qualifiers.len());
+    for qualifiers in pointer_qualifiers {
+        out.bool(qualifiers.is_const);
+        out.bool(qualifiers.is_volatile);
+    }
+}
+
 /// central registry
 #[derive(Debug, Clone)]
 pub struct TypeRegistry {
-    types: HashMap<TypeId, Type>,
-    dwarf_to_id: HashMap<u64, TypeId>,
-    name_to_ids: HashMap<String, Vec<TypeId>>,
+    types: hashbrown::HashMap<TypeId, Type>,
+    /// the unit types are being registered from
+    unit: UnitId,
+    /// how many inputs the types were read from; see `merge_source`
+    sources: u32,
+    dwarf_to_id: hashbrown::HashMap<(UnitId, u64), TypeId>,
+    name_to_ids: hashbrown::HashMap<String, Vec<TypeId>>,
+    /// unqualified id of an anonymous struct, union or enum -> the typedef
+    /// that names it. the smallest name wins if several do.
+    typedef_names: HashMap<TypeId, String>,
+    /// unqualified id -> the type's `DW_AT_description`
+    descriptions: HashMap<TypeId, String>,
+    /// unqualified ids of structs and unions defined in a source file rather
+    /// than a header
+    source_definitions: HashSet<TypeId>,
+    /// unqualified ids of structs and unions named by a typedef in a header
+    header_typedefs: HashSet<TypeId>,
+    /// unqualified id of a kind on a cycle of types -> the hash its pointer
+    /// ids are derived from; see `close_cycles`
+    cyclic_kinds: HashMap<TypeId, u64>,
+    /// name anonymous aggregates no typedef names by their synthetic name
+    demunge_anonymous: bool,
+    /// `synthetic_name`s, built on first use and dropped when the types
+    /// change. a plain cell without std, so the registry is only `Sync`
+    /// with it
+    synthetic_names: OnceLock<HashMap<TypeId, String>>,
 }
 
 impl TypeRegistry {
     pub fn new() -> Self {
         Self {
-            types: HashMap::new(),
-            dwarf_to_id: HashMap::new(),
-            name_to_ids: HashMap::new(),
+            types: hashbrown::HashMap::new(),
+            unit: UnitId::default(),
+            sources: 1,
+            dwarf_to_id: hashbrown::HashMap::new(),
+            name_to_ids: hashbrown::HashMap::new(),
+            typedef_names: HashMap::new(),
+            descriptions: HashMap::new(),
+            source_definitions: HashSet::new(),
+            header_typedefs: HashSet::new(),
+            cyclic_kinds: HashMap::new(),
+            demunge_anonymous: false,
+            synthetic_names: OnceLock::new(),
         }
     }
 
     /// register a new type with a content-addressed ID
     /// if an identical type already exists, returns its ID
     pub fn register_type(&mut self, mut type_: Type) -> TypeId {
-        // compute content-addressed ID from type structure
-        let id = compute_type_id(
-            &type_.kind,
-            type_.pointer_depth,
-            type_.is_const,
-            type_.is_volatile,
-        );
+        self.synthetic_names.take();
+        // stored as hashed, so equal types compare equal
+        type_.pointer_qualifiers.truncate(type_.pointer_depth);
+        while type_.pointer_qualifiers.last() == Some(&Qualifiers::default()) {
+            type_.pointer_qualifiers.pop();
+        }
+
+        // compute content-addressed ID from type structure, or from the
+        // cycle a pointer's kind is on
+        let cycle = match type_.pointer_depth {
+            0 => None,
+            _ if self.cyclic_kinds.is_empty() => None,
+            _ => self.cyclic_kinds.get(&type_.unqualified_id()).copied(),
+        };
+        let id = match cycle {
+            Some(cycle) => cyclic_type_id(
+                cycle,
+                type_.pointer_depth,
+                type_.is_const,
+                type_.is_volatile,
+                &type_.pointer_qualifiers,
+            ),
+            None => compute_type_id(
+                &type_.kind,
+                type_.pointer_depth,
+                type_.is_const,
+                type_.is_volatile,
+                &type_.pointer_qualifiers,
+            ),
+        };
+
+        // the same type may be read from several DIEs; each one maps to it
+        if let Some(offset) = type_.dwarf_offset {
+            self.dwarf_to_id.insert((self.unit, offset), id);
+        }
 
         // check if already exists (automatic deduplication!)
         if self.types.contains_key(&id) {
@@ -358,10 +877,6 @@ impl TypeRegistry {
 
         type_.id = id;
 
-        if let Some(offset) = type_.dwarf_offset {
-            self.dwarf_to_id.insert(offset, id);
-        }
-
         let name = type_.get_name();
         log::trace!("registered type {} with id {:016x}", name, id.0);
 
@@ -370,21 +885,331 @@ impl TypeRegistry {
             .or_default()
             .push(id);
 
+        // pointers and qualifiers apply to uses of the typedef, so `const
+        // Foo*` still names the aggregate
+        if let BaseTypeKind::Typedef {
+            name,
+            aliased_type_id,
+        } = &type_.kind
+        {
+            // aliased types are registered first, but fall back to the id
+            // itself, which is the unqualified one in the common case
+            let aliased = match self.types.get(aliased_type_id) {
+                Some(aliased) if aliased.is_anonymous_aggregate() => Some(aliased.unqualified_id()),
+                Some(_) => None,
+                None => Some(*aliased_type_id),
+            };
+            if let Some(aliased) = aliased {
+                self.add_typedef_name(aliased, name.clone());
+            }
+        }
+
+        self.types.insert(id, type_);
+        id
+    }
+
+    /// register the stand-in for a struct or union reached again while its
+    /// members are being extracted, as `struct Node*` is inside `struct
+    /// Node`. it is registered under an id of its own, given the aggregate's
+    /// kind by `complete_placeholder` and its real id by `close_cycles`.
+    pub(crate) fn register_placeholder(&mut self, mut type_: Type) -> TypeId {
+        self.synthetic_names.take();
+        type_.pointer_qualifiers.truncate(type_.pointer_depth);
+        while type_.pointer_qualifiers.last() == Some(&Qualifiers::default()) {
+            type_.pointer_qualifiers.pop();
+        }
+
+        // unique to the DIE, and past the kind variants so no type's content
+        // hashes the same bytes
+        let mut bytes = Vec::with_capacity(24);
+        bytes.u32(8);
+        bytes.u32(self.unit.source);
+        bytes.u64(self.unit.offset);
+        bytes.u64(type_.dwarf_offset.unwrap_or_default());
+        let id = hash_canonical(&bytes);
+
+        if let Some(offset) = type_.dwarf_offset {
+            self.dwarf_to_id.insert((self.unit, offset), id);
+        }
+        type_.id = id;
+        self.name_to_ids
+            .entry(type_.get_name())
+            .or_default()
+            .push(id);
         self.types.insert(id, type_);
         id
     }
 
+    /// give a placeholder the kind of the aggregate it stands in for
+    pub(crate) fn complete_placeholder(&mut self, id: TypeId, kind: &BaseTypeKind) {
+        if let Some(placeholder) = self.types.get_mut(&id) {
+            placeholder.kind = kind.clone();
+        }
+    }
+
+    /// give their final ids to the types registered while aggregates that
+    /// refer to themselves were being extracted: `pending`, the placeholders
+    /// standing in for those aggregates (completed by now) and every type
+    /// that refers to one, directly or not. `offsets` are the DIEs any of
+    /// them was registered for. returns the final id of each pending one,
+    /// for kinds still holding the old ids.
+    ///
+    /// kinds are taken a strongly connected group at a time, after the
+    /// groups they refer to. a kind off any cycle is hashed as usual, with
+    /// the final ids of what it refers to. a kind on a cycle is hashed once
+    /// for its whole cycle, read breadth first from it with each reference
+    /// back into the cycle written as the position of its kind and its
+    /// levels; pointers to it take their ids from that hash and their own
+    /// levels, and `register_type` does the same for pointers registered
+    /// later. types held by value are then hashed as usual. that makes the
+    /// ids the same whichever type of a cycle resolution started at.
+    pub(crate) fn close_cycles(
+        &mut self,
+        pending: impl IntoIterator<Item = TypeId>,
+        offsets: &[(UnitId, u64)],
+    ) -> HashMap<TypeId, TypeId> {
+        self.synthetic_names.take();
+        let mut ids: Vec<TypeId> = pending
+            .into_iter()
+            .filter(|id| self.types.contains_key(id))
+            .collect();
+        ids.sort();
+
+        // one node per kind, which a pointer shares with what it points to
+        let mut node_of: HashMap<TypeId, usize> = HashMap::new();
+        let mut node_by_kind: HashMap<TypeId, usize> = HashMap::new();
+        let mut members: Vec<Vec<TypeId>> = Vec::new();
+        for &id in &ids {
+            let node = *node_by_kind
+                .entry(self.types[&id].unqualified_id())
+                .or_insert_with(|| {
+                    members.push(Vec::new());
+                    members.len() - 1
+                });
+            members[node].push(id);
+            node_of.insert(id, node);
+        }
+        let edges: Vec<Vec<usize>> = members
+            .iter()
+            .map(|ids| {
+                self.types[&ids[0]]
+                    .kind
+                    .referenced_type_ids()
+                    .into_iter()
+                    .filter_map(|id| node_of.get(&id).copied())
+                    .collect()
+            })
+            .collect();
+
+        let mut renames: HashMap<TypeId, TypeId> = HashMap::new();
+        let mut cycles = Vec::new();
+        for component in strongly_connected(&edges) {
+            let node = component[0];
+            if component.len() == 1 && !edges[node].contains(&node) {
+                for &id in &members[node] {
+                    renames.insert(id, renamed_type_id(&self.types[&id], &renames));
+                }
+                continue;
+            }
+
+            let in_component: HashSet<usize> = component.iter().copied().collect();
+            let hashes: Vec<u64> = component
+                .iter()
+                .map(|&node| self.cycle_hash(node, &members, &node_of, &in_component, &renames))
+                .collect();
+            let mut by_value = Vec::new();
+            for (&node, &cycle) in component.iter().zip(&hashes) {
+                for &id in &members[node] {
+                    let type_ = &self.types[&id];
+                    if type_.pointer_depth == 0 {
+                        by_value.push((id, cycle));
+                        continue;
+                    }
+                    let final_id = cyclic_type_id(
+                        cycle,
+                        type_.pointer_depth,
+                        type_.is_const,
+                        type_.is_volatile,
+                        &type_.pointer_qualifiers,
+                    );
+                    renames.insert(id, final_id);
+                }
+            }
+
+            // a type held by value refers to the others of its cycle
+            // through pointers, so each round hashes at least one of them
+            // until C types nested by value in a loop, which only malformed
+            // DWARF has
+            while !by_value.is_empty() {
+                let before = by_value.len();
+                by_value.retain(|&(id, _)| {
+                    let type_ = &self.types[&id];
+                    let ready = type_
+                        .kind
+                        .referenced_type_ids()
+                        .iter()
+                        .all(|id| !node_of.contains_key(id) || renames.contains_key(id));
+                    if ready {
+                        renames.insert(id, renamed_type_id(type_, &renames));
+                    }
+                    !ready
+                });
+                if by_value.len() == before {
+                    for (id, cycle) in by_value.drain(..) {
+                        let type_ = &self.types[&id];
+                        let final_id =
+                            cyclic_type_id(cycle, 0, type_.is_const, type_.is_volatile, &[]);
+                        renames.insert(id, final_id);
+                    }
+                }
+            }
+            cycles.extend(component.into_iter().zip(hashes));
+        }
+
+        // the maps keyed by unqualified id move with their kinds
+        let unqualified: Vec<(TypeId, TypeId)> = members
+            .iter()
+            .map(|ids| {
+                let type_ = &self.types[&ids[0]];
+                let kind = renamed_kind(&type_.kind, &renames);
+                (
+                    type_.unqualified_id(),
+                    compute_type_id(&kind, 0, false, false, &[]),
+                )
+            })
+            .collect();
+
+        for &id in &ids {
+            let mut type_ = self.types.remove(&id).expect("pending type registered");
+            let new_id = renames[&id];
+            type_
+                .kind
+                .map_type_ids(|id| renames.get(&id).copied().unwrap_or(id));
+            type_.id = new_id;
+            if let Some(ids) = self.name_to_ids.get_mut(&type_.get_name()) {
+                ids.retain(|other| *other != id);
+                if !ids.contains(&new_id) {
+                    ids.push(new_id);
+                }
+            }
+            self.types.entry(new_id).or_insert(type_);
+        }
+        for key in offsets {
+            if let Some(id) = self.dwarf_to_id.get_mut(key)
+                && let Some(new_id) = renames.get(id)
+            {
+                *id = *new_id;
+            }
+        }
+        for (old, new) in unqualified {
+            if let Some(name) = self.typedef_names.remove(&old) {
+                self.add_typedef_name(new, name);
+            }
+            if let Some(description) = self.descriptions.remove(&old) {
+                self.descriptions.entry(new).or_insert(description);
+            }
+            if self.source_definitions.remove(&old) {
+                self.source_definitions.insert(new);
+            }
+            if self.header_typedefs.remove(&old) {
+                self.header_typedefs.insert(new);
+            }
+        }
+        for (node, cycle) in cycles {
+            let kind = &self.types[&renames[&members[node][0]]].kind;
+            self.cyclic_kinds
+                .insert(compute_type_id(kind, 0, false, false, &[]), cycle);
+        }
+        renames
+    }
+
+    /// the hash of a kind on a cycle: the kinds of the cycle breadth first
+    /// from `node`, each reference back into the cycle written as the
+    /// position of its kind in that order and its own levels, and every
+    /// other reference as its final id
+    fn cycle_hash(
+        &self,
+        node: usize,
+        members: &[Vec<TypeId>],
+        node_of: &HashMap<TypeId, usize>,
+        in_component: &HashSet<usize>,
+        renames: &HashMap<TypeId, TypeId>,
+    ) -> u64 {
+        let mut order = vec![node];
+        let mut positions: HashMap<usize, usize> = HashMap::new();
+        positions.insert(node, 0);
+        let mut bytes = Vec::with_capacity(256);
+        let mut next = 0;
+        while let Some(&node) = order.get(next) {
+            next += 1;
+            let mut sink = CycleSink {
+                bytes: &mut bytes,
+                write_id: |bytes: &mut Vec<u8>, id: TypeId| match node_of
+                    .get(&id)
+                    .filter(|node| in_component.contains(*node))
+                {
+                    Some(&node) => {
+                        let position = *positions.entry(node).or_insert_with(|| {
+                            order.push(node);
+                            order.len() - 1
+                        });
+                        let type_ = &self.types[&id];
+                        bytes.u32(1);
+                        bytes.usize(position);
+                        write_canonical_levels(
+                            bytes,
+                            type_.pointer_depth,
+                            type_.is_const,
+                            type_.is_volatile,
+                            &type_.pointer_qualifiers,
+                        );
+                    }
+                    None => {
+                        bytes.u32(0);
+                        bytes.id(renames.get(&id).copied().unwrap_or(id));
+                    }
+                },
+            };
+            self.types[&members[node][0]]
+                .kind
+                .write_canonical(&mut sink);
+        }
+        hash_canonical(&bytes).0
+    }
+
+    fn add_typedef_name(&mut self, aggregate_id: TypeId, name: String) {
+        let entry = self.typedef_names.entry(aggregate_id).or_insert_with(|| name.clone());
+        if name < *entry {
+            *entry = name;
+        }
+    }
+
     pub fn get_type(&self, id: TypeId) -> Option<&Type> {
         self.types.get(&id)
     }
 
     pub fn get_type_mut(&mut self, id: TypeId) -> Option<&mut Type> {
+        self.synthetic_names.take();
         self.types.get_mut(&id)
     }
 
+    /// types registered from now on come from `unit`
+    pub(crate) fn set_unit(&mut self, unit: UnitId) {
+        self.unit = unit;
+    }
+
+    /// the type read from the DIE at `offset` in the unit this registry is
+    /// currently registering from. DIE offsets are relative to their unit, so
+    /// once several units are merged this can't tell which one is meant.
+    #[deprecated(note = "DIE offsets are only unique within a unit, use `get_by_unit_offset`")]
     pub fn get_by_dwarf_offset(&self, offset: u64) -> Option<&Type> {
+        self.get_by_unit_offset(self.unit, offset)
+    }
+
+    /// the type read from the DIE at `offset` within `unit`
+    pub fn get_by_unit_offset(&self, unit: UnitId, offset: u64) -> Option<&Type> {
         self.dwarf_to_id
-            .get(&offset)
+            .get(&(unit, offset))
             .and_then(|id| self.types.get(id))
     }
 
@@ -395,24 +1220,641 @@ impl TypeRegistry {
             .unwrap_or_default()
     }
 
-    pub fn all_types(&self) -> impl Iterator<Item = &Type> {
-        self.types.values()
+    /// the unqualified, non-pointer type named `name`: `int` rather than
+    /// `const int` or `int*`. if several distinct types share the name (see
+    /// `name_collisions`), the first registered one.
+    pub fn get_base_by_name(&self, name: &str) -> Option<&Type> {
+        self.get_by_name(name)
+            .into_iter()
+            .find(|t| t.pointer_depth == 0 && !t.is_const && !t.is_volatile)
     }
 
-    pub fn len(&self) -> usize {
-        self.types.len()
+    /// names shared by more than one distinct type, with how many, sorted by
+    /// name. e.g. two `struct stat` layouts from different headers. tags and
+    /// typedef names are separate namespaces, so `typedef struct Foo Foo` is
+    /// not a collision, and opaque declarations don't count.
+    pub fn name_collisions(&self) -> Vec<(String, usize)> {
+        let mut collisions = Vec::new();
+        for (name, ids) in &self.name_to_ids {
+            if name.starts_with('<') {
+                continue;
+            }
+
+            let mut tags = 0;
+            let mut ordinary = 0;
+            for type_ in ids.iter().filter_map(|id| self.types.get(id)) {
+                if type_.pointer_depth > 0 || type_.is_const || type_.is_volatile {
+                    continue;
+                }
+                match &type_.kind {
+                    BaseTypeKind::Struct {
+                        is_opaque: true, ..
+                    } => {}
+                    BaseTypeKind::Struct { .. }
+                    | BaseTypeKind::Union { .. }
+                    | BaseTypeKind::Enum { .. } => tags += 1,
+                    _ => ordinary += 1,
+                }
+            }
+
+            let count = tags.max(ordinary);
+            if count > 1 {
+                collisions.push((name.clone(), count));
+            }
+        }
+        collisions.sort();
+        collisions
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.types.is_empty()
+    /// best public-facing name of a type: its own name, except that an
+    /// anonymous struct, union or enum is named by its typedef, e.g.
+    /// `BoundingBox` for `typedef struct { ... } BoundingBox`. None for
+    /// unknown ids.
+    pub fn display_name(&self, id: TypeId) -> Option<String> {
+        let type_ = self.get_type(id)?;
+        if type_.is_anonymous_aggregate()
+            && let Some(name) = self.typedef_names.get(&type_.unqualified_id())
+        {
+            return Some(name.clone());
+        }
+        if let Some(name) = self.demunged_name(id) {
+            return Some(name);
+        }
+        Some(type_.get_name())
     }
 
-    /// merge another registry into this one.
-    pub fn merge(&mut self, other: TypeRegistry) {
-        let initial_count = self.len();
-        let merging_count = other.len();
+    /// the `DW_AT_description` of a struct, union, enum or typedef, for the
+    /// type itself and any pointer to or qualified use of it
+    pub fn description(&self, id: TypeId) -> Option<&str> {
+        let type_ = self.get_type(id)?;
+        self.descriptions
+            .get(&type_.unqualified_id())
+            .map(String::as_str)
+    }
 
-        // union the types (content-addressed, so same ID = same type)
+    /// record the description of the type `kind` describes
+    pub(crate) fn set_description(&mut self, kind: &BaseTypeKind, description: String) {
+        let id = compute_type_id(kind, 0, false, false, &[]);
+        self.descriptions.entry(id).or_insert(description);
+    }
+
+    /// record that the struct or union `kind` describes is defined in a
+    /// source file rather than a header
+    pub(crate) fn set_source_definition(&mut self, kind: &BaseTypeKind) {
+        self.source_definitions
+            .insert(compute_type_id(kind, 0, false, false, &[]));
+    }
+
+    /// record that a typedef in a header names `aliased_id`
+    pub(crate) fn set_header_typedef(&mut self, aliased_id: TypeId) {
+        if let Some(aliased) = self.get_type(aliased_id)
+            && aliased.pointer_depth == 0
+        {
+            self.header_typedefs.insert(aliased.unqualified_id());
+        }
+    }
+
+    /// true for a struct or union its users only handle through pointers:
+    /// one that is only declared (`struct Foo;`), or one a header names with
+    /// a typedef while only a source file defines it, like a handle's
+    /// `typedef struct State State;` whose fields only the `.c` file sees.
+    /// also true for a typedef naming one.
+    pub fn is_opaque(&self, id: TypeId) -> bool {
+        let Some(type_) = self.get_type(id) else {
+            return false;
+        };
+
+        for (link, current) in self.typedef_chain(type_).enumerate() {
+            // a typedef of a pointer to one is a handle, not the struct
+            if link > 0 && current.pointer_depth > 0 {
+                return false;
+            }
+            match &current.kind {
+                BaseTypeKind::Typedef { .. } => {}
+                BaseTypeKind::Struct {
+                    is_opaque: true, ..
+                } => return true,
+                // the stand-in for a union inside its own members
+                BaseTypeKind::Union {
+                    variants, size: 0, ..
+                } if variants.is_empty() => {
+                    return true;
+                }
+                BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. } => {
+                    let id = current.unqualified_id();
+                    return self.source_definitions.contains(&id)
+                        && self.header_typedefs.contains(&id);
+                }
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    /// the opaque structs and unions (see [`is_opaque`](Self::is_opaque)),
+    /// one id each, unqualified where that is registered, sorted by name. a
+    /// declaration is left out when a public definition of the same name
+    /// exists.
+    pub fn opaque_types(&self) -> Vec<TypeId> {
+        // names with a public definition
+        let mut defined = HashSet::new();
+        let mut opaque = Vec::new();
+        for type_ in self.types.values() {
+            let name = match &type_.kind {
+                BaseTypeKind::Struct { name, .. } | BaseTypeKind::Union { name, .. } => name,
+                _ => continue,
+            };
+            if self.is_opaque(type_.id) {
+                opaque.push(type_);
+            } else {
+                defined.insert(name.as_str());
+            }
+        }
+
+        opaque.retain(|type_| match &type_.kind {
+            BaseTypeKind::Struct {
+                name,
+                is_opaque: true,
+                ..
+            } => !defined.contains(name.as_str()),
+            _ => true,
+        });
+        // the unqualified type sorts first among its pointers and qualified
+        // uses
+        opaque.sort_by_key(|type_| {
+            (
+                type_.get_name(),
+                type_.unqualified_id(),
+                type_.pointer_depth,
+                type_.is_const,
+                type_.is_volatile,
+                type_.id,
+            )
+        });
+        opaque.dedup_by_key(|type_| type_.unqualified_id());
+        opaque.iter().map(|type_| type_.id).collect()
+    }
+
+    /// name anonymous aggregates that no typedef names by their
+    /// [`synthetic_name`](Self::synthetic_name), in `display_name` and
+    /// wherever codegen needs a name for them
+    pub fn set_demunge_anonymous(&mut self, demunge: bool) {
+        self.demunge_anonymous = demunge;
+    }
+
+    /// the synthetic name of an anonymous aggregate, if
+    /// [`set_demunge_anonymous`](Self::set_demunge_anonymous) is on
+    pub fn demunged_name(&self, id: TypeId) -> Option<String> {
+        if !self.demunge_anonymous {
+            return None;
+        }
+        self.synthetic_name(id)
+    }
+
+    /// a stable name for an anonymous struct, union or enum that no typedef
+    /// names: `Person_anon_union_0` for the first anonymous union member of
+    /// `Person`, or `anon_union_<id>` from its content-addressed id if no
+    /// aggregate holds it. made from the types' contents only, so it is the
+    /// same across runs. None for any other type.
+    pub fn synthetic_name(&self, id: TypeId) -> Option<String> {
+        let type_ = self.get_type(id)?;
+        if !type_.is_anonymous_aggregate() {
+            return None;
+        }
+        self.synthetic_names
+            .get_or_init(|| self.build_synthetic_names())
+            .get(&type_.unqualified_id())
+            .cloned()
+    }
+
+    /// the synthetic name of every anonymous aggregate no typedef names, by
+    /// unqualified id
+    fn build_synthetic_names(&self) -> HashMap<TypeId, String> {
+        // every aggregate holding an anonymous aggregate by value, with its
+        // position among that aggregate's anonymous members of the same kind
+        let mut enclosing: HashMap<TypeId, Vec<(&Type, usize)>> = HashMap::new();
+        for parent in self
+            .types
+            .values()
+            .filter(|parent| parent.pointer_depth == 0)
+        {
+            let members: Vec<TypeId> = match &parent.kind {
+                BaseTypeKind::Struct { .. } => parent
+                    .fields_in_declaration_order()
+                    .into_iter()
+                    .map(|field| field.type_id)
+                    .collect(),
+                BaseTypeKind::Union { variants, .. } => {
+                    variants.iter().map(|variant| variant.type_id).collect()
+                }
+                _ => continue,
+            };
+
+            let mut anonymous: Vec<(&str, TypeId)> = Vec::new();
+            for member in members.iter().filter_map(|id| self.get_type(*id)) {
+                if member.pointer_depth != 0 || !member.is_anonymous_aggregate() {
+                    continue;
+                }
+                let Some(keyword) = aggregate_keyword(&member.kind) else {
+                    continue;
+                };
+                let member_id = member.unqualified_id();
+                if !anonymous.contains(&(keyword, member_id)) {
+                    let position = anonymous
+                        .iter()
+                        .filter(|(kind, _)| *kind == keyword)
+                        .count();
+                    anonymous.push((keyword, member_id));
+                    enclosing
+                        .entry(member_id)
+                        .or_default()
+                        .push((parent, position));
+                }
+            }
+        }
+
+        let mut names = HashMap::new();
+        for type_ in self.types.values() {
+            self.synthetic_name_in(type_, &enclosing, &mut names, 0);
+        }
+        names
+    }
+
+    /// the synthetic name of `type_`, naming the aggregates enclosing it
+    /// first and recording every name made in `names`
+    fn synthetic_name_in(
+        &self,
+        type_: &Type,
+        enclosing: &HashMap<TypeId, Vec<(&Type, usize)>>,
+        names: &mut HashMap<TypeId, String>,
+        depth: usize,
+    ) -> Option<String> {
+        if !type_.is_anonymous_aggregate() {
+            return None;
+        }
+        let aggregate_id = type_.unqualified_id();
+        if let Some(name) = names.get(&aggregate_id) {
+            return Some(name.clone());
+        }
+        if self.typedef_names.contains_key(&aggregate_id) {
+            return None;
+        }
+        let keyword = aggregate_keyword(&type_.kind)?;
+        if depth > MAX_ANONYMOUS_NESTING {
+            return None;
+        }
+
+        // the smallest (name, position) wins, so the choice doesn't depend
+        // on hash order
+        let parent = enclosing
+            .get(&aggregate_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|(parent, position)| {
+                let parent_name = if parent.is_anonymous_aggregate() {
+                    match self.typedef_names.get(&parent.unqualified_id()) {
+                        Some(name) => name.clone(),
+                        None => self.synthetic_name_in(parent, enclosing, names, depth + 1)?,
+                    }
+                } else {
+                    parent.get_name()
+                };
+                Some((parent_name, *position))
+            })
+            .min();
+
+        let name = match parent {
+            Some((parent, position)) => format!("{}_anon_{}_{}", parent, keyword, position),
+            None => format!("anon_{}_{:016x}", keyword, aggregate_id.0),
+        };
+        names.insert(aggregate_id, name.clone());
+        Some(name)
+    }
+
+    /// the members of anonymous enums that no typedef names, e.g. `A` and
+    /// `B` of `enum { A = 1, B = 2 };`. with no usable type they act like
+    /// `#define`s. enums are in id order, members in declaration order, and
+    /// a name seen twice is kept once.
+    pub fn anonymous_enum_constants(&self) -> Vec<(String, i64)> {
+        let mut enums: Vec<&Type> = self
+            .types
+            .values()
+            .filter(|type_| {
+                matches!(type_.kind, BaseTypeKind::Enum { .. })
+                    && type_.is_anonymous_aggregate()
+                    && type_.id == type_.unqualified_id()
+                    && !self.typedef_names.contains_key(&type_.id)
+            })
+            .collect();
+        enums.sort_by_key(|type_| type_.id);
+
+        let mut seen = HashSet::new();
+        let mut constants = Vec::new();
+        for type_ in enums {
+            if let BaseTypeKind::Enum { variants, .. } = &type_.kind {
+                for variant in variants {
+                    if seen.insert(variant.name.as_str()) {
+                        constants.push((variant.name.clone(), variant.value));
+                    }
+                }
+            }
+        }
+        constants
+    }
+
+    /// follow a chain of typedefs to the first non-typedef type. pointer and
+    /// qualifier levels of the typedefs along the way are not accumulated, so
+    /// callers that care about them should check each link themselves.
+    pub fn resolve_typedef(&self, id: TypeId) -> Option<&Type> {
+        self.typedef_chain(self.get_type(id)?)
+            .last()
+            .filter(|type_| !matches!(type_.kind, BaseTypeKind::Typedef { .. }))
+    }
+
+    /// `type_` and the types its typedefs name in turn, ending with the
+    /// first one that isn't a typedef. ends early at an unknown id, and
+    /// after as many links as there are types so a malformed cycle
+    /// terminates.
+    fn typedef_chain<'a>(&'a self, type_: &'a Type) -> impl Iterator<Item = &'a Type> {
+        let mut next = Some(type_);
+        let mut links = 0;
+        core::iter::from_fn(move || {
+            let current = next.take()?;
+            if let BaseTypeKind::Typedef {
+                aliased_type_id, ..
+            } = &current.kind
+            {
+                if links < self.types.len() {
+                    next = self.get_type(*aliased_type_id);
+                    links += 1;
+                } else {
+                    log::warn!("typedef cycle detected at {:016x}", type_.id.0);
+                }
+            }
+            Some(current)
+        })
+    }
+
+    /// the qualifiers of a value of type `id` itself, combining those
+    /// written where it is referred to with those its typedefs apply:
+    /// `const CInt` and `CInt` with `typedef const int CInt` are both const.
+    /// for a pointer, these are its outermost level's, so `const CString`
+    /// with `typedef const char* CString` is a const pointer.
+    pub fn effective_qualifiers(&self, id: TypeId) -> Qualifiers {
+        let mut qualifiers = Qualifiers::default();
+        let Some(type_) = self.get_type(id) else {
+            return qualifiers;
+        };
+
+        for current in self.typedef_chain(type_) {
+            if current.pointer_depth > 0 {
+                let level = current.pointer_qualifiers(0);
+                qualifiers.is_const |= level.is_const;
+                qualifiers.is_volatile |= level.is_volatile;
+                break;
+            }
+            qualifiers.is_const |= current.is_const;
+            qualifiers.is_volatile |= current.is_volatile;
+        }
+        qualifiers
+    }
+
+    /// whether an enum backed by `backing_id` holds unsigned values
+    pub fn is_unsigned_enum(&self, backing_id: TypeId) -> bool {
+        self.resolve_primitive(backing_id)
+            .is_some_and(|backing| !backing.is_signed)
+    }
+
+    /// resolve a type to its concrete primitive through any typedefs. returns
+    /// None for void, pointers and non-primitive types.
+    pub fn resolve_primitive(&self, id: TypeId) -> Option<ResolvedPrimitive> {
+        match &self.unaliased_value(id)?.kind {
+            BaseTypeKind::Primitive {
+                name,
+                size,
+                encoding,
+                ..
+            } if name != "void" => Some(ResolvedPrimitive::from_base(name, *size, *encoding)),
+            _ => None,
+        }
+    }
+
+    /// size in bytes of a value of this type. pointers (including pointers
+    /// hidden behind typedefs) are `pointer_width` bytes. returns None for
+    /// unknown ids and function types, which have no size.
+    pub fn size_of(&self, id: TypeId, pointer_width: usize) -> Option<usize> {
+        for current in self.typedef_chain(self.get_type(id)?) {
+            if current.pointer_depth > 0 {
+                return Some(pointer_width);
+            }
+
+            match &current.kind {
+                BaseTypeKind::Primitive { size, .. }
+                | BaseTypeKind::Struct { size, .. }
+                | BaseTypeKind::Union { size, .. }
+                | BaseTypeKind::Enum { size, .. }
+                | BaseTypeKind::Array { size, .. } => return Some(*size),
+                BaseTypeKind::Typedef { .. } => {}
+                BaseTypeKind::Function { .. } => return None,
+            }
+        }
+
+        None
+    }
+
+    /// alignment in bytes of a value of this type. arrays align like their
+    /// elements, enums like their backing type and pointers to
+    /// `pointer_width`. returns None for unknown ids and function types.
+    pub fn align_of(&self, id: TypeId, pointer_width: usize) -> Option<usize> {
+        let mut current = self.get_type(id)?;
+
+        for _ in 0..=self.types.len() {
+            if current.pointer_depth > 0 {
+                return Some(pointer_width);
+            }
+
+            let next = match &current.kind {
+                BaseTypeKind::Primitive { alignment, .. }
+                | BaseTypeKind::Struct { alignment, .. }
+                | BaseTypeKind::Union { alignment, .. } => return Some(*alignment),
+                BaseTypeKind::Enum { backing_id, .. } => *backing_id,
+                BaseTypeKind::Array {
+                    element_type_id, ..
+                } => *element_type_id,
+                BaseTypeKind::Typedef {
+                    aliased_type_id, ..
+                } => *aliased_type_id,
+                BaseTypeKind::Function { .. } => return None,
+            };
+            current = self.get_type(next)?;
+        }
+
+        None
+    }
+
+    /// true for a struct of at most 16 bytes whose members are all `float`
+    /// or `double`, directly or through nested structs and arrays, like the
+    /// test library's `Rectangle`. typedefs naming one count too.
+    ///
+    /// this assumes the System V AMD64 ABI, which passes and returns such a
+    /// struct by value in SSE registers rather than general-purpose ones, so
+    /// a backend that moves it as integers corrupts its fields. other ABIs
+    /// classify aggregates their own way (AArch64 allows up to four members
+    /// of one floating-point type, in any size), and `long double` members
+    /// never qualify.
+    pub fn is_small_float_aggregate(&self, id: TypeId) -> bool {
+        self.unaliased_value(id).is_some_and(|type_| {
+            matches!(type_.kind, BaseTypeKind::Struct { size, .. } if size > 0 && size <= 16)
+                && self.is_all_float(type_.id, MAX_DECLARATION_DEPTH)
+        })
+    }
+
+    /// whether every scalar in a value of type `id` is a `float` or `double`
+    fn is_all_float(&self, id: TypeId, depth: usize) -> bool {
+        let Some(type_) = self.unaliased_value(id) else {
+            return false;
+        };
+        if depth == 0 {
+            return false;
+        }
+        match &type_.kind {
+            BaseTypeKind::Primitive { .. } => self
+                .resolve_primitive(type_.id)
+                .is_some_and(|primitive| primitive.is_float && matches!(primitive.size, 4 | 8)),
+            BaseTypeKind::Struct {
+                fields,
+                is_opaque: false,
+                ..
+            } => {
+                !fields.is_empty()
+                    && fields
+                        .iter()
+                        .all(|field| self.is_all_float(field.type_id, depth - 1))
+            }
+            BaseTypeKind::Array {
+                element_type_id,
+                is_dynamic: false,
+                ..
+            } => self.is_all_float(*element_type_id, depth - 1),
+            _ => false,
+        }
+    }
+
+    /// the type a value of type `id` holds, through any typedefs. None for
+    /// pointers and unknown ids.
+    fn unaliased_value(&self, id: TypeId) -> Option<&Type> {
+        self.typedef_chain(self.get_type(id)?)
+            .find(|type_| {
+                type_.pointer_depth > 0 || !matches!(type_.kind, BaseTypeKind::Typedef { .. })
+            })
+            .filter(|type_| type_.pointer_depth == 0)
+    }
+
+    /// every type transitively referenced from `roots`, including the roots
+    /// themselves. follows struct fields, union variants, enum backing types,
+    /// array elements, typedef aliases and function return/parameter types.
+    /// ids missing from the registry are skipped.
+    pub fn reachable_types(&self, roots: &[TypeId]) -> HashSet<TypeId> {
+        let mut visited = HashSet::new();
+        let mut queue: VecDeque<TypeId> = roots.iter().copied().collect();
+
+        while let Some(id) = queue.pop_front() {
+            let Some(type_) = self.get_type(id) else {
+                continue;
+            };
+            if !visited.insert(id) {
+                continue;
+            }
+
+            queue.extend(
+                type_
+                    .kind
+                    .referenced_type_ids()
+                    .into_iter()
+                    .filter(|id| !visited.contains(id)),
+            );
+        }
+
+        visited
+    }
+
+    /// drop every type not reachable from `roots` (see `reachable_types`),
+    /// and what the name, DWARF offset, typedef name and description
+    /// indices record about them
+    pub fn retain_reachable(&mut self, roots: &[TypeId]) {
+        self.synthetic_names.take();
+        let reachable = self.reachable_types(roots);
+        self.types.retain(|id, _| reachable.contains(id));
+        self.dwarf_to_id.retain(|_, id| reachable.contains(id));
+        self.name_to_ids.retain(|_, ids| {
+            ids.retain(|id| reachable.contains(id));
+            !ids.is_empty()
+        });
+
+        // keyed by unqualified id, which may only be registered behind a
+        // pointer or qualifier
+        let unqualified: HashSet<TypeId> = self.types.values().map(Type::unqualified_id).collect();
+        self.typedef_names.retain(|id, _| unqualified.contains(id));
+        self.descriptions.retain(|id, _| unqualified.contains(id));
+        self.source_definitions
+            .retain(|id| unqualified.contains(id));
+        self.header_typedefs.retain(|id| unqualified.contains(id));
+    }
+
+    /// ids referenced by registered types that are not registered themselves,
+    /// e.g. a field whose type failed to resolve
+    pub fn missing_type_ids(&self) -> HashSet<TypeId> {
+        self.types
+            .values()
+            .flat_map(|type_| type_.kind.referenced_type_ids())
+            .filter(|id| !self.types.contains_key(id))
+            .collect()
+    }
+
+    pub fn all_types(&self) -> impl Iterator<Item = &Type> {
+        self.types.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// merge a registry read from another input (a separate file, or
+    /// another member of an archive) into this one. its units are numbered
+    /// as sources after this registry's, so the same unit offset in both
+    /// stays unambiguous; returns how far their numbers were moved.
+    pub fn merge_source(&mut self, mut other: TypeRegistry) -> u32 {
+        let shift = self.sources;
+        other.dwarf_to_id = other
+            .dwarf_to_id
+            .into_iter()
+            .map(|((unit, offset), id)| {
+                let unit = UnitId {
+                    source: unit.source + shift,
+                    ..unit
+                };
+                ((unit, offset), id)
+            })
+            .collect();
+        self.sources += other.sources;
+        self.merge(other);
+        shift
+    }
+
+    /// merge another registry into this one. its units must come from the
+    /// same input as this one's; use [`merge_source`](Self::merge_source)
+    /// for another file.
+    pub fn merge(&mut self, other: TypeRegistry) {
+        self.synthetic_names.take();
+        self.sources = self.sources.max(other.sources);
+        let initial_count = self.len();
+        let merging_count = other.len();
+
+        // union the types (content-addressed, so same ID = same type)
         for (id, type_) in other.types {
             self.types.entry(id).or_insert(type_);
         }
@@ -427,11 +1869,23 @@ impl TypeRegistry {
             }
         }
 
-        // merge DWARF offset index
-        for (offset, id) in other.dwarf_to_id {
-            self.dwarf_to_id.entry(offset).or_insert(id);
+        // merge DWARF offset index. keys are per unit, so units don't
+        // collide
+        for (key, id) in other.dwarf_to_id {
+            self.dwarf_to_id.entry(key).or_insert(id);
+        }
+
+        for (aggregate_id, name) in other.typedef_names {
+            self.add_typedef_name(aggregate_id, name);
         }
 
+        for (id, description) in other.descriptions {
+            self.descriptions.entry(id).or_insert(description);
+        }
+        self.source_definitions.extend(other.source_definitions);
+        self.header_typedefs.extend(other.header_typedefs);
+        self.cyclic_kinds.extend(other.cyclic_kinds);
+
         let final_count = self.len();
         let added = final_count - initial_count;
         let duplicates = merging_count - added;
@@ -440,6 +1894,24 @@ impl TypeRegistry {
     }
 }
 
+/// how many enclosing anonymous aggregates are followed when naming one.
+/// members held by value can't form a cycle; this is for malformed input
+const MAX_ANONYMOUS_NESTING: usize = 64;
+
+/// how deeply `to_c_string` and `c_declaration` follow array elements and
+/// function pointer parameters and returns. C code never comes close.
+pub const MAX_DECLARATION_DEPTH: usize = 64;
+
+/// `struct`, `union` or `enum`
+fn aggregate_keyword(kind: &BaseTypeKind) -> Option<&'static str> {
+    match kind {
+        BaseTypeKind::Struct { .. } => Some("struct"),
+        BaseTypeKind::Union { .. } => Some("union"),
+        BaseTypeKind::Enum { .. } => Some("enum"),
+        _ => None,
+    }
+}
+
 impl Default for TypeRegistry {
     fn default() -> Self {
         Self::new()
@@ -447,7 +1919,51 @@ impl Default for TypeRegistry {
 }
 
 impl Type {
-    pub(crate) fn get_name(&self) -> String {
+    /// a type of `kind`, neither a pointer nor qualified. its id is filled
+    /// in by [`TypeRegistry::register_type`].
+    pub fn new(kind: BaseTypeKind) -> Self {
+        Self {
+            id: TypeId(0),
+            kind,
+            pointer_depth: 0,
+            is_const: false,
+            is_volatile: false,
+            pointer_qualifiers: Vec::new(),
+            dwarf_offset: None,
+        }
+    }
+
+    /// a builtin type like `int` or `double`, aligned to its size
+    pub fn primitive(name: &str, size: usize) -> Self {
+        Self::new(BaseTypeKind::Primitive {
+            name: name.to_string(),
+            size,
+            alignment: size.max(1),
+            encoding: None,
+        })
+    }
+
+    /// an unqualified pointer to `pointee`, whose qualifiers stay with what
+    /// it points to: a pointer to `const char` is `const char*`
+    pub fn pointer_to(mut pointee: Type) -> Self {
+        pointee.pointer_depth += 1;
+        if !pointee.pointer_qualifiers.is_empty() {
+            pointee.pointer_qualifiers.insert(0, Qualifiers::default());
+        }
+        pointee.id = TypeId(0);
+        pointee.dwarf_offset = None;
+        pointee
+    }
+
+    /// this type `const`: the pointee's `const` for a pointer, as in
+    /// `const char*`
+    pub fn with_const(mut self) -> Self {
+        self.is_const = true;
+        self
+    }
+
+    /// name of the type; arrays and function types have placeholder names
+    pub fn get_name(&self) -> String {
         match &self.kind {
             BaseTypeKind::Primitive { name, .. } => name.clone(),
             BaseTypeKind::Struct { name, .. } => name.clone(),
@@ -459,78 +1975,536 @@ impl Type {
         }
     }
 
-    /// c code string representation
-    pub fn to_c_string(&self, registry: &TypeRegistry) -> String {
-        let mut base_str = match &self.kind {
-            BaseTypeKind::Primitive { name, .. } => name.clone(),
+    /// true for structs, unions and enums declared without a tag
+    pub fn is_anonymous_aggregate(&self) -> bool {
+        match &self.kind {
+            BaseTypeKind::Struct { name, .. }
+            | BaseTypeKind::Union { name, .. }
+            | BaseTypeKind::Enum { name, .. } => name.starts_with('<'),
+            _ => false,
+        }
+    }
 
-            BaseTypeKind::Struct { name, .. } => format!("struct {}", name),
+    /// true for types dwarffi could not model: DWARF tags it doesn't know
+    /// and C++ pointer-to-member types. these are size-0 primitives with
+    /// names like `<unknown:DW_TAG_...>`.
+    pub fn is_placeholder(&self) -> bool {
+        matches!(&self.kind, BaseTypeKind::Primitive { name, .. } if name.starts_with('<'))
+    }
 
-            BaseTypeKind::Union { name, .. } => format!("union {}", name),
+    /// id of this type with pointer levels and qualifiers stripped, e.g. the
+    /// id of `Point` for `const Point*`. the stripped type may not be
+    /// registered.
+    pub fn unqualified_id(&self) -> TypeId {
+        compute_type_id(&self.kind, 0, false, false, &[])
+    }
 
-            BaseTypeKind::Enum { name, .. } => name.clone(),
+    /// true for `char*` and `const char*` with plain `char`, including through
+    /// typedefs. `signed char*` and `unsigned char*` are byte buffers, not
+    /// strings, and return false.
+    pub fn is_c_string_pointer(&self, registry: &TypeRegistry) -> bool {
+        let aliased = match &self.kind {
+            BaseTypeKind::Primitive { name, .. } => {
+                return self.pointer_depth == 1 && name == "char";
+            }
+            BaseTypeKind::Typedef {
+                aliased_type_id, ..
+            } => match registry.get_type(*aliased_type_id) {
+                Some(aliased) => aliased,
+                None => return false,
+            },
+            _ => return false,
+        };
+
+        match self.pointer_depth {
+            // `typedef char* str_t`
+            0 => aliased.is_c_string_pointer(registry),
+            // `gchar*` where `typedef char gchar`
+            1 if aliased.pointer_depth == 0 => registry
+                .resolve_typedef(aliased.id)
+                .is_some_and(|t| t.pointer_depth == 0 && t.get_name() == "char"),
+            _ => false,
+        }
+    }
+
+    /// the function a function pointer calls, seen through any typedefs:
+    /// `typedef void (*Callback)(int)`, `void (*)(int)`, or `Fn*` for
+    /// `typedef void Fn(int)`. None for anything else, including pointers to
+    /// function pointers.
+    pub fn as_function_pointer<'a>(
+        &'a self,
+        registry: &'a TypeRegistry,
+    ) -> Option<FunctionPointer<'a>> {
+        let mut pointer_depth = 0;
+
+        for current in registry.typedef_chain(self) {
+            pointer_depth += current.pointer_depth;
+            match &current.kind {
+                BaseTypeKind::Typedef { .. } => {}
+                BaseTypeKind::Function {
+                    return_type_id,
+                    parameter_type_ids,
+                    is_variadic,
+                } if pointer_depth == 1 => {
+                    return Some(FunctionPointer {
+                        return_type_id: *return_type_id,
+                        parameter_type_ids,
+                        is_variadic: *is_variadic,
+                    });
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// true if `as_function_pointer` finds a function
+    pub fn is_function_pointer(&self, registry: &TypeRegistry) -> bool {
+        self.as_function_pointer(registry).is_some()
+    }
+
+    /// struct fields in source declaration order, e.g. for re-emitting C.
+    /// empty for non-struct types.
+    pub fn fields_in_declaration_order(&self) -> Vec<&StructField> {
+        let mut fields: Vec<&StructField> = match &self.kind {
+            BaseTypeKind::Struct { fields, .. } => fields.iter().collect(),
+            _ => Vec::new(),
+        };
+        fields.sort_by_key(|f| f.declaration_index);
+        fields
+    }
+
+    /// c declaration of `declarator` with this type, placing the name where
+    /// c syntax puts it: `int x[10]`, `char *s`, `void (*f)(int)`. an empty
+    /// declarator gives the abstract form used for unnamed parameters.
+    pub fn c_declaration(&self, registry: &TypeRegistry, declarator: &str) -> String {
+        self.declaration(registry, declarator, false, MAX_DECLARATION_DEPTH)
+    }
+
+    /// `c_declaration`, spelling the base type as `to_c_string` does, e.g.
+    /// `int (*arr)[5]` for a parameter
+    pub(crate) fn display_declaration(&self, registry: &TypeRegistry, declarator: &str) -> String {
+        self.declaration(registry, declarator, true, MAX_DECLARATION_DEPTH)
+    }
+
+    /// `c_declaration`; with `display`, anonymous aggregates go by their
+    /// typedef and enums by their bare name. array elements and function
+    /// parameters and returns nested deeper than `depth` are left as
+    /// `/* ... */`, so a malformed type can't overflow the stack.
+    fn declaration(
+        &self,
+        registry: &TypeRegistry,
+        declarator: &str,
+        display: bool,
+        depth: usize,
+    ) -> String {
+        let mut decl = (self.pointer_declarator() + declarator)
+            .trim_end()
+            .to_string();
+
+        if depth == 0 {
+            return format!("/* ... */ {}", decl).trim_end().to_string();
+        }
+
+        // a pointer to an array or function binds tighter than the suffix
+        let parenthesize = |decl: String| {
+            if decl.starts_with('*') {
+                format!("({})", decl)
+            } else {
+                decl
+            }
+        };
 
+        match &self.kind {
             BaseTypeKind::Array {
                 element_type_id,
                 count,
+                is_dynamic,
                 ..
             } => {
+                let bound = if *is_dynamic {
+                    "*".to_string()
+                } else {
+                    count.to_string()
+                };
+                decl = format!("{}[{}]", parenthesize(decl), bound);
+
                 let elem = registry
                     .get_type(*element_type_id)
-                    .map(|t| t.to_c_string(registry))
-                    .unwrap_or_else(|| "void".to_string());
-                format!("{}[{}]", elem, count)
+                    .map(|t| t.declaration(registry, &decl, display, depth - 1))
+                    .unwrap_or_else(|| format!("void {}", decl));
+                self.qualify(registry, elem)
             }
 
-            BaseTypeKind::Typedef { name, .. } => name.clone(),
-
             BaseTypeKind::Function {
                 return_type_id,
                 parameter_type_ids,
                 is_variadic,
             } => {
-                let ret = return_type_id
-                    .and_then(|id| registry.get_type(id))
-                    .map(|t| t.to_c_string(registry))
-                    .unwrap_or_else(|| "void".to_string());
-
-                let params: Vec<String> = parameter_type_ids
+                let mut params: Vec<String> = parameter_type_ids
                     .iter()
                     .filter_map(|id| registry.get_type(*id))
-                    .map(|t| t.to_c_string(registry))
+                    .map(|t| t.declaration(registry, "", display, depth - 1))
                     .collect();
-
-                let param_str = if params.is_empty() {
-                    "void".to_string()
+                if params.is_empty() {
+                    params.push("void".to_string());
                 } else if *is_variadic {
-                    format!("{}, ...", params.join(", "))
-                } else {
-                    params.join(", ")
+                    params.push("...".to_string());
+                }
+                decl = format!("{}({})", parenthesize(decl), params.join(", "));
+
+                return_type_id
+                    .and_then(|id| registry.get_type(id))
+                    .map(|t| t.declaration(registry, &decl, display, depth - 1))
+                    .unwrap_or_else(|| format!("void {}", decl))
+            }
+
+            _ => {
+                let base = match &self.kind {
+                    _ if display => self.specifier(registry),
+                    BaseTypeKind::Struct { name, .. } => format!("struct {}", name),
+                    BaseTypeKind::Union { name, .. } => format!("union {}", name),
+                    BaseTypeKind::Enum { name, .. } => format!("enum {}", name),
+                    _ => self.get_name(),
                 };
+                let base = self.qualify(registry, base);
+                if decl.is_empty() {
+                    base
+                } else if decl.starts_with('[') {
+                    // abstract array: `int[10]`
+                    format!("{}{}", base, decl)
+                } else {
+                    format!("{} {}", base, decl)
+                }
+            }
+        }
+    }
+
+    /// the `*`s of a declarator, innermost first, with their qualifiers:
+    /// `*const *` for a pointer to a const pointer
+    fn pointer_declarator(&self) -> String {
+        let mut decl = String::new();
+        for level in (0..self.pointer_depth).rev() {
+            decl.push('*');
+            let qualifiers = self.pointer_qualifiers(level);
+            if qualifiers.is_const {
+                decl.push_str("const ");
+            }
+            if qualifiers.is_volatile {
+                decl.push_str("volatile ");
+            }
+        }
+        decl
+    }
+
+    /// qualifiers of pointer level `level`, counting from the outermost
+    pub fn pointer_qualifiers(&self, level: usize) -> Qualifiers {
+        self.pointer_qualifiers
+            .get(level)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// the type name without qualifiers or pointers, as `to_c_string` spells
+    /// it. arrays and functions have no specifier of their own.
+    fn specifier(&self, registry: &TypeRegistry) -> String {
+        match &self.kind {
+            BaseTypeKind::Primitive { name, .. } => name.clone(),
+
+            // spelled by the typedef that names it, when there is one
+            _ if self.is_anonymous_aggregate()
+                && let Some(name) = registry.typedef_names.get(&self.unqualified_id()) =>
+            {
+                name.clone()
+            }
+
+            BaseTypeKind::Struct { name, .. } => format!("struct {}", name),
 
-                format!("{} (*)({})", ret, param_str)
+            BaseTypeKind::Union { name, .. } => format!("union {}", name),
+
+            BaseTypeKind::Enum { name, .. } | BaseTypeKind::Typedef { name, .. } => name.clone(),
+
+            BaseTypeKind::Array { .. } | BaseTypeKind::Function { .. } => self.get_name(),
+        }
+    }
+
+    /// prefix `specifier` with this type's qualifiers, as `to_c_string` does.
+    /// one already spelled at the front of `specifier` (an array's element
+    /// type), or already applied by the typedef it names, is left out rather
+    /// than written twice.
+    fn qualify(&self, registry: &TypeRegistry, mut specifier: String) -> String {
+        let mut spelled = self.typedef_qualifiers(registry);
+        for word in specifier.split_whitespace() {
+            match word {
+                "const" => spelled.is_const = true,
+                "volatile" => spelled.is_volatile = true,
+                _ => break,
             }
+        }
+
+        if self.is_const && !spelled.is_const {
+            specifier = format!("const {}", specifier);
+        }
+        if self.is_volatile && !spelled.is_volatile {
+            specifier = format!("volatile {}", specifier);
+        }
+        specifier
+    }
+
+    /// the qualifiers a typedef applies to the type it names, which
+    /// qualifying the typedef again doesn't change
+    fn typedef_qualifiers(&self, registry: &TypeRegistry) -> Qualifiers {
+        match &self.kind {
+            BaseTypeKind::Typedef {
+                aliased_type_id, ..
+            } => registry.effective_qualifiers(*aliased_type_id),
+            _ => Qualifiers::default(),
+        }
+    }
+
+    /// whether this type is the one a C type spelling names, comparing
+    /// structure rather than text: `const char*`, `char const *` and
+    /// `const char *` all match the same type, as do `unsigned long` and
+    /// `long unsigned int`. a typedef matches only its own name, `struct X`
+    /// the struct tagged `X`, and a bare name an anonymous aggregate named
+    /// by that typedef, as `to_c_string` spells it. a qualifier the typedef
+    /// already applies may be spelled or not.
+    /// arrays, function types and spellings that don't parse never match.
+    pub fn matches_c_type(&self, registry: &TypeRegistry, c_type: &str) -> bool {
+        let Some(parsed) = c_type::parse(c_type) else {
+            return false;
         };
+        let pointers = (0..self.pointer_depth).map(|level| self.pointer_qualifiers(level));
+        // `const CInt` names `CInt` when the typedef is const already
+        let implied = self.typedef_qualifiers(registry);
+        if parsed.pointers.len() != self.pointer_depth
+            || !parsed.pointers.iter().copied().eq(pointers)
+            || (parsed.qualifiers.is_const || implied.is_const)
+                != (self.is_const || implied.is_const)
+            || (parsed.qualifiers.is_volatile || implied.is_volatile)
+                != (self.is_volatile || implied.is_volatile)
+        {
+            return false;
+        }
 
-        if self.is_const {
-            base_str = format!("const {}", base_str);
+        let typedef_name = || registry.typedef_names.get(&self.unqualified_id());
+        match (&parsed.specifier, &self.kind) {
+            (Specifier::Builtin(spelled), BaseTypeKind::Primitive { name, .. }) => {
+                let words: Vec<&str> = name.split_whitespace().collect();
+                c_type::builtin_name(&words).as_ref() == Some(spelled)
+            }
+            (Specifier::Name(spelled), BaseTypeKind::Typedef { name, .. }) => spelled == name,
+            // `to_c_string` spells enums bare, and anonymous aggregates by
+            // their typedef
+            (Specifier::Name(spelled), BaseTypeKind::Enum { name, .. }) => {
+                spelled == name || typedef_name() == Some(spelled)
+            }
+            (Specifier::Name(spelled), BaseTypeKind::Struct { .. })
+            | (Specifier::Name(spelled), BaseTypeKind::Union { .. }) => {
+                self.is_anonymous_aggregate() && typedef_name() == Some(spelled)
+            }
+            (Specifier::Tagged(keyword, spelled), kind) => {
+                aggregate_keyword(kind) == Some(*keyword) && self.get_name() == *spelled
+            }
+            _ => false,
         }
-        if self.is_volatile {
-            base_str = format!("volatile {}", base_str);
+    }
+
+    /// c code string representation
+    pub fn to_c_string(&self, registry: &TypeRegistry) -> String {
+        self.to_c_string_with_depth(registry, MAX_DECLARATION_DEPTH)
+    }
+
+    /// `to_c_string`, following array elements and function pointer
+    /// parameters and returns at most `max_depth` levels deep. deeper ones
+    /// are spelled `/* ... */`.
+    pub fn to_c_string_with_depth(&self, registry: &TypeRegistry, max_depth: usize) -> String {
+        // pointers to arrays and functions belong inside the declarator:
+        // `int (*)[5]`, `void (*)(int)`
+        if matches!(
+            self.kind,
+            BaseTypeKind::Array { .. } | BaseTypeKind::Function { .. }
+        ) {
+            return self.declaration(registry, "", true, max_depth);
         }
 
-        for _ in 0..self.pointer_depth {
+        // qualifiers before the specifier apply to the pointee; each `*` is
+        // followed by its own, so `const Point*`, `Point* const` and
+        // `const Point* const` stay distinct
+        let mut base_str = self.qualify(registry, self.specifier(registry));
+        for level in (0..self.pointer_depth).rev() {
             base_str.push('*');
+            let qualifiers = self.pointer_qualifiers(level);
+            if qualifiers.is_const {
+                base_str.push_str(" const");
+            }
+            if qualifiers.is_volatile {
+                base_str.push_str(" volatile");
+            }
         }
 
         base_str
     }
 }
 
+#[cfg(feature = "rust-types")]
+impl Type {
+    /// Rust spelling of the type, as `to_c_string` gives the C one: `int` is
+    /// `core::ffi::c_int`, `const char*` is `*const core::ffi::c_char`, a
+    /// function pointer is `Option<unsafe extern "C" fn(..)>`. typedefs and
+    /// aggregates keep their names, except the `<stdint.h>` ones, which are
+    /// Rust's fixed-width integers. a primitive Rust has no equivalent for,
+    /// like `long double`, is kept only by its size, as bytes.
+    pub fn to_rust_type(&self, registry: &TypeRegistry) -> String {
+        self.rust_type_with_depth(registry, MAX_DECLARATION_DEPTH)
+    }
+
+    fn rust_type_with_depth(&self, registry: &TypeRegistry, depth: usize) -> String {
+        if depth == 0 {
+            return "_".to_string();
+        }
+        let rust_type_of = |id| {
+            registry
+                .get_type(id)
+                .map_or("core::ffi::c_void".to_string(), |t| {
+                    t.rust_type_with_depth(registry, depth - 1)
+                })
+        };
+
+        // a function pointer's first level is the `fn` itself
+        let mut pointer_depth = self.pointer_depth;
+        let mut rust_type = match &self.kind {
+            BaseTypeKind::Function {
+                return_type_id,
+                parameter_type_ids,
+                is_variadic,
+            } => {
+                let mut params: Vec<String> = parameter_type_ids
+                    .iter()
+                    .map(|id| rust_type_of(*id))
+                    .collect();
+                if *is_variadic {
+                    params.push("...".to_string());
+                }
+                let returns = return_type_id
+                    .map(rust_type_of)
+                    .filter(|returns| returns != "()")
+                    .map_or(String::new(), |returns| format!(" -> {}", returns));
+                let function = format!("unsafe extern \"C\" fn({}){}", params.join(", "), returns);
+                if pointer_depth == 0 {
+                    function
+                } else {
+                    pointer_depth -= 1;
+                    format!("Option<{}>", function)
+                }
+            }
+            BaseTypeKind::Primitive { name, .. } if name == "void" => {
+                if pointer_depth == 0 {
+                    "()".to_string()
+                } else {
+                    "core::ffi::c_void".to_string()
+                }
+            }
+            BaseTypeKind::Primitive { name, size, .. } => {
+                rust_primitive(name).map_or_else(|| format!("[u8; {}]", size), str::to_string)
+            }
+            BaseTypeKind::Typedef { name, .. } => {
+                rust_fixed_width(name).map_or_else(|| name.clone(), str::to_string)
+            }
+            BaseTypeKind::Struct { .. }
+            | BaseTypeKind::Union { .. }
+            | BaseTypeKind::Enum { .. } => {
+                let name = registry
+                    .display_name(self.id)
+                    .unwrap_or_else(|| self.get_name());
+                if name.starts_with('<') {
+                    registry.synthetic_name(self.id).unwrap_or(name)
+                } else {
+                    name
+                }
+            }
+            BaseTypeKind::Array {
+                element_type_id,
+                count,
+                ..
+            } => format!("[{}; {}]", rust_type_of(*element_type_id), count),
+        };
+
+        // innermost pointer first; each one is `*const` if what it points
+        // to is, counting a const the typedef it points to applies
+        for level in (0..pointer_depth).rev() {
+            let pointee_is_const = if level + 1 == pointer_depth {
+                self.is_const || self.typedef_qualifiers(registry).is_const
+            } else {
+                self.pointer_qualifiers(level + 1).is_const
+            };
+            let pointer = if pointee_is_const { "*const" } else { "*mut" };
+            rust_type = format!("{} {}", pointer, rust_type);
+        }
+
+        rust_type
+    }
+}
+
+/// the `core::ffi` type for a C builtin type, however the compiler spelled
+/// it, or None if Rust has no equivalent (`long double`)
+#[cfg(feature = "rust-types")]
+pub fn rust_primitive(name: &str) -> Option<&'static str> {
+    let rust_type = match name {
+        "_Bool" | "bool" => "bool",
+        "float" => "core::ffi::c_float",
+        "double" => "core::ffi::c_double",
+        _ => match c_type::canonical_integer_name(name, None)?.as_str() {
+            "char" => "core::ffi::c_char",
+            "signed char" => "core::ffi::c_schar",
+            "unsigned char" => "core::ffi::c_uchar",
+            "short" => "core::ffi::c_short",
+            "unsigned short" => "core::ffi::c_ushort",
+            "int" => "core::ffi::c_int",
+            "unsigned int" => "core::ffi::c_uint",
+            "long" => "core::ffi::c_long",
+            "unsigned long" => "core::ffi::c_ulong",
+            "long long" => "core::ffi::c_longlong",
+            "unsigned long long" => "core::ffi::c_ulonglong",
+            "__int128" => "i128",
+            "unsigned __int128" => "u128",
+            _ => return None,
+        },
+    };
+    Some(rust_type)
+}
+
+/// Rust's own type for a `<stdint.h>` or `<stddef.h>` typedef
+#[cfg(feature = "rust-types")]
+pub fn rust_fixed_width(name: &str) -> Option<&'static str> {
+    let rust_type = match name {
+        "int8_t" => "i8",
+        "uint8_t" => "u8",
+        "int16_t" => "i16",
+        "uint16_t" => "u16",
+        "int32_t" => "i32",
+        "uint32_t" => "u32",
+        "int64_t" => "i64",
+        "uint64_t" => "u64",
+        "size_t" | "uintptr_t" => "usize",
+        "ssize_t" | "ptrdiff_t" | "intptr_t" => "isize",
+        _ => return None,
+    };
+    Some(rust_type)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_analysis_results_can_be_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<TypeRegistry>();
+        assert_send_sync::<crate::AnalysisResult>();
+    }
+
     #[test]
     fn test_registry_basic_operations() {
         let mut registry = TypeRegistry::new();
@@ -541,10 +2515,12 @@ mod tests {
                 name: "int".to_string(),
                 size: 4,
                 alignment: 4,
+                encoding: None,
             },
             pointer_depth: 0,
             is_const: false,
             is_volatile: false,
+            pointer_qualifiers: Vec::new(),
             dwarf_offset: Some(0x1234),
         };
 
@@ -561,6 +2537,7 @@ mod tests {
                 name,
                 size,
                 alignment,
+                ..
             } => {
                 assert_eq!(name, "int");
                 assert_eq!(*size, 4);
@@ -570,10 +2547,58 @@ mod tests {
         }
 
         // by DWARF offset
-        let by_offset = registry.get_by_dwarf_offset(0x1234).unwrap();
+        let by_offset = registry
+            .get_by_unit_offset(UnitId::default(), 0x1234)
+            .unwrap();
         assert_eq!(by_offset.id, id);
     }
 
+    #[test]
+    fn test_offsets_are_qualified_by_unit() {
+        let primitive = |name: &str| Type {
+            dwarf_offset: Some(0x2a),
+            ..Type::primitive(name, 4)
+        };
+
+        let unit = |source, offset| UnitId { source, offset };
+
+        // the same unit-relative offset in two different units
+        let mut first = TypeRegistry::new();
+        first.set_unit(unit(0, 0));
+        let int_id = first.register_type(primitive("int"));
+        let mut second = TypeRegistry::new();
+        second.set_unit(unit(0, 0x100));
+        let float_id = second.register_type(primitive("float"));
+
+        first.merge(second);
+        assert_eq!(first.get_by_unit_offset(unit(0, 0), 0x2a).unwrap().id, int_id);
+        assert_eq!(
+            first.get_by_unit_offset(unit(0, 0x100), 0x2a).unwrap().id,
+            float_id
+        );
+        assert!(first.get_by_unit_offset(unit(0, 0x200), 0x2a).is_none());
+
+        // and in units at the same offset of two different files
+        let mut other_file = TypeRegistry::new();
+        let char_id = other_file.register_type(primitive("char"));
+        let mut last_file = TypeRegistry::new();
+        let long_id = last_file.register_type(primitive("long"));
+        assert_eq!(first.merge_source(other_file), 1);
+        assert_eq!(first.merge_source(last_file), 2);
+        assert_eq!(
+            first.get_by_unit_offset(unit(0, 0), 0x2a).unwrap().id,
+            int_id
+        );
+        assert_eq!(
+            first.get_by_unit_offset(unit(1, 0), 0x2a).unwrap().id,
+            char_id
+        );
+        assert_eq!(
+            first.get_by_unit_offset(unit(2, 0), 0x2a).unwrap().id,
+            long_id
+        );
+    }
+
     #[test]
     fn test_registry_multiple_types() {
         let mut registry = TypeRegistry::new();
@@ -584,10 +2609,12 @@ mod tests {
                 name: "int".to_string(),
                 size: 4,
                 alignment: 4,
+                encoding: None,
             },
             pointer_depth: 0,
             is_const: false,
             is_volatile: false,
+            pointer_qualifiers: Vec::new(),
             dwarf_offset: Some(0x100),
         };
 
@@ -597,10 +2624,12 @@ mod tests {
                 name: "float".to_string(),
                 size: 4,
                 alignment: 4,
+                encoding: None,
             },
             pointer_depth: 0,
             is_const: false,
             is_volatile: false,
+            pointer_qualifiers: Vec::new(),
             dwarf_offset: Some(0x200),
         };
 
@@ -616,18 +2645,7 @@ mod tests {
     fn test_get_by_name() {
         let mut registry = TypeRegistry::new();
 
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let int_type = Type::primitive("int", 4);
 
         let id = registry.register_type(int_type);
 
@@ -639,23 +2657,47 @@ mod tests {
         assert_eq!(no_types.len(), 0);
     }
 
+    #[test]
+    fn test_get_base_by_name_and_name_collisions() {
+        let mut registry = TypeRegistry::new();
+        let int_id = registry.register_type(Type::primitive("int", 4));
+        let stat = |size, pointer_depth, is_opaque| Type {
+            pointer_depth,
+            ..Type::new(BaseTypeKind::Struct {
+                name: "stat".to_string(),
+                fields: vec![StructField::new("st_size", int_id, 0, 4)],
+                size,
+                alignment: 4,
+                is_opaque,
+            })
+        };
+
+        // pointer registered first, so get_by_name alone would return it
+        registry.register_type(stat(144, 1, false));
+        let stat_id = registry.register_type(stat(144, 0, false));
+        registry.register_type(stat(0, 0, true));
+        registry.register_type(Type::new(BaseTypeKind::Typedef {
+            name: "stat".to_string(),
+            aliased_type_id: stat_id,
+        }));
+
+        let base = registry.get_base_by_name("stat").unwrap();
+        assert_eq!(base.id, stat_id);
+        assert!(registry.get_base_by_name("nonexistent").is_none());
+
+        // opaque declaration and same-named typedef are not collisions
+        assert!(registry.name_collisions().is_empty());
+
+        registry.register_type(stat(128, 0, false));
+        assert_eq!(registry.name_collisions(), [("stat".to_string(), 2)]);
+    }
+
     #[test]
     fn test_pointer_depth() {
         let mut registry = TypeRegistry::new();
 
         // int**
-        let int_double_ptr = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 2,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let int_double_ptr = Type::pointer_to(Type::pointer_to(Type::primitive("int", 4)));
 
         let id = registry.register_type(int_double_ptr);
         let retrieved = registry.get_type(id).unwrap();
@@ -666,18 +2708,7 @@ mod tests {
     fn test_const_volatile_flags() {
         let mut registry = TypeRegistry::new();
 
-        let const_int = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 1,
-            is_const: true,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let const_int = Type::pointer_to(Type::primitive("int", 4).with_const());
 
         let id = registry.register_type(const_int);
         let retrieved = registry.get_type(id).unwrap();
@@ -685,52 +2716,27 @@ mod tests {
         assert!(!retrieved.is_volatile);
     }
 
-    #[test]
-    fn test_struct_type() {
-        let mut registry = TypeRegistry::new();
-
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
-        let int_id = registry.register_type(int_type);
-
-        let point_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Struct {
-                name: "Point".to_string(),
-                fields: vec![
-                    StructField {
-                        name: "x".to_string(),
-                        type_id: int_id,
-                        offset: 0,
-                        size: 4,
-                    },
-                    StructField {
-                        name: "y".to_string(),
-                        type_id: int_id,
-                        offset: 4,
-                        size: 4,
-                    },
-                ],
-                size: 8,
-                alignment: 4,
-                is_opaque: false,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
-
+    #[test]
+    fn test_struct_type() {
+        let mut registry = TypeRegistry::new();
+
+        let int_type = Type::primitive("int", 4);
+        let int_id = registry.register_type(int_type);
+
+        let point_type = Type::new(BaseTypeKind::Struct {
+            name: "Point".to_string(),
+            fields: vec![
+                StructField::new("x", int_id, 0, 4),
+                StructField {
+                    declaration_index: 1,
+                    ..StructField::new("y", int_id, 4, 4)
+                },
+            ],
+            size: 8,
+            alignment: 4,
+            is_opaque: false,
+        });
+
         let point_id = registry.register_type(point_type);
         let retrieved = registry.get_type(point_id).unwrap();
 
@@ -757,42 +2763,24 @@ mod tests {
     fn test_enum_type() {
         let mut registry = TypeRegistry::new();
 
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let int_type = Type::primitive("int", 4);
         let int_id = registry.register_type(int_type);
 
-        let status_enum = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Enum {
-                name: "Status".to_string(),
-                backing_id: int_id,
-                variants: vec![
-                    EnumVariant {
-                        name: "STATUS_OK".to_string(),
-                        value: 0,
-                    },
-                    EnumVariant {
-                        name: "STATUS_ERROR".to_string(),
-                        value: 1,
-                    },
-                ],
-                size: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let status_enum = Type::new(BaseTypeKind::Enum {
+            name: "Status".to_string(),
+            backing_id: int_id,
+            variants: vec![
+                EnumVariant {
+                    name: "STATUS_OK".to_string(),
+                    value: 0,
+                },
+                EnumVariant {
+                    name: "STATUS_ERROR".to_string(),
+                    value: 1,
+                },
+            ],
+            size: 4,
+        });
 
         let enum_id = registry.register_type(status_enum);
         let retrieved = registry.get_type(enum_id).unwrap();
@@ -818,32 +2806,16 @@ mod tests {
     fn test_array_type() {
         let mut registry = TypeRegistry::new();
 
-        let char_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "char".to_string(),
-                size: 1,
-                alignment: 1,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let char_type = Type::primitive("char", 1);
         let char_id = registry.register_type(char_type);
 
-        let char_array = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Array {
-                element_type_id: char_id,
-                count: 64,
-                size: 64,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let char_array = Type::new(BaseTypeKind::Array {
+            element_type_id: char_id,
+            count: 64,
+            size: 64,
+            stride: 1,
+            is_dynamic: false,
+        });
 
         let array_id = registry.register_type(char_array);
         let retrieved = registry.get_type(array_id).unwrap();
@@ -853,44 +2825,456 @@ mod tests {
                 element_type_id,
                 count,
                 size,
+                stride,
+                is_dynamic,
             } => {
                 assert_eq!(*element_type_id, char_id);
                 assert_eq!(*count, 64);
                 assert_eq!(*size, 64);
+                assert_eq!(*stride, 1);
+                assert!(!is_dynamic);
             }
             _ => panic!("Expected array type"),
         }
     }
 
     #[test]
-    fn test_typedef() {
+    fn test_dynamic_array_distinct_from_zero_length() {
         let mut registry = TypeRegistry::new();
 
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
+        let int_id = registry.register_type(Type::primitive("int", 4));
+
+        let array = |is_dynamic| {
+            Type::new(BaseTypeKind::Array {
+                element_type_id: int_id,
+                count: 0,
+                size: 0,
+                stride: 4,
+                is_dynamic,
+            })
+        };
+
+        let flexible_id = registry.register_type(array(false));
+        let dynamic_id = registry.register_type(array(true));
+        assert_ne!(flexible_id, dynamic_id);
+
+        let flexible = registry.get_type(flexible_id).unwrap();
+        let dynamic = registry.get_type(dynamic_id).unwrap();
+        assert_eq!(flexible.to_c_string(&registry), "int[0]");
+        assert_eq!(dynamic.to_c_string(&registry), "int[*]");
+    }
+
+    #[test]
+    fn test_deeply_nested_arrays_are_cut_short() {
+        let mut registry = TypeRegistry::new();
+        let mut element_type_id = registry.register_type(Type::primitive("int", 4));
+        // far deeper than the stack would allow without a limit
+        for _ in 0..100_000 {
+            element_type_id = registry.register_type(Type::new(BaseTypeKind::Array {
+                element_type_id,
+                count: 1,
                 size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
+                stride: 4,
+                is_dynamic: false,
+            }));
+        }
+        let nested = registry.get_type(element_type_id).unwrap();
+
+        let c_string = nested.to_c_string(&registry);
+        assert!(c_string.starts_with("/* ... */ [1][1]"), "{}", c_string);
+        assert_eq!(c_string.matches("[1]").count(), MAX_DECLARATION_DEPTH);
+        assert_eq!(nested.to_c_string_with_depth(&registry, 2), "/* ... */ [1][1]");
+        assert!(nested.c_declaration(&registry, "x").starts_with("/* ... */ x[1]"));
+    }
+
+    #[test]
+    fn test_c_declaration_places_declarator() {
+        let mut registry = TypeRegistry::new();
+        let primitive = |name: &str, size, pointer_depth| Type {
+            pointer_depth,
+            ..Type::primitive(name, size)
+        };
+        let void_id = registry.register_type(primitive("void", 0, 0));
+        let int_id = registry.register_type(primitive("int", 4, 0));
+        let char_ptr_id = registry.register_type(primitive("char", 1, 1));
+
+        let array = |pointer_depth| Type {
+            pointer_depth,
+            ..Type::new(BaseTypeKind::Array {
+                element_type_id: int_id,
+                count: 10,
+                size: 40,
+                stride: 4,
+                is_dynamic: false,
+            })
+        };
+        let array_id = registry.register_type(array(0));
+        let array_ptr_id = registry.register_type(array(1));
+        let pointer_array_id = registry.register_type(Type::new(BaseTypeKind::Array {
+            element_type_id: char_ptr_id,
+            count: 4,
+            size: 32,
+            stride: 8,
+            is_dynamic: false,
+        }));
+
+        let callback_id =
+            registry.register_type(Type::pointer_to(Type::new(BaseTypeKind::Function {
+                return_type_id: Some(void_id),
+                parameter_type_ids: vec![int_id],
+                is_variadic: false,
+            })));
+
+        let decl = |id, name| {
+            registry
+                .get_type(id)
+                .unwrap()
+                .c_declaration(&registry, name)
+        };
+        assert_eq!(decl(array_id, "x"), "int x[10]");
+        assert_eq!(decl(char_ptr_id, "s"), "char *s");
+        assert_eq!(decl(callback_id, "f"), "void (*f)(int)");
+        assert_eq!(decl(array_ptr_id, "p"), "int (*p)[10]");
+        assert_eq!(decl(char_ptr_id, ""), "char *");
+        assert_eq!(decl(callback_id, ""), "void (*)(int)");
+        assert_eq!(decl(pointer_array_id, "argv"), "char *argv[4]");
+        let callback = registry.get_type(callback_id).unwrap();
+        assert_eq!(callback.to_c_string(&registry), "void (*)(int)");
+
+        // pointer to array vs. array of pointers
+        let c_string = |id| registry.get_type(id).unwrap().to_c_string(&registry);
+        assert_eq!(c_string(array_id), "int[10]");
+        assert_eq!(c_string(array_ptr_id), "int (*)[10]");
+        assert_eq!(c_string(pointer_array_id), "char *[4]");
+    }
+
+    #[test]
+    fn test_as_function_pointer_unwraps_typedefs() {
+        let mut registry = TypeRegistry::new();
+        let make = |kind, pointer_depth| Type {
+            pointer_depth,
+            ..Type::new(kind)
+        };
+        let int_id = registry.register_type(Type::primitive("int", 4));
+        let function = |pointer_depth| {
+            make(
+                BaseTypeKind::Function {
+                    return_type_id: Some(int_id),
+                    parameter_type_ids: vec![int_id],
+                    is_variadic: false,
+                },
+                pointer_depth,
+            )
+        };
+        let typedef = |name: &str, aliased_type_id, pointer_depth| {
+            make(
+                BaseTypeKind::Typedef {
+                    name: name.to_string(),
+                    aliased_type_id,
+                },
+                pointer_depth,
+            )
+        };
+
+        let bare_id = registry.register_type(function(0));
+        let pointer_id = registry.register_type(function(1));
+        let pointer_pointer_id = registry.register_type(function(2));
+        // typedef int (*Op)(int); typedef Op Op2;
+        let op_id = registry.register_type(typedef("Op", pointer_id, 0));
+        let op2_id = registry.register_type(typedef("Op2", op_id, 0));
+        // typedef int Fn(int); Fn*
+        let fn_ptr_id = registry.register_type(typedef("Fn", bare_id, 1));
+
+        let as_function_pointer = |id| {
+            registry
+                .get_type(id)
+                .unwrap()
+                .as_function_pointer(&registry)
+        };
+        let expected = FunctionPointer {
+            return_type_id: Some(int_id),
+            parameter_type_ids: &[int_id],
+            is_variadic: false,
+        };
+        for id in [pointer_id, op_id, op2_id, fn_ptr_id] {
+            assert_eq!(as_function_pointer(id), Some(expected));
+        }
+        for id in [int_id, bare_id, pointer_pointer_id] {
+            assert_eq!(as_function_pointer(id), None);
+        }
+    }
+
+    #[test]
+    fn test_pointer_level_qualifiers() {
+        let mut registry = TypeRegistry::new();
+        let uint32_ptr = |is_volatile, pointer_qualifiers| Type {
+            is_volatile,
+            pointer_qualifiers,
+            ..Type::pointer_to(Type::primitive("uint32_t", 4))
+        };
+        let const_level = Qualifiers {
+            is_const: true,
             is_volatile: false,
-            dwarf_offset: None,
         };
-        let int_id = registry.register_type(int_type);
 
-        let size_t_typedef = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Typedef {
-                name: "size_t".to_string(),
-                aliased_type_id: int_id,
+        // pointer to volatile vs. volatile pointer
+        let to_volatile = registry.register_type(uint32_ptr(true, Vec::new()));
+        let volatile_ptr = registry.register_type(uint32_ptr(
+            false,
+            vec![Qualifiers {
+                is_const: false,
+                is_volatile: true,
+            }],
+        ));
+        let both = registry.register_type(uint32_ptr(true, vec![const_level]));
+        assert_ne!(to_volatile, volatile_ptr);
+
+        let render = |id| {
+            let type_ = registry.get_type(id).unwrap();
+            (
+                type_.to_c_string(&registry),
+                type_.c_declaration(&registry, "reg"),
+            )
+        };
+        assert_eq!(
+            render(to_volatile),
+            (
+                "volatile uint32_t*".to_string(),
+                "volatile uint32_t *reg".to_string()
+            )
+        );
+        assert_eq!(
+            render(volatile_ptr),
+            (
+                "uint32_t* volatile".to_string(),
+                "uint32_t *volatile reg".to_string()
+            )
+        );
+        assert_eq!(
+            render(both),
+            (
+                "volatile uint32_t* const".to_string(),
+                "volatile uint32_t *const reg".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_unqualified_pointer_levels_share_an_id() {
+        let mut registry = TypeRegistry::new();
+        let const_level = Qualifiers {
+            is_const: true,
+            is_volatile: false,
+        };
+        let int_ptr = |depth, pointer_qualifiers| Type {
+            pointer_depth: depth,
+            pointer_qualifiers,
+            ..Type::primitive("int", 4)
+        };
+
+        for depth in 1..=3 {
+            let bare = registry.register_type(int_ptr(depth, Vec::new()));
+            let spelled_out =
+                registry.register_type(int_ptr(depth, vec![Qualifiers::default(); depth]));
+            assert_eq!(bare, spelled_out, "depth {}", depth);
+            let stored = registry.get_type(spelled_out).unwrap();
+            assert!(stored.pointer_qualifiers.is_empty());
+        }
+
+        // `int* const*`: only levels after the last qualified one are dropped
+        let const_outer = registry.register_type(int_ptr(2, vec![const_level]));
+        assert_eq!(
+            registry.register_type(int_ptr(2, vec![const_level, Qualifiers::default()])),
+            const_outer
+        );
+        assert_ne!(
+            registry.register_type(int_ptr(2, vec![Qualifiers::default(), const_level])),
+            const_outer
+        );
+    }
+
+    #[test]
+    fn test_type_ids_do_not_change() {
+        // ids end up in generated files and JSON output, so a change to how
+        // they're computed shows up here before it reaches anyone's diff
+        let big_bits = StructField {
+            endianness: Some(Endianness::Big),
+            bit_size: Some(3),
+            bit_offset: Some(37),
+            ..StructField::new("flags", TypeId(2), 4, 4)
+        };
+        let variant = |name: &str, type_id, discriminant_value| UnionField {
+            name: name.to_string(),
+            type_id: TypeId(type_id),
+            discriminant_value,
+        };
+        let enum_variant = |name: &str, value| EnumVariant {
+            name: name.to_string(),
+            value,
+        };
+        let kinds = [
+            BaseTypeKind::Primitive {
+                name: "unsigned int".to_string(),
+                size: 4,
+                alignment: 4,
+                encoding: None,
             },
-            pointer_depth: 0,
-            is_const: false,
+            BaseTypeKind::Primitive {
+                name: "double".to_string(),
+                size: 8,
+                alignment: 8,
+                encoding: Some(PrimitiveEncoding::Float),
+            },
+            BaseTypeKind::Struct {
+                name: "Packet".to_string(),
+                fields: vec![StructField::new("id", TypeId(1), 0, 4), big_bits],
+                size: 8,
+                alignment: 4,
+                is_opaque: false,
+            },
+            BaseTypeKind::Struct {
+                name: "Handle".to_string(),
+                fields: Vec::new(),
+                size: 0,
+                alignment: 1,
+                is_opaque: true,
+            },
+            BaseTypeKind::Union {
+                name: "Value".to_string(),
+                variants: vec![variant("i", 1, None), variant("d", 2, None)],
+                size: 8,
+                alignment: 8,
+                discriminant: None,
+            },
+            BaseTypeKind::Union {
+                name: "Shape".to_string(),
+                variants: vec![
+                    variant("Square", 3, Some(1)),
+                    variant("Circle", 4, Some(-1)),
+                ],
+                size: 16,
+                alignment: 8,
+                discriminant: Some(Discriminant {
+                    name: "tag".to_string(),
+                    type_id: TypeId(5),
+                    offset: 0,
+                }),
+            },
+            BaseTypeKind::Enum {
+                name: "Color".to_string(),
+                backing_id: TypeId(1),
+                variants: vec![enum_variant("RED", 0), enum_variant("BLUE", -2)],
+                size: 4,
+            },
+            BaseTypeKind::Array {
+                element_type_id: TypeId(1),
+                count: 16,
+                size: 64,
+                stride: 4,
+                is_dynamic: false,
+            },
+            BaseTypeKind::Typedef {
+                name: "packet_t".to_string(),
+                aliased_type_id: TypeId(u64::MAX),
+            },
+            BaseTypeKind::Function {
+                return_type_id: None,
+                parameter_type_ids: vec![TypeId(1), TypeId(2)],
+                is_variadic: true,
+            },
+            BaseTypeKind::Function {
+                return_type_id: Some(TypeId(1)),
+                parameter_type_ids: Vec::new(),
+                is_variadic: false,
+            },
+        ];
+        let ids: Vec<u64> = kinds
+            .iter()
+            .map(|kind| compute_type_id(kind, 0, false, false, &[]).0)
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                0x248c79544aca2aea,
+                0x66893697106e1839,
+                0x0439c78762e451e5,
+                0xc3984fe6c5d99997,
+                0xaa755301382b9160,
+                0xa13d92801a43352f,
+                0xef58023a18da08f9,
+                0x120226f269b7efbb,
+                0x7033c62863d22336,
+                0x9f6b00f23859a4ab,
+                0xfd4e9b52f391ce8f,
+            ]
+        );
+
+        // `const volatile Packet* const*`
+        let const_level = Qualifiers {
+            is_const: true,
             is_volatile: false,
-            dwarf_offset: None,
         };
+        assert_eq!(
+            compute_type_id(&kinds[2], 2, true, true, &[const_level]).0,
+            0x1a117573bc744f78
+        );
+    }
+
+    #[test]
+    fn test_const_struct_pointer_renderings() {
+        let mut registry = TypeRegistry::new();
+        let point_ptr = |is_const, pointer_qualifiers| Type {
+            is_const,
+            pointer_qualifiers,
+            ..Type::pointer_to(Type::new(BaseTypeKind::Struct {
+                name: "Point".to_string(),
+                fields: Vec::new(),
+                size: 8,
+                alignment: 4,
+                is_opaque: true,
+            }))
+        };
+        let const_level = vec![Qualifiers {
+            is_const: true,
+            is_volatile: false,
+        }];
+
+        let to_const = registry.register_type(point_ptr(true, Vec::new()));
+        let const_ptr = registry.register_type(point_ptr(false, const_level.clone()));
+        let both = registry.register_type(point_ptr(true, const_level));
+
+        let spellings = [
+            (to_const, "const struct Point*"),
+            (const_ptr, "struct Point* const"),
+            (both, "const struct Point* const"),
+        ];
+        for (id, spelling) in spellings {
+            let type_ = registry.get_type(id).unwrap();
+            assert_eq!(type_.to_c_string(&registry), spelling);
+            // each spelling parses back to its own type and no other
+            for (other, _) in spellings {
+                let other = registry.get_type(other).unwrap();
+                assert_eq!(
+                    other.matches_c_type(&registry, spelling),
+                    other.id == id,
+                    "{}",
+                    spelling
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_typedef() {
+        let mut registry = TypeRegistry::new();
+
+        let int_type = Type::primitive("int", 4);
+        let int_id = registry.register_type(int_type);
+
+        let size_t_typedef = Type::new(BaseTypeKind::Typedef {
+            name: "size_t".to_string(),
+            aliased_type_id: int_id,
+        });
 
         let typedef_id = registry.register_type(size_t_typedef);
         let retrieved = registry.get_type(typedef_id).unwrap();
@@ -913,78 +3297,39 @@ mod tests {
         let mut registry2 = TypeRegistry::new();
 
         let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
             dwarf_offset: Some(0x100),
+            ..Type::primitive("int", 4)
         };
         registry1.register_type(int_type);
 
         let float_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "float".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
             dwarf_offset: Some(0x200),
+            ..Type::primitive("float", 4)
         };
         registry2.register_type(float_type);
 
         registry1.merge(registry2);
 
         assert_eq!(registry1.len(), 2);
-        assert!(registry1.get_by_name("int").len() == 1);
-        assert!(registry1.get_by_name("float").len() == 1);
-    }
-
-    #[test]
-    fn test_merge_with_references() {
-        let mut registry1 = TypeRegistry::new();
-        let mut registry2 = TypeRegistry::new();
-
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
-        let int_id = registry2.register_type(int_type);
-
-        let point_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Struct {
-                name: "Point".to_string(),
-                fields: vec![StructField {
-                    name: "x".to_string(),
-                    type_id: int_id,
-                    offset: 0,
-                    size: 4,
-                }],
-                size: 4,
-                alignment: 4,
-                is_opaque: false,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        assert!(registry1.get_by_name("int").len() == 1);
+        assert!(registry1.get_by_name("float").len() == 1);
+    }
+
+    #[test]
+    fn test_merge_with_references() {
+        let mut registry1 = TypeRegistry::new();
+        let mut registry2 = TypeRegistry::new();
+
+        let int_type = Type::primitive("int", 4);
+        let int_id = registry2.register_type(int_type);
+
+        let point_type = Type::new(BaseTypeKind::Struct {
+            name: "Point".to_string(),
+            fields: vec![StructField::new("x", int_id, 0, 4)],
+            size: 4,
+            alignment: 4,
+            is_opaque: false,
+        });
         registry2.register_type(point_type);
 
         // Merge registry2 into registry1
@@ -1018,31 +3363,9 @@ mod tests {
     fn test_all_types_iterator() {
         let mut registry = TypeRegistry::new();
 
-        registry.register_type(Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        });
+        registry.register_type(Type::primitive("int", 4));
 
-        registry.register_type(Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "float".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        });
+        registry.register_type(Type::primitive("float", 4));
 
         let count = registry.all_types().count();
         assert_eq!(count, 2);
@@ -1053,16 +3376,8 @@ mod tests {
         let mut registry = TypeRegistry::new();
 
         let int_type1 = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
             dwarf_offset: Some(0x100),
+            ..Type::primitive("int", 4)
         };
 
         let int_type2 = Type {
@@ -1071,10 +3386,12 @@ mod tests {
                 name: "int".to_string(),
                 size: 4,
                 alignment: 4,
+                encoding: None,
             },
             pointer_depth: 0,
             is_const: false,
             is_volatile: false,
+            pointer_qualifiers: Vec::new(),
             dwarf_offset: Some(0x200), // different DWARF offset
         };
 
@@ -1086,49 +3403,53 @@ mod tests {
     }
 
     #[test]
-    fn test_deduplication_same_struct_twice() {
+    fn test_type_equality_ignores_dwarf_offset() {
         let mut registry = TypeRegistry::new();
+        let int_id = registry.register_type(Type {
+            dwarf_offset: Some(0x100),
+            ..Type::primitive("int", 4)
+        });
 
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
+        let first = registry.get_type(int_id).unwrap().clone();
+        let second = Type {
+            pointer_qualifiers: Vec::new(),
+            dwarf_offset: Some(0x200),
+            ..first.clone()
+        };
+        assert_eq!(first, second);
+
+        let set: HashSet<Type> = [first.clone(), second].into_iter().collect();
+        assert_eq!(set.len(), 1);
+
+        let const_int = Type {
+            is_const: true,
+            ..first.clone()
         };
+        assert_ne!(first, const_int);
+    }
+
+    #[test]
+    fn test_deduplication_same_struct_twice() {
+        let mut registry = TypeRegistry::new();
+
+        let int_type = Type::primitive("int", 4);
         let int_id = registry.register_type(int_type);
 
         let point1 = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Struct {
+            dwarf_offset: Some(0x1000),
+            ..Type::new(BaseTypeKind::Struct {
                 name: "Point".to_string(),
                 fields: vec![
+                    StructField::new("x", int_id, 0, 4),
                     StructField {
-                        name: "x".to_string(),
-                        type_id: int_id,
-                        offset: 0,
-                        size: 4,
-                    },
-                    StructField {
-                        name: "y".to_string(),
-                        type_id: int_id,
-                        offset: 4,
-                        size: 4,
+                        declaration_index: 1,
+                        ..StructField::new("y", int_id, 4, 4)
                     },
                 ],
                 size: 8,
                 alignment: 4,
                 is_opaque: false,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: Some(0x1000),
+            })
         };
 
         let point2 = Type {
@@ -1136,17 +3457,10 @@ mod tests {
             kind: BaseTypeKind::Struct {
                 name: "Point".to_string(),
                 fields: vec![
+                    StructField::new("x", int_id, 0, 4),
                     StructField {
-                        name: "x".to_string(),
-                        type_id: int_id,
-                        offset: 0,
-                        size: 4,
-                    },
-                    StructField {
-                        name: "y".to_string(),
-                        type_id: int_id,
-                        offset: 4,
-                        size: 4,
+                        declaration_index: 1,
+                        ..StructField::new("y", int_id, 4, 4)
                     },
                 ],
                 size: 8,
@@ -1156,6 +3470,7 @@ mod tests {
             pointer_depth: 0,
             is_const: false,
             is_volatile: false,
+            pointer_qualifiers: Vec::new(),
             dwarf_offset: Some(0x2000), // different offset
         };
 
@@ -1170,23 +3485,12 @@ mod tests {
     fn test_deduplication_same_enum_twice() {
         let mut registry = TypeRegistry::new();
 
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let int_type = Type::primitive("int", 4);
         let int_id = registry.register_type(int_type);
 
         let enum1 = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Enum {
+            dwarf_offset: Some(0x1000),
+            ..Type::new(BaseTypeKind::Enum {
                 name: "Status".to_string(),
                 backing_id: int_id,
                 variants: vec![
@@ -1200,16 +3504,12 @@ mod tests {
                     },
                 ],
                 size: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: Some(0x1000),
+            })
         };
 
         let enum2 = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Enum {
+            dwarf_offset: Some(0x2000),
+            ..Type::new(BaseTypeKind::Enum {
                 name: "Status".to_string(),
                 backing_id: int_id,
                 variants: vec![
@@ -1223,11 +3523,7 @@ mod tests {
                     },
                 ],
                 size: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: Some(0x2000),
+            })
         };
 
         let id1 = registry.register_type(enum1);
@@ -1241,31 +3537,9 @@ mod tests {
     fn test_no_deduplication_different_types() {
         let mut registry = TypeRegistry::new();
 
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let int_type = Type::primitive("int", 4);
 
-        let float_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "float".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let float_type = Type::primitive("float", 4);
 
         let int_id = registry.register_type(int_type);
         let float_id = registry.register_type(float_type);
@@ -1278,67 +3552,42 @@ mod tests {
     fn test_enum_variant_order_independence() {
         let mut registry = TypeRegistry::new();
 
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let int_type = Type::primitive("int", 4);
         let int_id = registry.register_type(int_type);
 
         // order of enum variants: [OK, ERROR]
-        let enum1 = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Enum {
-                name: "Status".to_string(),
-                backing_id: int_id,
-                variants: vec![
-                    EnumVariant {
-                        name: "OK".to_string(),
-                        value: 0,
-                    },
-                    EnumVariant {
-                        name: "ERROR".to_string(),
-                        value: 1,
-                    },
-                ],
-                size: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let enum1 = Type::new(BaseTypeKind::Enum {
+            name: "Status".to_string(),
+            backing_id: int_id,
+            variants: vec![
+                EnumVariant {
+                    name: "OK".to_string(),
+                    value: 0,
+                },
+                EnumVariant {
+                    name: "ERROR".to_string(),
+                    value: 1,
+                },
+            ],
+            size: 4,
+        });
 
         // order of enum variants: [ERROR, OK]
-        let enum2 = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Enum {
-                name: "Status".to_string(),
-                backing_id: int_id,
-                variants: vec![
-                    EnumVariant {
-                        name: "ERROR".to_string(),
-                        value: 1,
-                    },
-                    EnumVariant {
-                        name: "OK".to_string(),
-                        value: 0,
-                    },
-                ],
-                size: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let enum2 = Type::new(BaseTypeKind::Enum {
+            name: "Status".to_string(),
+            backing_id: int_id,
+            variants: vec![
+                EnumVariant {
+                    name: "ERROR".to_string(),
+                    value: 1,
+                },
+                EnumVariant {
+                    name: "OK".to_string(),
+                    value: 0,
+                },
+            ],
+            size: 4,
+        });
 
         let id1 = registry.register_type(enum1);
         let id2 = registry.register_type(enum2);
@@ -1352,81 +3601,51 @@ mod tests {
     fn test_union_variant_order_independence() {
         let mut registry = TypeRegistry::new();
 
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let int_type = Type::primitive("int", 4);
         let int_id = registry.register_type(int_type);
 
-        let float_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "float".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let float_type = Type::primitive("float", 4);
         let float_id = registry.register_type(float_type);
 
         // variants in order: [as_int, as_float]
-        let union1 = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Union {
-                name: "DataUnion".to_string(),
-                variants: vec![
-                    UnionField {
-                        name: "as_int".to_string(),
-                        type_id: int_id,
-                    },
-                    UnionField {
-                        name: "as_float".to_string(),
-                        type_id: float_id,
-                    },
-                ],
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let union1 = Type::new(BaseTypeKind::Union {
+            name: "DataUnion".to_string(),
+            variants: vec![
+                UnionField {
+                    name: "as_int".to_string(),
+                    type_id: int_id,
+                    discriminant_value: None,
+                },
+                UnionField {
+                    name: "as_float".to_string(),
+                    type_id: float_id,
+                    discriminant_value: None,
+                },
+            ],
+            size: 4,
+            alignment: 4,
+            discriminant: None,
+        });
 
         // variants in different order: [as_float, as_int]
-        let union2 = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Union {
-                name: "DataUnion".to_string(),
-                variants: vec![
-                    UnionField {
-                        name: "as_float".to_string(),
-                        type_id: float_id,
-                    },
-                    UnionField {
-                        name: "as_int".to_string(),
-                        type_id: int_id,
-                    },
-                ],
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let union2 = Type::new(BaseTypeKind::Union {
+            name: "DataUnion".to_string(),
+            variants: vec![
+                UnionField {
+                    name: "as_float".to_string(),
+                    type_id: float_id,
+                    discriminant_value: None,
+                },
+                UnionField {
+                    name: "as_int".to_string(),
+                    type_id: int_id,
+                    discriminant_value: None,
+                },
+            ],
+            size: 4,
+            alignment: 4,
+            discriminant: None,
+        });
 
         let id1 = registry.register_type(union1);
         let id2 = registry.register_type(union2);
@@ -1438,80 +3657,42 @@ mod tests {
     }
 
     #[test]
-    fn test_struct_field_order_dependence() {
-        let mut registry = TypeRegistry::new();
-
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+    fn test_struct_field_order_dependence() {
+        let mut registry = TypeRegistry::new();
+
+        let int_type = Type::primitive("int", 4);
         let int_id = registry.register_type(int_type);
 
         // struct with fields [x, y]
-        let struct1 = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Struct {
-                name: "Point".to_string(),
-                fields: vec![
-                    StructField {
-                        name: "x".to_string(),
-                        type_id: int_id,
-                        offset: 0,
-                        size: 4,
-                    },
-                    StructField {
-                        name: "y".to_string(),
-                        type_id: int_id,
-                        offset: 4,
-                        size: 4,
-                    },
-                ],
-                size: 8,
-                alignment: 4,
-                is_opaque: false,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let struct1 = Type::new(BaseTypeKind::Struct {
+            name: "Point".to_string(),
+            fields: vec![
+                StructField::new("x", int_id, 0, 4),
+                StructField {
+                    declaration_index: 1,
+                    ..StructField::new("y", int_id, 4, 4)
+                },
+            ],
+            size: 8,
+            alignment: 4,
+            is_opaque: false,
+        });
 
         // struct with fields in DIFFERENT order: [y, x]
-        let struct2 = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Struct {
-                name: "Point".to_string(),
-                fields: vec![
-                    StructField {
-                        name: "y".to_string(),
-                        type_id: int_id,
-                        offset: 0, // Different offset!
-                        size: 4,
-                    },
-                    StructField {
-                        name: "x".to_string(),
-                        type_id: int_id,
-                        offset: 4,
-                        size: 4,
-                    },
-                ],
-                size: 8,
-                alignment: 4,
-                is_opaque: false,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let struct2 = Type::new(BaseTypeKind::Struct {
+            name: "Point".to_string(),
+            fields: vec![
+                // Different offset!
+                StructField::new("y", int_id, 0, 4),
+                StructField {
+                    declaration_index: 1,
+                    ..StructField::new("x", int_id, 4, 4)
+                },
+            ],
+            size: 8,
+            alignment: 4,
+            is_opaque: false,
+        });
 
         let id1 = registry.register_type(struct1);
         let id2 = registry.register_type(struct2);
@@ -1526,32 +3707,10 @@ mod tests {
     fn test_function_param_order_dependence() {
         let mut registry = TypeRegistry::new();
 
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let int_type = Type::primitive("int", 4);
         let int_id = registry.register_type(int_type);
 
-        let float_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "float".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let float_type = Type::primitive("float", 4);
         let float_id = registry.register_type(float_type);
 
         // function(int, float)
@@ -1565,22 +3724,16 @@ mod tests {
             pointer_depth: 1, // Function pointer
             is_const: false,
             is_volatile: false,
+            pointer_qualifiers: Vec::new(),
             dwarf_offset: None,
         };
 
         // function(float, int)
-        let func2 = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Function {
-                return_type_id: None,
-                parameter_type_ids: vec![float_id, int_id],
-                is_variadic: false,
-            },
-            pointer_depth: 1,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let func2 = Type::pointer_to(Type::new(BaseTypeKind::Function {
+            return_type_id: None,
+            parameter_type_ids: vec![float_id, int_id],
+            is_variadic: false,
+        }));
 
         let id1 = registry.register_type(func1);
         let id2 = registry.register_type(func2);
@@ -1597,29 +3750,13 @@ mod tests {
         let mut registry2 = TypeRegistry::new();
 
         let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
             dwarf_offset: Some(0x100),
+            ..Type::primitive("int", 4)
         };
 
         let float_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "float".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
             dwarf_offset: Some(0x200),
+            ..Type::primitive("float", 4)
         };
 
         // both registries have the same types
@@ -1645,44 +3782,11 @@ mod tests {
         let mut registry1 = TypeRegistry::new();
         let mut registry2 = TypeRegistry::new();
 
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let int_type = Type::primitive("int", 4);
 
-        let float_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "float".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let float_type = Type::primitive("float", 4);
 
-        let double_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "double".to_string(),
-                size: 8,
-                alignment: 8,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let double_type = Type::primitive("double", 8);
 
         // registry1 has int, float
         registry1.register_type(int_type.clone());
@@ -1710,40 +3814,17 @@ mod tests {
         let mut registry2 = TypeRegistry::new();
 
         // register int in registry2
-        let int_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let int_type = Type::primitive("int", 4);
         let int_id_reg2 = registry2.register_type(int_type.clone());
 
         // register struct in registry2 that references int
-        let point_type = Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Struct {
-                name: "Point".to_string(),
-                fields: vec![StructField {
-                    name: "x".to_string(),
-                    type_id: int_id_reg2,
-                    offset: 0,
-                    size: 4,
-                }],
-                size: 4,
-                alignment: 4,
-                is_opaque: false,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        };
+        let point_type = Type::new(BaseTypeKind::Struct {
+            name: "Point".to_string(),
+            fields: vec![StructField::new("x", int_id_reg2, 0, 4)],
+            size: 4,
+            alignment: 4,
+            is_opaque: false,
+        });
         registry2.register_type(point_type);
 
         // register int in registry1 independently
@@ -1773,4 +3854,460 @@ mod tests {
             _ => panic!("Expected struct"),
         }
     }
+
+    #[test]
+    fn test_qualifiers_through_typedefs() {
+        let mut registry = TypeRegistry::new();
+        let make = |kind, pointer_depth, is_const, pointer_qualifiers| Type {
+            pointer_depth,
+            is_const,
+            pointer_qualifiers,
+            ..Type::new(kind)
+        };
+        let int = || BaseTypeKind::Primitive {
+            name: "int".to_string(),
+            size: 4,
+            alignment: 4,
+            encoding: None,
+        };
+        let typedef = |name: &str, aliased_type_id| BaseTypeKind::Typedef {
+            name: name.to_string(),
+            aliased_type_id,
+        };
+        let const_level = vec![Qualifiers {
+            is_const: true,
+            is_volatile: false,
+        }];
+
+        // typedef const char* CString; referred to as `const CString`
+        let char_ptr = registry.register_type(make(
+            BaseTypeKind::Primitive {
+                name: "char".to_string(),
+                size: 1,
+                alignment: 1,
+                encoding: None,
+            },
+            1,
+            true,
+            Vec::new(),
+        ));
+        let cstring =
+            registry.register_type(make(typedef("CString", char_ptr), 0, false, Vec::new()));
+        let const_cstring =
+            registry.register_type(make(typedef("CString", char_ptr), 0, true, Vec::new()));
+
+        // typedef const int CInt; referred to as `const CInt` and `const CInt*`
+        let const_int = registry.register_type(make(int(), 0, true, Vec::new()));
+        let cint = registry.register_type(make(typedef("CInt", const_int), 0, false, Vec::new()));
+        let const_cint =
+            registry.register_type(make(typedef("CInt", const_int), 0, true, Vec::new()));
+        let const_cint_ptr =
+            registry.register_type(make(typedef("CInt", const_int), 1, true, Vec::new()));
+
+        // typedef const int CArray[2]; referred to as `const CArray`
+        let array = registry.register_type(make(
+            BaseTypeKind::Array {
+                element_type_id: const_int,
+                count: 2,
+                size: 8,
+                stride: 4,
+                is_dynamic: false,
+            },
+            0,
+            true,
+            Vec::new(),
+        ));
+
+        // typedef char* const FixedString; a pointer to it
+        let char_const_ptr = registry.register_type(make(
+            BaseTypeKind::Primitive {
+                name: "char".to_string(),
+                size: 1,
+                alignment: 1,
+                encoding: None,
+            },
+            1,
+            false,
+            const_level.clone(),
+        ));
+        let fixed = registry.register_type(make(
+            typedef("FixedString", char_const_ptr),
+            1,
+            false,
+            const_level,
+        ));
+
+        let effective = |id| registry.effective_qualifiers(id).is_const;
+        assert!(!effective(cstring));
+        assert!(effective(const_cstring));
+        assert!(effective(cint));
+        assert!(effective(const_cint));
+        assert!(!effective(const_cint_ptr));
+        assert!(effective(fixed));
+
+        let render = |id| registry.get_type(id).unwrap().to_c_string(&registry);
+        assert_eq!(render(const_cstring), "const CString");
+        assert_eq!(render(const_cint), "CInt");
+        assert_eq!(render(const_cint_ptr), "CInt*");
+        assert_eq!(render(array), "const int[2]");
+        assert_eq!(render(fixed), "FixedString* const");
+
+        // the qualifier CInt applies may be spelled or not
+        let const_cint = registry.get_type(const_cint).unwrap();
+        assert!(const_cint.matches_c_type(&registry, "const CInt"));
+        assert!(const_cint.matches_c_type(&registry, "CInt"));
+        assert!(
+            !registry
+                .get_type(cstring)
+                .unwrap()
+                .matches_c_type(&registry, "const CString")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rust-types")]
+    fn test_rust_types() {
+        let mut registry = TypeRegistry::new();
+        let make = |kind, pointer_depth, is_const, pointer_qualifiers| Type {
+            pointer_depth,
+            is_const,
+            pointer_qualifiers,
+            ..Type::new(kind)
+        };
+        let primitive = |name: &str, size| BaseTypeKind::Primitive {
+            name: name.to_string(),
+            size,
+            alignment: size,
+            encoding: None,
+        };
+        let mut rust_type = |type_| {
+            let id = registry.register_type(type_);
+            registry.get_type(id).unwrap().to_rust_type(&registry)
+        };
+
+        for (name, size, expected) in [
+            ("int", 4, "core::ffi::c_int"),
+            ("long unsigned int", 8, "core::ffi::c_ulong"),
+            ("char", 1, "core::ffi::c_char"),
+            ("unsigned char", 1, "core::ffi::c_uchar"),
+            ("double", 8, "core::ffi::c_double"),
+            ("_Bool", 1, "bool"),
+            ("long double", 16, "[u8; 16]"),
+            ("void", 0, "()"),
+        ] {
+            assert_eq!(
+                rust_type(make(primitive(name, size), 0, false, Vec::new())),
+                expected
+            );
+        }
+
+        // `const char*`, `char* const` and `const char* const*`
+        let const_level = Qualifiers {
+            is_const: true,
+            is_volatile: false,
+        };
+        assert_eq!(
+            rust_type(make(primitive("char", 1), 1, true, Vec::new())),
+            "*const core::ffi::c_char"
+        );
+        assert_eq!(
+            rust_type(make(primitive("char", 1), 1, false, vec![const_level])),
+            "*mut core::ffi::c_char"
+        );
+        assert_eq!(
+            rust_type(make(
+                primitive("char", 1),
+                2,
+                true,
+                vec![Qualifiers::default(), const_level]
+            )),
+            "*const *const core::ffi::c_char"
+        );
+        assert_eq!(
+            rust_type(make(primitive("void", 0), 1, false, Vec::new())),
+            "*mut core::ffi::c_void"
+        );
+
+        // a struct by value and through a const pointer
+        let point = |pointer_depth, is_const| {
+            make(
+                BaseTypeKind::Struct {
+                    name: "Point".to_string(),
+                    fields: Vec::new(),
+                    size: 8,
+                    alignment: 4,
+                    is_opaque: true,
+                },
+                pointer_depth,
+                is_const,
+                Vec::new(),
+            )
+        };
+        assert_eq!(rust_type(point(0, false)), "Point");
+        assert_eq!(rust_type(point(1, true)), "*const Point");
+    }
+
+    #[test]
+    fn test_reachable_types() {
+        let mut registry = TypeRegistry::new();
+
+        let int_id = registry.register_type(Type::primitive("int", 4));
+        let float_id = registry.register_type(Type::primitive("float", 4));
+        let typedef_id = registry.register_type(Type::new(BaseTypeKind::Typedef {
+            name: "myint".to_string(),
+            aliased_type_id: int_id,
+        }));
+        let node_id = registry.register_type(Type::new(BaseTypeKind::Struct {
+            name: "Node".to_string(),
+            fields: vec![StructField::new("value", typedef_id, 0, 4)],
+            size: 4,
+            alignment: 4,
+            is_opaque: false,
+        }));
+
+        let reachable = registry.reachable_types(&[node_id]);
+        assert_eq!(reachable.len(), 3);
+        assert!(reachable.contains(&node_id));
+        assert!(reachable.contains(&typedef_id));
+        assert!(reachable.contains(&int_id));
+        assert!(!reachable.contains(&float_id));
+
+        // a cycle back to the root must terminate
+        if let Some(BaseTypeKind::Typedef {
+            aliased_type_id, ..
+        }) = registry.get_type_mut(typedef_id).map(|t| &mut t.kind)
+        {
+            *aliased_type_id = node_id;
+        }
+        let reachable = registry.reachable_types(&[node_id]);
+        assert_eq!(reachable.len(), 2);
+    }
+
+    #[test]
+    fn test_retain_reachable_drops_unused_types() {
+        let mut registry = TypeRegistry::new();
+        let make = |kind, dwarf_offset| Type {
+            dwarf_offset: Some(dwarf_offset),
+            ..Type::new(kind)
+        };
+        let primitive = |name: &str| BaseTypeKind::Primitive {
+            name: name.to_string(),
+            size: 4,
+            alignment: 4,
+            encoding: None,
+        };
+
+        let int_id = registry.register_type(make(primitive("int"), 0x10));
+        let float_id = registry.register_type(make(primitive("float"), 0x20));
+        let myint_id = registry.register_type(make(
+            BaseTypeKind::Typedef {
+                name: "myint".to_string(),
+                aliased_type_id: int_id,
+            },
+            0x30,
+        ));
+        registry.register_type(make(
+            BaseTypeKind::Typedef {
+                name: "myfloat".to_string(),
+                aliased_type_id: float_id,
+            },
+            0x40,
+        ));
+
+        registry.retain_reachable(&[myint_id]);
+        assert_eq!(registry.len(), 2);
+        assert!(registry.get_type(int_id).is_some());
+        assert!(registry.get_type(float_id).is_none());
+        assert!(registry.get_by_name("myfloat").is_empty());
+        assert!(registry.get_by_unit_offset(UnitId::default(), 0x20).is_none());
+        assert_eq!(
+            registry
+                .get_by_unit_offset(UnitId::default(), 0x30)
+                .unwrap()
+                .id,
+            myint_id
+        );
+    }
+
+    #[test]
+    fn test_display_name_prefers_typedef_of_anonymous_aggregate() {
+        let mut registry = TypeRegistry::new();
+
+        let make = |kind, pointer_depth| Type {
+            pointer_depth,
+            pointer_qualifiers: vec![Qualifiers::default(); pointer_depth],
+            ..Type::new(kind)
+        };
+        let aggregate = |name: &str| BaseTypeKind::Struct {
+            name: name.to_string(),
+            fields: Vec::new(),
+            size: 4,
+            alignment: 4,
+            is_opaque: false,
+        };
+
+        let anonymous_id = registry.register_type(make(aggregate("<anonymous>"), 0));
+        let anonymous_ptr_id = registry.register_type(make(aggregate("<anonymous>"), 1));
+        let typedef_id = registry.register_type(make(
+            BaseTypeKind::Typedef {
+                name: "BoundingBox".to_string(),
+                aliased_type_id: anonymous_id,
+            },
+            0,
+        ));
+        let named_id = registry.register_type(make(aggregate("Point"), 0));
+
+        assert_eq!(registry.display_name(anonymous_id).as_deref(), Some("BoundingBox"));
+        assert_eq!(registry.display_name(anonymous_ptr_id).as_deref(), Some("BoundingBox"));
+        assert_eq!(registry.display_name(typedef_id).as_deref(), Some("BoundingBox"));
+        assert_eq!(registry.display_name(named_id).as_deref(), Some("Point"));
+
+        // survives a merge into a registry that never saw the typedef
+        let mut merged = TypeRegistry::new();
+        merged.merge(registry);
+        assert_eq!(merged.display_name(anonymous_id).as_deref(), Some("BoundingBox"));
+    }
+
+    #[test]
+    fn test_anonymous_enum_constants() {
+        let enum_of = |name: &str, variants: &[(&str, i64)]| BaseTypeKind::Enum {
+            name: name.to_string(),
+            backing_id: TypeId(1),
+            variants: variants
+                .iter()
+                .map(|(name, value)| EnumVariant {
+                    name: name.to_string(),
+                    value: *value,
+                })
+                .collect(),
+            size: 4,
+        };
+
+        let mut registry = TypeRegistry::new();
+        registry.register_type(Type::new(enum_of("<anonymous>", &[("LIMIT", 64), ("DEPTH", -1)])));
+        // `typedef enum { ... } Status` has a name to put its constants under
+        let status = registry.register_type(Type::new(enum_of("<anonymous>", &[("STATUS_OK", 0)])));
+        registry.register_type(Type::new(BaseTypeKind::Typedef {
+            name: "Status".to_string(),
+            aliased_type_id: status,
+        }));
+        registry.register_type(Type::new(enum_of("Color", &[("RED", 0)])));
+
+        assert_eq!(
+            registry.anonymous_enum_constants(),
+            [("LIMIT".to_string(), 64), ("DEPTH".to_string(), -1)]
+        );
+    }
+
+    #[test]
+    fn test_enum_value_string_reads_unsigned_bits() {
+        let variant = EnumVariant {
+            name: "ALL".to_string(),
+            value: u64::MAX as i64,
+        };
+        assert_eq!(variant.value_string(false), "-1");
+        assert_eq!(variant.value_string(true), "18446744073709551615");
+    }
+
+    #[test]
+    fn test_synthetic_names_are_stable() {
+        let id_of = |kind: &BaseTypeKind| compute_type_id(kind, 0, false, false, &[]);
+        let int = BaseTypeKind::Primitive {
+            name: "int".to_string(),
+            size: 4,
+            alignment: 4,
+            encoding: None,
+        };
+        let union_of = |names: &[&str]| BaseTypeKind::Union {
+            name: "<anonymous>".to_string(),
+            variants: names
+                .iter()
+                .map(|name| UnionField {
+                    name: name.to_string(),
+                    type_id: id_of(&int),
+                    discriminant_value: None,
+                })
+                .collect(),
+            size: 4,
+            alignment: 4,
+            discriminant: None,
+        };
+        let struct_of = |name: &str, members: &[(&str, TypeId)]| BaseTypeKind::Struct {
+            name: name.to_string(),
+            fields: members
+                .iter()
+                .enumerate()
+                .map(|(i, (name, type_id))| StructField {
+                    declaration_index: i,
+                    ..StructField::new(name, *type_id, i * 4, 4)
+                })
+                .collect(),
+            size: members.len() * 4,
+            alignment: 4,
+            is_opaque: false,
+        };
+
+        // struct Person { union { int i, f; }; struct { int a; } inner;
+        //                 union { int c; } extra; };  enum { A };
+        let numbers = union_of(&["i", "f"]);
+        let bytes = union_of(&["c"]);
+        let inner = struct_of("<anonymous>", &[("a", id_of(&int))]);
+        let person = struct_of(
+            "Person",
+            &[("", id_of(&numbers)), ("inner", id_of(&inner)), ("extra", id_of(&bytes))],
+        );
+        let standalone = BaseTypeKind::Enum {
+            name: "<anonymous>".to_string(),
+            backing_id: id_of(&int),
+            variants: vec![EnumVariant {
+                name: "A".to_string(),
+                value: 0,
+            }],
+            size: 4,
+        };
+        let kinds = [int, numbers, bytes, inner, person, standalone];
+
+        // registration order, like hash order, must not matter
+        let names = |reversed: bool| {
+            let mut registry = TypeRegistry::new();
+            let mut ids: Vec<TypeId> = kinds.iter().map(id_of).collect();
+            let mut order: Vec<&BaseTypeKind> = kinds.iter().collect();
+            if reversed {
+                order.reverse();
+            }
+            for kind in order {
+                registry.register_type(Type::new(kind.clone()));
+            }
+            ids.retain(|id| registry.get_type(*id).unwrap().is_anonymous_aggregate());
+            let names: Vec<String> = ids
+                .iter()
+                .map(|id| registry.synthetic_name(*id).unwrap())
+                .collect();
+            (registry, names)
+        };
+
+        let (mut registry, first) = names(false);
+        assert_eq!(first, names(true).1);
+        assert_eq!(
+            first[..3],
+            ["Person_anon_union_0", "Person_anon_union_1", "Person_anon_struct_0"]
+        );
+        assert!(first[3].starts_with("anon_enum_"));
+
+        // named types have none, and display_name uses them only on request
+        let person = registry.get_by_name("Person")[0].id;
+        assert_eq!(registry.synthetic_name(person), None);
+        let numbers = id_of(&kinds[1]);
+        assert_eq!(registry.display_name(numbers).as_deref(), Some("<anonymous>"));
+        registry.set_demunge_anonymous(true);
+        assert_eq!(registry.display_name(numbers).as_deref(), Some("Person_anon_union_0"));
+
+        // names made before a typedef names the union are not kept
+        registry.register_type(Type::new(BaseTypeKind::Typedef {
+            name: "Numbers".to_string(),
+            aliased_type_id: numbers,
+        }));
+        assert_eq!(registry.synthetic_name(numbers), None);
+        assert_eq!(registry.display_name(numbers).as_deref(), Some("Numbers"));
+    }
 }
@@ -1,28 +1,170 @@
-use crate::type_registry::{BaseTypeKind, Type, TypeId, TypeRegistry};
-use anyhow::{Result, anyhow};
-use gimli::{AttributeValue, DebuggingInformationEntry, Dwarf, ReaderOffset, Unit, UnitOffset};
-
-/// resolve DWARF type information into structured type registry
+use crate::error::ReadResult as Result;
+use crate::lines;
+use crate::prelude::*;
+use crate::target::Endianness;
+use crate::type_registry::{
+    BaseTypeKind, Discriminant, POINTER_TO_MEMBER, PrimitiveEncoding, Qualifiers, Type, TypeId,
+    TypeRegistry, UnitId,
+};
+use alloc::rc::Rc;
+use anyhow::anyhow;
+use gimli::{
+    AttributeValue, DebugInfoOffset, DebuggingInformationEntry, Dwarf, ReaderOffset, Unit,
+    UnitHeader, UnitOffset,
+};
+use hashbrown::{HashMap, HashSet};
+
+/// resolves the type DIEs of one compilation unit into a [`TypeRegistry`].
+///
+/// `R` is any gimli reader: [`DwarfReader`](crate::DwarfReader) as used by
+/// [`DwarfAnalyzer`](crate::DwarfAnalyzer), or e.g. a
+/// `gimli::EndianSlice` over sections from a custom loader. types are
+/// content-addressed, so registries from several units can be combined with
+/// [`TypeRegistry::merge`].
 pub struct TypeResolver<'dwarf, R: gimli::Reader> {
     dwarf: &'dwarf Dwarf<R>,
     unit: &'dwarf Unit<R>,
     type_registry: TypeRegistry,
+    /// the structs and unions whose members are being extracted, by unit and
+    /// offset, since resolution can cross into other units
+    in_progress: HashSet<(UnitId, u64)>,
+    /// the types met while those refer back to one of them
+    cycles: OpenCycles,
+    /// nesting of `resolve_type` calls
+    depth: usize,
+    /// aggregate declarations and the definitions that complete them through
+    /// `DW_AT_specification`, read on first use
+    definitions: Option<HashMap<u64, UnitOffset<R::Offset>>>,
+    /// the units `DW_FORM_ref_addr` references land in, read on first use
+    /// and shared with the resolvers of those units
+    foreign_units: Option<ForeignUnits<R>>,
+    /// spell integer base types the same whichever compiler named them
+    normalize_primitive_names: bool,
+    /// time spent in `build_type_registry_entry`, and its cache hits
+    #[cfg(feature = "profiling")]
+    timings: crate::profiling::Timings,
+}
+
+const MAX_TYPE_DEPTH: usize = 128;
+
+/// types registered with ids of their own while a struct or union refers
+/// back to itself, until the outermost aggregate in progress is extracted
+/// and [`TypeRegistry::close_cycles`] gives them their real ones
+#[derive(Default)]
+struct OpenCycles {
+    /// the aggregate the type being registered stands in for, set where an
+    /// aggregate in progress is reached again
+    back_reference: Option<(UnitId, u64)>,
+    /// the stand-ins not yet given their aggregate's kind, with the
+    /// aggregate
+    placeholders: Vec<(TypeId, (UnitId, u64))>,
+    /// the stand-ins, and every type registered that refers to one of these
+    pending: HashSet<TypeId>,
+    /// the DIEs a pending type was registered for
+    offsets: Vec<(UnitId, u64)>,
 }
 
 impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
-    /// create new with empty registry with the given DWARF and unit
+    /// a resolver with an empty registry for one unit of `dwarf`
     pub fn new(dwarf: &'dwarf Dwarf<R>, unit: &'dwarf Unit<R>) -> Self {
+        let mut type_registry = TypeRegistry::new();
+        type_registry.set_unit(unit_id(unit.header.offset()));
         Self {
             dwarf,
             unit,
-            type_registry: TypeRegistry::new(),
+            type_registry,
+            in_progress: HashSet::new(),
+            cycles: OpenCycles::default(),
+            depth: 0,
+            definitions: None,
+            foreign_units: None,
+            normalize_primitive_names: false,
+            #[cfg(feature = "profiling")]
+            timings: Default::default(),
+        }
+    }
+
+    /// name integer base types by their usual C spelling and encoding rather
+    /// than as DWARF does, so gcc's `long unsigned int` and clang's
+    /// `unsigned long` are one type with one id
+    pub fn normalize_primitive_names(mut self, normalize: bool) -> Self {
+        self.normalize_primitive_names = normalize;
+        self
+    }
+
+    /// register the type at `offset` in the unit (a `DW_AT_type` value of a
+    /// function, parameter or member), along with every type it refers to.
+    /// resolving the same offset again returns the same id.
+    pub fn build_type_registry_entry(
+        &mut self,
+        offset: UnitOffset<R::Offset>,
+    ) -> crate::Result<TypeId> {
+        self.profiled(|resolver| Ok(resolver.resolve_type(offset)?))
+    }
+
+    /// register the type a `DW_AT_type` value refers to: a `UnitRef` into
+    /// this unit, or a `DebugInfoRef` (`DW_FORM_ref_addr`) into any unit of
+    /// the `.debug_info` section. None if the value isn't a reference.
+    pub fn build_type_registry_entry_for(
+        &mut self,
+        value: AttributeValue<R>,
+    ) -> crate::Result<Option<TypeId>> {
+        self.profiled(|resolver| Ok(resolver.resolve_reference(value)?))
+    }
+
+    /// time a resolution, counting it as a cache hit if it registered no
+    /// new type
+    #[cfg(feature = "profiling")]
+    fn profiled<T>(&mut self, resolve: impl FnOnce(&mut Self) -> T) -> T {
+        let types_before = self.type_registry.len();
+        let start = std::time::Instant::now();
+        let result = resolve(self);
+        self.timings.type_resolution += start.elapsed();
+        if self.type_registry.len() == types_before {
+            self.timings.type_cache_hits += 1;
+        } else {
+            self.timings.type_cache_misses += 1;
         }
+        result
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    fn profiled<T>(&mut self, resolve: impl FnOnce(&mut Self) -> T) -> T {
+        resolve(self)
+    }
+
+    /// time spent resolving types so far, and the cache hits and misses
+    #[cfg(feature = "profiling")]
+    pub fn timings(&self) -> &crate::profiling::Timings {
+        &self.timings
+    }
+
+    /// the id of `void`, for functions and pointers without a `DW_AT_type`
+    pub fn get_void_type_id(&mut self) -> crate::Result<TypeId> {
+        Ok(self.get_or_create_void_type()?)
+    }
+
+    /// the registry built so far
+    pub fn into_registry(self) -> TypeRegistry {
+        self.type_registry
+    }
+
+    /// the registry built so far, while resolution continues
+    pub fn get_registry(&self) -> &TypeRegistry {
+        &self.type_registry
+    }
+
+    fn unit_id(&self) -> UnitId {
+        unit_id(self.unit.header.offset())
     }
 
-    pub fn build_type_registry_entry(&mut self, offset: UnitOffset<R::Offset>) -> Result<TypeId> {
+    fn resolve_type(&mut self, offset: UnitOffset<R::Offset>) -> Result<TypeId> {
         let dwarf_offset = offset.0.into_u64();
 
-        if let Some(type_) = self.type_registry.get_by_dwarf_offset(dwarf_offset) {
+        if let Some(type_) = self
+            .type_registry
+            .get_by_unit_offset(self.unit_id(), dwarf_offset)
+        {
             log::trace!("type already registered at offset {:#010x}", dwarf_offset);
             return Ok(type_.id);
         }
@@ -34,48 +176,333 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
 
         log::trace!("extracting type at offset {:#010x}", dwarf_offset);
 
-        let (kind, pointer_depth, is_const, is_volatile) =
-            self.extract_type_metadata(entry, offset)?;
+        // C types only refer to themselves through a struct or union, which
+        // is handled below; deeper nesting than this is a cycle in malformed
+        // DWARF
+        if self.depth >= MAX_TYPE_DEPTH {
+            return Err(anyhow!(
+                "type at offset {:#010x} nests more than {} levels deep",
+                dwarf_offset,
+                MAX_TYPE_DEPTH
+            )
+            .into());
+        }
+        self.depth += 1;
+        let metadata = self.extract_type_metadata(entry, offset);
+        self.depth -= 1;
+        let back_reference = self.cycles.back_reference.take();
+        let (kind, mut levels) = metadata?;
+
+        // the last level qualifies the base type, the others the pointers
+        let base = levels.pop().unwrap_or_default();
+        let pointer_depth = levels.len();
+        if levels.iter().all(|q| *q == Qualifiers::default()) {
+            levels.clear();
+        }
 
         let extracted_type = Type {
             id: TypeId(0),
             kind,
             pointer_depth,
-            is_const,
-            is_volatile,
+            is_const: base.is_const,
+            is_volatile: base.is_volatile,
+            pointer_qualifiers: levels,
             dwarf_offset: Some(dwarf_offset),
         };
 
-        let id = self.type_registry.register_type(extracted_type);
+        let id = match back_reference {
+            Some(aggregate) => {
+                let id = self.type_registry.register_placeholder(extracted_type);
+                self.cycles.placeholders.push((id, aggregate));
+                self.cycles.pending.insert(id);
+                id
+            }
+            None => {
+                let is_pending = !self.cycles.pending.is_empty()
+                    && extracted_type
+                        .kind
+                        .referenced_type_ids()
+                        .iter()
+                        .any(|id| self.cycles.pending.contains(id));
+                let id = self.type_registry.register_type(extracted_type);
+                if is_pending {
+                    self.cycles.pending.insert(id);
+                }
+                id
+            }
+        };
+        if self.cycles.pending.contains(&id) {
+            self.cycles.offsets.push((self.unit_id(), dwarf_offset));
+        }
         Ok(id)
     }
 
-    pub fn get_void_type_id(&mut self) -> Result<TypeId> {
-        self.get_or_create_void_type()
+    /// where a reference attribute points, relative to this unit if it can be
+    fn type_ref(&self, value: AttributeValue<R>) -> Option<TypeRef<R::Offset>> {
+        match value {
+            AttributeValue::UnitRef(offset) => Some(TypeRef::Local(offset)),
+            AttributeValue::DebugInfoRef(offset) => {
+                Some(match offset.to_unit_offset(&self.unit.header) {
+                    Some(offset) => TypeRef::Local(offset),
+                    None => TypeRef::Foreign(offset),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn resolve_reference(&mut self, value: AttributeValue<R>) -> Result<Option<TypeId>> {
+        match self.type_ref(value) {
+            Some(TypeRef::Local(offset)) => Ok(Some(self.resolve_type(offset)?)),
+            Some(TypeRef::Foreign(offset)) => Ok(Some(self.resolve_foreign(offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// the type `entry`'s `DW_AT_type` refers to, if it has one
+    fn resolve_type_attr(
+        &mut self,
+        entry: &DebuggingInformationEntry<R>,
+    ) -> Result<Option<TypeId>> {
+        match entry.attr_value(gimli::DW_AT_type)? {
+            Some(value) => self.resolve_reference(value),
+            None => Ok(None),
+        }
+    }
+
+    /// resolve a type in another unit (a typedef here aliasing a struct
+    /// defined only there, say) with a resolver for that unit, merging what
+    /// it registers into this registry
+    fn resolve_foreign(&mut self, offset: DebugInfoOffset<R::Offset>) -> Result<TypeId> {
+        if self.foreign_units.is_none() {
+            self.foreign_units = Some(ForeignUnits::new(self.dwarf)?);
+        }
+        let foreign_units = self
+            .foreign_units
+            .as_mut()
+            .expect("foreign units just read");
+        let (id, unit_offset) = foreign_units
+            .find(offset)
+            .ok_or_else(|| anyhow!("no unit contains offset {:#010x}", offset.0.into_u64()))?;
+
+        if let Some(type_) = self
+            .type_registry
+            .get_by_unit_offset(id, unit_offset.0.into_u64())
+        {
+            return Ok(type_.id);
+        }
+
+        log::debug!(
+            "{:>12} {:#010x}: in unit at {:#x}",
+            "cross-unit",
+            offset.0.into_u64(),
+            id.offset
+        );
+        let unit = foreign_units.unit(self.dwarf, id)?;
+        let mut resolver = TypeResolver::new(self.dwarf, &unit);
+        // shared so cycles through several units still end, and still stop
+        // at a struct whose members are being extracted
+        resolver.depth = self.depth;
+        resolver.normalize_primitive_names = self.normalize_primitive_names;
+        resolver.in_progress = core::mem::take(&mut self.in_progress);
+        resolver.cycles = core::mem::take(&mut self.cycles);
+        resolver.foreign_units = self.foreign_units.take();
+        let result = resolver.resolve_type(unit_offset);
+        self.in_progress = core::mem::take(&mut resolver.in_progress);
+        self.cycles = core::mem::take(&mut resolver.cycles);
+        self.foreign_units = resolver.foreign_units.take();
+        self.type_registry.merge(resolver.into_registry());
+        result
+    }
+
+    /// the base type and qualifiers of a type chain that continues into
+    /// another unit: `levels` so far, then those of the foreign type
+    fn extract_foreign_metadata(
+        &mut self,
+        offset: DebugInfoOffset<R::Offset>,
+        levels: Vec<Qualifiers>,
+    ) -> Result<(BaseTypeKind, Vec<Qualifiers>)> {
+        let id = self.resolve_foreign(offset)?;
+        self.extend_with_registered(id, levels)
+    }
+
+    /// the base type and qualifiers of a type chain that reaches a type
+    /// already in the registry: `levels` so far, then those of the type
+    fn extend_with_registered(
+        &self,
+        id: TypeId,
+        mut levels: Vec<Qualifiers>,
+    ) -> Result<(BaseTypeKind, Vec<Qualifiers>)> {
+        let foreign = self
+            .type_registry
+            .get_type(id)
+            .ok_or_else(|| anyhow!("type {:016x} not registered", id.0))?;
+
+        // the registered type's outermost level is the one the chain reached
+        let mut foreign_levels: Vec<Qualifiers> = (0..foreign.pointer_depth)
+            .map(|level| foreign.pointer_qualifiers(level))
+            .collect();
+        foreign_levels.push(Qualifiers {
+            is_const: foreign.is_const,
+            is_volatile: foreign.is_volatile,
+        });
+        let last = levels.last_mut().unwrap();
+        last.is_const |= foreign_levels[0].is_const;
+        last.is_volatile |= foreign_levels[0].is_volatile;
+        levels.extend(foreign_levels.into_iter().skip(1));
+
+        Ok((foreign.kind.clone(), levels))
     }
 
     fn get_name(&self, entry: &DebuggingInformationEntry<R>) -> Result<String> {
         if let Some(attr) = entry.attr(gimli::DW_AT_name)? {
             let name_reader = self.dwarf.attr_string(self.unit, attr.value())?;
             let bytes = name_reader.to_slice()?;
-            let name_str = String::from_utf8(bytes.to_vec())?;
+            let name_str = String::from_utf8(bytes.to_vec()).map_err(anyhow::Error::from)?;
             return Ok(name_str);
         }
 
-        Err(anyhow!("no name attribute"))
+        // a definition that completes a declaration may only be named there
+        if let Some(AttributeValue::UnitRef(offset)) =
+            entry.attr_value(gimli::DW_AT_specification)?
+        {
+            let mut entries = self.unit.entries_at_offset(offset)?;
+            if let Some((_, declaration)) = entries.next_dfs()?
+                && declaration.attr(gimli::DW_AT_specification)?.is_none()
+            {
+                return self.get_name(declaration);
+            }
+        }
+
+        Err(anyhow!("no name attribute").into())
+    }
+
+    /// keep the `DW_AT_description` of the type `entry` defines as `kind`
+    fn record_description(
+        &mut self,
+        entry: &DebuggingInformationEntry<R>,
+        kind: &BaseTypeKind,
+    ) -> Result<()> {
+        if let Some(attr) = entry.attr(gimli::DW_AT_description)? {
+            let description = self.dwarf.attr_string(self.unit, attr.value())?;
+            let description = description.to_string_lossy()?.trim().to_string();
+            if !description.is_empty() {
+                self.type_registry.set_description(kind, description);
+            }
+        }
+        Ok(())
+    }
+
+    /// note a struct or union defined in a source file (`.c`, `.cpp`, ...),
+    /// or a typedef in a header naming one: together they make the handle
+    /// types [`TypeRegistry::is_opaque`] reports
+    fn record_definition_file(
+        &mut self,
+        entry: &DebuggingInformationEntry<R>,
+        kind: &BaseTypeKind,
+    ) -> Result<()> {
+        let Some(path) = self.decl_file(entry)? else {
+            return Ok(());
+        };
+        match kind {
+            BaseTypeKind::Typedef {
+                aliased_type_id, ..
+            } if !is_source_file(&path) => self.type_registry.set_header_typedef(*aliased_type_id),
+            BaseTypeKind::Struct { .. } | BaseTypeKind::Union { .. }
+                if is_source_file(&path) && entry.attr(gimli::DW_AT_declaration)?.is_none() =>
+            {
+                self.type_registry.set_source_definition(kind)
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// full path of the file `entry` is declared in
+    fn decl_file(&self, entry: &DebuggingInformationEntry<R>) -> Result<Option<String>> {
+        let Some(AttributeValue::FileIndex(index)) = entry.attr_value(gimli::DW_AT_decl_file)?
+        else {
+            return Ok(None);
+        };
+        let Some(program) = &self.unit.line_program else {
+            return Ok(None);
+        };
+        Ok(lines::file_path(
+            self.dwarf,
+            self.unit,
+            program.header(),
+            index,
+        ))
+    }
+
+    /// the definition of a struct, union or enum declared at `entry`, when
+    /// it is given elsewhere in the unit with a `DW_AT_specification` back to
+    /// the declaration (C++ nested types defined out of line, some C)
+    fn find_definition(
+        &mut self,
+        entry: &DebuggingInformationEntry<R>,
+    ) -> Result<Option<UnitOffset<R::Offset>>> {
+        if entry.attr(gimli::DW_AT_declaration)?.is_none() {
+            return Ok(None);
+        }
+
+        if self.definitions.is_none() {
+            let mut definitions = HashMap::new();
+            let mut entries = self.unit.entries();
+            while let Some((_, candidate)) = entries.next_dfs()? {
+                if matches!(
+                    candidate.tag(),
+                    gimli::DW_TAG_structure_type
+                        | gimli::DW_TAG_union_type
+                        | gimli::DW_TAG_enumeration_type
+                ) && let Some(AttributeValue::UnitRef(declaration)) =
+                    candidate.attr_value(gimli::DW_AT_specification)?
+                {
+                    definitions.insert(declaration.0.into_u64(), candidate.offset());
+                }
+            }
+            self.definitions = Some(definitions);
+        }
+
+        Ok(self
+            .definitions
+            .as_ref()
+            .and_then(|definitions| definitions.get(&entry.offset().0.into_u64()))
+            .copied())
     }
 
+    /// the base type behind any pointers and qualifiers, plus the qualifiers
+    /// met at each level: one entry per pointer, outermost first, and a last
+    /// entry for the base type itself
     fn extract_type_metadata(
         &mut self,
         _entry: &DebuggingInformationEntry<R>,
         offset: UnitOffset<R::Offset>,
-    ) -> Result<(BaseTypeKind, usize, bool, bool)> {
-        let mut pointer_depth = 0;
-        let mut is_const = false;
-        let mut is_volatile = false;
+    ) -> Result<(BaseTypeKind, Vec<Qualifiers>)> {
+        let mut levels = vec![Qualifiers::default()];
         let mut current_offset = offset;
+        let mut visited = HashSet::new();
 
         loop {
+            if !visited.insert(current_offset.0.into_u64()) {
+                return Err(anyhow!(
+                    "type at offset {:#010x} refers to itself",
+                    current_offset.0.into_u64()
+                )
+                .into());
+            }
+
+            // the rest of the chain was read before, through another path
+            // to it (`T*` after `const T*`, a member after a parameter), so
+            // take it from the registry instead of extracting it again
+            if current_offset != offset
+                && let Some(registered) = self
+                    .type_registry
+                    .get_by_unit_offset(self.unit_id(), current_offset.0.into_u64())
+            {
+                return self.extend_with_registered(registered.id, levels);
+            }
+
             let mut entries = self.unit.entries_at_offset(current_offset)?;
             let (_, entry) = entries
                 .next_dfs()?
@@ -83,108 +510,292 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
 
             match entry.tag() {
                 gimli::DW_TAG_pointer_type => {
-                    pointer_depth += 1;
+                    levels.push(Qualifiers::default());
                     // follow to pointee
-                    if let Some(attr) = entry.attr(gimli::DW_AT_type)?
-                        && let AttributeValue::UnitRef(next_offset) = attr.value()
+                    match entry
+                        .attr_value(gimli::DW_AT_type)?
+                        .and_then(|v| self.type_ref(v))
                     {
-                        current_offset = next_offset;
-                        continue;
+                        Some(TypeRef::Local(next_offset)) => {
+                            current_offset = next_offset;
+                            continue;
+                        }
+                        Some(TypeRef::Foreign(next_offset)) => {
+                            return self.extract_foreign_metadata(next_offset, levels);
+                        }
+                        None => {}
                     }
                     // void* if no type attribute
                     let kind = BaseTypeKind::Primitive {
                         name: "void".to_string(),
                         size: 0,
                         alignment: 1,
+                        encoding: None,
                     };
-                    return Ok((kind, pointer_depth, is_const, is_volatile));
+                    return Ok((kind, levels));
                 }
 
                 gimli::DW_TAG_const_type => {
-                    is_const = true;
+                    levels.last_mut().unwrap().is_const = true;
                     // Follow to inner type
-                    if let Some(attr) = entry.attr(gimli::DW_AT_type)?
-                        && let AttributeValue::UnitRef(next_offset) = attr.value()
+                    match entry
+                        .attr_value(gimli::DW_AT_type)?
+                        .and_then(|v| self.type_ref(v))
                     {
-                        current_offset = next_offset;
-                        continue;
+                        Some(TypeRef::Local(next_offset)) => {
+                            current_offset = next_offset;
+                            continue;
+                        }
+                        Some(TypeRef::Foreign(next_offset)) => {
+                            return self.extract_foreign_metadata(next_offset, levels);
+                        }
+                        None => {}
                     }
                     // const void if no type
                     let kind = BaseTypeKind::Primitive {
                         name: "void".to_string(),
                         size: 0,
                         alignment: 1,
+                        encoding: None,
                     };
-                    return Ok((kind, pointer_depth, is_const, is_volatile));
+                    return Ok((kind, levels));
                 }
 
                 gimli::DW_TAG_volatile_type => {
-                    is_volatile = true;
+                    levels.last_mut().unwrap().is_volatile = true;
                     // follow to inner type
-                    if let Some(attr) = entry.attr(gimli::DW_AT_type)?
-                        && let AttributeValue::UnitRef(next_offset) = attr.value()
+                    match entry
+                        .attr_value(gimli::DW_AT_type)?
+                        .and_then(|v| self.type_ref(v))
                     {
-                        current_offset = next_offset;
-                        continue;
+                        Some(TypeRef::Local(next_offset)) => {
+                            current_offset = next_offset;
+                            continue;
+                        }
+                        Some(TypeRef::Foreign(next_offset)) => {
+                            return self.extract_foreign_metadata(next_offset, levels);
+                        }
+                        None => {}
                     }
                     let kind = BaseTypeKind::Primitive {
                         name: "void".to_string(),
                         size: 0,
                         alignment: 1,
+                        encoding: None,
                     };
-                    return Ok((kind, pointer_depth, is_const, is_volatile));
+                    return Ok((kind, levels));
                 }
 
                 gimli::DW_TAG_base_type => {
                     let kind = self.extract_primitive_type(entry)?;
-                    return Ok((kind, pointer_depth, is_const, is_volatile));
+                    return Ok((kind, levels));
                 }
 
                 gimli::DW_TAG_typedef => {
                     let kind = self.extract_typedef_type(entry)?;
-                    return Ok((kind, pointer_depth, is_const, is_volatile));
+                    self.record_description(entry, &kind)?;
+                    self.record_definition_file(entry, &kind)?;
+                    return Ok((kind, levels));
+                }
+
+                gimli::DW_TAG_structure_type
+                | gimli::DW_TAG_union_type
+                | gimli::DW_TAG_enumeration_type
+                    if let Some(definition) = self.find_definition(entry)? =>
+                {
+                    log::debug!(
+                        "{:>12} {:#010x}: declaration completed at {:#010x}",
+                        "aggregate",
+                        current_offset.0.into_u64(),
+                        definition.0.into_u64()
+                    );
+                    current_offset = definition;
+                    continue;
+                }
+
+                // an aggregate reached again while its own members are
+                // being extracted (`struct Node { struct Node *next; }`)
+                // is registered as a placeholder, given the aggregate's kind
+                // once it's extracted
+                gimli::DW_TAG_structure_type | gimli::DW_TAG_union_type
+                    if self
+                        .in_progress
+                        .contains(&(self.unit_id(), current_offset.0.into_u64())) =>
+                {
+                    let kind = self.extract_aggregate_declaration(entry)?;
+                    self.cycles.back_reference =
+                        Some((self.unit_id(), current_offset.0.into_u64()));
+                    return Ok((kind, levels));
                 }
 
                 gimli::DW_TAG_structure_type => {
-                    let kind = self.extract_struct_type(entry, current_offset)?;
-                    return Ok((kind, pointer_depth, is_const, is_volatile));
+                    let kind = self.with_in_progress(current_offset, |resolver| {
+                        resolver.extract_struct_type(entry, current_offset)
+                    })?;
+                    self.record_description(entry, &kind)?;
+                    self.record_definition_file(entry, &kind)?;
+                    return Ok((kind, levels));
                 }
 
                 gimli::DW_TAG_union_type => {
-                    let kind = self.extract_union_type(entry, current_offset)?;
-                    return Ok((kind, pointer_depth, is_const, is_volatile));
+                    let kind = self.with_in_progress(current_offset, |resolver| {
+                        resolver.extract_union_type(entry, current_offset)
+                    })?;
+                    self.record_description(entry, &kind)?;
+                    self.record_definition_file(entry, &kind)?;
+                    return Ok((kind, levels));
                 }
 
                 gimli::DW_TAG_enumeration_type => {
                     let kind = self.extract_enum_type(entry, current_offset)?;
-                    return Ok((kind, pointer_depth, is_const, is_volatile));
+                    self.record_description(entry, &kind)?;
+                    return Ok((kind, levels));
                 }
 
                 gimli::DW_TAG_array_type => {
                     let kind = self.extract_array_type(entry, current_offset)?;
-                    return Ok((kind, pointer_depth, is_const, is_volatile));
+                    return Ok((kind, levels));
                 }
 
                 gimli::DW_TAG_subroutine_type => {
                     let kind = self.extract_function_type(entry, current_offset)?;
-                    return Ok((kind, pointer_depth, is_const, is_volatile));
+                    return Ok((kind, levels));
+                }
+
+                gimli::DW_TAG_ptr_to_member_type => {
+                    let kind = self.extract_pointer_to_member_type(entry)?;
+                    return Ok((kind, levels));
                 }
 
                 _ => {
-                    // Placeholder for now
+                    // tags with no model of their own (restrict, _Atomic,
+                    // vendor array tags, ...) wrap another type; look through
+                    // them so the accumulated qualifiers still apply
+                    if let Some(next) = entry
+                        .attr_value(gimli::DW_AT_type)?
+                        .and_then(|v| self.type_ref(v))
+                    {
+                        log::debug!(
+                            "{:>12} {:#010x}: following {} to its type",
+                            "unknown",
+                            current_offset.0.into_u64(),
+                            entry.tag()
+                        );
+                        match next {
+                            TypeRef::Local(next_offset) => {
+                                current_offset = next_offset;
+                                continue;
+                            }
+                            TypeRef::Foreign(next_offset) => {
+                                return self.extract_foreign_metadata(next_offset, levels);
+                            }
+                        }
+                    }
                     let kind = BaseTypeKind::Primitive {
                         name: format!("<unknown:{}>", entry.tag()),
                         size: 0,
                         alignment: 1,
+                        encoding: None,
                     };
-                    return Ok((kind, pointer_depth, is_const, is_volatile));
+                    return Ok((kind, levels));
                 }
             }
         }
     }
 
+    /// run `extract` with the aggregate at `offset` marked as in progress,
+    /// then give the placeholders standing in for it its kind. once no
+    /// aggregate is in progress, the types that referred back to one get
+    /// their ids, and so do those in the kind returned.
+    fn with_in_progress(
+        &mut self,
+        offset: UnitOffset<R::Offset>,
+        extract: impl FnOnce(&mut Self) -> Result<BaseTypeKind>,
+    ) -> Result<BaseTypeKind> {
+        let key = (self.unit_id(), offset.0.into_u64());
+        let newly_inserted = self.in_progress.insert(key);
+        let mut result = extract(self);
+        if newly_inserted {
+            self.in_progress.remove(&key);
+        }
+
+        // a placeholder for an aggregate that failed to extract keeps the
+        // declaration it was registered with
+        let registry = &mut self.type_registry;
+        self.cycles.placeholders.retain(|&(id, aggregate)| {
+            if aggregate != key {
+                return true;
+            }
+            if let Ok(kind) = &result {
+                registry.complete_placeholder(id, kind);
+            }
+            false
+        });
+
+        if self.in_progress.is_empty() && !self.cycles.pending.is_empty() {
+            let cycles = core::mem::take(&mut self.cycles);
+            let renames = self
+                .type_registry
+                .close_cycles(cycles.pending, &cycles.offsets);
+            if let Ok(kind) = &mut result {
+                kind.map_type_ids(|id| renames.get(&id).copied().unwrap_or(id));
+            }
+        }
+        result
+    }
+
+    /// an opaque struct or union with the aggregate's name and tag, standing
+    /// in for it inside its own members until it is extracted
+    fn extract_aggregate_declaration(
+        &self,
+        entry: &DebuggingInformationEntry<R>,
+    ) -> Result<BaseTypeKind> {
+        let name = self
+            .get_name(entry)
+            .unwrap_or_else(|_| "<anonymous>".to_string());
+        let is_union = entry.tag() == gimli::DW_TAG_union_type;
+        log::debug!(
+            "{:>12} {:#010x}: {} (declaration, refers to itself)",
+            if is_union { "union" } else { "struct" },
+            entry.offset().0.into_u64(),
+            name
+        );
+        if is_union {
+            // a union without variants, which `TypeRegistry::is_opaque` takes
+            // for a declaration
+            return Ok(BaseTypeKind::Union {
+                name,
+                variants: Vec::new(),
+                size: 0,
+                alignment: 1,
+                discriminant: None,
+            });
+        }
+        Ok(BaseTypeKind::Struct {
+            name,
+            fields: Vec::new(),
+            size: 0,
+            alignment: 1,
+            is_opaque: true,
+        })
+    }
+
     fn extract_primitive_type(&self, entry: &DebuggingInformationEntry<R>) -> Result<BaseTypeKind> {
-        let name = self.get_name(entry)?;
+        let mut name = self.get_name(entry)?;
+        let encoding = match entry.attr_value(gimli::DW_AT_encoding)? {
+            Some(AttributeValue::Encoding(encoding)) => PrimitiveEncoding::from_dwarf(encoding),
+            _ => None,
+        };
+        if self.normalize_primitive_names {
+            let is_unsigned = match encoding {
+                Some(PrimitiveEncoding::Unsigned) => Some(true),
+                Some(PrimitiveEncoding::Signed) => Some(false),
+                _ => None,
+            };
+            if let Some(canonical) = crate::c_type::canonical_integer_name(&name, is_unsigned) {
+                name = canonical;
+            }
+        }
         let size = entry
             .attr(gimli::DW_AT_byte_size)?
             .and_then(|attr| attr.udata_value())
@@ -196,23 +807,61 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
             name,
             size,
             alignment: size, // alignment = size for primitives
+            encoding,
+        })
+    }
+
+    /// C++ pointer-to-member types are kept as an opaque primitive of the
+    /// right size rather than modeled
+    fn extract_pointer_to_member_type(
+        &self,
+        entry: &DebuggingInformationEntry<R>,
+    ) -> Result<BaseTypeKind> {
+        let address_size = self.unit.encoding().address_size as usize;
+
+        let size = match entry
+            .attr(gimli::DW_AT_byte_size)?
+            .and_then(|attr| attr.udata_value())
+        {
+            Some(size) => size as usize,
+            // itanium ABI: member function pointers are a (pointer,
+            // adjustment) pair, data member pointers are an offset
+            None if self.points_to_subroutine(entry)? => 2 * address_size,
+            None => address_size,
+        };
+
+        log::debug!("{:>12} ({} bytes)", "ptr-to-mem", size);
+
+        Ok(BaseTypeKind::Primitive {
+            name: POINTER_TO_MEMBER.to_string(),
+            size,
+            alignment: address_size,
+            encoding: None,
         })
     }
 
+    fn points_to_subroutine(&self, entry: &DebuggingInformationEntry<R>) -> Result<bool> {
+        let Some(AttributeValue::UnitRef(offset)) = entry.attr_value(gimli::DW_AT_type)? else {
+            return Ok(false);
+        };
+        let mut entries = self.unit.entries_at_offset(offset)?;
+        Ok(entries
+            .next_dfs()?
+            .is_some_and(|(_, pointee)| pointee.tag() == gimli::DW_TAG_subroutine_type))
+    }
+
     fn extract_typedef_type(
         &mut self,
         entry: &DebuggingInformationEntry<R>,
     ) -> Result<BaseTypeKind> {
         let name = self.get_name(entry)?;
 
-        let aliased_type_id = if let Some(attr) = entry.attr(gimli::DW_AT_type)? {
-            if let AttributeValue::UnitRef(offset) = attr.value() {
-                self.build_type_registry_entry(offset)?
-            } else {
-                self.get_or_create_void_type()?
-            }
-        } else {
-            self.get_or_create_void_type()?
+        let aliased_type_id = match self.objc_runtime_type(&name)? {
+            Some(id) => id,
+            None => match self.resolve_type_attr(entry)? {
+                Some(id) => id,
+                None => self.get_or_create_void_type()?,
+            },
         };
 
         log::debug!("{:>12} {}", "typedef", name);
@@ -223,9 +872,65 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
         })
     }
 
+    /// the opaque pointer an Objective-C runtime typedef stands for: `id`,
+    /// `Class` and `SEL` point to the runtime's `objc_object`, `objc_class`
+    /// and `objc_selector`, and `IMP` to code, as `void*`. their layouts
+    /// belong to the runtime, so bindings pass them as handles. None for
+    /// other names and outside Objective-C units.
+    fn objc_runtime_type(&mut self, name: &str) -> Result<Option<TypeId>> {
+        let pointee = match name {
+            "id" => "objc_object",
+            "Class" => "objc_class",
+            "SEL" => "objc_selector",
+            "IMP" => "void",
+            _ => return Ok(None),
+        };
+        if !self.is_objc_unit()? {
+            return Ok(None);
+        }
+
+        let kind = match pointee {
+            "void" => BaseTypeKind::Primitive {
+                name: "void".to_string(),
+                size: 0,
+                alignment: 1,
+                encoding: None,
+            },
+            _ => BaseTypeKind::Struct {
+                name: pointee.to_string(),
+                fields: Vec::new(),
+                size: 0,
+                alignment: 1,
+                is_opaque: true,
+            },
+        };
+        Ok(Some(self.type_registry.register_type(Type {
+            id: TypeId(0),
+            kind,
+            pointer_depth: 1,
+            is_const: false,
+            is_volatile: false,
+            pointer_qualifiers: Vec::new(),
+            dwarf_offset: None,
+        })))
+    }
+
+    /// whether the unit's `DW_AT_language` is Objective-C or Objective-C++
+    fn is_objc_unit(&self) -> Result<bool> {
+        let mut entries = self.unit.entries();
+        let Some((_, root)) = entries.next_dfs()? else {
+            return Ok(false);
+        };
+        Ok(matches!(
+            root.attr_value(gimli::DW_AT_language)?,
+            Some(AttributeValue::Language(
+                gimli::DW_LANG_ObjC | gimli::DW_LANG_ObjC_plus_plus
+            ))
+        ))
+    }
+
     fn get_or_create_void_type(&mut self) -> Result<TypeId> {
-        let void_types = self.type_registry.get_by_name("void");
-        if let Some(void_type) = void_types.first() {
+        if let Some(void_type) = self.type_registry.get_base_by_name("void") {
             return Ok(void_type.id);
         }
 
@@ -235,10 +940,12 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
                 name: "void".to_string(),
                 size: 0,
                 alignment: 1,
+                encoding: None,
             },
             pointer_depth: 0,
             is_const: false,
             is_volatile: false,
+            pointer_qualifiers: Vec::new(),
             dwarf_offset: None,
         };
 
@@ -279,10 +986,19 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
             );
         }
 
+        // a struct made of a variant part is a discriminated union
+        if !is_opaque && let Some(kind) = self.extract_variant_part(&name, size, offset)? {
+            return Ok(kind);
+        }
+
         // extract fields (children of struct entry)
         let fields = self.extract_struct_fields(offset)?;
 
-        let alignment = fields.iter().map(|f| f.size).max().unwrap_or(1);
+        let alignment = fields
+            .iter()
+            .map(|f| self.type_alignment(f.type_id))
+            .max()
+            .unwrap_or(1);
 
         Ok(BaseTypeKind::Struct {
             name,
@@ -311,35 +1027,73 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
 
             let name = self.get_name(entry).unwrap_or_default();
 
-            let type_id = if let Some(attr) = entry.attr(gimli::DW_AT_type)? {
-                if let AttributeValue::UnitRef(offset) = attr.value() {
-                    self.build_type_registry_entry(offset)?
-                } else {
-                    log::trace!("skip field {} with invalid type reference", name);
+            let type_id = match entry.attr_value(gimli::DW_AT_type)? {
+                Some(value) => match self.resolve_reference(value)? {
+                    Some(id) => id,
+                    None => {
+                        log::trace!("skip field {} with invalid type reference", name);
+                        continue;
+                    }
+                },
+                None => {
+                    log::trace!("skip field {} with no type", name);
                     continue;
                 }
-            } else {
-                log::trace!("skip field {} with no type", name);
-                continue;
             };
 
-            let offset = entry
-                .attr(gimli::DW_AT_data_member_location)?
+            let bit_size = entry
+                .attr(gimli::DW_AT_bit_size)?
                 .and_then(|attr| attr.udata_value())
-                .unwrap_or(0) as usize;
+                .map(|bits| bits as u32);
+            let data_bit_offset = entry
+                .attr(gimli::DW_AT_data_bit_offset)?
+                .and_then(|attr| attr.udata_value())
+                .map(|bits| bits as u32);
+
+            // DWARF 4+ bitfields give a bit offset instead of a byte offset;
+            // report the byte they start in
+            let offset = match entry.attr(gimli::DW_AT_data_member_location)? {
+                Some(attr) => match self.member_location(&attr)? {
+                    Some(offset) => offset as usize,
+                    None => {
+                        log::warn!("field {} has a dynamic location, assuming offset 0", name);
+                        0
+                    }
+                },
+                None => data_bit_offset.map_or(0, |bits| bits as usize / 8),
+            };
 
             // Get size from the field's type
-            let field_type = self.type_registry.get_type(type_id);
-            let size = if let Some(ft) = field_type {
-                match &ft.kind {
-                    BaseTypeKind::Primitive { size, .. } => *size,
-                    BaseTypeKind::Struct { size, .. } => *size,
-                    BaseTypeKind::Array { size, .. } => *size,
-                    _ => 0,
+            let size = self.type_size(type_id);
+
+            let bit_offset = match (bit_size, data_bit_offset) {
+                (None, _) => None,
+                (Some(_), Some(bits)) => Some(bits),
+                // DWARF 2/3 count DW_AT_bit_offset from the most significant
+                // bit of a storage unit at the member's location
+                (Some(width), None) => {
+                    let from_msb = entry
+                        .attr(gimli::DW_AT_bit_offset)?
+                        .and_then(|attr| attr.udata_value())
+                        .unwrap_or(0) as u32;
+                    let storage_bits = entry
+                        .attr(gimli::DW_AT_byte_size)?
+                        .and_then(|attr| attr.udata_value())
+                        .map_or(size, |bytes| bytes as usize)
+                        as u32
+                        * 8;
+                    let big_endian = gimli::Endianity::is_big_endian(
+                        gimli::Section::reader(&self.dwarf.debug_info).endian(),
+                    );
+                    let within = if big_endian {
+                        from_msb
+                    } else {
+                        storage_bits.saturating_sub(from_msb + width)
+                    };
+                    Some(offset as u32 * 8 + within)
                 }
-            } else {
-                0
             };
+            let endianness = self.member_endianness(entry)?;
 
             log::trace!(
                 "{:>12} {:#010x}: {} @ offset {}",
@@ -354,61 +1108,283 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
                 type_id,
                 offset,
                 size,
+                declaration_index: fields.len(),
+                endianness,
+                bit_size,
+                bit_offset,
             });
         }
 
+        // memory order for layout; stable, so fields sharing an offset
+        // (bitfields) stay in declaration order
+        fields.sort_by_key(|f| f.offset);
+
         log::debug!("extracted {} fields", fields.len());
         Ok(fields)
     }
 
-    fn extract_union_type(
+    /// the struct at `struct_offset` as a discriminated union if its only
+    /// member is a `DW_TAG_variant_part`, as Rust emits for enums with data:
+    /// the discriminant member named by `DW_AT_discr` plus one union variant
+    /// per member of each `DW_TAG_variant`, laid over each other. None for
+    /// any other struct; a variant part next to other members is left out.
+    fn extract_variant_part(
         &mut self,
-        entry: &DebuggingInformationEntry<R>,
-        offset: UnitOffset<R::Offset>,
-    ) -> Result<BaseTypeKind> {
-        let name = self
-            .get_name(entry)
-            .unwrap_or_else(|_| "<anonymous>".to_string());
-
-        let size = entry
-            .attr(gimli::DW_AT_byte_size)?
-            .and_then(|attr| attr.udata_value())
-            .unwrap_or(0) as usize;
-
-        log::debug!(
-            "{:>12} {:#010x}: {} ({} bytes)",
-            "union",
-            offset.0.into_u64(),
-            name,
-            size
-        );
-
-        let variants = self.extract_union_fields(offset)?;
+        name: &str,
+        size: usize,
+        struct_offset: UnitOffset<R::Offset>,
+    ) -> Result<Option<BaseTypeKind>> {
+        let mut variant_parts = Vec::new();
+        let mut members = Vec::new();
+        let mut tree = self.unit.entries_tree(Some(struct_offset))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            match child.entry().tag() {
+                gimli::DW_TAG_variant_part => variant_parts.push(child.entry().offset()),
+                gimli::DW_TAG_member => members.push(child.entry().offset()),
+                _ => {}
+            }
+        }
+        let [variant_part] = variant_parts[..] else {
+            if !variant_parts.is_empty() {
+                log::warn!("{}: more than one variant part, not extracted", name);
+            }
+            return Ok(None);
+        };
 
-        let alignment = variants
+        let part = self.unit.entry(variant_part)?;
+        let discriminant_offset = match part.attr_value(gimli::DW_AT_discr)? {
+            Some(AttributeValue::UnitRef(offset)) => Some(offset),
+            _ => None,
+        };
+        // the discriminant may also be a member of the struct itself
+        if members
             .iter()
-            .filter_map(|v| {
-                self.type_registry
-                    .get_type(v.type_id)
-                    .and_then(|t| match &t.kind {
-                        BaseTypeKind::Primitive { alignment, .. } => Some(*alignment),
-                        BaseTypeKind::Struct { alignment, .. } => Some(*alignment),
-                        _ => None,
-                    })
-            })
-            .max()
-            .unwrap_or(1);
+            .any(|&offset| Some(offset) != discriminant_offset)
+        {
+            log::warn!(
+                "{}: variant part next to other members, not extracted",
+                name
+            );
+            return Ok(None);
+        }
 
-        Ok(BaseTypeKind::Union {
-            name,
-            variants,
-            size,
-            alignment,
-        })
-    }
+        let discriminant = match discriminant_offset {
+            Some(offset) => {
+                let entry = self.unit.entry(offset)?;
+                let member_name = self.get_name(&entry).unwrap_or_default();
+                let type_id = match entry.attr_value(gimli::DW_AT_type)? {
+                    Some(value) => self.resolve_reference(value)?,
+                    None => None,
+                };
+                let offset = match entry.attr(gimli::DW_AT_data_member_location)? {
+                    Some(attr) => self.member_location(&attr)?,
+                    None => Some(0),
+                };
+                match (type_id, offset) {
+                    (Some(type_id), Some(offset)) => Some(Discriminant {
+                        name: member_name,
+                        type_id,
+                        offset: offset as usize,
+                    }),
+                    _ => {
+                        log::warn!(
+                            "{}: discriminant {} has no usable type or location",
+                            name,
+                            member_name
+                        );
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
 
-    fn extract_union_fields(
-        &mut self,
+        let mut variant_members = Vec::new();
+        let mut tree = self.unit.entries_tree(Some(variant_part))?;
+        let mut children = tree.root()?.children();
+        while let Some(variant) = children.next()? {
+            if variant.entry().tag() != gimli::DW_TAG_variant {
+                continue;
+            }
+            // no value (or a DW_AT_discr_list of ranges) is the default variant
+            let value = match variant.entry().attr_value(gimli::DW_AT_discr_value)? {
+                Some(AttributeValue::Sdata(value)) => Some(value),
+                Some(value) => value.udata_value().map(|value| value as i64),
+                None => None,
+            };
+            let mut members = variant.children();
+            while let Some(member) = members.next()? {
+                if member.entry().tag() == gimli::DW_TAG_member {
+                    variant_members.push((member.entry().offset(), value));
+                }
+            }
+        }
+
+        let mut variants = Vec::new();
+        for (offset, discriminant_value) in variant_members {
+            let entry = self.unit.entry(offset)?;
+            let member_name = self.get_name(&entry).unwrap_or_default();
+            // union variants start at the union's start; rustc puts the
+            // member of each variant there and pads it to the enum's size
+            let location = match entry.attr(gimli::DW_AT_data_member_location)? {
+                Some(attr) => self.member_location(&attr)?,
+                None => Some(0),
+            };
+            if location != Some(0) {
+                log::warn!("{}: skip variant {} not at offset 0", name, member_name);
+                continue;
+            }
+            let Some(type_id) = (match entry.attr_value(gimli::DW_AT_type)? {
+                Some(value) => self.resolve_reference(value)?,
+                None => None,
+            }) else {
+                log::trace!("skip variant {} with no type", member_name);
+                continue;
+            };
+
+            log::trace!(
+                "{:>12} {} = {:?}",
+                "variant",
+                member_name,
+                discriminant_value
+            );
+            variants.push(crate::type_registry::UnionField {
+                name: member_name,
+                type_id,
+                discriminant_value,
+            });
+        }
+
+        let alignment = variants
+            .iter()
+            .map(|v| v.type_id)
+            .chain(discriminant.iter().map(|d| d.type_id))
+            .map(|id| self.type_alignment(id))
+            .max()
+            .unwrap_or(1);
+
+        log::debug!(
+            "{:>12} {}: {} variants",
+            "variant part",
+            name,
+            variants.len()
+        );
+        Ok(Some(BaseTypeKind::Union {
+            name: name.to_string(),
+            variants,
+            size,
+            alignment,
+            discriminant,
+        }))
+    }
+
+    /// byte order of a member from `DW_AT_endianity`, on the member itself
+    /// or, as gcc emits it for `scalar_storage_order`, on the base type it
+    /// refers to through typedefs and qualifiers. None for the unit's
+    /// default order.
+    fn member_endianness(
+        &self,
+        member: &DebuggingInformationEntry<R>,
+    ) -> Result<Option<Endianness>> {
+        let endianness = |entry: &DebuggingInformationEntry<R>| -> Result<_> {
+            Ok(match entry.attr_value(gimli::DW_AT_endianity)? {
+                Some(AttributeValue::Endianity(gimli::DW_END_big)) => Some(Endianness::Big),
+                Some(AttributeValue::Endianity(gimli::DW_END_little)) => Some(Endianness::Little),
+                _ => None,
+            })
+        };
+        if let Some(found) = endianness(member)? {
+            return Ok(Some(found));
+        }
+
+        let mut next = member.attr_value(gimli::DW_AT_type)?;
+        for _ in 0..MAX_TYPE_DEPTH {
+            let Some(TypeRef::Local(offset)) = next.and_then(|value| self.type_ref(value)) else {
+                break;
+            };
+            let entry = self.unit.entry(offset)?;
+            if let Some(found) = endianness(&entry)? {
+                return Ok(Some(found));
+            }
+            if !matches!(
+                entry.tag(),
+                gimli::DW_TAG_typedef | gimli::DW_TAG_const_type | gimli::DW_TAG_volatile_type
+            ) {
+                break;
+            }
+            next = entry.attr_value(gimli::DW_AT_type)?;
+        }
+        Ok(None)
+    }
+
+    /// byte offset from DW_AT_data_member_location. DWARF 2/3 producers
+    /// write it as a `DW_OP_plus_uconst` expression rather than a constant;
+    /// None if the expression needs the object address to evaluate
+    fn member_location(&self, attr: &gimli::Attribute<R>) -> Result<Option<u64>> {
+        if let Some(offset) = attr.udata_value() {
+            return Ok(Some(offset));
+        }
+
+        let expression = match attr.value() {
+            AttributeValue::Exprloc(expression) => expression,
+            AttributeValue::Block(data) => gimli::Expression(data),
+            _ => return Ok(None),
+        };
+
+        let mut operations = expression.operations(self.unit.encoding());
+        let offset = match operations.next()? {
+            Some(gimli::Operation::PlusConstant { value }) => value,
+            Some(gimli::Operation::UnsignedConstant { value }) => value,
+            _ => return Ok(None),
+        };
+        if operations.next()?.is_some() {
+            return Ok(None);
+        }
+        Ok(Some(offset))
+    }
+
+    fn extract_union_type(
+        &mut self,
+        entry: &DebuggingInformationEntry<R>,
+        offset: UnitOffset<R::Offset>,
+    ) -> Result<BaseTypeKind> {
+        let name = self
+            .get_name(entry)
+            .unwrap_or_else(|_| "<anonymous>".to_string());
+
+        let size = entry
+            .attr(gimli::DW_AT_byte_size)?
+            .and_then(|attr| attr.udata_value())
+            .unwrap_or(0) as usize;
+
+        log::debug!(
+            "{:>12} {:#010x}: {} ({} bytes)",
+            "union",
+            offset.0.into_u64(),
+            name,
+            size
+        );
+
+        let variants = self.extract_union_fields(offset)?;
+
+        let alignment = variants
+            .iter()
+            .map(|v| self.type_alignment(v.type_id))
+            .max()
+            .unwrap_or(1);
+
+        Ok(BaseTypeKind::Union {
+            name,
+            variants,
+            size,
+            alignment,
+            discriminant: None,
+        })
+    }
+
+    fn extract_union_fields(
+        &mut self,
         union_offset: UnitOffset<R::Offset>,
     ) -> Result<Vec<crate::type_registry::UnionField>> {
         let mut variants = Vec::new();
@@ -425,20 +1401,26 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
 
             let name = self.get_name(entry).unwrap_or_default();
 
-            let type_id = if let Some(attr) = entry.attr(gimli::DW_AT_type)? {
-                if let AttributeValue::UnitRef(offset) = attr.value() {
-                    self.build_type_registry_entry(offset)?
-                } else {
-                    log::trace!("skip variant {} with invalid type reference", name);
+            let type_id = match entry.attr_value(gimli::DW_AT_type)? {
+                Some(value) => match self.resolve_reference(value)? {
+                    Some(id) => id,
+                    None => {
+                        log::trace!("skip variant {} with invalid type reference", name);
+                        continue;
+                    }
+                },
+                None => {
+                    log::trace!("skip variant {} with no type", name);
                     continue;
                 }
-            } else {
-                log::trace!("skip variant {} with no type", name);
-                continue;
             };
 
             log::trace!("{:>12} {}", "variant", name);
-            variants.push(crate::type_registry::UnionField { name, type_id });
+            variants.push(crate::type_registry::UnionField {
+                name,
+                type_id,
+                discriminant_value: None,
+            });
         }
 
         log::debug!("extracted {} variants", variants.len());
@@ -454,10 +1436,44 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
             .get_name(entry)
             .unwrap_or_else(|_| "<anonymous>".to_string());
 
-        let size = entry
+        // extract underlying type (DWARF DW_AT_type on enum)
+        let explicit_backing_id = self.resolve_type_attr(entry)?;
+        let backing_id = match explicit_backing_id {
+            Some(id) => id,
+            None => self.get_or_create_int_type()?,
+        };
+
+        // the enum's own size is what lays out structs containing it; an
+        // explicit underlying type (`enum E : uint8_t`) gives it otherwise,
+        // and plain C enums are int-sized
+        let backing_size = explicit_backing_id
+            .map(|id| self.type_size(id))
+            .filter(|size| *size > 0);
+        let declared_size = match entry
             .attr(gimli::DW_AT_byte_size)?
             .and_then(|attr| attr.udata_value())
-            .unwrap_or(4) as usize; // Default to int size
+        {
+            Some(bytes) => Some(bytes as usize),
+            None => entry
+                .attr(gimli::DW_AT_bit_size)?
+                .and_then(|attr| attr.udata_value())
+                .map(|bits| bits.div_ceil(8) as usize),
+        };
+        let size = match (declared_size, backing_size) {
+            (Some(declared), Some(backing)) if declared != backing => {
+                log::warn!(
+                    "enum {} is {} bytes but its underlying type is {} bytes; using {}",
+                    name,
+                    declared,
+                    backing,
+                    declared
+                );
+                declared
+            }
+            (Some(declared), _) => declared,
+            (None, Some(backing)) => backing,
+            (None, None) => 4,
+        };
 
         log::debug!(
             "{:>12} {:#010x}: {} ({} bytes)",
@@ -467,18 +1483,10 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
             size
         );
 
-        // extract underlying type (DWARF DW_AT_type on enum)
-        let backing_id = if let Some(attr) = entry.attr(gimli::DW_AT_type)? {
-            if let AttributeValue::UnitRef(type_offset) = attr.value() {
-                self.build_type_registry_entry(type_offset)?
-            } else {
-                self.get_or_create_int_type()?
-            }
-        } else {
-            self.get_or_create_int_type()?
-        };
-
-        let variants = self.extract_enum_variants(offset)?;
+        // the same DW_FORM_data4 holds -1 for an int enum and 0xFFFFFFFF
+        // for an unsigned one; the backing type says which
+        let is_unsigned = self.type_registry.is_unsigned_enum(backing_id);
+        let variants = self.extract_enum_variants(offset, is_unsigned)?;
 
         Ok(BaseTypeKind::Enum {
             name,
@@ -491,6 +1499,7 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
     fn extract_enum_variants(
         &mut self,
         enum_offset: UnitOffset<R::Offset>,
+        is_unsigned: bool,
     ) -> Result<Vec<crate::type_registry::EnumVariant>> {
         let mut variants = Vec::new();
         let mut tree = self.unit.entries_tree(Some(enum_offset))?;
@@ -506,9 +1515,14 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
 
             let name = self.get_name(entry).unwrap_or_default();
 
+            // an unsigned value above i64::MAX keeps its bits; see
+            // `EnumVariant::value_string`
             let value = entry
                 .attr(gimli::DW_AT_const_value)?
-                .and_then(|attr| attr.sdata_value())
+                .and_then(|attr| match attr.udata_value() {
+                    Some(value) if is_unsigned => Some(value as i64),
+                    _ => attr.sdata_value(),
+                })
                 .unwrap_or(0);
 
             log::trace!("{:>12} {} = {}", "enumerator", name, value);
@@ -525,38 +1539,52 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
         offset: UnitOffset<R::Offset>,
     ) -> Result<BaseTypeKind> {
         // get element type
-        let element_type_id = if let Some(attr) = entry.attr(gimli::DW_AT_type)? {
-            if let AttributeValue::UnitRef(type_offset) = attr.value() {
-                self.build_type_registry_entry(type_offset)?
-            } else {
-                return Err(anyhow!("array missing element type"));
-            }
-        } else {
-            return Err(anyhow!("array missing element type"));
+        let element_type_id = self
+            .resolve_type_attr(entry)?
+            .ok_or_else(|| anyhow!("array missing element type"))?;
+
+        // get array dimensions (subrange children). a non-constant bound has
+        // no static length, so it is recorded as dynamic rather than 0
+        let (mut count, mut is_dynamic) = match self.extract_array_count(offset)? {
+            ArrayBound::Constant(count) => (count, false),
+            ArrayBound::Dynamic => (0, true),
         };
 
-        // get array dimensions (subrange children)
-        let count = self.extract_array_count(offset)?;
-
         // calculate size
-        let element_type = self
-            .type_registry
-            .get_type(element_type_id)
-            .ok_or_else(|| anyhow!("element type not found"))?;
-        let element_size = match &element_type.kind {
-            BaseTypeKind::Primitive { size, .. } => *size,
-            BaseTypeKind::Struct { size, .. } => *size,
-            BaseTypeKind::Array { size, .. } => *size,
-            _ => 0,
+        if self.type_registry.get_type(element_type_id).is_none() {
+            return Err(anyhow!("element type not found").into());
+        }
+        let element_size = self.type_size(element_type_id);
+
+        // padded elements can be further apart than their size
+        let stride = match self.extract_array_stride(entry)? {
+            ArrayStride::Element => element_size,
+            ArrayStride::Bytes(stride) => stride,
+            // a packed bit array (Ada, Pascal) has no byte layout to give,
+            // so like a VLA it says nothing about its length
+            ArrayStride::Bits(bits) => {
+                log::warn!(
+                    "array at {:#010x} packs its elements {} bits apart; treating it as dynamic",
+                    offset.0.into_u64(),
+                    bits
+                );
+                count = 0;
+                is_dynamic = true;
+                element_size
+            }
         };
+        let total_size = stride * count;
 
-        let total_size = element_size * count;
-
+        let bound = if is_dynamic {
+            "*".to_string()
+        } else {
+            count.to_string()
+        };
         log::debug!(
             "{:>12} {:#010x}: [{}] ({} bytes)",
             "array",
             offset.0.into_u64(),
-            count,
+            bound,
             total_size
         );
 
@@ -564,10 +1592,48 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
             element_type_id,
             count,
             size: total_size,
+            stride,
+            is_dynamic,
+        })
+    }
+
+    /// size of a registered type, using the unit's address size for pointers
+    fn type_size(&self, type_id: TypeId) -> usize {
+        let pointer_width = self.unit.encoding().address_size as usize;
+        self.type_registry
+            .size_of(type_id, pointer_width)
+            .unwrap_or(0)
+    }
+
+    /// alignment of a registered type, using the unit's address size for
+    /// pointers
+    fn type_alignment(&self, type_id: TypeId) -> usize {
+        let pointer_width = self.unit.encoding().address_size as usize;
+        self.type_registry
+            .align_of(type_id, pointer_width)
+            .unwrap_or(1)
+    }
+
+    /// explicit element stride, from DW_AT_byte_stride or DW_AT_bit_stride
+    fn extract_array_stride(&self, entry: &DebuggingInformationEntry<R>) -> Result<ArrayStride> {
+        if let Some(stride) = entry
+            .attr(gimli::DW_AT_byte_stride)?
+            .and_then(|attr| attr.udata_value())
+        {
+            return Ok(ArrayStride::Bytes(stride as usize));
+        }
+
+        let bit_stride = entry
+            .attr(gimli::DW_AT_bit_stride)?
+            .and_then(|attr| attr.udata_value());
+        Ok(match bit_stride {
+            None => ArrayStride::Element,
+            Some(bits) if bits % 8 == 0 => ArrayStride::Bytes((bits / 8) as usize),
+            Some(bits) => ArrayStride::Bits(bits),
         })
     }
 
-    fn extract_array_count(&mut self, array_offset: UnitOffset<R::Offset>) -> Result<usize> {
+    fn extract_array_count(&mut self, array_offset: UnitOffset<R::Offset>) -> Result<ArrayBound> {
         let mut tree = self.unit.entries_tree(Some(array_offset))?;
         let array_node = tree.root()?;
 
@@ -577,23 +1643,36 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
 
             if entry.tag() == gimli::DW_TAG_subrange_type {
                 // DW_AT_upper_bound or DW_AT_count
-                if let Some(attr) = entry.attr(gimli::DW_AT_count)?
-                    && let Some(count) = attr.udata_value()
-                {
-                    return Ok(count as usize);
+                if let Some(attr) = entry.attr(gimli::DW_AT_count)? {
+                    if let Some(count) = bound_value(&attr) {
+                        return Ok(ArrayBound::Constant(usize::try_from(count).unwrap_or(0)));
+                    }
+                    if is_runtime_bound(&attr) {
+                        return Ok(ArrayBound::Dynamic);
+                    }
                 }
 
-                if let Some(attr) = entry.attr(gimli::DW_AT_upper_bound)?
-                    && let Some(upper) = attr.udata_value()
-                {
-                    // Count = upper_bound + 1 (0-indexed)
-                    return Ok((upper + 1) as usize);
+                if let Some(attr) = entry.attr(gimli::DW_AT_upper_bound)? {
+                    if let Some(upper) = bound_value(&attr) {
+                        // C indexes from 0, other languages may say otherwise
+                        let lower = match entry.attr(gimli::DW_AT_lower_bound)? {
+                            Some(attr) => bound_value(&attr).unwrap_or(0),
+                            None => 0,
+                        };
+                        // an upper bound below the lower one (gcc's -1 for
+                        // `int a[0]`) is an empty array
+                        let count = i128::from(upper) - i128::from(lower) + 1;
+                        return Ok(ArrayBound::Constant(usize::try_from(count).unwrap_or(0)));
+                    }
+                    if is_runtime_bound(&attr) {
+                        return Ok(ArrayBound::Dynamic);
+                    }
                 }
             }
         }
 
-        // unknown/unbounded array
-        Ok(0)
+        // unknown/unbounded array, e.g. a flexible array member
+        Ok(ArrayBound::Constant(0))
     }
 
     fn extract_function_type(
@@ -608,15 +1687,7 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
         );
 
         // extract return type from DW_AT_type (none = void)
-        let return_type_id = if let Some(attr) = entry.attr(gimli::DW_AT_type)? {
-            if let AttributeValue::UnitRef(type_offset) = attr.value() {
-                Some(self.build_type_registry_entry(type_offset)?)
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let return_type_id = self.resolve_type_attr(entry)?;
 
         // extract parameters from children
         let (parameter_type_ids, is_variadic) = self.extract_function_parameters(offset)?;
@@ -651,10 +1722,7 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
             match entry.tag() {
                 gimli::DW_TAG_formal_parameter => {
                     // Extract parameter type
-                    if let Some(attr) = entry.attr(gimli::DW_AT_type)?
-                        && let AttributeValue::UnitRef(type_offset) = attr.value()
-                    {
-                        let param_type_id = self.build_type_registry_entry(type_offset)?;
+                    if let Some(param_type_id) = self.resolve_type_attr(entry)? {
                         parameter_type_ids.push(param_type_id);
                         log::trace!("{:>12} parameter type added", "function");
                     }
@@ -675,8 +1743,7 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
     }
 
     fn get_or_create_int_type(&mut self) -> Result<TypeId> {
-        let int_types = self.type_registry.get_by_name("int");
-        if let Some(int_type) = int_types.first() {
+        if let Some(int_type) = self.type_registry.get_base_by_name("int") {
             return Ok(int_type.id);
         }
 
@@ -687,22 +1754,1005 @@ impl<'dwarf, R: gimli::Reader> TypeResolver<'dwarf, R> {
                 name: "int".to_string(),
                 size: 4,
                 alignment: 4,
+                encoding: None,
             },
             pointer_depth: 0,
             is_const: false,
             is_volatile: false,
+            pointer_qualifiers: Vec::new(),
             dwarf_offset: None,
         };
 
         Ok(self.type_registry.register_type(int_type))
     }
+}
 
-    pub fn into_registry(self) -> TypeRegistry {
-        self.type_registry
+/// every unit header of `.debug_info` in section order, so the unit a
+/// `DW_FORM_ref_addr` lands in is a binary search, and the units parsed
+/// from them so far
+struct ForeignUnits<R: gimli::Reader> {
+    headers: Vec<UnitHeader<R>>,
+    units: HashMap<UnitId, Rc<Unit<R>>>,
+}
+
+impl<R: gimli::Reader> ForeignUnits<R> {
+    fn new(dwarf: &Dwarf<R>) -> Result<Self> {
+        let mut headers = Vec::new();
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            headers.push(header);
+        }
+        Ok(Self {
+            headers,
+            units: HashMap::new(),
+        })
     }
 
-    #[allow(dead_code)]
-    pub fn get_registry(&self) -> &TypeRegistry {
-        &self.type_registry
+    /// the unit containing `offset`, and the offset within it
+    fn find(&self, offset: DebugInfoOffset<R::Offset>) -> Option<(UnitId, UnitOffset<R::Offset>)> {
+        let start = |header: &UnitHeader<R>| unit_id(header.offset()).offset;
+        let after = self
+            .headers
+            .partition_point(|header| start(header) <= offset.0.into_u64());
+        let header = &self.headers[after.checked_sub(1)?];
+        let unit_offset = offset.to_unit_offset(header)?;
+        Some((unit_id(header.offset()), unit_offset))
+    }
+
+    /// the unit with header `id`, parsed on first use
+    fn unit(&mut self, dwarf: &Dwarf<R>, id: UnitId) -> Result<Rc<Unit<R>>> {
+        if let Some(unit) = self.units.get(&id) {
+            return Ok(Rc::clone(unit));
+        }
+        let header = self
+            .headers
+            .iter()
+            .find(|header| unit_id(header.offset()) == id)
+            .ok_or_else(|| anyhow!("no unit at {:#x}", id.offset))?;
+        let unit = Rc::new(dwarf.unit(header.clone())?);
+        self.units.insert(id, Rc::clone(&unit));
+        Ok(unit)
+    }
+}
+
+/// where a `DW_AT_type` reference points
+enum TypeRef<T: ReaderOffset> {
+    Local(UnitOffset<T>),
+    /// a `DW_FORM_ref_addr` into another unit
+    Foreign(DebugInfoOffset<T>),
+}
+
+/// a unit's id within the file it is read from: its offset in `.debug_info`,
+/// or in `.debug_types` for DWARF 4 type units
+pub(crate) fn unit_id<T: gimli::ReaderOffset>(offset: gimli::UnitSectionOffset<T>) -> UnitId {
+    let offset = match offset {
+        gimli::UnitSectionOffset::DebugInfoOffset(offset) => offset.0,
+        gimli::UnitSectionOffset::DebugTypesOffset(offset) => offset.0,
+    };
+    UnitId {
+        source: 0,
+        offset: offset.into_u64(),
+    }
+}
+
+/// length of an array dimension as described by its subrange
+enum ArrayBound {
+    Constant(usize),
+    /// bound is computed at runtime (VLAs), so there is no static length
+    Dynamic,
+}
+
+/// distance between consecutive elements of an array
+enum ArrayStride {
+    /// no stride given: the element size
+    Element,
+    Bytes(usize),
+    /// a stride that isn't a whole number of bytes
+    Bits(u64),
+}
+
+/// the value of a constant array bound in any constant form, including
+/// `DW_FORM_implicit_const` (read as `Sdata`, the value is in the
+/// abbreviation). `data*` forms carry no sign, so they are unsigned except
+/// for an all-ones `data4`/`data8`, which is how producers write -1.
+fn bound_value<R: gimli::Reader>(attr: &gimli::Attribute<R>) -> Option<i64> {
+    match attr.value() {
+        AttributeValue::Sdata(value) => Some(value),
+        AttributeValue::Data4(u32::MAX) | AttributeValue::Data8(u64::MAX) => Some(-1),
+        value => value
+            .udata_value()
+            .and_then(|value| i64::try_from(value).ok()),
+    }
+}
+
+/// true if a bound attribute refers to another DIE or a DWARF expression
+/// instead of holding a constant
+fn is_runtime_bound<R: gimli::Reader>(attr: &gimli::Attribute<R>) -> bool {
+    matches!(
+        attr.value(),
+        AttributeValue::UnitRef(_)
+            | AttributeValue::DebugInfoRef(_)
+            | AttributeValue::Exprloc(_)
+            | AttributeValue::Block(_)
+    )
+}
+
+/// true for a C, C++ or Objective-C source file, as opposed to a header
+fn is_source_file(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let extension = file_name
+        .rsplit_once('.')
+        .filter(|(stem, _)| !stem.is_empty())
+        .map(|(_, extension)| extension);
+    matches!(
+        extension,
+        Some("c" | "cc" | "cpp" | "cxx" | "c++" | "m" | "mm")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gimli::write::{self, DwarfUnit, EndianVec, Sections};
+    use gimli::{EndianSlice, LittleEndian};
+
+    /// a unit where `struct Widget` is declared, used through a pointer, and
+    /// only then defined by a DIE that refers back to the declaration
+    fn split_definition_sections() -> Sections<EndianVec<LittleEndian>> {
+        let mut dwarf = new_unit();
+        let root = dwarf.unit.root();
+
+        let int = dwarf.unit.add(root, gimli::DW_TAG_base_type);
+        let entry = dwarf.unit.get_mut(int);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"int".to_vec()),
+        );
+        entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(4));
+
+        let declaration = dwarf.unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = dwarf.unit.get_mut(declaration);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"Widget".to_vec()),
+        );
+        entry.set(gimli::DW_AT_declaration, write::AttributeValue::Flag(true));
+
+        let pointer = dwarf.unit.add(root, gimli::DW_TAG_pointer_type);
+        dwarf.unit.get_mut(pointer).set(
+            gimli::DW_AT_type,
+            write::AttributeValue::UnitRef(declaration),
+        );
+
+        let definition = dwarf.unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = dwarf.unit.get_mut(definition);
+        entry.set(
+            gimli::DW_AT_specification,
+            write::AttributeValue::UnitRef(declaration),
+        );
+        entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(8));
+        for (name, offset) in [("width", 0), ("height", 4)] {
+            let member = dwarf.unit.add(definition, gimli::DW_TAG_member);
+            let entry = dwarf.unit.get_mut(member);
+            entry.set(
+                gimli::DW_AT_name,
+                write::AttributeValue::String(name.as_bytes().to_vec()),
+            );
+            entry.set(gimli::DW_AT_type, write::AttributeValue::UnitRef(int));
+            entry.set(
+                gimli::DW_AT_data_member_location,
+                write::AttributeValue::Udata(offset),
+            );
+        }
+
+        write_sections(&mut dwarf)
+    }
+
+    fn new_unit() -> DwarfUnit {
+        DwarfUnit::new(gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        })
+    }
+
+    fn write_sections(dwarf: &mut DwarfUnit) -> Sections<EndianVec<LittleEndian>> {
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        sections
+    }
+
+    /// resolve the first DIE with `tag` in the written unit
+    fn resolve_first(
+        sections: &Sections<EndianVec<LittleEndian>>,
+        tag: gimli::DwTag,
+    ) -> (TypeRegistry, TypeId) {
+        resolve_first_in(
+            |id| sections.get(id).map_or(&[][..], |section| section.slice()),
+            tag,
+        )
+    }
+
+    /// resolve the first DIE with `tag` in the first unit of the sections
+    fn resolve_first_in<'a>(
+        section: impl Fn(gimli::SectionId) -> &'a [u8],
+        tag: gimli::DwTag,
+    ) -> (TypeRegistry, TypeId) {
+        let dwarf = Dwarf::load(|id| -> gimli::Result<_> {
+            Ok(EndianSlice::new(section(id), LittleEndian))
+        })
+        .unwrap();
+        let header = dwarf.units().next().unwrap().unwrap();
+        let unit = dwarf.unit(header).unwrap();
+
+        let mut entries = unit.entries();
+        let offset = loop {
+            let (_, entry) = entries.next_dfs().unwrap().expect("no entry with tag");
+            if entry.tag() == tag {
+                break entry.offset();
+            }
+        };
+
+        let mut resolver = TypeResolver::new(&dwarf, &unit);
+        let id = resolver.build_type_registry_entry(offset).unwrap();
+        (resolver.into_registry(), id)
+    }
+
+    #[test]
+    fn test_declaration_follows_specification_to_definition() {
+        let sections = split_definition_sections();
+        let (registry, pointer_id) = resolve_first(&sections, gimli::DW_TAG_pointer_type);
+
+        let pointer = registry.get_type(pointer_id).unwrap();
+        assert_eq!(pointer.pointer_depth, 1);
+        let BaseTypeKind::Struct {
+            name,
+            fields,
+            size,
+            is_opaque,
+            ..
+        } = &pointer.kind
+        else {
+            panic!("expected a struct, got {:?}", pointer.kind);
+        };
+        assert_eq!(name, "Widget");
+        assert!(!is_opaque);
+        assert_eq!(*size, 8);
+        let fields = fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(fields, [("width", 0), ("height", 4)]);
+    }
+
+    #[test]
+    fn test_foreign_units_find_the_unit_containing_an_offset() {
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        for name in [&b"int"[..], b"long"] {
+            let id = dwarf
+                .units
+                .add(write::Unit::new(encoding, write::LineProgram::none()));
+            let unit = dwarf.units.get_mut(id);
+            let root = unit.root();
+            let base = unit.add(root, gimli::DW_TAG_base_type);
+            unit.get_mut(base).set(
+                gimli::DW_AT_name,
+                write::AttributeValue::String(name.to_vec()),
+            );
+        }
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        let dwarf = Dwarf::load(|id| -> gimli::Result<_> {
+            Ok(EndianSlice::new(
+                sections.get(id).map_or(&[][..], |section| section.slice()),
+                LittleEndian,
+            ))
+        })
+        .unwrap();
+
+        let mut foreign_units = ForeignUnits::new(&dwarf).unwrap();
+        assert_eq!(foreign_units.headers.len(), 2);
+        for header in foreign_units.headers.clone() {
+            let id = unit_id(header.offset());
+            let first = header.offset().as_debug_info_offset().unwrap().0 + 11;
+            let last = first + header.unit_length() - 8;
+            for offset in [first, last] {
+                let (found, _) = foreign_units.find(DebugInfoOffset(offset)).unwrap();
+                assert_eq!(found, id, "offset {:#x}", offset);
+            }
+            // the header itself has no entries
+            assert!(foreign_units.find(DebugInfoOffset(first - 1)).is_none());
+
+            let unit = foreign_units.unit(&dwarf, id).unwrap();
+            assert!(Rc::ptr_eq(&unit, &foreign_units.unit(&dwarf, id).unwrap()));
+        }
+        let end = sections
+            .get(gimli::SectionId::DebugInfo)
+            .unwrap()
+            .slice()
+            .len();
+        assert!(foreign_units.find(DebugInfoOffset(end)).is_none());
+    }
+
+    #[test]
+    fn test_typedef_chain_across_units() {
+        // unit 0: `typedef struct Widget Widget_t;` where `struct Widget` is
+        // defined in unit 1 and referenced with DW_FORM_ref_addr
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let user = dwarf
+            .units
+            .add(write::Unit::new(encoding, write::LineProgram::none()));
+        let owner = dwarf
+            .units
+            .add(write::Unit::new(encoding, write::LineProgram::none()));
+
+        let unit = dwarf.units.get_mut(owner);
+        let root = unit.root();
+        let int = unit.add(root, gimli::DW_TAG_base_type);
+        let entry = unit.get_mut(int);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"int".to_vec()),
+        );
+        entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(4));
+        let widget = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(widget);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"Widget".to_vec()),
+        );
+        entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(4));
+        let member = unit.add(widget, gimli::DW_TAG_member);
+        let entry = unit.get_mut(member);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"width".to_vec()),
+        );
+        entry.set(gimli::DW_AT_type, write::AttributeValue::UnitRef(int));
+        entry.set(
+            gimli::DW_AT_data_member_location,
+            write::AttributeValue::Udata(0),
+        );
+
+        let unit = dwarf.units.get_mut(user);
+        let root = unit.root();
+        let typedef = unit.add(root, gimli::DW_TAG_typedef);
+        let entry = unit.get_mut(typedef);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"Widget_t".to_vec()),
+        );
+        entry.set(
+            gimli::DW_AT_type,
+            write::AttributeValue::DebugInfoRef(write::Reference::Entry(owner, widget)),
+        );
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let (registry, typedef_id) = resolve_first(&sections, gimli::DW_TAG_typedef);
+        let typedef = registry.get_type(typedef_id).unwrap();
+        let BaseTypeKind::Typedef {
+            name,
+            aliased_type_id,
+        } = &typedef.kind
+        else {
+            panic!("expected a typedef, got {:?}", typedef.kind);
+        };
+        assert_eq!(name, "Widget_t");
+        let widget = registry.get_type(*aliased_type_id).unwrap();
+        let BaseTypeKind::Struct {
+            name,
+            fields,
+            is_opaque,
+            ..
+        } = &widget.kind
+        else {
+            panic!("expected a struct, got {:?}", widget.kind);
+        };
+        assert_eq!(name, "Widget");
+        assert!(!is_opaque);
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].name, "width");
+    }
+
+    /// a unit with `struct Node { int value; struct Node* next; }` and
+    /// `struct A { struct B* b; }`, `struct B { struct A* a; }`: the structs
+    /// and pointers in DIE order are `Node`, `Node*`, `A`, `B`, `A*`, `B*`
+    fn cyclic_sections() -> Sections<EndianVec<LittleEndian>> {
+        let mut dwarf = new_unit();
+        let root = dwarf.unit.root();
+
+        let int = dwarf.unit.add(root, gimli::DW_TAG_base_type);
+        let entry = dwarf.unit.get_mut(int);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"int".to_vec()),
+        );
+        entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(4));
+
+        let add_struct = |dwarf: &mut DwarfUnit, name: &str, size: u64| {
+            let id = dwarf.unit.add(root, gimli::DW_TAG_structure_type);
+            let entry = dwarf.unit.get_mut(id);
+            entry.set(
+                gimli::DW_AT_name,
+                write::AttributeValue::String(name.as_bytes().to_vec()),
+            );
+            entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(size));
+            id
+        };
+        let node = add_struct(&mut dwarf, "Node", 16);
+        let node_pointer = dwarf.unit.add(root, gimli::DW_TAG_pointer_type);
+        let a = add_struct(&mut dwarf, "A", 8);
+        let b = add_struct(&mut dwarf, "B", 8);
+        let a_pointer = dwarf.unit.add(root, gimli::DW_TAG_pointer_type);
+        let b_pointer = dwarf.unit.add(root, gimli::DW_TAG_pointer_type);
+        for (pointer, pointee) in [(node_pointer, node), (a_pointer, a), (b_pointer, b)] {
+            let entry = dwarf.unit.get_mut(pointer);
+            entry.set(gimli::DW_AT_type, write::AttributeValue::UnitRef(pointee));
+            entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(8));
+        }
+
+        let members = [
+            (node, "value", int, 0),
+            (node, "next", node_pointer, 8),
+            (a, "b", b_pointer, 0),
+            (b, "a", a_pointer, 0),
+        ];
+        for (parent, name, type_, offset) in members {
+            let member = dwarf.unit.add(parent, gimli::DW_TAG_member);
+            let entry = dwarf.unit.get_mut(member);
+            entry.set(
+                gimli::DW_AT_name,
+                write::AttributeValue::String(name.as_bytes().to_vec()),
+            );
+            entry.set(gimli::DW_AT_type, write::AttributeValue::UnitRef(type_));
+            entry.set(
+                gimli::DW_AT_data_member_location,
+                write::AttributeValue::Udata(offset),
+            );
+        }
+
+        write_sections(&mut dwarf)
+    }
+
+    /// resolve the structs and pointers of the unit with one resolver, by
+    /// their positions in DIE order as listed in `order`; their ids in DIE
+    /// order
+    fn resolve_in_order(
+        sections: &Sections<EndianVec<LittleEndian>>,
+        order: &[usize],
+    ) -> (TypeRegistry, Vec<TypeId>) {
+        let dwarf = Dwarf::load(|id| -> gimli::Result<_> {
+            Ok(EndianSlice::new(
+                sections.get(id).map_or(&[][..], |section| section.slice()),
+                LittleEndian,
+            ))
+        })
+        .unwrap();
+        let header = dwarf.units().next().unwrap().unwrap();
+        let unit = dwarf.unit(header).unwrap();
+
+        let mut offsets = Vec::new();
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            if matches!(
+                entry.tag(),
+                gimli::DW_TAG_structure_type | gimli::DW_TAG_pointer_type
+            ) {
+                offsets.push(entry.offset());
+            }
+        }
+
+        let mut resolver = TypeResolver::new(&dwarf, &unit);
+        let mut ids = vec![TypeId(0); offsets.len()];
+        for &position in order {
+            ids[position] = resolver
+                .build_type_registry_entry(offsets[position])
+                .unwrap();
+        }
+        (resolver.into_registry(), ids)
+    }
+
+    #[test]
+    fn test_cycles_get_the_same_ids_whichever_type_is_resolved_first() {
+        let sections = cyclic_sections();
+        let (mut registry, ids) = resolve_in_order(&sections, &[0, 1, 2, 3, 4, 5]);
+        let (_, reversed) = resolve_in_order(&sections, &[5, 4, 3, 2, 1, 0]);
+        let (_, pointers_first) = resolve_in_order(&sections, &[1, 4, 5, 0, 2, 3]);
+        assert_eq!(ids, reversed);
+        assert_eq!(ids, pointers_first);
+
+        // `next` is the pointer to `Node` itself, not to a declaration
+        let node = registry.get_type(ids[0]).unwrap().clone();
+        let BaseTypeKind::Struct {
+            fields, is_opaque, ..
+        } = &node.kind
+        else {
+            panic!("expected a struct, got {:?}", node.kind);
+        };
+        assert!(!is_opaque);
+        assert_eq!(fields[1].type_id, ids[1]);
+        let node_pointer = registry.get_type(ids[1]).unwrap();
+        assert_eq!(node_pointer.kind, node.kind);
+        assert_eq!(node_pointer.unqualified_id(), node.id);
+
+        for (aggregate, pointer) in [(ids[2], ids[5]), (ids[3], ids[4])] {
+            let BaseTypeKind::Struct { fields, .. } = &registry.get_type(aggregate).unwrap().kind
+            else {
+                panic!("expected a struct");
+            };
+            assert_eq!(fields[0].type_id, pointer);
+        }
+
+        // a pointer registered once the cycle is closed gets the same id
+        assert_eq!(registry.register_type(Type::pointer_to(node)), ids[1]);
+    }
+
+    /// the length of an `int` array whose subrange has `attributes`
+    fn array_count(attributes: &[(gimli::DwAt, write::AttributeValue)]) -> usize {
+        let mut dwarf = new_unit();
+        let root = dwarf.unit.root();
+
+        let int = dwarf.unit.add(root, gimli::DW_TAG_base_type);
+        let entry = dwarf.unit.get_mut(int);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"int".to_vec()),
+        );
+        entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(4));
+        let array = dwarf.unit.add(root, gimli::DW_TAG_array_type);
+        dwarf
+            .unit
+            .get_mut(array)
+            .set(gimli::DW_AT_type, write::AttributeValue::UnitRef(int));
+        let subrange = dwarf.unit.add(array, gimli::DW_TAG_subrange_type);
+        for (name, value) in attributes {
+            dwarf.unit.get_mut(subrange).set(*name, value.clone());
+        }
+
+        let (registry, id) = resolve_first(&write_sections(&mut dwarf), gimli::DW_TAG_array_type);
+        match registry.get_type(id).unwrap().kind {
+            BaseTypeKind::Array { count, .. } => count,
+            ref kind => panic!("expected an array, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_array_bounds_in_constant_forms() {
+        use write::AttributeValue::{Data1, Data4, Data8, Sdata, Udata};
+
+        assert_eq!(array_count(&[(gimli::DW_AT_count, Udata(16))]), 16);
+        assert_eq!(array_count(&[(gimli::DW_AT_count, Data1(200))]), 200);
+        assert_eq!(array_count(&[(gimli::DW_AT_count, Sdata(7))]), 7);
+        assert_eq!(array_count(&[(gimli::DW_AT_upper_bound, Sdata(9))]), 10);
+        // `char a[256]`: 255 fits a data1, and isn't -1
+        assert_eq!(array_count(&[(gimli::DW_AT_upper_bound, Data1(255))]), 256);
+
+        // zero-length arrays: upper bound -1, however it is written
+        assert_eq!(array_count(&[(gimli::DW_AT_upper_bound, Sdata(-1))]), 0);
+        assert_eq!(
+            array_count(&[(gimli::DW_AT_upper_bound, Data4(u32::MAX))]),
+            0
+        );
+        assert_eq!(
+            array_count(&[(gimli::DW_AT_upper_bound, Data8(u64::MAX))]),
+            0
+        );
+        assert_eq!(array_count(&[(gimli::DW_AT_count, Sdata(-3))]), 0);
+
+        assert_eq!(
+            array_count(&[
+                (gimli::DW_AT_lower_bound, Sdata(1)),
+                (gimli::DW_AT_upper_bound, Sdata(4)),
+            ]),
+            4
+        );
+    }
+
+    /// `int[4]` whose elements are `stride` (DW_AT_byte_stride or
+    /// DW_AT_bit_stride) apart
+    fn strided_array(stride: gimli::DwAt, value: u64) -> BaseTypeKind {
+        let mut dwarf = new_unit();
+        let root = dwarf.unit.root();
+
+        let int = dwarf.unit.add(root, gimli::DW_TAG_base_type);
+        let entry = dwarf.unit.get_mut(int);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"int".to_vec()),
+        );
+        entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(4));
+        let array = dwarf.unit.add(root, gimli::DW_TAG_array_type);
+        let entry = dwarf.unit.get_mut(array);
+        entry.set(gimli::DW_AT_type, write::AttributeValue::UnitRef(int));
+        entry.set(stride, write::AttributeValue::Udata(value));
+        let subrange = dwarf.unit.add(array, gimli::DW_TAG_subrange_type);
+        dwarf
+            .unit
+            .get_mut(subrange)
+            .set(gimli::DW_AT_count, write::AttributeValue::Udata(4));
+
+        let (registry, id) = resolve_first(&write_sections(&mut dwarf), gimli::DW_TAG_array_type);
+        registry.get_type(id).unwrap().kind.clone()
+    }
+
+    #[test]
+    fn test_array_strides() {
+        let strided = |stride, value| match strided_array(stride, value) {
+            BaseTypeKind::Array {
+                count,
+                size,
+                stride,
+                is_dynamic,
+                ..
+            } => (count, size, stride, is_dynamic),
+            kind => panic!("expected an array, got {:?}", kind),
+        };
+
+        assert_eq!(strided(gimli::DW_AT_byte_stride, 8), (4, 32, 8, false));
+        assert_eq!(strided(gimli::DW_AT_bit_stride, 64), (4, 32, 8, false));
+        // elements 3 bits apart have no byte layout
+        assert_eq!(strided(gimli::DW_AT_bit_stride, 3), (0, 0, 4, true));
+    }
+
+    #[test]
+    fn test_array_count_as_implicit_const() {
+        // gimli::write can't emit DW_FORM_implicit_const, so assemble the
+        // unit by hand: `int[16]` whose DW_AT_count lives in the abbreviation
+        let abbrev = [
+            1, 0x11, 1, 0, 0, // compile unit, children
+            2, 0x24, 0, 0x03, 0x08, 0x0b, 0x0b, 0, 0, // base type: name, byte size
+            3, 0x01, 1, 0x49, 0x13, 0, 0, // array type: type (ref4), children
+            4, 0x21, 0, 0x37, 0x21, 16, 0, 0, // subrange: count implicit_const 16
+            0,
+        ];
+        let mut info = vec![0, 0, 0, 0, 5, 0, gimli::DW_UT_compile.0, 8, 0, 0, 0, 0];
+        info.push(1);
+        let int_offset = info.len() as u32;
+        info.extend_from_slice(&[2, b'i', b'n', b't', 0, 4]);
+        info.push(3);
+        info.extend_from_slice(&int_offset.to_le_bytes());
+        info.extend_from_slice(&[4, 0, 0]);
+        let length = (info.len() - 4) as u32;
+        info[..4].copy_from_slice(&length.to_le_bytes());
+
+        let (registry, id) = resolve_first_in(
+            |id| match id {
+                gimli::SectionId::DebugAbbrev => &abbrev[..],
+                gimli::SectionId::DebugInfo => &info[..],
+                _ => &[],
+            },
+            gimli::DW_TAG_array_type,
+        );
+        assert!(matches!(
+            registry.get_type(id).unwrap().kind,
+            BaseTypeKind::Array { count: 16, .. }
+        ));
+    }
+
+    #[test]
+    fn test_normalized_integer_names_match_across_compilers() {
+        // gcc and clang name the same 8-byte unsigned base type differently
+        let resolve = |name: &str, normalize: bool| {
+            let mut dwarf = new_unit();
+            let root = dwarf.unit.root();
+            let base = dwarf.unit.add(root, gimli::DW_TAG_base_type);
+            let entry = dwarf.unit.get_mut(base);
+            entry.set(
+                gimli::DW_AT_name,
+                write::AttributeValue::String(name.as_bytes().to_vec()),
+            );
+            entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(8));
+            entry.set(
+                gimli::DW_AT_encoding,
+                write::AttributeValue::Encoding(gimli::DW_ATE_unsigned),
+            );
+            let sections = write_sections(&mut dwarf);
+
+            let dwarf = Dwarf::load(|id| -> gimli::Result<_> {
+                let data = sections.get(id).map_or(&[][..], |section| section.slice());
+                Ok(EndianSlice::new(data, LittleEndian))
+            })
+            .unwrap();
+            let unit = dwarf.unit(dwarf.units().next().unwrap().unwrap()).unwrap();
+            let mut entries = unit.entries();
+            let offset = loop {
+                let (_, entry) = entries.next_dfs().unwrap().expect("no base type");
+                if entry.tag() == gimli::DW_TAG_base_type {
+                    break entry.offset();
+                }
+            };
+
+            let mut resolver =
+                TypeResolver::new(&dwarf, &unit).normalize_primitive_names(normalize);
+            let id = resolver.build_type_registry_entry(offset).unwrap();
+            let registry = resolver.into_registry();
+            (id, registry.get_type(id).unwrap().get_name())
+        };
+
+        let gcc = resolve("long unsigned int", true);
+        assert_eq!(gcc.1, "unsigned long");
+        assert_eq!(gcc, resolve("unsigned long", true));
+
+        // as DWARF spells them otherwise
+        assert_eq!(resolve("long unsigned int", false).1, "long unsigned int");
+        assert_ne!(
+            resolve("long unsigned int", false).0,
+            resolve("unsigned long", false).0
+        );
+    }
+
+    #[test]
+    fn test_primitive_signedness_from_encoding() {
+        // plain `char` is unsigned on arm Linux, which only the encoding says
+        let mut dwarf = new_unit();
+        let root = dwarf.unit.root();
+        let base = dwarf.unit.add(root, gimli::DW_TAG_base_type);
+        let entry = dwarf.unit.get_mut(base);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"char".to_vec()),
+        );
+        entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(1));
+        entry.set(
+            gimli::DW_AT_encoding,
+            write::AttributeValue::Encoding(gimli::DW_ATE_unsigned_char),
+        );
+        let sections = write_sections(&mut dwarf);
+        let (mut registry, char_id) = resolve_first(&sections, gimli::DW_TAG_base_type);
+
+        let resolved = registry.resolve_primitive(char_id).unwrap();
+        assert!(!resolved.is_signed);
+        assert!(!resolved.is_float);
+
+        // without an encoding the name decides
+        let guessed_id = registry.register_type(Type::primitive("char", 1));
+        assert_ne!(guessed_id, char_id);
+        assert!(registry.resolve_primitive(guessed_id).unwrap().is_signed);
+    }
+
+    #[test]
+    fn test_pointer_to_member_sizes() {
+        // `int Widget::*` is an offset, `int (Widget::*)()` a (pointer,
+        // adjustment) pair, unless DW_AT_byte_size says otherwise
+        for (member_function, byte_size, expected) in
+            [(false, None, 8), (true, None, 16), (true, Some(8), 8)]
+        {
+            let mut dwarf = new_unit();
+            let root = dwarf.unit.root();
+
+            let int = dwarf.unit.add(root, gimli::DW_TAG_base_type);
+            let entry = dwarf.unit.get_mut(int);
+            entry.set(
+                gimli::DW_AT_name,
+                write::AttributeValue::String(b"int".to_vec()),
+            );
+            entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(4));
+
+            let widget = dwarf.unit.add(root, gimli::DW_TAG_structure_type);
+            let entry = dwarf.unit.get_mut(widget);
+            entry.set(
+                gimli::DW_AT_name,
+                write::AttributeValue::String(b"Widget".to_vec()),
+            );
+            entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(4));
+
+            let pointee = if member_function {
+                let method = dwarf.unit.add(root, gimli::DW_TAG_subroutine_type);
+                dwarf
+                    .unit
+                    .get_mut(method)
+                    .set(gimli::DW_AT_type, write::AttributeValue::UnitRef(int));
+                method
+            } else {
+                int
+            };
+
+            let member_pointer = dwarf.unit.add(root, gimli::DW_TAG_ptr_to_member_type);
+            let entry = dwarf.unit.get_mut(member_pointer);
+            entry.set(gimli::DW_AT_type, write::AttributeValue::UnitRef(pointee));
+            entry.set(
+                gimli::DW_AT_containing_type,
+                write::AttributeValue::UnitRef(widget),
+            );
+            if let Some(byte_size) = byte_size {
+                entry.set(
+                    gimli::DW_AT_byte_size,
+                    write::AttributeValue::Udata(byte_size),
+                );
+            }
+
+            let (registry, id) = resolve_first(
+                &write_sections(&mut dwarf),
+                gimli::DW_TAG_ptr_to_member_type,
+            );
+            let member_pointer = registry.get_type(id).unwrap();
+            assert_eq!(member_pointer.pointer_depth, 0);
+            let BaseTypeKind::Primitive {
+                name,
+                size,
+                alignment,
+                ..
+            } = &member_pointer.kind
+            else {
+                panic!("expected a primitive, got {:?}", member_pointer.kind);
+            };
+            assert_eq!(name, POINTER_TO_MEMBER);
+            assert_eq!(*size, expected, "member function: {}", member_function);
+            assert_eq!(*alignment, 8);
+        }
+    }
+
+    #[test]
+    fn test_enum_size_from_underlying_type() {
+        // `enum Mode : unsigned char { ... }` without a DW_AT_byte_size,
+        // inside `struct Packet { enum Mode mode; char tag; }`
+        let mut dwarf = new_unit();
+        let root = dwarf.unit.root();
+
+        let uchar = dwarf.unit.add(root, gimli::DW_TAG_base_type);
+        let entry = dwarf.unit.get_mut(uchar);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"unsigned char".to_vec()),
+        );
+        entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(1));
+
+        let mode = dwarf.unit.add(root, gimli::DW_TAG_enumeration_type);
+        let entry = dwarf.unit.get_mut(mode);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"Mode".to_vec()),
+        );
+        entry.set(gimli::DW_AT_type, write::AttributeValue::UnitRef(uchar));
+        let variant = dwarf.unit.add(mode, gimli::DW_TAG_enumerator);
+        let entry = dwarf.unit.get_mut(variant);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"MODE_ON".to_vec()),
+        );
+        entry.set(gimli::DW_AT_const_value, write::AttributeValue::Udata(1));
+
+        let packet = dwarf.unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = dwarf.unit.get_mut(packet);
+        entry.set(
+            gimli::DW_AT_name,
+            write::AttributeValue::String(b"Packet".to_vec()),
+        );
+        entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(2));
+        for (name, type_, offset) in [("mode", mode, 0), ("tag", uchar, 1)] {
+            let member = dwarf.unit.add(packet, gimli::DW_TAG_member);
+            let entry = dwarf.unit.get_mut(member);
+            entry.set(
+                gimli::DW_AT_name,
+                write::AttributeValue::String(name.as_bytes().to_vec()),
+            );
+            entry.set(gimli::DW_AT_type, write::AttributeValue::UnitRef(type_));
+            entry.set(
+                gimli::DW_AT_data_member_location,
+                write::AttributeValue::Udata(offset),
+            );
+        }
+
+        let (registry, packet_id) =
+            resolve_first(&write_sections(&mut dwarf), gimli::DW_TAG_structure_type);
+        let packet = registry.get_type(packet_id).unwrap();
+        let BaseTypeKind::Struct {
+            fields, alignment, ..
+        } = &packet.kind
+        else {
+            panic!("expected a struct, got {:?}", packet.kind);
+        };
+        assert_eq!(*alignment, 1);
+        assert_eq!(fields[0].size, 1);
+
+        let mode = registry.get_type(fields[0].type_id).unwrap();
+        assert!(matches!(mode.kind, BaseTypeKind::Enum { size: 1, .. }));
+        assert_eq!(registry.size_of(mode.id, 8), Some(1));
+    }
+
+    #[test]
+    fn test_variant_part_is_a_discriminated_union() {
+        // what rustc emits for `enum Shape { Circle(f32), Square(u32) }`
+        let mut dwarf = new_unit();
+        let root = dwarf.unit.root();
+
+        let mut base_type = |name: &str, size, encoding| {
+            let id = dwarf.unit.add(root, gimli::DW_TAG_base_type);
+            let entry = dwarf.unit.get_mut(id);
+            entry.set(
+                gimli::DW_AT_name,
+                write::AttributeValue::String(name.as_bytes().to_vec()),
+            );
+            entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(size));
+            entry.set(
+                gimli::DW_AT_encoding,
+                write::AttributeValue::Encoding(encoding),
+            );
+            id
+        };
+        let u8_type = base_type("u8", 1, gimli::DW_ATE_unsigned);
+        let f32_type = base_type("f32", 4, gimli::DW_ATE_float);
+        let u32_type = base_type("u32", 4, gimli::DW_ATE_unsigned);
+
+        let add_member = |dwarf: &mut DwarfUnit, parent, name: &str, type_, offset| {
+            let member = dwarf.unit.add(parent, gimli::DW_TAG_member);
+            let entry = dwarf.unit.get_mut(member);
+            entry.set(
+                gimli::DW_AT_name,
+                write::AttributeValue::String(name.as_bytes().to_vec()),
+            );
+            entry.set(gimli::DW_AT_type, write::AttributeValue::UnitRef(type_));
+            entry.set(
+                gimli::DW_AT_data_member_location,
+                write::AttributeValue::Udata(offset),
+            );
+            member
+        };
+        let add_struct = |dwarf: &mut DwarfUnit, parent, name: &str| {
+            let id = dwarf.unit.add(parent, gimli::DW_TAG_structure_type);
+            let entry = dwarf.unit.get_mut(id);
+            entry.set(
+                gimli::DW_AT_name,
+                write::AttributeValue::String(name.as_bytes().to_vec()),
+            );
+            entry.set(gimli::DW_AT_byte_size, write::AttributeValue::Udata(8));
+            id
+        };
+
+        let shape = add_struct(&mut dwarf, root, "Shape");
+        let variant_part = dwarf.unit.add(shape, gimli::DW_TAG_variant_part);
+        let tag = add_member(&mut dwarf, variant_part, "tag", u8_type, 0);
+        dwarf
+            .unit
+            .get_mut(variant_part)
+            .set(gimli::DW_AT_discr, write::AttributeValue::UnitRef(tag));
+        for (value, name, field_type) in [(0, "Circle", f32_type), (1, "Square", u32_type)] {
+            let variant_struct = add_struct(&mut dwarf, shape, name);
+            add_member(&mut dwarf, variant_struct, "__0", field_type, 4);
+
+            let variant = dwarf.unit.add(variant_part, gimli::DW_TAG_variant);
+            dwarf.unit.get_mut(variant).set(
+                gimli::DW_AT_discr_value,
+                write::AttributeValue::Data1(value),
+            );
+            add_member(&mut dwarf, variant, name, variant_struct, 0);
+        }
+
+        let (registry, shape_id) =
+            resolve_first(&write_sections(&mut dwarf), gimli::DW_TAG_structure_type);
+        let shape = registry.get_type(shape_id).unwrap();
+        let BaseTypeKind::Union {
+            name,
+            variants,
+            size,
+            alignment,
+            discriminant: Some(discriminant),
+        } = &shape.kind
+        else {
+            panic!("expected a discriminated union, got {:?}", shape.kind);
+        };
+        assert_eq!(name, "Shape");
+        assert_eq!((*size, *alignment), (8, 4));
+        assert_eq!(discriminant.name, "tag");
+        assert_eq!(discriminant.offset, 0);
+        assert_eq!(registry.size_of(discriminant.type_id, 8), Some(1));
+
+        let variants: Vec<_> = variants
+            .iter()
+            .map(|v| (v.name.as_str(), v.discriminant_value))
+            .collect();
+        assert_eq!(variants, [("Circle", Some(0)), ("Square", Some(1))]);
     }
 }
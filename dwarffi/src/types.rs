@@ -1,10 +1,21 @@
-use crate::type_registry::{TypeId, TypeRegistry};
+use crate::collections::HashSet;
+use crate::prelude::*;
+use crate::type_registry::{BaseTypeKind, TypeId, TypeRegistry};
+use crate::validation::UnresolvedType;
+use serde::Serialize;
 
 /// c function parameters have a name and a type
 #[derive(Debug, Clone)]
 pub struct Parameter {
     pub name: String,
     pub type_id: TypeId,
+    /// offset of its `DW_TAG_formal_parameter` within the compilation unit.
+    /// for a parameter optimized out of an out-of-line copy of an inline
+    /// function, the abstract instance's.
+    pub dwarf_offset: Option<u64>,
+    /// `DW_AT_artificial`: put there by the compiler rather than declared,
+    /// like the implicit `this` of a C++ method
+    pub is_artificial: bool,
 }
 
 /// struct to hold a complete function signature
@@ -14,34 +25,184 @@ pub struct FunctionSignature {
     pub return_type_id: TypeId,
     pub parameters: Vec<Parameter>,
     pub is_variadic: bool,
+    /// declared with a prototype. an unprototyped C function, `int f()`,
+    /// takes unspecified arguments, unlike `int f(void)`
+    pub is_prototyped: bool,
     pub is_exported: bool,
+    /// `DW_AT_external`: global linkage, as opposed to `static`
+    pub is_external: bool,
+    /// virtual address of the function's entry point: `DW_AT_low_pc`, or the
+    /// symbol value when DWARF doesn't record one
+    pub address: Option<u64>,
+    /// declared `_Noreturn` / `__attribute__((noreturn))`
+    pub is_noreturn: bool,
+    pub inline_kind: InlineKind,
+    /// `DW_AT_virtuality`: a C++ virtual method, called through a vtable
+    pub is_virtual: bool,
+    /// `DW_AT_description`, for the doc comment of generated bindings
+    pub description: Option<String>,
+}
+
+/// `DW_AT_inline`: whether the function was declared `inline` and whether
+/// the compiler inlined it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InlineKind {
+    /// no `DW_AT_inline`: an ordinary function
+    #[default]
+    NotInlined,
+    /// not declared inline, but inlined by the compiler
+    Inlined,
+    /// declared inline, but not inlined
+    DeclaredNotInlined,
+    /// declared inline and inlined
+    DeclaredInlined,
+}
+
+impl Parameter {
+    /// a declared parameter, with no DIE behind it
+    pub fn new(name: &str, type_id: TypeId) -> Self {
+        Self {
+            name: name.to_string(),
+            type_id,
+            dwarf_offset: None,
+            is_artificial: false,
+        }
+    }
+
+    /// C spelling of the parameter's type, e.g. `const char*`, or `void` if
+    /// the type isn't registered
+    pub fn type_c_string(&self, registry: &TypeRegistry) -> String {
+        type_string(registry, self.type_id)
+    }
 }
 
 impl FunctionSignature {
-    /// format the function signature as a C-style declaration
+    /// an ordinary exported C function, prototyped and not variadic, with no
+    /// address or description
+    pub fn new(name: &str, return_type_id: TypeId, parameters: Vec<Parameter>) -> Self {
+        Self {
+            name: name.to_string(),
+            return_type_id,
+            parameters,
+            is_variadic: false,
+            is_prototyped: true,
+            is_exported: true,
+            is_external: true,
+            address: None,
+            is_noreturn: false,
+            inline_kind: InlineKind::NotInlined,
+            description: None,
+            is_virtual: false,
+        }
+    }
+
+    /// C spelling of the return type, or `void` if the type isn't registered
+    pub fn return_c_string(&self, registry: &TypeRegistry) -> String {
+        type_string(registry, self.return_type_id)
+    }
+
+    /// C spelling of each parameter's type, in order, as
+    /// [`Parameter::type_c_string`] gives it
+    ///
+    /// ```
+    /// # use dwarffi::{FunctionSignature, Parameter, Type, TypeRegistry};
+    /// # let mut registry = TypeRegistry::new();
+    /// # let string = registry.register_type(Type::pointer_to(Type::primitive("char", 1).with_const()));
+    /// # let buffer = registry.register_type(Type::pointer_to(Type::primitive("char", 1)));
+    /// # let params = vec![Parameter::new("dst", buffer), Parameter::new("src", string)];
+    /// # let copy = FunctionSignature::new("copy", buffer, params);
+    /// // `char* copy(char* dst, const char* src)`
+    /// assert_eq!(copy.return_c_string(&registry), "char*");
+    /// assert_eq!(copy.parameter_c_strings(&registry), ["char*", "const char*"]);
+    /// assert_eq!(copy.parameters[1].type_c_string(&registry), "const char*");
+    /// ```
+    pub fn parameter_c_strings(&self, registry: &TypeRegistry) -> Vec<String> {
+        self.parameters
+            .iter()
+            .map(|p| p.type_c_string(registry))
+            .collect()
+    }
+
+    /// every type this function's return and parameter types transitively
+    /// reference
+    pub fn reachable_types(&self, registry: &TypeRegistry) -> HashSet<TypeId> {
+        let roots: Vec<TypeId> = core::iter::once(self.return_type_id)
+            .chain(self.parameters.iter().map(|p| p.type_id))
+            .collect();
+        registry.reachable_types(&roots)
+    }
+
+    /// the types this function uses that could not be fully resolved; see
+    /// [`TypeRegistry::unresolved_types`]
+    pub fn unresolved_types(&self, registry: &TypeRegistry) -> Vec<UnresolvedType> {
+        let roots: Vec<TypeId> = core::iter::once(self.return_type_id)
+            .chain(self.parameters.iter().map(|p| p.type_id))
+            .collect();
+        registry.unresolved_types(&roots)
+    }
+
+    /// identity of the function's ABI: name, return type and parameter types,
+    /// e.g. `int(printf)(const char*,...)`. parameter names are left out so
+    /// renaming a parameter doesn't change the key.
+    pub fn abi_key(&self, registry: &TypeRegistry) -> String {
+        let mut params = self.parameter_c_strings(registry);
+        if self.is_variadic {
+            params.push("...".to_string());
+        }
+
+        format!(
+            "{}({})({})",
+            self.return_c_string(registry),
+            self.name,
+            params.join(",")
+        )
+    }
+
+    /// format the function signature as a C-style declaration. no parameters
+    /// is `(void)`, or `()` for a function without a prototype
     pub fn to_string(&self, registry: &TypeRegistry) -> String {
+        self.to_string_with_void_param(registry, true)
+    }
+
+    /// format the function signature as [`Self::to_string`] does, but with
+    /// `void_param` false an empty parameter list is always `()`
+    pub fn to_string_with_void_param(&self, registry: &TypeRegistry, void_param: bool) -> String {
         // Resolve return type
-        let return_type_str = registry
-            .get_type(self.return_type_id)
-            .map(|t| t.to_c_string(registry))
-            .unwrap_or_else(|| "void".to_string());
+        let return_type_str = self.return_c_string(registry);
 
         let params = if self.parameters.is_empty() {
-            "void".to_string()
+            // only variadic arguments: `f(...)`, not `f(void)`
+            if self.is_variadic {
+                "...".to_string()
+            } else if void_param && self.is_prototyped {
+                "void".to_string()
+            } else {
+                String::new()
+            }
         } else {
             let param_strings: Vec<String> = self
                 .parameters
                 .iter()
-                .map(|p| {
-                    let type_str = registry
-                        .get_type(p.type_id)
-                        .map(|t| t.to_c_string(registry))
-                        .unwrap_or_else(|| "void".to_string());
-
-                    if p.name.is_empty() {
-                        type_str
-                    } else {
-                        format!("{} {}", type_str, p.name)
+                .map(|p| match registry.get_type(p.type_id) {
+                    // function pointers and arrays wrap the name:
+                    // `int (*op)(int, int)`, `int (*arr)[5]`
+                    Some(t)
+                        if matches!(
+                            t.kind,
+                            BaseTypeKind::Function { .. } | BaseTypeKind::Array { .. }
+                        ) =>
+                    {
+                        t.display_declaration(registry, &p.name)
+                    }
+                    _ => {
+                        let type_str = p.type_c_string(registry);
+
+                        if p.name.is_empty() {
+                            type_str
+                        } else {
+                            format!("{} {}", type_str, p.name)
+                        }
                     }
                 })
                 .collect();
@@ -57,85 +218,42 @@ impl FunctionSignature {
     }
 }
 
+/// C spelling of a type, or `void` if it isn't registered
+fn type_string(registry: &TypeRegistry, type_id: TypeId) -> String {
+    registry
+        .get_type(type_id)
+        .map(|t| t.to_c_string(registry))
+        .unwrap_or_else(|| "void".to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::type_registry::{BaseTypeKind, Type};
+    use crate::type_registry::Type;
 
     fn create_test_registry() -> TypeRegistry {
         let mut registry = TypeRegistry::new();
 
         // Register void
-        registry.register_type(Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "void".to_string(),
-                size: 0,
-                alignment: 1,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        });
+        registry.register_type(Type::primitive("void", 0));
 
         // Register int
-        registry.register_type(Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "int".to_string(),
-                size: 4,
-                alignment: 4,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        });
+        registry.register_type(Type::primitive("int", 4));
 
         // Register char
-        registry.register_type(Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "char".to_string(),
-                size: 1,
-                alignment: 1,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        });
+        registry.register_type(Type::primitive("char", 1));
 
         // Register const char*
-        registry.register_type(Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Primitive {
-                name: "char".to_string(),
-                size: 1,
-                alignment: 1,
-            },
-            pointer_depth: 1,
-            is_const: true,
-            is_volatile: false,
-            dwarf_offset: None,
-        });
+        registry.register_type(Type::pointer_to(Type::primitive("char", 1).with_const()));
 
         // Register Point struct
-        registry.register_type(Type {
-            id: TypeId(0),
-            kind: BaseTypeKind::Struct {
-                name: "Point".to_string(),
-                fields: vec![],
-                size: 8,
-                alignment: 4,
-                is_opaque: false,
-            },
-            pointer_depth: 0,
-            is_const: false,
-            is_volatile: false,
-            dwarf_offset: None,
-        });
+        registry.register_type(Type::new(BaseTypeKind::Struct {
+            name: "Point".to_string(),
+            fields: vec![],
+            size: 8,
+            alignment: 4,
+            is_opaque: false,
+        }));
 
         registry
     }
@@ -174,13 +292,7 @@ mod tests {
         let registry = create_test_registry();
         let void_id = get_type_id(&registry, "void", 0, false);
 
-        let sig = FunctionSignature {
-            name: "test_func".to_string(),
-            return_type_id: void_id,
-            parameters: vec![],
-            is_variadic: false,
-            is_exported: true,
-        };
+        let sig = FunctionSignature::new("test_func", void_id, Vec::new());
 
         assert_eq!(sig.to_string(&registry), "void test_func(void)");
     }
@@ -190,41 +302,38 @@ mod tests {
         let registry = create_test_registry();
         let int_id = get_type_id(&registry, "int", 0, false);
 
-        let sig = FunctionSignature {
-            name: "test_func".to_string(),
-            return_type_id: int_id,
-            parameters: vec![Parameter {
-                name: "x".to_string(),
-                type_id: int_id,
-            }],
-            is_variadic: false,
-            is_exported: true,
-        };
+        let sig = FunctionSignature::new("test_func", int_id, vec![Parameter::new("x", int_id)]);
 
         assert_eq!(sig.to_string(&registry), "int test_func(int x)");
     }
 
+    #[test]
+    fn test_c_string_accessors_fall_back_to_void() {
+        let registry = create_test_registry();
+        let point_id = get_type_id(&registry, "Point", 0, false);
+        let missing = TypeId(0xdead);
+
+        let sig = FunctionSignature::new(
+            "test_func",
+            missing,
+            vec![Parameter::new("p", point_id), Parameter::new("q", missing)],
+        );
+
+        assert_eq!(sig.return_c_string(&registry), "void");
+        assert_eq!(sig.parameters[0].type_c_string(&registry), "struct Point");
+        assert_eq!(sig.parameter_c_strings(&registry), ["struct Point", "void"]);
+    }
+
     #[test]
     fn test_function_with_multiple_params() {
         let registry = create_test_registry();
         let int_id = get_type_id(&registry, "int", 0, false);
 
-        let sig = FunctionSignature {
-            name: "add".to_string(),
-            return_type_id: int_id,
-            parameters: vec![
-                Parameter {
-                    name: "a".to_string(),
-                    type_id: int_id,
-                },
-                Parameter {
-                    name: "b".to_string(),
-                    type_id: int_id,
-                },
-            ],
-            is_variadic: false,
-            is_exported: true,
-        };
+        let sig = FunctionSignature::new(
+            "add",
+            int_id,
+            vec![Parameter::new("a", int_id), Parameter::new("b", int_id)],
+        );
 
         assert_eq!(sig.to_string(&registry), "int add(int a, int b)");
     }
@@ -236,14 +345,12 @@ mod tests {
         let const_char_ptr_id = get_type_id(&registry, "char", 1, true);
 
         let sig = FunctionSignature {
-            name: "printf".to_string(),
-            return_type_id: int_id,
-            parameters: vec![Parameter {
-                name: "format".to_string(),
-                type_id: const_char_ptr_id,
-            }],
             is_variadic: true,
-            is_exported: true,
+            ..FunctionSignature::new(
+                "printf",
+                int_id,
+                vec![Parameter::new("format", const_char_ptr_id)],
+            )
         };
 
         assert_eq!(
@@ -252,6 +359,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_variadic_function_without_named_params() {
+        let registry = create_test_registry();
+        let int_id = get_type_id(&registry, "int", 0, false);
+
+        let sig = FunctionSignature {
+            is_variadic: true,
+            ..FunctionSignature::new("log_all", int_id, Vec::new())
+        };
+
+        assert_eq!(sig.to_string(&registry), "int log_all(...)");
+        assert_eq!(
+            sig.to_string_with_void_param(&registry, false),
+            "int log_all(...)"
+        );
+    }
+
     #[test]
     fn test_parameter_without_name() {
         let registry = create_test_registry();
@@ -259,14 +383,8 @@ mod tests {
         let int_id = get_type_id(&registry, "int", 0, false);
 
         let sig = FunctionSignature {
-            name: "test_func".to_string(),
-            return_type_id: void_id,
-            parameters: vec![Parameter {
-                name: "".to_string(),
-                type_id: int_id,
-            }],
-            is_variadic: false,
             is_exported: false,
+            ..FunctionSignature::new("test_func", void_id, vec![Parameter::new("", int_id)])
         };
 
         assert_eq!(sig.to_string(&registry), "void test_func(int)");
@@ -277,13 +395,7 @@ mod tests {
         let registry = create_test_registry();
         let const_char_ptr_id = get_type_id(&registry, "char", 1, true);
 
-        let sig = FunctionSignature {
-            name: "get_string".to_string(),
-            return_type_id: const_char_ptr_id,
-            parameters: vec![],
-            is_variadic: false,
-            is_exported: true,
-        };
+        let sig = FunctionSignature::new("get_string", const_char_ptr_id, Vec::new());
 
         assert_eq!(sig.to_string(&registry), "const char* get_string(void)");
     }
@@ -294,26 +406,42 @@ mod tests {
         let point_id = get_type_id(&registry, "Point", 0, false);
         let int_id = get_type_id(&registry, "int", 0, false);
 
-        let sig = FunctionSignature {
-            name: "create_point".to_string(),
-            return_type_id: point_id,
-            parameters: vec![
-                Parameter {
-                    name: "x".to_string(),
-                    type_id: int_id,
-                },
-                Parameter {
-                    name: "y".to_string(),
-                    type_id: int_id,
-                },
-            ],
-            is_variadic: false,
-            is_exported: true,
-        };
+        let sig = FunctionSignature::new(
+            "create_point",
+            point_id,
+            vec![Parameter::new("x", int_id), Parameter::new("y", int_id)],
+        );
 
         assert_eq!(
             sig.to_string(&registry),
             "struct Point create_point(int x, int y)"
         );
     }
+
+    #[test]
+    fn test_abi_key_ignores_parameter_names() {
+        let registry = create_test_registry();
+        let int_id = get_type_id(&registry, "int", 0, false);
+        let const_char_ptr_id = get_type_id(&registry, "char", 1, true);
+
+        let sig = |param_name: &str, type_id| FunctionSignature {
+            is_variadic: true,
+            ..FunctionSignature::new("printf", int_id, vec![Parameter::new(param_name, type_id)])
+        };
+
+        let key = sig("format", const_char_ptr_id).abi_key(&registry);
+        assert_eq!(key, "int(printf)(const char*,...)");
+        assert_eq!(sig("fmt", const_char_ptr_id).abi_key(&registry), key);
+        assert_ne!(sig("format", int_id).abi_key(&registry), key);
+    }
+
+    #[test]
+    fn test_abi_key_no_params() {
+        let registry = create_test_registry();
+        let void_id = get_type_id(&registry, "void", 0, false);
+
+        let sig = FunctionSignature::new("test_func", void_id, Vec::new());
+
+        assert_eq!(sig.abi_key(&registry), "void(test_func)()");
+    }
 }
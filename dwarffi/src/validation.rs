@@ -0,0 +1,525 @@
+//! integrity checks over a [`TypeRegistry`]: what a correct extraction never
+//! produces, so anything found points at a bug or at malformed DWARF
+use crate::collections::{BTreeMap, HashSet};
+use crate::prelude::*;
+use crate::type_registry::{BaseTypeKind, Type, TypeId, TypeRegistry};
+use core::fmt;
+
+/// a problem [`TypeRegistry::validate`] found. `name` is the offending
+/// type's display name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// a type refers to an id that isn't registered
+    DanglingReference {
+        type_id: TypeId,
+        name: String,
+        missing: TypeId,
+    },
+    /// a typedef chain that leads back to itself
+    TypedefCycle { type_id: TypeId, name: String },
+    /// a struct or union smaller than its members need
+    SizeMismatch {
+        type_id: TypeId,
+        name: String,
+        size: usize,
+        required: usize,
+    },
+    /// an enum whose size isn't its backing type's
+    EnumSizeMismatch {
+        type_id: TypeId,
+        name: String,
+        size: usize,
+        backing_size: usize,
+    },
+    /// two struct fields occupying the same bytes
+    OverlappingFields {
+        type_id: TypeId,
+        name: String,
+        first: String,
+        second: String,
+    },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::DanglingReference { name, missing, .. } => {
+                write!(f, "{} refers to unregistered type {:016x}", name, missing.0)
+            }
+            ValidationIssue::TypedefCycle { name, .. } => {
+                write!(f, "typedef {} leads back to itself", name)
+            }
+            ValidationIssue::SizeMismatch {
+                name,
+                size,
+                required,
+                ..
+            } => write!(
+                f,
+                "{} is {} bytes but its members need {}",
+                name, size, required
+            ),
+            ValidationIssue::EnumSizeMismatch {
+                name,
+                size,
+                backing_size,
+                ..
+            } => write!(
+                f,
+                "enum {} is {} bytes but its backing type is {}",
+                name, size, backing_size
+            ),
+            ValidationIssue::OverlappingFields {
+                name,
+                first,
+                second,
+                ..
+            } => write!(f, "{}: fields {} and {} overlap", name, first, second),
+        }
+    }
+}
+
+/// a type [`TypeRegistry::unresolved_types`] found that extraction could
+/// not fully resolve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnresolvedType {
+    /// an id that isn't registered
+    Missing { type_id: TypeId },
+    /// a DWARF construct dwarffi could not model, e.g. `<unknown:DW_TAG_...>`
+    Placeholder { type_id: TypeId, name: String },
+    /// a struct only declared where its layout is needed: used by value
+    /// rather than behind a pointer
+    Incomplete { type_id: TypeId, name: String },
+}
+
+impl fmt::Display for UnresolvedType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnresolvedType::Missing { type_id } => {
+                write!(f, "unregistered type {:016x}", type_id.0)
+            }
+            UnresolvedType::Placeholder { name, .. } => write!(f, "{} could not be resolved", name),
+            UnresolvedType::Incomplete { name, .. } => {
+                write!(f, "{} is used by value but never defined", name)
+            }
+        }
+    }
+}
+
+impl TypeRegistry {
+    /// the types reachable from `roots` that could not be fully resolved:
+    /// unregistered ids, placeholders, and structs only declared where a
+    /// definition is needed. a declared-only struct behind a pointer is an
+    /// opaque handle and fine. sorted by type id.
+    pub fn unresolved_types(&self, roots: &[TypeId]) -> Vec<UnresolvedType> {
+        let mut found = BTreeMap::new();
+        let mut visited = HashSet::new();
+        // each id with whether its layout is needed (not behind a pointer)
+        let mut queue: Vec<(TypeId, bool)> = roots.iter().map(|&id| (id, true)).collect();
+
+        while let Some((id, needs_layout)) = queue.pop() {
+            if !visited.insert((id, needs_layout)) {
+                continue;
+            }
+            let Some(type_) = self.get_type(id) else {
+                found.insert(id, UnresolvedType::Missing { type_id: id });
+                continue;
+            };
+            let name = || self.display_name(id).unwrap_or_default();
+
+            let needs_layout = needs_layout && type_.pointer_depth == 0;
+            if type_.is_placeholder() {
+                found.insert(
+                    id,
+                    UnresolvedType::Placeholder {
+                        type_id: id,
+                        name: name(),
+                    },
+                );
+            } else if needs_layout
+                && matches!(
+                    type_.kind,
+                    BaseTypeKind::Struct {
+                        is_opaque: true,
+                        ..
+                    }
+                )
+            {
+                found.insert(
+                    id,
+                    UnresolvedType::Incomplete {
+                        type_id: id,
+                        name: name(),
+                    },
+                );
+            }
+
+            // a typedef passes on how it's used; members, elements and
+            // function parameters are always laid out in place
+            let children_need_layout = match type_.kind {
+                BaseTypeKind::Typedef { .. } => needs_layout,
+                _ => true,
+            };
+            queue.extend(
+                type_
+                    .kind
+                    .referenced_type_ids()
+                    .into_iter()
+                    .map(|id| (id, children_need_layout)),
+            );
+        }
+
+        found.into_values().collect()
+    }
+
+    /// check the registry's integrity: dangling references, typedef cycles,
+    /// structs and unions smaller than their members, enums sized unlike
+    /// their backing type, and overlapping struct fields. issues are
+    /// ordered by type id, so the result is the same across runs.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut types: Vec<&Type> = self.all_types().collect();
+        types.sort_by_key(|type_| type_.id);
+
+        let mut issues = Vec::new();
+        for type_ in types {
+            let name = || self.display_name(type_.id).unwrap_or_default();
+
+            let mut missing = type_.kind.referenced_type_ids();
+            missing.retain(|id| self.get_type(*id).is_none());
+            missing.sort();
+            missing.dedup();
+            for missing in missing {
+                issues.push(ValidationIssue::DanglingReference {
+                    type_id: type_.id,
+                    name: name(),
+                    missing,
+                });
+            }
+
+            match &type_.kind {
+                BaseTypeKind::Typedef { .. } if self.is_typedef_cycle(type_) => {
+                    issues.push(ValidationIssue::TypedefCycle {
+                        type_id: type_.id,
+                        name: name(),
+                    });
+                }
+                BaseTypeKind::Struct {
+                    fields,
+                    size,
+                    is_opaque: false,
+                    ..
+                } => {
+                    // fields are in memory order. bitfields are compared by
+                    // the bits they occupy, since several share a byte
+                    let mut required = 0;
+                    let mut previous: Option<(&crate::StructField, usize)> = None;
+                    for field in fields {
+                        let (start, end) = match (field.bit_offset, field.bit_size) {
+                            (Some(offset), Some(bits)) => {
+                                (offset as usize, (offset + bits) as usize)
+                            }
+                            _ => (field.offset * 8, (field.offset + field.size) * 8),
+                        };
+
+                        if let Some((previous, previous_end)) = previous
+                            && start < previous_end
+                        {
+                            issues.push(ValidationIssue::OverlappingFields {
+                                type_id: type_.id,
+                                name: name(),
+                                first: previous.name.clone(),
+                                second: field.name.clone(),
+                            });
+                        }
+
+                        required = required.max(end.div_ceil(8));
+                        previous = Some((field, end));
+                    }
+
+                    if required > *size {
+                        issues.push(ValidationIssue::SizeMismatch {
+                            type_id: type_.id,
+                            name: name(),
+                            size: *size,
+                            required,
+                        });
+                    }
+                }
+                BaseTypeKind::Union { variants, size, .. } => {
+                    let required = variants
+                        .iter()
+                        .filter_map(|variant| self.fixed_size_of(variant.type_id))
+                        .max()
+                        .unwrap_or(0);
+                    if required > *size {
+                        issues.push(ValidationIssue::SizeMismatch {
+                            type_id: type_.id,
+                            name: name(),
+                            size: *size,
+                            required,
+                        });
+                    }
+                }
+                BaseTypeKind::Enum {
+                    backing_id, size, ..
+                } => {
+                    if let Some(backing_size) = self.fixed_size_of(*backing_id)
+                        && backing_size != *size
+                    {
+                        issues.push(ValidationIssue::EnumSizeMismatch {
+                            type_id: type_.id,
+                            name: name(),
+                            size: *size,
+                            backing_size,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        issues
+    }
+
+    /// size of a type that doesn't depend on the pointer width, since the
+    /// registry doesn't know the target's
+    fn fixed_size_of(&self, id: TypeId) -> Option<usize> {
+        let size = self.size_of(id, 4)?;
+        (self.size_of(id, 8)? == size).then_some(size)
+    }
+
+    fn is_typedef_cycle(&self, typedef: &Type) -> bool {
+        let mut seen = HashSet::new();
+        let mut current = typedef;
+        while let BaseTypeKind::Typedef {
+            aliased_type_id, ..
+        } = &current.kind
+        {
+            if !seen.insert(current.id) {
+                return current.id == typedef.id;
+            }
+            match self.get_type(*aliased_type_id) {
+                Some(aliased) => current = aliased,
+                None => return false,
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_registry::{EnumVariant, StructField, UnionField};
+
+    fn field(name: &str, type_id: TypeId, offset: usize, size: usize) -> StructField {
+        StructField {
+            declaration_index: offset,
+            ..StructField::new(name, type_id, offset, size)
+        }
+    }
+
+    fn struct_type(name: &str, fields: Vec<StructField>, size: usize) -> Type {
+        Type::new(BaseTypeKind::Struct {
+            name: name.to_string(),
+            fields,
+            size,
+            alignment: 4,
+            is_opaque: false,
+        })
+    }
+
+    #[test]
+    fn test_consistent_types_have_no_issues() {
+        let mut registry = TypeRegistry::new();
+        let int = registry.register_type(Type::primitive("int", 4));
+        let unsigned = registry.register_type(Type::primitive("unsigned int", 4));
+        let double = registry.register_type(Type::primitive("double", 8));
+        registry.register_type(struct_type(
+            "Point",
+            vec![field("x", int, 0, 4), field("y", int, 4, 4)],
+            8,
+        ));
+        // `unsigned a : 12; unsigned b : 12;` - b starts in byte 1
+        let bitfield = |name, offset: u32, bits| StructField {
+            bit_size: Some(bits),
+            bit_offset: Some(offset),
+            ..field(name, unsigned, offset as usize / 8, 4)
+        };
+        registry.register_type(struct_type(
+            "Flags",
+            vec![bitfield("a", 0, 12), bitfield("b", 12, 12)],
+            4,
+        ));
+        registry.register_type(Type::new(BaseTypeKind::Union {
+            name: "Value".to_string(),
+            variants: vec![
+                UnionField {
+                    name: "i".to_string(),
+                    type_id: int,
+                    discriminant_value: None,
+                },
+                UnionField {
+                    name: "d".to_string(),
+                    type_id: double,
+                    discriminant_value: None,
+                },
+            ],
+            size: 8,
+            alignment: 8,
+            discriminant: None,
+        }));
+
+        assert_eq!(registry.validate(), []);
+    }
+
+    #[test]
+    fn test_reports_each_kind_of_issue() {
+        let mut registry = TypeRegistry::new();
+        let int = registry.register_type(Type::primitive("int", 4));
+        let double = registry.register_type(Type::primitive("double", 8));
+        let char_ = registry.register_type(Type::primitive("char", 1));
+
+        let dangling = registry.register_type(Type::new(BaseTypeKind::Typedef {
+            name: "Missing_t".to_string(),
+            aliased_type_id: TypeId(42),
+        }));
+        let too_small = registry.register_type(struct_type(
+            "Pair",
+            vec![field("a", int, 0, 4), field("b", int, 4, 4)],
+            4,
+        ));
+        let overlapping = registry.register_type(struct_type(
+            "Mixed",
+            vec![field("d", double, 0, 8), field("c", char_, 4, 1)],
+            8,
+        ));
+        // sharing a byte doesn't make integers bitfields
+        let overlapping_ints = registry.register_type(struct_type(
+            "Ints",
+            vec![field("a", int, 0, 4), field("b", int, 2, 4)],
+            8,
+        ));
+        let byte_enum = registry.register_type(Type::new(BaseTypeKind::Enum {
+            name: "Mode".to_string(),
+            backing_id: int,
+            variants: vec![EnumVariant {
+                name: "MODE_ON".to_string(),
+                value: 1,
+            }],
+            size: 1,
+        }));
+
+        let issues = registry.validate();
+        assert_eq!(issues.len(), 5, "{:?}", issues);
+        let dangling = ValidationIssue::DanglingReference {
+            type_id: dangling,
+            name: "Missing_t".to_string(),
+            missing: TypeId(42),
+        };
+        assert_eq!(
+            dangling.to_string(),
+            "Missing_t refers to unregistered type 000000000000002a"
+        );
+        assert!(issues.contains(&dangling));
+        assert!(issues.contains(&ValidationIssue::SizeMismatch {
+            type_id: too_small,
+            name: "Pair".to_string(),
+            size: 4,
+            required: 8,
+        }));
+        assert!(issues.contains(&ValidationIssue::OverlappingFields {
+            type_id: overlapping,
+            name: "Mixed".to_string(),
+            first: "d".to_string(),
+            second: "c".to_string(),
+        }));
+        assert!(issues.contains(&ValidationIssue::OverlappingFields {
+            type_id: overlapping_ints,
+            name: "Ints".to_string(),
+            first: "a".to_string(),
+            second: "b".to_string(),
+        }));
+        assert!(issues.contains(&ValidationIssue::EnumSizeMismatch {
+            type_id: byte_enum,
+            name: "Mode".to_string(),
+            size: 1,
+            backing_size: 4,
+        }));
+    }
+
+    #[test]
+    fn test_dangling_reference_reported_once() {
+        let mut registry = TypeRegistry::new();
+        let int = registry.register_type(Type::primitive("int", 4));
+
+        // the same missing type through members that aren't adjacent
+        let holder = registry.register_type(struct_type(
+            "Holder",
+            vec![
+                field("a", TypeId(7), 0, 4),
+                field("b", int, 4, 4),
+                field("c", TypeId(7), 8, 4),
+                field("d", TypeId(3), 12, 4),
+            ],
+            16,
+        ));
+
+        let missing: Vec<TypeId> = registry
+            .validate()
+            .into_iter()
+            .filter_map(|issue| match issue {
+                ValidationIssue::DanglingReference {
+                    type_id, missing, ..
+                } if type_id == holder => Some(missing),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(missing, [TypeId(3), TypeId(7)]);
+    }
+
+    #[test]
+    fn test_unresolved_types_need_a_layout() {
+        let mut registry = TypeRegistry::new();
+        let opaque = || {
+            Type::new(BaseTypeKind::Struct {
+                name: "Handle".to_string(),
+                fields: Vec::new(),
+                size: 0,
+                alignment: 0,
+                is_opaque: true,
+            })
+        };
+        let by_value = registry.register_type(opaque());
+        let behind_pointer = registry.register_type(Type {
+            pointer_depth: 1,
+            ..opaque()
+        });
+        let handle_t = registry.register_type(Type::pointer_to(Type::new(BaseTypeKind::Typedef {
+            name: "Handle_t".to_string(),
+            aliased_type_id: by_value,
+        })));
+        let unknown = registry.register_type(Type::primitive("<unknown:DW_TAG_foo>", 0));
+        let holder = registry.register_type(Type {
+            pointer_depth: 1,
+            ..struct_type("Holder", vec![field("u", unknown, 0, 0)], 0)
+        });
+
+        // an opaque handle, directly or through a typedef, is fine
+        assert_eq!(registry.unresolved_types(&[behind_pointer, handle_t]), []);
+
+        let unresolved = registry.unresolved_types(&[by_value, holder, TypeId(42)]);
+        assert_eq!(unresolved.len(), 3, "{:?}", unresolved);
+        assert!(unresolved.contains(&UnresolvedType::Incomplete {
+            type_id: by_value,
+            name: "Handle".to_string(),
+        }));
+        assert!(unresolved.contains(&UnresolvedType::Placeholder {
+            type_id: unknown,
+            name: "<unknown:DW_TAG_foo>".to_string(),
+        }));
+        assert!(unresolved.contains(&UnresolvedType::Missing {
+            type_id: TypeId(42)
+        }));
+    }
+}
@@ -0,0 +1,186 @@
+//! minimal WebAssembly module reader. emscripten and wasi-sdk keep DWARF in
+//! custom sections named like the ELF ones (`.debug_info`, ...), so only the
+//! section table and the exports are needed.
+use crate::error::{DwarffiError, Result};
+use crate::prelude::*;
+
+const MAGIC: &[u8] = b"\0asm";
+const CUSTOM_SECTION: u8 = 0;
+const EXPORT_SECTION: u8 = 7;
+const FUNCTION_EXPORT: u8 = 0;
+
+/// true if the data starts with the wasm magic number
+pub(crate) fn is_wasm(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub(crate) struct WasmModule<'data> {
+    custom_sections: Vec<(&'data str, &'data [u8])>,
+    /// names of exported functions
+    function_exports: Vec<&'data str>,
+}
+
+impl<'data> WasmModule<'data> {
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        let mut input = Cursor { data, position: 0 };
+        if input.bytes(4)? != MAGIC {
+            return Err(malformed("missing magic number"));
+        }
+        let version = input.bytes(4)?;
+        if version != [1, 0, 0, 0] {
+            return Err(malformed(&format!("unsupported version {:?}", version)));
+        }
+
+        let mut module = Self {
+            custom_sections: Vec::new(),
+            function_exports: Vec::new(),
+        };
+        while !input.is_empty() {
+            let id = input.byte()?;
+            let size = input.leb128()? as usize;
+            let mut payload = Cursor {
+                data: input.bytes(size)?,
+                position: 0,
+            };
+
+            match id {
+                CUSTOM_SECTION => {
+                    let name = payload.name()?;
+                    module.custom_sections.push((name, payload.rest()));
+                }
+                EXPORT_SECTION => {
+                    for _ in 0..payload.leb128()? {
+                        let name = payload.name()?;
+                        let kind = payload.byte()?;
+                        payload.leb128()?; // index
+                        if kind == FUNCTION_EXPORT {
+                            module.function_exports.push(name);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        log::debug!(
+            "wasm module: {} custom sections, {} exported functions",
+            module.custom_sections.len(),
+            module.function_exports.len()
+        );
+        Ok(module)
+    }
+
+    /// contents of the custom section with this name
+    pub fn section(&self, name: &str) -> Option<&'data [u8]> {
+        self.custom_sections
+            .iter()
+            .find(|(section_name, _)| *section_name == name)
+            .map(|(_, data)| *data)
+    }
+
+    pub fn function_exports(&self) -> impl Iterator<Item = &'data str> + '_ {
+        self.function_exports.iter().copied()
+    }
+
+    /// pointer width from the address size of the first compilation unit:
+    /// 4 for wasm32, 8 for memory64 modules
+    pub fn pointer_width(&self) -> usize {
+        self.section(".debug_info")
+            .and_then(|data| {
+                let debug_info = gimli::DebugInfo::new(data, gimli::LittleEndian);
+                let header = debug_info.units().next().ok()??;
+                Some(header.address_size() as usize)
+            })
+            .unwrap_or(4)
+    }
+}
+
+fn malformed(reason: &str) -> DwarffiError {
+    DwarffiError::UnsupportedFormat(format!("malformed wasm module: {}", reason))
+}
+
+struct Cursor<'data> {
+    data: &'data [u8],
+    position: usize,
+}
+
+impl<'data> Cursor<'data> {
+    fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'data [u8]> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| malformed("unexpected end of data"))?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn leb128(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(malformed("LEB128 value too long"))
+    }
+
+    fn name(&mut self) -> Result<&'data str> {
+        let len = self.leb128()? as usize;
+        core::str::from_utf8(self.bytes(len)?).map_err(|_| malformed("name is not utf-8"))
+    }
+
+    fn rest(&mut self) -> &'data [u8] {
+        let rest = &self.data[self.position..];
+        self.position = self.data.len();
+        rest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(id: u8, payload: &[u8]) -> Vec<u8> {
+        let mut section = vec![id, payload.len() as u8];
+        section.extend_from_slice(payload);
+        section
+    }
+
+    #[test]
+    fn test_parse_custom_sections_and_exports() {
+        let mut data = b"\0asm\x01\0\0\0".to_vec();
+        data.extend(section(CUSTOM_SECTION, b"\x0b.debug_infoDWARF"));
+        // one function export "add" and one memory export "memory"
+        data.extend(section(
+            EXPORT_SECTION,
+            b"\x02\x03add\x00\x00\x06memory\x02\x00",
+        ));
+
+        let module = WasmModule::parse(&data).unwrap();
+        assert_eq!(module.section(".debug_info"), Some(&b"DWARF"[..]));
+        assert_eq!(module.section(".debug_line"), None);
+        assert_eq!(module.function_exports().collect::<Vec<_>>(), ["add"]);
+    }
+
+    #[test]
+    fn test_truncated_module_is_an_error() {
+        let mut data = b"\0asm\x01\0\0\0".to_vec();
+        data.extend([CUSTOM_SECTION, 0x20, 0x01]);
+        assert!(matches!(
+            WasmModule::parse(&data),
+            Err(DwarffiError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -1,54 +1,78 @@
 mod common;
 
-use dwarffi::DwarfAnalyzer;
+use dwarffi::{DwarfAnalyzer, DwarffiError, ExtractOptions};
 use std::path::PathBuf;
 
 /// expected functions from test C lib.
 ///
 /// TODO FIXME!! requires manual syncing. maybe an annotation in the comment of
 /// the c source,  can be used to auto-generate this list when the test runs?
+/// kept sorted by function name.
 const EXPECTED_SIGNATURES: &[&str] = &[
     "Point add_points(Point p1, Point p2)",
     "int add_two_ints(int a, int b)",
     "int* allocate_array(size_t count)",
     "void allocate_matrix(int** matrix, int rows, int cols)",
+    "int apply_operation(int a, int b, int (*operation)(int, int))",
     "Color blend_colors(Color c1, Color c2)",
+    "size_t byte_buffer_length(const ByteBuffer* buffer)",
     "float calculate_distance(Point p1, Point p2)",
+    "int clamp_percent(int value)",
     "void cleanup_state(InternalState* state)",
+    "void clear_registers(volatile uint32_t* const regs, size_t count)",
     "void complex_function(const char* name, Point* points, size_t point_count, Rectangle bounds, Status* out_status)",
     "double compute_double(double x, double y, double z)",
+    "void copy_ints(int* dst, const int* src, size_t count)",
+    "int count_status_flags(StatusFlags flags)",
     "BoundingBox create_bounding_box(Point tl, Point br)",
     "DataUnion create_data_union(int value)",
     "Person* create_person(const char* name, int age)",
     "Point create_point(int x, int y)",
     "Rectangle create_rectangle(float w, float h)",
+    "TaggedValue create_tagged_value(Status status, int value)",
     "void destroy_person(Person* p)",
+    "void fatal_error(const char* message)",
+    "Permission get_default_permission(void)",
+    "double get_double_from_wide_union(WideUnion data)",
     "float get_float_from_union(DataUnion data)",
     "size_t get_size(void)",
+    "ssize_t get_ssize(void)",
     "Status get_status(void)",
     "const char* get_string(void)",
+    "uintptr_t get_uintptr(void)",
     "InternalState* init_state(void)",
     "int internal_compute(int a, int b)",
     "void internal_helper(void)",
     "void internal_process_data(const char* data, size_t len)",
+    "void invoke_with_status(Status status, void (*handler)(Status))",
     "int is_point_inside(BoundingBox box, Point p)",
+    "int load_atomic(int* value)",
     "void modify_value(int* ptr)",
     "void move_point(Point* p, int dx, int dy)",
     "float multiply_floats(float a, float b)",
+    "const Point* pick_point(const Point* first, Point* const second, const Point* const third)",
     "void print_string(const char* str)",
-    "void process_2d_array(int[5]* arr)",
+    "void process_2d_array(int (*arr)[5])",
     "void process_buffer(char* buffer, size_t length)",
     "uint8_t process_byte(uint8_t value)",
     "void process_fixed_array(int* arr)",
+    "int16_t process_int16(int16_t value)",
+    "int32_t process_int32(int32_t value)",
+    "int8_t process_int8(int8_t value)",
     "int64_t process_long(int64_t value)",
     "Status process_person_batch(Person** people, size_t count, Callback on_complete)",
     "int process_state(InternalState* state, int value)",
+    "uint16_t process_uint16(uint16_t value)",
+    "uint32_t process_uint32(uint32_t value)",
+    "uint64_t process_uint64(uint64_t value)",
+    "uint32_t read_register(volatile uint32_t* reg)",
     "void register_callback(Callback cb, void* userdata)",
     "int return_int(void)",
     "void set_status(Status s)",
     "void simple_void_function(void)",
     "void sort_array(int* arr, size_t count, Comparator cmp)",
     "int sum_array(const int* arr, size_t length)",
+    "int sum_padded_items(const PaddedItems* items)",
     "int sum_varargs(int count, ...)",
     "void update_person_status(Person* p, Status new_status)",
 ];
@@ -77,12 +101,303 @@ fn test_load_dsym() {
     }
 }
 
+#[test]
+#[cfg(target_os = "macos")]
+/// an unlinked `.o` keeps its DWARF in the `__DWARF` segment rather than a
+/// dSYM, and is read directly
+fn test_load_macho_object_file() {
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("point.c");
+    std::fs::write(
+        &source,
+        "typedef struct { int x; int y; } Point;\n\
+         int point_sum(Point p) { return p.x + p.y; }\n",
+    )
+    .unwrap();
+    let object = dir.path().join("point.o");
+    let status = Command::new("cc")
+        .args(["-c", "-g", "-O0", "-o"])
+        .arg(&object)
+        .arg(&source)
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("cc unavailable or failed - skipping Mach-O object test");
+        return;
+    }
+
+    let result = DwarfAnalyzer::from_file(&object)
+        .expect("fail to load object file")
+        .extract_analysis(false)
+        .expect("fail to extract functions");
+    let signatures: Vec<String> = result
+        .signatures
+        .iter()
+        .map(|f| f.to_string(&result.type_registry))
+        .collect();
+    assert!(
+        signatures.contains(&"int point_sum(Point p)".to_string()),
+        "no point_sum in {:?}",
+        signatures
+    );
+}
+
+#[test]
+#[cfg(target_os = "macos")]
+/// the dSYM's DWARF alone, without the dylib's symbol table, still finds
+/// every function the dylib exports
+fn test_dsym_exported_only_without_binary() {
+    let result = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("fail to load dSYM file")
+        .extract_analysis(true)
+        .expect("fail to extract functions");
+    let names: std::collections::HashSet<&str> =
+        result.signatures.iter().map(|s| s.name.as_str()).collect();
+
+    let exported = DwarfAnalyzer::from_file(&common::get_test_dylib_path())
+        .expect("fail to load dylib")
+        .get_exported_symbols()
+        .expect("fail to read symbols");
+    assert!(!exported.is_empty());
+    for symbol in &exported {
+        let name = symbol.name.strip_prefix('_').unwrap_or(&symbol.name);
+        assert!(names.contains(name), "{} missing from the dSYM", name);
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// a debug file with no symbol table (`objcopy --only-section`) falls back
+/// to global linkage for `exported_only`: the library's exports, plus the
+/// global functions `-fvisibility=hidden` kept out of them
+fn test_debug_file_without_symbols_uses_global_linkage() {
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let debug_file = dir.path().join("libtestlib.debug");
+    let status = Command::new("objcopy")
+        .arg("--only-section=.debug*")
+        .arg(common::get_test_lib_path())
+        .arg(&debug_file)
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("objcopy unavailable or failed - skipping debug file test");
+        return;
+    }
+
+    let signatures = |path: &std::path::Path| {
+        let analyzer = DwarfAnalyzer::from_file(path).expect("fail to load library");
+        let result = analyzer
+            .extract_analysis(true)
+            .expect("fail to extract functions");
+        let mut signatures: Vec<String> = result
+            .signatures
+            .iter()
+            .map(|s| s.to_string(&result.type_registry))
+            .collect();
+        signatures.sort();
+        signatures
+    };
+
+    let from_debug = signatures(&debug_file);
+    let from_library = signatures(&common::get_test_lib_path());
+    for signature in &from_library {
+        assert!(from_debug.contains(signature), "{} missing", signature);
+    }
+    assert!(
+        from_debug
+            .iter()
+            .any(|s| s.starts_with("int internal_compute(")),
+        "hidden global function missing"
+    );
+}
+
+#[test]
+/// analysis of in-memory bytes matches loading the file
+fn test_from_bytes_matches_from_file() {
+    let path = common::get_test_lib_path();
+    let data = std::fs::read(&path).expect("fail to read test library");
+
+    let from_bytes = DwarfAnalyzer::from_bytes(data, None)
+        .expect("fail to load test library bytes")
+        .extract_analysis(true)
+        .expect("fail to extract functions");
+    let from_file = DwarfAnalyzer::from_file(&path)
+        .expect("fail to load test library")
+        .extract_analysis(true)
+        .expect("fail to extract functions");
+
+    assert_eq!(from_bytes.signatures.len(), from_file.signatures.len());
+    assert_eq!(
+        from_bytes.type_registry.len(),
+        from_file.type_registry.len()
+    );
+}
+
 #[test]
 /// test error on nonexistent file
 fn test_error_on_nonexistent_file() {
     let path = PathBuf::from("nonexistent/library.dylib");
     let result = DwarfAnalyzer::from_file(&path);
-    assert!(result.is_err(), "Should fail on nonexistent file");
+    assert!(
+        matches!(result, Err(DwarffiError::Io { .. })),
+        "Should fail with an io error on nonexistent file"
+    );
+}
+
+#[test]
+/// test error on a file that isn't an object file
+fn test_error_on_non_object_file() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../test_c/testlib.h");
+    let analyzer = DwarfAnalyzer::from_file(&path).expect("fail to read header file");
+    let result = analyzer.extract_analysis(false);
+    assert!(
+        matches!(result, Err(DwarffiError::ObjectParse(_))),
+        "Should fail to parse a non-object file"
+    );
+}
+
+#[test]
+/// analyzing the same library twice merges to the single-file result
+fn test_extract_analysis_multi_dedups() {
+    let path = common::get_test_lib_path();
+    let single = DwarfAnalyzer::from_file(&path)
+        .expect("fail to load test library")
+        .extract_analysis(true)
+        .expect("fail to extract functions");
+
+    let merged = DwarfAnalyzer::extract_analysis_multi(&[path.clone(), path], true)
+        .expect("fail to extract from multiple files");
+
+    assert_eq!(merged.signatures.len(), single.signatures.len());
+    assert_eq!(merged.type_registry.len(), single.type_registry.len());
+    assert_eq!(merged.target, single.target);
+
+    let mut names: Vec<&str> = merged.signatures.iter().map(|s| s.name.as_str()).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), merged.signatures.len(), "duplicate signatures");
+
+    // signature type ids still resolve in the merged registry
+    for sig in &merged.signatures {
+        assert!(merged.type_registry.get_type(sig.return_type_id).is_some());
+    }
+}
+
+#[test]
+/// no inputs is an error rather than an empty result
+fn test_extract_analysis_multi_requires_input() {
+    let result = DwarfAnalyzer::extract_analysis_multi(&[], true);
+    assert!(matches!(result, Err(DwarffiError::NoInput)));
+}
+
+#[test]
+/// abi keys depend only on the signature, not on which other functions were
+/// analyzed alongside it
+fn test_abi_keys_stable_across_analyses() {
+    use std::collections::{HashMap, HashSet};
+
+    let analyzer =
+        DwarfAnalyzer::from_file(&common::get_test_lib_path()).expect("fail to load test library");
+    let keys = |exported_only: bool| -> HashMap<String, String> {
+        let result = analyzer
+            .extract_analysis(exported_only)
+            .expect("fail to extract functions");
+        result
+            .signatures
+            .iter()
+            .map(|sig| (sig.name.clone(), sig.abi_key(&result.type_registry)))
+            .collect()
+    };
+
+    let all_keys = keys(false);
+    let exported_keys = keys(true);
+
+    for (name, key) in &exported_keys {
+        assert_eq!(&all_keys[name], key, "abi key of {} changed", name);
+    }
+    assert_eq!(exported_keys["sum_varargs"], "int(sum_varargs)(int,...)");
+    assert_eq!(
+        exported_keys["complex_function"],
+        "void(complex_function)(const char*,Point*,size_t,Rectangle,Status*)"
+    );
+
+    // distinct functions have distinct keys
+    let unique: HashSet<&String> = all_keys.values().collect();
+    assert_eq!(unique.len(), all_keys.len());
+}
+
+#[test]
+/// excluded globs drop matching functions and nothing else
+fn test_extract_options_exclude() {
+    let analyzer =
+        DwarfAnalyzer::from_file(&common::get_test_lib_path()).expect("fail to load test library");
+
+    let all = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract functions");
+    let options = ExtractOptions::new()
+        .exported_only(false)
+        .exclude("internal_*")
+        .exclude("get_?");
+    let excluded = analyzer
+        .extract_analysis_with(&options)
+        .expect("fail to extract functions");
+
+    let names: Vec<&str> = excluded
+        .signatures
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect();
+    assert!(!names.iter().any(|n| n.starts_with("internal_")));
+    assert!(
+        names.contains(&"get_int"),
+        "get_? matches one character only"
+    );
+    assert!(names.contains(&"multiply_internal"));
+
+    let dropped = all.signatures.len() - excluded.signatures.len();
+    assert_eq!(
+        dropped, 3,
+        "internal_helper, internal_compute, internal_process_data"
+    );
+
+    let excluding = analyzer
+        .extract_analysis_excluding(false, &["internal_*".to_string(), "get_?".to_string()])
+        .expect("fail to extract functions");
+    let excluding_names: Vec<&str> = excluding
+        .signatures
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect();
+    assert_eq!(excluding_names, names);
+}
+
+#[test]
+/// progress is reported once per compilation unit and ends at the totals
+fn test_extract_analysis_progress() {
+    use dwarffi::UnitProgress;
+    use std::cell::RefCell;
+
+    let analyzer =
+        DwarfAnalyzer::from_file(&common::get_test_lib_path()).expect("fail to load test library");
+
+    let reports: RefCell<Vec<UnitProgress>> = RefCell::new(Vec::new());
+    let result = analyzer
+        .extract_analysis_with_progress(&ExtractOptions::new(), &|p| reports.borrow_mut().push(p))
+        .expect("fail to extract functions");
+
+    let reports = reports.into_inner();
+    assert!(!reports.is_empty());
+    for (i, report) in reports.iter().enumerate() {
+        assert_eq!(report.unit_index, i);
+        assert_eq!(report.total_units, reports.len());
+    }
+
+    let last = reports.last().unwrap();
+    assert_eq!(last.functions_so_far, result.signatures.len());
+    assert_eq!(last.types_so_far, result.type_registry.len());
 }
 
 #[test]
@@ -191,16 +506,10 @@ fn test_simple_void_function_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "simple_void_function")
+        .function_by_name("simple_void_function")
         .expect("simple_void_function not found");
 
-    let return_type_str = result
-        .type_registry
-        .get_type(sig.return_type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let return_type_str = sig.return_c_string(&result.type_registry);
     assert_eq!(return_type_str, "void");
     assert_eq!(sig.parameters.len(), 0);
     assert!(!sig.is_variadic);
@@ -210,6 +519,126 @@ fn test_simple_void_function_signature() {
     );
 }
 
+#[test]
+/// test looking functions up by name, including through a stale index
+fn test_function_by_name() {
+    let path = common::get_test_lib_path();
+    let analyzer = DwarfAnalyzer::from_file(&path).expect("fail to load test library");
+    let mut result = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+
+    for sig in &result.signatures {
+        let found = result.function_by_name(&sig.name).expect("indexed function");
+        assert_eq!(found.name, sig.name);
+    }
+    assert!(result.function_by_name("no_such_function").is_none());
+
+    // reordering without reindexing still finds the right function
+    result.signatures.reverse();
+    let sig = result.function_by_name("add_two_ints").unwrap();
+    assert_eq!(sig.name, "add_two_ints");
+    result.index_functions();
+    assert_eq!(result.function_by_name("add_two_ints").unwrap().name, "add_two_ints");
+}
+
+#[test]
+fn test_from_sections_matches_object_file() {
+    use object::{Object, ObjectSection};
+
+    let path = common::get_test_lib_path();
+    let data = std::fs::read(&path).unwrap();
+    let object_file = object::File::parse(&data[..]).unwrap();
+
+    let mut sections = std::collections::HashMap::new();
+    for id in [
+        gimli::SectionId::DebugInfo,
+        gimli::SectionId::DebugAbbrev,
+        gimli::SectionId::DebugStr,
+        gimli::SectionId::DebugLineStr,
+        gimli::SectionId::DebugStrOffsets,
+        gimli::SectionId::DebugAddr,
+        gimli::SectionId::DebugLine,
+        gimli::SectionId::DebugRngLists,
+        gimli::SectionId::DebugRanges,
+    ] {
+        if let Some(section) = object_file.section_by_name(id.name()) {
+            sections.insert(id, section.uncompressed_data().unwrap().into_owned());
+        }
+    }
+
+    let expected = DwarfAnalyzer::from_file(&path)
+        .unwrap()
+        .extract_analysis(false)
+        .unwrap();
+    let analyzer = DwarfAnalyzer::from_sections(
+        sections,
+        expected.target.endianness,
+        expected.target.pointer_width,
+    );
+    let result = analyzer.extract_analysis(false).unwrap();
+
+    let rendered = |result: &dwarffi::AnalysisResult| {
+        result
+            .signatures
+            .iter()
+            .map(|sig| sig.to_string(&result.type_registry))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(rendered(&result), rendered(&expected));
+    assert_eq!(result.target.pointer_width, expected.target.pointer_width);
+    // with no symbol table, global linkage decides
+    assert!(
+        result
+            .signatures
+            .iter()
+            .all(|sig| sig.is_exported == sig.is_external)
+    );
+    assert!(analyzer.get_exported_symbols().unwrap().is_empty());
+}
+
+#[test]
+#[cfg(feature = "profiling")]
+fn test_profiling_records_each_unit() {
+    let path = common::get_test_lib_path();
+    let analyzer = DwarfAnalyzer::from_file(&path).expect("fail to load test library");
+    let result = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+
+    let timings = &result.timings;
+    assert!(!timings.units.is_empty());
+    // the library's functions share types, so some are resolved again
+    assert!(timings.type_cache_misses > 0);
+    assert!(timings.type_cache_hits > 0);
+}
+
+#[test]
+/// test that pruning after filtering to one function keeps only its types
+fn test_prune_types_after_filtering() {
+    let path = common::get_test_lib_path();
+    let analyzer = DwarfAnalyzer::from_file(&path).expect("fail to load test library");
+    let mut result = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+    let before = result.type_registry.len();
+    assert!(!result.type_registry.get_by_name("BoundingBox").is_empty());
+
+    result.signatures.retain(|s| s.name == "create_point");
+    result.index_functions();
+    result.prune_types();
+
+    let registry = &result.type_registry;
+    assert!(registry.len() < before);
+    let sig = result.function_by_name("create_point").unwrap();
+    assert_eq!(sig.to_string(registry), "Point create_point(int x, int y)");
+    assert!(!registry.get_by_name("Point").is_empty());
+    for unrelated in ["BoundingBox", "Person", "Rectangle", "Status"] {
+        assert!(registry.get_by_name(unrelated).is_empty(), "{}", unrelated);
+    }
+    assert_eq!(registry.validate(), []);
+}
+
 #[test]
 /// test primitive parameters signature
 fn test_primitive_parameters_signature() {
@@ -220,31 +649,17 @@ fn test_primitive_parameters_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "add_two_ints")
+        .function_by_name("add_two_ints")
         .expect("add_two_ints not found");
 
-    let return_type_str = result
-        .type_registry
-        .get_type(sig.return_type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let return_type_str = sig.return_c_string(&result.type_registry);
     assert_eq!(return_type_str, "int");
     assert_eq!(sig.parameters.len(), 2);
     assert_eq!(sig.parameters[0].name, "a");
-    let param0_type = result
-        .type_registry
-        .get_type(sig.parameters[0].type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let param0_type = sig.parameters[0].type_c_string(&result.type_registry);
     assert_eq!(param0_type, "int");
     assert_eq!(sig.parameters[1].name, "b");
-    let param1_type = result
-        .type_registry
-        .get_type(sig.parameters[1].type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let param1_type = sig.parameters[1].type_c_string(&result.type_registry);
     assert_eq!(param1_type, "int");
     assert_eq!(
         sig.to_string(&result.type_registry),
@@ -262,16 +677,10 @@ fn test_pointer_types_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "get_string")
+        .function_by_name("get_string")
         .expect("get_string not found");
 
-    let return_type_str = result
-        .type_registry
-        .get_type(sig.return_type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let return_type_str = sig.return_c_string(&result.type_registry);
     assert_eq!(return_type_str, "const char*");
     assert_eq!(sig.parameters.len(), 0);
 }
@@ -286,16 +695,10 @@ fn test_struct_types_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "create_point")
+        .function_by_name("create_point")
         .expect("create_point not found");
 
-    let return_type_str = result
-        .type_registry
-        .get_type(sig.return_type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let return_type_str = sig.return_c_string(&result.type_registry);
     assert_eq!(return_type_str, "Point");
     assert_eq!(sig.parameters.len(), 2);
     assert_eq!(
@@ -314,16 +717,10 @@ fn test_nested_struct_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "create_bounding_box")
+        .function_by_name("create_bounding_box")
         .expect("create_bounding_box not found");
 
-    let return_type_str = result
-        .type_registry
-        .get_type(sig.return_type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let return_type_str = sig.return_c_string(&result.type_registry);
     assert_eq!(return_type_str, "BoundingBox");
     assert_eq!(sig.parameters.len(), 2);
 }
@@ -338,16 +735,10 @@ fn test_opaque_pointer_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "init_state")
+        .function_by_name("init_state")
         .expect("init_state not found");
 
-    let return_type_str = result
-        .type_registry
-        .get_type(sig.return_type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let return_type_str = sig.return_c_string(&result.type_registry);
     assert!(return_type_str.contains("InternalState") && return_type_str.contains("*"));
     assert_eq!(sig.parameters.len(), 0);
 }
@@ -362,16 +753,10 @@ fn test_enum_types_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "get_status")
+        .function_by_name("get_status")
         .expect("get_status not found");
 
-    let return_type_str = result
-        .type_registry
-        .get_type(sig.return_type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let return_type_str = sig.return_c_string(&result.type_registry);
     assert_eq!(return_type_str, "Status");
 }
 
@@ -385,16 +770,10 @@ fn test_union_types_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "create_data_union")
+        .function_by_name("create_data_union")
         .expect("create_data_union not found");
 
-    let return_type_str = result
-        .type_registry
-        .get_type(sig.return_type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let return_type_str = sig.return_c_string(&result.type_registry);
     assert_eq!(return_type_str, "DataUnion");
 }
 
@@ -408,16 +787,10 @@ fn test_double_pointer_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "allocate_matrix")
+        .function_by_name("allocate_matrix")
         .expect("allocate_matrix not found");
 
-    let param0_type = result
-        .type_registry
-        .get_type(sig.parameters[0].type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let param0_type = sig.parameters[0].type_c_string(&result.type_registry);
     assert!(param0_type.contains("int**"));
 }
 
@@ -431,9 +804,7 @@ fn test_variadic_function_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "sum_varargs")
+        .function_by_name("sum_varargs")
         .expect("sum_varargs not found");
 
     assert!(sig.is_variadic);
@@ -450,47 +821,21 @@ fn test_complex_function_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "complex_function")
+        .function_by_name("complex_function")
         .expect("complex_function not found");
 
-    let return_type_str = result
-        .type_registry
-        .get_type(sig.return_type_id)
-        .map(|t| t.to_c_string(&result.type_registry))
-        .unwrap_or_else(|| "void".to_string());
+    let return_type_str = sig.return_c_string(&result.type_registry);
     assert_eq!(return_type_str, "void");
     assert_eq!(sig.parameters.len(), 5);
     // Verify it has the expected complex parameter types
-    assert!(sig.parameters.iter().any(|p| {
-        result
-            .type_registry
-            .get_type(p.type_id)
-            .map(|t| t.to_c_string(&result.type_registry).contains("const char*"))
-            .unwrap_or(false)
-    }));
-    assert!(sig.parameters.iter().any(|p| {
-        result
-            .type_registry
-            .get_type(p.type_id)
-            .map(|t| t.to_c_string(&result.type_registry).contains("Point*"))
-            .unwrap_or(false)
-    }));
-    assert!(sig.parameters.iter().any(|p| {
-        result
-            .type_registry
-            .get_type(p.type_id)
-            .map(|t| t.to_c_string(&result.type_registry).contains("Rectangle"))
-            .unwrap_or(false)
-    }));
-    assert!(sig.parameters.iter().any(|p| {
-        result
-            .type_registry
-            .get_type(p.type_id)
-            .map(|t| t.to_c_string(&result.type_registry).contains("Status*"))
-            .unwrap_or(false)
-    }));
+    let param_types = sig.parameter_c_strings(&result.type_registry);
+    for expected in ["const char*", "Point*", "Rectangle", "Status*"] {
+        assert!(
+            param_types.iter().any(|t| t.contains(expected)),
+            "{:?}",
+            param_types
+        );
+    }
 }
 
 #[test]
@@ -503,226 +848,111 @@ fn test_function_pointer_parameter_signature() {
         .expect("fail to extract analysis");
 
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "register_callback")
+        .function_by_name("register_callback")
         .expect("register_callback not found");
 
     assert_eq!(sig.parameters.len(), 2);
     // Should have Callback function pointer parameter
-    assert!(sig.parameters.iter().any(|p| {
-        result
-            .type_registry
-            .get_type(p.type_id)
-            .map(|t| t.to_c_string(&result.type_registry) == "Callback")
-            .unwrap_or(false)
-    }));
+    assert!(
+        sig.parameter_c_strings(&result.type_registry)
+            .iter()
+            .any(|t| t == "Callback")
+    );
 }
 
 #[test]
 /// test callback typedef resolution to function pointer
 fn test_callback_typedef_resolution() {
-    use dwarffi::type_registry::BaseTypeKind;
-
     let path = common::get_test_lib_path();
     let analyzer = DwarfAnalyzer::from_file(&path).expect("fail to load test library");
     let result = analyzer
         .extract_analysis(false)
         .expect("fail to extract analysis");
+    let registry = &result.type_registry;
 
     // Find register_callback function: void register_callback(Callback cb, void* userdata);
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "register_callback")
+        .function_by_name("register_callback")
         .expect("register_callback not found");
 
     assert_eq!(sig.parameters.len(), 2);
 
-    // Get the Callback parameter type (first parameter)
-    let callback_param_type = result
-        .type_registry
+    let callback = registry
         .get_type(sig.parameters[0].type_id)
         .expect("callback parameter type not found");
+    assert_eq!(callback.get_name(), "Callback");
 
-    // Should be a Typedef
-    match &callback_param_type.kind {
-        BaseTypeKind::Typedef {
-            name,
-            aliased_type_id,
-        } => {
-            assert_eq!(name, "Callback");
-
-            // Follow typedef to the aliased type (should be pointer to function)
-            let aliased_type = result
-                .type_registry
-                .get_type(*aliased_type_id)
-                .expect("aliased type not found");
-
-            // Should be a pointer (pointer_depth = 1)
-            assert_eq!(
-                aliased_type.pointer_depth, 1,
-                "Callback should be a function pointer"
-            );
-
-            // The base type should be a Function
-            match &aliased_type.kind {
-                BaseTypeKind::Function {
-                    return_type_id,
-                    parameter_type_ids,
-                    is_variadic,
-                } => {
-                    // Verify return type is void
-                    assert!(return_type_id.is_none(), "Callback should return void");
-
-                    // Verify parameters: (int code, void* userdata)
-                    assert_eq!(
-                        parameter_type_ids.len(),
-                        2,
-                        "Callback should have 2 parameters"
-                    );
-
-                    // First parameter should be int
-                    let param0_type = result
-                        .type_registry
-                        .get_type(parameter_type_ids[0])
-                        .expect("callback param 0 type not found");
-                    let param0_str = param0_type.to_c_string(&result.type_registry);
-                    assert!(
-                        param0_str.contains("int"),
-                        "First parameter should be int, got: {}",
-                        param0_str
-                    );
-
-                    // Second parameter should be void*
-                    let param1_type = result
-                        .type_registry
-                        .get_type(parameter_type_ids[1])
-                        .expect("callback param 1 type not found");
-                    assert_eq!(
-                        param1_type.pointer_depth, 1,
-                        "Second parameter should be a pointer"
-                    );
-
-                    // Not variadic
-                    assert!(!is_variadic, "Callback should not be variadic");
-                }
-                _ => panic!(
-                    "Callback should resolve to a Function type, got: {:?}",
-                    aliased_type.kind
-                ),
-            }
-        }
-        _ => panic!(
-            "Callback should be a Typedef, got: {:?}",
-            callback_param_type.kind
-        ),
-    }
+    // typedef void (*Callback)(int code, void* userdata);
+    let function = callback
+        .as_function_pointer(registry)
+        .expect("Callback should be a function pointer");
+    assert!(
+        function.return_type_id.is_none(),
+        "Callback should return void"
+    );
+    let params: Vec<_> = function
+        .parameter_type_ids
+        .iter()
+        .map(|id| {
+            registry
+                .get_type(*id)
+                .expect("callback param type not found")
+        })
+        .collect();
+    assert_eq!(params.len(), 2, "Callback should have 2 parameters");
+    assert_eq!(params[0].to_c_string(registry), "int");
+    assert_eq!(params[1].pointer_depth, 1, "userdata should be a pointer");
+    assert!(!function.is_variadic, "Callback should not be variadic");
+
+    // the other parameter is a plain pointer, not a callback
+    let userdata = registry.get_type(sig.parameters[1].type_id).unwrap();
+    assert!(!userdata.is_function_pointer(registry));
 }
 
 #[test]
 /// test comparator typedef resolution to function pointer
 fn test_comparator_typedef_resolution() {
-    use dwarffi::type_registry::BaseTypeKind;
-
     let path = common::get_test_lib_path();
     let analyzer = DwarfAnalyzer::from_file(&path).expect("fail to load test library");
     let result = analyzer
         .extract_analysis(false)
         .expect("fail to extract analysis");
+    let registry = &result.type_registry;
 
     // Find sort_array function: void sort_array(int* arr, size_t count, Comparator cmp);
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "sort_array")
+        .function_by_name("sort_array")
         .expect("sort_array not found");
 
     assert_eq!(sig.parameters.len(), 3);
 
-    // Get the Comparator parameter type (third parameter)
-    let comparator_param_type = result
-        .type_registry
+    let comparator = registry
         .get_type(sig.parameters[2].type_id)
         .expect("comparator parameter type not found");
+    assert_eq!(comparator.get_name(), "Comparator");
+
+    // typedef int (*Comparator)(const void* a, const void* b);
+    let function = comparator
+        .as_function_pointer(registry)
+        .expect("Comparator should be a function pointer");
+    let return_type = function
+        .return_type_id
+        .and_then(|id| registry.get_type(id))
+        .expect("comparator return type not found");
+    assert_eq!(return_type.to_c_string(registry), "int");
 
-    // Should be a Typedef
-    match &comparator_param_type.kind {
-        BaseTypeKind::Typedef {
-            name,
-            aliased_type_id,
-        } => {
-            assert_eq!(name, "Comparator");
-
-            // Follow typedef to the aliased type (should be pointer to function)
-            let aliased_type = result
-                .type_registry
-                .get_type(*aliased_type_id)
-                .expect("aliased type not found");
-
-            // Should be a pointer (pointer_depth = 1)
-            assert_eq!(
-                aliased_type.pointer_depth, 1,
-                "Comparator should be a function pointer"
-            );
-
-            // The base type should be a Function
-            match &aliased_type.kind {
-                BaseTypeKind::Function {
-                    return_type_id,
-                    parameter_type_ids,
-                    is_variadic,
-                } => {
-                    // Verify return type is int
-                    let return_type = return_type_id
-                        .and_then(|id| result.type_registry.get_type(id))
-                        .expect("comparator return type not found");
-                    let return_str = return_type.to_c_string(&result.type_registry);
-                    assert!(
-                        return_str.contains("int"),
-                        "Comparator should return int, got: {}",
-                        return_str
-                    );
-
-                    // Verify parameters: (const void* a, const void* b)
-                    assert_eq!(
-                        parameter_type_ids.len(),
-                        2,
-                        "Comparator should have 2 parameters"
-                    );
-
-                    // Both parameters should be const void*
-                    for (i, param_id) in parameter_type_ids.iter().enumerate() {
-                        let param_type = result
-                            .type_registry
-                            .get_type(*param_id)
-                            .unwrap_or_else(|| panic!("comparator param {} type not found", i));
-                        assert_eq!(
-                            param_type.pointer_depth, 1,
-                            "Comparator param {} should be a pointer",
-                            i
-                        );
-                        assert!(
-                            param_type.is_const,
-                            "Comparator param {} should be const",
-                            i
-                        );
-                    }
-
-                    // Not variadic
-                    assert!(!is_variadic, "Comparator should not be variadic");
-                }
-                _ => panic!(
-                    "Comparator should resolve to a Function type, got: {:?}",
-                    aliased_type.kind
-                ),
-            }
-        }
-        _ => panic!(
-            "Comparator should be a Typedef, got: {:?}",
-            comparator_param_type.kind
-        ),
+    assert_eq!(
+        function.parameter_type_ids.len(),
+        2,
+        "Comparator should have 2 parameters"
+    );
+    for (i, param_id) in function.parameter_type_ids.iter().enumerate() {
+        let param_type = registry
+            .get_type(*param_id)
+            .unwrap_or_else(|| panic!("comparator param {} type not found", i));
+        assert_eq!(param_type.to_c_string(registry), "const void*");
     }
+    assert!(!function.is_variadic, "Comparator should not be variadic");
 }
 
 #[test]
@@ -736,9 +966,7 @@ fn test_function_pointer_signature_formatting() {
 
     // Find register_callback
     let sig = result
-        .signatures
-        .iter()
-        .find(|s| s.name == "register_callback")
+        .function_by_name("register_callback")
         .expect("register_callback not found");
 
     let sig_str = sig.to_string(&result.type_registry);
@@ -755,3 +983,1894 @@ fn test_function_pointer_signature_formatting() {
         sig_str
     );
 }
+
+#[test]
+/// plain `char*` parameters are strings; other pointers and chars are not
+fn test_c_string_pointer_detection() {
+    let path = common::get_test_lib_path();
+    let analyzer = DwarfAnalyzer::from_file(&path).expect("fail to load test library");
+    let result = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+    let registry = &result.type_registry;
+
+    let param_type = |func: &str, index: usize| {
+        let sig = result
+            .signatures
+            .iter()
+            .find(|s| s.name == func)
+            .unwrap_or_else(|| panic!("{} not found", func));
+        registry
+            .get_type(sig.parameters[index].type_id)
+            .expect("parameter type not found")
+    };
+
+    // const char* str
+    assert!(param_type("print_string", 0).is_c_string_pointer(registry));
+    // char* buffer, size_t length
+    assert!(param_type("process_buffer", 0).is_c_string_pointer(registry));
+    assert!(!param_type("process_buffer", 1).is_c_string_pointer(registry));
+    // Point* points
+    assert!(!param_type("complex_function", 1).is_c_string_pointer(registry));
+
+    let unsigned_char = result
+        .function_by_name("get_unsigned_char")
+        .expect("get_unsigned_char not found");
+    let return_type = registry.get_type(unsigned_char.return_type_id).unwrap();
+    assert!(!return_type.is_c_string_pointer(registry));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// a GNU-versioned library: `versioned` is defined at V2 (`versioned@@V2`)
+/// and, for compatibility, V1 (`versioned@V1`), which must count once and
+/// under the name DWARF uses
+fn test_versioned_and_weak_symbols() {
+    use dwarffi::SymbolInfo;
+    use std::process::Command;
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("versioned.c");
+    let map = dir.path().join("versioned.map");
+    let lib_path = dir.path().join("libversioned.so");
+    std::fs::write(
+        &source,
+        "int versioned(void) { return 2; }\n\
+         int versioned_v1(void) { return 1; }\n\
+         __asm__(\".symver versioned_v1, versioned@V1\");\n\
+         __attribute__((weak)) int overridable(void) { return 0; }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        &map,
+        "V1 { };\nV2 { global: versioned; overridable; local: *; } V1;\n",
+    )
+    .unwrap();
+
+    let status = Command::new(&cc)
+        .args(["-shared", "-fPIC", "-g", "-o"])
+        .arg(&lib_path)
+        .arg(format!("-Wl,--version-script={}", map.display()))
+        .arg(&source)
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        _ => {
+            eprintln!(
+                "{} unavailable or failed - skipping symbol version test",
+                cc
+            );
+            return;
+        }
+    }
+
+    let analyzer = DwarfAnalyzer::from_file(&lib_path).expect("fail to load library");
+    let mut symbols = analyzer.get_exported_symbols().unwrap();
+    symbols.sort_by(|a, b| a.name.cmp(&b.name));
+    let symbol = |name: &str, is_weak| SymbolInfo {
+        name: name.to_string(),
+        is_weak,
+        is_global: true,
+        version: Some("V2".to_string()),
+    };
+    assert_eq!(
+        symbols,
+        [symbol("overridable", true), symbol("versioned", false)]
+    );
+
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+    let mut names: Vec<&str> = result.signatures.iter().map(|s| s.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, ["overridable", "versioned"]);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// a DWARF 5 build with a name index resolves the same names. clang emits
+/// `DW_FORM_strx` names here, which need `.debug_str_offsets`; set `CC=clang`
+/// to exercise that path.
+fn test_dwarf5_string_forms() {
+    use std::process::Command;
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let lib_path = dir.path().join("libtestlib_dwarf5.so");
+
+    let status = Command::new(&cc)
+        .args(["-shared", "-fPIC", "-O0", "-gdwarf-5", "-gpubnames", "-o"])
+        .arg(&lib_path)
+        .arg(common::get_test_lib_dir().join("testlib.c"))
+        .arg("-lm")
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        _ => {
+            eprintln!("{} unavailable or failed - skipping DWARF 5 test", cc);
+            return;
+        }
+    }
+
+    let signatures = |path: &std::path::Path| {
+        let analyzer = DwarfAnalyzer::from_file(path).expect("fail to load library");
+        let result = analyzer
+            .extract_analysis(false)
+            .expect("fail to extract analysis");
+        assert!(result.signatures.iter().all(|s| !s.name.is_empty()));
+        let mut sigs: Vec<String> = result
+            .signatures
+            .iter()
+            .map(|s| s.to_string(&result.type_registry))
+            .collect();
+        sigs.sort();
+        sigs
+    };
+
+    assert_eq!(
+        signatures(&lib_path),
+        signatures(&common::get_test_lib_path())
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// with `-gdwarf-2 -gstrict-dwarf`, gcc writes member offsets as
+/// `DW_OP_plus_uconst` expressions; the layout must match the default build
+fn test_dwarf2_member_location_expressions() {
+    use dwarffi::BaseTypeKind;
+    use std::process::Command;
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let lib_path = dir.path().join("libtestlib_dwarf2.so");
+
+    let status = Command::new(&cc)
+        .args([
+            "-shared",
+            "-fPIC",
+            "-O0",
+            "-gdwarf-2",
+            "-gstrict-dwarf",
+            "-o",
+        ])
+        .arg(&lib_path)
+        .arg(common::get_test_lib_dir().join("testlib.c"))
+        .arg("-lm")
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        _ => {
+            eprintln!("{} unavailable or failed - skipping DWARF 2 test", cc);
+            return;
+        }
+    }
+
+    // (struct, field, offset) for every named struct
+    let layouts = |path: &std::path::Path| {
+        let analyzer = DwarfAnalyzer::from_file(path).expect("fail to load library");
+        let result = analyzer
+            .extract_analysis(false)
+            .expect("fail to extract analysis");
+        let mut layouts: Vec<(String, String, usize)> = result
+            .type_registry
+            .all_types()
+            .filter_map(|t| match &t.kind {
+                BaseTypeKind::Struct { name, fields, .. } if !name.starts_with('<') => Some(
+                    fields
+                        .iter()
+                        .map(|f| (name.clone(), f.name.clone(), f.offset))
+                        .collect::<Vec<_>>(),
+                ),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        layouts.sort();
+        layouts.dedup();
+        layouts
+    };
+
+    let dwarf2 = layouts(&lib_path);
+    assert!(
+        dwarf2.iter().any(|(_, _, offset)| *offset > 0),
+        "all fields at offset 0"
+    );
+    assert_eq!(dwarf2, layouts(&common::get_test_lib_path()));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// both `SHF_COMPRESSED` sections and GNU `.zdebug_*` sections decompress to
+/// the same signatures, and a corrupt one is an error rather than empty output
+fn test_compressed_debug_sections() {
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let compress = |format: &str| {
+        let path = dir.path().join(format!("libtestlib_{}.so", format));
+        let status = Command::new("objcopy")
+            .arg(format!("--compress-debug-sections={}", format))
+            .arg(common::get_test_lib_path())
+            .arg(&path)
+            .status();
+        matches!(status, Ok(status) if status.success()).then_some(path)
+    };
+    let (Some(zlib), Some(zlib_gnu)) = (compress("zlib"), compress("zlib-gnu")) else {
+        eprintln!("objcopy unavailable or failed - skipping compressed sections test");
+        return;
+    };
+
+    let signatures = |path: &std::path::Path| {
+        let analyzer = DwarfAnalyzer::from_file(path).expect("fail to load library");
+        let result = analyzer
+            .extract_analysis(false)
+            .expect("fail to extract analysis");
+        let mut sigs: Vec<String> = result
+            .signatures
+            .iter()
+            .map(|s| s.to_string(&result.type_registry))
+            .collect();
+        sigs.sort();
+        sigs
+    };
+
+    let expected = signatures(&common::get_test_lib_path());
+    assert_eq!(signatures(&zlib), expected);
+    assert_eq!(signatures(&zlib_gnu), expected);
+
+    // a `.zdebug_*` section starts with "ZLIB" and its big-endian inflated
+    // size; claiming a different size makes decompression fail
+    let mut data = std::fs::read(&zlib_gnu).unwrap();
+    let header = data
+        .windows(4)
+        .position(|w| w == b"ZLIB")
+        .expect("no GNU compressed section");
+    data[header + 4] ^= 0xff;
+
+    let analyzer = DwarfAnalyzer::new(data);
+    match analyzer.extract_analysis(false) {
+        Err(DwarffiError::SectionDecompress { section, .. }) => {
+            assert!(section.starts_with(".debug_"), "{}", section)
+        }
+        other => panic!("expected SectionDecompress, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+/// every extracted function carries its entry address, matching the symbol
+fn test_function_addresses_match_symbols() {
+    let analyzer =
+        DwarfAnalyzer::from_file(&common::get_test_lib_path()).expect("fail to load library");
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+    let symbols = analyzer
+        .get_function_addresses()
+        .expect("fail to read symbols");
+
+    let mut addresses = std::collections::HashSet::new();
+    for sig in &result.signatures {
+        let address = sig
+            .address
+            .unwrap_or_else(|| panic!("{} has no address", sig.name));
+        let symbol = symbols
+            .get(&sig.name)
+            .or_else(|| symbols.get(&format!("_{}", sig.name)));
+        assert_eq!(Some(&vec![address]), symbol, "{}", sig.name);
+        assert!(addresses.insert(address), "{} shares an address", sig.name);
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// `static` functions of the same name in two files keep their own addresses
+fn test_static_functions_sharing_a_name_keep_their_addresses() {
+    use std::process::Command;
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let lib_path = dir.path().join("libshared_names.so");
+    let mut sources = Vec::new();
+    for (file, body) in [("first", "x + 1"), ("second", "x * 2 + 3")] {
+        let source = dir.path().join(format!("{}.c", file));
+        std::fs::write(
+            &source,
+            format!(
+                "static int helper(int x) {{ return {}; }}\n\
+                 int {}_entry(int x) {{ return helper(x); }}\n",
+                body, file
+            ),
+        )
+        .expect("fail to write source");
+        sources.push(source);
+    }
+
+    let status = Command::new(&cc)
+        .args(["-shared", "-fPIC", "-O0", "-g", "-o"])
+        .arg(&lib_path)
+        .args(&sources)
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        _ => {
+            eprintln!("{} unavailable or failed - skipping shared name test", cc);
+            return;
+        }
+    }
+
+    let analyzer = DwarfAnalyzer::from_file(&lib_path).expect("fail to load library");
+    let result = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+    let symbols = analyzer
+        .get_function_addresses()
+        .expect("fail to read symbols");
+
+    let mut helpers: Vec<u64> = result
+        .signatures
+        .iter()
+        .filter(|sig| sig.name == "helper")
+        .map(|sig| sig.address.expect("helper has no address"))
+        .collect();
+    helpers.sort();
+    let mut expected = symbols["helper"].clone();
+    expected.sort();
+    assert_eq!(helpers.len(), 2);
+    assert_eq!(helpers, expected);
+}
+
+#[test]
+/// `DW_AT_noreturn` and `DW_AT_inline` on subprograms
+fn test_noreturn_and_inline_attributes() {
+    use dwarffi::InlineKind;
+
+    let analyzer =
+        DwarfAnalyzer::from_file(&common::get_test_lib_path()).expect("fail to load library");
+    let result = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+    let find = |name: &str| {
+        result
+            .signatures
+            .iter()
+            .find(|s| s.name == name)
+            .unwrap_or_else(|| panic!("{} not found", name))
+    };
+
+    assert!(find("fatal_error").is_noreturn);
+    assert!(!find("add_two_ints").is_noreturn);
+    assert_eq!(find("clamp_percent").inline_kind, InlineKind::NotInlined);
+
+    // the always_inline helper has no out-of-line copy
+    let clamp = find("clamp");
+    assert_eq!(clamp.inline_kind, InlineKind::DeclaredInlined);
+    assert_eq!(clamp.address, None);
+}
+
+#[test]
+/// `#define` constants from testlib.h, which the makefile builds with -g3
+fn test_extract_macros() {
+    use dwarffi::MacroValue;
+
+    let analyzer =
+        DwarfAnalyzer::from_file(&common::get_test_lib_path()).expect("fail to load library");
+    let macros = analyzer.extract_macros().expect("fail to extract macros");
+
+    let value = |name: &str| {
+        macros
+            .iter()
+            .find(|m| m.name == name)
+            .map(|m| m.value.clone())
+    };
+    assert_eq!(value("TESTLIB_MAX_POINTS"), Some(MacroValue::Integer(1024)));
+    assert_eq!(value("TESTLIB_FLAG_MASK"), Some(MacroValue::Integer(15)));
+    assert_eq!(value("TESTLIB_INVALID"), Some(MacroValue::Integer(-1)));
+    assert_eq!(
+        value("TESTLIB_VERSION"),
+        Some(MacroValue::String("1.2.0".to_string()))
+    );
+
+    // function-like, valueless, and system header macros are left out
+    assert_eq!(value("TESTLIB_SQUARE"), None);
+    assert_eq!(value("TESTLIB_H"), None);
+    assert_eq!(value("INT32_MAX"), None);
+
+    let max_points = macros
+        .iter()
+        .find(|m| m.name == "TESTLIB_MAX_POINTS")
+        .unwrap();
+    assert!(
+        max_points.file.ends_with("testlib.h"),
+        "{}",
+        max_points.file
+    );
+
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+    assert_eq!(result.macros, macros);
+}
+
+#[test]
+/// the anonymous enum in testlib.h, but not the typedef'd `Status` or `Color`
+fn test_anonymous_enum_constants() {
+    let analyzer =
+        DwarfAnalyzer::from_file(&common::get_test_lib_path()).expect("fail to load library");
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+
+    assert_eq!(
+        result.anonymous_constants(),
+        [
+            ("TESTLIB_STATE_INITIAL_COUNT".to_string(), 0),
+            ("TESTLIB_STATE_BUFFER_SIZE".to_string(), 256),
+        ]
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// DWARF 4 records macros in `.debug_macinfo` rather than `.debug_macro`
+fn test_extract_macros_from_macinfo() {
+    use std::process::Command;
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let lib_path = dir.path().join("libtestlib_macinfo.so");
+
+    let status = Command::new(&cc)
+        .args([
+            "-shared",
+            "-fPIC",
+            "-O0",
+            "-g3",
+            "-gdwarf-4",
+            "-gstrict-dwarf",
+            "-o",
+        ])
+        .arg(&lib_path)
+        .arg(common::get_test_lib_dir().join("testlib.c"))
+        .arg("-lm")
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        _ => {
+            eprintln!("{} unavailable or failed - skipping macinfo test", cc);
+            return;
+        }
+    }
+
+    let names = |path: &std::path::Path| {
+        let analyzer = DwarfAnalyzer::from_file(path).expect("fail to load library");
+        let mut names: Vec<String> = analyzer
+            .extract_macros()
+            .expect("fail to extract macros")
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        names.sort();
+        names
+    };
+
+    let macinfo = names(&lib_path);
+    assert!(macinfo.contains(&"TESTLIB_MAX_POINTS".to_string()));
+    assert_eq!(macinfo, names(&common::get_test_lib_path()));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// a K&R-style definition has no prototype, so it takes unspecified
+/// arguments and lists as `()` rather than `(void)`
+fn test_unprototyped_functions_have_empty_parameter_lists() {
+    use std::process::Command;
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("legacy.c");
+    std::fs::write(
+        &source,
+        "int legacy() { return 0; }\n\
+         int modern(void) { return 1; }\n",
+    )
+    .unwrap();
+    let lib_path = dir.path().join("liblegacy.so");
+    let status = Command::new(&cc)
+        .args(["-std=c99", "-shared", "-fPIC", "-g", "-O0", "-o"])
+        .arg(&lib_path)
+        .arg(&source)
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("{} unavailable or failed - skipping prototype test", cc);
+        return;
+    }
+
+    let result = DwarfAnalyzer::from_file(&lib_path)
+        .expect("fail to load library")
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+    let signature = |name: &str| {
+        result
+            .signatures
+            .iter()
+            .find(|s| s.name == name)
+            .unwrap_or_else(|| panic!("{} not found", name))
+    };
+
+    let legacy = signature("legacy");
+    assert!(!legacy.is_prototyped);
+    assert_eq!(legacy.to_string(&result.type_registry), "int legacy()");
+
+    let modern = signature("modern");
+    assert!(modern.is_prototyped);
+    assert_eq!(modern.to_string(&result.type_registry), "int modern(void)");
+    assert_eq!(
+        modern.to_string_with_void_param(&result.type_registry, false),
+        "int modern()"
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// a running program's libraries are found through `/proc/PID/maps`
+fn test_extract_analysis_pid() {
+    use std::io::{Read, Write};
+    use std::process::{Command, Stdio};
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("main.c");
+    // says it's running once the libraries are loaded, then waits for stdin
+    std::fs::write(
+        &source,
+        "#include <unistd.h>\n\
+         int add_two_ints(int a, int b);\n\
+         int main(void) {\n\
+             char c;\n\
+             if (write(1, \"r\", 1) != 1) return 1;\n\
+             return read(0, &c, 1) < 0 ? 1 : add_two_ints(0, 0);\n\
+         }\n",
+    )
+    .unwrap();
+    let program = dir.path().join("uses_testlib");
+    let lib_dir = common::get_test_lib_dir();
+    let status = Command::new(&cc)
+        .args(["-g", "-O0", "-o"])
+        .arg(&program)
+        .arg(&source)
+        .arg(format!("-L{}", lib_dir.display()))
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-ltestlib")
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("{} unavailable or failed - skipping pid test", cc);
+        return;
+    }
+
+    let mut child = Command::new(&program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("fail to run program");
+    let mut ready = [0u8];
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_exact(&mut ready)
+        .expect("program did not start");
+
+    let result = DwarfAnalyzer::extract_analysis_pid(child.id(), &ExtractOptions::new());
+    child.stdin.take().unwrap().write_all(b"\n").unwrap();
+    child.wait().unwrap();
+
+    let result = result.expect("fail to analyze process");
+    let add = result
+        .function_by_name("add_two_ints")
+        .expect("add_two_ints not found in the process's libraries");
+    assert_eq!(
+        add.to_string(&result.type_registry),
+        "int add_two_ints(int a, int b)"
+    );
+}
+
+#[test]
+/// test target metadata matches the host that built the test library
+fn test_target_info() {
+    use dwarffi::Endianness;
+
+    let path = common::get_test_lib_path();
+    let analyzer = DwarfAnalyzer::from_file(&path).expect("fail to load test library");
+    let result = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+
+    // test library is compiled on the machine running the tests
+    assert_eq!(result.target.architecture, std::env::consts::ARCH);
+    assert_eq!(
+        result.target.pointer_width,
+        std::mem::size_of::<*const u8>()
+    );
+    assert_eq!(result.target.endianness, Endianness::host());
+    assert!(result.target.matches_host());
+
+    let target = analyzer.target_info().expect("fail to read target info");
+    assert_eq!(target, result.target);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// a wasm module keeps DWARF in custom sections. no wasm toolchain is
+/// assumed, so the DWARF of a 32-bit ELF build is wrapped in a module that
+/// exports the function, which is what clang's wasm32 output looks like to
+/// the analyzer.
+fn test_wasm_module_with_dwarf() {
+    use object::{Object, ObjectSection};
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("buffer.c");
+    std::fs::write(
+        &source,
+        "typedef struct { const char* data; long count; } Buffer;\n\
+         long buffer_count(const Buffer* buffer) { return buffer->count; }\n",
+    )
+    .unwrap();
+    let elf = dir.path().join("libbuffer.so");
+    let status = Command::new("cc")
+        .args(["-m32", "-g", "-O0", "-shared", "-fPIC", "-nostdlib", "-o"])
+        .arg(&elf)
+        .arg(&source)
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("cc -m32 unavailable or failed - skipping wasm test");
+        return;
+    }
+
+    fn leb128(mut value: usize, out: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                return;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+    fn section(id: u8, payload: &[u8], out: &mut Vec<u8>) {
+        out.push(id);
+        leb128(payload.len(), out);
+        out.extend_from_slice(payload);
+    }
+
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+    // type (i32) -> i32, one function of that type returning 0
+    section(1, &[1, 0x60, 1, 0x7f, 1, 0x7f], &mut module);
+    section(3, &[1, 0], &mut module);
+    let mut exports = vec![1];
+    leb128("buffer_count".len(), &mut exports);
+    exports.extend_from_slice(b"buffer_count");
+    exports.extend_from_slice(&[0, 0]);
+    section(7, &exports, &mut module);
+    section(10, &[1, 4, 0, 0x41, 0, 0x0b], &mut module);
+
+    let elf_data = std::fs::read(&elf).unwrap();
+    let elf_file = object::File::parse(&elf_data[..]).unwrap();
+    for elf_section in elf_file.sections() {
+        let name = elf_section.name().unwrap();
+        if name.starts_with(".debug_") {
+            let mut payload = Vec::new();
+            leb128(name.len(), &mut payload);
+            payload.extend_from_slice(name.as_bytes());
+            payload.extend_from_slice(&elf_section.uncompressed_data().unwrap());
+            section(0, &payload, &mut module);
+        }
+    }
+
+    let wasm = dir.path().join("buffer.wasm");
+    std::fs::write(&wasm, &module).unwrap();
+
+    let analyzer = DwarfAnalyzer::from_file(&wasm).expect("fail to load wasm module");
+    let target = analyzer.target_info().expect("fail to read target info");
+    assert_eq!(target.architecture, "wasm32");
+    assert_eq!(target.pointer_width, 4);
+
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+    let signatures: Vec<String> = result
+        .signatures
+        .iter()
+        .map(|s| s.to_string(&result.type_registry))
+        .collect();
+    assert_eq!(signatures, ["long int buffer_count(const Buffer* buffer)"]);
+
+    // the parameter is a 4 byte pointer to a struct of two 4 byte members
+    let registry = &result.type_registry;
+    let param_type = result.signatures[0].parameters[0].type_id;
+    assert_eq!(registry.size_of(param_type, target.pointer_width), Some(4));
+    let buffer = registry
+        .resolve_typedef(param_type)
+        .expect("Buffer not found");
+    assert_eq!(registry.size_of(buffer.id, target.pointer_width), Some(8));
+}
+
+#[test]
+/// without export filtering, `is_exported` still reflects the symbol table
+fn test_all_functions_mark_internal_ones() {
+    let analyzer =
+        DwarfAnalyzer::from_file(&common::get_test_lib_path()).expect("fail to load library");
+    let result = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+
+    let is_exported = |name: &str| {
+        result
+            .signatures
+            .iter()
+            .find(|s| s.name == name)
+            .unwrap_or_else(|| panic!("{} not found", name))
+            .is_exported
+    };
+    assert!(is_exported("add_two_ints"));
+    assert!(!is_exported("internal_helper"));
+}
+
+#[test]
+/// `typedef struct { ... } BoundingBox` is only ever called BoundingBox
+fn test_anonymous_aggregate_named_by_typedef() {
+    let analyzer =
+        DwarfAnalyzer::from_file(&common::get_test_lib_path()).expect("fail to load library");
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+    let registry = &result.type_registry;
+
+    let sig = result
+        .function_by_name("create_bounding_box")
+        .expect("create_bounding_box not found");
+    let aggregate = registry
+        .resolve_typedef(sig.return_type_id)
+        .expect("BoundingBox not found");
+    assert!(aggregate.is_anonymous_aggregate());
+
+    assert_eq!(
+        registry.display_name(aggregate.id).as_deref(),
+        Some("BoundingBox")
+    );
+    assert_eq!(aggregate.to_c_string(registry), "BoundingBox");
+}
+
+#[test]
+/// each distinct compiler identity is listed once
+fn test_producers() {
+    let analyzer =
+        DwarfAnalyzer::from_file(&common::get_test_lib_path()).expect("fail to load library");
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+
+    // the test library is one compilation unit
+    assert_eq!(result.producers.len(), 1, "{:?}", result.producers);
+    let producer = &result.producers[0];
+    assert!(
+        producer.contains("GNU C") || producer.contains("clang"),
+        "unexpected producer: {}",
+        producer
+    );
+    assert_eq!(analyzer.producers().unwrap(), result.producers);
+
+    // analyzing the library twice doesn't repeat it
+    let path = common::get_test_lib_path();
+    let merged =
+        DwarfAnalyzer::extract_analysis_multi_with(&[path.clone(), path], &ExtractOptions::new())
+            .expect("fail to extract from multiple files");
+    assert_eq!(merged.producers, result.producers);
+}
+
+/// file offset and size of a section in the test library
+fn section_range(data: &[u8], name: &str) -> (usize, usize) {
+    use object::{Object, ObjectSection};
+    let file = object::File::parse(data).expect("fail to parse test library");
+    let (offset, size) = file
+        .section_by_name(name)
+        .and_then(|s| s.file_range())
+        .unwrap_or_else(|| panic!("{} not found", name));
+    (offset as usize, size as usize)
+}
+
+#[test]
+/// a compilation unit with an unreadable header is skipped rather than
+/// failing the whole analysis
+fn test_malformed_unit_header_is_skipped() {
+    let mut data = std::fs::read(common::get_test_lib_path()).expect("fail to read library");
+    let (offset, _) = section_range(&data, ".debug_info");
+    // the 2-byte version follows the 4-byte unit length
+    data[offset + 4..offset + 6].copy_from_slice(&99u16.to_le_bytes());
+
+    let result = DwarfAnalyzer::new(data)
+        .extract_analysis(false)
+        .expect("malformed unit should be skipped");
+    assert!(result.signatures.is_empty());
+}
+
+#[test]
+#[cfg(unix)]
+/// with `continue_on_error`, a unit that can't be read is reported alongside
+/// the functions of the units that could
+fn test_continue_on_error_collects_unit_errors() {
+    use dwarffi::UnitId;
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let good = dir.path().join("good.c");
+    let bad = dir.path().join("bad.c");
+    std::fs::write(&good, "int good_fn(int x) { return x + 1; }\n").unwrap();
+    std::fs::write(&bad, "int bad_fn(int x) { return x - 1; }\n").unwrap();
+    let library = dir.path().join("libmixed.so");
+    let status = Command::new("cc")
+        .args(["-g", "-O0", "-shared", "-fPIC", "-o"])
+        .arg(&library)
+        .args([&good, &bad])
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("cc unavailable or failed - skipping continue-on-error test");
+        return;
+    }
+
+    // find the unit compiled from bad.c and where its abbreviations start
+    let mut data = std::fs::read(&library).expect("fail to read library");
+    let (bad_unit, abbrev_offset) = {
+        use object::{Object, ObjectSection};
+        let file = object::File::parse(&data[..]).expect("fail to parse library");
+        let sections = gimli::DwarfSections::load(|id| -> gimli::Result<_> {
+            Ok(file
+                .section_by_name(id.name())
+                .and_then(|section| section.uncompressed_data().ok())
+                .unwrap_or_default())
+        })
+        .expect("fail to load DWARF");
+        let dwarf =
+            sections.borrow(|section| gimli::EndianSlice::new(section, gimli::LittleEndian));
+        let mut units = dwarf.units();
+        let mut found = None;
+        while let Some(header) = units.next().expect("fail to read unit header") {
+            let unit = dwarf.unit(header).expect("fail to parse unit");
+            let name = unit.name.map(|name| name.to_string_lossy().into_owned());
+            if name.is_some_and(|name| name.ends_with("bad.c")) {
+                let gimli::UnitSectionOffset::DebugInfoOffset(offset) = header.offset() else {
+                    unreachable!();
+                };
+                let unit = UnitId {
+                    source: 0,
+                    offset: offset.0 as u64,
+                };
+                found = Some((unit, header.debug_abbrev_offset().0));
+            }
+        }
+        found.expect("bad.c unit not found")
+    };
+
+    // the first abbreviation is a one-byte code and tag followed by the
+    // children flag, which must be 0 or 1
+    let (abbrev_section, _) = section_range(&data, ".debug_abbrev");
+    let children = abbrev_section + abbrev_offset + 2;
+    assert!(data[children] <= 1, "unexpected abbreviation layout");
+    data[children] = 7;
+
+    let analyzer = DwarfAnalyzer::new(data);
+    let options = ExtractOptions::new().exported_only(false);
+    let result = analyzer
+        .extract_analysis_with(&options.clone().continue_on_error(true))
+        .expect("fail to extract analysis");
+    let names: Vec<_> = result.signatures.iter().map(|s| s.name.as_str()).collect();
+    assert!(names.contains(&"good_fn"), "{:?}", names);
+    assert!(!names.contains(&"bad_fn"), "{:?}", names);
+    assert_eq!(result.errors.len(), 1, "{:?}", result.errors);
+    assert_eq!(result.errors[0].unit, Some(bad_unit));
+    assert_eq!(result.errors[0].function, None);
+
+    // without it, the unit is still skipped but not reported
+    let result = analyzer
+        .extract_analysis_with(&options)
+        .expect("fail to extract analysis");
+    assert!(result.errors.is_empty());
+    assert_eq!(result.signatures.len(), names.len());
+}
+
+#[test]
+/// corrupted and truncated debug info may fail to analyze, but never panics
+fn test_corrupted_debug_info_does_not_panic() {
+    let original = std::fs::read(common::get_test_lib_path()).expect("fail to read library");
+    let sections = [".debug_info", ".debug_abbrev", ".debug_str", ".debug_line"]
+        .map(|name| section_range(&original, name));
+
+    // xorshift, so failures reproduce
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize
+    };
+
+    for _ in 0..100 {
+        let mut data = original.clone();
+        let (offset, size) = sections[next() % sections.len()];
+        for _ in 0..1 + next() % 8 {
+            data[offset + next() % size] = next() as u8;
+        }
+        let analyzer = DwarfAnalyzer::new(data);
+        let _ = analyzer.extract_analysis(false);
+        let _ = analyzer.extract_macros();
+    }
+
+    for _ in 0..20 {
+        let data = original[..next() % original.len()].to_vec();
+        let _ = DwarfAnalyzer::new(data).extract_analysis(false);
+    }
+}
+
+#[test]
+#[cfg(unix)]
+/// a struct that points to itself refers to itself inside its own fields,
+/// with the same pointer id wherever resolution started
+fn test_self_referential_struct() {
+    use dwarffi::BaseTypeKind;
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("list.c");
+    std::fs::write(
+        &source,
+        "struct Node { int value; struct Node* next; };\n\
+         int node_len(const struct Node* n) { int c = 0; for (; n; n = n->next) c++; return c; }\n\
+         struct Node* node_next(struct Node* n) { return n->next; }\n",
+    )
+    .unwrap();
+    let library = dir.path().join("liblist.so");
+    let status = Command::new("cc")
+        .args(["-g", "-O0", "-shared", "-fPIC", "-o"])
+        .arg(&library)
+        .arg(&source)
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("cc unavailable or failed - skipping self-referential struct test");
+        return;
+    }
+
+    let result = DwarfAnalyzer::from_file(&library)
+        .expect("fail to load library")
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+    let registry = &result.type_registry;
+
+    let sig = result
+        .function_by_name("node_len")
+        .expect("node_len not found");
+    assert_eq!(
+        sig.to_string(registry),
+        "int node_len(const struct Node* n)"
+    );
+
+    let node = registry
+        .get_type(sig.parameters[0].type_id)
+        .expect("parameter type not found");
+    let BaseTypeKind::Struct { fields, .. } = &node.kind else {
+        panic!("expected a struct, got {:?}", node.kind);
+    };
+    let next = registry
+        .get_type(fields[1].type_id)
+        .expect("next field type not found");
+    assert_eq!(next.pointer_depth, 1);
+    assert_eq!(next.kind, node.kind);
+    assert!(!registry.is_opaque(next.id));
+
+    let node_next = result
+        .function_by_name("node_next")
+        .expect("node_next not found");
+    assert_eq!(node_next.return_type_id, next.id);
+    assert_eq!(node_next.parameters[0].type_id, next.id);
+}
+
+#[test]
+#[cfg(unix)]
+/// a union pointing to itself keeps its tag, so it round-trips as `union U*`
+/// rather than `struct U*`
+fn test_self_referential_union() {
+    use dwarffi::BaseTypeKind;
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("chain.c");
+    std::fs::write(
+        &source,
+        "typedef union U { int i; union U* next; } U;\n\
+         int u_value(U u) { return u.i; }\n",
+    )
+    .unwrap();
+    let library = dir.path().join("libchain.so");
+    let status = Command::new("cc")
+        .args(["-g", "-O0", "-shared", "-fPIC", "-o"])
+        .arg(&library)
+        .arg(&source)
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("cc unavailable or failed - skipping self-referential union test");
+        return;
+    }
+
+    let result = DwarfAnalyzer::from_file(&library)
+        .expect("fail to load library")
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+    let registry = &result.type_registry;
+
+    let sig = result
+        .function_by_name("u_value")
+        .expect("u_value not found");
+    assert_eq!(sig.to_string(registry), "int u_value(U u)");
+
+    let union = registry
+        .resolve_typedef(sig.parameters[0].type_id)
+        .expect("parameter type not found");
+    let BaseTypeKind::Union { variants, .. } = &union.kind else {
+        panic!("expected a union, got {:?}", union.kind);
+    };
+    let next = registry
+        .get_type(variants[1].type_id)
+        .expect("next variant type not found");
+    assert_eq!(next.to_c_string(registry), "union U*");
+    assert_eq!(next.c_declaration(registry, "next"), "union U *next");
+    assert_eq!(next.unqualified_id(), union.id);
+    assert!(!registry.is_opaque(next.id));
+    assert!(!registry.is_opaque(union.id));
+}
+
+#[test]
+/// type resolution can be driven over a unit loaded outside the analyzer
+fn test_type_resolver_over_custom_loader() {
+    use dwarffi::TypeResolver;
+    use object::{Object, ObjectSection};
+
+    let data = std::fs::read(common::get_test_lib_path()).expect("fail to read library");
+    let file = object::File::parse(&data[..]).expect("fail to parse library");
+    let sections = gimli::DwarfSections::load(|id| -> gimli::Result<_> {
+        Ok(file
+            .section_by_name(id.name())
+            .and_then(|section| section.uncompressed_data().ok())
+            .unwrap_or_default())
+    })
+    .expect("fail to load DWARF");
+    let dwarf = sections.borrow(|section| gimli::EndianSlice::new(section, gimli::LittleEndian));
+
+    let mut units = dwarf.units();
+    let header = units.next().unwrap().expect("no compilation unit");
+    let unit = dwarf.unit(header).expect("fail to parse unit");
+
+    // the first parameter of `move_point`
+    let mut entries = unit.entries();
+    let mut in_move_point = false;
+    let mut parameter_type = None;
+    while let Some((_, entry)) = entries.next_dfs().unwrap() {
+        if entry.tag() == gimli::DW_TAG_subprogram {
+            let name = entry
+                .attr_value(gimli::DW_AT_name)
+                .unwrap()
+                .and_then(|value| dwarf.attr_string(&unit, value).ok());
+            in_move_point = name.is_some_and(|name| name.slice() == b"move_point");
+        } else if in_move_point && entry.tag() == gimli::DW_TAG_formal_parameter {
+            if let Some(gimli::AttributeValue::UnitRef(offset)) =
+                entry.attr_value(gimli::DW_AT_type).unwrap()
+            {
+                parameter_type = Some(offset);
+            }
+            break;
+        }
+    }
+
+    let mut resolver = TypeResolver::new(&dwarf, &unit);
+    let id = resolver
+        .build_type_registry_entry(parameter_type.expect("move_point not found"))
+        .expect("fail to resolve parameter type");
+    let void_id = resolver.get_void_type_id().expect("fail to register void");
+    let registry = resolver.into_registry();
+
+    assert_eq!(
+        registry.get_type(id).unwrap().to_c_string(&registry),
+        "Point*"
+    );
+    assert_eq!(registry.get_type(void_id).unwrap().get_name(), "void");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// optimized builds may list parameters behind the abstract instance of an
+/// inline function, or reorder what they describe; either way they must come
+/// out in source order, with gcc and clang alike
+fn test_parameter_order_matches_source() {
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let inlined = dir.path().join("inlined.c");
+    std::fs::write(
+        &inlined,
+        "int scale(int value, int factor, int offset) { return value * factor + offset; }\n\
+         int scale_twice(int value) { return scale(scale(value, 2, 1), 3, 0); }\n",
+    )
+    .unwrap();
+
+    for cc in ["gcc", "clang"] {
+        let lib_path = dir.path().join(format!("libtestlib_{cc}.so"));
+        let status = Command::new(cc)
+            // without interposition `scale` is inlined into `scale_twice`,
+            // which leaves its exported copy pointing at an abstract instance
+            .args(["-O2", "-g", "-shared", "-fPIC", "-fno-semantic-interposition"])
+            .arg("-o")
+            .arg(&lib_path)
+            .arg(common::get_test_lib_dir().join("testlib.c"))
+            .arg(&inlined)
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            _ => {
+                eprintln!("{} unavailable or failed - skipping", cc);
+                continue;
+            }
+        }
+
+        let analyzer = DwarfAnalyzer::from_file(&lib_path).expect("fail to load library");
+        let result = analyzer
+            .extract_analysis(false)
+            .expect("fail to extract analysis");
+        let parameters = |name: &str| {
+            let signature = result
+                .signatures
+                .iter()
+                .find(|s| s.name == name)
+                .unwrap_or_else(|| panic!("{cc}: {name} not found"));
+            let offsets: Vec<u64> = signature
+                .parameters
+                .iter()
+                .map(|p| p.dwarf_offset.expect("parameter without an offset"))
+                .collect();
+            let names: Vec<String> = signature
+                .parameters
+                .iter()
+                .map(|p| p.name.clone())
+                .collect();
+            (names, offsets)
+        };
+
+        let (names, offsets) = parameters("complex_function");
+        assert_eq!(
+            names,
+            ["name", "points", "point_count", "bounds", "out_status"],
+            "{cc}"
+        );
+        assert!(offsets.is_sorted(), "{cc}: {offsets:?}");
+
+        let (names, _) = parameters("scale");
+        assert_eq!(names, ["value", "factor", "offset"], "{cc}");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// C++ functions nested in namespaces and classes, which have no linkage
+/// name when their linkage is internal, are qualified so they don't collide
+fn test_cpp_members_are_qualified() {
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("members.cpp");
+    let lib_path = dir.path().join("libmembers.so");
+    std::fs::write(
+        &source,
+        "namespace geometry {\n\
+         struct Box { int w; int size() const; };\n\
+         int Box::size() const { return w; }\n\
+         namespace { int helper() { return 1; } }\n\
+         int area() { return helper(); }\n\
+         }\n\
+         namespace text {\n\
+         namespace { int helper() { return 2; } }\n\
+         int length() { return helper(); }\n\
+         }\n\
+         namespace ns { extern \"C\" int c_in_namespace(int x) { return x; } }\n\
+         extern \"C\" int plain_c(int x) { return x; }\n",
+    )
+    .unwrap();
+
+    let cxx = std::env::var("CXX").unwrap_or_else(|_| "c++".to_string());
+    let status = Command::new(&cxx)
+        .args(["-shared", "-fPIC", "-g", "-O0", "-o"])
+        .arg(&lib_path)
+        .arg(&source)
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        _ => {
+            eprintln!("{} unavailable or failed - skipping C++ member test", cxx);
+            return;
+        }
+    }
+
+    let analyzer = DwarfAnalyzer::from_file(&lib_path).expect("fail to load library");
+    let names = |options: &dwarffi::ExtractOptions| {
+        let result = analyzer
+            .extract_analysis_with(options)
+            .expect("fail to extract analysis");
+        let mut names: Vec<String> = result.signatures.into_iter().map(|s| s.name).collect();
+        names.sort();
+        names
+    };
+
+    let all = names(&dwarffi::ExtractOptions::new().exported_only(false));
+    assert!(all.contains(&"geometry::(anonymous namespace)::helper".to_string()));
+    assert!(all.contains(&"text::(anonymous namespace)::helper".to_string()));
+    assert!(all.contains(&"c_in_namespace".to_string()));
+    assert!(!all.contains(&"helper".to_string()));
+
+    let extern_c = names(
+        &dwarffi::ExtractOptions::new()
+            .exported_only(false)
+            .extern_c_only(true),
+    );
+    assert_eq!(extern_c, ["c_in_namespace", "plain_c"]);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// a C++ method's implicit `this` is flagged, and virtual methods can be
+/// skipped
+fn test_cpp_this_and_virtual_methods() {
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("shape.cpp");
+    let lib_path = dir.path().join("libshape.so");
+    std::fs::write(
+        &source,
+        "struct Shape { int w; virtual int area() const; int scale(int by); };\n\
+         int Shape::area() const { return w * w; }\n\
+         int Shape::scale(int by) { w *= by; return w; }\n\
+         extern \"C\" int plain(int x) { return x; }\n",
+    )
+    .unwrap();
+
+    let cxx = std::env::var("CXX").unwrap_or_else(|_| "c++".to_string());
+    let status = Command::new(&cxx)
+        .args(["-shared", "-fPIC", "-g", "-O0", "-o"])
+        .arg(&lib_path)
+        .arg(&source)
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        _ => {
+            eprintln!("{} unavailable or failed - skipping C++ method test", cxx);
+            return;
+        }
+    }
+
+    let analyzer = DwarfAnalyzer::from_file(&lib_path).expect("fail to load library");
+    let extract = |skip_virtual| {
+        analyzer
+            .extract_analysis_with(&dwarffi::ExtractOptions::new().skip_virtual(skip_virtual))
+            .expect("fail to extract analysis")
+            .signatures
+    };
+
+    let all = extract(false);
+    let scale = all.iter().find(|s| s.name == "_ZN5Shape5scaleEi").unwrap();
+    let flags: Vec<(&str, bool)> = scale
+        .parameters
+        .iter()
+        .map(|p| (p.name.as_str(), p.is_artificial))
+        .collect();
+    assert_eq!(flags, [("this", true), ("by", false)]);
+    assert!(!scale.is_virtual);
+    let area = all.iter().find(|s| s.name == "_ZNK5Shape4areaEv").unwrap();
+    assert!(area.is_virtual);
+    let plain = all.iter().find(|s| s.name == "plain").unwrap();
+    assert!(!plain.is_virtual && !plain.parameters[0].is_artificial);
+
+    let mut names: Vec<String> = extract(true).into_iter().map(|s| s.name).collect();
+    names.sort();
+    assert_eq!(names, ["_ZN5Shape5scaleEi", "plain"]);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// the linker fills in a `.o`'s offsets into `.debug_str` and between its
+/// DWARF sections, so names and types only read right with its relocations
+/// applied
+fn test_object_file_relocations_are_applied() {
+    use std::process::Command;
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("pair.c");
+    let object = dir.path().join("pair.o");
+    std::fs::write(
+        &source,
+        "struct pair { int first; char second; };\n\
+         int pair_sum(struct pair p) { return p.first + p.second; }\n\
+         int negate(int x) { return -x; }\n",
+    )
+    .unwrap();
+
+    let status = Command::new(&cc)
+        .args(["-c", "-g", "-O0", "-o"])
+        .arg(&object)
+        .arg(&source)
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        _ => {
+            eprintln!("{} unavailable or failed - skipping relocation test", cc);
+            return;
+        }
+    }
+
+    let analyzer = DwarfAnalyzer::from_file(&object).expect("fail to load object");
+    let result = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+    let mut signatures: Vec<String> = result
+        .signatures
+        .iter()
+        .map(|s| s.to_string(&result.type_registry))
+        .collect();
+    signatures.sort();
+    assert_eq!(
+        signatures,
+        ["int negate(int x)", "int pair_sum(struct pair p)"]
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// an object file exports nothing dynamically; `External` still tells its
+/// global functions from its `static` ones
+fn test_external_visibility_skips_statics() {
+    use dwarffi::{ExtractOptions, Visibility};
+    use std::process::Command;
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("linkage.c");
+    let object = dir.path().join("linkage.o");
+    std::fs::write(
+        &source,
+        "static int file_local(int x) { return x * 2; }\n\
+         int public_api(int x) { return file_local(x) + 1; }\n",
+    )
+    .unwrap();
+
+    let status = Command::new(&cc)
+        .args(["-c", "-g", "-O0", "-o"])
+        .arg(&object)
+        .arg(&source)
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        _ => {
+            eprintln!("{} unavailable or failed - skipping visibility test", cc);
+            return;
+        }
+    }
+
+    let analyzer = DwarfAnalyzer::from_file(&object).expect("fail to load object");
+    let names = |visibility| {
+        let result = analyzer
+            .extract_analysis_with(&ExtractOptions::new().visibility(visibility))
+            .expect("fail to extract analysis");
+        let mut names: Vec<(String, bool)> = result
+            .signatures
+            .into_iter()
+            .map(|s| (s.name, s.is_external))
+            .collect();
+        names.sort();
+        names
+    };
+
+    assert_eq!(names(Visibility::External), [("public_api".to_string(), true)]);
+    assert_eq!(
+        names(Visibility::All),
+        [
+            ("file_local".to_string(), false),
+            ("public_api".to_string(), true)
+        ]
+    );
+}
+
+/// a wasm module holding DWARF written directly, in custom sections
+fn wasm_module(dwarf: &mut gimli::write::DwarfUnit) -> Vec<u8> {
+    use gimli::write::{EndianVec, Sections};
+
+    let mut sections = Sections::new(EndianVec::new(gimli::LittleEndian));
+    dwarf.write(&mut sections).unwrap();
+
+    fn leb128(mut value: usize, out: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                return;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+    sections
+        .for_each(|id, data| {
+            let mut payload = Vec::new();
+            leb128(id.name().len(), &mut payload);
+            payload.extend_from_slice(id.name().as_bytes());
+            payload.extend_from_slice(data.slice());
+            module.push(0);
+            leb128(payload.len(), &mut module);
+            module.extend_from_slice(&payload);
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+    module
+}
+
+#[test]
+/// compilers only emit `DW_AT_description` when asked to (and not gcc or
+/// clang by default), so the DWARF is written directly and wrapped in a wasm
+/// module, which holds it in custom sections
+fn test_descriptions_are_captured() {
+    use gimli::write::{AttributeValue, DwarfUnit, EndianVec, Sections};
+
+    let mut dwarf = DwarfUnit::new(gimli::Encoding {
+        format: gimli::Format::Dwarf32,
+        version: 4,
+        address_size: 4,
+    });
+    let root = dwarf.unit.root();
+    let mut add = |parent, tag, name: &str, description: Option<&str>| {
+        let id = dwarf.unit.add(parent, tag);
+        let entry = dwarf.unit.get_mut(id);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(name.into()));
+        if let Some(description) = description {
+            entry.set(
+                gimli::DW_AT_description,
+                AttributeValue::String(description.into()),
+            );
+        }
+        id
+    };
+
+    let int = add(root, gimli::DW_TAG_base_type, "int", None);
+    let point = add(
+        root,
+        gimli::DW_TAG_structure_type,
+        "Point",
+        Some("a point in the plane"),
+    );
+    let x = add(point, gimli::DW_TAG_member, "x", None);
+    let function = add(
+        root,
+        gimli::DW_TAG_subprogram,
+        "point_x",
+        Some("the x coordinate of `point`\n"),
+    );
+    let pointer = dwarf.unit.add(root, gimli::DW_TAG_pointer_type);
+    let parameter = dwarf.unit.add(function, gimli::DW_TAG_formal_parameter);
+
+    dwarf
+        .unit
+        .get_mut(int)
+        .set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+    dwarf
+        .unit
+        .get_mut(point)
+        .set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+    let entry = dwarf.unit.get_mut(x);
+    entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(int));
+    entry.set(gimli::DW_AT_data_member_location, AttributeValue::Udata(0));
+    dwarf
+        .unit
+        .get_mut(pointer)
+        .set(gimli::DW_AT_type, AttributeValue::UnitRef(point));
+    let entry = dwarf.unit.get_mut(function);
+    entry.set(gimli::DW_AT_external, AttributeValue::Flag(true));
+    entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(int));
+    let entry = dwarf.unit.get_mut(parameter);
+    entry.set(gimli::DW_AT_name, AttributeValue::String(b"point".to_vec()));
+    entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(pointer));
+
+    let mut sections = Sections::new(EndianVec::new(gimli::LittleEndian));
+    dwarf.write(&mut sections).unwrap();
+
+    fn leb128(mut value: usize, out: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                return;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+    sections
+        .for_each(|id, data| {
+            let mut payload = Vec::new();
+            leb128(id.name().len(), &mut payload);
+            payload.extend_from_slice(id.name().as_bytes());
+            payload.extend_from_slice(data.slice());
+            module.push(0);
+            leb128(payload.len(), &mut module);
+            module.extend_from_slice(&payload);
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+
+    let analyzer = DwarfAnalyzer::from_bytes(module, None).expect("fail to load wasm module");
+    let result = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+
+    let function = &result.signatures[0];
+    assert_eq!(function.name, "point_x");
+    assert_eq!(
+        function.description.as_deref(),
+        Some("the x coordinate of `point`")
+    );
+
+    // the struct's description applies through the pointer to it too
+    let registry = &result.type_registry;
+    let parameter = function.parameters[0].type_id;
+    assert_eq!(registry.description(parameter), Some("a point in the plane"));
+    assert_eq!(registry.description(function.return_type_id), None);
+}
+
+#[test]
+/// Objective-C DWARF as clang writes it for Apple frameworks: methods named
+/// by selector and defined outside the class that declares them, and the
+/// runtime's `id`, `SEL` and `IMP` typedefs. there is no Objective-C
+/// compiler to test with, so the DWARF is written directly.
+fn test_objc_methods_and_runtime_types() {
+    use gimli::write::{AttributeValue, DwarfUnit, UnitEntryId};
+
+    let mut dwarf = DwarfUnit::new(gimli::Encoding {
+        format: gimli::Format::Dwarf32,
+        version: 4,
+        address_size: 8,
+    });
+    let root = dwarf.unit.root();
+    dwarf.unit.get_mut(root).set(
+        gimli::DW_AT_language,
+        AttributeValue::Language(gimli::DW_LANG_ObjC),
+    );
+    let mut add = |parent, tag, attrs: Vec<(gimli::DwAt, AttributeValue)>| -> UnitEntryId {
+        let id = dwarf.unit.add(parent, tag);
+        let entry = dwarf.unit.get_mut(id);
+        for (name, value) in attrs {
+            entry.set(name, value);
+        }
+        id
+    };
+    let name = |name: &str| (gimli::DW_AT_name, AttributeValue::String(name.into()));
+    let type_ = |id| (gimli::DW_AT_type, AttributeValue::UnitRef(id));
+    let artificial = (gimli::DW_AT_artificial, AttributeValue::Flag(true));
+
+    // typedef struct objc_selector *SEL; typedef struct objc_object *id;
+    let selector = add(
+        root,
+        gimli::DW_TAG_structure_type,
+        vec![
+            name("objc_selector"),
+            (gimli::DW_AT_declaration, AttributeValue::Flag(true)),
+        ],
+    );
+    let selector_ptr = add(root, gimli::DW_TAG_pointer_type, vec![type_(selector)]);
+    let sel = add(root, gimli::DW_TAG_typedef, vec![name("SEL"), type_(selector_ptr)]);
+    let object = add(
+        root,
+        gimli::DW_TAG_structure_type,
+        vec![name("objc_object"), (gimli::DW_AT_byte_size, AttributeValue::Udata(8))],
+    );
+    let object_ptr = add(root, gimli::DW_TAG_pointer_type, vec![type_(object)]);
+    let id = add(root, gimli::DW_TAG_typedef, vec![name("id"), type_(object_ptr)]);
+    // typedef void (*IMP)(void);
+    let code = add(root, gimli::DW_TAG_subroutine_type, Vec::new());
+    let code_ptr = add(root, gimli::DW_TAG_pointer_type, vec![type_(code)]);
+    let imp = add(root, gimli::DW_TAG_typedef, vec![name("IMP"), type_(code_ptr)]);
+
+    // @interface Counter - (id)count; @end, and its definition
+    let counter = add(
+        root,
+        gimli::DW_TAG_structure_type,
+        vec![name("Counter"), (gimli::DW_AT_byte_size, AttributeValue::Udata(8))],
+    );
+    let declaration = add(
+        counter,
+        gimli::DW_TAG_subprogram,
+        vec![
+            name("-[Counter count]"),
+            (gimli::DW_AT_declaration, AttributeValue::Flag(true)),
+        ],
+    );
+    let counter_ptr = add(root, gimli::DW_TAG_pointer_type, vec![type_(counter)]);
+    let method = add(
+        root,
+        gimli::DW_TAG_subprogram,
+        vec![
+            (gimli::DW_AT_specification, AttributeValue::UnitRef(declaration)),
+            type_(id),
+        ],
+    );
+    add(
+        method,
+        gimli::DW_TAG_formal_parameter,
+        vec![name("self"), type_(counter_ptr), artificial.clone()],
+    );
+    add(
+        method,
+        gimli::DW_TAG_formal_parameter,
+        vec![name("_cmd"), type_(sel), artificial],
+    );
+
+    // IMP lookup(id object, SEL selector);
+    let lookup = add(root, gimli::DW_TAG_subprogram, vec![name("lookup"), type_(imp)]);
+    add(
+        lookup,
+        gimli::DW_TAG_formal_parameter,
+        vec![name("object"), type_(id)],
+    );
+    add(
+        lookup,
+        gimli::DW_TAG_formal_parameter,
+        vec![name("selector"), type_(sel)],
+    );
+
+    let analyzer =
+        DwarfAnalyzer::from_bytes(wasm_module(&mut dwarf), None).expect("fail to load wasm module");
+    let result = analyzer
+        .extract_analysis(false)
+        .expect("fail to extract analysis");
+    let registry = &result.type_registry;
+
+    // the selector name is kept, not qualified by the class
+    let method = result
+        .function_by_name("-[Counter count]")
+        .expect("-[Counter count] not found");
+    assert_eq!(
+        method.to_string(registry),
+        "id -[Counter count](struct Counter* self, SEL _cmd)"
+    );
+
+    // the runtime typedefs are opaque pointers, whatever the DWARF says
+    // the runtime's structs hold
+    let lookup = result.function_by_name("lookup").expect("lookup not found");
+    let aliased = |type_id| {
+        let aliased = registry.resolve_typedef(type_id).unwrap();
+        let is_opaque = matches!(
+            aliased.kind,
+            dwarffi::BaseTypeKind::Struct {
+                is_opaque: true,
+                ..
+            }
+        );
+        (aliased.to_c_string(registry), is_opaque)
+    };
+    assert_eq!(aliased(lookup.return_type_id).0, "void*");
+    assert_eq!(
+        aliased(lookup.parameters[0].type_id),
+        ("struct objc_object*".to_string(), true)
+    );
+    assert_eq!(
+        aliased(lookup.parameters[1].type_id),
+        ("struct objc_selector*".to_string(), true)
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// a static archive is read member by member, skipping objects built
+/// without `-g`, and its types are merged across members
+fn test_static_archive_members_are_merged() {
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let point = "typedef struct { int x, y; } Point;\n";
+    let sources = [
+        (
+            "sum.c",
+            format!(
+                "{}static int twice(int x) {{ return 2 * x; }}\n\
+                 int point_sum(Point p) {{ return twice(p.x) + p.y; }}\n",
+                point
+            ),
+            true,
+        ),
+        (
+            "make.c",
+            format!(
+                "{}Point point_make(int x, int y) {{ Point p = {{x, y}}; return p; }}\n",
+                point
+            ),
+            true,
+        ),
+        ("plain.c", "int no_debug(void) { return 1; }\n".to_string(), false),
+    ];
+
+    let mut objects = Vec::new();
+    for (name, source, debug) in &sources {
+        let source_path = dir.path().join(name);
+        std::fs::write(&source_path, source).unwrap();
+        let object = source_path.with_extension("o");
+        let status = Command::new("cc")
+            .args(if *debug { &["-c", "-g"][..] } else { &["-c"][..] })
+            .arg("-o")
+            .arg(&object)
+            .arg(&source_path)
+            .status();
+        if !matches!(status, Ok(status) if status.success()) {
+            eprintln!("cc unavailable or failed - skipping archive test");
+            return;
+        }
+        objects.push(object);
+    }
+    let archive = dir.path().join("libpoint.a");
+    let status = Command::new("ar").arg("rcs").arg(&archive).args(&objects).status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("ar unavailable or failed - skipping archive test");
+        return;
+    }
+
+    let analyzer = DwarfAnalyzer::from_file(&archive).expect("fail to load archive");
+    assert!(analyzer.target_info().is_ok());
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+
+    let mut signatures: Vec<String> = result
+        .signatures
+        .iter()
+        .map(|s| s.to_string(&result.type_registry))
+        .collect();
+    signatures.sort();
+    assert_eq!(
+        signatures,
+        ["Point point_make(int x, int y)", "int point_sum(Point p)"]
+    );
+
+    // both members define the same `Point`, which is registered once
+    assert_eq!(result.type_registry.get_by_name("Point").len(), 1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+/// every member of an archive has a unit at offset 0, so its types' DWARF
+/// offsets are told apart by member
+fn test_archive_member_offsets_stay_distinct() {
+    use dwarffi::UnitId;
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let mut objects = Vec::new();
+    // same shape and name lengths, so the same DIE offsets in both
+    for name in ["alpha", "gamma"] {
+        let source = dir.path().join(format!("{}.c", name));
+        std::fs::write(
+            &source,
+            format!(
+                "struct {0} {{ int a; }};\n\
+                 int {0}_get(struct {0}* p) {{ return p->a; }}\n",
+                name
+            ),
+        )
+        .unwrap();
+        let object = source.with_extension("o");
+        let status = Command::new("cc")
+            .args(["-c", "-g", "-o"])
+            .arg(&object)
+            .arg(&source)
+            .status();
+        if !matches!(status, Ok(status) if status.success()) {
+            eprintln!("cc unavailable or failed - skipping archive offset test");
+            return;
+        }
+        objects.push(object);
+    }
+    let archive = dir.path().join("libgreek.a");
+    let status = Command::new("ar")
+        .arg("rcs")
+        .arg(&archive)
+        .args(&objects)
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("ar unavailable or failed - skipping archive offset test");
+        return;
+    }
+
+    let analyzer = DwarfAnalyzer::from_file(&archive).expect("fail to load archive");
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+    let registry = &result.type_registry;
+    let offset = |name| registry.get_by_name(name)[0].dwarf_offset.unwrap();
+    assert_eq!(offset("alpha"), offset("gamma"));
+
+    let name_at = |source| {
+        let unit = UnitId { source, offset: 0 };
+        registry
+            .get_by_unit_offset(unit, offset("alpha"))
+            .map(|type_| type_.get_name())
+    };
+    assert_eq!(name_at(0).as_deref(), Some("alpha"));
+    assert_eq!(name_at(1).as_deref(), Some("gamma"));
+    assert_eq!(name_at(2), None);
+}
+
+#[test]
+fn test_line_mappings_map_function_entry_to_source() {
+    let lib_path = common::get_test_lib_path();
+    let analyzer = DwarfAnalyzer::from_file(&lib_path).expect("fail to load test lib");
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("fail to extract analysis");
+    let address = result
+        .function_by_name("add_two_ints")
+        .and_then(|s| s.address)
+        .expect("add_two_ints should have an address");
+
+    let rows = analyzer.line_mappings().expect("fail to read line table");
+    let entry = rows
+        .iter()
+        .find(|row| row.address == address)
+        .expect("a row should start at the function's entry");
+
+    // the prologue is attributed to the line the definition opens on, or
+    // (with gcc) to the brace on the line after it
+    let source = std::fs::read_to_string(common::get_test_lib_dir().join("testlib.c")).unwrap();
+    let line = source
+        .lines()
+        .position(|line| line.starts_with("int add_two_ints("))
+        .unwrap() as u64
+        + 1;
+    assert!(
+        entry.file.as_deref().is_some_and(|file| file.ends_with("testlib.c")),
+        "{:?}",
+        entry.file
+    );
+    assert!(
+        (line..=line + 1).contains(&entry.line),
+        "line {}",
+        entry.line
+    );
+    assert!(entry.is_stmt);
+}
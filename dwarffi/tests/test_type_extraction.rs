@@ -29,9 +29,8 @@ fn test_extract_types_from_testlib() {
     );
 
     // Find the base int type (not a pointer)
-    let int_type = int_types
-        .iter()
-        .find(|t| t.pointer_depth == 0 && !t.is_const && !t.is_volatile)
+    let int_type = registry
+        .get_base_by_name("int")
         .expect("Should have found base 'int' type");
 
     assert_eq!(int_type.pointer_depth, 0, "int should not be a pointer");
@@ -69,78 +68,16 @@ fn test_compare_with_string_extraction() {
 
 #[test]
 fn test_no_dangling_references() {
-    use dwarffi::{BaseTypeKind, TypeId};
-    use std::collections::HashSet;
-
     let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
         .expect("Failed to load test library");
 
     let result = analyzer
         .extract_analysis(true)
         .expect("Failed to extract analysis");
-    let registry = result.type_registry;
-
-    // Collect all TypeIds that exist in the registry
-    let existing_ids: HashSet<TypeId> = registry.all_types().map(|t| t.id).collect();
-
-    // Collect all referenced TypeIds
-    let mut referenced_ids = HashSet::new();
-
-    for type_ in registry.all_types() {
-        match &type_.kind {
-            BaseTypeKind::Struct { fields, .. } => {
-                for field in fields {
-                    referenced_ids.insert(field.type_id);
-                }
-            }
-            BaseTypeKind::Union { variants, .. } => {
-                for variant in variants {
-                    referenced_ids.insert(variant.type_id);
-                }
-            }
-            BaseTypeKind::Enum { backing_id, .. } => {
-                referenced_ids.insert(*backing_id);
-            }
-            BaseTypeKind::Array {
-                element_type_id, ..
-            } => {
-                referenced_ids.insert(*element_type_id);
-            }
-            BaseTypeKind::Typedef {
-                aliased_type_id, ..
-            } => {
-                referenced_ids.insert(*aliased_type_id);
-            }
-            BaseTypeKind::Function {
-                return_type_id,
-                parameter_type_ids,
-                ..
-            } => {
-                if let Some(id) = return_type_id {
-                    referenced_ids.insert(*id);
-                }
-                for id in parameter_type_ids {
-                    referenced_ids.insert(*id);
-                }
-            }
-            BaseTypeKind::Primitive { .. } => {
-                // Primitives don't reference other types
-            }
-        }
-    }
 
-    // Check that all referenced TypeIds exist
-    for ref_id in &referenced_ids {
-        assert!(
-            existing_ids.contains(ref_id),
-            "Dangling reference: TypeId {:?} is referenced but doesn't exist in registry",
-            ref_id
-        );
-    }
-
-    println!("✓ No dangling references found");
-    println!("  Existing types: {}", existing_ids.len());
-    println!("  Referenced types: {}", referenced_ids.len());
+    let issues = result.type_registry.validate();
+    assert!(issues.is_empty(), "integrity issues: {:#?}", issues);
+    println!("✓ No integrity issues across {} types", result.type_registry.len());
 }
 
 #[test]
@@ -340,3 +277,546 @@ fn test_typedef_chain_closure() {
     println!("  Typedefs found: {}", typedef_count);
     println!("  Chains verified: {}", chain_verified);
 }
+
+#[test]
+fn test_stdint_typedefs_resolve_to_primitives() {
+    let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("Failed to load test library");
+
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+
+    // (function, typedef name, size, signed)
+    let cases = [
+        ("get_size", "size_t", 8, false),
+        ("get_ssize", "ssize_t", 8, true),
+        ("get_uintptr", "uintptr_t", 8, false),
+        ("process_int8", "int8_t", 1, true),
+        ("process_int16", "int16_t", 2, true),
+        ("process_int32", "int32_t", 4, true),
+        ("process_long", "int64_t", 8, true),
+        ("process_byte", "uint8_t", 1, false),
+        ("process_uint16", "uint16_t", 2, false),
+        ("process_uint32", "uint32_t", 4, false),
+        ("process_uint64", "uint64_t", 8, false),
+    ];
+
+    for (func_name, typedef_name, size, is_signed) in cases {
+        let sig = result
+            .signatures
+            .iter()
+            .find(|s| s.name == func_name)
+            .unwrap_or_else(|| panic!("{} not found", func_name));
+
+        let return_type = registry
+            .get_type(sig.return_type_id)
+            .expect("return type should exist");
+        assert_eq!(return_type.to_c_string(registry), typedef_name);
+
+        // typedef chain (e.g. uint8_t -> __uint8_t -> unsigned char) ends at a primitive
+        let underlying = registry
+            .resolve_typedef(sig.return_type_id)
+            .expect("typedef should resolve");
+        assert!(
+            matches!(underlying.kind, dwarffi::BaseTypeKind::Primitive { .. }),
+            "{} should resolve to a primitive, got {:?}",
+            typedef_name,
+            underlying.kind
+        );
+
+        let resolved = registry
+            .resolve_primitive(sig.return_type_id)
+            .unwrap_or_else(|| panic!("{} should resolve to a primitive", typedef_name));
+        assert_eq!(resolved.size, size, "{} size", typedef_name);
+        assert_eq!(resolved.is_signed, is_signed, "{} signedness", typedef_name);
+        assert!(!resolved.is_float);
+    }
+}
+
+#[test]
+fn test_struct_field_sizes_for_union_enum_and_pointer_fields() {
+    use dwarffi::BaseTypeKind;
+
+    let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("Failed to load test library");
+
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+    let pointer_width = result.target.pointer_width;
+
+    let sig = result
+        .function_by_name("create_tagged_value")
+        .expect("create_tagged_value not found");
+
+    // TaggedValue is a typedef of an anonymous struct
+    let tagged = registry
+        .resolve_typedef(sig.return_type_id)
+        .expect("TaggedValue should resolve");
+    let BaseTypeKind::Struct { fields, size, .. } = &tagged.kind else {
+        panic!("TaggedValue should be a struct, got {:?}", tagged.kind);
+    };
+
+    // (field, expected size)
+    let expected = [
+        ("status", 4),                // enum, through the Status typedef
+        ("data", 4),                  // union, through the DataUnion typedef
+        ("on_change", pointer_width), // function pointer typedef
+        ("label", pointer_width),     // const char*
+    ];
+
+    for (name, expected_size) in expected {
+        let field = fields
+            .iter()
+            .find(|f| f.name == name)
+            .unwrap_or_else(|| panic!("field {} not found", name));
+        assert_eq!(field.size, expected_size, "size of field {}", name);
+        assert_eq!(
+            registry.size_of(field.type_id, pointer_width),
+            Some(expected_size)
+        );
+    }
+
+    // the last field ends inside the struct
+    let last = fields.last().expect("TaggedValue should have fields");
+    assert!(last.offset + last.size <= *size);
+}
+
+#[test]
+fn test_struct_field_sizes_through_primitive_typedefs() {
+    use dwarffi::BaseTypeKind;
+
+    let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("Failed to load test library");
+
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+    let pointer_width = result.target.pointer_width;
+
+    let sig = result
+        .function_by_name("byte_buffer_length")
+        .expect("byte_buffer_length not found");
+
+    // const ByteBuffer* -> anonymous struct
+    let buffer = registry
+        .resolve_typedef(sig.parameters[0].type_id)
+        .expect("ByteBuffer should resolve");
+    let BaseTypeKind::Struct { fields, size, .. } = &buffer.kind else {
+        panic!("ByteBuffer should be a struct, got {:?}", buffer.kind);
+    };
+
+    // (field, expected size); size_t and uint32_t are typedefs
+    let expected = [
+        ("data", pointer_width),
+        ("length", pointer_width),
+        ("checksum", 4),
+    ];
+    for (name, expected_size) in expected {
+        let field = fields
+            .iter()
+            .find(|f| f.name == name)
+            .unwrap_or_else(|| panic!("field {} not found", name));
+        assert_eq!(field.size, expected_size, "size of field {}", name);
+    }
+
+    // the checksum follows the length, and the struct pads to its alignment
+    let checksum = fields.iter().find(|f| f.name == "checksum").unwrap();
+    assert_eq!(checksum.offset, 2 * pointer_width);
+    assert_eq!(*size, 3 * pointer_width);
+}
+
+#[test]
+fn test_array_of_padded_structs_uses_stride() {
+    use dwarffi::BaseTypeKind;
+
+    let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("Failed to load test library");
+
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+
+    let sig = result
+        .function_by_name("sum_padded_items")
+        .expect("sum_padded_items not found");
+
+    // const PaddedItems* -> anonymous struct
+    let BaseTypeKind::Struct { fields, size, .. } = &registry
+        .resolve_typedef(sig.parameters[0].type_id)
+        .expect("PaddedItems should resolve")
+        .kind
+    else {
+        panic!("PaddedItems should be a struct");
+    };
+
+    let items = fields
+        .iter()
+        .find(|f| f.name == "items")
+        .expect("items field not found");
+    let BaseTypeKind::Array {
+        count,
+        size: array_size,
+        stride,
+        ..
+    } = &registry.get_type(items.type_id).unwrap().kind
+    else {
+        panic!("items should be an array");
+    };
+
+    // { int; char; } is padded from 5 to 8 bytes, and elements are laid
+    // out at that padded stride
+    assert_eq!(*count, 3);
+    assert_eq!(*stride, 8);
+    assert_eq!(*array_size, 24);
+    assert_eq!(items.size, 24);
+    assert_eq!(*size, 24);
+}
+
+#[test]
+fn test_union_alignment_with_array_member() {
+    use dwarffi::BaseTypeKind;
+
+    let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("Failed to load test library");
+
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+
+    let sig = result
+        .function_by_name("get_double_from_wide_union")
+        .expect("get_double_from_wide_union not found");
+
+    // union { double; char[16]; } aligns to the double, not the array size
+    let wide = registry
+        .resolve_typedef(sig.parameters[0].type_id)
+        .expect("WideUnion should resolve");
+    let BaseTypeKind::Union {
+        variants,
+        size,
+        alignment,
+        ..
+    } = &wide.kind
+    else {
+        panic!("WideUnion should be a union, got {:?}", wide.kind);
+    };
+    assert_eq!(*size, 16);
+    assert_eq!(*alignment, 8);
+
+    // char[16] on its own aligns like char
+    let bytes = variants
+        .iter()
+        .find(|v| v.name == "as_bytes")
+        .expect("as_bytes not found");
+    assert_eq!(
+        registry.align_of(bytes.type_id, result.target.pointer_width),
+        Some(1)
+    );
+}
+
+#[test]
+fn test_bitfield_struct_fields_in_memory_and_declaration_order() {
+    let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("Failed to load test library");
+
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+
+    let sig = result
+        .function_by_name("count_status_flags")
+        .expect("count_status_flags not found");
+    let flags = registry
+        .resolve_typedef(sig.parameters[0].type_id)
+        .expect("StatusFlags should resolve");
+    let dwarffi::BaseTypeKind::Struct { fields, .. } = &flags.kind else {
+        panic!("StatusFlags should be a struct, got {:?}", flags.kind);
+    };
+
+    // bitfields are placed by bit offset, not all at byte 0
+    let layout: Vec<(&str, usize)> = fields.iter().map(|f| (f.name.as_str(), f.offset)).collect();
+    assert_eq!(
+        layout,
+        [("ready", 0), ("mode", 0), ("count", 4), ("error", 8)]
+    );
+
+    let declared: Vec<&str> = flags
+        .fields_in_declaration_order()
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect();
+    assert_eq!(declared, ["ready", "mode", "count", "error"]);
+    assert!(fields.windows(2).all(|w| w[0].offset <= w[1].offset));
+
+    let bits: Vec<(Option<u32>, Option<u32>)> =
+        fields.iter().map(|f| (f.bit_size, f.bit_offset)).collect();
+    assert_eq!(
+        bits,
+        [
+            (Some(1), Some(0)),
+            (Some(3), Some(1)),
+            (None, None),
+            (Some(1), Some(64))
+        ]
+    );
+}
+
+#[test]
+fn test_parameter_types_match_c_spellings() {
+    let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("Failed to load test library");
+
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+    let parameter_type = |func_name: &str, index: usize| {
+        let sig = result
+            .signatures
+            .iter()
+            .find(|s| s.name == func_name)
+            .unwrap_or_else(|| panic!("{} not found", func_name));
+        registry
+            .get_type(sig.parameters[index].type_id)
+            .expect("parameter type should exist")
+    };
+
+    // (function, parameter, spellings that match, spellings that don't)
+    let cases: &[(&str, usize, &[&str], &[&str])] = &[
+        (
+            "create_person",
+            0,
+            &["const char*", "char const *", "const char *"],
+            &["char*", "const char", "const char**", "const unsigned char*"],
+        ),
+        ("create_person", 1, &["int", "signed int", "signed"], &["unsigned", "long"]),
+        (
+            "clear_registers",
+            0,
+            &["volatile uint32_t* const", "uint32_t volatile *const"],
+            &["volatile uint32_t*", "uint32_t* const", "volatile unsigned int* const"],
+        ),
+        ("clear_registers", 1, &["size_t"], &["unsigned long", "long unsigned int"]),
+        ("process_person_batch", 0, &["Person**"], &["Person*", "struct Person**"]),
+        ("is_point_inside", 0, &["BoundingBox"], &["struct BoundingBox"]),
+    ];
+
+    for (func_name, index, matching, other) in cases {
+        let type_ = parameter_type(func_name, *index);
+        for spelling in *matching {
+            assert!(
+                type_.matches_c_type(registry, spelling),
+                "{} parameter {} should match `{}`",
+                func_name,
+                index,
+                spelling
+            );
+        }
+        for spelling in *other {
+            assert!(
+                !type_.matches_c_type(registry, spelling),
+                "{} parameter {} should not match `{}`",
+                func_name,
+                index,
+                spelling
+            );
+        }
+    }
+
+    // a primitive matches however its name is spelled
+    let size = registry
+        .resolve_typedef(parameter_type("clear_registers", 1).id)
+        .expect("size_t should resolve");
+    assert!(size.matches_c_type(registry, "unsigned long"));
+    assert!(size.matches_c_type(registry, "long unsigned int"));
+}
+
+#[test]
+fn test_handle_types_are_opaque() {
+    let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("Failed to load test library");
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+
+    // `struct InternalState` is defined in testlib.c; the header only
+    // declares it for `InternalState*`
+    let opaque = result.opaque_types();
+    let names: Vec<String> = opaque
+        .iter()
+        .map(|id| registry.get_type(*id).unwrap().get_name())
+        .collect();
+    assert_eq!(names, ["InternalState"]);
+    let state = registry.get_type(opaque[0]).unwrap();
+    assert_eq!(state.pointer_depth, 0);
+    assert!(registry.is_opaque(state.id));
+
+    // defined in the header
+    let point = registry.get_base_by_name("Point").unwrap();
+    assert!(!registry.is_opaque(point.id));
+}
+
+#[test]
+fn test_small_float_aggregates() {
+    let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("Failed to load test library");
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+    let is_float_aggregate = |name| {
+        let type_ = registry.get_base_by_name(name).unwrap();
+        registry.is_small_float_aggregate(type_.id)
+    };
+
+    // two floats, through its typedef
+    assert!(is_float_aggregate("Rectangle"));
+    // two `Point`s of ints
+    assert!(!is_float_aggregate("BoundingBox"));
+    // an enum, not a struct
+    assert!(!is_float_aggregate("Color"));
+    // `calculate_rectangle_area(Rectangle* rect)` takes an address
+    let rect = &result
+        .function_by_name("calculate_rectangle_area")
+        .unwrap()
+        .parameters[0];
+    assert!(!registry.is_small_float_aggregate(rect.type_id));
+}
+
+#[test]
+fn test_unsigned_enum_values_keep_their_sign() {
+    use dwarffi::BaseTypeKind;
+
+    let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("Failed to load test library");
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+
+    // PERMISSION_ALL = 0xFFFFFFFF backs the enum with unsigned int
+    let permission = registry
+        .resolve_typedef(registry.get_base_by_name("Permission").unwrap().id)
+        .expect("Permission should resolve");
+    let BaseTypeKind::Enum {
+        backing_id,
+        variants,
+        ..
+    } = &permission.kind
+    else {
+        panic!("Permission should be an enum, got {:?}", permission.kind);
+    };
+    assert!(registry.is_unsigned_enum(*backing_id));
+    let all = variants
+        .iter()
+        .find(|v| v.name == "PERMISSION_ALL")
+        .expect("PERMISSION_ALL not found");
+    assert_eq!(all.value, 0xFFFF_FFFF);
+    assert_eq!(all.value_string(true), "4294967295");
+}
+
+#[test]
+fn test_const_pointer_levels_render_and_reparse() {
+    let analyzer = DwarfAnalyzer::from_file(&common::get_test_lib_path())
+        .expect("Failed to load test library");
+    let result = analyzer
+        .extract_analysis(true)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+
+    let sig = result
+        .function_by_name("pick_point")
+        .expect("pick_point not found");
+    let return_type = registry.get_type(sig.return_type_id).unwrap();
+    assert_eq!(return_type.to_c_string(registry), "const Point*");
+
+    let expected = ["const Point*", "Point* const", "const Point* const"];
+    let types: Vec<_> = sig
+        .parameters
+        .iter()
+        .map(|param| registry.get_type(param.type_id).unwrap())
+        .collect();
+    for (type_, spelling) in types.iter().zip(expected) {
+        assert_eq!(type_.to_c_string(registry), spelling);
+        // the spelling names exactly this parameter's type
+        for other in &types {
+            assert_eq!(
+                other.matches_c_type(registry, spelling),
+                other.id == type_.id,
+                "{}",
+                spelling
+            );
+        }
+    }
+}
+
+#[test]
+fn test_scalar_storage_order_members_record_endianness() {
+    use dwarffi::{BaseTypeKind, Endianness};
+    use std::process::Command;
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let dir = tempfile::tempdir().expect("fail to create temp dir");
+    let source = dir.path().join("protocol.c");
+    std::fs::write(
+        &source,
+        "struct __attribute__((scalar_storage_order(\"big-endian\"))) Header {\n\
+         \x20   unsigned short length;\n\
+         \x20   unsigned int sequence;\n\
+         };\n\
+         struct Native { unsigned int sequence; };\n\
+         unsigned int header_sequence(struct Header* h, struct Native* n) {\n\
+         \x20   return h->sequence + n->sequence;\n\
+         }\n",
+    )
+    .unwrap();
+    let lib_path = dir.path().join("libprotocol.so");
+    let status = Command::new(&cc)
+        .args(["-shared", "-fPIC", "-g", "-O0", "-o"])
+        .arg(&lib_path)
+        .arg(&source)
+        .status();
+    if !matches!(status, Ok(status) if status.success()) {
+        eprintln!("{} unavailable or failed - skipping endianity test", cc);
+        return;
+    }
+
+    let result = DwarfAnalyzer::from_file(&lib_path)
+        .expect("Failed to load library")
+        .extract_analysis(false)
+        .expect("Failed to extract analysis");
+    let registry = &result.type_registry;
+    let sig = result
+        .function_by_name("header_sequence")
+        .expect("header_sequence not found");
+    // the struct each pointer parameter points to
+    let fields = |index: usize| {
+        let type_ = registry.get_type(sig.parameters[index].type_id).unwrap();
+        match &type_.kind {
+            BaseTypeKind::Struct { fields, .. } => fields.clone(),
+            kind => panic!("parameter {} should be a struct, got {:?}", index, kind),
+        }
+    };
+
+    let header = fields(0);
+    if header.iter().all(|field| field.endianness.is_none()) {
+        eprintln!("{} ignores scalar_storage_order - skipping endianity test", cc);
+        return;
+    }
+    for field in &header {
+        assert_eq!(field.endianness, Some(Endianness::Big), "{}", field.name);
+    }
+    // members in the target's order leave it unset
+    for field in fields(1) {
+        assert_eq!(field.endianness, None, "{}", field.name);
+    }
+}
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// any input may fail to analyze, but must not panic
+fuzz_target!(|data: &[u8]| {
+    let analyzer = dwarffi::DwarfAnalyzer::new(data.to_vec());
+    let _ = analyzer.extract_analysis(false);
+    let _ = analyzer.extract_macros();
+    let _ = analyzer.producers();
+});
@@ -11,7 +11,7 @@ struct InternalState
 {
     int counter;
     double value;
-    char buffer[256];
+    char buffer[TESTLIB_STATE_BUFFER_SIZE];
 };
 
 // internal/hidden functions
@@ -159,6 +159,46 @@ size_t get_size(void)
     return sizeof(Person);
 }
 
+int8_t process_int8(int8_t value)
+{
+    return -value;
+}
+
+int16_t process_int16(int16_t value)
+{
+    return value * 2;
+}
+
+int32_t process_int32(int32_t value)
+{
+    return value * 2;
+}
+
+uint16_t process_uint16(uint16_t value)
+{
+    return value * 2;
+}
+
+uint32_t process_uint32(uint32_t value)
+{
+    return value * 2;
+}
+
+uint64_t process_uint64(uint64_t value)
+{
+    return value * 2;
+}
+
+ssize_t get_ssize(void)
+{
+    return -1;
+}
+
+uintptr_t get_uintptr(void)
+{
+    return (uintptr_t)&get_uintptr;
+}
+
 void modify_value(int *ptr)
 {
     if (ptr)
@@ -206,6 +246,64 @@ int sum_array(const int *arr, size_t length)
     return sum;
 }
 
+void copy_ints(int *restrict dst, const int *restrict src, size_t count)
+{
+    for (size_t i = 0; i < count; i++)
+    {
+        dst[i] = src[i];
+    }
+}
+
+int load_atomic(_Atomic int *value)
+{
+    return *value;
+}
+
+uint32_t read_register(volatile uint32_t *reg)
+{
+    return *reg;
+}
+
+void clear_registers(volatile uint32_t *const regs, size_t count)
+{
+    for (size_t i = 0; i < count; i++)
+    {
+        regs[i] = 0;
+    }
+}
+
+_Noreturn void fatal_error(const char *message)
+{
+    fprintf(stderr, "fatal: %s\n", message);
+    abort();
+}
+
+// inlined even at -O0, so no out-of-line copy is emitted
+__attribute__((always_inline)) static inline int clamp(int value, int lo, int hi)
+{
+    return value < lo ? lo : value > hi ? hi : value;
+}
+
+int clamp_percent(int value)
+{
+    return clamp(value, 0, 100);
+}
+
+int sum_padded_items(const PaddedItems *items)
+{
+    int sum = 0;
+    for (size_t i = 0; i < 3; i++)
+    {
+        sum += items->items[i].value;
+    }
+    return sum;
+}
+
+size_t byte_buffer_length(const ByteBuffer *buffer)
+{
+    return buffer ? buffer->length : 0;
+}
+
 void print_string(const char *str)
 {
     if (str)
@@ -225,6 +323,11 @@ void set_status(Status s)
     (void)s;
 }
 
+Permission get_default_permission(void)
+{
+    return PERMISSION_ALL;
+}
+
 Color blend_colors(Color c1, Color c2)
 {
     // simple logic
@@ -248,6 +351,11 @@ void move_point(Point *p, int dx, int dy)
     }
 }
 
+const Point *pick_point(const Point *first, Point *const second, const Point *const third)
+{
+    return first ? first : (second ? second : third);
+}
+
 float calculate_distance(Point p1, Point p2)
 {
     int dx = p2.x - p1.x;
@@ -323,7 +431,7 @@ InternalState *init_state(void)
     InternalState *state = (InternalState *)malloc(sizeof(InternalState));
     if (state)
     {
-        state->counter = 0;
+        state->counter = TESTLIB_STATE_INITIAL_COUNT;
         state->value = 0.0;
         memset(state->buffer, 0, sizeof(state->buffer));
     }
@@ -352,6 +460,26 @@ DataUnion create_data_union(int value)
     return data;
 }
 
+TaggedValue create_tagged_value(Status status, int value)
+{
+    TaggedValue tagged;
+    tagged.status = status;
+    tagged.data.as_int = value;
+    tagged.on_change = NULL;
+    tagged.label = NULL;
+    return tagged;
+}
+
+double get_double_from_wide_union(WideUnion data)
+{
+    return data.as_double;
+}
+
+int count_status_flags(StatusFlags flags)
+{
+    return flags.ready + flags.mode + flags.error + flags.count;
+}
+
 float get_float_from_union(DataUnion data)
 {
     return data.as_float;
@@ -3,6 +3,15 @@
 
 #include <stdint.h>
 #include <stddef.h>
+#include <sys/types.h>
+
+// constants (recorded in DWARF with -g3)
+
+#define TESTLIB_MAX_POINTS 1024
+#define TESTLIB_FLAG_MASK 0x0Fu
+#define TESTLIB_INVALID (-1)
+#define TESTLIB_VERSION "1.2.0"
+#define TESTLIB_SQUARE(x) ((x) * (x))
 
 // enums
 
@@ -19,6 +28,18 @@ typedef enum {
     COLOR_BLUE
 } Color;
 
+// a value past INT_MAX makes the compiler back this enum with unsigned int
+typedef enum {
+    PERMISSION_NONE = 0,
+    PERMISSION_ALL = 0xFFFFFFFF
+} Permission;
+
+// an anonymous enum names no type; its members are plain constants
+enum {
+    TESTLIB_STATE_INITIAL_COUNT = 0,
+    TESTLIB_STATE_BUFFER_SIZE = 256
+};
+
 // simple structs
 
 typedef struct {
@@ -51,6 +72,25 @@ typedef struct {
     void *userdata;
 } Person;
 
+// array of structs with trailing padding
+
+typedef struct {
+    int value;
+    char tag;
+} PaddedItem;
+
+typedef struct {
+    PaddedItem items[3];
+} PaddedItems;
+
+// fields whose types are typedefs of primitives
+
+typedef struct {
+    const char* data;
+    size_t length;
+    uint32_t checksum;
+} ByteBuffer;
+
 // opaque types (forward declarations)
 
 typedef struct InternalState InternalState;
@@ -63,11 +103,33 @@ typedef union {
     char as_bytes[4];
 } DataUnion;
 
+typedef union {
+    double as_double;
+    char as_bytes[16];
+} WideUnion;
+
+// bitfields share storage units, so several members start in the same byte
+typedef struct {
+    unsigned int ready : 1;
+    unsigned int mode : 3;
+    int count;
+    unsigned int error : 1;
+} StatusFlags;
+
 // function pointer types
 
 typedef void (*Callback)(int code, void* userdata);
 typedef int (*Comparator)(const void* a, const void* b);
 
+// struct embedding a union, an enum and a function pointer
+
+typedef struct {
+    Status status;
+    DataUnion data;
+    Callback on_change;
+    const char* label;
+} TaggedValue;
+
 // exported api functions
 
 // basic types - primitives
@@ -141,6 +203,31 @@ int64_t process_long(int64_t value);
 __attribute__((visibility("default")))
 size_t get_size(void);
 
+// stdint/stddef typedefs - resolved through to their base types
+__attribute__((visibility("default")))
+int8_t process_int8(int8_t value);
+
+__attribute__((visibility("default")))
+int16_t process_int16(int16_t value);
+
+__attribute__((visibility("default")))
+int32_t process_int32(int32_t value);
+
+__attribute__((visibility("default")))
+uint16_t process_uint16(uint16_t value);
+
+__attribute__((visibility("default")))
+uint32_t process_uint32(uint32_t value);
+
+__attribute__((visibility("default")))
+uint64_t process_uint64(uint64_t value);
+
+__attribute__((visibility("default")))
+ssize_t get_ssize(void);
+
+__attribute__((visibility("default")))
+uintptr_t get_uintptr(void);
+
 // pointers
 __attribute__((visibility("default")))
 void modify_value(int* ptr);
@@ -162,9 +249,36 @@ void allocate_matrix(int** matrix, int rows, int cols);
 __attribute__((visibility("default")))
 int sum_array(const int* arr, size_t length);
 
+__attribute__((visibility("default")))
+int sum_padded_items(const PaddedItems* items);
+
+__attribute__((visibility("default")))
+size_t byte_buffer_length(const ByteBuffer* buffer);
+
 __attribute__((visibility("default")))
 void print_string(const char* str);
 
+// qualifiers without their own type model
+__attribute__((visibility("default")))
+void copy_ints(int* restrict dst, const int* restrict src, size_t count);
+
+__attribute__((visibility("default")))
+int load_atomic(_Atomic int* value);
+
+// memory-mapped registers: pointer to volatile vs. const pointer
+__attribute__((visibility("default")))
+uint32_t read_register(volatile uint32_t* reg);
+
+__attribute__((visibility("default")))
+void clear_registers(volatile uint32_t* const regs, size_t count);
+
+// noreturn, and a function that calls an always-inlined helper
+__attribute__((visibility("default")))
+_Noreturn void fatal_error(const char* message);
+
+__attribute__((visibility("default")))
+int clamp_percent(int value);
+
 // enums
 __attribute__((visibility("default")))
 Status get_status(void);
@@ -172,6 +286,9 @@ Status get_status(void);
 __attribute__((visibility("default")))
 void set_status(Status s);
 
+__attribute__((visibility("default")))
+Permission get_default_permission(void);
+
 __attribute__((visibility("default")))
 Color blend_colors(Color c1, Color c2);
 
@@ -182,6 +299,10 @@ Point create_point(int x, int y);
 __attribute__((visibility("default")))
 void move_point(Point* p, int dx, int dy);
 
+// const on the pointee, the pointer, and both
+__attribute__((visibility("default")))
+const Point* pick_point(const Point* first, Point* const second, const Point* const third);
+
 __attribute__((visibility("default")))
 float calculate_distance(Point p1, Point p2);
 
@@ -229,6 +350,15 @@ DataUnion create_data_union(int value);
 __attribute__((visibility("default")))
 float get_float_from_union(DataUnion data);
 
+__attribute__((visibility("default")))
+double get_double_from_wide_union(WideUnion data);
+
+__attribute__((visibility("default")))
+TaggedValue create_tagged_value(Status status, int value);
+
+__attribute__((visibility("default")))
+int count_status_flags(StatusFlags flags);
+
 // function pointers
 __attribute__((visibility("default")))
 void register_callback(Callback cb, void* userdata);